@@ -0,0 +1,25 @@
+//! Embeds the git version (`git describe --always --dirty --tags`) as `GIT_VERSION`,
+//! unless the environment already sets it (e.g. a Docker build without `.git`).
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=GIT_VERSION");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+
+    if std::env::var("GIT_VERSION").is_ok_and(|v| !v.is_empty()) {
+        return;
+    }
+    let describe = Command::new("git")
+        .args(["describe", "--always", "--dirty", "--tags"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty());
+    if let Some(version) = describe {
+        println!("cargo:rustc-env=GIT_VERSION={}", version);
+    }
+}
@@ -0,0 +1,73 @@
+//! Benchmark: SNIP-12 order signing, one-off vs batched through `OrderBuilder`
+//!
+//! One-off signing recomputes the domain hash, type hash and parsed asset IDs for every
+//! order; the builder computes them once per market. Signs the same sliced orders both
+//! ways and prints the per-order time.
+//!
+//! Run with: cargo run --release --example bench_signing [orders]
+
+use extended_connector::snip12::{sign_order, OrderAmounts, OrderBuilder};
+use std::time::{Duration, Instant};
+
+const BASE_ASSET_ID: &str = "0x534f4c2d33";
+const QUOTE_ASSET_ID: &str = "0x1";
+const POSITION_ID: u64 = 226109;
+const PUBLIC_KEY: &str = "0x338f4cb92453dfb7c7764549d85ab624e6614db51b4c25c0fd63da09f07d127";
+const PRIVATE_KEY: &str = "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abc";
+
+fn per_order(elapsed: Duration, orders: usize) -> f64 {
+    elapsed.as_secs_f64() * 1e6 / orders as f64
+}
+
+fn main() -> Result<(), String> {
+    let count: usize = std::env::args()
+        .nth(1)
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1000);
+
+    // Child orders of a sliced 0.1 SOL sell
+    let orders: Vec<OrderAmounts> = (0..count as u64)
+        .map(|i| OrderAmounts {
+            base_amount: -100,
+            quote_amount: 16229000,
+            fee_amount: 9738,
+            nonce: 1_000_000 + i,
+            expiry_epoch_millis: 1700000000000 + i,
+        })
+        .collect();
+
+    println!("Signing {} orders\n", count);
+
+    let start = Instant::now();
+    for order in &orders {
+        sign_order(
+            BASE_ASSET_ID,
+            QUOTE_ASSET_ID,
+            order.base_amount,
+            order.quote_amount,
+            order.fee_amount,
+            POSITION_ID,
+            order.nonce,
+            order.expiry_epoch_millis,
+            PUBLIC_KEY,
+            PRIVATE_KEY,
+            "SN_MAIN",
+        )?;
+    }
+    let one_off = start.elapsed();
+
+    let start = Instant::now();
+    let builder = OrderBuilder::new(BASE_ASSET_ID, QUOTE_ASSET_ID, POSITION_ID, PUBLIC_KEY, PRIVATE_KEY, "SN_MAIN")?;
+    let signatures = builder.sign_batch(&orders)?;
+    let batched = start.elapsed();
+    assert_eq!(signatures.len(), count);
+
+    println!("One-off sign_order:      {:>10.1} µs/order", per_order(one_off, count));
+    println!("OrderBuilder::sign_batch: {:>9.1} µs/order (builder setup included)", per_order(batched, count));
+    println!(
+        "Speedup:                 {:>10.2}x",
+        one_off.as_secs_f64() / batched.as_secs_f64().max(f64::EPSILON)
+    );
+
+    Ok(())
+}
@@ -1,9 +1,9 @@
+#![allow(clippy::result_large_err)]
 use dotenv::dotenv;
 use extended_connector::{ConnectorError, OrderSide, RestClient};
 use std::env;
 use std::io::{self, Write};
 use std::time::Duration;
-use tokio::time::sleep;
 
 #[tokio::main]
 async fn main() -> Result<(), ConnectorError> {
@@ -98,7 +98,7 @@ async fn main() -> Result<(), ConnectorError> {
 
     // Step 1: Place market BUY order
     println!("--- Step 1: Placing Market BUY Order ---");
-    let _buy_order = match client
+    let buy_order = match client
         .place_market_order(
             "BTC-USD",
             OrderSide::Buy,
@@ -106,6 +106,8 @@ async fn main() -> Result<(), ConnectorError> {
             &stark_private_key,
             &stark_public_key,
             actual_vault_id,
+            false,
+            None,
         )
         .await
     {
@@ -128,8 +130,11 @@ async fn main() -> Result<(), ConnectorError> {
     };
 
     // Wait for order to be processed
-    println!("Waiting 5 seconds for order to fill...");
-    sleep(Duration::from_secs(5)).await;
+    println!("Waiting for order to fill...");
+    match client.wait_for_fill(&buy_order.external_id, Duration::from_secs(10)).await {
+        Ok(order) => println!("  Order {}: {} filled", order.status, order.filled_qty_f64()),
+        Err(e) => println!("  ⚠️  {}", e),
+    }
     println!();
 
     // Step 2: Query current position to get exact size
@@ -167,6 +172,8 @@ async fn main() -> Result<(), ConnectorError> {
             &stark_private_key,
             &stark_public_key,
             actual_vault_id,
+            false,
+            None,
         )
         .await
     {
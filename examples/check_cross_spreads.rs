@@ -1,3 +1,4 @@
+#![allow(dead_code)]
 use extended_connector::{init_logging, PacificaTrading, PacificaCredentials, RestClient};
 use std::env;
 
@@ -1,3 +1,4 @@
+#![allow(clippy::result_large_err)]
 use dotenv::dotenv;
 use extended_connector::{init_logging, ConnectorError, OrderSide, RestClient};
 use std::env;
@@ -148,6 +149,8 @@ async fn main() -> Result<(), ConnectorError> {
             &stark_private_key,
             &stark_public_key,
             actual_vault_id,
+            false,
+            None,
         )
         .await
     {
@@ -164,8 +167,11 @@ async fn main() -> Result<(), ConnectorError> {
     };
 
     // Step 2: Wait and check position
-    println!("⏳ Waiting 5 seconds for order to fill...\n");
-    sleep(Duration::from_secs(5)).await;
+    println!("⏳ Waiting for order to fill...\n");
+    match client.wait_for_fill(&buy_order.external_id, Duration::from_secs(10)).await {
+        Ok(order) => println!("  Order {}: {} filled\n", order.status, order.filled_qty_f64()),
+        Err(e) => println!("  ⚠️  {}\n", e),
+    }
 
     println!("--- Step 2: Checking LONG Position ---");
     let positions_after_buy = client.get_positions(Some("ETH-USD")).await?;
@@ -202,6 +208,8 @@ async fn main() -> Result<(), ConnectorError> {
             &stark_private_key,
             &stark_public_key,
             actual_vault_id,
+            false,
+            None,
         )
         .await
     {
@@ -219,19 +227,22 @@ async fn main() -> Result<(), ConnectorError> {
     };
 
     // Step 4: Verify position is flat
-    println!("⏳ Waiting 5 seconds for order to fill...\n");
-    sleep(Duration::from_secs(5)).await;
+    println!("⏳ Waiting for order to fill...\n");
+    match client.wait_for_fill(&sell_order.external_id, Duration::from_secs(10)).await {
+        Ok(order) => println!("  Order {}: {} filled\n", order.status, order.filled_qty_f64()),
+        Err(e) => println!("  ⚠️  {}\n", e),
+    }
 
     println!("--- Step 4: Verifying Position is Flat ---");
     let positions_after_sell = client.get_positions(Some("ETH-USD")).await?;
     let eth_position_after = positions_after_sell.iter().find(|p| p.market == "ETH-USD");
 
-    if eth_position_after.is_none() {
-        println!("✅ SUCCESS: LONG position closed, ETH position is FLAT!\n");
-    } else {
+    if let Some(position) = eth_position_after {
         println!("⚠️  WARNING: ETH position still exists:");
-        println!("   {}", eth_position_after.unwrap());
+        println!("   {}", position);
         println!("   This may indicate partial fills or size mismatch.\n");
+    } else {
+        println!("✅ SUCCESS: LONG position closed, ETH position is FLAT!\n");
     }
 
     println!("────────────────────────────────────────────────────────────────\n");
@@ -257,6 +268,8 @@ async fn main() -> Result<(), ConnectorError> {
             &stark_private_key,
             &stark_public_key,
             actual_vault_id,
+            false,
+            None,
         )
         .await
     {
@@ -273,8 +286,11 @@ async fn main() -> Result<(), ConnectorError> {
     };
 
     // Step 2: Wait and check position
-    println!("⏳ Waiting 5 seconds for order to fill...\n");
-    sleep(Duration::from_secs(5)).await;
+    println!("⏳ Waiting for order to fill...\n");
+    match client.wait_for_fill(&short_sell_order.external_id, Duration::from_secs(10)).await {
+        Ok(order) => println!("  Order {}: {} filled\n", order.status, order.filled_qty_f64()),
+        Err(e) => println!("  ⚠️  {}\n", e),
+    }
 
     println!("--- Step 2: Checking SHORT Position ---");
     let positions_after_short = client.get_positions(Some("ETH-USD")).await?;
@@ -311,6 +327,8 @@ async fn main() -> Result<(), ConnectorError> {
             &stark_private_key,
             &stark_public_key,
             actual_vault_id,
+            false,
+            None,
         )
         .await
     {
@@ -328,19 +346,22 @@ async fn main() -> Result<(), ConnectorError> {
     };
 
     // Step 4: Verify position is flat
-    println!("⏳ Waiting 5 seconds for order to fill...\n");
-    sleep(Duration::from_secs(5)).await;
+    println!("⏳ Waiting for order to fill...\n");
+    match client.wait_for_fill(&buy_back_order.external_id, Duration::from_secs(10)).await {
+        Ok(order) => println!("  Order {}: {} filled\n", order.status, order.filled_qty_f64()),
+        Err(e) => println!("  ⚠️  {}\n", e),
+    }
 
     println!("--- Step 4: Verifying Position is Flat ---");
     let final_positions = client.get_positions(Some("ETH-USD")).await?;
     let eth_final_position = final_positions.iter().find(|p| p.market == "ETH-USD");
 
-    if eth_final_position.is_none() {
-        println!("✅ SUCCESS: SHORT position closed, ETH position is FLAT!\n");
-    } else {
+    if let Some(position) = eth_final_position {
         println!("⚠️  WARNING: ETH position still exists:");
-        println!("   {}", eth_final_position.unwrap());
+        println!("   {}", position);
         println!("   This may indicate partial fills or size mismatch.\n");
+    } else {
+        println!("✅ SUCCESS: SHORT position closed, ETH position is FLAT!\n");
     }
 
     // ═══════════════════════════════════════════════════
@@ -0,0 +1,85 @@
+/// Drive the position manager from another process
+///
+/// Reads one JSON command per line on stdin and answers with one JSON line on stdout,
+/// so a signal generator in any language (e.g. a Python model) can pipe into it:
+///
+///   {"cmd": "open", "symbol": "BTC", "direction": "long_extended_short_pacifica", "notional_usd": 200}
+///   {"cmd": "close"}
+///   {"cmd": "status"}
+///   {"cmd": "reconcile"}
+///
+/// Uses the same .env credentials and config.json as the bot (logs go to stderr).
+///
+/// Run: python model.py | cargo run --example external_signals
+///
+use extended_connector::{BotError, Direction, FundingBot, OpportunityConfig, PacificaCredentials, PositionManager};
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Command {
+    Open { symbol: String, direction: Direction, notional_usd: f64 },
+    Close,
+    Status,
+    Reconcile,
+}
+
+/// Outcome of a command, with the position held afterwards
+fn reply(result: Result<(), BotError>, manager: &PositionManager) -> serde_json::Value {
+    match result {
+        Ok(()) => serde_json::json!({ "ok": true, "position": manager.state().current_position }),
+        Err(e) => serde_json::json!({ "ok": false, "error": e.to_string() }),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .init();
+    dotenv::dotenv().ok();
+
+    let extended_api_key = std::env::var("EXTENDED_API_KEY")
+        .or_else(|_| std::env::var("API_KEY"))
+        .ok();
+    let mut manager = FundingBot::builder(OpportunityConfig::load("config.json")?)
+        .extended_api_key(extended_api_key)
+        .pacifica_credentials(PacificaCredentials::from_env()?)
+        .stark_keys(
+            std::env::var("STARK_PRIVATE")?,
+            std::env::var("STARK_PUBLIC")?,
+            std::env::var("VAULT_NUMBER")?,
+        )
+        .build_position_manager()?;
+    manager.start().await?;
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Command>(&line) {
+            Err(e) => serde_json::json!({ "ok": false, "error": format!("bad command: {}", e) }),
+            Ok(Command::Status) => match manager.status().await {
+                Ok(status) => serde_json::json!({ "ok": true, "status": status }),
+                Err(e) => serde_json::json!({ "ok": false, "error": e.to_string() }),
+            },
+            Ok(Command::Open { symbol, direction, notional_usd }) => {
+                let result = manager.open(&symbol, direction, notional_usd).await;
+                reply(result, &manager)
+            }
+            Ok(Command::Close) => {
+                let result = manager.close().await;
+                reply(result, &manager)
+            }
+            Ok(Command::Reconcile) => {
+                let result = manager.reconcile().await;
+                reply(result, &manager)
+            }
+        };
+        println!("{}", response);
+    }
+    Ok(())
+}
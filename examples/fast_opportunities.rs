@@ -1,5 +1,7 @@
-use extended_connector::{init_logging, PacificaTrading, PacificaCredentials, RestClient};
+#![allow(dead_code)]
+use extended_connector::{init_logging, Direction, FundingForecast, PacificaTrading, PacificaCredentials, RestClient};
 use std::collections::HashSet;
+use std::sync::Arc;
 use tokio::task::JoinSet;
 use serde::Deserialize;
 use std::fs;
@@ -58,7 +60,7 @@ struct OpportunityData {
     extended_funding_rate_apr: f64,
     pacifica_funding_rate_apr: f64,
     total_volume_24h: f64,
-    best_direction: String,
+    best_direction: Direction,
     best_net_apr: f64,
 }
 
@@ -137,13 +139,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let start_volume_fetch = std::time::Instant::now();
 
     let mut volume_tasks = JoinSet::new();
+    let kline_client = Arc::new(PacificaTrading::new(PacificaCredentials::from_env()?));
 
     for symbol in &common_symbols {
         let symbol = symbol.clone();
         let api_key = extended_api_key.clone();
+        let kline_client = kline_client.clone();
         volume_tasks.spawn(async move {
             let extended_vol = fetch_extended_volume_with_key(&format!("{}-USD", symbol), api_key).await.unwrap_or(0.0);
-            let pacifica_vol = fetch_pacifica_volume(&symbol).await.unwrap_or(0.0);
+            let pacifica_vol = fetch_pacifica_volume(&kline_client, &symbol).await.unwrap_or(0.0);
             VolumeData {
                 symbol,
                 extended_volume: extended_vol,
@@ -270,7 +274,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             opp.pacifica_spread_pct,
             opp.cross_spread_pct,
             opp.best_net_apr,
-            truncate(&opp.best_direction, 25)
+            truncate(&opp.best_direction.to_string(), 25)
         );
     }
 
@@ -308,82 +312,19 @@ fn truncate(s: &str, max_len: usize) -> String {
 }
 
 async fn fetch_extended_volume_with_key(market: &str, api_key: Option<String>) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-    let url = format!("https://api.starknet.extended.exchange/api/v1/info/markets/{}/stats", market);
-
-    let client = reqwest::Client::new();
-    let mut request = client.get(&url)
-        .header("User-Agent", "extended-connector/0.1.0");
-
-    if let Some(key) = api_key {
-        request = request.header("X-Api-Key", key);
-    }
-
-    let response = request.send().await?;
-
-    if response.status().is_success() {
-        #[derive(serde::Deserialize)]
-        struct StatsResponse {
-            data: Option<MarketStats>,
-        }
-        #[derive(serde::Deserialize)]
-        struct MarketStats {
-            #[serde(rename = "dailyVolume")]
-            daily_volume: String,
-        }
-
-        let stats: StatsResponse = response.json().await?;
-        Ok(stats.data
-            .and_then(|d| d.daily_volume.parse::<f64>().ok())
-            .unwrap_or(0.0))
-    } else {
-        Ok(0.0)
-    }
+    let client = RestClient::new_mainnet(api_key)?;
+    Ok(client.get_market_stats(market).await?.daily_volume_f64())
 }
 
-async fn fetch_pacifica_volume(symbol: &str) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-    // Use kline endpoint to get 24h volume
-    // Volume is in BASE currency (e.g., BTC), so we need to convert to USD
-
+async fn fetch_pacifica_volume(client: &PacificaTrading, symbol: &str) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+    // Daily candle volume is in BASE currency (e.g., BTC); valued at the close in USD
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)?
         .as_millis() as u64;
     let start = now - (24 * 60 * 60 * 1000); // 24 hours ago
 
-    let url = format!(
-        "https://api.pacifica.fi/api/v1/kline?symbol={}&interval=1d&start_time={}&end_time={}",
-        symbol, start, now
-    );
-
-    let response = reqwest::get(&url).await?;
-
-    if response.status().is_success() {
-        #[derive(serde::Deserialize)]
-        struct KlineResponse {
-            success: bool,
-            data: Option<Vec<Candle>>,
-        }
-
-        #[derive(serde::Deserialize)]
-        struct Candle {
-            #[serde(rename = "v")]
-            volume: String,  // Volume in BASE currency (e.g., BTC)
-            #[serde(rename = "c")]
-            close: String,   // Close price in USD
-        }
-
-        let kline: KlineResponse = response.json().await?;
-
-        if let Some(candles) = kline.data {
-            if let Some(candle) = candles.first() {
-                // Convert BASE volume to USD: volume_btc * price_usd
-                let vol_base = candle.volume.parse::<f64>().unwrap_or(0.0);
-                let price = candle.close.parse::<f64>().unwrap_or(0.0);
-                return Ok(vol_base * price);
-            }
-        }
-    }
-
-    Ok(0.0)
+    let candles = client.get_klines(symbol, "1d", start, now).await?;
+    Ok(candles.first().map_or(0.0, |c| c.volume_usd()))
 }
 
 async fn fetch_opportunity_data(
@@ -435,16 +376,14 @@ async fn fetch_opportunity_data(
 
     let cross_spread = ((pac_mid - ext_mid).abs() / ext_mid) * 100.0;
 
-    // Fetch funding rates
-    // Extended funding rates are HOURLY (applied once per hour)
-    // Use raw decimal rate, multiply by periods per year, then convert to percentage
+    // Fetch funding rates (both normalized to the next settlement, annualized)
     let ext_funding_apr = match extended_client.get_funding_rate(&extended_market).await {
-        Ok(Some(fr)) => fr.rate * 24.0 * 365.0 * 100.0,  // rate is decimal, convert to %
+        Ok(Some(fr)) => FundingForecast::from_extended(&fr).apr_pct(),
         _ => 0.0,
     };
 
     let pac_funding_apr = match pacifica_client.get_funding_rate(&symbol).await {
-        Ok(fr) => fr.next_rate_percentage * 24.0 * 365.0,  // Use next/projected rate (not historical)
+        Ok(fr) => FundingForecast::from_pacifica(&fr).apr_pct(),
         Err(_) => 0.0,
     };
 
@@ -452,9 +391,9 @@ async fn fetch_opportunity_data(
     let net_apr_long_pac = -pac_funding_apr + ext_funding_apr;
 
     let (best_direction, best_net_apr) = if net_apr_long_ext > net_apr_long_pac {
-        ("Long Extended / Short Pacifica".to_string(), net_apr_long_ext)
+        (Direction::LongExtendedShortPacifica, net_apr_long_ext)
     } else {
-        ("Long Pacifica / Short Extended".to_string(), net_apr_long_pac)
+        (Direction::LongPacificaShortExtended, net_apr_long_pac)
     };
 
     Ok(Some(OpportunityData {
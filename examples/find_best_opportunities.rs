@@ -1,4 +1,5 @@
-use extended_connector::{init_logging, PacificaTrading, PacificaCredentials, RestClient};
+#![allow(dead_code)]
+use extended_connector::{init_logging, Direction, FundingForecast, PacificaTrading, PacificaCredentials, RestClient};
 use std::collections::HashSet;
 
 #[derive(Debug, Clone)]
@@ -26,7 +27,7 @@ struct OpportunityData {
     funding_diff_apr: f64,  // extended_funding - pacifica_funding (positive = long extended, short pacifica)
     net_apr_long_extended: f64,   // If we long Extended, short Pacifica
     net_apr_long_pacifica: f64,   // If we long Pacifica, short Extended
-    best_direction: String,
+    best_direction: Direction,
     best_net_apr: f64,
 }
 
@@ -196,37 +197,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let cross_spread_pct = ((pacifica_mid - extended_mid).abs() / extended_mid) * 100.0;
 
         // Fetch Extended 24h volume
-        let extended_volume_24h = if let Some(ref _client) = extended_client {
-            // Fetch market stats for volume
-            let url = format!("https://api.starknet.extended.exchange/api/v1/info/markets/{}/stats", extended_market);
-            match reqwest::get(&url).await {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        #[derive(serde::Deserialize)]
-                        struct StatsResponse {
-                            data: Option<MarketStats>,
-                        }
-                        #[derive(serde::Deserialize)]
-                        struct MarketStats {
-                            #[serde(rename = "dailyVolume")]
-                            daily_volume: String,
-                        }
-                        match response.json::<StatsResponse>().await {
-                            Ok(stats) => {
-                                stats.data
-                                    .and_then(|d| d.daily_volume.parse::<f64>().ok())
-                                    .unwrap_or(0.0)
-                            }
-                            Err(_) => 0.0,
-                        }
-                    } else {
-                        0.0
-                    }
-                }
-                Err(_) => 0.0,
-            }
-        } else {
-            0.0
+        let extended_volume_24h = match extended_client {
+            Some(ref client) => client
+                .get_market_stats(&extended_market)
+                .await
+                .map_or(0.0, |stats| stats.daily_volume_f64()),
+            None => 0.0,
         };
 
         // Fetch Pacifica 24h volume - need to fetch from API with marketStats
@@ -265,11 +241,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Fetch Extended funding rate
         let extended_funding_rate_apr = if let Some(ref client) = extended_client {
             match client.get_funding_rate(&extended_market).await {
-                Ok(Some(funding_info)) => {
-                    // Extended funding rates are HOURLY (applied once per hour)
-                    // Use raw decimal rate, multiply by periods per year, then convert to percentage
-                    funding_info.rate * 24.0 * 365.0 * 100.0  // rate is decimal, convert to %
-                }
+                Ok(Some(funding_info)) => FundingForecast::from_extended(&funding_info).apr_pct(),
                 _ => 0.0,
             }
         } else {
@@ -279,10 +251,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Fetch Pacifica funding rate
         let pacifica_funding_rate_apr = if let Some(ref mut client) = pacifica_client {
             match client.get_funding_rate(symbol).await {
-                Ok(funding_rate) => {
-                    // Use next/projected rate (not historical), settled hourly (24 times/day)
-                    funding_rate.next_rate_percentage * 24.0 * 365.0
-                }
+                // Next/projected rate (not historical)
+                Ok(funding_rate) => FundingForecast::from_pacifica(&funding_rate).apr_pct(),
                 Err(_) => 0.0,
             }
         } else {
@@ -299,9 +269,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let net_apr_long_pacifica = -pacifica_funding_rate_apr + extended_funding_rate_apr;
 
         let (best_direction, best_net_apr) = if net_apr_long_extended > net_apr_long_pacifica {
-            ("Long Extended / Short Pacifica".to_string(), net_apr_long_extended)
+            (Direction::LongExtendedShortPacifica, net_apr_long_extended)
         } else {
-            ("Long Pacifica / Short Extended".to_string(), net_apr_long_pacifica)
+            (Direction::LongPacificaShortExtended, net_apr_long_pacifica)
         };
 
         let opp = OpportunityData {
@@ -419,7 +389,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("   🎯 Optimal Strategy:");
         println!("      • Direction: {}", opp.best_direction);
         println!("      • Net APR: {:.2}%", opp.best_net_apr);
-        if opp.best_direction.contains("Extended / Short Pacifica") {
+        if opp.best_direction.long_on_extended() {
             println!("      • Action: Open LONG on Extended, SHORT on Pacifica");
             println!("      • You PAY: {:.2}% on Extended", opp.extended_funding_rate_apr.abs());
             println!("      • You RECEIVE: {:.2}% on Pacifica", opp.pacifica_funding_rate_apr.abs());
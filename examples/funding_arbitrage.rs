@@ -1,3 +1,4 @@
+use extended_connector::funding::{apr_from_rate, EXTENDED_FUNDING_INTERVAL_HOURS};
 use extended_connector::{
     init_logging, FundingRateInfo, PacificaCredentials, PacificaFundingRate, PacificaTrading,
     RestClient,
@@ -55,8 +56,8 @@ impl ArbitrageOpportunity {
                 )
             };
 
-        // Calculate APR (hourly rate × 24 × 365)
-        let apr = net_funding_rate * 24.0 * 365.0;
+        // Both venues settle hourly, so the net rate annualizes on the same interval
+        let apr = apr_from_rate(net_funding_rate, EXTENDED_FUNDING_INTERVAL_HOURS);
 
         Self {
             symbol,
@@ -74,11 +75,7 @@ impl ArbitrageOpportunity {
 fn map_extended_to_pacifica(extended_market: &str) -> Option<String> {
     // Extended uses format like "BTC-USD", "ETH-USD"
     // Pacifica uses format like "BTC", "ETH"
-    if let Some(base) = extended_market.split('-').next() {
-        Some(base.to_string())
-    } else {
-        None
-    }
+    extended_market.split('-').next().map(|base| base.to_string())
 }
 
 #[tokio::main]
@@ -1,9 +1,9 @@
+#![allow(clippy::result_large_err)]
 use dotenv::dotenv;
 use extended_connector::{ConnectorError, OrderSide, RestClient};
 use std::env;
 use std::io::{self, Write};
 use std::time::Duration;
-use tokio::time::sleep;
 
 #[tokio::main]
 async fn main() -> Result<(), ConnectorError> {
@@ -105,6 +105,8 @@ async fn main() -> Result<(), ConnectorError> {
             &stark_private_key,
             &stark_public_key,
             actual_vault_id,
+            false,
+            None,
         )
         .await
     {
@@ -127,13 +129,16 @@ async fn main() -> Result<(), ConnectorError> {
     };
 
     // Wait for order to be processed
-    println!("Waiting 5 seconds for order to fill...");
-    sleep(Duration::from_secs(5)).await;
+    println!("Waiting for order to fill...");
+    match client.wait_for_fill(&buy_order.external_id, Duration::from_secs(10)).await {
+        Ok(order) => println!("  Order {}: {} filled", order.status, order.filled_qty_f64()),
+        Err(e) => println!("  ⚠️  {}", e),
+    }
     println!();
 
     // Step 2: Place market SELL order to close position
     println!("--- Step 2: Placing Market SELL Order ---");
-    let sell_order = match client
+    let _sell_order = match client
         .place_market_order(
             "SOL-USD",
             OrderSide::Sell,
@@ -141,6 +146,8 @@ async fn main() -> Result<(), ConnectorError> {
             &stark_private_key,
             &stark_public_key,
             actual_vault_id,
+            false,
+            None,
         )
         .await
     {
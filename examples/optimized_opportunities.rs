@@ -1,4 +1,5 @@
-use extended_connector::{init_logging, PacificaTrading, PacificaCredentials, RestClient};
+#![allow(dead_code)]
+use extended_connector::{init_logging, Direction, FundingForecast, PacificaTrading, PacificaCredentials, RestClient};
 use std::collections::HashSet;
 
 #[derive(Debug, Clone)]
@@ -25,7 +26,7 @@ struct OpportunityData {
     funding_diff_apr: f64,
     net_apr_long_extended: f64,
     net_apr_long_pacifica: f64,
-    best_direction: String,
+    best_direction: Direction,
     best_net_apr: f64,
 }
 
@@ -285,16 +286,14 @@ async fn fetch_market_data(
     let pacifica_volume_24h = fetch_pacifica_volume(&symbol).await.unwrap_or(0.0);
     let total_volume_24h = extended_volume_24h + pacifica_volume_24h;
 
-    // Fetch funding rates
-    // Extended funding rates are HOURLY (applied once per hour)
-    // Use raw decimal rate, multiply by periods per year, then convert to percentage
+    // Fetch funding rates (both normalized to the next settlement, annualized)
     let extended_funding_rate_apr = match extended_client.get_funding_rate(&extended_market).await {
-        Ok(Some(funding_info)) => funding_info.rate * 24.0 * 365.0 * 100.0,  // rate is decimal, convert to %
+        Ok(Some(funding_info)) => FundingForecast::from_extended(&funding_info).apr_pct(),
         _ => 0.0,
     };
 
     let pacifica_funding_rate_apr = match pacifica_client.get_funding_rate(&symbol).await {
-        Ok(funding_rate) => funding_rate.next_rate_percentage * 24.0 * 365.0,  // Use next/projected rate (not historical)
+        Ok(funding_rate) => FundingForecast::from_pacifica(&funding_rate).apr_pct(),
         Err(_) => 0.0,
     };
 
@@ -304,9 +303,9 @@ async fn fetch_market_data(
     let net_apr_long_pacifica = -pacifica_funding_rate_apr + extended_funding_rate_apr;
 
     let (best_direction, best_net_apr) = if net_apr_long_extended > net_apr_long_pacifica {
-        ("Long Extended / Short Pacifica".to_string(), net_apr_long_extended)
+        (Direction::LongExtendedShortPacifica, net_apr_long_extended)
     } else {
-        ("Long Pacifica / Short Extended".to_string(), net_apr_long_pacifica)
+        (Direction::LongPacificaShortExtended, net_apr_long_pacifica)
     };
 
     Ok(Some(OpportunityData {
@@ -334,27 +333,8 @@ async fn fetch_market_data(
 }
 
 async fn fetch_extended_volume(market: &str) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-    let url = format!("https://api.starknet.extended.exchange/api/v1/info/markets/{}/stats", market);
-    let response = reqwest::get(&url).await?;
-
-    if response.status().is_success() {
-        #[derive(serde::Deserialize)]
-        struct StatsResponse {
-            data: Option<MarketStats>,
-        }
-        #[derive(serde::Deserialize)]
-        struct MarketStats {
-            #[serde(rename = "dailyVolume")]
-            daily_volume: String,
-        }
-
-        let stats: StatsResponse = response.json().await?;
-        Ok(stats.data
-            .and_then(|d| d.daily_volume.parse::<f64>().ok())
-            .unwrap_or(0.0))
-    } else {
-        Ok(0.0)
-    }
+    let client = RestClient::new_mainnet(None)?;
+    Ok(client.get_market_stats(market).await?.daily_volume_f64())
 }
 
 async fn fetch_pacifica_volume(symbol: &str) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
@@ -1,3 +1,4 @@
+use extended_connector::funding::{apr_from_rate, PACIFICA_FUNDING_INTERVAL_HOURS};
 use extended_connector::{init_logging, PacificaCredentials, PacificaTrading};
 use prettytable::{color, Attr, Cell, Row, Table};
 
@@ -56,8 +57,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     for rate in &funding_rates {
         let is_positive = rate.rate_percentage >= 0.0;
 
-        // Calculate estimated APR (hourly * 24 * 365)
-        let apr = rate.rate_percentage * 24.0 * 365.0;
+        // Calculate estimated APR
+        let apr = apr_from_rate(rate.rate_percentage, PACIFICA_FUNDING_INTERVAL_HOURS);
 
         let current_rate_cell = if is_positive {
             Cell::new(&format!("{:>10.6}", rate.rate_percentage))
@@ -114,7 +115,7 @@ fn print_summary(rates: &[extended_connector::PacificaFundingRate]) {
 
     let total = rates.len();
     let avg_rate = rates.iter().map(|r| r.rate_percentage).sum::<f64>() / total as f64;
-    let avg_apr = avg_rate * 24.0 * 365.0; // Convert hourly to APR
+    let avg_apr = apr_from_rate(avg_rate, PACIFICA_FUNDING_INTERVAL_HOURS);
 
     let max_rate = rates
         .iter()
@@ -21,7 +21,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut client = PacificaTrading::new(credentials);
 
     // Define test coins - amounts designed to exceed $20 minimum
-    let test_coins = vec![
+    let test_coins = [
         TestCoin {
             symbol: "SOL",
             size: 0.15,      // ~$30 at $200/SOL
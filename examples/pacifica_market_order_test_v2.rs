@@ -182,7 +182,7 @@ async fn run_test_cycle(
     // Step 1: Place BUY market order
     println!("📈 Step 1: Placing BUY market order for {} {}...", coin.size, coin.symbol);
     let buy_order = client
-        .place_market_order(coin.symbol, OrderSide::Buy, coin.size, coin.slippage)
+        .place_market_order(coin.symbol, OrderSide::Buy, coin.size, coin.slippage, false)
         .await?;
 
     let buy_order_id = buy_order.order_id.or(buy_order.i).unwrap_or(0);
@@ -197,7 +197,7 @@ async fn run_test_cycle(
     // Step 3: Place SELL market order (exact same size to close position)
     println!("\n📉 Step 3: Placing SELL market order for {} {}...", coin.size, coin.symbol);
     let sell_order = client
-        .place_market_order(coin.symbol, OrderSide::Sell, coin.size, coin.slippage)
+        .place_market_order(coin.symbol, OrderSide::Sell, coin.size, coin.slippage, false)
         .await?;
 
     let sell_order_id = sell_order.order_id.or(sell_order.i).unwrap_or(0);
@@ -40,10 +40,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let orderbook = client.get_orderbook_rest(symbol, 1).await?;
 
     let best_bid = orderbook.bids.first()
-        .ok_or_else(|| "No bids in orderbook")?
+        .ok_or("No bids in orderbook")?
         .price.parse::<f64>()?;
     let best_ask = orderbook.asks.first()
-        .ok_or_else(|| "No asks in orderbook")?
+        .ok_or("No asks in orderbook")?
         .price.parse::<f64>()?;
     let mid_price = (best_bid + best_ask) / 2.0;
 
@@ -69,7 +69,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("📈 Placing BUY market order for {} PUMP...", pump_size);
     let buy_order = client
-        .place_market_order("PUMP", OrderSide::Buy, pump_size, slippage)
+        .place_market_order("PUMP", OrderSide::Buy, pump_size, slippage, false)
         .await?;
 
     let buy_order_id = buy_order.order_id.or(buy_order.i).unwrap_or(0);
@@ -96,7 +96,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("📉 Placing SELL market order for {} PUMP...", pump_size);
     let sell_order = client
-        .place_market_order("PUMP", OrderSide::Sell, pump_size, slippage)
+        .place_market_order("PUMP", OrderSide::Sell, pump_size, slippage, false)
         .await?;
 
     let sell_order_id = sell_order.order_id.or(sell_order.i).unwrap_or(0);
@@ -43,7 +43,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("✅ Scan complete in {:.2}s\n", elapsed.as_secs_f64());
 
     // Display comprehensive summary table
-    scan_result.display_summary(&config.filters);
+    scan_result.display_summary(&config);
 
     // Display results
     if scan_result.opportunities.is_empty() {
@@ -68,7 +68,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             opp.pacifica_spread_pct,
             opp.cross_spread_pct,
             opp.best_net_apr,
-            truncate(&opp.best_direction, 25)
+            truncate(&opp.best_direction.to_string(), 25)
         );
     }
 
@@ -1,15 +1,16 @@
-/// Example: Signing orders for Extended DEX using Python SDK integration
-///
-/// This example demonstrates the recommended production approach:
-/// Using the Python SDK via subprocess to ensure 100% compatibility
-/// with Extended's signature format.
-///
-/// Run with: cargo run --example sign_order_example
+//! Example: Signing orders for Extended DEX using Python SDK integration
+//!
+//! This example demonstrates the recommended production approach:
+//! Using the Python SDK via subprocess to ensure 100% compatibility
+//! with Extended's signature format.
+//!
+//! Run with: cargo run --example sign_order_example
 
 use serde_json::json;
 use std::io::Write;
 use std::process::{Command, Stdio};
 
+#[allow(clippy::too_many_arguments)]
 fn sign_order_python(
     base_asset_id: &str,
     quote_asset_id: &str,
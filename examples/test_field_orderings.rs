@@ -1,6 +1,10 @@
-/// Test different Order struct field orderings to find the correct one
-///
-/// Run with: cargo test --test test_field_orderings -- --nocapture
+#![cfg_attr(not(test), allow(dead_code))]
+//! Test different Order struct field orderings to find the correct one
+//!
+//! Historical: the verified layout now lives in `snip12::hash_order` (see the
+//! `snip12` module docs). None of the unquoted type strings tried here match.
+//!
+//! Run with: cargo test --test test_field_orderings -- --nocapture
 
 use starknet_crypto::{poseidon_hash_many, Felt};
 use sha3::{Digest, Keccak256};
@@ -221,3 +225,7 @@ fn test_all_field_orderings() {
 
     println!("\n✗ No match found in common patterns");
 }
+
+// Also discovered as an example target; the real entry point is the test above.
+#[cfg(not(test))]
+fn main() {}
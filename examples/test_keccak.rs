@@ -11,7 +11,7 @@ fn main() {
     bytes.copy_from_slice(&result);
     let felt = Felt::from_bytes_be(&bytes);
 
-    println!("Raw Keccak bytes: 0x{}", hex::encode(&bytes));
+    println!("Raw Keccak bytes: 0x{}", hex::encode(bytes));
     println!("As Felt: {}", felt.to_hex_string());
 
     // Now test with domain type string
@@ -33,6 +33,6 @@ fn main() {
     let felt2 = Felt::from_bytes_be(&bytes2);
 
     println!("\nDomain type string: {}", domain_type_string);
-    println!("Raw Keccak bytes: 0x{}", hex::encode(&bytes2));
+    println!("Raw Keccak bytes: 0x{}", hex::encode(bytes2));
     println!("As Felt: {}", felt2.to_hex_string());
 }
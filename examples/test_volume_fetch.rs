@@ -1,4 +1,6 @@
+#![allow(dead_code)]
 // Quick test to debug volume fetching
+use extended_connector::RestClient;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -13,28 +15,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let text = response.text().await?;
     println!("Raw response:\n{}\n", text);
 
-    // Try parsing
-    #[derive(serde::Deserialize, Debug)]
-    struct StatsResponse {
-        status: Option<String>,
-        data: Option<MarketStats>,
-    }
-
-    #[derive(serde::Deserialize, Debug)]
-    struct MarketStats {
-        #[serde(rename = "dailyVolume")]
-        daily_volume: String,
-    }
-
-    let parsed: Result<StatsResponse, _> = serde_json::from_str(&text);
-    match parsed {
+    // Same request through the typed client
+    let client = RestClient::new_mainnet(None)?;
+    match client.get_market_stats("BTC-USD").await {
         Ok(stats) => {
             println!("Parsed successfully!");
             println!("Stats: {:?}", stats);
-            if let Some(data) = stats.data {
-                println!("Daily volume string: {}", data.daily_volume);
-                println!("Daily volume as f64: {}", data.daily_volume.parse::<f64>()?);
-            }
+            println!("Daily volume string: {}", stats.daily_volume);
+            println!("Daily volume as f64: {}", stats.daily_volume_f64());
         }
         Err(e) => {
             println!("Parse error: {}", e);
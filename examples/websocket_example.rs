@@ -77,6 +77,14 @@ async fn stream_multiple_markets(
         "SOL-USD".to_string(),
     ];
 
+    // Each market stream reconnects and resubscribes by itself; print its state changes
+    let mut events = subscriber.connection_events();
+    tokio::spawn(async move {
+        while let Ok(event) = events.recv().await {
+            println!("Connection: {:?}", event);
+        }
+    });
+
     println!("Subscribing to markets: {:?}", markets);
     let mut rx = subscriber.subscribe_markets(markets).await?;
 
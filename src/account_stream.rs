@@ -0,0 +1,117 @@
+//! Streamed Pacifica account info
+//!
+//! With `account_stream.enabled`, the bot keeps a subscription to Pacifica's
+//! `account_info` channel open and caches the latest equity, free collateral and margin
+//! in a `PacificaAccountCache`. Opens, equity records and the stress report read the
+//! cache while it is fresher than `max_age_seconds`; otherwise (stream down, or an
+//! account quiet for longer than that) they fall back to the one-shot WebSocket fetch,
+//! whose answer refreshes the cache. Without the stream each of those reads connects,
+//! subscribes and waits for a first message, which takes seconds on the open path.
+use crate::fees;
+use crate::opportunity::Environment;
+use crate::pacifica::account_stream::AccountStreamClient;
+use crate::pacifica::PacificaAccountInfo;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountStreamConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Streamed account info older than this is fetched again
+    #[serde(default = "default_max_age_seconds")]
+    pub max_age_seconds: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_age_seconds() -> u64 {
+    60
+}
+
+impl Default for AccountStreamConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            max_age_seconds: default_max_age_seconds(),
+        }
+    }
+}
+
+impl AccountStreamConfig {
+    pub fn max_age(&self) -> Duration {
+        Duration::from_secs(self.max_age_seconds)
+    }
+}
+
+/// Latest Pacifica account info, with the local time it was received
+#[derive(Debug, Clone, Default)]
+pub struct PacificaAccountCache {
+    latest: Arc<RwLock<Option<(PacificaAccountInfo, Instant)>>>,
+}
+
+impl PacificaAccountCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to `account`'s info in the background and feed a new cache. The stream
+    /// reconnects on its own and stops once the cache is dropped.
+    pub fn start(environment: Environment, account: &str) -> Self {
+        let cache = Self::new();
+        let mut stream = AccountStreamClient::new(account, environment.is_testnet()).subscribe_reconnecting();
+        let weak = Arc::downgrade(&cache.latest);
+        tokio::spawn(async move {
+            while let Some(info) = stream.recv().await {
+                let Some(latest) = weak.upgrade() else { break };
+                Self { latest }.update(info);
+            }
+        });
+        cache
+    }
+
+    pub fn update(&self, info: PacificaAccountInfo) {
+        debug!("Streamed Pacifica account info: {}", info);
+        fees::record_pacifica_tier(info.fee_tier);
+        *self.latest.write().unwrap_or_else(PoisonError::into_inner) = Some((info, Instant::now()));
+    }
+
+    /// Account info received within `max_age`
+    pub fn fresh(&self, max_age: Duration) -> Option<PacificaAccountInfo> {
+        let latest = self.latest.read().unwrap_or_else(PoisonError::into_inner);
+        let (info, received) = latest.as_ref()?;
+        (received.elapsed() <= max_age).then(|| info.clone())
+    }
+
+    /// Seconds since the last update, if any arrived
+    pub fn age_secs(&self) -> Option<u64> {
+        let latest = self.latest.read().unwrap_or_else(PoisonError::into_inner);
+        latest.as_ref().map(|(_, received)| received.elapsed().as_secs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_serves_fresh_info_only() {
+        let cache = PacificaAccountCache::new();
+        assert!(cache.fresh(Duration::from_secs(60)).is_none());
+        assert_eq!(cache.age_secs(), None);
+
+        let info: PacificaAccountInfo = serde_json::from_str(
+            r#"{"ae":"1000","as":"600","aw":"590","b":"1000","mu":"400","cm":"40","f":0,"oc":0,"pc":1,"sc":0,"t":0}"#,
+        ).unwrap();
+        cache.update(info);
+        assert_eq!(cache.fresh(Duration::from_secs(60)).map(|i| i.available_to_spend_f64()), Some(600.0));
+        assert_eq!(cache.age_secs(), Some(0));
+
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.fresh(Duration::from_millis(1)).is_none());
+    }
+}
@@ -0,0 +1,334 @@
+//! Raw API capture for debugging
+//!
+//! With `api_capture.enabled`, or after `POST /capture {"enabled": true}` on the control
+//! server, every Extended and Pacifica REST call is appended to `api_capture.path` as
+//! one JSON line: endpoint, URL, request body, HTTP status and response body. When a
+//! venue rejects an order for its signature or serialization, the exact payload sent and
+//! the venue's answer can be read back without rebuilding the bot with extra logging.
+//!
+//! Values under keys naming a signature, key, secret or private material are replaced
+//! by `"<redacted>"` before anything is written, and headers (which carry the Extended
+//! API key) are never written. The file is rotated once it reaches
+//! `api_capture.max_file_mb`, keeping `api_capture.max_files` older files as `<path>.1`
+//! (newest) to `<path>.<max_files>`.
+use crate::metrics;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::future::Future;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Written in place of a redacted value
+pub const REDACTED: &str = "<redacted>";
+/// Longer non-JSON response bodies are cut to this many bytes
+const MAX_TEXT_BODY_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiCaptureConfig {
+    /// Capture from startup; the control server can switch it at runtime either way
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_path")]
+    pub path: String,
+    /// Size (MB) at which the file is rotated
+    #[serde(default = "default_max_file_mb")]
+    pub max_file_mb: u64,
+    /// Rotated files kept next to the current one
+    #[serde(default = "default_max_files")]
+    pub max_files: u32,
+}
+
+fn default_path() -> String {
+    "api_capture.jsonl".to_string()
+}
+
+fn default_max_file_mb() -> u64 {
+    10
+}
+
+fn default_max_files() -> u32 {
+    3
+}
+
+impl Default for ApiCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_path(),
+            max_file_mb: default_max_file_mb(),
+            max_files: default_max_files(),
+        }
+    }
+}
+
+impl ApiCaptureConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.path.trim().is_empty() {
+            return Err("api_capture.path must not be empty".into());
+        }
+        if self.max_file_mb == 0 {
+            return Err("api_capture.max_file_mb must be positive".into());
+        }
+        Ok(())
+    }
+}
+
+/// One captured call, as written to the capture file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapturedCall {
+    /// Unix timestamp (ms) when the request was sent
+    pub at_ms: u64,
+    pub endpoint: String,
+    pub url: Option<String>,
+    /// JSON body sent, redacted; `None` for calls without one
+    pub request: Option<Value>,
+    pub status: Option<u16>,
+    /// Response body, redacted: parsed when it is JSON, the raw text otherwise
+    pub response: Option<Value>,
+    /// Why no response was read (connection failure, timeout...)
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+/// Whether a JSON key holds credentials or signatures
+fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase().replace(['_', '-'], "");
+    key.contains("signature") || key.contains("secret") || key.contains("private") || key.ends_with("key")
+}
+
+/// `value` with every sensitive field replaced by `REDACTED`, at any depth
+pub fn redact(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let value = if is_sensitive_key(&key) { Value::String(REDACTED.to_string()) } else { redact(value) };
+                    (key, value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact).collect()),
+        other => other,
+    }
+}
+
+/// A response body as captured: redacted JSON, or the (possibly cut) text
+fn response_value(body: &[u8]) -> Value {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(json) => redact(json),
+        Err(_) => {
+            let text = String::from_utf8_lossy(body);
+            if text.len() <= MAX_TEXT_BODY_BYTES {
+                Value::String(text.into_owned())
+            } else {
+                let mut end = MAX_TEXT_BODY_BYTES;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                Value::String(format!("{}... ({} bytes)", &text[..end], text.len()))
+            }
+        }
+    }
+}
+
+/// Rotated file `index` of `path` (`<path>.<index>`)
+pub fn rotated_path(path: &str, index: u32) -> String {
+    format!("{}.{}", path, index)
+}
+
+/// Shift `path` to `<path>.1`, older files one further, dropping the one past `max_files`
+fn rotate(path: &str, max_files: u32) -> io::Result<()> {
+    if max_files == 0 {
+        return fs::remove_file(path);
+    }
+    let _ = fs::remove_file(rotated_path(path, max_files));
+    for index in (1..max_files).rev() {
+        let from = rotated_path(path, index);
+        if fs::metadata(&from).is_ok() {
+            fs::rename(&from, rotated_path(path, index + 1))?;
+        }
+    }
+    fs::rename(path, rotated_path(path, 1))
+}
+
+/// The capture switch and file settings
+pub struct ApiCapture {
+    enabled: AtomicBool,
+    config: Mutex<ApiCaptureConfig>,
+}
+
+impl ApiCapture {
+    pub fn new(config: ApiCaptureConfig) -> Self {
+        Self { enabled: AtomicBool::new(config.enabled), config: Mutex::new(config) }
+    }
+
+    /// Apply a loaded config, including its `enabled` switch
+    pub fn configure(&self, config: &ApiCaptureConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = config.clone();
+        self.set_enabled(config.enabled);
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn path(&self) -> String {
+        self.config.lock().unwrap_or_else(|e| e.into_inner()).path.clone()
+    }
+
+    /// Append `call` as one line, rotating the file first if the line would take it past
+    /// `max_file_mb`
+    pub fn write(&self, call: &CapturedCall) -> io::Result<()> {
+        let config = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        let mut line = serde_json::to_string(call)?;
+        line.push('\n');
+        let max_bytes = config.max_file_mb.saturating_mul(1024 * 1024);
+        let size = fs::metadata(&config.path).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 > max_bytes {
+            rotate(&config.path, config.max_files)?;
+        }
+        OpenOptions::new().create(true).append(true).open(&config.path)?.write_all(line.as_bytes())
+    }
+}
+
+/// Process-wide capture used by the API clients
+pub fn global() -> &'static ApiCapture {
+    static CAPTURE: OnceLock<ApiCapture> = OnceLock::new();
+    CAPTURE.get_or_init(|| ApiCapture::new(ApiCaptureConfig::default()))
+}
+
+/// `metrics::observe_http` for a request without a body, captured while enabled
+pub async fn observe_http(
+    endpoint: &str,
+    request: impl Future<Output = reqwest::Result<reqwest::Response>>,
+) -> reqwest::Result<reqwest::Response> {
+    observe(endpoint, None, request).await
+}
+
+/// `metrics::observe_http` for a request sending `body` as JSON, captured while enabled
+pub async fn observe_http_json<B: Serialize + ?Sized>(
+    endpoint: &str,
+    body: &B,
+    request: impl Future<Output = reqwest::Result<reqwest::Response>>,
+) -> reqwest::Result<reqwest::Response> {
+    // Only serialized again while capturing
+    let body = global().is_enabled().then(|| serde_json::to_value(body).ok()).flatten();
+    observe(endpoint, body, request).await
+}
+
+async fn observe(
+    endpoint: &str,
+    body: Option<Value>,
+    request: impl Future<Output = reqwest::Result<reqwest::Response>>,
+) -> reqwest::Result<reqwest::Response> {
+    let capture = global();
+    if !capture.is_enabled() {
+        return metrics::observe_http(endpoint, request).await;
+    }
+    let at_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    let started = Instant::now();
+    let mut call = CapturedCall {
+        at_ms,
+        endpoint: endpoint.to_string(),
+        url: None,
+        request: body.map(redact),
+        status: None,
+        response: None,
+        error: None,
+        elapsed_ms: 0,
+    };
+    let result = match metrics::observe_http(endpoint, request).await {
+        Ok(response) => {
+            call.url = Some(response.url().to_string());
+            call.status = Some(response.status().as_u16());
+            // The body can only be read once: read it here and hand the caller a copy
+            let (status, version, headers) = (response.status(), response.version(), response.headers().clone());
+            match response.bytes().await {
+                Ok(bytes) => {
+                    call.response = Some(response_value(&bytes));
+                    let mut copy = http::Response::new(bytes);
+                    *copy.status_mut() = status;
+                    *copy.version_mut() = version;
+                    *copy.headers_mut() = headers;
+                    Ok(reqwest::Response::from(copy))
+                }
+                Err(e) => {
+                    call.error = Some(e.to_string());
+                    Err(e)
+                }
+            }
+        }
+        Err(e) => {
+            call.url = e.url().map(|u| u.to_string());
+            call.error = Some(e.to_string());
+            Err(e)
+        }
+    };
+    call.elapsed_ms = started.elapsed().as_millis() as u64;
+    if let Err(e) = capture.write(&call) {
+        warn!("Failed to write API capture to {}: {}", capture.path(), e);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redact_and_rotate() {
+        let order = json!({
+            "account": "wallet",
+            "signature": "5xYz",
+            "agent_wallet": "agent",
+            "settlement": {"signature": {"r": "0x1", "s": "0x2"}, "starkKey": "0xabc", "collateralPosition": "7"},
+            "orders": [{"api_key": "k", "price": "100"}],
+        });
+        let redacted = redact(order);
+        assert_eq!(redacted["account"], "wallet");
+        assert_eq!(redacted["agent_wallet"], "agent");
+        assert_eq!(redacted["signature"], REDACTED);
+        assert_eq!(redacted["settlement"]["signature"], REDACTED);
+        assert_eq!(redacted["settlement"]["starkKey"], REDACTED);
+        assert_eq!(redacted["settlement"]["collateralPosition"], "7");
+        assert_eq!(redacted["orders"][0]["api_key"], REDACTED);
+        assert_eq!(redacted["orders"][0]["price"], "100");
+        assert_eq!(response_value(b"Bad Gateway"), json!("Bad Gateway"));
+
+        let dir = std::env::temp_dir().join(format!("api_capture_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("capture.jsonl").to_string_lossy().into_owned();
+        let capture = ApiCapture::new(ApiCaptureConfig { enabled: true, path: path.clone(), max_file_mb: 1, max_files: 2 });
+        let call = CapturedCall {
+            at_ms: 0,
+            endpoint: "extended POST /user/order".to_string(),
+            url: None,
+            request: None,
+            status: Some(400),
+            response: Some(Value::String("x".repeat(400 * 1024))),
+            error: None,
+            elapsed_ms: 5,
+        };
+        // Two ~400KB lines fit in 1MB; each further one rotates
+        for _ in 0..5 {
+            capture.write(&call).unwrap();
+        }
+        let lines = |p: &str| fs::read_to_string(p).map(|s| s.lines().count()).unwrap_or(0);
+        assert_eq!(lines(&path), 1);
+        assert_eq!(lines(&rotated_path(&path, 1)), 2);
+        assert_eq!(lines(&rotated_path(&path, 2)), 2);
+        assert!(fs::metadata(rotated_path(&path, 3)).is_err());
+        let back: CapturedCall = serde_json::from_str(fs::read_to_string(&path).unwrap().trim()).unwrap();
+        assert_eq!(back, call);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+//! Periodic full reconciliation audit
+//!
+//! Every `audit.interval_minutes` the bot cross-checks its state against live positions,
+//! resting orders and recent fills on both venues. Each disagreement is a `Divergence`:
+//! a tracked leg whose live size differs (or is gone), a live position the state does
+//! not know about, a resting order nothing should have left behind, or a fill on a
+//! symbol the bot neither held nor traded since the previous audit. The resulting
+//! `AuditReport` is logged as a single JSON line and, when it found anything, sent to
+//! the audit hooks (a JSON POST to `audit.webhook_url` when set). With
+//! `audit.auto_remediate` the bot cancels orphan orders and re-syncs its state to the
+//! live legs; unknown positions on other symbols are only reported.
+use crate::capital::Venue;
+use crate::trading::DeltaNeutralPosition;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Relative size difference below which a tracked and a live leg agree
+const SIZE_TOLERANCE: f64 = 1e-6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Minutes between audits
+    #[serde(default = "default_interval_minutes")]
+    pub interval_minutes: u64,
+    /// Cancel orphan orders and re-sync state to the live legs after an audit that
+    /// found divergences
+    #[serde(default)]
+    pub auto_remediate: bool,
+    /// POST each audit that found divergences as JSON to this URL (Slack/Discord-compatible)
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+fn default_interval_minutes() -> u64 {
+    60
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: default_interval_minutes(),
+            auto_remediate: false,
+            webhook_url: None,
+        }
+    }
+}
+
+impl AuditConfig {
+    /// Whether an audit is due given the previous one at `last_at` (unix seconds)
+    pub fn is_due(&self, last_at: Option<u64>, now: u64) -> bool {
+        self.enabled && last_at.is_none_or(|at| now.saturating_sub(at) >= self.interval_minutes * 60)
+    }
+}
+
+/// Live position leg; `size_base` is signed (long positive)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveLeg {
+    pub venue: Venue,
+    pub symbol: String,
+    pub size_base: f64,
+}
+
+/// Resting order on a venue
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveOrder {
+    pub venue: Venue,
+    pub symbol: String,
+    /// ID the order can be cancelled by: the external ID on Extended, the order ID on Pacifica
+    pub order_id: String,
+    pub size_base: f64,
+}
+
+/// Fill since the previous audit
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveFill {
+    pub venue: Venue,
+    pub symbol: String,
+    pub size_base: f64,
+    /// Unix timestamp in milliseconds
+    pub at_ms: u64,
+}
+
+/// What the venues report, normalized to bare symbols (`BTC`, not `BTC-USD`)
+#[derive(Debug, Clone, Default)]
+pub struct LiveAccounts {
+    pub positions: Vec<LiveLeg>,
+    pub orders: Vec<LiveOrder>,
+    pub fills: Vec<LiveFill>,
+}
+
+/// One disagreement between bot state and a venue
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Divergence {
+    /// A tracked leg is live with a different signed size
+    SizeMismatch { venue: Venue, symbol: String, tracked_base: f64, live_base: f64 },
+    /// A tracked leg is not live
+    MissingLeg { venue: Venue, symbol: String, tracked_base: f64 },
+    /// A live position the state does not track
+    UnknownPosition { venue: Venue, symbol: String, live_base: f64 },
+    /// A resting order; the bot leaves none between cycles
+    OrphanOrder { venue: Venue, symbol: String, order_id: String, size_base: f64 },
+    /// A fill on a symbol the bot neither held nor traded
+    UnexpectedFill { venue: Venue, symbol: String, size_base: f64, at_ms: u64 },
+}
+
+impl Divergence {
+    /// Whether re-syncing state to the live legs addresses this divergence
+    pub fn is_position(&self) -> bool {
+        matches!(
+            self,
+            Divergence::SizeMismatch { .. } | Divergence::MissingLeg { .. } | Divergence::UnknownPosition { .. }
+        )
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Divergence::SizeMismatch { venue, symbol, tracked_base, live_base } => {
+                write!(f, "{} {} size {} tracked vs {} live", venue, symbol, tracked_base, live_base)
+            }
+            Divergence::MissingLeg { venue, symbol, tracked_base } => {
+                write!(f, "{} {} leg of {} tracked but not live", venue, symbol, tracked_base)
+            }
+            Divergence::UnknownPosition { venue, symbol, live_base } => {
+                write!(f, "{} {} position of {} not tracked", venue, symbol, live_base)
+            }
+            Divergence::OrphanOrder { venue, symbol, order_id, size_base } => {
+                write!(f, "{} {} order {} for {} left resting", venue, symbol, order_id, size_base)
+            }
+            Divergence::UnexpectedFill { venue, symbol, size_base, at_ms } => {
+                write!(f, "{} {} fill of {} at {}ms not made by the bot", venue, symbol, size_base, at_ms)
+            }
+        }
+    }
+}
+
+fn sizes_match(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-12 + SIZE_TOLERANCE * a.abs().max(b.abs())
+}
+
+/// Signed tracked size of each leg of `position`
+pub fn tracked_legs(position: &DeltaNeutralPosition) -> Vec<LiveLeg> {
+    let mut legs = Vec::new();
+    if let Some(p) = &position.extended_position {
+        let sign = if p.is_long() { 1.0 } else { -1.0 };
+        legs.push(LiveLeg { venue: Venue::Extended, symbol: position.symbol.clone(), size_base: sign * p.size_f64() });
+    }
+    if let Some(p) = &position.pacifica_position {
+        let sign = if p.is_long() { 1.0 } else { -1.0 };
+        legs.push(LiveLeg { venue: Venue::Pacifica, symbol: position.symbol.clone(), size_base: sign * p.size() });
+    }
+    legs
+}
+
+/// Compare tracked state with the venues. Fills on `traded_symbols` (or the held
+/// symbol) are expected; any other fill is reported.
+pub fn find_divergences(
+    tracked: Option<&DeltaNeutralPosition>,
+    live: &LiveAccounts,
+    traded_symbols: &HashSet<String>,
+) -> Vec<Divergence> {
+    let tracked_legs = tracked.map(tracked_legs).unwrap_or_default();
+    let mut divergences = Vec::new();
+
+    for leg in &tracked_legs {
+        match live.positions.iter().find(|p| p.venue == leg.venue && p.symbol == leg.symbol) {
+            Some(p) if sizes_match(p.size_base, leg.size_base) => {}
+            Some(p) => divergences.push(Divergence::SizeMismatch {
+                venue: leg.venue,
+                symbol: leg.symbol.clone(),
+                tracked_base: leg.size_base,
+                live_base: p.size_base,
+            }),
+            None => divergences.push(Divergence::MissingLeg {
+                venue: leg.venue,
+                symbol: leg.symbol.clone(),
+                tracked_base: leg.size_base,
+            }),
+        }
+    }
+
+    for p in &live.positions {
+        let is_tracked = tracked_legs.iter().any(|leg| leg.venue == p.venue && leg.symbol == p.symbol);
+        if !is_tracked && p.size_base != 0.0 {
+            divergences.push(Divergence::UnknownPosition {
+                venue: p.venue,
+                symbol: p.symbol.clone(),
+                live_base: p.size_base,
+            });
+        }
+    }
+
+    for order in &live.orders {
+        divergences.push(Divergence::OrphanOrder {
+            venue: order.venue,
+            symbol: order.symbol.clone(),
+            order_id: order.order_id.clone(),
+            size_base: order.size_base,
+        });
+    }
+
+    let held = tracked.map(|p| p.symbol.as_str());
+    for fill in &live.fills {
+        if held != Some(fill.symbol.as_str()) && !traded_symbols.contains(&fill.symbol) {
+            divergences.push(Divergence::UnexpectedFill {
+                venue: fill.venue,
+                symbol: fill.symbol.clone(),
+                size_base: fill.size_base,
+                at_ms: fill.at_ms,
+            });
+        }
+    }
+
+    divergences
+}
+
+/// Result of one audit, as logged and sent to audit hooks
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditReport {
+    /// Unix timestamp (seconds) of the audit
+    pub at: u64,
+    /// Symbol held according to bot state, if any
+    pub symbol: Option<String>,
+    pub divergences: Vec<Divergence>,
+    /// Remediation carried out (`audit.auto_remediate`), one entry per action
+    pub remediation: Vec<String>,
+}
+
+impl AuditReport {
+    pub fn is_clean(&self) -> bool {
+        self.divergences.is_empty()
+    }
+
+    /// Single-line JSON for log aggregation
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| self.to_string())
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return write!(f, "Audit: state matches both venues");
+        }
+        let details: Vec<String> = self.divergences.iter().map(|d| d.to_string()).collect();
+        write!(f, "Audit: {} divergence(s): {}", self.divergences.len(), details.join("; "))?;
+        if !self.remediation.is_empty() {
+            write!(f, " | remediated: {}", self.remediation.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pacifica::types::PacificaPosition;
+    use crate::types::{Position, PositionSide};
+    use crate::funding::FundingRateAverage;
+
+    #[test]
+    fn test_find_divergences() {
+        let position = DeltaNeutralPosition {
+            position_id: String::new(),
+            symbol: "BTC".to_string(),
+            extended_position: Some(Position {
+                market: "BTC-USD".to_string(),
+                side: PositionSide::Long,
+                size: "0.5".to_string(),
+                value: "50000".to_string(),
+                entry_price: None,
+                unrealized_pnl: None,
+            }),
+            pacifica_position: Some(PacificaPosition {
+                symbol: "BTC".to_string(),
+                side: "ask".to_string(),
+                amount: "0.5".to_string(),
+                entry_price: "100000".to_string(),
+                margin: String::new(),
+                funding: "0".to_string(),
+                isolated: false,
+                created_at: 0,
+                updated_at: 0,
+            }),
+            opened_at: 0,
+            target_notional_usd: 50_000.0,
+            realized_funding_extended_usd: 0.0,
+            realized_funding_pacifica_usd: 0.0,
+            entry_net_apr: None,
+            extended_entry_price: None,
+            pacifica_entry_price: None,
+            entry_basis_pct: None,
+            spread_capture: None,
+            captured_basis_usd: None,
+            open_execution: None,
+            extended_vault_id: None,
+            direction: None,
+            reductions: 0,
+            protective_generation: None,
+            hold_restarted_at: None,
+            funding_average: FundingRateAverage::default(),
+            extended_market: None,
+            pacifica_market: None,
+            multipliers: Default::default(),
+        };
+        let leg = |venue, symbol: &str, size_base| LiveLeg { venue, symbol: symbol.to_string(), size_base };
+        let fill = |symbol: &str| LiveFill { venue: Venue::Pacifica, symbol: symbol.to_string(), size_base: 1.0, at_ms: 1 };
+        let traded: HashSet<String> = ["ETH".to_string()].into();
+
+        let clean = LiveAccounts {
+            positions: vec![leg(Venue::Extended, "BTC", 0.5), leg(Venue::Pacifica, "BTC", -0.5)],
+            orders: Vec::new(),
+            fills: vec![fill("BTC"), fill("ETH")],
+        };
+        assert!(find_divergences(Some(&position), &clean, &traded).is_empty());
+
+        let diverged = LiveAccounts {
+            positions: vec![leg(Venue::Extended, "BTC", 0.4), leg(Venue::Pacifica, "SOL", 3.0)],
+            orders: vec![LiveOrder { venue: Venue::Extended, symbol: "BTC".to_string(), order_id: "x".to_string(), size_base: 0.1 }],
+            fills: vec![fill("SOL")],
+        };
+        let divergences = find_divergences(Some(&position), &diverged, &traded);
+        assert_eq!(divergences.len(), 5);
+        assert!(matches!(divergences[0], Divergence::SizeMismatch { live_base, .. } if live_base == 0.4));
+        assert!(matches!(divergences[1], Divergence::MissingLeg { venue: Venue::Pacifica, .. }));
+        assert!(matches!(divergences[2], Divergence::UnknownPosition { ref symbol, .. } if symbol == "SOL"));
+        assert!(matches!(divergences[3], Divergence::OrphanOrder { .. }));
+        assert!(matches!(divergences[4], Divergence::UnexpectedFill { ref symbol, .. } if symbol == "SOL"));
+        assert!(divergences[2].is_position() && !divergences[3].is_position());
+
+        // Flat: any live position is unknown
+        let flat = find_divergences(None, &clean, &traded);
+        assert_eq!(flat.len(), 3);
+        assert!(flat.iter().take(2).all(|d| matches!(d, Divergence::UnknownPosition { .. })));
+
+        let config = AuditConfig { enabled: true, ..AuditConfig::default() };
+        assert!(config.is_due(None, 0));
+        assert!(!config.is_due(Some(1_000), 1_000 + 59 * 60));
+        assert!(config.is_due(Some(1_000), 1_000 + 60 * 60));
+        assert!(!AuditConfig::default().is_due(None, 0));
+    }
+}
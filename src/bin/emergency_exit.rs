@@ -1,15 +1,16 @@
 //! Emergency Exit Script
 //!
 //! Standalone binary that immediately closes ALL positions on both Extended and Pacifica.
-//! Uses the bot's proven close logic with working signatures.
+//! Closes every live position, including ones the bot is not tracking, via
+//! `trading::emergency_flatten_all`.
 //!
 //! Usage: cargo run --bin emergency_exit
 //!
 //! This script:
 //! - Loads credentials from .env
 //! - Initializes the bot infrastructure (which has working order placement)
-//! - Closes all positions using the bot's proven close logic
-//! - Reports success/failure
+//! - Closes every position on both venues with reduce-only orders, retrying per leg
+//! - Reports what closed, what was only partially closed, and what is still open
 //!
 //! WARNING: This will close ALL positions without confirmation!
 
@@ -76,30 +77,43 @@ async fn main() -> Result<(), Box<dyn Error>> {
     info!("📊 Current positions:");
     let _ = bot.display_status().await;
 
-    info!("\n🔥 Closing all positions using bot's proven close logic...");
-
-    // Use the bot's proven close logic which has working signatures
-    match bot.close_current_position().await {
-        Ok(()) => {
-            info!("\n╔═══════════════════════════════════════════════════════════════╗");
-            info!("║                  EMERGENCY EXIT SUCCESSFUL                    ║");
-            info!("╚═══════════════════════════════════════════════════════════════╝");
-            info!("\n✅ All positions closed successfully!");
-        }
-        Err(e) => {
-            error!("\n╔═══════════════════════════════════════════════════════════════╗");
-            error!("║                     EMERGENCY EXIT FAILED                     ║");
-            error!("╚═══════════════════════════════════════════════════════════════╝");
-            error!("\n❌ Failed to close positions: {}", e);
-            error!("\n⚠️  IMMEDIATE ACTIONS REQUIRED:");
-            error!("   1. Close manually via exchange web interfaces:");
-            error!("      - Extended: https://app.extended.exchange");
-            error!("      - Pacifica: https://app.pacifica.fi");
-            error!("   2. Check error details above for specific issues");
-            error!("\n⚠️  Some positions may still be open! Verify manually!");
-
-            return Err(e);
-        }
+    info!("\n🔥 Flattening every position on both exchanges...");
+
+    // Enumerates all live positions (not just the tracked one) and closes each leg
+    // with reduce-only orders, retrying per leg
+    let report = bot.emergency_flatten_all().await?;
+
+    for leg in &report.legs {
+        info!(
+            "   {} {} {}: closed {} of {} ({:?}, {} order(s))",
+            leg.exchange,
+            leg.market,
+            if leg.is_long { "LONG" } else { "SHORT" },
+            leg.closed_size(),
+            leg.initial_size,
+            leg.status(),
+            leg.attempts
+        );
+    }
+
+    if report.is_flat() {
+        info!("\n╔═══════════════════════════════════════════════════════════════╗");
+        info!("║                  EMERGENCY EXIT SUCCESSFUL                    ║");
+        info!("╚═══════════════════════════════════════════════════════════════╝");
+        info!("\n✅ {}", report);
+    } else {
+        error!("\n╔═══════════════════════════════════════════════════════════════╗");
+        error!("║                     EMERGENCY EXIT FAILED                     ║");
+        error!("╚═══════════════════════════════════════════════════════════════╝");
+        error!("\n❌ {}", report);
+        error!("\n⚠️  IMMEDIATE ACTIONS REQUIRED:");
+        error!("   1. Close manually via exchange web interfaces:");
+        error!("      - Extended: https://app.extended.exchange");
+        error!("      - Pacifica: https://app.pacifica.fi");
+        error!("   2. Check error details above for specific issues");
+        error!("\n⚠️  Some positions may still be open! Verify manually!");
+
+        return Err(format!("Emergency exit incomplete: {}", report).into());
     }
 
     Ok(())
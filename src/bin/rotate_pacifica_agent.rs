@@ -0,0 +1,66 @@
+//! Pacifica Agent Key Rotation
+//!
+//! Generates a new Pacifica agent wallet, binds it to the account and writes it to the
+//! credentials file. A bot started with `PACIFICA_CREDENTIALS_FILE` re-reads that file at
+//! the start of every cycle and switches to the new key without a restart; one reading
+//! `.env` picks it up at its next restart. The old agent stays bound until it is revoked,
+//! so orders signed with it in the meantime still go through.
+//!
+//! Usage: `SOL_PRIVATE=<main wallet private key> cargo run --bin rotate_pacifica_agent`
+//!
+//! This script:
+//! - Loads SOL_WALLET / API_PUBLIC / API_PRIVATE from the credentials file
+//!   (`PACIFICA_CREDENTIALS_FILE`, default `.env`)
+//! - Binds a freshly generated agent wallet, signed with SOL_PRIVATE (Pacifica only
+//!   accepts binds from the main account key; an agent cannot bind another agent)
+//! - Rewrites API_PUBLIC and API_PRIVATE in the credentials file, keeping other lines
+//!
+//! SOL_PRIVATE is read from the environment only and should not be kept in `.env`.
+
+use extended_connector::opportunity::Config as OpportunityConfig;
+use extended_connector::pacifica::agent::{reloadable_credentials_path, resolve_credentials_path, rewrite_env};
+use extended_connector::pacifica::PacificaCredentials;
+use std::error::Error;
+use tracing::{info, Level};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    tracing_subscriber::fmt()
+        .with_max_level(Level::INFO)
+        .with_target(false)
+        .compact()
+        .init();
+
+    let path = resolve_credentials_path();
+    let credentials = PacificaCredentials::from_env_file(&path)?;
+    let main_private_key = std::env::var("SOL_PRIVATE")
+        .map_err(|_| "SOL_PRIVATE (main wallet private key) must be set to bind an agent wallet")?;
+    let config = OpportunityConfig::load("config.json")?;
+
+    let old_agent = credentials.agent_wallet.clone();
+    info!("Account: {}", credentials.account);
+    info!("Current agent wallet: {}", old_agent);
+
+    let mut client = config.environment.pacifica_client(credentials);
+    let rotated = client.rotate_agent_key(&main_private_key).await?;
+
+    // Write a sibling file and rename it over the original, so a bot reloading the
+    // file never sees it half written
+    let content = std::fs::read_to_string(&path)?;
+    let updated = rewrite_env(
+        &content,
+        &[("API_PUBLIC", &rotated.agent_wallet), ("API_PRIVATE", &rotated.private_key)],
+    );
+    let tmp_path = format!("{}.tmp", path);
+    std::fs::write(&tmp_path, updated)?;
+    std::fs::rename(&tmp_path, &path)?;
+
+    info!("✅ New agent wallet {} bound and written to {}", rotated.agent_wallet, path);
+    if reloadable_credentials_path().is_some() {
+        info!("Running bots switch to it at their next cycle.");
+    } else {
+        info!("Restart running bots to switch to it (set PACIFICA_CREDENTIALS_FILE to switch without a restart).");
+    }
+    info!("Once they have, revoke the old agent wallet {} in the Pacifica dashboard.", old_agent);
+    Ok(())
+}
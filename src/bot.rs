@@ -1,111 +1,362 @@
-/// Funding rate arbitrage bot orchestration and state management
+//! Funding rate arbitrage bot orchestration and state management
 use crate::{
-    OpportunityFinder, RestClient, PacificaTrading, PacificaCredentials, Position,
+    BidAsk, OpportunityFinder, RestClient, PacificaTrading, PacificaCredentials,
     trading::{
-        backoff_delay_ms, calculate_position_size, close_delta_neutral_position,
-        looks_like_rate_limit, open_delta_neutral_position, DeltaNeutralPosition,
+        close_delta_neutral_position, close_partial, emergency_flatten_all, fetch_live_positions,
+        estimated_liquidation_distance_pct, open_delta_neutral_position,
+        minimum_order_notional_usd, plan_spread_capture, validate_leverage, validate_order_size, OrderSizeLimits, DeltaNeutralPosition, ExecutionReport, FlattenReport,
+        LegSequence, LeggingStats, TopOfBookQuotes,
     },
-    OpportunityConfig,
+    Direction, Environment, FundingForecast, FundingRateAverage, MarginMode, Opportunity, OpportunityConfig, ScanResult, SizingConfig,
 };
-use crate::pacifica::types::PacificaPosition;
-use crate::pacifica::PacificaWsTrading;
+use crate::opportunity::{early_rotation_gain_apr, mid_and_spread_pct};
+use crate::funding::{funding_flipped, net_apr_pct};
+use crate::error::BotError;
+use crate::heartbeat::{resolve_heartbeat_path, Heartbeat};
+use crate::state_lock::StateLock;
+use crate::state_store::StateStore;
+use crate::order_sweeper::{OrderSweeper, RestingOrder};
+use crate::protective_orders::{cancel_protective_orders, place_protective_orders, protective_order_ids};
+use crate::strategy::{CrossExchangeFunding, PositionTarget, Strategy};
+use crate::risk::RiskManager;
+use crate::margin_watch::{BalanceSample, MarginLevel};
+use crate::numeric::decimal_to_f64;
+use crate::stress::{risk_report, RiskReport, StressLeg};
+use crate::api_capture;
+use crate::metrics::{self, Degradation, EndpointSummary, VenueHealth};
+use crate::equity::{resolve_equity_history_path, EquityCurve, EquityHistory};
+use crate::decision_log::{resolve_decision_log_path, CycleDecision, DecisionAction, DecisionLog};
+use crate::intent_log::{resolve_intent_log_path, IntentLog, IntentPhase, UnresolvedExecution};
+use crate::build_info::BuildInfo;
+use crate::cycle_budget::{CycleTimer, Phase};
+use crate::capital::{max_pair_notional_usd, CapitalSnapshot, LogAlertHook, TransferSuggestion, Venue, VenueMargin};
+use crate::notify::{Hook, WebhookSink};
+use crate::watchdog::{self, CycleFailure, Incident, IncidentKind};
+use crate::funding_stream::FundingRateCache;
+use crate::account_stream::PacificaAccountCache;
+use crate::fees;
+use crate::shutdown::{Drain, InFlight, ShutdownController};
+use crate::contingency::{BackupHedge, Contingency, ExposureAlert};
+use crate::control::{ControlCommand, ControlError, ControlRequest, ControlServer};
+use crate::daily_summary::{DailySummary, LedgerTotals};
+use crate::audit::{find_divergences, AuditReport, Divergence, LiveAccounts, LiveFill, LiveLeg, LiveOrder};
+use crate::observer::ReadOnlyBot;
+use crate::report;
+use crate::position_manager::PositionManager;
+use crate::entry_timing::{extended_conditions, pacifica_conditions, EntryDelay, EntryTiming};
+use crate::sizing::{
+    calculate_dynamic_position_size, compounded_max_position_size_usd, fetch_atr_pct, margin_capacity_usd,
+    quality_scale, volatility_scale,
+};
+use crate::pacifica::{agent, clock, order_timeout, PacificaAccountInfo, PacificaWsTrading};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::fs;
-use std::path::Path;
-use std::time::Duration;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
 use tokio::time::sleep;
-use futures_util::FutureExt;
-use tracing::{info, warn, error};
-use prettytable::{Table, Row, Cell, format};
-use colored::*;
+use tracing::{debug, info, warn, error};
 
 const DEFAULT_STATE_FILE: &str = "bot_state.json";
 const MONITORING_INTERVAL_MINUTES: u64 = 15;
-const LIVE_POSITIONS_MAX_ATTEMPTS: u32 = 6;
+/// Wait before retrying an open aborted by the pre-send spread check
+const SPREAD_RECHECK_RETRY_SECS: u64 = 60;
+/// Most recent fills fetched per venue by the reconciliation audit
+const AUDIT_FILL_LIMIT: u32 = 100;
+
+/// Point-in-time bot status for external monitoring (`--status-json`)
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusSnapshot {
+    /// Unix timestamp (seconds) when the snapshot was taken
+    pub generated_at: u64,
+    pub environment: Environment,
+    pub strategy: String,
+    pub hold_time_hours: u64,
+    pub position: Option<PositionStatus>,
+    pub last_rotation_time: Option<u64>,
+    pub total_rotations: u64,
+    /// Funding realized over all closed positions, in USD
+    pub total_realized_funding_usd: f64,
+    /// Net APR (%) the last closed position was opened at and averaged while held
+    pub last_closed_net_apr: Option<f64>,
+    pub last_closed_average_net_apr: Option<f64>,
+    /// Basis captured at entry over all closed positions, in USD
+    pub total_captured_basis_usd: f64,
+    /// Trading fees over all opens and closes, in USD
+    pub total_fees_usd: f64,
+    /// Slippage against decision prices over all opens and closes, in USD
+    pub total_slippage_usd: f64,
+    /// Per-leg report of the latest open or close
+    pub last_execution: Option<ExecutionReport>,
+    /// Realized legging risk per leg submission order
+    pub legging: BTreeMap<LegSequence, LeggingStats>,
+    /// Counters kept in memory by the trading process; absent from a snapshot taken by
+    /// another process (`--status-json`, `observe`), which cannot see them
+    #[serde(flatten)]
+    pub runtime: Option<RuntimeStatus>,
+    /// New opens and rotations stopped by a control `pause` or `close` command, or by
+    /// rejected credentials
+    pub paused: bool,
+    /// Why a venue refused the credentials, while paused for it
+    pub auth_failure: Option<String>,
+    /// Stress test of the held position; `None` without one or when prices or equity
+    /// could not be fetched
+    pub risk_report: Option<RiskReport>,
+}
+
+/// Part of a `StatusSnapshot` only the process running the bot knows
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeStatus {
+    pub consecutive_failed_opens: u32,
+    /// Error-budget verdict over the configured window
+    pub api_degradation: Degradation,
+    pub api_health: Vec<VenueHealth>,
+    /// Per-endpoint call counts and latency since this process started
+    pub api_endpoints: Vec<EndpointSummary>,
+}
+
+/// Held position within a `StatusSnapshot`; live fields are `None` when unavailable
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionStatus {
+    pub symbol: String,
+    pub position_id: String,
+    pub opened_at: u64,
+    /// When the position is due for rotation (unix seconds)
+    pub rotation_at: u64,
+    pub hours_until_rotation: f64,
+    pub target_notional_usd: f64,
+    pub entry_net_apr: Option<f64>,
+    /// Time-weighted net funding APR (%) sampled since open, to compare with `entry_net_apr`
+    pub average_net_apr: Option<f64>,
+    pub realized_funding_usd: f64,
+    pub realized_funding_extended_usd: f64,
+    pub realized_funding_pacifica_usd: f64,
+    pub realized_funding_apr: Option<f64>,
+    pub extended_leg_active: bool,
+    pub pacifica_leg_active: bool,
+    pub extended_unrealized_pnl_usd: Option<f64>,
+    pub extended_entry_price: Option<f64>,
+    pub pacifica_entry_price: Option<f64>,
+    pub pacifica_size: Option<f64>,
+    /// Pacifica premium over Extended (%) at entry and at current mids
+    pub entry_basis_pct: Option<f64>,
+    pub current_basis_pct: Option<f64>,
+    /// Price PnL of both legs at current mids, excluding funding
+    pub basis_pnl_usd: Option<f64>,
+    /// Basis locked in by a spread-capture open, in USD
+    pub captured_basis_usd: Option<f64>,
+    /// Funding expected on each leg until rotation at current rates, in USD
+    pub projected_funding_extended_usd: Option<f64>,
+    pub projected_funding_pacifica_usd: Option<f64>,
+    /// Net of both legs; `None` unless both rates are known
+    pub projected_funding_usd: Option<f64>,
+}
+
+/// Current `bot_state.json` schema version. Changes that a `#[serde(default)]` field
+/// cannot absorb bump it and add a step to `STATE_MIGRATIONS`.
+pub const BOT_STATE_VERSION: u32 = 1;
+
+/// `STATE_MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`
+const STATE_MIGRATIONS: [fn(&mut serde_json::Value); BOT_STATE_VERSION as usize] = [migrate_state_v0_to_v1];
+
+/// Version 0 is every file written before versioning. A position opened by those
+/// builds has no `position_id`, so its close orders would get a new random client
+/// order ID seed on every attempt; give it a stable one.
+fn migrate_state_v0_to_v1(state: &mut serde_json::Value) {
+    if let Some(position) = state.get_mut("current_position").and_then(|p| p.as_object_mut()) {
+        let has_id = position
+            .get("position_id")
+            .and_then(|id| id.as_str())
+            .is_some_and(|id| !id.is_empty());
+        if !has_id {
+            position.insert("position_id".to_string(), uuid::Uuid::new_v4().to_string().into());
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BotState {
+    /// Schema version the file was written with (0 when absent: pre-versioning files)
+    #[serde(default)]
+    pub version: u32,
     pub current_position: Option<DeltaNeutralPosition>,
     pub last_rotation_time: Option<u64>,
     pub total_rotations: u64,
+    /// Funding realized over all closed positions, in USD
+    #[serde(default)]
+    pub total_realized_funding_usd: f64,
+    /// Basis captured at entry by spread-capture opens over all closed positions, in USD
+    #[serde(default)]
+    pub total_captured_basis_usd: f64,
+    /// Trading fees paid over all opens and closes, in USD
+    #[serde(default)]
+    pub total_fees_usd: f64,
+    /// Slippage against decision prices over all opens and closes, in USD
+    #[serde(default)]
+    pub total_slippage_usd: f64,
+    /// Per-leg report of the latest open or close
+    #[serde(default)]
+    pub last_execution: Option<ExecutionReport>,
+    /// Legging time and slippage of opens by how their legs were submitted
+    #[serde(default)]
+    pub legging: BTreeMap<LegSequence, LeggingStats>,
+    /// When the last position was closed (unix seconds)
+    #[serde(default)]
+    pub last_close_time: Option<u64>,
+    /// Symbol of the last closed position, for churn protection
+    #[serde(default)]
+    pub last_closed_symbol: Option<String>,
+    /// Net APR (%) the last closed position was opened at
+    #[serde(default)]
+    pub last_closed_net_apr: Option<f64>,
+    /// Time-weighted net funding APR (%) the last closed position actually earned
+    #[serde(default)]
+    pub last_closed_average_net_apr: Option<f64>,
+    /// Combined equity when compounding started; position size grows relative to it
+    #[serde(default)]
+    pub compounding_base_equity_usd: Option<f64>,
+    /// Ledger at the start of the current daily summary period
+    #[serde(default)]
+    pub daily_summary_baseline: Option<LedgerTotals>,
+    /// Symbol the strategy last selected to open, for selection hysteresis
+    #[serde(default)]
+    pub last_selected_symbol: Option<String>,
+    /// Code and config of the bot that last saved this state
+    #[serde(default)]
+    pub build: Option<BuildInfo>,
+    /// Opens and rotations stopped until a control `resume`; kept across restarts
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl Default for BotState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BotState {
     pub fn new() -> Self {
         Self {
+            version: BOT_STATE_VERSION,
             current_position: None,
             last_rotation_time: None,
             total_rotations: 0,
+            total_realized_funding_usd: 0.0,
+            total_captured_basis_usd: 0.0,
+            total_fees_usd: 0.0,
+            total_slippage_usd: 0.0,
+            last_execution: None,
+            legging: BTreeMap::new(),
+            last_close_time: None,
+            last_closed_symbol: None,
+            last_closed_net_apr: None,
+            last_closed_average_net_apr: None,
+            compounding_base_equity_usd: None,
+            daily_summary_baseline: None,
+            last_selected_symbol: None,
+            build: None,
+            paused: false,
         }
     }
 
-    /// Load state from JSON file
-    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        if Path::new(path).exists() {
-            let content = fs::read_to_string(path)?;
-            match serde_json::from_str::<BotState>(&content) {
-                Ok(state) => {
+    /// Parse a state document of any supported version, migrating it to the current
+    /// one. Returns the state and the version the document was written with.
+    pub fn from_json(content: &str) -> Result<(Self, u32), BotError> {
+        let mut document: serde_json::Value = serde_json::from_str(content)?;
+        let stored_version = document.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+        if stored_version > BOT_STATE_VERSION as u64 {
+            return Err(BotError::Config(format!(
+                "state file version {} is newer than this build supports ({}); refusing to load it",
+                stored_version, BOT_STATE_VERSION
+            )));
+        }
+        let stored_version = stored_version as u32;
+        for migrate in &STATE_MIGRATIONS[stored_version as usize..] {
+            migrate(&mut document);
+        }
+        let mut state: BotState = serde_json::from_value(document)?;
+        state.version = BOT_STATE_VERSION;
+        Ok((state, stored_version))
+    }
+
+    /// Load state from `store`, falling back to its backup when the current document
+    /// does not parse. A missing document starts fresh and is written right away.
+    pub fn load(store: &StateStore) -> Result<Self, BotError> {
+        match store.read()? {
+            Some(content) => match Self::from_json(&content) {
+                Ok((state, stored_version)) => {
                     info!("Loaded bot state from {}: {} rotations, position: {}",
-                        path,
+                        store,
                         state.total_rotations,
                         if state.current_position.is_some() { "active" } else { "none" }
                     );
+                    if stored_version < BOT_STATE_VERSION {
+                        info!("Migrated state {} from version {} to {}", store, stored_version, BOT_STATE_VERSION);
+                        state.save(store)?;
+                    }
                     Ok(state)
                 }
+                // A newer build wrote this file: starting fresh would drop its position
+                Err(e @ BotError::Config(_)) => Err(e),
                 Err(e) => {
-                    warn!("Failed to parse state file {}: {}. Trying backup.", path, e);
-                    let backup_path = format!("{}.bak", path);
-                    if Path::new(&backup_path).exists() {
-                        let backup_content = fs::read_to_string(&backup_path)?;
-                        let backup_state: BotState = serde_json::from_str(&backup_content)?;
-                        info!("Loaded bot state from backup {}", backup_path);
+                    warn!("Failed to parse state {}: {}. Trying backup.", store, e);
+                    if let Some(backup_content) = store.read_backup()? {
+                        let (backup_state, _) = Self::from_json(&backup_content)?;
+                        info!("Loaded bot state from the backup of {}", store);
                         Ok(backup_state)
                     } else {
                         warn!("No valid backup found. Starting fresh.");
                         let state = Self::new();
-                        if let Err(write_err) = state.save_to_file(path) {
-                            warn!("Failed to write initial state file {}: {}", path, write_err);
+                        if let Err(write_err) = state.save(store) {
+                            warn!("Failed to write initial state {}: {}", store, write_err);
                         }
                         Ok(state)
                     }
                 }
+            },
+            None => {
+                info!("No existing state found in {}, starting fresh", store);
+                let state = Self::new();
+                if let Err(e) = state.save(store) {
+                    warn!("Failed to write initial state {}: {}", store, e);
+                }
+                Ok(state)
             }
-        } else {
-            info!("No existing state file found, starting fresh");
-            let state = Self::new();
-            if let Err(e) = state.save_to_file(path) {
-                warn!("Failed to write initial state file {}: {}", path, e);
-            }
-            Ok(state)
         }
     }
 
-    /// Save state to JSON file
-    pub fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(parent) = Path::new(path).parent() {
-            if !parent.as_os_str().is_empty() {
-                fs::create_dir_all(parent)?;
-            }
-        }
-        if Path::new(path).exists() {
-            let backup_path = format!("{}.bak", path);
-            if let Err(e) = fs::copy(path, &backup_path) {
-                warn!("Failed to write backup state file {}: {}", backup_path, e);
-            }
+    /// Load state without ever writing: no initial document, no migration in the store.
+    /// A missing document is a fresh state; an unreadable one is an error.
+    pub fn read(store: &StateStore) -> Result<Self, BotError> {
+        match store.read()? {
+            Some(content) => Ok(Self::from_json(&content)?.0),
+            None => Ok(Self::new()),
         }
-        let content = serde_json::to_string_pretty(self)?;
-        let temp_path = format!("{}.tmp", path);
-        fs::write(&temp_path, &content)?;
-        if let Err(e) = fs::rename(&temp_path, path) {
-            warn!("Atomic state file replace failed: {}. Falling back to direct write.", e);
-            fs::write(path, content)?;
-            let _ = fs::remove_file(&temp_path);
-        }
-        info!("Saved bot state to {}", path);
+    }
+
+    /// Save state to `store`, keeping the previous document as its backup
+    pub fn save(&self, store: &StateStore) -> Result<(), BotError> {
+        store.write(&serde_json::to_string_pretty(self)?)?;
+        info!("Saved bot state to {}", store);
         Ok(())
     }
 
+    /// Load state from JSON file
+    pub fn load_from_file(path: &str) -> Result<Self, BotError> {
+        Self::load(&StateStore::File(path.to_string()))
+    }
+
+    /// `read` from a JSON file
+    pub fn read_from_file(path: &str) -> Result<Self, BotError> {
+        Self::read(&StateStore::File(path.to_string()))
+    }
+
+    /// Save state to JSON file
+    pub fn save_to_file(&self, path: &str) -> Result<(), BotError> {
+        self.save(&StateStore::File(path.to_string()))
+    }
+
     /// Check if current position should be rotated based on configured hold time
     pub fn should_rotate(&self, hold_time_hours: u64) -> bool {
         if let Some(pos) = &self.current_position {
@@ -113,13 +364,72 @@ impl BotState {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs();
-            let elapsed_hours = (now - pos.opened_at) / 3600;
+            let elapsed_hours = now.saturating_sub(pos.hold_started_at()) / 3600;
             elapsed_hours >= hold_time_hours
         } else {
             false
         }
     }
 
+    /// Seconds left before a new position may be opened after the last close
+    pub fn rotation_cooldown_remaining_secs(&self, min_minutes_between_rotations: u64, now: u64) -> u64 {
+        match self.last_close_time {
+            Some(closed_at) => (closed_at + min_minutes_between_rotations * 60).saturating_sub(now),
+            None => 0,
+        }
+    }
+
+    /// True if opening `symbol` at `net_apr` would re-enter the last closed symbol at a lower APR
+    pub fn is_churn_reentry(&self, symbol: &str, net_apr: f64) -> bool {
+        match (&self.last_closed_symbol, self.last_closed_net_apr) {
+            (Some(last), Some(last_apr)) => last == symbol && net_apr < last_apr,
+            _ => false,
+        }
+    }
+
+    /// Symbol a new selection must beat by `scoring.switch_margin_apr_pct`: the held
+    /// one, else the last one selected
+    pub fn previous_selection(&self) -> Option<&str> {
+        self.current_position
+            .as_ref()
+            .map(|p| p.symbol.as_str())
+            .or(self.last_selected_symbol.as_deref())
+    }
+
+    /// Add an open's or close's fees and slippage to the running totals
+    fn record_execution(&mut self, report: &ExecutionReport) {
+        self.total_fees_usd += report.total_fees_usd();
+        self.total_slippage_usd += report.total_slippage_usd();
+        self.last_execution = Some(report.clone());
+        if let Some(sequence) = report.sequence {
+            self.legging.entry(sequence).or_default().record(report);
+        }
+    }
+
+    /// Record a closed position for cooldown and churn checks
+    fn record_close(&mut self, closed: &DeltaNeutralPosition, now: u64) {
+        self.total_realized_funding_usd += closed.realized_funding_usd();
+        self.total_captured_basis_usd += closed.captured_basis_usd.unwrap_or(0.0);
+        self.last_close_time = Some(now);
+        self.last_closed_symbol = Some(closed.symbol.clone());
+        self.last_closed_net_apr = closed.entry_net_apr;
+        self.last_closed_average_net_apr = closed.average_net_apr();
+        if let (Some(entry), Some(average)) = (closed.entry_net_apr, closed.average_net_apr()) {
+            info!(
+                "Funding APR on {} over {:.1}h: {:.2}% at open, {:.2}% time-weighted average ({:+.2}% decay)",
+                closed.symbol, closed.funding_average.hours, entry, average, average - entry
+            );
+        }
+    }
+
+    /// Keep the current position for another hold period instead of rotating into the
+    /// same symbol and direction
+    fn restart_hold(&mut self, now: u64) {
+        if let Some(pos) = &mut self.current_position {
+            pos.hold_restarted_at = Some(now);
+        }
+    }
+
     /// Get time remaining until rotation (in hours)
     pub fn hours_until_rotation(&self, hold_time_hours: u64) -> Option<f64> {
         if let Some(pos) = &self.current_position {
@@ -127,7 +437,7 @@ impl BotState {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs();
-            let elapsed_hours = (now - pos.opened_at) as f64 / 3600.0;
+            let elapsed_hours = now.saturating_sub(pos.hold_started_at()) as f64 / 3600.0;
             let remaining = hold_time_hours as f64 - elapsed_hours;
             Some(remaining.max(0.0))
         } else {
@@ -149,16 +459,180 @@ pub struct FundingBot {
     opportunity_finder: OpportunityFinder,
     config: OpportunityConfig,
     state: BotState,
-    state_path: String,
+    state_store: StateStore,
     stark_private_key: String,
     stark_public_key: String,
     vault_id: String,
+    rebalance_hook: Box<dyn Hook<TransferSuggestion>>,
+    strategy: Box<dyn Strategy>,
+    risk: RiskManager,
+    heartbeat: Heartbeat,
+    heartbeat_path: String,
+    intents: IntentLog,
+    /// Per-cycle equity snapshots behind `equity_curve`
+    equity_history: EquityHistory,
+    /// Per-cycle scan inputs, actions and skip reasons
+    decision_log: DecisionLog,
+    /// Decision of the cycle in progress, appended to `decision_log` when it ends
+    decision: CycleDecision,
+    /// Executions a previous run never resolved and that are not in bot state
+    interrupted_executions: Vec<UnresolvedExecution>,
+    /// Symbol whose open was aborted because spreads widened after the scan; the
+    /// next attempt comes after `SPREAD_RECHECK_RETRY_SECS` instead of a full cycle
+    requeued_symbol: Option<String>,
+    /// Open delayed by `entry_timing` until candles calm down; the next cycle comes
+    /// after `entry_timing.recheck_seconds` instead of a full cycle
+    entry_delay: Option<EntryDelay>,
+    /// Phase timings of the cycle in progress, against `cycle_budget`
+    cycle_timer: CycleTimer,
+    /// Notified of every watchdog incident (logging happens regardless)
+    incident_hooks: Vec<Arc<dyn Hook<Incident>>>,
+    /// Notified of every daily summary (logging happens regardless)
+    summary_hooks: Vec<Box<dyn Hook<DailySummary>>>,
+    /// Notified of every audit that found divergences (logging happens regardless)
+    audit_hooks: Vec<Box<dyn Hook<AuditReport>>>,
+    /// Backup hedge and alerts for a leg whose rollback failed (`contingency`)
+    contingency: Contingency,
+    /// Unix timestamp (seconds) of the last reconciliation audit in this process
+    last_audit_at: Option<u64>,
+    /// Built for a `ReadOnlyBot`: state is reconciled in memory and never written back
+    read_only: bool,
+    /// Single-instance lock on the state file, taken by `recover_at_startup`
+    state_lock: Option<StateLock>,
+    /// Cancels resting orders of no tracked position (`order_sweep`)
+    order_sweeper: OrderSweeper,
+    /// Background canceller of stale Pacifica orders (`pacifica_order_timeout`)
+    order_timeout_task: Option<JoinHandle<()>>,
+    /// ID of the held position, shared with the canceller so it keeps that position's orders
+    held_position_id: Arc<Mutex<Option<String>>>,
+    /// Runtime command server (`control`), started by `run`
+    control: Option<ControlServer>,
+    /// Set when a venue refused the credentials; cleared by a control `resume`
+    auth_failure: Option<String>,
+    /// Set by a control `rotate`: the next cycle rotates regardless of hold time
+    force_rotate: bool,
+    /// Streamed funding rates (`funding_stream`), started by `run`
+    funding_cache: Option<FundingRateCache>,
+    /// Streamed Pacifica account info (`account_stream`), started by `run`
+    pacifica_account: Option<PacificaAccountCache>,
+    /// The held position's streamed net APR is below `funding_stream.flip_threshold_apr_pct`;
+    /// a flip wakes the bot once, not on every check while it lasts
+    funding_flipped: bool,
+    /// Shutdown signal and the opens, closes and reductions exit waits for
+    shutdown: ShutdownController,
+}
+
+/// Assembles a `FundingBot` (`build`), a `PositionManager` driven by external signals
+/// (`build_position_manager`) or a `ReadOnlyBot` (`build_read_only`). Only the first
+/// two accept signing keys into a bot; `build_read_only` discards them.
+pub struct BotBuilder {
+    config: OpportunityConfig,
+    extended_api_key: Option<String>,
+    pacifica_account: Option<String>,
+    pacifica_creds: Option<PacificaCredentials>,
+    /// (private key, public key, vault ID)
+    stark_keys: Option<(String, String, String)>,
+}
+
+impl BotBuilder {
+    pub fn extended_api_key(mut self, api_key: Option<String>) -> Self {
+        self.extended_api_key = api_key;
+        self
+    }
+
+    /// Pacifica account address (SOL_WALLET), all a read-only bot needs
+    pub fn pacifica_account(mut self, account: impl Into<String>) -> Self {
+        self.pacifica_account = Some(account.into());
+        self
+    }
+
+    /// Pacifica account and agent signing key
+    pub fn pacifica_credentials(mut self, credentials: PacificaCredentials) -> Self {
+        self.pacifica_creds = Some(credentials);
+        self
+    }
+
+    /// Starknet keys and vault for signing Extended orders
+    pub fn stark_keys(mut self, private_key: impl Into<String>, public_key: impl Into<String>, vault_id: impl Into<String>) -> Self {
+        self.stark_keys = Some((private_key.into(), public_key.into(), vault_id.into()));
+        self
+    }
+
+    /// Trading bot; fails without signing keys for both venues
+    pub fn build(self) -> Result<FundingBot, BotError> {
+        let pacifica_creds = self
+            .pacifica_creds
+            .filter(PacificaCredentials::can_sign)
+            .ok_or_else(|| BotError::Config("Pacifica agent key required to trade (use build_read_only to observe)".into()))?;
+        let (stark_private_key, stark_public_key, vault_id) = self
+            .stark_keys
+            .ok_or_else(|| BotError::Config("Stark keys and vault required to trade (use build_read_only to observe)".into()))?;
+        FundingBot::new(self.extended_api_key, pacifica_creds, self.config, stark_private_key, stark_public_key, vault_id)
+    }
+
+    /// Trading bot without the scanning loop, for strategies that pick positions
+    /// themselves; needs the same signing keys as `build`
+    pub fn build_position_manager(self) -> Result<PositionManager, BotError> {
+        let extended_api_key = self.extended_api_key.clone();
+        Ok(PositionManager::new(self.build()?, extended_api_key))
+    }
+
+    /// Observer without signing keys: any given to the builder are dropped here
+    pub fn build_read_only(self) -> Result<ReadOnlyBot, BotError> {
+        let account = self
+            .pacifica_account
+            .or(self.pacifica_creds.map(|c| c.account))
+            .ok_or_else(|| BotError::Config("Pacifica account (SOL_WALLET) required".into()))?;
+        let state = BotState::read(&resolve_state_store()?)?;
+        let mut bot = FundingBot::with_state(
+            self.extended_api_key.clone(),
+            PacificaCredentials::read_only(account),
+            self.config,
+            String::new(),
+            String::new(),
+            String::new(),
+            state,
+        )?;
+        bot.read_only = true;
+        Ok(ReadOnlyBot::new(bot, self.extended_api_key))
+    }
 }
 
-fn resolve_state_path() -> String {
+pub(crate) fn resolve_state_path() -> String {
     std::env::var("STATE_FILE_PATH").unwrap_or_else(|_| DEFAULT_STATE_FILE.to_string())
 }
 
+/// State backend named by `STATE_FILE_PATH` (a path or a `scheme://` URI)
+pub(crate) fn resolve_state_store() -> Result<StateStore, BotError> {
+    StateStore::parse(&resolve_state_path())
+}
+
+/// Record what a resting order found at startup did against the logged intent it
+/// belongs to. Orders matching no intent are only logged. An unfilled order that could
+/// not be cancelled stays `Pending`, since it may still execute.
+fn adopt_in_flight_order(
+    intents: &IntentLog,
+    executions: &[UnresolvedExecution],
+    venue: Venue,
+    client_id: &str,
+    filled: f64,
+    cancelled: bool,
+) {
+    let Some(intent) = executions.iter().find_map(|e| e.order_for_client_id(client_id)) else {
+        warn!("{} order {} matches no logged intent{}", venue, client_id,
+            if cancelled { "; cancelled" } else { "" });
+        return;
+    };
+    let leg = intent.leg.map_or(String::new(), |leg| format!("{:?}", leg));
+    info!("Adopted {} {} order {} of {} (filled {}{})", venue, leg, client_id, intent.symbol, filled,
+        if cancelled { ", remainder cancelled" } else { "" });
+    if filled > 0.0 {
+        intents.record_or_warn(&intent.outcome(IntentPhase::Filled, filled));
+    } else if cancelled {
+        intents.record_or_warn(&intent.outcome(IntentPhase::Failed, 0.0));
+    }
+}
+
 impl FundingBot {
     pub fn new(
         extended_api_key: Option<String>,
@@ -167,17 +641,51 @@ impl FundingBot {
         stark_private_key: String,
         stark_public_key: String,
         vault_id: String,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let extended_client = RestClient::new_mainnet(extended_api_key.clone())?;
-        let pacifica_client = PacificaTrading::new(pacifica_creds.clone());
+    ) -> Result<Self, BotError> {
+        let state = BotState::load(&resolve_state_store()?)?;
+        Self::with_state(extended_api_key, pacifica_creds, config, stark_private_key, stark_public_key, vault_id, state)
+    }
+
+    fn with_state(
+        extended_api_key: Option<String>,
+        pacifica_creds: PacificaCredentials,
+        config: OpportunityConfig,
+        stark_private_key: String,
+        stark_public_key: String,
+        vault_id: String,
+        mut state: BotState,
+    ) -> Result<Self, BotError> {
+        api_capture::global().configure(&config.api_capture);
+        let build_info = BuildInfo::new(&config);
+        if let Some(previous) = state.build.as_ref().filter(|previous| **previous != build_info) {
+            info!("State was last saved by {}", previous);
+        }
+        state.build = Some(build_info);
+        let extended_client = config.environment.extended_client(extended_api_key.clone())?;
+        let pacifica_client = config.environment.pacifica_client(pacifica_creds.clone()).with_retry(config.retry.clone());
         let opportunity_finder = OpportunityFinder::new(
             extended_api_key.clone(),
             pacifica_creds.clone(),
             config.clone(),
         )?;
 
-        let state_path = resolve_state_path();
-        let state = BotState::load_from_file(&state_path)?;
+        let state_store = resolve_state_store()?;
+        let risk = RiskManager::new(config.risk.clone());
+        let mut incident_hooks: Vec<Arc<dyn Hook<Incident>>> = Vec::new();
+        if let Some(url) = config.watchdog.webhook_url.as_ref().filter(|u| !u.is_empty()) {
+            incident_hooks.push(Arc::new(WebhookSink::new(url.clone(), "incident", |incident: &Incident| format!("🚨 {}", incident))));
+        }
+        let mut summary_hooks: Vec<Box<dyn Hook<DailySummary>>> = Vec::new();
+        if let Some(url) = config.daily_summary.webhook_url.as_ref().filter(|u| !u.is_empty()) {
+            summary_hooks.push(Box::new(WebhookSink::new(url.clone(), "summary", |summary: &DailySummary| format!("📊 {}", summary))));
+        }
+        let mut audit_hooks: Vec<Box<dyn Hook<AuditReport>>> = Vec::new();
+        if let Some(url) = config.audit.webhook_url.as_ref().filter(|u| !u.is_empty()) {
+            audit_hooks.push(Box::new(WebhookSink::new(url.clone(), "audit", |report: &AuditReport| format!("🔎 {}", report))));
+        }
+
+        let contingency = Contingency::from_config(&config.contingency);
+        let cycle_timer = CycleTimer::start(&config.cycle_budget);
 
         Ok(Self {
             extended_client,
@@ -186,85 +694,394 @@ impl FundingBot {
             opportunity_finder,
             config,
             state,
-            state_path,
+            state_store,
             stark_private_key,
             stark_public_key,
             vault_id,
+            rebalance_hook: Box::new(LogAlertHook),
+            strategy: Box::new(CrossExchangeFunding),
+            risk,
+            heartbeat: Heartbeat::new(),
+            heartbeat_path: resolve_heartbeat_path(),
+            intents: IntentLog::new(resolve_intent_log_path()),
+            equity_history: EquityHistory::new(resolve_equity_history_path()),
+            decision_log: DecisionLog::new(resolve_decision_log_path()),
+            decision: CycleDecision::new(0, None),
+            interrupted_executions: Vec::new(),
+            requeued_symbol: None,
+            entry_delay: None,
+            cycle_timer,
+            incident_hooks,
+            summary_hooks,
+            audit_hooks,
+            contingency,
+            last_audit_at: None,
+            read_only: false,
+            state_lock: None,
+            order_sweeper: OrderSweeper::new(),
+            order_timeout_task: None,
+            held_position_id: Arc::new(Mutex::new(None)),
+            control: None,
+            auth_failure: None,
+            force_rotate: false,
+            funding_cache: None,
+            pacifica_account: None,
+            funding_flipped: false,
+            shutdown: ShutdownController::new(),
         })
     }
 
-    async fn fetch_live_positions_with_backoff(
+    /// Builder for a trading bot or, with read credentials only, a `ReadOnlyBot`
+    pub fn builder(config: OpportunityConfig) -> BotBuilder {
+        BotBuilder {
+            config,
+            extended_api_key: None,
+            pacifica_account: None,
+            pacifica_creds: None,
+            stark_keys: None,
+        }
+    }
+
+    async fn save_state(&self) -> Result<(), BotError> {
+        self.publish_held_position();
+        if self.read_only {
+            return Ok(());
+        }
+        let state = self.state.clone();
+        self.state_store.blocking(move |store| state.save(store)).await
+    }
+
+    /// Stop or allow opens and rotations, saved so a restart keeps the pause
+    async fn set_paused(&mut self, paused: bool) {
+        self.state.paused = paused;
+        if let Err(e) = self.save_state().await {
+            warn!("Failed to save the {} state: {}", if paused { "paused" } else { "resumed" }, e);
+        }
+    }
+
+    pub fn state(&self) -> &BotState {
+        &self.state
+    }
+
+    /// Version of this binary and hash of the config it runs with
+    pub fn build_info(&self) -> BuildInfo {
+        self.state.build.clone().unwrap_or_else(|| BuildInfo::new(&self.config))
+    }
+
+    /// Replace in-memory state with the state file (written by another bot process)
+    pub(crate) async fn reload_state(&mut self) -> Result<(), BotError> {
+        self.state = self.state_store.blocking(BotState::read).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn scan(&self, extended_api_key: Option<String>) -> Result<ScanResult, BotError> {
+        self.opportunity_finder.scan(extended_api_key).await
+    }
+
+    pub(crate) async fn evaluate_symbol(&self, symbol: &str, extended_api_key: Option<String>) -> Result<Opportunity, BotError> {
+        self.opportunity_finder.evaluate_symbol(symbol, extended_api_key).await
+    }
+
+    /// Reconcile the tracked position, or adopt an untracked live one, as at startup
+    pub(crate) async fn reconcile_or_recover(&mut self) -> Result<(), BotError> {
+        self.reconcile_state().await?;
+        if let RecoveryOutcome::Blocked(details) = self.recover_state_if_untracked().await? {
+            warn!("Live positions do not match a single delta-neutral pair: {}", details);
+        }
+        Ok(())
+    }
+
+    /// Check that `extended_vaults` names no vault but `VAULT_NUMBER`, which must be an
+    /// account of the API key signed for by the Stark key. Nothing to check without overrides.
+    pub async fn verify_extended_vaults(&self) -> Result<(), BotError> {
+        if self.config.extended_vaults.configured_vaults().is_empty() {
+            return Ok(());
+        }
+        let accounts = self.extended_client.get_accounts().await?;
+        self.config
+            .extended_vaults
+            .verify(&self.vault_id, &accounts, &self.stark_public_key)
+            .map_err(BotError::Config)
+    }
+
+    /// Replace the default (log-only) rebalance hook, e.g. with automated transfers
+    pub fn set_rebalance_hook(&mut self, hook: Box<dyn Hook<TransferSuggestion>>) {
+        self.rebalance_hook = hook;
+    }
+
+    /// Add a watchdog incident receiver (e.g. email, Telegram) next to the configured webhook
+    pub fn add_incident_hook(&mut self, hook: Box<dyn Hook<Incident>>) {
+        self.incident_hooks.push(Arc::from(hook));
+    }
+
+    /// Add a receiver of daily summaries (see `daily_summary.enabled`)
+    pub fn add_summary_hook(&mut self, hook: Box<dyn Hook<DailySummary>>) {
+        self.summary_hooks.push(hook);
+    }
+
+    /// Add a receiver of audits that found divergences (see `audit.enabled`)
+    pub fn add_audit_hook(&mut self, hook: Box<dyn Hook<AuditReport>>) {
+        self.audit_hooks.push(hook);
+    }
+
+    /// Hedge a leg whose rollback failed on a backup venue (see `contingency.enabled`)
+    pub fn set_backup_hedge(&mut self, hedge: Box<dyn BackupHedge>) {
+        self.contingency.set_backup_hedge(hedge);
+    }
+
+    /// Add a receiver of exposure alerts (see `contingency.enabled`) next to the configured webhook
+    pub fn add_exposure_hook(&mut self, hook: Box<dyn Hook<ExposureAlert>>) {
+        self.contingency.add_hook(hook);
+    }
+
+    /// Replace the default cross-exchange funding strategy. The bot keeps scheduling,
+    /// state, sizing and execution; the strategy only chooses what to open.
+    pub fn set_strategy(&mut self, strategy: Box<dyn Strategy>) {
+        info!("Using strategy: {}", strategy.name());
+        self.strategy = strategy;
+    }
+
+    /// Current top of book on both venues: streamed when fresh, REST otherwise
+    async fn live_quotes(
         &self,
-    ) -> Result<(Vec<Position>, Vec<PacificaPosition>), Box<dyn std::error::Error>> {
-        let mut attempt = 0;
+        symbol: &str,
+        extended_market: &str,
+        pacifica_market: &str,
+    ) -> Result<(BidAsk, BidAsk), BotError> {
+        let extended = match self.opportunity_finder.extended_quote(symbol) {
+            Some(quote) => quote,
+            None => BidAsk::from(&self.extended_client.get_orderbook(extended_market).await?),
+        };
+        let pacifica = match self.opportunity_finder.pacifica_quote(symbol) {
+            Some(quote) => quote,
+            None => self.pacifica_client.get_bid_ask_rest(pacifica_market, 1).await?,
+        };
+        Ok((extended, pacifica))
+    }
 
-        loop {
-            attempt += 1;
+    /// Orderbook streams silent for longer than `performance.max_stream_silence_seconds`,
+    /// described for logs; `None` while every started stream is live
+    fn stale_streams(&self) -> Option<String> {
+        let stale = self.opportunity_finder.stale_streams();
+        if stale.is_empty() {
+            return None;
+        }
+        Some(
+            stale
+                .iter()
+                .map(|(venue, silent)| format!("{} orderbook stream silent for {}s", venue, silent.as_secs()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
 
-            let extended_positions = self.extended_client.get_positions(None).await;
-            let pacifica_positions = self.pacifica_client.get_positions().await;
+    /// Error-budget degradation across venues, logging venues over budget
+    fn api_degradation(&self) -> Degradation {
+        let (degradation, health) = metrics::global().degradation(&self.config.error_budget);
+        for venue in health.iter().filter(|h| h.degradation != Degradation::Normal) {
+            warn!("{} API: {}/{} calls failed ({:.1}%) in the last {} minutes",
+                venue.venue, venue.errors, venue.calls, venue.error_rate_pct, self.config.error_budget.window_minutes);
+        }
+        degradation
+    }
 
-            match (extended_positions, pacifica_positions) {
-                (Ok(ext), Ok(pac)) => return Ok((ext, pac)),
-                (ext_res, pac_res) => {
-                    let mut parts = Vec::new();
-                    if let Err(e) = &ext_res {
-                        parts.push(format!("Extended: {}", e));
-                    }
-                    if let Err(e) = &pac_res {
-                        parts.push(format!("Pacifica: {}", e));
-                    }
-                    let err_msg = if parts.is_empty() {
-                        "Unknown error".to_string()
-                    } else {
-                        parts.join(" | ")
-                    };
-
-                    if attempt >= LIVE_POSITIONS_MAX_ATTEMPTS {
-                        return Err(Box::new(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            err_msg,
-                        )));
-                    }
+    /// Compare free collateral on both venues and notify the rebalance hook if skewed
+    async fn advise_rebalance(&self, snapshot: CapitalSnapshot) {
+        if let Some(suggestion) = snapshot.suggest_transfer(&self.config.capital) {
+            if let Err(e) = self.rebalance_hook.notify(&suggestion).await {
+                warn!("Rebalance hook failed: {}", e);
+            }
+        }
+    }
 
-                    let rate_limited = looks_like_rate_limit(&err_msg);
-                    let delay_ms = backoff_delay_ms(attempt, rate_limited);
-                    warn!(
-                        "Failed to fetch live positions (attempt {}/{}{}): {}. Retrying in {}ms...",
-                        attempt,
-                        LIVE_POSITIONS_MAX_ATTEMPTS,
-                        if rate_limited { " - rate limited" } else { "" },
-                        err_msg,
-                        delay_ms
-                    );
-                    sleep(Duration::from_millis(delay_ms)).await;
+    /// Pick up a rotated Pacifica agent key from `PACIFICA_CREDENTIALS_FILE`, when set.
+    /// Only the agent key may change; a file naming another account, or a key that does
+    /// not match API_PUBLIC, is ignored and the current key stays in use.
+    fn reload_pacifica_credentials(&mut self) {
+        let Some(path) = agent::reloadable_credentials_path() else {
+            return;
+        };
+        if !std::path::Path::new(&path).exists() {
+            return;
+        }
+        let reloaded = match PacificaCredentials::from_env_file(&path) {
+            Ok(credentials) => credentials,
+            Err(e) => {
+                warn!("Ignoring Pacifica credentials in {}: {:#}", path, e);
+                return;
+            }
+        };
+        if reloaded.same_as(&self.pacifica_creds) {
+            return;
+        }
+        if reloaded.account != self.pacifica_creds.account {
+            warn!("Ignoring Pacifica credentials in {}: account changed from {} to {} (restart to switch accounts)",
+                path, self.pacifica_creds.account, reloaded.account);
+            return;
+        }
+        info!("🔑 Pacifica agent key rotated: {} -> {}", self.pacifica_creds.agent_wallet, reloaded.agent_wallet);
+        self.pacifica_client.set_credentials(reloaded.clone());
+        self.pacifica_creds = reloaded;
+        if self.order_timeout_task.is_some() {
+            self.start_order_timeout();
+        }
+    }
+
+    /// (Re)start the stale Pacifica order canceller with the current credentials
+    fn start_order_timeout(&mut self) {
+        if let Some(task) = self.order_timeout_task.take() {
+            task.abort();
+        }
+        if self.config.pacifica_order_timeout.enabled {
+            self.publish_held_position();
+            let client = self.config.environment.pacifica_client(self.pacifica_creds.clone());
+            let held = Arc::clone(&self.held_position_id);
+            let intents = IntentLog::new(self.intents.path());
+            self.order_timeout_task = Some(order_timeout::spawn_stale_order_canceller(
+                client,
+                self.config.pacifica_order_timeout.clone(),
+                move || {
+                    let mut position_ids: Vec<String> = held.lock().unwrap_or_else(PoisonError::into_inner).iter().cloned().collect();
+                    position_ids.extend(intents.unresolved()?.into_iter().map(|e| e.position_id));
+                    Ok(position_ids)
+                },
+            ));
+        }
+    }
+
+    /// Share the held position's ID with the stale order canceller
+    fn publish_held_position(&self) {
+        *self.held_position_id.lock().unwrap_or_else(PoisonError::into_inner) =
+            self.state.current_position.as_ref().map(|p| p.position_id.clone());
+    }
+
+    /// Re-estimate the clock offset used for Pacifica signatures.
+    /// Fails if the host clock is too far off to trust, even with correction.
+    async fn sync_clock(&self) -> Result<(), BotError> {
+        let offset_ms = self.pacifica_client.sync_clock().await?;
+        if offset_ms.abs() > clock::CLOCK_SKEW_MAX_MS {
+            return Err(BotError::ClockSkew { offset_ms });
+        }
+        Ok(())
+    }
+
+    /// Replay executions the previous run never resolved, e.g. after a crash between
+    /// the two legs of an open. Untracked ones guide `recover_state_if_untracked`.
+    pub fn replay_intent_log(&mut self) -> Result<(), BotError> {
+        let tracked_id = self.state.current_position.as_ref().map(|p| p.position_id.clone());
+        for execution in self.intents.unresolved()? {
+            if tracked_id.as_deref() == Some(execution.position_id.as_str()) {
+                info!("Unfinished execution for tracked {} position: {}", execution.symbol, execution.summary());
+                continue;
+            }
+            if execution.died_between_legs() {
+                error!("⚠️  Previous run stopped between the legs of a {} open ({}). One leg may be open.",
+                    execution.symbol, execution.summary());
+            } else {
+                warn!("Previous run left an unresolved {} execution ({})", execution.symbol, execution.summary());
+            }
+            self.interrupted_executions.push(execution);
+        }
+        self.intents.compact()
+    }
+
+    /// Settle orders a previous run left resting on either venue, e.g. after a crash
+    /// between placing an order and confirming it. An order whose client ID matches a
+    /// logged intent is adopted: what it filled is recorded against that intent, so the
+    /// replay sees the leg as executed. Every resting order is then cancelled, since this
+    /// process does not continue the execution that placed it, except the held position's
+    /// protective TP/SL orders.
+    pub async fn recover_in_flight_orders(&mut self) -> Result<(), BotError> {
+        let executions = self.intents.unresolved()?;
+        // The held position's TP/SL orders guard it across restarts: leave them resting
+        let protective = self.state.current_position.as_ref().map(protective_order_ids).unwrap_or_default();
+        let protected = |client_id: &str| protective.iter().any(|id| id == client_id);
+        let mut extended_orders = self.extended_client.get_open_orders(None).await?;
+        let mut pacifica_orders = self.pacifica_client.get_open_orders(None).await?;
+        extended_orders.retain(|order| !protected(&order.external_id));
+        pacifica_orders.retain(|order| !order.client_order_id.as_deref().is_some_and(protected));
+        if extended_orders.is_empty() && pacifica_orders.is_empty() {
+            return Ok(());
+        }
+        warn!("Found {} Extended and {} Pacifica open order(s) at startup",
+            extended_orders.len(), pacifica_orders.len());
+
+        for order in &extended_orders {
+            let cancelled = match self.extended_client.cancel_order_by_external_id(&order.external_id).await {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Failed to cancel Extended order {} on {}: {}", order.external_id, order.market, e);
+                    false
+                }
+            };
+            adopt_in_flight_order(&self.intents, &executions, Venue::Extended, &order.external_id, order.filled_qty_f64(), cancelled);
+        }
+
+        let mut unidentified_symbols: Vec<&str> = Vec::new();
+        for order in &pacifica_orders {
+            let Some(client_id) = order.client_order_id.as_deref() else {
+                if !unidentified_symbols.contains(&order.symbol.as_str()) {
+                    unidentified_symbols.push(&order.symbol);
+                }
+                continue;
+            };
+            let cancelled = match self.pacifica_client.cancel_order(&order.symbol, client_id).await {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Failed to cancel Pacifica order {} on {}: {}", client_id, order.symbol, e);
+                    false
                 }
+            };
+            let filled = order.filled_amount.parse::<f64>().unwrap_or(0.0);
+            adopt_in_flight_order(&self.intents, &executions, Venue::Pacifica, client_id, filled, cancelled);
+        }
+        // The bot always sets a client ID, so these match no intent
+        for symbol in unidentified_symbols {
+            warn!("Cancelling Pacifica {} orders without a client order ID", symbol);
+            // Reduce-only orders are spared: they cannot open exposure, and may be TP/SL
+            if let Err(e) = self.pacifica_client.cancel_all_orders(false, Some(symbol), true).await {
+                error!("Failed to cancel Pacifica {} orders: {}", symbol, e);
             }
         }
+        Ok(())
+    }
+
+    /// Mark interrupted executions as settled, either all of them or those of one symbol
+    fn resolve_interrupted_executions(&mut self, symbol: Option<&str>) {
+        let intents = &self.intents;
+        self.interrupted_executions.retain(|execution| {
+            if symbol.is_some_and(|s| s != execution.symbol) {
+                return true;
+            }
+            intents.resolve(&execution.position_id, &execution.symbol);
+            false
+        });
     }
 
     async fn recover_state_if_untracked(
         &mut self,
-    ) -> Result<RecoveryOutcome, Box<dyn std::error::Error>> {
+    ) -> Result<RecoveryOutcome, BotError> {
         if self.state.current_position.is_some() {
             return Ok(RecoveryOutcome::NoAction);
         }
 
-        let (extended_positions, pacifica_positions) = self.fetch_live_positions_with_backoff().await?;
+        let (extended_positions, pacifica_positions) = fetch_live_positions(&self.extended_client, &self.pacifica_client, &self.config.retry).await?;
 
         if extended_positions.is_empty() && pacifica_positions.is_empty() {
+            // Whatever an interrupted execution sent, nothing is left open
+            self.resolve_interrupted_executions(None);
             return Ok(RecoveryOutcome::NoAction);
         }
 
+        let symbols = &self.config.symbols;
         let mut extended_symbols = HashSet::new();
         for pos in &extended_positions {
-            let sym = pos.market.strip_suffix("-USD").unwrap_or(&pos.market).to_string();
-            extended_symbols.insert(sym);
+            extended_symbols.insert(symbols.symbol_or_market(Venue::Extended, &pos.market));
         }
 
         let mut pacifica_symbols = HashSet::new();
         for pos in &pacifica_positions {
-            pacifica_symbols.insert(pos.symbol.clone());
+            pacifica_symbols.insert(symbols.symbol_or_market(Venue::Pacifica, &pos.symbol));
         }
 
         let mut ext_list: Vec<String> = extended_symbols.iter().cloned().collect();
@@ -289,7 +1106,18 @@ impl FundingBot {
         let mut all_symbols: Vec<String> = extended_symbols.union(&pacifica_symbols).cloned().collect();
         all_symbols.sort();
 
-        let symbol = if overlap.len() == 1 {
+        // A symbol from an interrupted execution is ours, even alongside unrelated positions
+        let interrupted = self
+            .interrupted_executions
+            .iter()
+            .rev()
+            .find(|e| all_symbols.contains(&e.symbol))
+            .cloned();
+
+        let symbol = if let Some(ref execution) = interrupted {
+            info!("Live {} position matches an interrupted execution ({})", execution.symbol, execution.summary());
+            execution.symbol.clone()
+        } else if overlap.len() == 1 {
             overlap[0].clone()
         } else if overlap.is_empty() && all_symbols.len() == 1 {
             all_symbols[0].clone()
@@ -297,12 +1125,14 @@ impl FundingBot {
             return Ok(RecoveryOutcome::Blocked(details));
         };
 
+        let (extended_market, pacifica_market) = (symbols.extended_market(&symbol), symbols.pacifica_market(&symbol));
+        let multipliers = symbols.multipliers(&symbol);
         let extended_position = extended_positions
             .into_iter()
-            .find(|p| p.market.strip_suffix("-USD").unwrap_or(&p.market) == symbol);
+            .find(|p| p.market == extended_market);
         let pacifica_position = pacifica_positions
             .into_iter()
-            .find(|p| p.symbol == symbol);
+            .find(|p| p.symbol == pacifica_market);
 
         let opened_at = pacifica_position
             .as_ref()
@@ -316,31 +1146,58 @@ impl FundingBot {
 
         let mut target_notional_usd: f64 = 0.0;
         if let Some(ref ext_pos) = extended_position {
-            let value = ext_pos.value_f64();
+            let value = decimal_to_f64(ext_pos.value_decimal());
             if value > 0.0 {
                 target_notional_usd = target_notional_usd.max(value);
             }
         }
         if let Some(ref pac_pos) = pacifica_position {
-            let value = pac_pos.size() * pac_pos.entry();
+            let value = decimal_to_f64(pac_pos.size_decimal() * pac_pos.entry_decimal());
             if value > 0.0 {
                 target_notional_usd = target_notional_usd.max(value);
             }
         }
 
-        let position = DeltaNeutralPosition {
+        // Keep the interrupted execution's ID so its client order IDs stay recognizable
+        let position_id = interrupted
+            .map(|e| e.position_id)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let mut position = DeltaNeutralPosition {
+            position_id,
             symbol,
             extended_position,
             pacifica_position,
             opened_at,
             target_notional_usd,
+            realized_funding_extended_usd: 0.0,
+            realized_funding_pacifica_usd: 0.0,
+            entry_net_apr: None,
+            extended_entry_price: None,
+            pacifica_entry_price: None,
+            entry_basis_pct: None,
+            spread_capture: None,
+            captured_basis_usd: None,
+            open_execution: None,
+            extended_vault_id: None,
+            direction: None,
+            reductions: 0,
+            protective_generation: None,
+            hold_restarted_at: None,
+            funding_average: FundingRateAverage::default(),
+            extended_market: Some(extended_market),
+            pacifica_market: Some(pacifica_market),
+            multipliers,
         };
+        position.direction = position.held_direction();
+        position.record_entry_prices();
 
+        let symbol = position.symbol.clone();
         self.state.current_position = Some(position);
         if self.state.last_rotation_time.is_none() {
             self.state.last_rotation_time = Some(opened_at);
         }
-        self.state.save_to_file(&self.state_path)?;
+        self.save_state().await?;
+        self.resolve_interrupted_executions(Some(&symbol));
         info!("Recovered bot state from live positions. {}", details);
 
         Ok(RecoveryOutcome::Recovered)
@@ -353,14 +1210,14 @@ impl FundingBot {
     ///
     /// Returns Ok(()) if reconciliation was successful (or state was empty).
     /// Returns Err if network/API calls failed - in this case state is NOT modified.
-    pub async fn reconcile_state(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn reconcile_state(&mut self) -> Result<(), BotError> {
         let Some(saved_pos) = self.state.current_position.clone() else {
             // Nothing to reconcile
             return Ok(());
         };
 
         let symbol = saved_pos.symbol.clone();
-        let extended_market = format!("{}-USD", symbol);
+        let extended_market = saved_pos.extended_market_name();
 
         // Query live positions on Extended
         // We propagate errors here so we don't accidentally clear state on network failure
@@ -374,7 +1231,7 @@ impl FundingBot {
         let live_ext = live_ext_list.into_iter().find(|p| p.market == extended_market);
 
         // Query live position on Pacifica
-        let live_pac = match self.pacifica_client.get_position(&symbol).await {
+        let live_pac = match self.pacifica_client.get_position(&saved_pos.pacifica_market_name()).await {
             Ok(pos_opt) => pos_opt,
             Err(e) => {
                 warn!("Network error checking Pacifica positions: {}. Keeping existing state.", e);
@@ -389,7 +1246,11 @@ impl FundingBot {
                 symbol
             );
             self.state.current_position = None;
-            self.state.save_to_file(&self.state_path)?;
+            self.save_state().await?;
+            // Whatever closed the legs (e.g. one of their own stops) may have left the rest
+            if self.config.protective_orders.enabled {
+                cancel_protective_orders(&saved_pos, &self.extended_client, &self.pacifica_client).await;
+            }
             return Ok(());
         }
 
@@ -398,108 +1259,475 @@ impl FundingBot {
         updated.extended_position = live_ext;
         updated.pacifica_position = live_pac;
         self.state.current_position = Some(updated);
-        self.state.save_to_file(&self.state_path)?;
+        self.save_state().await?;
         Ok(())
     }
 
-    /// Display current status summary
-    pub async fn display_status(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let mut table = Table::new();
-        table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    /// Refresh realized funding for the current position from both venues' payment history.
+    /// Sums are recomputed from `opened_at` each time, so repeated calls never double count.
+    pub async fn refresh_realized_funding(&mut self) -> Result<(), BotError> {
+        let Some(pos) = self.state.current_position.as_ref() else {
+            return Ok(());
+        };
 
-        // Title Row
-        table.set_titles(Row::new(vec![
-            Cell::new("FUNDING RATE BOT STATUS")
-                .style_spec("cb") // Center, Bold
-                .with_hspan(2)
-        ]));
+        let since_ms = pos.opened_at * 1000;
+        let extended_market = pos.extended_market_name();
+        let pacifica_market = pos.pacifica_market_name();
+
+        let extended_payments = self
+            .extended_client
+            .get_funding_payments(Some(&extended_market), Some(since_ms))
+            .await?;
+        let pacifica_payments = self
+            .pacifica_client
+            .get_funding_payments(Some(&pacifica_market), Some(since_ms))
+            .await?;
+
+        let extended_total = decimal_to_f64(extended_payments.iter().map(|p| p.fee_decimal()).sum());
+        let pacifica_total = decimal_to_f64(pacifica_payments.iter().map(|p| p.payout_decimal()).sum());
+
+        if let Some(pos) = self.state.current_position.as_mut() {
+            pos.realized_funding_extended_usd = extended_total;
+            pos.realized_funding_pacifica_usd = pacifica_total;
+        }
+        self.save_state().await?;
+        Ok(())
+    }
 
-        if let Some(pos) = &self.state.current_position {
-            let hold_time_hours = self.config.trading.hold_time_hours;
-            let hours_remaining = self.state.hours_until_rotation(hold_time_hours).unwrap_or(0.0);
-
-            // Convert opened_at timestamp to datetime
-            let opened_datetime = chrono::DateTime::from_timestamp(pos.opened_at as i64, 0)
-                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
-                .unwrap_or_else(|| "Unknown".to_string());
-
-            // Calculate rotation time
-            let rotation_timestamp = pos.opened_at + (hold_time_hours * 3600);
-            let rotation_datetime = chrono::DateTime::from_timestamp(rotation_timestamp as i64, 0)
-                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
-                .unwrap_or_else(|| "Unknown".to_string());
-
-            let hours_formatted = format!("{:.1} hours", hours_remaining);
-
-            table.add_row(Row::new(vec![Cell::new("Symbol"), Cell::new(&pos.symbol).style_spec("b")]));
-            table.add_row(Row::new(vec![Cell::new("Notional"), Cell::new(&format!("${:.2}", pos.target_notional_usd))]));
-            table.add_row(Row::new(vec![Cell::new("Opened"), Cell::new(&opened_datetime)]));
-            table.add_row(Row::new(vec![Cell::new("Rotation"), Cell::new(&rotation_datetime)]));
-            table.add_row(Row::new(vec![Cell::new("Time Remaining"), Cell::new(&hours_formatted).style_spec(if hours_remaining < 1.0 { "Fr" } else { "Fg" })])); // Red if < 1h, else Green
-
-            // Extended Position Status
-            let ext_status = if pos.extended_position.is_some() {
-                "ACTIVE".green().bold()
-            } else {
-                "NONE".red().bold()
-            };
-            table.add_row(Row::new(vec![Cell::new("Extended Position"), Cell::new(&ext_status.to_string())]));
+    /// Add the current funding rates of both legs to the held position's time-weighted average
+    pub async fn sample_funding_rates(&mut self) -> Result<(), BotError> {
+        let Some(symbol) = self.state.current_position.as_ref().map(|p| p.symbol.clone()) else {
+            return Ok(());
+        };
+        // A missing rate was already logged; the previous sample keeps counting until the next one
+        let (Some(extended), Some(pacifica)) = self.funding_forecasts(&symbol).await else {
+            return Ok(());
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        if let Some(pos) = self.state.current_position.as_mut() {
+            pos.funding_average.record(now, extended.apr_pct(), pacifica.apr_pct());
+        }
+        self.save_state().await?;
+        Ok(())
+    }
 
-            // Pacifica Position Status
-            let pac_status = if pos.pacifica_position.is_some() {
-                "ACTIVE".green().bold()
-            } else {
-                "NONE".red().bold()
-            };
-            table.add_row(Row::new(vec![Cell::new("Pacifica Position"), Cell::new(&pac_status.to_string())]));
+    /// Machine-readable status: saved state plus live PnL of the held legs.
+    /// Live lookups that fail are left as `None` rather than failing the snapshot.
+    pub async fn status_snapshot(&self) -> Result<StatusSnapshot, BotError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let hold_time_hours = self.config.trading.hold_time_hours;
+
+        let position = match &self.state.current_position {
+            Some(pos) => {
+                let mut status = PositionStatus {
+                    symbol: pos.symbol.clone(),
+                    position_id: pos.position_id.clone(),
+                    opened_at: pos.opened_at,
+                    rotation_at: pos.hold_started_at() + hold_time_hours * 3600,
+                    hours_until_rotation: self.state.hours_until_rotation(hold_time_hours).unwrap_or(0.0),
+                    target_notional_usd: pos.target_notional_usd,
+                    entry_net_apr: pos.entry_net_apr,
+                    average_net_apr: pos.average_net_apr(),
+                    realized_funding_usd: pos.realized_funding_usd(),
+                    realized_funding_extended_usd: pos.realized_funding_extended_usd,
+                    realized_funding_pacifica_usd: pos.realized_funding_pacifica_usd,
+                    realized_funding_apr: pos.realized_funding_apr(now),
+                    extended_leg_active: pos.extended_position.is_some(),
+                    pacifica_leg_active: pos.pacifica_position.is_some(),
+                    extended_unrealized_pnl_usd: None,
+                    extended_entry_price: None,
+                    pacifica_entry_price: None,
+                    pacifica_size: None,
+                    entry_basis_pct: None,
+                    current_basis_pct: None,
+                    basis_pnl_usd: None,
+                    captured_basis_usd: pos.captured_basis_usd,
+                    projected_funding_extended_usd: None,
+                    projected_funding_pacifica_usd: None,
+                    projected_funding_usd: None,
+                };
+
+                // Positions saved before entry prices were recorded fall back to the legs' entries
+                let mut priced = pos.clone();
+                if priced.extended_entry_price.is_none() || priced.pacifica_entry_price.is_none() {
+                    priced.record_entry_prices();
+                }
+                status.extended_entry_price = priced.extended_entry_price;
+                status.pacifica_entry_price = priced.pacifica_entry_price;
+                status.entry_basis_pct = priced.entry_basis_pct;
+
+                let (extended_market, pacifica_market) = (pos.extended_market_name(), pos.pacifica_market_name());
+                if let Ok((ext_quote, pac_quote)) = self.live_quotes(&pos.symbol, &extended_market, &pacifica_market).await {
+                    if let (Some((ext_mid, _)), Some((pac_mid, _))) =
+                        (mid_and_spread_pct(&ext_quote), mid_and_spread_pct(&pac_quote))
+                    {
+                        status.current_basis_pct = Some(priced.basis_pct(ext_mid, pac_mid));
+                        status.basis_pnl_usd = priced.basis_pnl_usd(ext_mid, pac_mid);
+                    }
+                }
 
-            // Fetch current positions for PnL display
-            if let Ok(extended_positions) = self.extended_client.get_positions(None).await {
-                if let Some(ext_pos) = extended_positions.iter().find(|p| p.market.starts_with(&pos.symbol)) {
-                    let pnl = ext_pos.unrealized_pnl.as_ref()
-                        .and_then(|s| s.parse::<f64>().ok())
-                        .unwrap_or(0.0);
+                // Current rates carried over the rest of the hold window
+                if let Some(direction) = pos.held_direction() {
+                    let long_on_extended = direction == Direction::LongExtendedShortPacifica;
+                    let hours = status.hours_until_rotation;
+                    let (extended, pacifica) = self.funding_forecasts(&pos.symbol).await;
+                    status.projected_funding_extended_usd =
+                        extended.map(|f| f.leg_funding_usd(long_on_extended, pos.target_notional_usd, hours));
+                    status.projected_funding_pacifica_usd =
+                        pacifica.map(|f| f.leg_funding_usd(!long_on_extended, pos.target_notional_usd, hours));
+                    status.projected_funding_usd = status
+                        .projected_funding_extended_usd
+                        .zip(status.projected_funding_pacifica_usd)
+                        .map(|(ext, pac)| ext + pac);
+                }
 
-                    let pnl_formatted = format!("${:.2}", pnl);
-                    let style = if pnl >= 0.0 { "Fg" } else { "Fr" };
-                    table.add_row(Row::new(vec![Cell::new("Extended PnL"), Cell::new(&pnl_formatted).style_spec(style)]));
+                if let Ok(extended_positions) = self.extended_client.get_positions(None).await {
+                    if let Some(ext_pos) = extended_positions.iter().find(|p| p.market == extended_market) {
+                        status.extended_unrealized_pnl_usd = Some(
+                            ext_pos.unrealized_pnl.as_ref()
+                                .and_then(|s| s.parse::<f64>().ok())
+                                .unwrap_or(0.0),
+                        );
+                    }
+                }
+                if let Ok(pacifica_positions) = self.pacifica_client.get_positions().await {
+                    if let Some(pac_pos) = pacifica_positions.iter().find(|p| p.symbol == pacifica_market) {
+                        status.pacifica_entry_price.get_or_insert(pac_pos.entry());
+                        status.pacifica_size = Some(pac_pos.size());
+                    }
                 }
+                Some(status)
             }
+            None => None,
+        };
 
-            if let Ok(pacifica_positions) = self.pacifica_client.get_positions().await {
-                if let Some(pac_pos) = pacifica_positions.iter().find(|p| p.symbol == pos.symbol) {
-                    let entry = pac_pos.entry();
-                    let size = pac_pos.size();
-                    table.add_row(Row::new(vec![Cell::new("Pacifica Entry"), Cell::new(&format!("${:.2}", entry))]));
-                    table.add_row(Row::new(vec![Cell::new("Pacifica Size"), Cell::new(&format!("{:.6}", size))]));
-                }
+        // Only the process holding the state lock runs the bot and counts its API calls
+        let runtime = self.state_lock.is_some().then(|| {
+            let (api_degradation, api_health) = metrics::global().degradation(&self.config.error_budget);
+            RuntimeStatus {
+                consecutive_failed_opens: self.risk.consecutive_failed_opens(),
+                api_degradation,
+                api_health,
+                api_endpoints: metrics::global().summaries(),
             }
-        } else {
-            table.add_row(Row::new(vec![
-                Cell::new("Status"),
-                Cell::new("NO ACTIVE POSITION").style_spec("Fy") // Yellow
-            ]));
-        }
+        });
+        Ok(StatusSnapshot {
+            generated_at: now,
+            environment: self.config.environment,
+            strategy: self.strategy.name().to_string(),
+            hold_time_hours,
+            position,
+            last_rotation_time: self.state.last_rotation_time,
+            total_rotations: self.state.total_rotations,
+            total_realized_funding_usd: self.state.total_realized_funding_usd,
+            last_closed_net_apr: self.state.last_closed_net_apr,
+            last_closed_average_net_apr: self.state.last_closed_average_net_apr,
+            total_captured_basis_usd: self.state.total_captured_basis_usd,
+            total_fees_usd: self.state.total_fees_usd,
+            total_slippage_usd: self.state.total_slippage_usd,
+            last_execution: self.state.last_execution.clone(),
+            legging: self.state.legging.clone(),
+            runtime,
+            paused: self.state.paused,
+            auth_failure: self.auth_failure.clone(),
+            risk_report: self.risk_report().await.unwrap_or_else(|e| {
+                warn!("Failed to stress test the held position: {}", e);
+                None
+            }),
+        })
+    }
 
-        table.add_row(Row::new(vec![Cell::new("Total Rotations"), Cell::new(&self.state.total_rotations.to_string())]));
+    /// Stress test of the held position at current mids and account equity, see
+    /// `stress`; `None` without a position. A leg whose venue equity cannot be fetched
+    /// is left out.
+    pub async fn risk_report(&self) -> Result<Option<RiskReport>, BotError> {
+        let Some(pos) = &self.state.current_position else {
+            return Ok(None);
+        };
+        let (extended_market, pacifica_market) = (pos.extended_market_name(), pos.pacifica_market_name());
+        let (ext_quote, pac_quote) = self.live_quotes(&pos.symbol, &extended_market, &pacifica_market).await?;
+        let (ext_mid, pac_mid) = (mid_and_spread_pct(&ext_quote).map(|(mid, _)| mid), mid_and_spread_pct(&pac_quote).map(|(mid, _)| mid));
+        let (extended_equity, pacifica_equity) = self.fetch_equities().await;
+        let leverage = self.config.trading.leverage;
+
+        let extended = pos.extended_position.as_ref().map(|p| (Venue::Extended, p.is_long(), p.size_f64(), ext_mid, extended_equity));
+        let pacifica = pos.pacifica_position.as_ref().map(|p| (Venue::Pacifica, p.is_long(), p.size(), pac_mid, pacifica_equity));
+        let mut legs = Vec::new();
+        for (venue, is_long, size, price, equity_usd) in extended.into_iter().chain(pacifica) {
+            match (price, equity_usd) {
+                (Some(price), Some(equity_usd)) => legs.push(StressLeg { venue, is_long, size, price, equity_usd, leverage }),
+                _ => warn!("No {} {} for the stress test; leaving that leg out", venue, if price.is_none() { "price" } else { "equity" }),
+            }
+        }
+        let basis_pct = ext_mid.zip(pac_mid).map(|(ext, pac)| pos.basis_pct(ext, pac));
+        Ok(Some(risk_report(&pos.symbol, basis_pct, &legs, &self.config.stress)))
+    }
 
-        table.printstd();
+    /// Current funding forecasts for `symbol` on Extended and Pacifica, with the configured
+    /// intervals and caps applied as in scans; `None` where the rate could not be fetched
+    async fn funding_forecasts(&self, symbol: &str) -> (Option<FundingForecast>, Option<FundingForecast>) {
+        let intervals = &self.config.funding_intervals;
+        let caps = &self.config.funding_caps;
+        let streamed = |venue| {
+            let cache = self.funding_cache.as_ref()?;
+            cache.forecast(venue, symbol, self.config.funding_stream.max_age(), intervals, caps)
+        };
+        if let (Some(extended), Some(pacifica)) = (streamed(Venue::Extended), streamed(Venue::Pacifica)) {
+            return (Some(extended), Some(pacifica));
+        }
 
-        Ok(())
+        let extended = match self.extended_client.get_funding_rate(&self.config.symbols.extended_market(symbol)).await {
+            Ok(Some(rate)) => Some(
+                FundingForecast::from_extended(&rate)
+                    .with_interval_hours(intervals.hours(Venue::Extended, symbol))
+                    .capped(caps.max_rate(Venue::Extended, symbol)),
+            ),
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Failed to fetch Extended funding rate for {}: {}", symbol, e);
+                None
+            }
+        };
+        let pacifica = match self.pacifica_client.get_current_funding_rate(&self.config.symbols.pacifica_market(symbol)).await {
+            Ok(rate) => Some(
+                FundingForecast::from_pacifica(&rate)
+                    .with_interval_hours(intervals.hours(Venue::Pacifica, symbol))
+                    .capped(caps.max_rate(Venue::Pacifica, symbol)),
+            ),
+            Err(e) => {
+                warn!("Failed to fetch Pacifica funding rate for {}: {}", symbol, e);
+                None
+            }
+        };
+        (extended, pacifica)
     }
 
-    /// Find and open the best opportunity
-    pub async fn open_best_opportunity(
-        &mut self,
-        extended_api_key: Option<String>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        info!("{}", "🔍 Scanning for best opportunity...");
+    /// Whether the held position's streamed net APR just dropped below
+    /// `funding_stream.flip_threshold_apr_pct`, so the next cycle should run now
+    fn check_funding_flip(&mut self) -> bool {
+        let net_apr = self.funding_cache.as_ref().zip(self.state.current_position.as_ref()).and_then(|(cache, pos)| {
+            cache.net_apr(
+                &pos.symbol,
+                pos.held_direction()?,
+                self.config.funding_stream.max_age(),
+                &self.config.funding_intervals,
+                &self.config.funding_caps,
+            )
+        });
+        let threshold = self.config.funding_stream.flip_threshold_apr_pct;
+        let flipped = net_apr.is_some_and(|apr| apr < threshold);
+        let newly_flipped = flipped && !self.funding_flipped;
+        self.funding_flipped = flipped;
+        if newly_flipped {
+            warn!("⚡ Streamed funding on {} flipped: held net APR {:.2}% is below {:.2}%, running a cycle now",
+                self.state.current_position.as_ref().map_or("", |p| p.symbol.as_str()),
+                net_apr.unwrap_or_default(), threshold);
+        }
+        newly_flipped
+    }
 
-        // Safety net: if state is empty but exchanges report open positions, abort opening
-        match self.recover_state_if_untracked().await? {
-            RecoveryOutcome::Recovered => {
-                warn!("Recovered bot state from live positions; skipping new open.");
+    /// Poll the Extended balance of the held position and act on the risk manager's
+    /// verdict: warn, reduce both legs, or close and pause (see `margin_watch`)
+    async fn check_margin(&mut self) {
+        let Some(symbol) = self.state.current_position.as_ref().map(|p| p.symbol.clone()) else {
+            self.risk.reset_margin_watch();
+            return;
+        };
+        let balance = match self.extended_client.get_balance().await {
+            Ok(balance) => balance,
+            Err(e) => {
+                warn!("Margin watch: failed to fetch Extended balance: {}", e);
+                return;
+            }
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let assessment = self.risk.observe_margin(BalanceSample::from_balance(&balance, now));
+        let action = match assessment.level {
+            MarginLevel::Normal => return,
+            MarginLevel::Warn => {
+                warn!("⚠️  Extended margin on {}: {}", symbol, assessment);
+                return;
+            }
+            MarginLevel::Reduce => {
+                let fraction = self.config.risk.margin_call.reduce_fraction;
+                warn!("🩸 Extended margin on {}: {}; reducing both legs by {:.0}%", symbol, assessment, fraction * 100.0);
+                match self.reduce_current_position(fraction).await {
+                    Ok(()) => format!("reduced {} by {:.0}%", symbol, fraction * 100.0),
+                    Err(e) => format!("reducing {} failed: {}", symbol, e),
+                }
+            }
+            MarginLevel::Close => {
+                error!("🩸 Extended margin on {}: {}; closing the position and pausing", symbol, assessment);
+                // Otherwise the next cycle would open a new position right away
+                self.set_paused(true).await;
+                match self.close_current_position().await {
+                    Ok(()) => format!("closed {}, opens paused until resume", symbol),
+                    Err(e) => format!("closing {} failed: {}", symbol, e),
+                }
+            }
+        };
+        let failure = CycleFailure {
+            kind: IncidentKind::MarginCall,
+            message: format!("{}: {}", assessment, action),
+            elapsed: Duration::ZERO,
+        };
+        let incident = Incident::new(failure, now, Some(symbol), 1, false);
+        self.report_incident(&incident).await;
+    }
+
+    /// Display current status summary
+    pub async fn display_status(&self) -> Result<(), BotError> {
+        report::print_pretty(&self.status_snapshot().await?);
+        Ok(())
+    }
+
+    /// Find and open the best opportunity
+    pub async fn open_best_opportunity(
+        &mut self,
+        extended_api_key: Option<String>,
+    ) -> Result<(), BotError> {
+        info!("{}", "🔍 Scanning for best opportunity...");
+        // A delay only carries over while the same symbol is selected again
+        let pending_delay = self.entry_delay.take();
+
+        if let Some(reason) = self.open_blocker().await? {
+            warn!("⏸️  Skipping new open: {}", reason);
+            self.decision.skip(reason);
+            return Ok(());
+        }
+
+        // Churn protection: don't reopen right after a close
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let cooldown_secs = self.state.rotation_cooldown_remaining_secs(
+            self.config.trading.min_minutes_between_rotations,
+            now,
+        );
+        if cooldown_secs > 0 {
+            info!("⏸️  Rotation cooldown: {:.1} minutes left before opening a new position",
+                cooldown_secs as f64 / 60.0);
+            self.decision.skip(format!("rotation cooldown: {:.1} minutes left", cooldown_secs as f64 / 60.0));
+            return Ok(());
+        }
+
+        let scan_result = self.opportunity_finder.scan(extended_api_key.clone()).await?;
+        self.decision.record_scan(&scan_result);
+
+        // Display comprehensive scan summary
+        if self.cycle_timer.allows("scan table") {
+            scan_result.display_summary(&self.config);
+        }
+
+        if scan_result.opportunities.is_empty() {
+            warn!("{}", "No opportunities found matching criteria");
+            self.decision.skip("no opportunity passed the filters");
+            return Ok(());
+        }
+
+        // Symbols whose minimum order is out of reach are skipped for the next best one
+        let mut scan_result = scan_result;
+        loop {
+            let Some(target) = self.strategy.select(&scan_result, &self.state, &self.config) else {
+                warn!("No opportunity selected by strategy {}", self.strategy.name());
+                self.decision.skip(format!("strategy {} selected no opportunity", self.strategy.name()));
                 return Ok(());
+            };
+            self.state.last_selected_symbol = Some(target.symbol().to_string());
+            let best = &target.opportunity;
+            info!("{} {} {}",
+                "✅ Selected best opportunity:",
+                best.symbol,
+                format!("(Net APR: {:.2}%)", best.best_net_apr));
+            info!("   {}: {}", "Strategy", best.best_direction);
+            info!("   {}: ${:.0}", "Volume", best.total_volume_24h);
+            info!("   {}: Ext {:.3}%, Pac {:.3}%, Cross {:.3}%",
+                "Spreads",
+                best.extended_spread_pct, best.pacifica_spread_pct, best.cross_spread_pct);
+
+            if let Some(reason) = self.entry_timing_delay(target.symbol(), pending_delay.as_ref()).await? {
+                info!("⏳ Delaying the open of {}: {}", target.symbol(), reason);
+                self.decision.skip(format!("entry timing: {}", reason));
+                return Ok(());
+            }
+
+            match self.open_target(&target, None).await {
+                Err(e @ BotError::InsufficientCapital { .. }) => {
+                    warn!("💸 {}; skipping {}", e, target.opportunity.symbol);
+                    self.decision.skip(format!("{}: {}", target.opportunity.symbol, e));
+                    let remaining = scan_result.opportunities.len();
+                    scan_result.opportunities.retain(|o| o.symbol != target.opportunity.symbol);
+                    // A strategy picking outside the scan's opportunities would select it again
+                    if scan_result.opportunities.len() == remaining {
+                        return Err(e);
+                    }
+                }
+                Ok(()) => {
+                    self.decision.opened(&target.opportunity.symbol);
+                    return Ok(());
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Why opening `symbol` should wait for calmer candles (`entry_timing`), if it should.
+    /// `pending` is the delay of the previous attempt; the wait ends `max_wait_minutes`
+    /// after it started on the same symbol.
+    async fn entry_timing_delay(&mut self, symbol: &str, pending: Option<&EntryDelay>) -> Result<Option<String>, BotError> {
+        let timing = &self.config.entry_timing;
+        if !timing.enabled {
+            return Ok(None);
+        }
+        let extended_market = self.config.symbols.extended_market(symbol);
+        let pacifica_market = self.config.symbols.pacifica_market(symbol);
+        let (extended, pacifica) = tokio::join!(
+            extended_conditions(&self.extended_client, &extended_market, timing),
+            pacifica_conditions(&self.pacifica_client, &pacifica_market, timing),
+        );
+        let mut readings = Vec::new();
+        for (venue, conditions) in [(Venue::Extended, extended), (Venue::Pacifica, pacifica)] {
+            match conditions {
+                Ok(Some(conditions)) => readings.push((venue, conditions)),
+                Ok(None) => debug!("Not enough {} candles for {} to time the entry", venue, symbol),
+                Err(e) => warn!("Failed to fetch {} candles for {} (timing the entry without them): {}", venue, symbol, e),
+            }
+        }
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let waited_secs = pending
+            .filter(|delay| delay.symbol == symbol)
+            .map(|delay| now.saturating_sub(delay.since));
+        match timing.decide(&readings, waited_secs) {
+            EntryTiming::Enter => Ok(None),
+            EntryTiming::GiveUp(reason) => {
+                warn!("⏳ {} still extreme after {} minutes ({}): opening anyway", symbol, timing.max_wait_minutes, reason);
+                Ok(None)
+            }
+            EntryTiming::Wait(reason) => {
+                let since = waited_secs.map_or(now, |waited| now - waited);
+                self.entry_delay = Some(EntryDelay { symbol: symbol.to_string(), since });
+                Ok(Some(format!("{} (waited {} of {} minutes)", reason, (now - since) / 60, timing.max_wait_minutes)))
+            }
+        }
+    }
+
+    /// Why a new position must not be opened right now, if anything. Live positions
+    /// missing from state are adopted first; positions that cannot be adopted are an error.
+    pub(crate) async fn open_blocker(&mut self) -> Result<Option<String>, BotError> {
+        // Safety net: if state is empty but exchanges report open positions, abort opening
+        match self.recover_state_if_untracked().await? {
+            RecoveryOutcome::Recovered => {
+                return Ok(Some("recovered bot state from live positions".to_string()));
             }
             RecoveryOutcome::Blocked(details) => {
                 return Err(format!(
@@ -510,78 +1738,221 @@ impl FundingBot {
             RecoveryOutcome::NoAction => {}
         }
 
-        let scan_result = self.opportunity_finder.scan(extended_api_key.clone()).await?;
+        self.check_auth_failures().await;
+        if let Some(failure) = &self.auth_failure {
+            return Ok(Some(format!("authentication failed ({}); replace the credentials and resume", failure)));
+        }
+        if self.state.paused {
+            return Ok(Some("paused by control command (resume to allow opens)".to_string()));
+        }
 
-        // Display comprehensive scan summary
-        scan_result.display_summary(&self.config.filters);
+        // Back off while a venue's API is failing too often
+        if self.api_degradation() >= Degradation::DelayOpens {
+            return Ok(Some("API error budget exceeded, delaying new opens until error rates recover".to_string()));
+        }
 
-        if scan_result.opportunities.is_empty() {
-            warn!("{}", "No opportunities found matching criteria");
-            return Ok(());
+        // A silently dead stream must not feed stale prices into execution
+        if let Some(stale) = self.stale_streams() {
+            return Ok(Some(format!("{}, delaying new opens until it recovers", stale)));
         }
 
-        let best = &scan_result.opportunities[0];
-        info!("{} {} {}",
-            "✅ Selected best opportunity:",
-            best.symbol,
-            format!("(Net APR: {:.2}%)", best.best_net_apr));
-        info!("   {}: {}", "Strategy", best.best_direction);
-        info!("   {}: ${:.0}", "Volume", best.total_volume_24h);
-        info!("   {}: Ext {:.3}%, Pac {:.3}%, Cross {:.3}%",
-            "Spreads",
-            best.extended_spread_pct, best.pacifica_spread_pct, best.cross_spread_pct);
+        // Halted after repeated failed opens
+        self.risk.check_can_open()?;
+        Ok(None)
+    }
 
-        // Determine position direction
-        let long_on_extended = best.best_direction.contains("Long Extended");
+    /// Size `target` and open it with the two-leg execution. `notional_usd` replaces the
+    /// configured size cap (compounding and dynamic sizing included) when given; risk
+    /// limits and free collateral still apply.
+    pub(crate) async fn open_target(
+        &mut self,
+        target: &PositionTarget,
+        notional_usd: Option<f64>,
+    ) -> Result<(), BotError> {
+        let best = &target.opportunity;
+        let _in_flight = self.begin_operation(format!("open {}", best.symbol))?;
+        let net_apr = best.net_apr_for(target.direction);
+        let long_on_extended = target.direction.long_on_extended();
 
         // Get market symbols
-        let extended_market = format!("{}-USD", best.symbol);
-        let pacifica_market = best.symbol.clone();
+        let extended_market = self.config.symbols.extended_market(&best.symbol);
+        let pacifica_market = self.config.symbols.pacifica_market(&best.symbol);
 
         // Fetch current prices and account info
         let extended_balance = self.extended_client.get_balance().await?;
         let extended_free = extended_balance.available_for_trade.parse::<f64>()?;
 
-        // Fetch Pacifica account balance via WebSocket
-        let pacifica_ws = PacificaWsTrading::new(self.pacifica_creds.clone(), false); // false = mainnet
-        let pacifica_account_info = pacifica_ws.get_account_info().await?;
+        let pacifica_account_info = self.pacifica_account_info().await?;
         let pacifica_free = pacifica_account_info.available_to_spend_f64();
 
         info!("{} {}", "💰 Extended free collateral:", format!("${:.2}", extended_free));
         info!("{} {}", "💰 Pacifica free collateral:", format!("${:.2}", pacifica_free));
 
+        self.advise_rebalance(CapitalSnapshot {
+            extended_free_usd: extended_free,
+            pacifica_free_usd: pacifica_free,
+        }).await;
+
+        let equity = decimal_to_f64(extended_balance.equity_decimal() + pacifica_account_info.account_equity_decimal());
+
+        // Compounding: scale the size cap with equity growth since compounding started
+        let max_position_size_usd = if let Some(notional_usd) = notional_usd {
+            notional_usd
+        } else if self.config.sizing.compound {
+            let base_equity = *self.state.compounding_base_equity_usd.get_or_insert(equity);
+            let cap = compounded_max_position_size_usd(
+                self.config.trading.max_position_size_usd,
+                base_equity,
+                equity,
+                self.config.sizing.profit_skim_pct,
+            );
+            info!("📈 Compounding: equity ${:.2} (base ${:.2}, skim {:.0}%), max position ${:.2}",
+                equity, base_equity, self.config.sizing.profit_skim_pct, cap);
+            cap
+        } else {
+            self.config.trading.max_position_size_usd
+        };
+        let risk_cap = self.risk.max_notional_usd(&best.symbol, equity);
+        if risk_cap < max_position_size_usd {
+            info!("🛡️  Risk limit caps {} notional at ${:.2}", best.symbol, risk_cap);
+        }
+        let max_position_size_usd = max_position_size_usd.min(risk_cap);
+
         // Get lot sizes
         let extended_market_config = self.extended_client.get_market_config(&extended_market).await?;
-        let extended_lot_size = extended_market_config.trading_config.min_order_size_change.parse::<f64>()?;
 
         let pacifica_markets = self.pacifica_client.get_market_info().await?;
         let pacifica_market_info = pacifica_markets.get(&pacifica_market)
             .ok_or_else(|| format!("Pacifica market {} not found", pacifica_market))?;
-        let pacifica_lot_size = pacifica_market_info.lot_size.parse::<f64>()?;
-
-        // Get current price
-        let orderbook = self.extended_client.get_orderbook(&extended_market).await?;
-        let current_price = if let (Some(bid), Some(ask)) = (orderbook.bid.first(), orderbook.ask.first()) {
-            let bid_price = bid.price.parse::<f64>()?;
-            let ask_price = ask.price.parse::<f64>()?;
-            (bid_price + ask_price) / 2.0
+        let pacifica_max_leverage = pacifica_market_info.max_leverage;
+        let pacifica_isolated_only = pacifica_market_info.isolated_only.unwrap_or(false);
+        // Sized in canonical base units, which each venue's contracts are converted to
+        let multipliers = self.config.symbols.multipliers(&best.symbol);
+        let size_limits = [
+            OrderSizeLimits::extended(&extended_market_config.trading_config)?.with_multiplier(multipliers.extended),
+            OrderSizeLimits::pacifica(pacifica_market_info)?.with_multiplier(multipliers.pacifica),
+        ];
+
+        // Validate configured leverage against both venues' limits
+        let leverage = validate_leverage(
+            self.config.trading.leverage,
+            extended_market_config.trading_config.max_leverage_f64(),
+            pacifica_max_leverage,
+        )?;
+        let margin_mode = if pacifica_isolated_only {
+            MarginMode::Isolated
+        } else {
+            self.config.trading.pacifica_margin_mode
+        };
+
+        // Margin already used on each account, across everything it holds, counts against
+        // the utilization cap before the new pair is added
+        let max_utilization_pct = self.config.capital.max_margin_utilization_pct;
+        let margins = [
+            VenueMargin {
+                venue: Venue::Extended,
+                equity_usd: extended_balance.equity_f64(),
+                margin_used_usd: extended_balance.initial_margin_f64(),
+            },
+            VenueMargin {
+                venue: Venue::Pacifica,
+                equity_usd: pacifica_account_info.account_equity_f64(),
+                margin_used_usd: pacifica_account_info.margin_used_f64(),
+            },
+        ];
+        let margin_cap = max_pair_notional_usd(&margins, leverage, max_utilization_pct);
+        if margin_cap < max_position_size_usd {
+            let usage = margins
+                .iter()
+                .map(|m| format!("{} {:.1}%", m.venue, m.utilization_pct()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            info!("🛡️  Margin utilization cap of {:.0}% ({}) limits {} notional to ${:.2}",
+                max_utilization_pct, usage, best.symbol, margin_cap);
+        }
+        let max_position_size_usd = max_position_size_usd.min(margin_cap);
+
+        // Get current price, from the streamed book when fresh
+        let top_of_book = match self.opportunity_finder.extended_quote(&best.symbol) {
+            Some(quote) => quote,
+            None => BidAsk::from(&self.extended_client.get_orderbook(&extended_market).await?),
+        };
+        let current_price = if let (Some(bid), Some(ask)) = (&top_of_book.best_bid, &top_of_book.best_ask) {
+            let bid_price = bid.parse::<f64>()?;
+            let ask_price = ask.parse::<f64>()?;
+            multipliers.base_price(Venue::Extended, (bid_price + ask_price) / 2.0)
         } else {
             return Err("No orderbook data available".into());
         };
 
-        // Calculate position size
-        let position_size = calculate_position_size(
+        // Notional each venue's free collateral can margin at this leverage; Extended's
+        // risk factor raises the initial margin of large positions
+        let extended_capacity = margin_capacity_usd(
             extended_free,
-            pacifica_free,
-            extended_lot_size,
-            pacifica_lot_size,
+            leverage,
+            &extended_market_config.trading_config.margin_tiers(),
+        );
+        let pacifica_capacity = margin_capacity_usd(pacifica_free, leverage, &[]);
+
+        // Skip before touching leverage when even the venues' minimum order is out of reach
+        let minimum_usd = minimum_order_notional_usd(current_price, &size_limits);
+        let affordable_usd = (extended_capacity.min(pacifica_capacity) * 0.95).min(max_position_size_usd);
+        if affordable_usd < minimum_usd {
+            return Err(BotError::InsufficientCapital {
+                symbol: best.symbol.clone(),
+                minimum_usd,
+                available_usd: affordable_usd.max(0.0),
+            });
+        }
+
+        // An explicit notional is not scaled by opportunity quality or volatility
+        let sizing = SizingConfig {
+            dynamic: self.config.sizing.dynamic && notional_usd.is_none(),
+            ..self.config.sizing.clone()
+        };
+
+        // Recent volatility for dynamic sizing (falls back to no volatility scaling)
+        let atr_pct = if sizing.dynamic {
+            match fetch_atr_pct(&self.pacifica_client, &pacifica_market, self.config.sizing.atr_periods).await {
+                Ok(atr) => atr,
+                Err(e) => {
+                    warn!("Failed to fetch volatility for {} (sizing without it): {}", pacifica_market, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Calculate position size within the notional each leg can margin
+        let position_size = calculate_dynamic_position_size(
+            extended_capacity,
+            pacifica_capacity,
+            size_limits[0].lot_size,
+            size_limits[1].lot_size,
             current_price,
-            self.config.trading.max_position_size_usd,
+            max_position_size_usd,
+            net_apr,
+            atr_pct,
+            &sizing,
         );
+        if sizing.dynamic {
+            info!("📐 Dynamic sizing: quality x{:.2}, volatility x{:.2} (ATR {})",
+                quality_scale(net_apr, &sizing),
+                volatility_scale(atr_pct, &sizing),
+                atr_pct.map(|a| format!("{:.2}%", a)).unwrap_or_else(|| "N/A".to_string()));
+        }
 
-        if position_size <= 0.0 {
-            return Err("Insufficient capital to open position".into());
+        // Dynamic sizing can scale an affordable order back below the minimum
+        if position_size * current_price + 1e-9 < minimum_usd {
+            return Err(BotError::InsufficientCapital {
+                symbol: best.symbol.clone(),
+                minimum_usd,
+                available_usd: position_size * current_price,
+            });
         }
+        let position_size = validate_order_size(position_size, current_price, &size_limits)?;
+        self.risk.check_open(&best.symbol, position_size * current_price, equity)?;
 
         info!("{} {:.6} {} ({})",
             "📊 Calculated position size:",
@@ -589,25 +1960,122 @@ impl FundingBot {
             best.symbol,
             format!("${:.2}", position_size * current_price));
 
-        // Set leverage to 1x on both exchanges before opening position
-        info!("{}", "⚙️  Setting leverage to 1x on both exchanges...");
+        let simulation = best.simulate(position_size * current_price, &self.config);
+        match simulation.break_even_hours {
+            Some(hours) if simulation.breaks_even() => info!(
+                "🧮 Projected over {:.0}h: funding ${:.2}, fees ${:.2}, slippage ${:.2}; breaks even after {:.1}h",
+                simulation.hold_hours, simulation.funding_usd, simulation.fees_usd, simulation.slippage_usd, hours
+            ),
+            Some(hours) => warn!(
+                "⚠️  {} breaks even after {:.1}h, beyond the {:.0}h hold: costs ${:.2} vs funding ${:.2}",
+                best.symbol, hours, simulation.hold_hours, simulation.fees_usd + simulation.slippage_usd, simulation.funding_usd
+            ),
+            None => warn!("⚠️  {} funding does not pay back its ${:.2} round-trip costs", best.symbol,
+                simulation.fees_usd + simulation.slippage_usd),
+        }
 
-        // Set Extended leverage to 1x
-        match self.extended_client.update_leverage(&extended_market, "1").await {
-            Ok(_) => info!("   ✅ Extended leverage set to 1x for {}", extended_market),
-            Err(e) => {
-                warn!("   ⚠️  Failed to set Extended leverage (continuing anyway): {}", e);
-            }
+        // Apply leverage and margin mode on both exchanges before opening position
+        info!("⚙️  Setting leverage to {}x on both exchanges (~{:.1}% liquidation distance)...",
+            leverage, estimated_liquidation_distance_pct(leverage));
+
+        // Sizing and the liquidation distance assume this leverage: do not open on another one
+        if let Err(e) = self.extended_client.update_leverage(&extended_market, &leverage.to_string()).await {
+            return Err(BotError::execution(
+                format!("Aborted opening {} before sending orders: failed to set Extended leverage to {}x: {}",
+                    best.symbol, leverage, e),
+                true,
+            ));
+        }
+        info!("   ✅ Extended leverage set to {}x for {}", leverage, extended_market);
+
+        if let Err(e) = self.pacifica_client.update_margin_mode(&pacifica_market, margin_mode == MarginMode::Isolated).await {
+            return Err(BotError::execution(
+                format!("Aborted opening {} before sending orders: failed to set Pacifica margin mode to {:?}: {}",
+                    best.symbol, margin_mode, e),
+                true,
+            ));
+        }
+        info!("   ✅ Pacifica margin mode set to {:?} for {}", margin_mode, pacifica_market);
+
+        if let Err(e) = self.pacifica_client.update_leverage(&pacifica_market, leverage as i32).await {
+            return Err(BotError::execution(
+                format!("Aborted opening {} before sending orders: failed to set Pacifica leverage to {}x: {}",
+                    best.symbol, leverage, e),
+                true,
+            ));
+        }
+        info!("   ✅ Pacifica leverage set to {}x for {}", leverage, pacifica_market);
+
+        // Spreads may have widened since the scan: re-check both books right before sending
+        let (extended_quote, pacifica_quote) = self.live_quotes(&best.symbol, &extended_market, &pacifica_market).await?;
+        let live = best.with_live_spreads(&extended_quote, &pacifica_quote, multipliers);
+        let filters = &self.config.filters;
+        let rejection = match &live {
+            None => Some("an orderbook side is empty".to_string()),
+            Some(live) if live.extended_spread_pct > filters.max_intra_exchange_spread_pct
+                || live.pacifica_spread_pct > filters.max_intra_exchange_spread_pct
+                || live.cross_spread_pct > filters.max_cross_exchange_spread_pct => Some(format!(
+                "spreads now Ext {:.3}%, Pac {:.3}%, Cross {:.3}% (limits {:.3}% intra, {:.3}% cross)",
+                live.extended_spread_pct,
+                live.pacifica_spread_pct,
+                live.cross_spread_pct,
+                filters.max_intra_exchange_spread_pct,
+                filters.max_cross_exchange_spread_pct
+            )),
+            Some(_) => None,
+        };
+        if let Some(reason) = rejection {
+            self.requeued_symbol = Some(best.symbol.clone());
+            return Err(BotError::execution(
+                format!("Aborted opening {} before sending orders: {}", best.symbol, reason),
+                true,
+            ));
         }
 
-        // Set Pacifica leverage to 1x
-        match self.pacifica_client.update_leverage(&pacifica_market, 1).await {
-            Ok(_) => info!("   ✅ Pacifica leverage set to 1x for {}", pacifica_market),
-            Err(e) => {
-                warn!("   ⚠️  Failed to set Pacifica leverage (continuing anyway): {}", e);
+        // Near settlement a rate can change sign: the scanned direction would then pay funding
+        if self.config.execution.recheck_funding {
+            match self.funding_forecasts(&best.symbol).await {
+                (Some(extended), Some(pacifica)) => {
+                    let live_apr = if long_on_extended { net_apr_pct(&extended, &pacifica) } else { net_apr_pct(&pacifica, &extended) };
+                    if funding_flipped(net_apr, live_apr) {
+                        return Err(BotError::execution(
+                            format!("Aborted opening {} before sending orders: net funding APR flipped from {:.2}% at scan to {:.2}%",
+                                best.symbol, net_apr, live_apr),
+                            true,
+                        ));
+                    }
+                    info!("Net funding APR {:.2}% just before sending (scan {:.2}%)", live_apr, net_apr);
+                }
+                _ => warn!("Could not re-check funding rates for {}; opening on the scanned rates", best.symbol),
             }
         }
 
+        if self.shutdown.is_requested() {
+            return Err(BotError::execution(format!("Aborted opening {} before sending orders: shutting down", best.symbol), false));
+        }
+
+        let capture = plan_spread_capture(
+            long_on_extended,
+            &extended_quote,
+            &pacifica_quote,
+            multipliers,
+            self.config.execution.spread_capture_pct,
+        );
+        let sequence = best.leg_sequence(self.config.execution.leg_order, position_size * current_price);
+
+        let vault_id = self
+            .config
+            .extended_vaults
+            .vault_for(self.strategy.name(), &best.symbol, &self.vault_id)
+            .to_string();
+        // Positions and balances of other vaults are invisible to this API key
+        if vault_id != self.vault_id {
+            return Err(BotError::Config(format!(
+                "Not opening {} in Extended vault {}: only VAULT_NUMBER {} is supported",
+                best.symbol, vault_id, self.vault_id
+            )));
+        }
+
         // Open delta neutral position
         let position = open_delta_neutral_position(
             &best.symbol,
@@ -618,12 +2086,33 @@ impl FundingBot {
             &mut self.pacifica_client,
             &extended_market,
             &pacifica_market,
+            multipliers,
             &self.stark_private_key,
             &self.stark_public_key,
-            &self.vault_id,
-        ).await.map_err(|e| format!("Failed to open position: {}", e))?;
+            &vault_id,
+            self.config.execution.max_slippage_pct,
+            self.config.execution.slippage_margin_pct,
+            &self.config.retry,
+            &self.intents,
+            &TopOfBookQuotes {
+                extended: Some(extended_quote),
+                pacifica: Some(pacifica_quote),
+                pacifica_book: self.opportunity_finder.pacifica_depth(&best.symbol),
+            },
+            sequence,
+            capture.as_ref(),
+            self.config.contingency.enabled.then_some(&self.contingency),
+        ).await;
+        self.risk.record_open_result(position.is_ok());
+        let (mut position, mut report) = position.map_err(|e| format!("Failed to open position: {}", e))?;
+        report.estimate_missing_fees(&self.config.fee_schedule());
+        position.entry_net_apr = Some(net_apr);
+        position.funding_average.record(position.opened_at, best.extended_funding_rate_apr, best.pacifica_funding_rate_apr);
+        position.open_execution = Some(report.clone());
+        let (position_id, symbol) = (position.position_id.clone(), position.symbol.clone());
 
         // Update state
+        self.state.record_execution(&report);
         self.state.current_position = Some(position);
         self.state.last_rotation_time = Some(
             std::time::SystemTime::now()
@@ -631,16 +2120,40 @@ impl FundingBot {
                 .as_secs()
         );
         self.state.total_rotations += 1;
-        self.state.save_to_file(&self.state_path)?;
+        self.save_state().await?;
+        self.intents.resolve(&position_id, &symbol);
 
         info!("{}", "✅ Position opened successfully!");
 
+        if self.config.protective_orders.enabled {
+            let protective = &self.config.protective_orders;
+            if protective.band_pct >= estimated_liquidation_distance_pct(leverage) {
+                warn!("⚠️  Protective band {:.1}% is beyond the ~{:.1}% liquidation distance at {}x: a leg can be liquidated before its stop triggers",
+                    protective.band_pct, estimated_liquidation_distance_pct(leverage), leverage);
+            }
+            if let Some(position) = &self.state.current_position {
+                if let Err(e) = place_protective_orders(
+                    position,
+                    Some(current_price),
+                    &self.extended_client,
+                    &mut self.pacifica_client,
+                    &self.stark_private_key,
+                    &self.stark_public_key,
+                    &vault_id,
+                    protective,
+                ).await {
+                    warn!("⚠️  {} (the position stays open without them)", e);
+                }
+            }
+        }
+
         Ok(())
     }
 
     /// Close the current position
-    pub async fn close_current_position(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        if self.state.current_position.is_some() {
+    pub async fn close_current_position(&mut self) -> Result<(), BotError> {
+        if let Some(symbol) = self.state.current_position.as_ref().map(|p| p.symbol.clone()) {
+            let _in_flight = self.begin_operation(format!("close {}", symbol))?;
             // Ensure state matches live positions before attempting close
             self.reconcile_state().await.ok();
 
@@ -650,21 +2163,52 @@ impl FundingBot {
                 return Ok(());
             }
 
+            // Capture final funding before the position leaves state
+            if let Err(e) = self.refresh_realized_funding().await {
+                warn!("Failed to refresh realized funding before close: {}", e);
+            }
+            if let Err(e) = self.sample_funding_rates().await {
+                warn!("Failed to sample funding rates before close: {}", e);
+            }
+
             let pos = self.state.current_position.as_ref().unwrap();
             info!("{} {}", "🔄 Closing current position:", pos.symbol);
 
-            close_delta_neutral_position(
+            // Decision prices for the execution report; closing does not wait on them
+            let quotes = match self.live_quotes(&pos.symbol, &pos.extended_market_name(), &pos.pacifica_market_name()).await {
+                Ok((extended, pacifica)) => TopOfBookQuotes { extended: Some(extended), pacifica: Some(pacifica), pacifica_book: None },
+                Err(_) => TopOfBookQuotes::default(),
+            };
+
+            let mut report = close_delta_neutral_position(
                 pos,
                 &self.extended_client,
                 &mut self.pacifica_client,
                 &self.stark_private_key,
                 &self.stark_public_key,
-                &self.vault_id,
+                pos.extended_vault_id.as_deref().unwrap_or(&self.vault_id),
+                &self.config.retry,
+                &self.intents,
+                &quotes,
             ).await.map_err(|e| format!("Failed to close position: {}", e))?;
-
-            // Clear position from state
-            self.state.current_position = None;
-            self.state.save_to_file(&self.state_path)?;
+            report.estimate_missing_fees(&self.config.fee_schedule());
+            self.state.record_execution(&report);
+
+            // Clear position from state, keeping its funding in the running total
+            let closed = self.state.current_position.take();
+            if let Some(ref closed) = closed {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)?
+                    .as_secs();
+                self.state.record_close(closed, now);
+            }
+            self.save_state().await?;
+            if let Some(closed) = closed {
+                self.intents.resolve(&closed.position_id, &closed.symbol);
+                if self.config.protective_orders.enabled {
+                    cancel_protective_orders(&closed, &self.extended_client, &self.pacifica_client).await;
+                }
+            }
 
             info!("{}", "✅ Position closed successfully!");
         } else {
@@ -674,6 +2218,171 @@ impl FundingBot {
         Ok(())
     }
 
+    /// Close `fraction` (between 0 and 1) of both legs of the held position, keeping the
+    /// rest open (see `trading::close_partial`)
+    pub async fn reduce_current_position(&mut self, fraction: f64) -> Result<(), BotError> {
+        let _in_flight = self.begin_operation(format!("reduce by {:.0}%", fraction * 100.0))?;
+        self.reconcile_state().await?;
+        let Some(pos) = self.state.current_position.as_mut() else {
+            return Err(BotError::execution("No active position to reduce", false));
+        };
+        // Saved before any order, so a reduction cut short never reuses its order IDs
+        pos.reductions += 1;
+        let pos = pos.clone();
+        self.save_state().await?;
+        info!("✂️  Reducing current position {} by {:.0}%", pos.symbol, fraction * 100.0);
+
+        let quotes = match self.live_quotes(&pos.symbol, &pos.extended_market_name(), &pos.pacifica_market_name()).await {
+            Ok((extended, pacifica)) => TopOfBookQuotes { extended: Some(extended), pacifica: Some(pacifica), pacifica_book: None },
+            Err(_) => TopOfBookQuotes::default(),
+        };
+        let (mut remaining, mut report) = close_partial(
+            &pos,
+            fraction,
+            &self.extended_client,
+            &mut self.pacifica_client,
+            &self.stark_private_key,
+            &self.stark_public_key,
+            pos.extended_vault_id.as_deref().unwrap_or(&self.vault_id),
+            &self.config.retry,
+            &self.intents,
+            &quotes,
+        ).await?;
+        report.estimate_missing_fees(&self.config.fee_schedule());
+        self.state.record_execution(&report);
+        let protective = self.config.protective_orders.enabled;
+        if protective {
+            // The resting orders were sized for the whole legs; the next generation is
+            // saved before it is placed so a close always cancels what is resting
+            cancel_protective_orders(&remaining, &self.extended_client, &self.pacifica_client).await;
+            remaining.protective_generation = Some(remaining.protective_generation.unwrap_or(1) + 1);
+        }
+        self.state.current_position = Some(remaining);
+        self.save_state().await?;
+        self.intents.resolve(&report.position_id, &report.symbol);
+
+        if protective {
+            if let Some(position) = &self.state.current_position {
+                if let Err(e) = place_protective_orders(
+                    position,
+                    None,
+                    &self.extended_client,
+                    &mut self.pacifica_client,
+                    &self.stark_private_key,
+                    &self.stark_public_key,
+                    position.extended_vault_id.as_deref().unwrap_or(&self.vault_id),
+                    &self.config.protective_orders,
+                ).await {
+                    warn!("⚠️  {} (the reduced position stays open without them)", e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Close every position on both venues, tracked or not (see `trading::emergency_flatten_all`).
+    /// The tracked position is cleared from state only once the report shows both venues flat.
+    pub async fn emergency_flatten_all(&mut self) -> Result<FlattenReport, BotError> {
+        if self.state.current_position.is_some() {
+            if let Err(e) = self.refresh_realized_funding().await {
+                warn!("Failed to refresh realized funding before flatten: {}", e);
+            }
+        }
+
+        let vault_id = self
+            .state
+            .current_position
+            .as_ref()
+            .and_then(|p| p.extended_vault_id.clone())
+            .unwrap_or_else(|| self.vault_id.clone());
+        let report = emergency_flatten_all(
+            &self.extended_client,
+            &mut self.pacifica_client,
+            &self.stark_private_key,
+            &self.stark_public_key,
+            &vault_id,
+            &self.config.retry,
+            &self.intents,
+        ).await;
+
+        if report.is_flat() {
+            if let Some(closed) = self.state.current_position.take() {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)?
+                    .as_secs();
+                self.state.record_close(&closed, now);
+                self.save_state().await?;
+                self.intents.resolve(&closed.position_id, &closed.symbol);
+                if self.config.protective_orders.enabled {
+                    cancel_protective_orders(&closed, &self.extended_client, &self.pacifica_client).await;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Whether the strategy would open the held position again as it is (same symbol
+    /// and direction), so rotating would only close and reopen it
+    fn strategy_reselects_held(&self, scan: &ScanResult) -> bool {
+        let Some(held) = &self.state.current_position else {
+            return false;
+        };
+        let Some(direction) = held.held_direction() else {
+            return false;
+        };
+        self.strategy
+            .select(scan, &self.state, &self.config)
+            .is_some_and(|target| target.symbol() == held.symbol && target.direction == direction)
+    }
+
+    /// APR points gained by rotating into the best scanned opportunity right now,
+    /// after switching costs. `None` when early rotation is disabled, nothing is held,
+    /// the best opportunity is the held symbol, or the gain is below the threshold.
+    fn early_rotation_gain(&self, scan: &ScanResult) -> Option<(String, f64)> {
+        let rotation = &self.config.rotation;
+        if rotation.early_rotation_threshold_apr_pct <= 0.0 {
+            return None;
+        }
+        let pos = self.state.current_position.as_ref()?;
+        let direction = pos.held_direction()?;
+        let target = self.strategy.select(scan, &self.state, &self.config)?;
+        let best = &target.opportunity;
+        if best.symbol == pos.symbol {
+            return None;
+        }
+        let held = scan
+            .all_candidates
+            .iter()
+            .map(|c| &c.opportunity)
+            .find(|o| o.symbol == pos.symbol)?;
+
+        let gain = early_rotation_gain_apr(
+            held,
+            held.net_apr_for(direction),
+            best,
+            self.config.trading.hold_time_hours,
+            self.config.round_trip_fee_pct(),
+        );
+        (gain >= rotation.early_rotation_threshold_apr_pct).then(|| (best.symbol.clone(), gain))
+    }
+
+    /// Close the held position early if its symbol became blacklisted, delisted or reduce-only.
+    /// Returns true if a close was performed.
+    pub async fn close_if_restricted(&mut self) -> Result<bool, BotError> {
+        let Some(symbol) = self.state.current_position.as_ref().map(|p| p.symbol.clone()) else {
+            return Ok(false);
+        };
+
+        let Some(reason) = self.opportunity_finder.symbol_restriction(&symbol).await? else {
+            return Ok(false);
+        };
+
+        warn!("🚫 Held symbol {} is restricted ({}). Closing early.", symbol, reason);
+        self.close_current_position().await?;
+        Ok(true)
+    }
+
     /// Check if the current position is imbalanced (only one leg active)
     pub fn is_imbalanced(&self) -> bool {
         if let Some(pos) = &self.state.current_position {
@@ -686,9 +2395,56 @@ impl FundingBot {
         }
     }
 
+    /// Stamp the current loop iteration and rewrite the heartbeat file.
+    /// Failures only warn: a missing heartbeat must not stop trading.
+    fn touch_heartbeat(&mut self) {
+        self.heartbeat.last_loop_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.heartbeat.symbol = self.state.current_position.as_ref().map(|p| p.symbol.clone());
+        if let Err(e) = self.heartbeat.write_to_file(&self.heartbeat_path) {
+            warn!("Failed to write heartbeat file {}: {}", self.heartbeat_path, e);
+        }
+    }
+
+    /// Clock check and recovery of whatever a previous run left behind, before any
+    /// position is touched. Fails only when the host clock is too far off to trade.
+    pub(crate) async fn recover_at_startup(&mut self) -> Result<(), BotError> {
+        // Refuse to run next to another instance on the same state file
+        if !self.read_only && self.state_lock.is_none() {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs();
+            self.state_lock = Some(self.state_store.blocking(move |store| StateLock::acquire(store, now)).await?);
+        }
+
+        // Startup clock sanity check: signed Pacifica requests expire after 5s
+        match self.sync_clock().await {
+            Err(e @ BotError::ClockSkew { .. }) => return Err(e),
+            Err(e) => warn!("Clock check against Pacifica failed: {}. Using local time.", e),
+            Ok(()) => info!("🕒 Clock offset vs Pacifica: {}ms", clock::offset_ms()),
+        }
+
+        // Settle orders a previous run left resting, then find executions it left
+        // half-done, before touching any position
+        if let Err(e) = self.recover_in_flight_orders().await {
+            warn!("Failed to recover open orders from a previous run: {}", e);
+        }
+        if let Err(e) = self.replay_intent_log() {
+            warn!("Failed to replay intent log {}: {}", self.intents.path(), e);
+        }
+        self.start_order_timeout();
+        Ok(())
+    }
+
     /// Main bot loop
-    pub async fn run(&mut self, extended_api_key: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn run(&mut self, extended_api_key: Option<String>) -> Result<(), BotError> {
         info!("{}", "🚀 Starting Funding Rate Arbitrage Bot");
+        info!("🏷️  Build {}", self.build_info());
+        if self.config.environment.is_testnet() {
+            warn!("{}", "🧪 TESTNET mode: Extended Sepolia and Pacifica test endpoints");
+        }
         info!("{} {} {}",
             "📊 Monitoring interval:",
             MONITORING_INTERVAL_MINUTES,
@@ -699,132 +2455,1025 @@ impl FundingBot {
             "hours");
         info!("{}", "🛑 Press Ctrl+C to stop gracefully");
 
+        self.recover_at_startup().await?;
+        if self.config.control.enabled {
+            self.control = Some(ControlServer::start(&self.config.control).await?);
+        }
+        if self.config.funding_stream.enabled {
+            self.funding_cache = Some(FundingRateCache::start(self.config.environment, extended_api_key.clone(), self.config.symbols.clone()));
+        }
+        if self.config.account_stream.enabled {
+            self.pacifica_account = Some(PacificaAccountCache::start(self.config.environment, &self.pacifica_creds.account));
+        }
+        let mut funding_check = tokio::time::interval(Duration::from_secs(
+            self.config.funding_stream.check_interval_seconds.max(1),
+        ));
+        funding_check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut order_sweep = tokio::time::interval(Duration::from_secs(
+            self.config.order_sweep.check_interval_seconds.max(1),
+        ));
+        order_sweep.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut margin_check = tokio::time::interval(Duration::from_secs(
+            self.config.risk.margin_call.poll_interval_seconds.max(1),
+        ));
+        margin_check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        self.shutdown.listen_for_signals();
+        let shutdown = self.shutdown.clone();
+        let drain_timeout = self.config.shutdown.drain_timeout();
+
+        let mut consecutive_incidents: u32 = 0;
         loop {
-            // Non-blocking check for Ctrl+C (gracefully exit; keep positions open)
-            if tokio::signal::ctrl_c().now_or_never().is_some() {
-                info!("{}", "");
-                info!("{}", "🛑 Shutdown signal received. Stopping bot gracefully...");
-                info!("{}", "ℹ️  Open positions (if any) will remain open.");
-                info!("{}", "   Manage them from the exchange dashboards or restart the bot.");
-                info!("{}", "👋 Bot stopped. Goodbye!");
-                return Ok(());
+            // Gracefully exit between cycles; positions stay open
+            if shutdown.is_requested() {
+                return self.finish_shutdown(Drain::Drained).await;
             }
 
-            // Reconcile any stale state before acting
-            if let Err(e) = self.reconcile_state().await {
-                warn!("Network error during state reconciliation: {}. Skipping cycle to prevent unsafe actions.", e);
-                sleep(Duration::from_secs(60)).await; // Wait 1 minute before retrying
-                continue;
-            }
-
-            // CRITICAL: Check for imbalance immediately after reconciliation
-            if self.is_imbalanced() {
-                error!("{}", "⚠️  CRITICAL: Position imbalance detected! One leg is missing.");
-                info!("{}", "🚨 Initiating EMERGENCY CLOSE of remaining leg to preserve capital...");
-                
-                if let Err(e) = self.close_current_position().await {
-                    error!("{} {}", "❌ Failed to close imbalanced position:", e);
-                    info!("{}", "Will retry immediately...");
-                    // Don't sleep long if we are in a critical state
-                    sleep(Duration::from_secs(5)).await;
-                    continue;
-                } else {
-                    info!("{}", "✅ Emergency close successful. State is now clean.");
-                    // Continue to normal loop to potentially re-open if opportunity exists
+            // Sent from inside `supervise`, while the cycle holds the bot
+            let hooks = self.incident_hooks.clone();
+            let symbol = self.state.current_position.as_ref().map(|p| p.symbol.clone());
+            let on_overrun = move |failure: CycleFailure| async move {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let incident = Incident::new(failure, now, symbol, consecutive_incidents, false);
+                error!("🚨 {}", incident);
+                error!("watchdog_incident {}", incident.to_json_line());
+                for hook in &hooks {
+                    if let Err(e) = hook.notify(&incident).await {
+                        warn!("Incident hook failed: {}", e);
+                    }
                 }
-            }
+            };
+            let deadline = self.config.watchdog.cycle_deadline();
+            let cycle = async {
+                match self.run_cycle(extended_api_key.clone()).await {
+                    // Restarting cannot fix rejected credentials: pause instead
+                    Err(e) if e.is_auth() => {
+                        self.pause_for_auth(e.to_string()).await;
+                        Ok(MONITORING_INTERVAL_MINUTES * 60)
+                    }
+                    result => result,
+                }
+            };
+            // A shutdown mid-cycle abandons the cycle once no open or close is in flight
+            let outcome = tokio::select! {
+                outcome = watchdog::supervise(deadline, &shutdown, on_overrun, cycle) => outcome,
+                drain = shutdown.drained(drain_timeout) => return self.finish_shutdown(drain).await,
+            };
+            let wait_secs = match outcome {
+                Ok(wait_secs) => {
+                    consecutive_incidents = 0;
+                    self.check_auth_failures().await;
+                    self.finish_cycle_timing();
+                    self.record_decision();
+                    self.record_equity().await;
+                    self.send_daily_summary_if_due().await;
+                    self.run_audit_if_due().await;
+                    wait_secs
+                }
+                Err(failure) => {
+                    consecutive_incidents += 1;
+                    let giving_up = self.config.watchdog.gives_up_after(consecutive_incidents);
+                    let now = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0);
+                    let incident = Incident::new(
+                        failure,
+                        now,
+                        self.state.current_position.as_ref().map(|p| p.symbol.clone()),
+                        consecutive_incidents,
+                        !giving_up,
+                    );
+                    self.decision.skip(format!("cycle aborted: {}", incident));
+                    self.finish_cycle_timing();
+                    self.record_decision();
+                    self.report_incident(&incident).await;
+                    if giving_up {
+                        return Err(BotError::execution(incident.to_string(), false));
+                    }
+                    self.config.watchdog.restart_delay_seconds
+                }
+            };
 
-            // If state is empty but live positions exist, refuse to open to prevent duplicates
-            if self.state.current_position.is_none() {
-                match self.recover_state_if_untracked().await {
-                    Ok(RecoveryOutcome::Recovered) => {
-                        info!("Recovered bot state from live positions. Monitoring only.");
+            // Wait for next monitoring cycle (interruptible by shutdown and control commands)
+            let wait = sleep(Duration::from_secs(wait_secs));
+            tokio::pin!(wait);
+            loop {
+                let request = tokio::select! {
+                    _ = &mut wait => break,
+                    drain = shutdown.drained(drain_timeout) => return self.finish_shutdown(drain).await,
+                    Some(request) = next_control_request(&mut self.control) => request,
+                    _ = funding_check.tick(), if self.funding_cache.is_some() => {
+                        if self.check_funding_flip() {
+                            break;
+                        }
+                        continue;
                     }
-                    Ok(RecoveryOutcome::Blocked(details)) => {
-                        error!("⚠️  Live positions detected while bot state is empty. Skipping open/rotation to avoid duplicate exposure. {}", details);
-                        info!("Resolve by closing manually (or run the emergency_exit binary) or reconstruct bot_state.json, then restart.");
-                        sleep(Duration::from_secs(MONITORING_INTERVAL_MINUTES * 60)).await;
+                    _ = order_sweep.tick(), if self.config.order_sweep.enabled => {
+                        self.sweep_orphan_orders().await;
                         continue;
                     }
-                    Err(e) => {
-                        warn!("Could not verify live positions (skipping cycle to avoid duplicates): {}", e);
-                        sleep(Duration::from_secs(60)).await;
+                    _ = margin_check.tick(), if self.config.risk.margin_call.enabled && !self.read_only => {
+                        self.check_margin().await;
                         continue;
                     }
-                    Ok(RecoveryOutcome::NoAction) => {}
+                };
+                if self.handle_control(request).await {
+                    break;
                 }
             }
 
-            // Display status
-            self.display_status().await?;
+            if consecutive_incidents > 0 {
+                self.restart_after_incident().await;
+            }
+        }
+    }
 
-            // Always scan and display opportunities at start of each cycle
-            info!("");
-            info!("{}", "🔍 Scanning current market opportunities...");
-            if let Ok(scan_result) = self.opportunity_finder.scan(extended_api_key.clone()).await {
-                scan_result.display_summary(&self.config.filters);
-            } else {
-                warn!("{}", "Failed to scan opportunities");
+    /// Save the final state once the shutdown drain ended. Positions stay open.
+    async fn finish_shutdown(&mut self, drain: Drain) -> Result<(), BotError> {
+        if let Drain::TimedOut(running) = &drain {
+            error!(
+                "🛑 Exiting with {} still running after {}s; the intent log is replayed on the next start",
+                running.join(", "),
+                self.config.shutdown.drain_timeout_seconds
+            );
+        }
+        self.save_state().await?;
+        info!("{}", "ℹ️  Open positions (if any) will remain open.");
+        info!("{}", "   Manage them from the exchange dashboards or restart the bot.");
+        info!("{}", "👋 Bot stopped. Goodbye!");
+        Ok(())
+    }
+
+    /// Mark an order-sending operation in flight until the guard drops, so shutdown waits
+    /// for it; refused once shutdown was requested
+    fn begin_operation(&self, operation: String) -> Result<InFlight, BotError> {
+        if self.shutdown.is_requested() {
+            return Err(BotError::execution(format!("Not starting {}: shutting down", operation), false));
+        }
+        Ok(self.shutdown.begin(operation))
+    }
+
+    /// Pause opens and rotations after a venue refused the credentials. Retrying cannot
+    /// succeed, and an open or rotation with one venue refusing orders would leave a leg
+    /// unhedged. Monitoring continues; hooks are notified once until the bot is resumed.
+    async fn pause_for_auth(&mut self, message: String) {
+        self.set_paused(true).await;
+        self.decision.skip(format!("paused: authentication failed ({})", message));
+        if self.auth_failure.is_some() {
+            return;
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let failure = CycleFailure { kind: IncidentKind::AuthFailure, message: message.clone(), elapsed: Duration::ZERO };
+        let incident = Incident::new(
+            failure,
+            now,
+            self.state.current_position.as_ref().map(|p| p.symbol.clone()),
+            1,
+            false,
+        );
+        self.auth_failure = Some(message);
+        self.report_incident(&incident).await;
+    }
+
+    /// Pause if any API call met a 401/403 since the previous check
+    async fn check_auth_failures(&mut self) {
+        let failures = metrics::global().take_auth_failures();
+        if !failures.is_empty() {
+            let message = failures.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ");
+            self.pause_for_auth(message).await;
+        }
+    }
+
+    /// Log an incident as one JSON line, record it in the heartbeat and notify hooks
+    async fn report_incident(&mut self, incident: &Incident) {
+        error!("🚨 {}", incident);
+        error!("watchdog_incident {}", incident.to_json_line());
+        self.heartbeat.watchdog_restarts += u64::from(incident.restarting);
+        self.heartbeat.last_incident_at = Some(incident.at);
+        if let Err(e) = self.heartbeat.write_to_file(&self.heartbeat_path) {
+            warn!("Failed to write heartbeat file {}: {}", self.heartbeat_path, e);
+        }
+        for hook in &self.incident_hooks {
+            if let Err(e) = hook.notify(incident).await {
+                warn!("Incident hook failed: {}", e);
             }
-            info!("");
+        }
+    }
 
-            // Check if we need to rotate
-            if self.state.should_rotate(self.config.trading.hold_time_hours) {
-                info!("{} {} {}",
-                    "⏰ Position has been open for",
-                    self.config.trading.hold_time_hours,
-                    "hours, rotating...");
+    /// Pacifica account info from the account stream while it is fresh, else fetched over
+    /// a one-shot WebSocket subscription (which also refreshes the stream's cache)
+    async fn pacifica_account_info(&self) -> Result<PacificaAccountInfo, BotError> {
+        let max_age = self.config.account_stream.max_age();
+        if let Some(info) = self.pacifica_account.as_ref().and_then(|cache| cache.fresh(max_age)) {
+            return Ok(info);
+        }
+        if let Some(cache) = &self.pacifica_account {
+            match cache.age_secs() {
+                Some(age) => debug!("Streamed Pacifica account info is {}s old, fetching it", age),
+                None => debug!("No streamed Pacifica account info yet, fetching it"),
+            }
+        }
+        let pacifica_ws = PacificaWsTrading::new(self.pacifica_creds.clone(), self.config.environment.is_testnet());
+        let info = pacifica_ws.get_account_info().await?;
+        fees::record_pacifica_tier(info.fee_tier);
+        if let Some(cache) = &self.pacifica_account {
+            cache.update(info.clone());
+        }
+        Ok(info)
+    }
+
+    /// Account equity on Extended and Pacifica, `None` where it could not be fetched
+    async fn fetch_equities(&self) -> (Option<f64>, Option<f64>) {
+        let extended = match self.extended_client.get_balance().await {
+            Ok(balance) => Some(balance.equity_f64()),
+            Err(e) => {
+                warn!("Failed to fetch Extended equity: {}", e);
+                None
+            }
+        };
+        let pacifica = match self.pacifica_account_info().await {
+            Ok(info) => Some(info.account_equity_f64()),
+            Err(e) => {
+                warn!("Failed to fetch Pacifica equity: {}", e);
+                None
+            }
+        };
+        (extended, pacifica)
+    }
+
+    /// Append this cycle's equity on both venues to the equity history
+    async fn record_equity(&self) {
+        if self.read_only {
+            return;
+        }
+        let (extended_equity, pacifica_equity) = self.fetch_equities().await;
+        if extended_equity.is_none() && pacifica_equity.is_none() {
+            return;
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let point = LedgerTotals::from_state(&self.state, now, extended_equity, pacifica_equity);
+        if let Err(e) = self.equity_history.record(&point) {
+            warn!("Failed to write equity history {}: {}", self.equity_history.path(), e);
+        }
+    }
+
+    /// Close the cycle's phase timings: log them, warn when the cycle ran over
+    /// `cycle_budget`, and keep them in the cycle's decision record
+    fn finish_cycle_timing(&mut self) {
+        let timer = &mut self.cycle_timer;
+        timer.finish();
+        let elapsed = timer.elapsed().as_secs_f64();
+        if timer.over_budget() {
+            let skipped = match timer.skipped() {
+                [] => String::new(),
+                skipped => format!("; skipped {}", skipped.join(", ")),
+            };
+            warn!("⏱️  Cycle took {:.1}s, over its {}s budget ({}{})",
+                elapsed, self.config.cycle_budget.budget_seconds, timer, skipped);
+        } else {
+            debug!("⏱️  Cycle took {:.1}s ({})", elapsed, timer);
+        }
+        self.decision.phase_ms = timer.phase_ms();
+    }
+
+    /// Append the cycle's decision to the decision log
+    fn record_decision(&self) {
+        if self.read_only {
+            return;
+        }
+        if let Err(e) = self.decision_log.record(&self.decision) {
+            warn!("Failed to write decision log {}: {}", self.decision_log.path(), e);
+        }
+    }
+
+    /// The last `limit` cycle decisions, oldest first
+    pub fn decisions(&self, limit: usize) -> Result<Vec<CycleDecision>, BotError> {
+        self.decision_log.recent(limit)
+    }
+
+    /// Equity snapshots recorded so far, one per monitoring cycle
+    pub fn equity_curve(&self) -> Result<EquityCurve, BotError> {
+        self.equity_history.curve()
+    }
+
+    /// Log the ledger changes since the last summary and notify hooks, once a day at
+    /// `daily_summary.hour_utc`. The first call only records the starting point.
+    async fn send_daily_summary_if_due(&mut self) {
+        if !self.config.daily_summary.enabled {
+            return;
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let due = match &self.state.daily_summary_baseline {
+            Some(baseline) => self.config.daily_summary.is_due(baseline.at, now),
+            None => true,
+        };
+        if !due {
+            return;
+        }
+
+        let (extended_equity, pacifica_equity) = self.fetch_equities().await;
+        let totals = LedgerTotals::from_state(&self.state, now, extended_equity, pacifica_equity);
+        if let Some(baseline) = self.state.daily_summary_baseline.replace(totals.clone()) {
+            let summary = DailySummary::between(&baseline, &totals);
+            info!("📊 {}", summary);
+            info!("daily_summary {}", summary.to_json_line());
+            for hook in &self.summary_hooks {
+                if let Err(e) = hook.notify(&summary).await {
+                    warn!("Summary hook failed: {}", e);
+                }
+            }
+        }
+        if let Err(e) = self.save_state().await {
+            warn!("Failed to save daily summary baseline: {}", e);
+        }
+    }
 
-                // Close current position
-                if let Err(e) = self.close_current_position().await {
-                    error!("{} {}", "Failed to close position:", e);
-                    info!("{}", "Will retry next cycle.");
-                    sleep(Duration::from_secs(MONITORING_INTERVAL_MINUTES * 60)).await;
+    /// Cross-check state against live positions, resting orders and the fills since
+    /// `since_ms` on both venues. With `audit.auto_remediate` (and not read-only),
+    /// orphan orders are cancelled and state is re-synced to the live legs.
+    pub async fn audit(&mut self, since_ms: u64) -> Result<AuditReport, BotError> {
+        let live = self.fetch_live_accounts(since_ms).await?;
+        let traded_symbols: HashSet<String> = self
+            .intents
+            .read_all()?
+            .into_iter()
+            .filter(|r| r.at_ms >= since_ms as i64)
+            .map(|r| r.symbol)
+            .collect();
+        let divergences = find_divergences(self.state.current_position.as_ref(), &live, &traded_symbols);
+
+        let mut remediation = Vec::new();
+        if self.config.audit.auto_remediate && !self.read_only && !divergences.is_empty() {
+            for divergence in &divergences {
+                let Divergence::OrphanOrder { venue, symbol, order_id, .. } = divergence else {
                     continue;
+                };
+                let cancelled = match venue {
+                    Venue::Extended if order_id.is_empty() => {
+                        warn!("Extended {} order without external ID cannot be cancelled by the audit", symbol);
+                        continue;
+                    }
+                    Venue::Extended => self.extended_client.cancel_order_by_external_id(order_id).await.map_err(BotError::from),
+                    Venue::Pacifica => match order_id.parse() {
+                        Ok(id) => self.pacifica_client.cancel_order_by_id(&self.config.symbols.pacifica_market(symbol), id).await.map_err(BotError::from),
+                        Err(_) => continue,
+                    },
+                };
+                match cancelled {
+                    Ok(()) => remediation.push(format!("cancelled {} {} order {}", venue, symbol, order_id)),
+                    Err(e) => warn!("Audit failed to cancel {} order {}: {}", venue, order_id, e),
                 }
+            }
+            if divergences.iter().any(Divergence::is_position) {
+                match self.reconcile_or_recover().await {
+                    Ok(()) => remediation.push("re-synced state to live positions".to_string()),
+                    Err(e) => warn!("Audit failed to reconcile state: {}", e),
+                }
+            }
+        }
 
-                // Wait a bit before opening new position
-                sleep(Duration::from_secs(5)).await;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        Ok(AuditReport {
+            at: now,
+            symbol: self.state.current_position.as_ref().map(|p| p.symbol.clone()),
+            divergences,
+            remediation,
+        })
+    }
 
-                // Open new position
-                if let Err(e) = self.open_best_opportunity(extended_api_key.clone()).await {
-                    error!("{} {}", "Failed to open new position:", e);
-                    info!("{}", "Will retry next cycle.");
+    /// Cancel resting orders on both venues that belong to neither the held position nor
+    /// an unresolved execution, once past `order_sweep.grace_seconds`
+    async fn sweep_orphan_orders(&mut self) {
+        let mut orders = Vec::new();
+        match self.extended_client.get_open_orders(None).await {
+            Ok(open) => orders.extend(open.into_iter().map(|o| RestingOrder {
+                venue: Venue::Extended,
+                symbol: self.config.symbols.symbol_or_market(Venue::Extended, &o.market),
+                order_id: o.id.to_string(),
+                client_order_id: Some(o.external_id).filter(|id| !id.is_empty()),
+                created_at_ms: o.created_time,
+            })),
+            Err(e) => warn!("Order sweep could not list Extended orders: {}", e),
+        }
+        match self.pacifica_client.get_open_orders(None).await {
+            Ok(open) => orders.extend(open.into_iter().map(|o| RestingOrder {
+                venue: Venue::Pacifica,
+                symbol: o.symbol,
+                order_id: o.order_id.to_string(),
+                client_order_id: o.client_order_id,
+                created_at_ms: Some(o.created_at).filter(|&t| t > 0),
+            })),
+            Err(e) => warn!("Order sweep could not list Pacifica orders: {}", e),
+        }
+
+        let mut position_ids: Vec<String> = self.state.current_position.iter().map(|p| p.position_id.clone()).collect();
+        match self.intents.unresolved() {
+            Ok(executions) => position_ids.extend(executions.into_iter().map(|e| e.position_id)),
+            Err(e) => {
+                // Without the intent log an in-flight order could look orphaned
+                warn!("Order sweep skipped: cannot read the intent log: {}", e);
+                return;
+            }
+        }
+
+        let now_ms = clock::now_ms().max(0) as u64;
+        for order in self.order_sweeper.orphans(&orders, &position_ids, &self.config.order_sweep, now_ms) {
+            warn!("🧹 Cancelling orphan {} {} order {} (client ID {})",
+                order.venue, order.symbol, order.order_id, order.client_order_id.as_deref().unwrap_or("none"));
+            let cancelled = match (order.venue, order.client_order_id.as_deref()) {
+                (Venue::Extended, Some(external_id)) => {
+                    self.extended_client.cancel_order_by_external_id(external_id).await.map_err(BotError::from)
+                }
+                (Venue::Extended, None) => {
+                    warn!("Extended order {} has no external ID and cannot be cancelled by the sweeper", order.order_id);
+                    continue;
                 }
-            } else if self.state.current_position.is_none() {
-                // No position, try to open one
-                info!("{}", "📭 No active position, looking for opportunity...");
+                (Venue::Pacifica, _) => match order.order_id.parse() {
+                    Ok(id) => self.pacifica_client.cancel_order_by_id(&order.symbol, id).await.map_err(BotError::from),
+                    Err(_) => continue,
+                },
+            };
+            if let Err(e) = cancelled {
+                warn!("Order sweep failed to cancel {} order {}: {}", order.venue, order.order_id, e);
+            }
+        }
+    }
+
+    /// Positions, resting orders and fills since `since_ms` on both venues
+    async fn fetch_live_accounts(&self, since_ms: u64) -> Result<LiveAccounts, BotError> {
+        let symbol_of = |venue, market: &str| self.config.symbols.symbol_or_market(venue, market);
+        let mut live = LiveAccounts::default();
+
+        for p in self.extended_client.get_positions(None).await? {
+            let sign = if p.is_long() { 1.0 } else { -1.0 };
+            live.positions.push(LiveLeg { venue: Venue::Extended, symbol: symbol_of(Venue::Extended, &p.market), size_base: sign * p.size_f64() });
+        }
+        for p in self.pacifica_client.get_positions().await? {
+            let sign = if p.is_long() { 1.0 } else { -1.0 };
+            live.positions.push(LiveLeg { venue: Venue::Pacifica, symbol: symbol_of(Venue::Pacifica, &p.symbol), size_base: sign * p.size() });
+        }
+
+        for order in self.extended_client.get_open_orders(None).await? {
+            live.orders.push(LiveOrder {
+                venue: Venue::Extended,
+                symbol: symbol_of(Venue::Extended, &order.market),
+                order_id: order.external_id.clone(),
+                size_base: order.qty.as_deref().and_then(|q| q.parse().ok()).unwrap_or(0.0),
+            });
+        }
+        for order in self.pacifica_client.get_open_orders(None).await? {
+            live.orders.push(LiveOrder {
+                venue: Venue::Pacifica,
+                symbol: symbol_of(Venue::Pacifica, &order.symbol),
+                order_id: order.order_id.to_string(),
+                size_base: order.initial_amount.parse().unwrap_or(0.0),
+            });
+        }
+
+        for trade in self.extended_client.get_trades(None, AUDIT_FILL_LIMIT).await? {
+            if trade.created_time >= since_ms {
+                live.fills.push(LiveFill {
+                    venue: Venue::Extended,
+                    symbol: symbol_of(Venue::Extended, &trade.market),
+                    size_base: trade.qty_f64(),
+                    at_ms: trade.created_time,
+                });
+            }
+        }
+        for trade in self
+            .pacifica_client
+            .get_trade_history(None, Some(AUDIT_FILL_LIMIT), Some(since_ms), None)
+            .await?
+        {
+            live.fills.push(LiveFill {
+                venue: Venue::Pacifica,
+                symbol: symbol_of(Venue::Pacifica, &trade.symbol),
+                size_base: trade.amount.parse().unwrap_or(0.0),
+                at_ms: trade.created_at,
+            });
+        }
+        Ok(live)
+    }
 
-                if let Err(e) = self.open_best_opportunity(extended_api_key.clone()).await {
-                    error!("{} {}", "Failed to open position:", e);
-                    info!("{}", "Will retry next cycle.");
+    /// Run the reconciliation audit every `audit.interval_minutes`: log the report as
+    /// one JSON line and notify hooks when it found divergences. The first audit looks
+    /// at fills over one interval.
+    async fn run_audit_if_due(&mut self) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        if !self.config.audit.is_due(self.last_audit_at, now) {
+            return;
+        }
+        let since = self
+            .last_audit_at
+            .unwrap_or_else(|| now.saturating_sub(self.config.audit.interval_minutes * 60));
+        self.last_audit_at = Some(now);
+
+        let report = match self.audit(since * 1000).await {
+            Ok(report) => report,
+            Err(e) => {
+                warn!("Reconciliation audit failed: {}", e);
+                return;
+            }
+        };
+        if report.is_clean() {
+            info!("🔎 {}", report);
+            info!("audit {}", report.to_json_line());
+            return;
+        }
+        warn!("🔎 {}", report);
+        warn!("audit {}", report.to_json_line());
+        for hook in &self.audit_hooks {
+            if let Err(e) = hook.notify(&report).await {
+                warn!("Audit hook failed: {}", e);
+            }
+        }
+    }
+
+    /// Carry out a control command and reply to it; true when the next cycle should
+    /// start now instead of after the remaining sleep
+    async fn handle_control(&mut self, request: ControlRequest) -> bool {
+        let (result, run_cycle_now) = self.apply_control(request.command).await;
+        if let Err(e) = &result {
+            warn!("Control command refused: {:?}", e);
+        }
+        let _ = request.reply.send(result);
+        run_cycle_now
+    }
+
+    async fn apply_control(&mut self, command: ControlCommand) -> (Result<serde_json::Value, ControlError>, bool) {
+        let held = self.state.current_position.as_ref().map(|p| p.symbol.clone());
+        match command {
+            ControlCommand::Status => {
+                let snapshot = self
+                    .status_snapshot()
+                    .await
+                    .map_err(|e| ControlError::Rejected(e.to_string()))
+                    .and_then(|s| serde_json::to_value(s).map_err(|e| ControlError::Rejected(e.to_string())));
+                (snapshot, false)
+            }
+            ControlCommand::RiskReport => {
+                let report = match self.risk_report().await {
+                    Ok(Some(report)) => serde_json::to_value(report).map_err(|e| ControlError::Rejected(e.to_string())),
+                    Ok(None) => Err(ControlError::Rejected("no position held".to_string())),
+                    Err(e) => Err(ControlError::Rejected(e.to_string())),
+                };
+                (report, false)
+            }
+            ControlCommand::Pause => {
+                self.set_paused(true).await;
+                info!("{}", "⏸️  Paused by control command: no new opens or rotations");
+                (Ok(serde_json::json!({ "paused": true })), false)
+            }
+            ControlCommand::Resume => {
+                self.set_paused(false).await;
+                if self.auth_failure.take().is_some() {
+                    // Failures recorded before the credentials were replaced are stale
+                    metrics::global().take_auth_failures();
+                    info!("{}", "🔐 Authentication pause cleared: a further 401/403 pauses again");
+                }
+                info!("{}", "▶️  Resumed by control command");
+                (Ok(serde_json::json!({ "paused": false })), true)
+            }
+            ControlCommand::ForceRotate => match held {
+                _ if self.state.paused => (Err(ControlError::Rejected("paused: resume before rotating".to_string())), false),
+                None => (Err(ControlError::Rejected("no position held".to_string())), false),
+                Some(symbol) => {
+                    self.force_rotate = true;
+                    (Ok(serde_json::json!({ "rotating": symbol })), true)
+                }
+            },
+            ControlCommand::CloseNow => {
+                // Otherwise the next cycle would open a new position right away
+                self.set_paused(true).await;
+                info!("{}", "🎛️  Closing on control command; opens paused until resume");
+                match self.close_current_position().await {
+                    Ok(()) => (Ok(serde_json::json!({ "closed": held, "paused": true })), false),
+                    Err(e) => (Err(ControlError::Rejected(format!("close failed: {}", e))), false),
+                }
+            }
+            ControlCommand::Reduce { fraction } => match self.reduce_current_position(fraction).await {
+                Ok(()) => {
+                    let notional = self.state.current_position.as_ref().map(|p| p.target_notional_usd);
+                    (Ok(serde_json::json!({ "reduced": held, "fraction": fraction, "notional_usd": notional })), false)
                 }
+                Err(e) => (Err(ControlError::Rejected(format!("reduce failed: {}", e))), false),
+            },
+            ControlCommand::SetMaxSize { max_position_size_usd } => {
+                let previous = self.config.trading.max_position_size_usd;
+                self.config.trading.max_position_size_usd = max_position_size_usd;
+                if let Err(e) = self.config.validate() {
+                    self.config.trading.max_position_size_usd = previous;
+                    return (Err(ControlError::BadRequest(e.to_string())), false);
+                }
+                self.opportunity_finder.set_max_position_size_usd(max_position_size_usd);
+                info!("🎛️  Max position size changed by control command: ${:.0} -> ${:.0}", previous, max_position_size_usd);
+                (Ok(serde_json::json!({ "max_position_size_usd": max_position_size_usd, "previous_usd": previous })), false)
+            }
+            ControlCommand::SetApiCapture { enabled } => {
+                let capture = api_capture::global();
+                capture.set_enabled(enabled);
+                info!("🎛️  API capture {} by control command ({})", if enabled { "enabled" } else { "disabled" }, capture.path());
+                (Ok(serde_json::json!({ "api_capture": enabled, "path": capture.path() })), false)
+            }
+            ControlCommand::Scan => (Ok(serde_json::json!({ "cycle_started": true })), true),
+        }
+    }
+
+    /// Start over from what the venues and the intent log say: a failed cycle may have
+    /// left an execution half-done or in-memory state out of date
+    async fn restart_after_incident(&mut self) {
+        info!("{}", "🔄 Watchdog restarting the bot loop with fresh reconciliation...");
+        self.requeued_symbol = None;
+        self.entry_delay = None;
+        match self.state_store.blocking(BotState::load).await {
+            Ok(state) => self.state = state,
+            Err(e) => warn!("Failed to reload state from {}: {}. Keeping in-memory state.", self.state_store, e),
+        }
+        self.interrupted_executions.clear();
+        if let Err(e) = self.replay_intent_log() {
+            warn!("Failed to replay intent log {}: {}", self.intents.path(), e);
+        }
+        if let Err(e) = self.reconcile_state().await {
+            warn!("Reconciliation after restart failed: {}. The next cycle retries it.", e);
+        }
+    }
+
+    /// One monitoring cycle; returns how many seconds to wait before the next one
+    async fn run_cycle(&mut self, extended_api_key: Option<String>) -> Result<u64, BotError> {
+        self.cycle_timer = CycleTimer::start(&self.config.cycle_budget);
+        let phase_start = Instant::now();
+        self.touch_heartbeat();
+        self.decision = CycleDecision::new(
+            self.heartbeat.last_loop_at,
+            self.state.current_position.as_ref().map(|p| p.symbol.clone()),
+        );
+        self.reload_pacifica_credentials();
+
+        // Track clock drift over long runs
+        if let Err(e) = self.sync_clock().await {
+            warn!("Clock re-sync failed: {}", e);
+        }
+
+        // Reconcile any stale state before acting
+        let reconciled = self.reconcile_state().await;
+        self.cycle_timer.record(Phase::Reconcile, phase_start);
+        if let Err(e) = reconciled {
+            warn!("Network error during state reconciliation: {}. Skipping cycle to prevent unsafe actions.", e);
+            self.decision.skip(format!("state reconciliation failed: {}", e));
+            return Ok(60); // Wait 1 minute before retrying
+        }
+        self.heartbeat.last_reconcile_at = Some(self.heartbeat.last_loop_at);
+
+        let phase_start = Instant::now();
+        // CRITICAL: Check for imbalance immediately after reconciliation
+        if self.is_imbalanced() {
+            error!("{}", "⚠️  CRITICAL: Position imbalance detected! One leg is missing.");
+            info!("{}", "🚨 Initiating EMERGENCY CLOSE of remaining leg to preserve capital...");
+            
+            self.decision.action = DecisionAction::CloseImbalanced;
+            if let Err(e) = self.close_current_position().await {
+                error!("{} {}", "❌ Failed to close imbalanced position:", e);
+                self.decision.skip(format!("closing the imbalanced position failed: {}", e));
+                info!("{}", "Will retry immediately...");
+                // Don't sleep long if we are in a critical state
+                return Ok(5);
             } else {
-                // Position active, just monitoring
-                if let Some(hours) = self.state.hours_until_rotation(self.config.trading.hold_time_hours) {
-                    info!("{} {} {}",
-                        "⏳ Position active,",
-                        format!("{:.1}", hours),
-                        "hours until rotation");
+                info!("{}", "✅ Emergency close successful. State is now clean.");
+                // Continue to normal loop to potentially re-open if opportunity exists
+            }
+        }
+
+        // If state is empty but live positions exist, refuse to open to prevent duplicates
+        if self.state.current_position.is_none() {
+            match self.recover_state_if_untracked().await {
+                Ok(RecoveryOutcome::Recovered) => {
+                    info!("Recovered bot state from live positions. Monitoring only.");
+                    self.decision.skip("recovered bot state from live positions");
+                }
+                Ok(RecoveryOutcome::Blocked(details)) => {
+                    error!("⚠️  Live positions detected while bot state is empty. Skipping open/rotation to avoid duplicate exposure. {}", details);
+                    self.decision.skip(format!("untracked live positions: {}", details));
+                    info!("Resolve by closing manually (or run the emergency_exit binary) or reconstruct bot_state.json, then restart.");
+                    return Ok(MONITORING_INTERVAL_MINUTES * 60);
+                }
+                Err(e) => {
+                    warn!("Could not verify live positions (skipping cycle to avoid duplicates): {}", e);
+                    self.decision.skip(format!("could not verify live positions: {}", e));
+                    return Ok(60);
+                }
+                Ok(RecoveryOutcome::NoAction) => {}
+            }
+        }
+
+        // Exit early if the held market was blacklisted or restricted since opening
+        match self.close_if_restricted().await {
+            Ok(true) => info!("{}", "✅ Restricted position closed. Looking for a new opportunity."),
+            Ok(false) => {}
+            Err(e) => warn!("Failed to check/close restricted symbol: {}", e),
+        }
+
+        // Pull actual funding payments for the held position
+        if let Err(e) = self.refresh_realized_funding().await {
+            warn!("Failed to refresh realized funding: {}", e);
+        }
+        if let Err(e) = self.sample_funding_rates().await {
+            warn!("Failed to sample funding rates: {}", e);
+        }
+        self.cycle_timer.record(Phase::Reconcile, phase_start);
+
+        // Display status (skipped when the cycle is over its time budget)
+        if self.cycle_timer.allows("status table") {
+            let phase_start = Instant::now();
+            self.display_status().await?;
+            self.cycle_timer.record(Phase::Display, phase_start);
+        }
+
+        // Always scan and display opportunities at start of each cycle
+        info!("");
+        let degradation = self.api_degradation();
+        let scan_result = if degradation >= Degradation::SkipScans {
+            warn!("{}", "⏸️  API error budget exceeded: skipping this cycle's scan");
+            self.decision.skip("API error budget exceeded: scan skipped");
+            None
+        } else {
+            info!("{}", "🔍 Scanning current market opportunities...");
+            let phase_start = Instant::now();
+            let scanned = self.opportunity_finder.scan(extended_api_key.clone()).await;
+            self.cycle_timer.record(Phase::Scan, phase_start);
+            match scanned {
+                Ok(scan_result) => {
+                    if self.cycle_timer.allows("scan table") {
+                        let phase_start = Instant::now();
+                        scan_result.display_summary(&self.config);
+                        self.cycle_timer.record(Phase::Display, phase_start);
+                    }
+                    self.heartbeat.last_scan_at = Some(scan_result.scanned_at);
+                    self.decision.record_scan(&scan_result);
+                    Some(scan_result)
+                }
+                Err(e) => {
+                    warn!("{}", "Failed to scan opportunities");
+                    self.decision.skip(format!("scan failed: {}", e));
+                    None
+                }
+            }
+        };
+        info!("");
+        self.touch_heartbeat();
+
+        // Quotes of a dead stream are already too old to be used; say why opens wait
+        self.cycle_timer.begin(Phase::Decision);
+        let stale_streams = self.stale_streams();
+        if let Some(stale) = &stale_streams {
+            warn!("⚠️  {}: streamed quotes are ignored and opens wait until it recovers", stale);
+        }
+
+        // Rotate early when a better opportunity outweighs the cost of switching
+        let early_rotation = scan_result.as_ref().and_then(|scan| self.early_rotation_gain(scan));
+
+        // Check if we need to rotate
+        let hold_expired = self.state.should_rotate(self.config.trading.hold_time_hours);
+        let forced = std::mem::take(&mut self.force_rotate) && self.state.current_position.is_some();
+        let wants_rotation = hold_expired || early_rotation.is_some() || forced;
+        if wants_rotation && self.state.paused {
+            info!("{}", "⏸️  Paused by control command: postponing rotation");
+            self.decision.skip("rotation postponed: paused by control command");
+        } else if wants_rotation && degradation >= Degradation::DelayOpens {
+            // Closing now would leave the bot flat until error rates recover
+            warn!("{}", "⏸️  API error budget exceeded: postponing rotation");
+            self.decision.skip("rotation postponed: API error budget exceeded");
+        } else if wants_rotation && stale_streams.is_some() {
+            warn!("{}", "⏸️  Orderbook stream down: postponing rotation");
+            self.decision.skip("rotation postponed: orderbook stream down");
+        } else if wants_rotation {
+            // Rotating into the held symbol and direction would only pay fees
+            let keep = !forced && early_rotation.is_none()
+                && scan_result.as_ref().is_some_and(|scan| self.strategy_reselects_held(scan));
+            if keep {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)?
+                    .as_secs();
+                self.state.restart_hold(now);
+                self.save_state().await?;
+                if let Some(held) = &self.state.current_position {
+                    self.decision.action = DecisionAction::Keep { symbol: held.symbol.clone() };
                 }
+                info!("♻️  {} is still the best opportunity: keeping the position for another {} hours",
+                    self.state.current_position.as_ref().map_or("", |p| p.symbol.as_str()),
+                    self.config.trading.hold_time_hours);
+                return Ok(MONITORING_INTERVAL_MINUTES * 60);
+            }
+
+            match &early_rotation {
+                _ if forced && !hold_expired => info!("{}", "🎛️  Rotating on control command"),
+                Some((symbol, gain)) if !hold_expired => info!(
+                    "⚡ Rotating early into {}: +{:.2}% APR after switching costs",
+                    symbol, gain
+                ),
+                _ => info!("{} {} {}",
+                    "⏰ Position has been open for",
+                    self.config.trading.hold_time_hours,
+                    "hours, rotating..."),
+            }
+
+            // Close current position
+            let from = self.state.current_position.as_ref().map(|p| p.symbol.clone());
+            if let Err(e) = self.close_current_position().await {
+                error!("{} {}", "Failed to close position:", e);
+                info!("{}", "Will retry next cycle.");
+                self.decision.skip(format!("closing for rotation failed: {}", e));
+                return Ok(MONITORING_INTERVAL_MINUTES * 60);
+            }
+            if let Some(from) = from {
+                self.decision.action = DecisionAction::Rotate { from, to: None };
+            }
+
+            // Wait a bit before opening new position
+            sleep(Duration::from_secs(5)).await;
+
+            // Open new position
+            if let Err(e) = self.open_best_opportunity(extended_api_key.clone()).await {
+                error!("{} {}", "Failed to open new position:", e);
+                self.decision.skip(format!("open failed: {}", e));
+                info!("{}", "Will retry next cycle.");
             }
+        } else if self.state.current_position.is_none() {
+            // No position, try to open one
+            info!("{}", "📭 No active position, looking for opportunity...");
+
+            if let Err(e) = self.open_best_opportunity(extended_api_key.clone()).await {
+                error!("{} {}", "Failed to open position:", e);
+                self.decision.skip(format!("open failed: {}", e));
+                info!("{}", "Will retry next cycle.");
+            }
+        } else {
+            // Position active, just monitoring
+            if let Some(hours) = self.state.hours_until_rotation(self.config.trading.hold_time_hours) {
+                info!("{} {} {}",
+                    "⏳ Position active,",
+                    format!("{:.1}", hours),
+                    "hours until rotation");
+            }
+        }
 
-            // Wait for next monitoring cycle (interruptible by Ctrl+C)
+        // Re-queued opens retry sooner than the next monitoring cycle
+        Ok(if let Some(symbol) = self.requeued_symbol.take() {
+            info!("🔁 {} re-queued after the spread check, retrying in {} seconds...",
+                symbol, SPREAD_RECHECK_RETRY_SECS);
+            SPREAD_RECHECK_RETRY_SECS
+        } else if let Some(delay) = self.entry_delay.as_ref().filter(|_| self.state.current_position.is_none()) {
+            info!("⏳ Rechecking entry timing for {} in {} seconds...",
+                delay.symbol, self.config.entry_timing.recheck_seconds);
+            self.config.entry_timing.recheck_seconds
+        } else {
             info!("{} {} {}",
                 "😴 Sleeping for",
                 MONITORING_INTERVAL_MINUTES,
                 "minutes...");
-            let wait = sleep(Duration::from_secs(MONITORING_INTERVAL_MINUTES * 60));
-            tokio::pin!(wait);
-            tokio::select! {
-                _ = &mut wait => {},
-                _ = tokio::signal::ctrl_c() => {
-                    info!("{}", "");
-                    info!("{}", "🛑 Shutdown signal received during sleep. Stopping gracefully.");
-                    info!("{}", "ℹ️  Open positions (if any) will remain open.");
-                    return Ok(());
-                }
-            }
+            MONITORING_INTERVAL_MINUTES * 60
+        })
+    }
+}
+
+/// Next command from the control server; never resolves when it is not running
+async fn next_control_request(control: &mut Option<ControlServer>) -> Option<ControlRequest> {
+    match control {
+        Some(server) => server.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+impl Drop for FundingBot {
+    fn drop(&mut self) {
+        if let Some(task) = self.order_timeout_task.take() {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn closed_position(symbol: &str, entry_net_apr: Option<f64>) -> DeltaNeutralPosition {
+        DeltaNeutralPosition {
+            position_id: String::new(),
+            symbol: symbol.to_string(),
+            extended_position: None,
+            pacifica_position: None,
+            opened_at: 0,
+            target_notional_usd: 1000.0,
+            realized_funding_extended_usd: 1.5,
+            realized_funding_pacifica_usd: 0.5,
+            entry_net_apr,
+            extended_entry_price: None,
+            pacifica_entry_price: None,
+            entry_basis_pct: None,
+            spread_capture: None,
+            captured_basis_usd: None,
+            open_execution: None,
+            extended_vault_id: None,
+            direction: None,
+            reductions: 0,
+            protective_generation: None,
+            hold_restarted_at: None,
+            funding_average: FundingRateAverage::default(),
+            extended_market: None,
+            pacifica_market: None,
+            multipliers: Default::default(),
         }
     }
+
+    #[test]
+    fn test_rotation_cooldown() {
+        let mut state = BotState::new();
+        assert_eq!(state.rotation_cooldown_remaining_secs(60, 1_000), 0);
+
+        state.record_close(&closed_position("BTC", Some(30.0)), 1_000);
+        assert_eq!(state.total_realized_funding_usd, 2.0);
+        assert_eq!(state.rotation_cooldown_remaining_secs(60, 1_000 + 600), 3_000);
+        assert_eq!(state.rotation_cooldown_remaining_secs(60, 1_000 + 3_600), 0);
+        assert_eq!(state.rotation_cooldown_remaining_secs(0, 1_000), 0);
+    }
+
+    #[test]
+    fn test_restart_hold() {
+        let mut state = BotState::new();
+        state.current_position = Some(closed_position("BTC", Some(30.0)));
+        assert!(state.should_rotate(48));
+
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+        state.restart_hold(now);
+        assert!(!state.should_rotate(48));
+        assert!(state.hours_until_rotation(48).unwrap() > 47.9);
+        assert_eq!(state.current_position.as_ref().unwrap().opened_at, 0);
+    }
+
+    #[test]
+    fn test_churn_reentry() {
+        let mut state = BotState::new();
+        assert!(!state.is_churn_reentry("BTC", 10.0));
+
+        state.record_close(&closed_position("BTC", Some(30.0)), 0);
+        assert!(state.is_churn_reentry("BTC", 25.0));
+        assert!(!state.is_churn_reentry("BTC", 35.0));
+        assert!(!state.is_churn_reentry("ETH", 5.0));
+
+        // Unknown entry APR (e.g. recovered position) never blocks
+        state.record_close(&closed_position("BTC", None), 0);
+        assert!(!state.is_churn_reentry("BTC", 1.0));
+    }
+
+    #[test]
+    fn test_state_migration_from_unversioned_file() {
+        // Format written before versioning (and before position IDs and funding tracking)
+        let legacy = r#"{
+            "current_position": {
+                "symbol": "BTC",
+                "extended_position": null,
+                "pacifica_position": null,
+                "opened_at": 1700000000,
+                "target_notional_usd": 500.0
+            },
+            "last_rotation_time": 1700000000,
+            "total_rotations": 7
+        }"#;
+        let (state, stored_version) = BotState::from_json(legacy).unwrap();
+        assert_eq!(stored_version, 0);
+        assert_eq!(state.version, BOT_STATE_VERSION);
+        assert_eq!(state.total_rotations, 7);
+        let position = state.current_position.as_ref().unwrap();
+        assert!(uuid::Uuid::parse_str(&position.position_id).is_ok());
+        assert_eq!(position.realized_funding_usd(), 0.0);
+
+        // Current files round-trip unchanged
+        let saved = serde_json::to_string(&state).unwrap();
+        let (reloaded, stored_version) = BotState::from_json(&saved).unwrap();
+        assert_eq!(stored_version, BOT_STATE_VERSION);
+        assert_eq!(reloaded.current_position.unwrap().position_id, position.position_id);
+    }
+
+    #[test]
+    fn test_state_from_newer_build_is_rejected() {
+        let newer = format!(r#"{{"version": {}, "current_position": null, "total_rotations": 0}}"#, BOT_STATE_VERSION + 1);
+        assert!(matches!(BotState::from_json(&newer), Err(BotError::Config(_))));
+    }
 }
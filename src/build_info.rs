@@ -0,0 +1,75 @@
+//! Build and configuration identity
+//!
+//! `BuildInfo` names the code and parameters a bot ran with: the crate version, the git
+//! version embedded at build time (`git describe --always --dirty --tags`, or the
+//! `GIT_VERSION` environment variable of the build; "unknown" outside a checkout) and a
+//! hash of the loaded config. The config hash covers every setting with defaults filled
+//! in, so two files that differ only in layout or in spelling out a default hash the
+//! same. The bot logs it at startup and writes it into the bot state and every equity
+//! history line, so a post-mortem can tie behavior to exact code and parameters.
+use crate::opportunity::Config;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Hex digits of the SHA-256 kept in `config_hash`
+const CONFIG_HASH_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub package_version: String,
+    pub git_version: String,
+    pub config_hash: String,
+}
+
+impl BuildInfo {
+    /// This binary with `config`
+    pub fn new(config: &Config) -> Self {
+        Self {
+            package_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_version: option_env!("GIT_VERSION").unwrap_or("unknown").to_string(),
+            config_hash: config_hash(config),
+        }
+    }
+}
+
+impl fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{} ({}), config {}", self.package_version, self.git_version, self.config_hash)
+    }
+}
+
+/// Truncated SHA-256 of the config serialized with sorted keys
+pub fn config_hash(config: &Config) -> String {
+    // A `Value` keeps object keys sorted, so field and map order do not matter
+    let canonical = serde_json::to_value(config)
+        .map(|value| value.to_string())
+        .unwrap_or_default();
+    let digest = hex::encode(Sha256::digest(canonical.as_bytes()));
+    digest[..CONFIG_HASH_LEN].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_hash_tracks_settings_not_layout() {
+        let config = Config::default_config();
+        let info = BuildInfo::new(&config);
+        assert_eq!(info.config_hash.len(), CONFIG_HASH_LEN);
+        assert_eq!(info.package_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.config_hash, config_hash(&config.clone()));
+
+        let mut changed = config.clone();
+        changed.trading.hold_time_hours += 1;
+        assert_ne!(config_hash(&changed), info.config_hash);
+
+        // The same settings reparsed from JSON with reordered fields hash the same
+        let mut value = serde_json::to_value(&config).unwrap();
+        let reordered: serde_json::Map<String, serde_json::Value> =
+            std::mem::take(value.as_object_mut().unwrap()).into_iter().rev().collect();
+        let reparsed: Config = serde_json::from_value(serde_json::Value::Object(reordered)).unwrap();
+        assert_eq!(config_hash(&reparsed), info.config_hash);
+    }
+}
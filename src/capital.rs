@@ -0,0 +1,206 @@
+//! Capital balance monitoring between Extended and Pacifica
+//!
+//! A delta-neutral position is sized by the venue with the least free collateral,
+//! so idle capital on the other venue is wasted. This module detects that imbalance
+//! and hands a suggested transfer to a pluggable `Hook<TransferSuggestion>` (alerting by default).
+//!
+//! It also caps new opens by margin utilization: each venue account reports the margin
+//! used by every position it holds, netted by the venue, so the bot's held pair,
+//! a pair being rotated out and anything else trading on the account all count.
+//! `capital.max_margin_utilization_pct` bounds the share of either account's equity
+//! that may be committed as margin once the new pair is added.
+use crate::notify::{Hook, HookFuture};
+use crate::opportunity::CapitalConfig;
+use tracing::warn;
+
+/// Trading venue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Venue {
+    Extended,
+    Pacifica,
+}
+
+impl std::fmt::Display for Venue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Venue::Extended => write!(f, "Extended"),
+            Venue::Pacifica => write!(f, "Pacifica"),
+        }
+    }
+}
+
+/// Free collateral on both venues at a point in time
+#[derive(Debug, Clone, Copy)]
+pub struct CapitalSnapshot {
+    pub extended_free_usd: f64,
+    pub pacifica_free_usd: f64,
+}
+
+/// Suggested transfer that would equalize free collateral
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct TransferSuggestion {
+    pub from: Venue,
+    pub to: Venue,
+    pub amount_usd: f64,
+    /// Imbalance that triggered the suggestion, as % of total free collateral
+    pub imbalance_pct: f64,
+}
+
+impl std::fmt::Display for TransferSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "move ${:.2} from {} to {} (imbalance {:.1}%)",
+            self.amount_usd, self.from, self.to, self.imbalance_pct
+        )
+    }
+}
+
+impl CapitalSnapshot {
+    pub fn total_free_usd(&self) -> f64 {
+        self.extended_free_usd + self.pacifica_free_usd
+    }
+
+    /// Absolute difference between venues as % of total free collateral
+    pub fn imbalance_pct(&self) -> f64 {
+        let total = self.total_free_usd();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        (self.extended_free_usd - self.pacifica_free_usd).abs() / total * 100.0
+    }
+
+    /// Venue currently limiting position size
+    pub fn limiting_venue(&self) -> Venue {
+        if self.extended_free_usd <= self.pacifica_free_usd {
+            Venue::Extended
+        } else {
+            Venue::Pacifica
+        }
+    }
+
+    /// Suggest a transfer of half the difference when the imbalance exceeds the threshold
+    pub fn suggest_transfer(&self, config: &CapitalConfig) -> Option<TransferSuggestion> {
+        let imbalance_pct = self.imbalance_pct();
+        if imbalance_pct < config.rebalance_threshold_pct {
+            return None;
+        }
+
+        let amount_usd = (self.extended_free_usd - self.pacifica_free_usd).abs() / 2.0;
+        if amount_usd < config.min_transfer_usd {
+            return None;
+        }
+
+        let to = self.limiting_venue();
+        let from = match to {
+            Venue::Extended => Venue::Pacifica,
+            Venue::Pacifica => Venue::Extended,
+        };
+
+        Some(TransferSuggestion {
+            from,
+            to,
+            amount_usd,
+            imbalance_pct,
+        })
+    }
+}
+
+/// Margin committed on one venue account across all of its positions
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct VenueMargin {
+    pub venue: Venue,
+    pub equity_usd: f64,
+    pub margin_used_usd: f64,
+}
+
+impl VenueMargin {
+    /// Margin used, in % of equity; 100% once the equity is gone
+    pub fn utilization_pct(&self) -> f64 {
+        if self.equity_usd <= 0.0 {
+            return 100.0;
+        }
+        self.margin_used_usd / self.equity_usd * 100.0
+    }
+
+    /// Margin a new leg may take before utilization reaches `max_utilization_pct`
+    pub fn headroom_usd(&self, max_utilization_pct: f64) -> f64 {
+        (self.equity_usd * max_utilization_pct / 100.0 - self.margin_used_usd).max(0.0)
+    }
+}
+
+/// Largest notional of a new pair at `leverage` that keeps every venue in `margins` at
+/// or below `max_utilization_pct`; unlimited when the cap is off (0)
+pub fn max_pair_notional_usd(margins: &[VenueMargin], leverage: u32, max_utilization_pct: f64) -> f64 {
+    if max_utilization_pct <= 0.0 {
+        return f64::INFINITY;
+    }
+    margins
+        .iter()
+        .map(|m| m.headroom_usd(max_utilization_pct) * f64::from(leverage.max(1)))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Default rebalance hook: log the suggested transfer for a human to act on. Replace it
+/// to wire automated withdraw/deposit flows.
+pub struct LogAlertHook;
+
+impl Hook<TransferSuggestion> for LogAlertHook {
+    fn notify<'a>(&'a self, suggestion: &'a TransferSuggestion) -> HookFuture<'a> {
+        Box::pin(async move {
+            warn!("💱 Capital imbalance detected: {}", suggestion);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> CapitalConfig {
+        CapitalConfig {
+            rebalance_threshold_pct: 20.0,
+            min_transfer_usd: 50.0,
+            ..CapitalConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_balanced_capital_has_no_suggestion() {
+        let snap = CapitalSnapshot { extended_free_usd: 1000.0, pacifica_free_usd: 900.0 };
+        assert!(snap.imbalance_pct() < 20.0);
+        assert!(snap.suggest_transfer(&config()).is_none());
+    }
+
+    #[test]
+    fn test_suggests_half_difference_to_limiting_venue() {
+        let snap = CapitalSnapshot { extended_free_usd: 400.0, pacifica_free_usd: 1600.0 };
+        let suggestion = snap.suggest_transfer(&config()).unwrap();
+        assert_eq!(suggestion.from, Venue::Pacifica);
+        assert_eq!(suggestion.to, Venue::Extended);
+        assert_eq!(suggestion.amount_usd, 600.0);
+        assert_eq!(suggestion.imbalance_pct, 60.0);
+    }
+
+    #[test]
+    fn test_small_transfers_are_ignored() {
+        let snap = CapitalSnapshot { extended_free_usd: 10.0, pacifica_free_usd: 90.0 };
+        assert!(snap.suggest_transfer(&config()).is_none());
+    }
+
+    #[test]
+    fn test_margin_utilization_caps_new_pairs() {
+        let extended = VenueMargin { venue: Venue::Extended, equity_usd: 1_000.0, margin_used_usd: 200.0 };
+        let pacifica = VenueMargin { venue: Venue::Pacifica, equity_usd: 1_000.0, margin_used_usd: 500.0 };
+        assert_eq!(extended.utilization_pct(), 20.0);
+        assert_eq!(max_pair_notional_usd(&[extended, pacifica], 5, 0.0), f64::INFINITY);
+        // 60% of $1000 leaves $400 of margin on Extended but $100 on Pacifica: $500 at 5x
+        assert_eq!(max_pair_notional_usd(&[extended, pacifica], 5, 60.0), 500.0);
+        // Pacifica is already past the cap
+        assert_eq!(max_pair_notional_usd(&[extended, pacifica], 5, 40.0), 0.0);
+        let wiped = VenueMargin { equity_usd: 0.0, ..extended };
+        assert_eq!(wiped.utilization_pct(), 100.0);
+        assert_eq!(max_pair_notional_usd(&[wiped], 5, 80.0), 0.0);
+    }
+}
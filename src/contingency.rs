@@ -0,0 +1,225 @@
+//! Bounded-loss contingency for a failed rollback
+//!
+//! When the hedge leg of an open fails, the leading leg is rolled back. If that rollback
+//! fails too, one leg stays open and unhedged. With `contingency.enabled` the bot then
+//! hands the exposed leg to a `BackupHedge` (an order on a third venue, plugged in with
+//! `FundingBot::set_backup_hedge`; none is built in) and raises an `ExposureAlert` at
+//! critical urgency through every `Hook<ExposureAlert>` (a JSON POST to
+//! `contingency.webhook_url` when set), whether or not the backup hedge succeeded. Without a backup hedge the
+//! alert alone goes out. The open still fails as non-recoverable: the exposed leg and
+//! any backup hedge need to be unwound by hand.
+use crate::capital::Venue;
+use crate::error::BotError;
+use crate::notify::{Hook, WebhookSink};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use tracing::{error, warn};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContingencyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// POST each exposure alert as JSON to this URL (Slack/Discord-compatible)
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+/// Leg left open after its rollback failed
+#[derive(Debug, Clone, Serialize)]
+pub struct ExposedLeg {
+    pub position_id: String,
+    pub symbol: String,
+    pub venue: Venue,
+    pub is_long: bool,
+    /// Base size
+    pub size: f64,
+    /// Price the open was sized at
+    pub price: f64,
+    /// Why the rollback failed
+    pub error: String,
+}
+
+impl ExposedLeg {
+    pub fn notional_usd(&self) -> f64 {
+        self.size * self.price
+    }
+}
+
+impl fmt::Display for ExposedLeg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {} {:.6} {} (${:.2})",
+            self.venue,
+            if self.is_long { "long" } else { "short" },
+            self.size,
+            self.symbol,
+            self.notional_usd()
+        )
+    }
+}
+
+/// What the backup hedge did with an exposed leg
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum HedgeOutcome {
+    NotConfigured,
+    Hedged { venue: String, order: String },
+    Failed { venue: String, error: String },
+}
+
+impl fmt::Display for HedgeOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HedgeOutcome::NotConfigured => write!(f, "no backup hedge configured"),
+            HedgeOutcome::Hedged { venue, order } => write!(f, "hedged on {} (order {})", venue, order),
+            HedgeOutcome::Failed { venue, error } => write!(f, "backup hedge on {} failed: {}", venue, error),
+        }
+    }
+}
+
+/// Critical alert for a leg left unhedged by a failed rollback
+#[derive(Debug, Clone, Serialize)]
+pub struct ExposureAlert {
+    pub urgency: &'static str,
+    pub leg: ExposedLeg,
+    pub hedge: HedgeOutcome,
+}
+
+impl fmt::Display for ExposureAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CRITICAL: {} left open after a failed rollback ({}); {}. Unwind manually",
+            self.leg, self.leg.error, self.hedge
+        )
+    }
+}
+
+pub type HedgeFuture<'a> = Pin<Box<dyn Future<Output = Result<String, BotError>> + Send + 'a>>;
+
+/// Offsets an exposed leg on a backup venue. Opens the opposite side of `leg` for the
+/// same size and returns a reference to the order.
+pub trait BackupHedge: Send + Sync {
+    fn venue(&self) -> &str;
+
+    fn hedge<'a>(&'a self, leg: &'a ExposedLeg) -> HedgeFuture<'a>;
+}
+
+
+/// Backup hedge and alert receivers run when a rollback fails
+#[derive(Default)]
+pub struct Contingency {
+    hedge: Option<Box<dyn BackupHedge>>,
+    hooks: Vec<Box<dyn Hook<ExposureAlert>>>,
+}
+
+impl Contingency {
+    /// Alert receivers from `config`, without a backup hedge
+    pub fn from_config(config: &ContingencyConfig) -> Self {
+        let mut contingency = Self::default();
+        if let Some(url) = config.webhook_url.as_ref().filter(|u| !u.is_empty()) {
+            contingency.add_hook(Box::new(WebhookSink::new(url.clone(), "exposure", |alert: &ExposureAlert| format!("🆘 {}", alert))));
+        }
+        contingency
+    }
+
+    pub fn set_backup_hedge(&mut self, hedge: Box<dyn BackupHedge>) {
+        self.hedge = Some(hedge);
+    }
+
+    pub fn add_hook(&mut self, hook: Box<dyn Hook<ExposureAlert>>) {
+        self.hooks.push(hook);
+    }
+
+    /// Hedge `leg` on the backup venue if one is set, then alert every hook
+    pub async fn handle(&self, leg: ExposedLeg) -> ExposureAlert {
+        let hedge = match &self.hedge {
+            None => HedgeOutcome::NotConfigured,
+            Some(hedge) => match hedge.hedge(&leg).await {
+                Ok(order) => HedgeOutcome::Hedged { venue: hedge.venue().to_string(), order },
+                Err(e) => HedgeOutcome::Failed { venue: hedge.venue().to_string(), error: e.to_string() },
+            },
+        };
+        let alert = ExposureAlert { urgency: "critical", leg, hedge };
+
+        error!("🆘 {}", alert);
+        match serde_json::to_string(&serde_json::json!({ "event": "exposure_alert", "alert": &alert })) {
+            Ok(line) => error!("{}", line),
+            Err(e) => warn!("Failed to serialize exposure alert: {}", e),
+        }
+        for hook in &self.hooks {
+            if let Err(e) = hook.notify(&alert).await {
+                warn!("Exposure hook failed: {}", e);
+            }
+        }
+        alert
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::HookFuture;
+    use std::sync::{Arc, Mutex};
+
+    struct FakeHedge(Result<String, String>);
+
+    impl BackupHedge for FakeHedge {
+        fn venue(&self) -> &str {
+            "backup"
+        }
+
+        fn hedge<'a>(&'a self, _leg: &'a ExposedLeg) -> HedgeFuture<'a> {
+            Box::pin(async move { self.0.clone().map_err(|e| BotError::execution(e, false)) })
+        }
+    }
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Hook<ExposureAlert> for Recorder {
+        fn notify<'a>(&'a self, alert: &'a ExposureAlert) -> HookFuture<'a> {
+            Box::pin(async move {
+                self.0.lock().unwrap().push(alert.to_string());
+                Ok(())
+            })
+        }
+    }
+
+    fn leg() -> ExposedLeg {
+        ExposedLeg {
+            position_id: "p".to_string(),
+            symbol: "BTC".to_string(),
+            venue: Venue::Extended,
+            is_long: true,
+            size: 0.01,
+            price: 100_000.0,
+            error: "rate limited".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_contingency_hedges_and_always_alerts() {
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let mut contingency = Contingency::default();
+        contingency.add_hook(Box::new(Recorder(Arc::clone(&alerts))));
+
+        let alert = contingency.handle(leg()).await;
+        assert!(matches!(alert.hedge, HedgeOutcome::NotConfigured));
+
+        contingency.set_backup_hedge(Box::new(FakeHedge(Ok("42".to_string()))));
+        let alert = contingency.handle(leg()).await;
+        assert!(matches!(&alert.hedge, HedgeOutcome::Hedged { order, .. } if order == "42"));
+
+        contingency.set_backup_hedge(Box::new(FakeHedge(Err("no margin".to_string()))));
+        let alert = contingency.handle(leg()).await;
+        assert!(matches!(&alert.hedge, HedgeOutcome::Failed { error, .. } if error.contains("no margin")));
+
+        let alerts = alerts.lock().unwrap();
+        assert_eq!(alerts.len(), 3);
+        assert!(alerts[1].contains("Extended long 0.010000 BTC ($1000.00)"));
+        assert!(alerts[1].contains("hedged on backup (order 42)"));
+    }
+}
@@ -0,0 +1,461 @@
+//! Runtime control server
+//!
+//! With `control.enabled`, the bot listens on `control.bind_address` for small HTTP
+//! requests with JSON replies:
+//!
+//!   GET  /status                      status snapshot, plus whether opens are paused
+//!   GET  /risk                        stress test of the held position (see `stress`)
+//!   POST /pause                       stop opening and rotating; the held position stays
+//!   POST /resume                      allow opens again and run a cycle now
+//!   POST /rotate                      close the held position and open the best one now
+//!   POST /close                       close the held position now and pause
+//!   POST /reduce    {"fraction": 0.5}  close that fraction of both legs now
+//!   POST /scan                        run a cycle (scan, then act on it) now
+//!   POST /max-size  {"max_position_size_usd": 500}
+//!                                     change trading.max_position_size_usd until restart
+//!   POST /capture   {"enabled": true}  start or stop raw API capture (see `api_capture`)
+//!
+//! When `CONTROL_TOKEN` is set, requests must carry `Authorization: Bearer <token>`;
+//! without it the server only binds loopback addresses. The request head is read
+//! before the token is checked, so it is bounded for anyone who can connect: lines of
+//! `MAX_LINE_BYTES`, `MAX_HEADERS` headers, `REQUEST_TIMEOUT_SECS` to send it, and
+//! `MAX_CONNECTIONS` connections at once. A pause is saved with the bot state and
+//! survives restarts.
+//! Commands are queued to the bot, which runs them between monitoring cycles (at once
+//! while it sleeps), so a reply can wait for the cycle in progress to finish.
+use crate::error::BotError;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot, Semaphore};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Largest request body accepted
+const MAX_BODY_BYTES: usize = 64 * 1024;
+/// Longest request line or header line accepted
+const MAX_LINE_BYTES: usize = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+/// Time a client has to send its whole request
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+/// Time a connection may stay open, reply included; commands wait for the cycle in progress
+const CONNECTION_TIMEOUT_SECS: u64 = 900;
+/// Connections served at once; more are closed unanswered
+const MAX_CONNECTIONS: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+}
+
+fn default_bind_address() -> String {
+    "127.0.0.1:8790".to_string()
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: default_bind_address(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlCommand {
+    Status,
+    RiskReport,
+    Pause,
+    Resume,
+    ForceRotate,
+    CloseNow,
+    Reduce { fraction: f64 },
+    SetMaxSize { max_position_size_usd: f64 },
+    SetApiCapture { enabled: bool },
+    Scan,
+}
+
+#[derive(Deserialize)]
+struct MaxSizeBody {
+    max_position_size_usd: f64,
+}
+
+#[derive(Deserialize)]
+struct CaptureBody {
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct ReduceBody {
+    fraction: f64,
+}
+
+impl ControlCommand {
+    /// Command for an HTTP method, path and body
+    pub fn from_request(method: &str, path: &str, body: &str) -> Result<Self, ControlError> {
+        match (method, path.trim_end_matches('/')) {
+            ("GET", "/status") => Ok(Self::Status),
+            ("GET", "/risk") => Ok(Self::RiskReport),
+            ("POST", "/pause") => Ok(Self::Pause),
+            ("POST", "/resume") => Ok(Self::Resume),
+            ("POST", "/rotate") => Ok(Self::ForceRotate),
+            ("POST", "/close") => Ok(Self::CloseNow),
+            ("POST", "/scan") => Ok(Self::Scan),
+            ("POST", "/reduce") => {
+                let body: ReduceBody = serde_json::from_str(body)
+                    .map_err(|e| ControlError::BadRequest(format!("expected {{\"fraction\": <0-1>}}: {}", e)))?;
+                Ok(Self::Reduce { fraction: body.fraction })
+            }
+            ("POST", "/max-size") => {
+                let body: MaxSizeBody = serde_json::from_str(body)
+                    .map_err(|e| ControlError::BadRequest(format!("expected {{\"max_position_size_usd\": <usd>}}: {}", e)))?;
+                Ok(Self::SetMaxSize { max_position_size_usd: body.max_position_size_usd })
+            }
+            ("POST", "/capture") => {
+                let body: CaptureBody = serde_json::from_str(body)
+                    .map_err(|e| ControlError::BadRequest(format!("expected {{\"enabled\": <true|false>}}: {}", e)))?;
+                Ok(Self::SetApiCapture { enabled: body.enabled })
+            }
+            (_, "/status" | "/risk" | "/pause" | "/resume" | "/rotate" | "/close" | "/reduce" | "/scan" | "/max-size" | "/capture") => {
+                Err(ControlError::MethodNotAllowed)
+            }
+            _ => Err(ControlError::NotFound),
+        }
+    }
+}
+
+/// Why a request was refused; each maps to an HTTP status
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlError {
+    BadRequest(String),
+    Unauthorized,
+    NotFound,
+    MethodNotAllowed,
+    /// Body over `MAX_BODY_BYTES`
+    PayloadTooLarge,
+    /// A head line over `MAX_LINE_BYTES`, or more than `MAX_HEADERS` headers
+    HeaderTooLarge,
+    /// The request was not sent within `REQUEST_TIMEOUT_SECS`
+    RequestTimeout,
+    /// The bot could not carry out the command
+    Rejected(String),
+    Unavailable,
+}
+
+impl ControlError {
+    fn status_line(&self) -> &'static str {
+        match self {
+            Self::BadRequest(_) => "400 Bad Request",
+            Self::Unauthorized => "401 Unauthorized",
+            Self::NotFound => "404 Not Found",
+            Self::MethodNotAllowed => "405 Method Not Allowed",
+            Self::PayloadTooLarge => "413 Payload Too Large",
+            Self::HeaderTooLarge => "431 Request Header Fields Too Large",
+            Self::RequestTimeout => "408 Request Timeout",
+            Self::Rejected(_) => "409 Conflict",
+            Self::Unavailable => "503 Service Unavailable",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Self::BadRequest(msg) | Self::Rejected(msg) => msg.clone(),
+            Self::Unauthorized => "missing or wrong bearer token".to_string(),
+            Self::NotFound => "unknown endpoint".to_string(),
+            Self::MethodNotAllowed => "wrong method for this endpoint".to_string(),
+            Self::PayloadTooLarge => format!("body larger than {} bytes", MAX_BODY_BYTES),
+            Self::HeaderTooLarge => format!("request line or header over {} bytes, or over {} headers", MAX_LINE_BYTES, MAX_HEADERS),
+            Self::RequestTimeout => format!("request not received within {}s", REQUEST_TIMEOUT_SECS),
+            Self::Unavailable => "bot is not accepting commands".to_string(),
+        }
+    }
+}
+
+/// A command waiting for the bot, with the channel its reply goes back on
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    pub reply: oneshot::Sender<Result<serde_json::Value, ControlError>>,
+}
+
+/// Listening control server; dropping it stops accepting connections
+pub struct ControlServer {
+    commands: mpsc::Receiver<ControlRequest>,
+    task: JoinHandle<()>,
+}
+
+impl ControlServer {
+    /// Bind `config.bind_address` and accept connections in the background. Fails for a
+    /// non-loopback address without `CONTROL_TOKEN`, where anyone who can reach the
+    /// server could close positions.
+    pub async fn start(config: &ControlConfig) -> Result<Self, BotError> {
+        let listener = TcpListener::bind(&config.bind_address)
+            .await
+            .map_err(|e| BotError::Config(format!("control server cannot bind {}: {}", config.bind_address, e)))?;
+        let token = std::env::var("CONTROL_TOKEN").ok().filter(|t| !t.is_empty());
+        if token.is_none() && !listener.local_addr().is_ok_and(|a| a.ip().is_loopback()) {
+            return Err(BotError::Config(format!(
+                "control server on {} needs CONTROL_TOKEN: set it, or bind a loopback address",
+                config.bind_address
+            )));
+        }
+        info!("🎛️  Control server listening on {}", config.bind_address);
+
+        let (sender, commands) = mpsc::channel(16);
+        let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+        let task = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, peer)) => {
+                        let Ok(permit) = Arc::clone(&connections).try_acquire_owned() else {
+                            warn!("Control server busy: closing connection from {}", peer);
+                            continue;
+                        };
+                        let sender = sender.clone();
+                        let token = token.clone();
+                        tokio::spawn(async move {
+                            let served = tokio::time::timeout(
+                                Duration::from_secs(CONNECTION_TIMEOUT_SECS),
+                                serve_connection(stream, sender, token.as_deref()),
+                            );
+                            match served.await {
+                                Ok(Ok(())) => {}
+                                Ok(Err(e)) => warn!("Control connection failed: {}", e),
+                                Err(_) => warn!("Control connection from {} timed out after {}s", peer, CONNECTION_TIMEOUT_SECS),
+                            }
+                            drop(permit);
+                        });
+                    }
+                    Err(e) => warn!("Control server accept failed: {}", e),
+                }
+            }
+        });
+        Ok(Self { commands, task })
+    }
+
+    /// Next queued command; waits until one arrives
+    pub async fn next(&mut self) -> Option<ControlRequest> {
+        self.commands.recv().await
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Request line, the headers the server uses, and the body
+#[derive(Debug, PartialEq)]
+struct HttpRequest {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    body: String,
+}
+
+/// One line of the request head; `None` when it runs past `MAX_LINE_BYTES`
+async fn read_head_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    (&mut *reader).take(MAX_LINE_BYTES as u64 + 1).read_line(&mut line).await?;
+    Ok((line.len() <= MAX_LINE_BYTES).then_some(line))
+}
+
+/// Read one request. Without `Content-Length` the body is empty; a body over
+/// `MAX_BODY_BYTES` is refused without reading it.
+async fn read_request<R: AsyncBufRead + Unpin>(reader: &mut R) -> std::io::Result<Result<HttpRequest, ControlError>> {
+    let Some(request_line) = read_head_line(reader).await? else {
+        return Ok(Err(ControlError::HeaderTooLarge));
+    };
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or("").to_string(), parts.next().unwrap_or("").to_string());
+
+    let mut content_length = 0usize;
+    let mut authorization = None;
+    let mut headers = 0;
+    loop {
+        let Some(header) = read_head_line(reader).await? else {
+            return Ok(Err(ControlError::HeaderTooLarge));
+        };
+        if header.trim().is_empty() {
+            break;
+        }
+        headers += 1;
+        if headers > MAX_HEADERS {
+            return Ok(Err(ControlError::HeaderTooLarge));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-length" => match value.trim().parse() {
+                    Ok(length) => content_length = length,
+                    Err(_) => return Ok(Err(ControlError::BadRequest(format!("invalid Content-Length {:?}", value.trim())))),
+                },
+                "authorization" => authorization = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Ok(Err(ControlError::PayloadTooLarge));
+    }
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body).await?;
+    Ok(Ok(HttpRequest { method, path, authorization, body: String::from_utf8_lossy(&body).into_owned() }))
+}
+
+/// Read one request, queue its command and write the reply
+async fn serve_connection(
+    stream: TcpStream,
+    sender: mpsc::Sender<ControlRequest>,
+    token: Option<&str>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let request = tokio::time::timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS), read_request(&mut reader))
+        .await
+        .unwrap_or(Ok(Err(ControlError::RequestTimeout)))?;
+    let result = match request {
+        Err(e) => Err(e),
+        Ok(request) => match authorize(token, request.authorization.as_deref()) {
+            Err(e) => Err(e),
+            Ok(()) => match ControlCommand::from_request(&request.method, &request.path, &request.body) {
+                Err(e) => Err(e),
+                Ok(command) => {
+                    info!("🎛️  Control command: {:?}", command);
+                    dispatch(&sender, command).await
+                }
+            },
+        },
+    };
+    let (status, body) = match result {
+        Ok(value) => ("200 OK", serde_json::json!({ "ok": true, "result": value })),
+        Err(e) => (e.status_line(), serde_json::json!({ "ok": false, "error": e.message() })),
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let mut stream = reader.into_inner();
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn authorize(token: Option<&str>, authorization: Option<&str>) -> Result<(), ControlError> {
+    match token {
+        Some(token) if !authorization
+            .and_then(|a| a.strip_prefix("Bearer "))
+            .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes())) =>
+        {
+            Err(ControlError::Unauthorized)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Byte comparison without an early exit, so the reply time does not tell how much of
+/// a guessed token was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn dispatch(sender: &mpsc::Sender<ControlRequest>, command: ControlCommand) -> Result<serde_json::Value, ControlError> {
+    let (reply, response) = oneshot::channel();
+    sender
+        .send(ControlRequest { command, reply })
+        .await
+        .map_err(|_| ControlError::Unavailable)?;
+    response.await.map_err(|_| ControlError::Unavailable)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_routing() {
+        assert_eq!(ControlCommand::from_request("GET", "/status", ""), Ok(ControlCommand::Status));
+        assert_eq!(ControlCommand::from_request("GET", "/risk", ""), Ok(ControlCommand::RiskReport));
+        assert_eq!(ControlCommand::from_request("POST", "/rotate/", ""), Ok(ControlCommand::ForceRotate));
+        assert_eq!(
+            ControlCommand::from_request("POST", "/max-size", r#"{"max_position_size_usd": 250}"#),
+            Ok(ControlCommand::SetMaxSize { max_position_size_usd: 250.0 })
+        );
+        assert!(matches!(ControlCommand::from_request("POST", "/max-size", "{}"), Err(ControlError::BadRequest(_))));
+        assert_eq!(
+            ControlCommand::from_request("POST", "/capture", r#"{"enabled": true}"#),
+            Ok(ControlCommand::SetApiCapture { enabled: true })
+        );
+        assert_eq!(ControlCommand::from_request("GET", "/close", ""), Err(ControlError::MethodNotAllowed));
+        assert_eq!(ControlCommand::from_request("POST", "/open", ""), Err(ControlError::NotFound));
+
+        assert_eq!(authorize(None, None), Ok(()));
+        assert_eq!(authorize(Some("secret"), Some("Bearer secret")), Ok(()));
+        assert_eq!(authorize(Some("secret"), Some("Bearer other")), Err(ControlError::Unauthorized));
+        assert_eq!(authorize(Some("secret"), None), Err(ControlError::Unauthorized));
+        assert_eq!(authorize(Some("secret"), Some("Bearer secre")), Err(ControlError::Unauthorized));
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+    }
+
+    #[tokio::test]
+    async fn test_read_request() {
+        // Head and body split across reads, mid-line and mid-body
+        let mut split = BufReader::new(
+            tokio_test::io::Builder::new()
+                .read(b"POST /reduce HT")
+                .read(b"TP/1.1\r\nAuthorization: Bearer s3cret\r\nContent-Le")
+                .read(b"ngth: 17\r\n\r\n{\"fraction\"")
+                .read(b": 0.5}")
+                .build(),
+        );
+        let request = read_request(&mut split).await.unwrap().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/reduce");
+        assert_eq!(request.authorization.as_deref(), Some("Bearer s3cret"));
+        assert_eq!(ControlCommand::from_request(&request.method, &request.path, &request.body), Ok(ControlCommand::Reduce { fraction: 0.5 }));
+
+        // No Content-Length: no body is read
+        let mut no_length = BufReader::new(tokio_test::io::Builder::new().read(b"POST /pause HTTP/1.1\r\nHost: x\r\n\r\n").build());
+        let request = read_request(&mut no_length).await.unwrap().unwrap();
+        assert_eq!((request.path.as_str(), request.body.as_str()), ("/pause", ""));
+        let mut no_length_body = BufReader::new(tokio_test::io::Builder::new().read(b"POST /max-size HTTP/1.1\r\n\r\n").build());
+        let request = read_request(&mut no_length_body).await.unwrap().unwrap();
+        assert!(matches!(ControlCommand::from_request(&request.method, &request.path, &request.body), Err(ControlError::BadRequest(_))));
+
+        // Oversized and malformed lengths are refused before reading a body
+        let oversized = format!("POST /reduce HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_BYTES + 1);
+        let mut oversized = BufReader::new(tokio_test::io::Builder::new().read(oversized.as_bytes()).build());
+        assert_eq!(read_request(&mut oversized).await.unwrap(), Err(ControlError::PayloadTooLarge));
+        let mut malformed = BufReader::new(tokio_test::io::Builder::new().read(b"POST /reduce HTTP/1.1\r\nContent-Length: -1\r\n\r\n").build());
+        assert!(matches!(read_request(&mut malformed).await.unwrap(), Err(ControlError::BadRequest(_))));
+
+        // Head lines and header counts are bounded before the token is checked
+        let long_line = format!("GET /status HTTP/1.1\r\nX-Pad: {}\r\n\r\n", "a".repeat(MAX_LINE_BYTES));
+        let mut long_line = BufReader::new(tokio_test::io::Builder::new().read(long_line.as_bytes()).build());
+        assert_eq!(read_request(&mut long_line).await.unwrap(), Err(ControlError::HeaderTooLarge));
+        let many_headers = format!("GET /status HTTP/1.1\r\n{}\r\n", "X-Pad: a\r\n".repeat(MAX_HEADERS + 1));
+        let mut many_headers = BufReader::new(tokio_test::io::Builder::new().read(many_headers.as_bytes()).build());
+        assert_eq!(read_request(&mut many_headers).await.unwrap(), Err(ControlError::HeaderTooLarge));
+
+        // A body shorter than announced is a connection error
+        let mut truncated = BufReader::new(tokio_test::io::Builder::new().read(b"POST /reduce HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}").build());
+        assert!(read_request(&mut truncated).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_non_loopback_needs_token() {
+        if std::env::var("CONTROL_TOKEN").is_ok_and(|t| !t.is_empty()) {
+            return;
+        }
+        let config = ControlConfig { enabled: true, bind_address: "0.0.0.0:0".to_string() };
+        assert!(ControlServer::start(&config).await.err().unwrap().to_string().contains("CONTROL_TOKEN"));
+        let config = ControlConfig { enabled: true, bind_address: "127.0.0.1:0".to_string() };
+        assert!(ControlServer::start(&config).await.is_ok());
+    }
+}
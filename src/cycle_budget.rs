@@ -0,0 +1,185 @@
+//! Per-cycle timing budget
+//!
+//! Every monitoring cycle times its phases: `reconcile` (clock sync, state
+//! reconciliation, recovery checks and the funding refresh), `display` (the status and
+//! scan tables), `scan` and `decision` (rotation, close and open, including the rescan
+//! before opening). The breakdown is logged at debug level and stored in the cycle's
+//! decision log record. Once a cycle has run longer than `cycle_budget.budget_seconds`,
+//! the remaining display phases are skipped (the status table also fetches live prices)
+//! and the cycle ends with a warning naming the slow phases. Reconciliation and trading
+//! decisions always run; the watchdog deadline still cancels a cycle that hangs.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleBudgetConfig {
+    /// Cycle time after which non-essential phases are skipped (0 = no budget)
+    #[serde(default = "default_budget_seconds")]
+    pub budget_seconds: u64,
+}
+
+fn default_budget_seconds() -> u64 {
+    180
+}
+
+impl Default for CycleBudgetConfig {
+    fn default() -> Self {
+        Self {
+            budget_seconds: default_budget_seconds(),
+        }
+    }
+}
+
+impl CycleBudgetConfig {
+    pub fn budget(&self) -> Option<Duration> {
+        (self.budget_seconds > 0).then(|| Duration::from_secs(self.budget_seconds))
+    }
+}
+
+/// Timed part of a monitoring cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Reconcile,
+    Display,
+    Scan,
+    Decision,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reconcile => write!(f, "reconcile"),
+            Self::Display => write!(f, "display"),
+            Self::Scan => write!(f, "scan"),
+            Self::Decision => write!(f, "decision"),
+        }
+    }
+}
+
+/// Phase durations of the cycle in progress
+#[derive(Debug, Clone)]
+pub struct CycleTimer {
+    started: Instant,
+    budget: Option<Duration>,
+    phases: BTreeMap<Phase, Duration>,
+    /// Phase started with `begin` and not finished yet
+    running: Option<(Phase, Instant)>,
+    /// Non-essential work skipped for the budget, in order
+    skipped: Vec<String>,
+}
+
+impl CycleTimer {
+    pub fn start(config: &CycleBudgetConfig) -> Self {
+        Self {
+            started: Instant::now(),
+            budget: config.budget(),
+            phases: BTreeMap::new(),
+            running: None,
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Add the time since `since` to `phase`; a phase timed twice adds up
+    pub fn record(&mut self, phase: Phase, since: Instant) {
+        self.add(phase, since.elapsed());
+    }
+
+    /// Time `phase` from now until `finish`, for a phase left through early returns
+    pub fn begin(&mut self, phase: Phase) {
+        self.finish();
+        self.running = Some((phase, Instant::now()));
+    }
+
+    /// End the phase started with `begin`, if any
+    pub fn finish(&mut self) {
+        if let Some((phase, since)) = self.running.take() {
+            self.record(phase, since);
+        }
+    }
+
+    fn add(&mut self, phase: Phase, duration: Duration) {
+        *self.phases.entry(phase).or_default() += duration;
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn over_budget(&self) -> bool {
+        self.budget.is_some_and(|budget| self.elapsed() > budget)
+    }
+
+    /// Whether the non-essential `work` should run. Over budget it is skipped, with a
+    /// warning the first time in the cycle.
+    pub fn allows(&mut self, work: &str) -> bool {
+        if !self.over_budget() {
+            return true;
+        }
+        if self.skipped.is_empty() {
+            warn!("⏱️  Cycle over its {}s budget after {:.1}s ({}): skipping non-essential phases",
+                self.budget.unwrap_or_default().as_secs(), self.elapsed().as_secs_f64(), self);
+        }
+        self.skipped.push(work.to_string());
+        false
+    }
+
+    /// Work skipped for the budget so far
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+
+    /// Milliseconds spent in each timed phase
+    pub fn phase_ms(&self) -> BTreeMap<Phase, u64> {
+        self.phases.iter().map(|(phase, d)| (*phase, d.as_millis() as u64)).collect()
+    }
+}
+
+impl fmt::Display for CycleTimer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.phases.is_empty() {
+            return write!(f, "no phase timed");
+        }
+        for (i, (phase, duration)) in self.phases.iter().enumerate() {
+            write!(f, "{}{} {:.1}s", if i > 0 { ", " } else { "" }, phase, duration.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_timer_budget() {
+        let mut timer = CycleTimer::start(&CycleBudgetConfig { budget_seconds: 60 });
+        timer.add(Phase::Scan, Duration::from_secs(40));
+        timer.add(Phase::Reconcile, Duration::from_millis(1500));
+        timer.record(Phase::Scan, Instant::now());
+        timer.add(Phase::Scan, Duration::from_secs(5));
+        timer.begin(Phase::Decision);
+        timer.finish();
+        assert!(timer.phase_ms().contains_key(&Phase::Decision));
+        assert!(timer.allows("status table"));
+        assert!(timer.skipped().is_empty());
+
+        let ms = timer.phase_ms();
+        assert!((45_000..46_000).contains(&ms[&Phase::Scan]));
+        assert!(timer.to_string().starts_with("reconcile 1.5s, scan 45.0s, decision 0.0s"));
+
+        // Over budget: display work is skipped and remembered
+        timer.budget = Some(Duration::ZERO);
+        assert!(!timer.allows("status table"));
+        assert!(!timer.allows("scan table"));
+        assert_eq!(timer.skipped(), ["status table", "scan table"]);
+
+        // No budget never skips
+        let mut unbounded = CycleTimer::start(&CycleBudgetConfig { budget_seconds: 0 });
+        assert!(!unbounded.over_budget());
+        assert!(unbounded.allows("status table"));
+    }
+}
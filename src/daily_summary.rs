@@ -0,0 +1,188 @@
+//! Daily summary of the bot's ledger
+//!
+//! The running totals kept in `BotState` (funding, fees, rotations) plus the equity on
+//! both venues are snapshotted as a `LedgerTotals` once a day at
+//! `daily_summary.hour_utc`. The difference to the previous snapshot is a
+//! `DailySummary`, logged as a single JSON line and sent to the summary hooks (a JSON
+//! POST to `daily_summary.webhook_url` when set). The previous snapshot is kept in the
+//! state file, so a restart neither loses nor repeats a day.
+use crate::bot::BotState;
+use crate::build_info::BuildInfo;
+use crate::funding::apr_from_rate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const SECS_PER_DAY: u64 = 24 * 3600;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DailySummaryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Hour of the day (UTC, 0-23) the summary is sent
+    #[serde(default)]
+    pub hour_utc: u32,
+    /// POST each summary as JSON to this URL (Slack/Discord-compatible)
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+impl DailySummaryConfig {
+    /// Latest scheduled summary time at or before `now` (unix seconds)
+    pub fn last_scheduled_at(&self, now: u64) -> u64 {
+        let today = now - now % SECS_PER_DAY + u64::from(self.hour_utc) * 3600;
+        if today <= now {
+            today
+        } else {
+            today.saturating_sub(SECS_PER_DAY)
+        }
+    }
+
+    /// Whether a summary is due for a period that started at `since`
+    pub fn is_due(&self, since: u64, now: u64) -> bool {
+        self.enabled && self.last_scheduled_at(now) > since
+    }
+}
+
+/// Cumulative ledger values at a point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerTotals {
+    /// Unix timestamp (seconds) of the snapshot
+    pub at: u64,
+    /// Funding over closed positions plus the open one so far, in USD
+    pub funding_usd: f64,
+    pub fees_usd: f64,
+    pub rotations: u64,
+    /// Notional of the position held at the snapshot (0 when flat)
+    pub notional_usd: f64,
+    /// Account equity on each venue, when it could be fetched
+    pub extended_equity_usd: Option<f64>,
+    pub pacifica_equity_usd: Option<f64>,
+    /// Code and config the bot ran with at the snapshot
+    #[serde(default)]
+    pub build: Option<BuildInfo>,
+}
+
+impl LedgerTotals {
+    pub fn from_state(state: &BotState, at: u64, extended_equity_usd: Option<f64>, pacifica_equity_usd: Option<f64>) -> Self {
+        let position = state.current_position.as_ref();
+        Self {
+            at,
+            funding_usd: state.total_realized_funding_usd + position.map_or(0.0, |p| p.realized_funding_usd()),
+            fees_usd: state.total_fees_usd,
+            rotations: state.total_rotations,
+            notional_usd: position.map_or(0.0, |p| p.target_notional_usd),
+            extended_equity_usd,
+            pacifica_equity_usd,
+            build: state.build.clone(),
+        }
+    }
+}
+
+/// What happened between two ledger snapshots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailySummary {
+    pub period_start: u64,
+    pub period_end: u64,
+    pub funding_usd: f64,
+    pub fees_usd: f64,
+    pub rotations: u64,
+    /// Funding annualized on the average of the start and end notional; `None` when
+    /// flat at both ends
+    pub avg_apr_pct: Option<f64>,
+    pub extended_equity_change_usd: Option<f64>,
+    pub pacifica_equity_change_usd: Option<f64>,
+}
+
+impl DailySummary {
+    pub fn between(start: &LedgerTotals, end: &LedgerTotals) -> Self {
+        let hours = end.at.saturating_sub(start.at) as f64 / 3600.0;
+        let funding_usd = end.funding_usd - start.funding_usd;
+        let notional = (start.notional_usd + end.notional_usd) / 2.0;
+        let change = |from: Option<f64>, to: Option<f64>| Some(to? - from?);
+        Self {
+            period_start: start.at,
+            period_end: end.at,
+            funding_usd,
+            fees_usd: end.fees_usd - start.fees_usd,
+            rotations: end.rotations.saturating_sub(start.rotations),
+            avg_apr_pct: (notional > 0.0 && hours > 0.0).then(|| apr_from_rate(funding_usd / notional, hours) * 100.0),
+            extended_equity_change_usd: change(start.extended_equity_usd, end.extended_equity_usd),
+            pacifica_equity_change_usd: change(start.pacifica_equity_usd, end.pacifica_equity_usd),
+        }
+    }
+
+    /// Funding collected net of fees, in USD
+    pub fn net_usd(&self) -> f64 {
+        self.funding_usd - self.fees_usd
+    }
+
+    /// Single-line JSON for log aggregation
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| self.to_string())
+    }
+}
+
+impl fmt::Display for DailySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let usd = |v: Option<f64>| v.map_or_else(|| "n/a".to_string(), |v| format!("${:+.2}", v));
+        write!(
+            f,
+            "Daily summary ({:.1}h): funding ${:+.2}, fees ${:.2}, net ${:+.2}, {} rotation(s), avg APR {}, equity Extended {} / Pacifica {}",
+            self.period_end.saturating_sub(self.period_start) as f64 / 3600.0,
+            self.funding_usd,
+            self.fees_usd,
+            self.net_usd(),
+            self.rotations,
+            self.avg_apr_pct.map_or_else(|| "n/a".to_string(), |apr| format!("{:.2}%", apr)),
+            usd(self.extended_equity_change_usd),
+            usd(self.pacifica_equity_change_usd)
+        )
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_summary_schedule_and_totals() {
+        let config = DailySummaryConfig { enabled: true, hour_utc: 8, webhook_url: None };
+        let day = 19_000 * SECS_PER_DAY;
+        assert_eq!(config.last_scheduled_at(day + 9 * 3600), day + 8 * 3600);
+        assert_eq!(config.last_scheduled_at(day + 7 * 3600), day - 16 * 3600);
+        assert!(config.is_due(day + 7 * 3600, day + 8 * 3600));
+        assert!(!config.is_due(day + 8 * 3600, day + 20 * 3600));
+        assert!(!DailySummaryConfig { enabled: false, ..config }.is_due(0, day));
+
+        let start = LedgerTotals {
+            at: day,
+            funding_usd: 10.0,
+            fees_usd: 2.0,
+            rotations: 3,
+            notional_usd: 1000.0,
+            extended_equity_usd: Some(500.0),
+            pacifica_equity_usd: None,
+            build: None,
+        };
+        let end = LedgerTotals {
+            at: day + SECS_PER_DAY,
+            funding_usd: 11.0,
+            fees_usd: 2.5,
+            rotations: 4,
+            extended_equity_usd: Some(510.0),
+            pacifica_equity_usd: Some(490.0),
+            ..start.clone()
+        };
+        let summary = DailySummary::between(&start, &end);
+        assert_eq!(summary.funding_usd, 1.0);
+        assert_eq!(summary.net_usd(), 0.5);
+        assert_eq!(summary.rotations, 1);
+        // $1 a day on $1000 is 36.5% a year
+        assert!((summary.avg_apr_pct.unwrap() - 36.5).abs() < 1e-9);
+        assert_eq!(summary.extended_equity_change_usd, Some(10.0));
+        assert_eq!(summary.pacifica_equity_change_usd, None);
+        let json: serde_json::Value = serde_json::from_str(&summary.to_json_line()).unwrap();
+        assert_eq!(json["rotations"], 1);
+    }
+}
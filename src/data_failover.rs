@@ -0,0 +1,211 @@
+//! Scan data failover
+//!
+//! A scan reads each symbol's top of book from the venue's stream when it is fresher
+//! than `performance.orderbook_max_age_seconds`, and from REST otherwise. With
+//! `data_failover.enabled`, a failed REST book no longer drops the symbol straight
+//! away: the streamed quote is used if it is younger than `max_cached_quote_age_seconds`,
+//! then, for Extended, the same call against `extended_secondary_url` when set. Only
+//! when all of them fail is the symbol skipped. A fallback quote has no depth beyond
+//! its top level, and the open path reads live quotes again before sending orders, so
+//! it only affects ranking. Orders are never routed through a fallback.
+//!
+//! Every scan counts, per venue, how many symbols got their book from the primary
+//! source, from a fallback, or not at all (`ScanResult::data_availability`), and warns
+//! when a venue needed fallbacks or lost symbols.
+use crate::capital::Venue;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataFailoverConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Oldest streamed quote used when the REST book fails
+    #[serde(default = "default_max_cached_quote_age_seconds")]
+    pub max_cached_quote_age_seconds: u64,
+    /// Another Extended API base URL (e.g. a proxy or mirror) asked for books the
+    /// primary endpoint failed to return
+    #[serde(default)]
+    pub extended_secondary_url: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_cached_quote_age_seconds() -> u64 {
+    120
+}
+
+impl Default for DataFailoverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            max_cached_quote_age_seconds: default_max_cached_quote_age_seconds(),
+            extended_secondary_url: None,
+        }
+    }
+}
+
+impl DataFailoverConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(url) = &self.extended_secondary_url {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return Err(format!("data_failover.extended_secondary_url must be an http(s) URL, got {}", url));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a scan got one venue's top of book for one symbol
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteSource {
+    /// Fresh streamed quote
+    Stream,
+    Rest,
+    /// Streamed quote past the freshness limit, after REST failed
+    CachedStream,
+    /// Extended secondary endpoint, after REST failed
+    Secondary,
+    /// No book: the symbol was skipped
+    #[default]
+    Missing,
+}
+
+impl QuoteSource {
+    pub fn is_fallback(self) -> bool {
+        matches!(self, Self::CachedStream | Self::Secondary)
+    }
+}
+
+impl fmt::Display for QuoteSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stream => write!(f, "stream"),
+            Self::Rest => write!(f, "REST"),
+            Self::CachedStream => write!(f, "cached stream"),
+            Self::Secondary => write!(f, "secondary endpoint"),
+            Self::Missing => write!(f, "missing"),
+        }
+    }
+}
+
+/// Sources of both venues' books for one symbol; a venue not reached stays `Missing`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BookSources {
+    pub extended: QuoteSource,
+    pub pacifica: QuoteSource,
+}
+
+/// Symbols of one scan by where a venue's book came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VenueData {
+    pub primary: usize,
+    pub fallback: usize,
+    pub missing: usize,
+}
+
+impl VenueData {
+    fn record(&mut self, source: QuoteSource) {
+        match source {
+            QuoteSource::Stream | QuoteSource::Rest => self.primary += 1,
+            QuoteSource::CachedStream | QuoteSource::Secondary => self.fallback += 1,
+            QuoteSource::Missing => self.missing += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.primary + self.fallback + self.missing
+    }
+
+    /// Share of symbols with a book from any source, in percent (100 for an empty scan)
+    pub fn available_pct(&self) -> f64 {
+        match self.total() {
+            0 => 100.0,
+            total => (self.primary + self.fallback) as f64 / total as f64 * 100.0,
+        }
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.fallback > 0 || self.missing > 0
+    }
+}
+
+/// Book availability of both venues over one scan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataAvailability {
+    pub extended: VenueData,
+    pub pacifica: VenueData,
+}
+
+impl DataAvailability {
+    /// Count one symbol. When a venue's book failed, the other venue's book was never
+    /// fetched: only the failed venue counts it missing.
+    pub fn record(&mut self, sources: BookSources) {
+        self.extended.record(sources.extended);
+        if sources.extended != QuoteSource::Missing || sources.pacifica != QuoteSource::Missing {
+            self.pacifica.record(sources.pacifica);
+        }
+    }
+
+    pub fn venue(&self, venue: Venue) -> &VenueData {
+        match venue {
+            Venue::Extended => &self.extended,
+            Venue::Pacifica => &self.pacifica,
+        }
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.extended.is_degraded() || self.pacifica.is_degraded()
+    }
+}
+
+impl fmt::Display for DataAvailability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let venues = [(Venue::Extended, &self.extended), (Venue::Pacifica, &self.pacifica)];
+        for (i, (venue, data)) in venues.into_iter().enumerate() {
+            write!(
+                f,
+                "{}{} {}/{} books ({} from fallback, {} missing)",
+                if i > 0 { ", " } else { "" },
+                venue,
+                data.primary + data.fallback,
+                data.total(),
+                data.fallback,
+                data.missing
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_availability_counts() {
+        let mut availability = DataAvailability::default();
+        let sources = |extended, pacifica| BookSources { extended, pacifica };
+        availability.record(sources(QuoteSource::Stream, QuoteSource::Rest));
+        availability.record(sources(QuoteSource::CachedStream, QuoteSource::Stream));
+        availability.record(sources(QuoteSource::Secondary, QuoteSource::Missing));
+        // Extended failed first: Pacifica was never asked and is not counted
+        availability.record(sources(QuoteSource::Missing, QuoteSource::Missing));
+
+        assert_eq!(availability.extended, VenueData { primary: 1, fallback: 2, missing: 1 });
+        assert_eq!(availability.pacifica, VenueData { primary: 2, fallback: 0, missing: 1 });
+        assert_eq!(availability.venue(Venue::Extended).available_pct(), 75.0);
+        assert!(availability.is_degraded());
+        assert_eq!(
+            availability.to_string(),
+            "Extended 3/4 books (2 from fallback, 1 missing), Pacifica 2/3 books (0 from fallback, 1 missing)"
+        );
+        assert!(!DataAvailability::default().is_degraded());
+
+        let config = DataFailoverConfig { extended_secondary_url: Some("api.example.com".to_string()), ..Default::default() };
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Per-cycle decision log
+//!
+//! Every monitoring cycle the bot appends what it saw and what it did to
+//! `decision_log.jsonl` (override with `DECISION_LOG_PATH`): the top candidates of the
+//! scan with the filter each one hit, every scanned symbol grouped by the filter that
+//! rejected it, the action taken, and the reasons anything that could have happened did
+//! not (cooldown, pause, capital, a failed spread re-check...). When the bot sits flat
+//! for days, `extended_connector decisions` shows which filter kept out which symbols
+//! without re-running scans by hand.
+use crate::cycle_budget::Phase;
+use crate::error::BotError;
+use crate::opportunity::{Direction, FilterResult, ScanResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use tracing::warn;
+
+pub const DEFAULT_DECISION_LOG_FILE: &str = "decision_log.jsonl";
+
+/// Candidates of each scan kept in the log, best net APR first
+pub const TOP_CANDIDATES: usize = 5;
+
+/// Decision log path, overridable with `DECISION_LOG_PATH`
+pub fn resolve_decision_log_path() -> String {
+    std::env::var("DECISION_LOG_PATH").unwrap_or_else(|_| DEFAULT_DECISION_LOG_FILE.to_string())
+}
+
+/// One scanned symbol as the filters judged it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CandidateSnapshot {
+    pub symbol: String,
+    pub direction: Direction,
+    pub net_apr: f64,
+    pub filter: FilterResult,
+}
+
+/// What a cycle ended up doing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DecisionAction {
+    /// A position is held and not due for rotation
+    Hold,
+    /// Flat and nothing was opened; the reasons say why
+    Wait,
+    /// Opened a new position
+    Open { symbol: String },
+    /// Closed `from` to rotate; `to` is the position opened in its place, if any
+    Rotate { from: String, to: Option<String> },
+    /// Rotation was due but the held position is still the best
+    Keep { symbol: String },
+    /// Closed the remaining leg of an imbalanced position
+    CloseImbalanced,
+}
+
+/// Inputs and outcome of one monitoring cycle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CycleDecision {
+    /// Unix timestamp (seconds) when the cycle started
+    pub at: u64,
+    /// Symbol held when the cycle started
+    pub held: Option<String>,
+    /// Symbols evaluated by the scan; `None` when no scan completed
+    pub scanned: Option<usize>,
+    pub top: Vec<CandidateSnapshot>,
+    /// Scanned symbols by the filter that rejected them
+    pub rejected: BTreeMap<FilterResult, Vec<String>>,
+    pub action: DecisionAction,
+    /// Why the bot did not open, rotate or scan, in the order it found out
+    pub reasons: Vec<String>,
+    /// Milliseconds the cycle spent in each phase (`cycle_budget`)
+    #[serde(default)]
+    pub phase_ms: BTreeMap<Phase, u64>,
+}
+
+impl CycleDecision {
+    pub fn new(at: u64, held: Option<String>) -> Self {
+        let action = if held.is_some() { DecisionAction::Hold } else { DecisionAction::Wait };
+        Self {
+            at,
+            held,
+            scanned: None,
+            top: Vec::new(),
+            rejected: BTreeMap::new(),
+            action,
+            reasons: Vec::new(),
+            phase_ms: BTreeMap::new(),
+        }
+    }
+
+    /// Take the candidates of `scan`; a later scan in the same cycle replaces an earlier one
+    pub fn record_scan(&mut self, scan: &ScanResult) {
+        let mut candidates: Vec<_> = scan.all_candidates.iter().collect();
+        candidates.sort_by(|a, b| b.opportunity.best_net_apr.total_cmp(&a.opportunity.best_net_apr));
+        self.scanned = Some(candidates.len());
+        self.top = candidates
+            .iter()
+            .take(TOP_CANDIDATES)
+            .map(|c| CandidateSnapshot {
+                symbol: c.opportunity.symbol.clone(),
+                direction: c.opportunity.best_direction,
+                net_apr: c.opportunity.best_net_apr,
+                filter: c.filter_result,
+            })
+            .collect();
+        self.rejected.clear();
+        for candidate in candidates.iter().filter(|c| c.filter_result != FilterResult::Passed) {
+            self.rejected
+                .entry(candidate.filter_result)
+                .or_default()
+                .push(candidate.opportunity.symbol.clone());
+        }
+    }
+
+    /// Note why something did not happen this cycle
+    pub fn skip(&mut self, reason: impl Into<String>) {
+        self.reasons.push(reason.into());
+    }
+
+    /// Record a newly opened position, completing a rotation in progress
+    pub fn opened(&mut self, symbol: &str) {
+        self.action = match std::mem::replace(&mut self.action, DecisionAction::Wait) {
+            DecisionAction::Rotate { from, .. } => DecisionAction::Rotate { from, to: Some(symbol.to_string()) },
+            _ => DecisionAction::Open { symbol: symbol.to_string() },
+        };
+    }
+
+    /// Human-readable summary of the cycle, one fact per line
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let when = chrono::DateTime::from_timestamp(self.at as i64, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| self.at.to_string());
+        let action = match &self.action {
+            DecisionAction::Hold => format!("hold {}", self.held.as_deref().unwrap_or("?")),
+            DecisionAction::Wait => "wait (flat)".to_string(),
+            DecisionAction::Open { symbol } => format!("open {}", symbol),
+            DecisionAction::Rotate { from, to: Some(to) } => format!("rotate {} -> {}", from, to),
+            DecisionAction::Rotate { from, to: None } => format!("rotate out of {} (nothing opened)", from),
+            DecisionAction::Keep { symbol } => format!("keep {} for another hold", symbol),
+            DecisionAction::CloseImbalanced => "close imbalanced leg".to_string(),
+        };
+        let _ = writeln!(out, "{}: {}", when, action);
+        match self.scanned {
+            Some(scanned) => {
+                let _ = writeln!(out, "  scanned {} symbols", scanned);
+            }
+            None => {
+                let _ = writeln!(out, "  no scan");
+            }
+        }
+        for c in &self.top {
+            let _ = writeln!(out, "  {:<10} {:>8.2}% {:<32} {:?}", c.symbol, c.net_apr, c.direction.to_string(), c.filter);
+        }
+        for (filter, symbols) in &self.rejected {
+            let _ = writeln!(out, "  {:?} ({}): {}", filter, symbols.len(), symbols.join(", "));
+        }
+        for reason in &self.reasons {
+            let _ = writeln!(out, "  - {}", reason);
+        }
+        out
+    }
+}
+
+/// Append-only file of per-cycle decisions
+pub struct DecisionLog {
+    path: String,
+}
+
+impl DecisionLog {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Append one cycle
+    pub fn record(&self, decision: &CycleDecision) -> Result<(), BotError> {
+        if let Some(parent) = Path::new(&self.path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(decision)?)?;
+        Ok(())
+    }
+
+    /// The last `limit` cycles, oldest first. A torn last line (crash mid-write) is skipped.
+    pub fn recent(&self, limit: usize) -> Result<Vec<CycleDecision>, BotError> {
+        if !Path::new(&self.path).exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)?;
+        let mut decisions = Vec::new();
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(decision) => decisions.push(decision),
+                Err(e) => warn!("Skipping unreadable decision log line: {}", e),
+            }
+        }
+        let skip = decisions.len().saturating_sub(limit);
+        decisions.drain(..skip);
+        Ok(decisions)
+    }
+}
+
+/// How often each symbol was rejected by each filter across `decisions`
+pub fn rejection_counts(decisions: &[CycleDecision]) -> BTreeMap<String, BTreeMap<FilterResult, usize>> {
+    let mut counts: BTreeMap<String, BTreeMap<FilterResult, usize>> = BTreeMap::new();
+    for decision in decisions {
+        for (filter, symbols) in &decision.rejected {
+            for symbol in symbols {
+                *counts.entry(symbol.clone()).or_default().entry(*filter).or_default() += 1;
+            }
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opportunity::{sample_opportunity, FilterStats, Opportunity, OpportunityCandidate};
+
+    fn candidate(symbol: &str, net_apr: f64, filter_result: FilterResult) -> OpportunityCandidate {
+        OpportunityCandidate {
+            opportunity: Opportunity {
+                extended_funding_rate_apr: net_apr,
+                pacifica_funding_rate_apr: 0.0,
+                ..sample_opportunity(symbol, net_apr, Direction::LongPacificaShortExtended)
+            },
+            filter_result,
+            filtered_cross_spread_pct: None,
+        }
+    }
+
+    fn scan(candidates: Vec<OpportunityCandidate>) -> ScanResult {
+        ScanResult {
+            opportunities: Vec::new(),
+            stats: FilterStats {
+                total_common_symbols: candidates.len(),
+                filtered_by_volume: 0,
+                filtered_by_spread: 0,
+                filtered_by_apr: 0,
+                filtered_by_open_interest: 0,
+                filtered_by_mark_index_gap: 0,
+                passed_filters: 0,
+            },
+            all_candidates: candidates,
+            scanned_at: 1_700_000_000,
+            data_availability: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_record_scan_and_round_trip() {
+        let mut decision = CycleDecision::new(1_700_000_000, None);
+        let mut candidates: Vec<_> = (0..6).map(|i| candidate(&format!("S{}", i), i as f64, FilterResult::FailedApr)).collect();
+        candidates.push(candidate("BTC", 30.0, FilterResult::FailedCrossSpread));
+        candidates.push(candidate("ETH", 20.0, FilterResult::Passed));
+        decision.record_scan(&scan(candidates));
+        decision.skip("rotation cooldown: 12.0 minutes left");
+
+        assert_eq!(decision.scanned, Some(8));
+        let top: Vec<_> = decision.top.iter().map(|c| c.symbol.as_str()).collect();
+        assert_eq!(top, ["BTC", "ETH", "S5", "S4", "S3"]);
+        assert_eq!(decision.rejected[&FilterResult::FailedCrossSpread], ["BTC"]);
+        assert_eq!(decision.rejected[&FilterResult::FailedApr].len(), 6);
+        assert!(!decision.rejected.contains_key(&FilterResult::Passed));
+        assert!(decision.render().contains("FailedCrossSpread (1): BTC"));
+
+        let path = std::env::temp_dir().join(format!("decision_log_test_{}.jsonl", uuid::Uuid::new_v4()));
+        let log = DecisionLog::new(path.to_str().unwrap());
+        assert!(log.recent(10).unwrap().is_empty());
+        log.record(&decision).unwrap();
+        let mut rotation = CycleDecision::new(1_700_003_600, Some("BTC".to_string()));
+        rotation.action = DecisionAction::Rotate { from: "BTC".to_string(), to: None };
+        rotation.opened("ETH");
+        log.record(&rotation).unwrap();
+
+        let recent = log.recent(10).unwrap();
+        assert_eq!(recent, vec![decision, rotation]);
+        assert_eq!(log.recent(1).unwrap()[0].action, DecisionAction::Rotate {
+            from: "BTC".to_string(),
+            to: Some("ETH".to_string()),
+        });
+        assert_eq!(rejection_counts(&recent)["BTC"][&FilterResult::FailedCrossSpread], 1);
+        let _ = fs::remove_file(log.path());
+    }
+}
@@ -0,0 +1,239 @@
+//! Candle-based entry timing
+//!
+//! With `entry_timing.enabled`, the bot reads the last `lookback_candles` candles of
+//! `candle_minutes` from both venues before opening the selected symbol. When either
+//! venue shows an extreme move (close-to-close change over the window above
+//! `max_move_pct`) or extreme volatility (ATR above `max_atr_pct` of price), the open
+//! is delayed: the cycle ends without opening and the next one runs after
+//! `recheck_seconds`. Once the symbol has been waited on for `max_wait_minutes`, it is
+//! opened regardless. A venue whose candles cannot be read does not delay the open.
+use crate::capital::Venue;
+use crate::pacifica::PacificaTrading;
+use crate::rest::RestClient;
+use crate::sizing::{atr_pct, OhlcCandle};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryTimingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_candle_minutes")]
+    pub candle_minutes: u64,
+    #[serde(default = "default_lookback_candles")]
+    pub lookback_candles: usize,
+    /// Largest close-to-close price change (%) over the window that is still calm
+    #[serde(default = "default_max_move_pct")]
+    pub max_move_pct: f64,
+    /// Largest ATR (% of the last close) over the window that is still calm
+    #[serde(default = "default_max_atr_pct")]
+    pub max_atr_pct: f64,
+    /// Longest delay of one symbol's open before it is opened anyway
+    #[serde(default = "default_max_wait_minutes")]
+    pub max_wait_minutes: u64,
+    /// Pause before the next cycle while an open is delayed
+    #[serde(default = "default_recheck_seconds")]
+    pub recheck_seconds: u64,
+}
+
+fn default_candle_minutes() -> u64 {
+    5
+}
+
+fn default_lookback_candles() -> usize {
+    6
+}
+
+fn default_max_move_pct() -> f64 {
+    2.0
+}
+
+fn default_max_atr_pct() -> f64 {
+    1.0
+}
+
+fn default_max_wait_minutes() -> u64 {
+    30
+}
+
+fn default_recheck_seconds() -> u64 {
+    60
+}
+
+impl Default for EntryTimingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            candle_minutes: default_candle_minutes(),
+            lookback_candles: default_lookback_candles(),
+            max_move_pct: default_max_move_pct(),
+            max_atr_pct: default_max_atr_pct(),
+            max_wait_minutes: default_max_wait_minutes(),
+            recheck_seconds: default_recheck_seconds(),
+        }
+    }
+}
+
+impl EntryTimingConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.candle_minutes == 0 {
+            return Err("entry_timing.candle_minutes must be positive".to_string());
+        }
+        if self.lookback_candles < 2 {
+            return Err("entry_timing.lookback_candles must be at least 2".to_string());
+        }
+        if self.max_move_pct <= 0.0 || self.max_atr_pct <= 0.0 {
+            return Err("entry_timing.max_move_pct and max_atr_pct must be positive".to_string());
+        }
+        if self.recheck_seconds == 0 {
+            return Err("entry_timing.recheck_seconds must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// Why `conditions` are too extreme to enter, if they are
+    pub fn extreme(&self, conditions: &CandleConditions) -> Option<String> {
+        if conditions.move_pct.abs() > self.max_move_pct {
+            Some(format!("moved {:+.2}% over {} min (limit {:.2}%)",
+                conditions.move_pct, self.window_minutes(), self.max_move_pct))
+        } else if conditions.atr_pct > self.max_atr_pct {
+            Some(format!("ATR {:.2}% (limit {:.2}%)", conditions.atr_pct, self.max_atr_pct))
+        } else {
+            None
+        }
+    }
+
+    /// Whether to open now, given each venue's readings and how long (seconds) the
+    /// symbol has been waited on so far, if at all
+    pub fn decide(&self, readings: &[(Venue, CandleConditions)], waited_secs: Option<u64>) -> EntryTiming {
+        let reasons: Vec<String> = readings
+            .iter()
+            .filter_map(|(venue, conditions)| self.extreme(conditions).map(|r| format!("{} {}", venue, r)))
+            .collect();
+        if reasons.is_empty() {
+            EntryTiming::Enter
+        } else if waited_secs.is_some_and(|waited| waited >= self.max_wait_minutes * 60) {
+            EntryTiming::GiveUp(reasons.join(", "))
+        } else {
+            EntryTiming::Wait(reasons.join(", "))
+        }
+    }
+
+    fn window_minutes(&self) -> u64 {
+        self.candle_minutes * self.lookback_candles as u64
+    }
+}
+
+/// Short-term momentum and volatility of one venue's candles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CandleConditions {
+    /// Change (%) from the first candle's close to the last one's
+    pub move_pct: f64,
+    /// Average true range (% of the last close)
+    pub atr_pct: f64,
+}
+
+impl CandleConditions {
+    /// Conditions over `candles`, oldest first; `None` with fewer than two
+    pub fn from_candles(candles: &[OhlcCandle]) -> Option<Self> {
+        let first = candles.first()?.close;
+        let last = candles.last()?.close;
+        if first <= 0.0 {
+            return None;
+        }
+        Some(Self {
+            move_pct: (last / first - 1.0) * 100.0,
+            atr_pct: atr_pct(candles)?,
+        })
+    }
+}
+
+/// Outcome of the entry timing check
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntryTiming {
+    Enter,
+    /// Too extreme: delay the open
+    Wait(String),
+    /// Still extreme after `max_wait_minutes`: open anyway
+    GiveUp(String),
+}
+
+/// Open being delayed for calmer conditions
+#[derive(Debug, Clone)]
+pub struct EntryDelay {
+    pub symbol: String,
+    /// Unix seconds of the first delay
+    pub since: u64,
+}
+
+/// Conditions over the latest Extended candles of `market`
+pub async fn extended_conditions(
+    client: &RestClient,
+    market: &str,
+    config: &EntryTimingConfig,
+) -> anyhow::Result<Option<CandleConditions>> {
+    let interval = format!("PT{}M", config.candle_minutes);
+    let candles: Vec<OhlcCandle> = client
+        .get_candles(market, &interval, config.lookback_candles as u32)
+        .await?
+        .iter()
+        .filter_map(|c| c.hlc_f64())
+        .map(|(high, low, close)| OhlcCandle { high, low, close })
+        .collect();
+    Ok(CandleConditions::from_candles(&candles))
+}
+
+/// Conditions over the latest Pacifica candles of `symbol`
+pub async fn pacifica_conditions(
+    client: &PacificaTrading,
+    symbol: &str,
+    config: &EntryTimingConfig,
+) -> anyhow::Result<Option<CandleConditions>> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis() as u64;
+    let start = now.saturating_sub(config.window_minutes() * 60 * 1000);
+    let interval = format!("{}m", config.candle_minutes);
+    let candles: Vec<OhlcCandle> = client
+        .get_klines(symbol, &interval, start, now)
+        .await?
+        .iter()
+        .map(OhlcCandle::from)
+        .collect();
+    Ok(CandleConditions::from_candles(&candles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_timing_waits_out_spikes() {
+        let config = EntryTimingConfig { enabled: true, ..Default::default() };
+        assert!(config.validate().is_ok());
+
+        let calm = [
+            OhlcCandle { high: 100.2, low: 99.8, close: 100.0 },
+            OhlcCandle { high: 100.4, low: 99.9, close: 100.3 },
+            OhlcCandle { high: 100.5, low: 100.1, close: 100.4 },
+        ];
+        let calm = CandleConditions::from_candles(&calm).unwrap();
+        assert!((calm.move_pct - 0.4).abs() < 1e-9);
+        assert_eq!(config.decide(&[(Venue::Extended, calm), (Venue::Pacifica, calm)], None), EntryTiming::Enter);
+
+        // Pacifica spiked 3% in the window: wait, until max_wait_minutes have passed
+        let spike = CandleConditions { move_pct: 3.0, atr_pct: 0.5 };
+        let readings = [(Venue::Extended, calm), (Venue::Pacifica, spike)];
+        assert_eq!(
+            config.decide(&readings, Some(60)),
+            EntryTiming::Wait("Pacifica moved +3.00% over 30 min (limit 2.00%)".to_string())
+        );
+        assert!(matches!(config.decide(&readings, Some(30 * 60)), EntryTiming::GiveUp(_)));
+
+        let choppy = CandleConditions { move_pct: -0.1, atr_pct: 1.5 };
+        assert!(config.extreme(&choppy).unwrap().starts_with("ATR 1.50%"));
+        assert!(CandleConditions::from_candles(&[OhlcCandle { high: 1.0, low: 1.0, close: 1.0 }]).is_none());
+    }
+}
@@ -0,0 +1,250 @@
+//! Equity history and equity curve
+//!
+//! Every monitoring cycle the bot appends the equity on both venues, together with the
+//! ledger totals at that moment, to `equity_history.jsonl` (override with
+//! `EQUITY_HISTORY_PATH`). `EquityCurve` reads the series back for drawdown statistics,
+//! CSV export and a terminal chart, and sets the equity change against the funding the
+//! ledger recorded over the same span, to check the strategy earns what it projects.
+use crate::daily_summary::LedgerTotals;
+use crate::error::BotError;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use tracing::warn;
+
+pub const DEFAULT_EQUITY_HISTORY_FILE: &str = "equity_history.jsonl";
+
+/// Equity history path, overridable with `EQUITY_HISTORY_PATH`
+pub fn resolve_equity_history_path() -> String {
+    std::env::var("EQUITY_HISTORY_PATH").unwrap_or_else(|_| DEFAULT_EQUITY_HISTORY_FILE.to_string())
+}
+
+impl LedgerTotals {
+    /// Combined equity of both venues; `None` unless both could be fetched
+    pub fn total_equity_usd(&self) -> Option<f64> {
+        Some(self.extended_equity_usd? + self.pacifica_equity_usd?)
+    }
+}
+
+/// Append-only file of per-cycle equity snapshots
+pub struct EquityHistory {
+    path: String,
+}
+
+impl EquityHistory {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Append one snapshot
+    pub fn record(&self, point: &LedgerTotals) -> Result<(), BotError> {
+        if let Some(parent) = Path::new(&self.path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(point)?)?;
+        Ok(())
+    }
+
+    /// All snapshots in order. A torn last line (crash mid-write) is skipped.
+    pub fn curve(&self) -> Result<EquityCurve, BotError> {
+        if !Path::new(&self.path).exists() {
+            return Ok(EquityCurve::default());
+        }
+        let content = fs::read_to_string(&self.path)?;
+        let mut points = Vec::new();
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(point) => points.push(point),
+                Err(e) => warn!("Skipping unreadable equity history line: {}", e),
+            }
+        }
+        Ok(EquityCurve { points })
+    }
+}
+
+/// Largest peak-to-trough fall of combined equity
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drawdown {
+    pub peak_at: u64,
+    pub peak_usd: f64,
+    pub trough_at: u64,
+    pub trough_usd: f64,
+}
+
+impl Drawdown {
+    pub fn amount_usd(&self) -> f64 {
+        self.peak_usd - self.trough_usd
+    }
+
+    pub fn pct(&self) -> f64 {
+        if self.peak_usd <= 0.0 {
+            return 0.0;
+        }
+        self.amount_usd() / self.peak_usd * 100.0
+    }
+}
+
+/// Equity snapshots over time, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct EquityCurve {
+    pub points: Vec<LedgerTotals>,
+}
+
+impl EquityCurve {
+    /// `(timestamp, combined equity)` of the snapshots where both venues reported
+    pub fn equity_series(&self) -> Vec<(u64, f64)> {
+        self.points
+            .iter()
+            .filter_map(|p| p.total_equity_usd().map(|equity| (p.at, equity)))
+            .collect()
+    }
+
+    /// Change in combined equity from the first to the last complete snapshot
+    pub fn equity_change_usd(&self) -> Option<f64> {
+        let series = self.equity_series();
+        Some(series.last()?.1 - series.first()?.1)
+    }
+
+    /// Funding net of fees the ledger recorded over the whole series, in USD
+    pub fn net_funding_usd(&self) -> Option<f64> {
+        let (first, last) = (self.points.first()?, self.points.last()?);
+        Some((last.funding_usd - first.funding_usd) - (last.fees_usd - first.fees_usd))
+    }
+
+    /// Deepest drawdown of combined equity; `None` when equity never fell
+    pub fn max_drawdown(&self) -> Option<Drawdown> {
+        let mut peak: Option<(u64, f64)> = None;
+        let mut worst: Option<Drawdown> = None;
+        for (at, equity) in self.equity_series() {
+            let (peak_at, peak_usd) = match peak {
+                Some(p) if p.1 >= equity => p,
+                _ => {
+                    peak = Some((at, equity));
+                    continue;
+                }
+            };
+            if worst.as_ref().is_none_or(|w| peak_usd - equity > w.amount_usd()) {
+                worst = Some(Drawdown { peak_at, peak_usd, trough_at: at, trough_usd: equity });
+            }
+        }
+        worst
+    }
+
+    /// One row per snapshot; equity cells are empty when a venue could not be read
+    pub fn to_csv(&self) -> String {
+        let cell = |v: Option<f64>| v.map_or_else(String::new, |v| format!("{:.2}", v));
+        let mut csv = String::from("timestamp,extended_equity_usd,pacifica_equity_usd,total_equity_usd,funding_usd,fees_usd,notional_usd,rotations\n");
+        for p in &self.points {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{:.4},{:.4},{:.2},{}",
+                p.at,
+                cell(p.extended_equity_usd),
+                cell(p.pacifica_equity_usd),
+                cell(p.total_equity_usd()),
+                p.funding_usd,
+                p.fees_usd,
+                p.notional_usd,
+                p.rotations
+            );
+        }
+        csv
+    }
+
+    /// Combined equity as a `width` x `height` character chart with the range on the left
+    pub fn to_ascii(&self, width: usize, height: usize) -> String {
+        let series = self.equity_series();
+        if series.is_empty() || width == 0 || height == 0 {
+            return "No equity history recorded yet\n".to_string();
+        }
+        let min = series.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let max = series.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+        let columns = width.min(series.len());
+        let mut grid = vec![vec![' '; columns]; height];
+        for (column, cells) in (0..columns).map(|c| (c, c * series.len() / columns)) {
+            let equity = series[cells].1;
+            let level = if max > min { (equity - min) / (max - min) } else { 0.5 };
+            let row = height - 1 - ((level * (height - 1) as f64).round() as usize).min(height - 1);
+            grid[row][column] = '*';
+        }
+        let mut chart = String::new();
+        for (i, row) in grid.iter().enumerate() {
+            let label = match i {
+                0 => format!("{:>10.2}", max),
+                _ if i == height - 1 => format!("{:>10.2}", min),
+                _ => " ".repeat(10),
+            };
+            let _ = writeln!(chart, "{} |{}", label, row.iter().collect::<String>());
+        }
+        let _ = writeln!(chart, "{} +{}", " ".repeat(10), "-".repeat(columns));
+        chart
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(at: u64, extended: f64, pacifica: Option<f64>, funding_usd: f64) -> LedgerTotals {
+        LedgerTotals {
+            at,
+            funding_usd,
+            fees_usd: 0.5,
+            rotations: 0,
+            notional_usd: 1000.0,
+            extended_equity_usd: Some(extended),
+            pacifica_equity_usd: pacifica,
+            build: None,
+        }
+    }
+
+    #[test]
+    fn test_equity_curve_drawdown_and_funding() {
+        let curve = EquityCurve {
+            points: vec![
+                point(1, 500.0, Some(500.0), 0.0),
+                point(2, 520.0, Some(500.0), 1.0),
+                // Pacifica unavailable: excluded from equity statistics
+                point(3, 100.0, None, 1.5),
+                point(4, 495.0, Some(500.0), 2.0),
+                point(5, 510.0, Some(505.0), 3.0),
+            ],
+        };
+        assert_eq!(curve.equity_series().len(), 4);
+        assert_eq!(curve.equity_change_usd(), Some(15.0));
+        assert_eq!(curve.net_funding_usd(), Some(3.0));
+
+        let drawdown = curve.max_drawdown().unwrap();
+        assert_eq!((drawdown.peak_at, drawdown.trough_at), (2, 4));
+        assert_eq!(drawdown.amount_usd(), 25.0);
+        assert!((drawdown.pct() - 25.0 / 1020.0 * 100.0).abs() < 1e-9);
+
+        let csv = curve.to_csv();
+        assert_eq!(csv.lines().count(), 6);
+        assert!(csv.lines().nth(3).unwrap().starts_with("3,100.00,,,"));
+
+        let chart = curve.to_ascii(40, 5);
+        assert_eq!(chart.lines().count(), 6);
+        assert!(chart.starts_with("   1020.00 |"));
+        assert!(EquityCurve::default().max_drawdown().is_none());
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let path = std::env::temp_dir().join(format!("equity_history_test_{}.jsonl", uuid::Uuid::new_v4()));
+        let history = EquityHistory::new(path.to_str().unwrap());
+        assert!(history.curve().unwrap().points.is_empty());
+        history.record(&point(1, 500.0, Some(500.0), 0.0)).unwrap();
+        history.record(&point(2, 505.0, Some(500.0), 1.0)).unwrap();
+        assert_eq!(history.curve().unwrap().equity_change_usd(), Some(5.0));
+        let _ = fs::remove_file(history.path());
+    }
+}
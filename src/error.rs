@@ -3,7 +3,7 @@ use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum ConnectorError {
     #[error("WebSocket error: {0}")]
-    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
 
     #[error("HTTP request error: {0}")]
     Http(#[from] reqwest::Error),
@@ -31,3 +31,251 @@ pub enum ConnectorError {
 }
 
 pub type Result<T> = std::result::Result<T, ConnectorError>;
+
+// Boxed: the tungstenite error would make every connector `Result` several times larger
+impl From<tokio_tungstenite::tungstenite::Error> for ConnectorError {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        ConnectorError::WebSocket(Box::new(e))
+    }
+}
+
+impl ConnectorError {
+    /// Whether the venue rejected the credentials; retrying cannot succeed
+    pub fn is_auth(&self) -> bool {
+        match self {
+            ConnectorError::Http(e) if e.status().is_some_and(|s| matches!(s.as_u16(), 401 | 403)) => true,
+            _ => matches!(BotError::classify("", self.to_string()), BotError::Auth { .. }),
+        }
+    }
+}
+
+/// Error type for the bot, trading and opportunity layers.
+///
+/// Connector errors from both venues are classified on conversion so callers can
+/// branch on the error class (rate limit, auth, balance, network) instead of
+/// matching on message text.
+#[derive(Error, Debug)]
+pub enum BotError {
+    #[error("Rate limited by {venue}: {message}")]
+    RateLimited { venue: &'static str, message: String },
+
+    #[error("Authentication failed on {venue}: {message}")]
+    Auth { venue: &'static str, message: String },
+
+    #[error("Insufficient balance on {venue}: {message}")]
+    InsufficientBalance { venue: &'static str, message: String },
+
+    #[error("Network error talking to {venue}: {message}")]
+    Network { venue: &'static str, message: String },
+
+    #[error("{venue} API error: {message}")]
+    Exchange { venue: &'static str, message: String },
+
+    #[error("Execution error: {message}")]
+    Execution { message: String, recoverable: bool },
+
+    #[error(transparent)]
+    CloseVerification(#[from] crate::trading::CloseVerificationError),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Parse error: {0}")]
+    Parse(#[from] std::num::ParseFloatError),
+
+    #[error("System clock error: {0}")]
+    Clock(#[from] std::time::SystemTimeError),
+
+    #[error("Insufficient capital for minimum order on {symbol}: the smallest order both venues accept is ${minimum_usd:.2}, capital allows ${available_usd:.2}")]
+    InsufficientCapital { symbol: String, minimum_usd: f64, available_usd: f64 },
+
+    #[error("Risk limit: {0}")]
+    Risk(String),
+
+    #[error("Host clock is off by {offset_ms}ms from exchange time; fix system time (NTP) before trading")]
+    ClockSkew { offset_ms: i64 },
+
+    #[error("Another bot instance (pid {pid}) holds {path}; stop it first")]
+    InstanceLocked { pid: u32, path: String },
+
+    #[error("{0}")]
+    Other(String),
+}
+
+pub type BotResult<T> = std::result::Result<T, BotError>;
+
+impl BotError {
+    pub fn execution(message: impl Into<String>, recoverable: bool) -> Self {
+        BotError::Execution { message: message.into(), recoverable }
+    }
+
+    /// True for errors that should be retried with a longer rate-limit backoff
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self, BotError::RateLimited { .. })
+    }
+
+    /// True when the venue rejected the credentials (expired or revoked API key, bad
+    /// signature); nothing but new credentials fixes these
+    pub fn is_auth(&self) -> bool {
+        matches!(self, BotError::Auth { .. })
+    }
+
+    /// True for errors worth retrying (transient network/venue conditions)
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            BotError::RateLimited { .. } | BotError::Network { .. } | BotError::Exchange { .. } => true,
+            BotError::Execution { recoverable, .. } => *recoverable,
+            _ => false,
+        }
+    }
+
+    /// Classify an error message returned by a venue.
+    /// Venues report most failures as text bodies, so this is the single place that inspects them.
+    pub fn classify(venue: &'static str, message: String) -> Self {
+        let msg = message.to_lowercase();
+        let status = http_status(&message);
+        if status == Some(429) || msg.contains("too many requests") || msg.contains("rate limit") {
+            BotError::RateLimited { venue, message }
+        } else if matches!(status, Some(401 | 403))
+            || msg.contains("invalid api key")
+            || msg.contains("invalid signature")
+        {
+            BotError::Auth { venue, message }
+        } else if msg.contains("insufficient") || msg.contains("not enough margin") {
+            BotError::InsufficientBalance { venue, message }
+        } else if msg.contains("timed out")
+            || msg.contains("timeout")
+            || msg.contains("connection")
+            || msg.contains("error sending request")
+        {
+            BotError::Network { venue, message }
+        } else {
+            BotError::Exchange { venue, message }
+        }
+    }
+}
+
+/// HTTP status a venue error reports, written `HTTP 401` or as a status line
+/// (`401 Unauthorized`, as reqwest prints it). Other numbers in the message, such as
+/// sizes, prices or order ids, are not taken for a status.
+fn http_status(message: &str) -> Option<u16> {
+    let mut previous = "";
+    for word in message.split(|c: char| !c.is_ascii_alphanumeric()).filter(|word| !word.is_empty()) {
+        if word.len() == 3 {
+            if let Ok(status) = reqwest::StatusCode::from_bytes(word.as_bytes()) {
+                let end = word.as_ptr() as usize - message.as_ptr() as usize + word.len();
+                let after = message[end..].trim_start_matches([' ', ':']);
+                if previous.eq_ignore_ascii_case("http") || status.canonical_reason().is_some_and(|reason| after.starts_with(reason)) {
+                    return Some(status.as_u16());
+                }
+            }
+        }
+        previous = word;
+    }
+    None
+}
+
+impl From<ConnectorError> for BotError {
+    fn from(err: ConnectorError) -> Self {
+        const VENUE: &str = "Extended";
+        match &err {
+            ConnectorError::Http(e) => {
+                if let Some(status) = e.status() {
+                    match status.as_u16() {
+                        429 => return BotError::RateLimited { venue: VENUE, message: err.to_string() },
+                        401 | 403 => return BotError::Auth { venue: VENUE, message: err.to_string() },
+                        _ => {}
+                    }
+                }
+                if e.is_timeout() || e.is_connect() || e.is_request() {
+                    return BotError::Network { venue: VENUE, message: err.to_string() };
+                }
+                BotError::classify(VENUE, err.to_string())
+            }
+            ConnectorError::WebSocket(_) | ConnectorError::ConnectionClosed => {
+                BotError::Network { venue: VENUE, message: err.to_string() }
+            }
+            _ => BotError::classify(VENUE, err.to_string()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for BotError {
+    fn from(err: anyhow::Error) -> Self {
+        // Pacifica client errors are anyhow-based; include the context chain
+        BotError::classify("Pacifica", format!("{:#}", err))
+    }
+}
+
+impl From<String> for BotError {
+    fn from(message: String) -> Self {
+        BotError::Other(message)
+    }
+}
+
+impl From<&str> for BotError {
+    fn from(message: &str) -> Self {
+        BotError::Other(message.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_messages() {
+        assert!(BotError::classify("Extended", "HTTP 429 Too Many Requests".into()).is_rate_limited());
+        assert!(matches!(
+            BotError::classify("Pacifica", "HTTP 401: Unauthorized".into()),
+            BotError::Auth { venue: "Pacifica", .. }
+        ));
+        assert!(matches!(
+            BotError::classify("Pacifica", "Order placement failed: 403 Forbidden - agent not bound".into()),
+            BotError::Auth { .. }
+        ));
+        // Numbers that are not a status
+        assert!(matches!(
+            BotError::classify("Extended", "HTTP 400 Bad Request: size 401 above the maximum".into()),
+            BotError::Exchange { .. }
+        ));
+        assert!(matches!(
+            BotError::classify("Pacifica", "Order 403 not found".into()),
+            BotError::Exchange { .. }
+        ));
+        assert!(matches!(
+            BotError::classify("Extended", "Insufficient collateral".into()),
+            BotError::InsufficientBalance { .. }
+        ));
+        assert!(matches!(
+            BotError::classify("Extended", "operation timed out".into()),
+            BotError::Network { .. }
+        ));
+        assert!(matches!(
+            BotError::classify("Extended", "Invalid order".into()),
+            BotError::Exchange { .. }
+        ));
+    }
+
+    #[test]
+    fn test_connector_and_anyhow_conversions() {
+        let err: BotError = ConnectorError::ApiError("HTTP 429: slow down".into()).into();
+        assert!(matches!(err, BotError::RateLimited { venue: "Extended", .. }));
+
+        let err: BotError = anyhow::anyhow!("Failed to create order: insufficient balance").into();
+        assert!(matches!(err, BotError::InsufficientBalance { venue: "Pacifica", .. }));
+
+        assert!(ConnectorError::ApiError("HTTP 401: API key expired".into()).is_auth());
+        assert!(!ConnectorError::ApiError("HTTP 400: bad price".into()).is_auth());
+
+        let err: BotError = ConnectorError::ConnectionClosed.into();
+        assert!(err.is_retryable());
+        assert!(!BotError::Config("bad".into()).is_retryable());
+    }
+}
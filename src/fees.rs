@@ -0,0 +1,225 @@
+//! Venue fee tiers
+//!
+//! With `fees.enabled`, trading costs in opportunity ranking (entry cost, break-even,
+//! `apr_minus_cost`, early rotation) and fees missing from execution reports are priced
+//! from per-tier maker and taker rates for each venue instead of the flat
+//! `rotation.taker_fee_pct`. Pacifica's tier is read from its account info (`fee_tier`)
+//! whenever the bot fetches or streams it, unless `fees.pacifica_tier` pins one;
+//! Extended's is `fees.extended_tier`. The tables default to the venues' published
+//! schedules and can be replaced in the config. A negative maker rate is a rebate.
+//!
+//! Every order the bot sends takes liquidity, including spread-capture orders (FOK/IOC
+//! limits priced through the touch), so maker rates only apply with
+//! `fees.capture_lead_as_maker`, for accounts credited maker fees on those orders.
+use crate::capital::Venue;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicI64, Ordering};
+use tracing::info;
+
+/// Maker and taker fee (% of notional) of one tier
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeeTier {
+    pub tier: i64,
+    /// Negative for a rebate
+    pub maker_pct: f64,
+    pub taker_pct: f64,
+}
+
+impl FeeTier {
+    const fn new(tier: i64, maker_pct: f64, taker_pct: f64) -> Self {
+        Self { tier, maker_pct, taker_pct }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub extended_tier: i64,
+    /// Pacifica tier to use instead of the one reported by the account
+    #[serde(default)]
+    pub pacifica_tier: Option<i64>,
+    /// Charge the leading leg of a spread-capture open at the maker rate
+    #[serde(default)]
+    pub capture_lead_as_maker: bool,
+    #[serde(default = "default_extended_tiers")]
+    pub extended_tiers: Vec<FeeTier>,
+    #[serde(default = "default_pacifica_tiers")]
+    pub pacifica_tiers: Vec<FeeTier>,
+}
+
+fn default_extended_tiers() -> Vec<FeeTier> {
+    vec![FeeTier::new(0, 0.0, 0.025)]
+}
+
+/// Tier 0 is Pacifica's base tier
+fn default_pacifica_tiers() -> Vec<FeeTier> {
+    vec![
+        FeeTier::new(0, 0.015, 0.04),
+        FeeTier::new(1, 0.012, 0.038),
+        FeeTier::new(2, 0.009, 0.036),
+        FeeTier::new(3, 0.006, 0.034),
+        FeeTier::new(4, 0.003, 0.032),
+        FeeTier::new(5, 0.0, 0.03),
+        FeeTier::new(6, 0.0, 0.029),
+        FeeTier::new(7, 0.0, 0.028),
+    ]
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            extended_tier: 0,
+            pacifica_tier: None,
+            capture_lead_as_maker: false,
+            extended_tiers: default_extended_tiers(),
+            pacifica_tiers: default_pacifica_tiers(),
+        }
+    }
+}
+
+/// Rates of `tier`, or of the highest tier below it when the table skips it; the
+/// lowest tier when `tier` is below them all
+fn lookup(tiers: &[FeeTier], tier: i64) -> Option<FeeTier> {
+    let lowest = tiers.iter().min_by_key(|t| t.tier).copied();
+    tiers.iter().filter(|t| t.tier <= tier).max_by_key(|t| t.tier).copied().or(lowest)
+}
+
+impl FeeConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for (name, tiers) in [("extended_tiers", &self.extended_tiers), ("pacifica_tiers", &self.pacifica_tiers)] {
+            if tiers.is_empty() {
+                return Err(format!("fees.{} must list at least one tier", name));
+            }
+            for tier in tiers {
+                if !(0.0..=1.0).contains(&tier.taker_pct) || !(-1.0..=tier.taker_pct).contains(&tier.maker_pct) {
+                    return Err(format!(
+                        "fees.{} tier {}: taker_pct must be between 0 and 1 and maker_pct between -1 and taker_pct",
+                        name, tier.tier
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Rates for the configured Extended tier and Pacifica's pinned tier, else
+    /// `detected_pacifica_tier`, else its lowest
+    pub fn schedule(&self, detected_pacifica_tier: Option<i64>) -> FeeSchedule {
+        let pacifica_tier = self.pacifica_tier.or(detected_pacifica_tier).unwrap_or(UNKNOWN_TIER);
+        let fallback = FeeTier::new(0, 0.0, 0.0);
+        FeeSchedule {
+            extended: lookup(&self.extended_tiers, self.extended_tier).unwrap_or(fallback),
+            pacifica: lookup(&self.pacifica_tiers, pacifica_tier).unwrap_or(fallback),
+            capture_lead_as_maker: self.capture_lead_as_maker,
+        }
+    }
+}
+
+/// Rates the cost model charges on each venue
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FeeSchedule {
+    pub extended: FeeTier,
+    pub pacifica: FeeTier,
+    pub capture_lead_as_maker: bool,
+}
+
+impl FeeSchedule {
+    /// `taker_pct` on every order of both venues
+    pub fn flat(taker_pct: f64) -> Self {
+        let tier = FeeTier::new(0, taker_pct, taker_pct);
+        Self { extended: tier, pacifica: tier, capture_lead_as_maker: false }
+    }
+
+    pub fn rates(&self, venue: Venue) -> FeeTier {
+        match venue {
+            Venue::Extended => self.extended,
+            Venue::Pacifica => self.pacifica,
+        }
+    }
+
+    /// Fee (%) of one order on `venue`
+    pub fn fee_pct(&self, venue: Venue, maker: bool) -> f64 {
+        let rates = self.rates(venue);
+        if maker { rates.maker_pct } else { rates.taker_pct }
+    }
+
+    /// Fees (% of the notional of one leg) of opening both legs. With a maker lead
+    /// (`spread_capture` and `capture_lead_as_maker`), the lead's venue is only picked
+    /// from the books at open, so the dearer of the two possible leads is assumed.
+    pub fn entry_fee_pct(&self, spread_capture: bool) -> f64 {
+        let takers = self.extended.taker_pct + self.pacifica.taker_pct;
+        if !(spread_capture && self.capture_lead_as_maker) {
+            return takers;
+        }
+        let extended_lead = self.extended.maker_pct + self.pacifica.taker_pct;
+        let pacifica_lead = self.pacifica.maker_pct + self.extended.taker_pct;
+        extended_lead.max(pacifica_lead)
+    }
+
+    /// Fees (% of the notional of one leg) of closing both legs at market
+    pub fn exit_fee_pct(&self) -> f64 {
+        self.extended.taker_pct + self.pacifica.taker_pct
+    }
+
+    /// Fees (% of the notional of one leg) of an open and its close
+    pub fn round_trip_fee_pct(&self, spread_capture: bool) -> f64 {
+        self.entry_fee_pct(spread_capture) + self.exit_fee_pct()
+    }
+}
+
+const UNKNOWN_TIER: i64 = i64::MIN;
+static PACIFICA_TIER: AtomicI64 = AtomicI64::new(UNKNOWN_TIER);
+
+/// Remember the fee tier from the latest Pacifica account info
+pub fn record_pacifica_tier(tier: i64) {
+    let previous = PACIFICA_TIER.swap(tier, Ordering::Relaxed);
+    if previous != tier {
+        info!("Pacifica fee tier: {}", tier);
+    }
+}
+
+/// Fee tier from the latest Pacifica account info, if any was read
+pub fn pacifica_tier() -> Option<i64> {
+    Some(PACIFICA_TIER.load(Ordering::Relaxed)).filter(|tier| *tier != UNKNOWN_TIER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tier_lookup_and_maker_lead() {
+        let config = FeeConfig {
+            enabled: true,
+            extended_tiers: vec![FeeTier::new(0, 0.0, 0.025), FeeTier::new(2, -0.005, 0.02)],
+            ..FeeConfig::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let base = config.schedule(None);
+        assert_eq!(base.extended.tier, 0);
+        assert_eq!(base.pacifica.taker_pct, 0.04);
+        assert!((base.entry_fee_pct(true) - 0.065).abs() < 1e-12);
+
+        // A tier missing from the table takes the one below; a pinned tier wins
+        let rebate = FeeConfig { extended_tier: 3, capture_lead_as_maker: true, ..config.clone() }.schedule(Some(4));
+        assert_eq!(rebate.extended.maker_pct, -0.005);
+        assert_eq!(rebate.pacifica.tier, 4);
+        assert_eq!(FeeConfig { pacifica_tier: Some(7), ..config.clone() }.schedule(Some(4)).pacifica.taker_pct, 0.028);
+
+        // Maker lead on either venue, the dearer counted: max(-0.005 + 0.032, 0.003 + 0.02)
+        assert!((rebate.entry_fee_pct(true) - 0.027).abs() < 1e-12);
+        assert!((rebate.entry_fee_pct(false) - 0.052).abs() < 1e-12);
+        assert!((rebate.round_trip_fee_pct(true) - 0.079).abs() < 1e-12);
+        assert_eq!(FeeSchedule::flat(0.05).round_trip_fee_pct(true), 0.2);
+
+        let inverted = FeeConfig { pacifica_tiers: vec![FeeTier::new(0, 0.05, 0.04)], ..config };
+        assert!(inverted.validate().is_err());
+    }
+}
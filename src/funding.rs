@@ -0,0 +1,377 @@
+//! Funding rate normalization across Extended and Pacifica
+//!
+//! Both venues settle funding hourly, but they expose different numbers:
+//! - Extended `fundingRate` (market stats) is the rate accruing for the current hour,
+//!   i.e. what the next settlement will charge, as a decimal per interval.
+//! - Pacifica `funding_rate` is the last settled rate; `next_funding_rate` is the
+//!   predicted rate for the next settlement, also a decimal per interval.
+//!
+//! `FundingForecast` always uses the forward-looking number so both legs are
+//! compared on the same basis: the expected rate over the next settlement and over
+//! a hold window, assuming the predicted rate persists.
+//!
+//! Neither venue's market API publishes its settlement interval, so intervals come
+//! from `FundingIntervalConfig` and every annualization goes through `apr_from_rate`.
+//! Nor do they publish their funding rate caps: `FundingCapConfig` holds them, and an
+//! advertised rate beyond its cap is clamped (`FundingForecast::capped`) before it is
+//! annualized, since that is the most the venue will actually settle.
+use crate::capital::Venue;
+use crate::pacifica::PacificaFundingRate;
+use crate::types::FundingRateInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Hours used to annualize rates (no leap years, matching both venues' UIs)
+pub const HOURS_PER_YEAR: f64 = 24.0 * 365.0;
+/// Extended settles funding every hour
+pub const EXTENDED_FUNDING_INTERVAL_HOURS: f64 = 1.0;
+/// Pacifica settles funding every hour
+pub const PACIFICA_FUNDING_INTERVAL_HOURS: f64 = 1.0;
+
+/// Annualize a rate paid every `interval_hours`. The result keeps the unit of `rate`
+/// (decimal in, decimal out; percent in, percent out).
+pub fn apr_from_rate(rate: f64, interval_hours: f64) -> f64 {
+    if interval_hours <= 0.0 {
+        return 0.0;
+    }
+    rate / interval_hours * HOURS_PER_YEAR
+}
+
+/// Hours between funding settlements, per venue with optional per-symbol overrides
+/// keyed by base symbol (e.g. "BTC")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingIntervalConfig {
+    #[serde(default = "default_extended_hours")]
+    pub extended_hours: f64,
+    #[serde(default = "default_pacifica_hours")]
+    pub pacifica_hours: f64,
+    #[serde(default)]
+    pub extended_symbol_hours: HashMap<String, f64>,
+    #[serde(default)]
+    pub pacifica_symbol_hours: HashMap<String, f64>,
+}
+
+fn default_extended_hours() -> f64 {
+    EXTENDED_FUNDING_INTERVAL_HOURS
+}
+
+fn default_pacifica_hours() -> f64 {
+    PACIFICA_FUNDING_INTERVAL_HOURS
+}
+
+impl Default for FundingIntervalConfig {
+    fn default() -> Self {
+        Self {
+            extended_hours: default_extended_hours(),
+            pacifica_hours: default_pacifica_hours(),
+            extended_symbol_hours: HashMap::new(),
+            pacifica_symbol_hours: HashMap::new(),
+        }
+    }
+}
+
+impl FundingIntervalConfig {
+    /// Settlement interval of `symbol` on `venue`
+    pub fn hours(&self, venue: Venue, symbol: &str) -> f64 {
+        let (default, overrides) = match venue {
+            Venue::Extended => (self.extended_hours, &self.extended_symbol_hours),
+            Venue::Pacifica => (self.pacifica_hours, &self.pacifica_symbol_hours),
+        };
+        overrides.get(symbol).copied().unwrap_or(default)
+    }
+
+    /// First interval that is not a positive number of hours, as (config key, value)
+    pub fn invalid_entry(&self) -> Option<(String, f64)> {
+        let defaults = [
+            ("extended_hours".to_string(), self.extended_hours),
+            ("pacifica_hours".to_string(), self.pacifica_hours),
+        ];
+        let overrides = self
+            .extended_symbol_hours
+            .iter()
+            .map(|(s, h)| (format!("extended_symbol_hours.{}", s), *h))
+            .chain(self.pacifica_symbol_hours.iter().map(|(s, h)| (format!("pacifica_symbol_hours.{}", s), *h)));
+        defaults.into_iter().chain(overrides).find(|(_, h)| !(h.is_finite() && *h > 0.0))
+    }
+}
+
+/// Largest funding rate each venue settles, in % per settlement interval, with
+/// optional per-symbol overrides keyed by base symbol (0 = uncapped)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FundingCapConfig {
+    #[serde(default)]
+    pub extended_max_rate_pct: f64,
+    #[serde(default)]
+    pub pacifica_max_rate_pct: f64,
+    #[serde(default)]
+    pub extended_symbol_max_rate_pct: HashMap<String, f64>,
+    #[serde(default)]
+    pub pacifica_symbol_max_rate_pct: HashMap<String, f64>,
+}
+
+impl FundingCapConfig {
+    /// Cap of `symbol` on `venue` as a decimal rate per interval, if capped
+    pub fn max_rate(&self, venue: Venue, symbol: &str) -> Option<f64> {
+        let (default, overrides) = match venue {
+            Venue::Extended => (self.extended_max_rate_pct, &self.extended_symbol_max_rate_pct),
+            Venue::Pacifica => (self.pacifica_max_rate_pct, &self.pacifica_symbol_max_rate_pct),
+        };
+        let pct = overrides.get(symbol).copied().unwrap_or(default);
+        (pct > 0.0).then_some(pct / 100.0)
+    }
+
+    /// First cap that is negative or not a number, as (config key, value)
+    pub fn invalid_entry(&self) -> Option<(String, f64)> {
+        let defaults = [
+            ("extended_max_rate_pct".to_string(), self.extended_max_rate_pct),
+            ("pacifica_max_rate_pct".to_string(), self.pacifica_max_rate_pct),
+        ];
+        let overrides = self
+            .extended_symbol_max_rate_pct
+            .iter()
+            .map(|(s, c)| (format!("extended_symbol_max_rate_pct.{}", s), *c))
+            .chain(self.pacifica_symbol_max_rate_pct.iter().map(|(s, c)| (format!("pacifica_symbol_max_rate_pct.{}", s), *c)));
+        defaults.into_iter().chain(overrides).find(|(_, c)| !(c.is_finite() && *c >= 0.0))
+    }
+}
+
+/// Expected funding for one venue. Rates are decimals (0.0001 = 0.01%);
+/// positive means longs pay shorts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FundingForecast {
+    pub venue: Venue,
+    /// Rate expected at the next settlement, per interval
+    pub next_settlement_rate: f64,
+    pub interval_hours: f64,
+}
+
+impl FundingForecast {
+    pub fn new(venue: Venue, next_settlement_rate: f64, interval_hours: f64) -> Self {
+        Self {
+            venue,
+            next_settlement_rate,
+            interval_hours,
+        }
+    }
+
+    /// Forecast from Extended market stats (current-hour rate)
+    pub fn from_extended(info: &FundingRateInfo) -> Self {
+        Self::new(Venue::Extended, info.rate, EXTENDED_FUNDING_INTERVAL_HOURS)
+    }
+
+    /// Forecast from Pacifica market info (predicted next rate, not the last settled one)
+    pub fn from_pacifica(rate: &PacificaFundingRate) -> Self {
+        Self::new(
+            Venue::Pacifica,
+            rate.next_funding_rate.parse().unwrap_or(0.0),
+            PACIFICA_FUNDING_INTERVAL_HOURS,
+        )
+    }
+
+    /// Same forecast for a venue settling every `interval_hours`
+    pub fn with_interval_hours(self, interval_hours: f64) -> Self {
+        Self { interval_hours, ..self }
+    }
+
+    /// Same forecast with the rate clamped to `±max_rate` per interval
+    pub fn capped(self, max_rate: Option<f64>) -> Self {
+        match max_rate {
+            Some(max) => Self {
+                next_settlement_rate: self.next_settlement_rate.clamp(-max, max),
+                ..self
+            },
+            None => self,
+        }
+    }
+
+    /// Expected rate per hour
+    pub fn hourly_rate(&self) -> f64 {
+        if self.interval_hours <= 0.0 {
+            return 0.0;
+        }
+        self.next_settlement_rate / self.interval_hours
+    }
+
+    /// Expected cumulative rate over `hours` (e.g. the configured hold time)
+    pub fn rate_over_hours(&self, hours: f64) -> f64 {
+        self.hourly_rate() * hours
+    }
+
+    /// Annualized rate in percent
+    pub fn apr_pct(&self) -> f64 {
+        apr_from_rate(self.next_settlement_rate, self.interval_hours) * 100.0
+    }
+
+    /// Funding (USD) a leg of `notional_usd` receives (+) or pays (-) over `hours` at this rate
+    pub fn leg_funding_usd(&self, is_long: bool, notional_usd: f64, hours: f64) -> f64 {
+        let paid_by_longs = self.rate_over_hours(hours) * notional_usd;
+        if is_long { -paid_by_longs } else { paid_by_longs }
+    }
+}
+
+/// Net funding APR (%) of being long on `long` and short on `short`:
+/// the long leg pays its rate, the short leg receives its rate.
+pub fn net_apr_pct(long: &FundingForecast, short: &FundingForecast) -> f64 {
+    short.apr_pct() - long.apr_pct()
+}
+
+/// Whether a differential that paid at scan time (`scan_net_apr_pct` > 0) no longer
+/// pays at the rates just fetched: opening in the scanned direction would pay funding
+pub fn funding_flipped(scan_net_apr_pct: f64, live_net_apr_pct: f64) -> bool {
+    scan_net_apr_pct > 0.0 && live_net_apr_pct <= 0.0
+}
+
+/// Net expected funding (decimal of notional) of the same pair over a hold window
+pub fn net_rate_over_hours(long: &FundingForecast, short: &FundingForecast, hours: f64) -> f64 {
+    short.rate_over_hours(hours) - long.rate_over_hours(hours)
+}
+
+/// Time-weighted average of the funding APRs seen on both legs while a position is held
+///
+/// Each sample is taken to hold until the next one, so every rate counts for as long
+/// as it was in force whatever the sampling schedule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FundingRateAverage {
+    /// Sum of APR (%) x hours in force, per venue
+    pub extended_apr_hours: f64,
+    pub pacifica_apr_hours: f64,
+    /// Hours covered by the samples so far
+    pub hours: f64,
+    /// Latest sample (unix seconds and APRs in %), credited once the next one arrives
+    pub last_sampled_at: Option<u64>,
+    pub last_extended_apr: f64,
+    pub last_pacifica_apr: f64,
+}
+
+impl FundingRateAverage {
+    /// Record the APRs (%) in force on each venue at `at`
+    pub fn record(&mut self, at: u64, extended_apr: f64, pacifica_apr: f64) {
+        if let Some(last) = self.last_sampled_at {
+            let hours = at.saturating_sub(last) as f64 / 3600.0;
+            self.extended_apr_hours += self.last_extended_apr * hours;
+            self.pacifica_apr_hours += self.last_pacifica_apr * hours;
+            self.hours += hours;
+        }
+        self.last_sampled_at = Some(at);
+        self.last_extended_apr = extended_apr;
+        self.last_pacifica_apr = pacifica_apr;
+    }
+
+    /// Average (Extended, Pacifica) APRs (%), or the only sample's before time has passed
+    pub fn average_aprs(&self) -> Option<(f64, f64)> {
+        self.last_sampled_at?;
+        if self.hours <= 0.0 {
+            return Some((self.last_extended_apr, self.last_pacifica_apr));
+        }
+        Some((self.extended_apr_hours / self.hours, self.pacifica_apr_hours / self.hours))
+    }
+
+    /// Average net APR (%) of holding the long leg on Extended (or on Pacifica)
+    pub fn average_net_apr(&self, long_on_extended: bool) -> Option<f64> {
+        let (extended, pacifica) = self.average_aprs()?;
+        Some(if long_on_extended { pacifica - extended } else { extended - pacifica })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_funding_rate_average_is_time_weighted() {
+        let mut average = FundingRateAverage::default();
+        assert_eq!(average.average_net_apr(true), None);
+
+        average.record(0, 10.0, 40.0);
+        assert_eq!(average.average_net_apr(true), Some(30.0));
+        // 3h at (10, 40), then 1h at (20, 20)
+        average.record(3 * 3600, 20.0, 20.0);
+        average.record(4 * 3600, 0.0, 0.0);
+        assert_eq!(average.average_aprs(), Some((12.5, 35.0)));
+        assert_eq!(average.average_net_apr(true), Some(22.5));
+        assert_eq!(average.average_net_apr(false), Some(-22.5));
+    }
+
+    #[test]
+    fn test_hourly_rate_annualization() {
+        let f = FundingForecast::new(Venue::Extended, 0.0001, 1.0);
+        assert!((f.apr_pct() - 87.6).abs() < 1e-9);
+        assert!((f.rate_over_hours(24.0) - 0.0024).abs() < 1e-12);
+
+        // An 8h venue with the same per-settlement rate accrues 8x slower
+        let eight_hourly = FundingForecast::new(Venue::Pacifica, 0.0001, 8.0);
+        assert!((eight_hourly.apr_pct() - 10.95).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_leg_funding_usd() {
+        // $1000 short over 24h at 0.01% an hour receives 0.24%; the long pays it
+        let f = FundingForecast::new(Venue::Extended, 0.0001, 1.0);
+        assert!((f.leg_funding_usd(false, 1_000.0, 24.0) - 2.4).abs() < 1e-9);
+        assert!((f.leg_funding_usd(true, 1_000.0, 24.0) + 2.4).abs() < 1e-9);
+        // Negative rates flip who pays
+        let negative = FundingForecast::new(Venue::Pacifica, -0.0001, 8.0);
+        assert!((negative.leg_funding_usd(true, 1_000.0, 24.0) - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_apr_from_rate_and_interval_overrides() {
+        assert!((apr_from_rate(0.0001, 1.0) - 0.876).abs() < 1e-12);
+        assert!((apr_from_rate(0.01, 8.0) - 10.95).abs() < 1e-9);
+        assert_eq!(apr_from_rate(0.01, 0.0), 0.0);
+
+        let mut intervals = FundingIntervalConfig::default();
+        intervals.pacifica_symbol_hours.insert("BTC".to_string(), 8.0);
+        assert_eq!(intervals.hours(Venue::Pacifica, "BTC"), 8.0);
+        assert_eq!(intervals.hours(Venue::Pacifica, "ETH"), 1.0);
+        assert_eq!(intervals.hours(Venue::Extended, "BTC"), 1.0);
+        assert!(intervals.invalid_entry().is_none());
+
+        intervals.extended_symbol_hours.insert("SOL".to_string(), 0.0);
+        assert_eq!(intervals.invalid_entry(), Some(("extended_symbol_hours.SOL".to_string(), 0.0)));
+    }
+
+    #[test]
+    fn test_funding_caps_clamp_advertised_rates() {
+        let mut caps = FundingCapConfig { pacifica_max_rate_pct: 0.5, ..Default::default() };
+        caps.extended_symbol_max_rate_pct.insert("DOGE".to_string(), 1.0);
+        assert_eq!(caps.max_rate(Venue::Pacifica, "BTC"), Some(0.005));
+        assert_eq!(caps.max_rate(Venue::Extended, "BTC"), None);
+        assert_eq!(caps.max_rate(Venue::Extended, "DOGE"), Some(0.01));
+
+        let extreme = FundingForecast::new(Venue::Pacifica, -0.02, 1.0);
+        assert_eq!(extreme.capped(caps.max_rate(Venue::Pacifica, "BTC")).next_settlement_rate, -0.005);
+        assert_eq!(extreme.capped(None), extreme);
+        assert!(caps.invalid_entry().is_none());
+
+        caps.pacifica_symbol_max_rate_pct.insert("SOL".to_string(), -1.0);
+        assert_eq!(caps.invalid_entry(), Some(("pacifica_symbol_max_rate_pct.SOL".to_string(), -1.0)));
+    }
+
+    #[test]
+    fn test_pacifica_uses_next_rate() {
+        let rate = PacificaFundingRate::from_market_info(
+            "BTC".to_string(),
+            "0.0005".to_string(),
+            "0.0001".to_string(),
+        );
+        let f = FundingForecast::from_pacifica(&rate);
+        assert_eq!(f.next_settlement_rate, 0.0001);
+        assert_eq!(f.venue, Venue::Pacifica);
+    }
+
+    #[test]
+    fn test_net_apr_long_low_short_high() {
+        let ext = FundingForecast::new(Venue::Extended, -0.00005, 1.0);
+        let pac = FundingForecast::new(Venue::Pacifica, 0.0001, 1.0);
+        // Long Extended receives 0.005%/h, short Pacifica receives 0.01%/h
+        assert!((net_apr_pct(&ext, &pac) - 131.4).abs() < 1e-9);
+        assert!((net_rate_over_hours(&ext, &pac, 10.0) - 0.0015).abs() < 1e-12);
+        assert!((net_apr_pct(&pac, &ext) + 131.4).abs() < 1e-9);
+
+        // The scanned direction now pays: the flip guard trips
+        assert!(funding_flipped(131.4, net_apr_pct(&pac, &ext)));
+        assert!(!funding_flipped(131.4, net_apr_pct(&ext, &pac)));
+        assert!(!funding_flipped(-5.0, -10.0));
+    }
+}
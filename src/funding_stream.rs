@@ -0,0 +1,189 @@
+//! Streamed funding rates
+//!
+//! With `funding_stream.enabled`, the bot subscribes to Extended's funding stream and
+//! Pacifica's `prices` channel and keeps the latest rate per venue and symbol in a
+//! `FundingRateCache`. Held-position funding then comes from the cache while it is
+//! fresher than `max_age_seconds` (REST otherwise), and between cycles the bot checks
+//! it every `check_interval_seconds`: when the held direction's net APR drops below
+//! `flip_threshold_apr_pct`, the next cycle (and its early rotation check) runs at once
+//! instead of after the monitoring interval.
+use crate::capital::Venue;
+use crate::funding::{FundingCapConfig, FundingForecast, FundingIntervalConfig};
+use crate::opportunity::{Direction, Environment};
+use crate::pacifica::PriceStreamClient;
+use crate::symbols::SymbolMap;
+use crate::websocket::ConnectionEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::debug;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingStreamConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Streamed rates older than this fall back to REST
+    #[serde(default = "default_max_age_seconds")]
+    pub max_age_seconds: u64,
+    /// How often the sleep between cycles checks the held position's streamed net APR
+    #[serde(default = "default_check_interval_seconds")]
+    pub check_interval_seconds: u64,
+    /// Held net APR (%) below which a cycle runs immediately
+    #[serde(default)]
+    pub flip_threshold_apr_pct: f64,
+}
+
+fn default_max_age_seconds() -> u64 {
+    120
+}
+
+fn default_check_interval_seconds() -> u64 {
+    10
+}
+
+impl Default for FundingStreamConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_age_seconds: default_max_age_seconds(),
+            check_interval_seconds: default_check_interval_seconds(),
+            flip_threshold_apr_pct: 0.0,
+        }
+    }
+}
+
+impl FundingStreamConfig {
+    pub fn max_age(&self) -> Duration {
+        Duration::from_secs(self.max_age_seconds)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct StreamedRate {
+    rate: f64,
+    received: Instant,
+}
+
+/// Latest streamed funding rate (decimal, per settlement interval) per venue and base
+/// symbol ("BTC"), with the local time it was received
+#[derive(Debug, Clone, Default)]
+pub struct FundingRateCache {
+    rates: Arc<RwLock<HashMap<(Venue, String), StreamedRate>>>,
+}
+
+impl FundingRateCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to both venues' funding streams in the background and feed a new cache.
+    /// Each stream reconnects on its own and stops once the cache is dropped. Rates are
+    /// keyed by canonical symbol, mapped from each venue's market names by `symbols`.
+    pub fn start(environment: Environment, extended_api_key: Option<String>, symbols: SymbolMap) -> Self {
+        let cache = Self::new();
+
+        let (events, _) = broadcast::channel::<ConnectionEvent>(16);
+        let mut extended = environment
+            .extended_ws_client(extended_api_key)
+            .subscribe_all_funding_rates_reconnecting(events);
+        let weak = Arc::downgrade(&cache.rates);
+        let extended_symbols = symbols.clone();
+        tokio::spawn(async move {
+            while let Some(info) = extended.recv().await {
+                let Some(rates) = weak.upgrade() else { break };
+                if let Some(symbol) = extended_symbols.symbol(Venue::Extended, &info.market) {
+                    Self { rates }.update(Venue::Extended, &symbol, info.rate);
+                }
+            }
+        });
+
+        let mut pacifica = PriceStreamClient::new(environment.is_testnet()).subscribe_reconnecting();
+        let weak = Arc::downgrade(&cache.rates);
+        tokio::spawn(async move {
+            while let Some(update) = pacifica.recv().await {
+                let Some(rates) = weak.upgrade() else { break };
+                if let (Some(rate), Some(symbol)) = (update.next_funding_rate(), symbols.symbol(Venue::Pacifica, &update.symbol)) {
+                    Self { rates }.update(Venue::Pacifica, &symbol, rate);
+                }
+            }
+        });
+
+        cache
+    }
+
+    pub fn update(&self, venue: Venue, symbol: &str, rate: f64) {
+        debug!("Streamed {:?} funding for {}: {}", venue, symbol, rate);
+        self.rates
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert((venue, symbol.to_string()), StreamedRate { rate, received: Instant::now() });
+    }
+
+    /// Rate received within `max_age`
+    pub fn fresh(&self, venue: Venue, symbol: &str, max_age: Duration) -> Option<f64> {
+        let rates = self.rates.read().unwrap_or_else(PoisonError::into_inner);
+        let streamed = rates.get(&(venue, symbol.to_string()))?;
+        (streamed.received.elapsed() <= max_age).then_some(streamed.rate)
+    }
+
+    /// Fresh rate as a forecast with the venue's configured interval and cap
+    pub fn forecast(
+        &self,
+        venue: Venue,
+        symbol: &str,
+        max_age: Duration,
+        intervals: &FundingIntervalConfig,
+        caps: &FundingCapConfig,
+    ) -> Option<FundingForecast> {
+        let rate = self.fresh(venue, symbol, max_age)?;
+        Some(FundingForecast::new(venue, rate, intervals.hours(venue, symbol)).capped(caps.max_rate(venue, symbol)))
+    }
+
+    /// Net APR (%) of holding `direction` on `symbol` from fresh rates on both venues
+    pub fn net_apr(
+        &self,
+        symbol: &str,
+        direction: Direction,
+        max_age: Duration,
+        intervals: &FundingIntervalConfig,
+        caps: &FundingCapConfig,
+    ) -> Option<f64> {
+        let extended = self.forecast(Venue::Extended, symbol, max_age, intervals, caps)?.apr_pct();
+        let pacifica = self.forecast(Venue::Pacifica, symbol, max_age, intervals, caps)?.apr_pct();
+        Some(if direction.long_on_extended() { pacifica - extended } else { extended - pacifica })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_serves_fresh_rates_only() {
+        let cache = FundingRateCache::new();
+        let intervals = FundingIntervalConfig::default();
+        let caps = FundingCapConfig::default();
+        let max_age = Duration::from_secs(60);
+
+        cache.update(Venue::Extended, "BTC", 0.0001);
+        assert_eq!(cache.fresh(Venue::Extended, "BTC", max_age), Some(0.0001));
+        assert_eq!(cache.fresh(Venue::Pacifica, "BTC", max_age), None);
+        assert_eq!(cache.net_apr("BTC", Direction::LongExtendedShortPacifica, max_age, &intervals, &caps), None);
+
+        // Extended longs pay more than Pacifica longs: shorting Extended earns the difference
+        cache.update(Venue::Pacifica, "BTC", 0.0);
+        let short_extended = cache
+            .net_apr("BTC", Direction::LongPacificaShortExtended, max_age, &intervals, &caps)
+            .unwrap();
+        assert!(short_extended > 0.0);
+        assert_eq!(
+            cache.net_apr("BTC", Direction::LongExtendedShortPacifica, max_age, &intervals, &caps),
+            Some(-short_extended)
+        );
+
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.fresh(Venue::Extended, "BTC", Duration::from_millis(1)), None);
+    }
+}
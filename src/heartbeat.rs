@@ -0,0 +1,98 @@
+//! Liveness heartbeat for process supervisors
+//!
+//! The bot rewrites a small JSON file on every loop iteration with the time of the
+//! iteration and of the last successful scan and reconcile. A supervisor (systemd
+//! watchdog script, Docker HEALTHCHECK, Kubernetes exec probe) runs
+//! `extended_connector --check-health` to restart the bot when the loop wedges.
+use crate::error::BotError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub const DEFAULT_HEARTBEAT_FILE: &str = "heartbeat.json";
+/// Default staleness limit for `--check-health`: two 15-minute monitoring cycles
+pub const DEFAULT_MAX_HEARTBEAT_AGE_SECS: u64 = 30 * 60;
+
+/// Heartbeat file path, overridable with `HEARTBEAT_FILE_PATH`
+pub fn resolve_heartbeat_path() -> String {
+    std::env::var("HEARTBEAT_FILE_PATH").unwrap_or_else(|_| DEFAULT_HEARTBEAT_FILE.to_string())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Heartbeat {
+    pub pid: u32,
+    /// Unix timestamp (seconds) of the latest loop iteration
+    pub last_loop_at: u64,
+    /// Unix timestamp (seconds) of the latest successful opportunity scan
+    pub last_scan_at: Option<u64>,
+    /// Unix timestamp (seconds) of the latest successful state reconciliation
+    pub last_reconcile_at: Option<u64>,
+    /// Symbol currently held, if any
+    pub symbol: Option<String>,
+    /// Loop restarts by the watchdog since the process started
+    #[serde(default)]
+    pub watchdog_restarts: u64,
+    /// Unix timestamp (seconds) of the latest watchdog incident
+    #[serde(default)]
+    pub last_incident_at: Option<u64>,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self {
+            pid: std::process::id(),
+            ..Self::default()
+        }
+    }
+
+    /// Seconds since the latest loop iteration
+    pub fn age_secs(&self, now: u64) -> u64 {
+        now.saturating_sub(self.last_loop_at)
+    }
+
+    pub fn is_stale(&self, max_age_secs: u64, now: u64) -> bool {
+        self.age_secs(now) > max_age_secs
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, BotError> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Write atomically so a probe never reads a half-written file
+    pub fn write_to_file(&self, path: &str) -> Result<(), BotError> {
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let temp_path = format!("{}.tmp", path);
+        fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staleness_and_round_trip() {
+        let heartbeat = Heartbeat {
+            last_loop_at: 1_000,
+            last_scan_at: Some(990),
+            ..Heartbeat::new()
+        };
+        assert_eq!(heartbeat.age_secs(1_600), 600);
+        assert!(!heartbeat.is_stale(600, 1_600));
+        assert!(heartbeat.is_stale(600, 1_601));
+        assert_eq!(heartbeat.age_secs(500), 0);
+
+        let path = std::env::temp_dir().join(format!("heartbeat_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        heartbeat.write_to_file(path).unwrap();
+        assert_eq!(Heartbeat::load_from_file(path).unwrap(), heartbeat);
+        let _ = fs::remove_file(path);
+    }
+}
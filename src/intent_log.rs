@@ -0,0 +1,354 @@
+//! Append-only write-ahead log of order intents
+//!
+//! Every order of an open, rollback or close is recorded as `Pending` before it is
+//! sent and as `Filled` or `Failed` afterwards. Once the outcome is reflected in
+//! `bot_state.json` the bot appends a `Resolved` record for the position. On startup,
+//! executions without a `Resolved` record tell the bot that the process died
+//! mid-execution (e.g. between the two legs of an open), and which symbol and
+//! client order IDs to look for.
+use crate::error::BotError;
+use crate::trading::{client_order_id, OrderLeg};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use tracing::warn;
+use uuid::Uuid;
+
+pub const DEFAULT_INTENT_LOG_FILE: &str = "intent_log.jsonl";
+
+/// Intent log path, overridable with `INTENT_LOG_PATH`
+pub fn resolve_intent_log_path() -> String {
+    std::env::var("INTENT_LOG_PATH").unwrap_or_else(|_| DEFAULT_INTENT_LOG_FILE.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentPhase {
+    /// Written before the order is sent
+    Pending,
+    /// The order executed (or an earlier attempt was found filled)
+    Filled,
+    /// The bot gave up on the order without finding a fill
+    Failed,
+    /// The execution is settled and reflected in bot state
+    Resolved,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntentRecord {
+    pub position_id: String,
+    pub symbol: String,
+    /// `None` for `Resolved` records, which cover the whole position
+    #[serde(default)]
+    pub leg: Option<OrderLeg>,
+    #[serde(default)]
+    pub side: Option<String>,
+    #[serde(default)]
+    pub size_base: f64,
+    /// Attempt number used to derive the client order ID (see `trading::client_order_id`)
+    #[serde(default)]
+    pub attempt: u32,
+    pub phase: IntentPhase,
+    /// Unix timestamp in milliseconds
+    pub at_ms: i64,
+}
+
+impl IntentRecord {
+    pub fn order(
+        position_id: &Uuid,
+        symbol: &str,
+        leg: OrderLeg,
+        side: impl std::fmt::Debug,
+        size_base: f64,
+        attempt: u32,
+        phase: IntentPhase,
+    ) -> Self {
+        Self {
+            position_id: position_id.to_string(),
+            symbol: symbol.to_string(),
+            leg: Some(leg),
+            side: Some(format!("{:?}", side)),
+            size_base,
+            attempt,
+            phase,
+            at_ms: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// Outcome of this order found after the fact (e.g. by a restarted process)
+    pub fn outcome(&self, phase: IntentPhase, size_base: f64) -> Self {
+        Self {
+            size_base,
+            phase,
+            at_ms: chrono::Utc::now().timestamp_millis(),
+            ..self.clone()
+        }
+    }
+
+    /// Client order ID the order was sent with, for order records of a valid position ID
+    pub fn client_order_id(&self) -> Option<String> {
+        let position_id = Uuid::parse_str(&self.position_id).ok()?;
+        Some(client_order_id(&position_id, self.leg?, self.attempt).to_string())
+    }
+
+    pub fn resolved(position_id: &str, symbol: &str) -> Self {
+        Self {
+            position_id: position_id.to_string(),
+            symbol: symbol.to_string(),
+            leg: None,
+            side: None,
+            size_base: 0.0,
+            attempt: 0,
+            phase: IntentPhase::Resolved,
+            at_ms: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// Records of one position's execution that never reached `Resolved`
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnresolvedExecution {
+    pub position_id: String,
+    pub symbol: String,
+    pub records: Vec<IntentRecord>,
+}
+
+impl UnresolvedExecution {
+    /// Latest phase recorded for `leg`, if any order of that leg was logged
+    pub fn last_phase(&self, leg: OrderLeg) -> Option<IntentPhase> {
+        self.records.iter().rev().find(|r| r.leg == Some(leg)).map(|r| r.phase)
+    }
+
+    fn may_have_executed(&self, leg: OrderLeg) -> bool {
+        matches!(self.last_phase(leg), Some(IntentPhase::Pending | IntentPhase::Filled))
+    }
+
+    /// True when the leading open leg (Extended, or Pacifica for spread-capture opens)
+    /// may have executed but the other leg never filled and no rollback completed:
+    /// a possible one-legged position.
+    pub fn died_between_legs(&self) -> bool {
+        let one_legged = |lead: OrderLeg, hedge: OrderLeg, rollback: OrderLeg| {
+            self.may_have_executed(lead)
+                && self.last_phase(hedge) != Some(IntentPhase::Filled)
+                && self.last_phase(rollback) != Some(IntentPhase::Filled)
+        };
+        one_legged(OrderLeg::ExtendedOpen, OrderLeg::PacificaOpen, OrderLeg::ExtendedRollback)
+            || one_legged(OrderLeg::PacificaOpen, OrderLeg::ExtendedOpen, OrderLeg::PacificaRollback)
+    }
+
+    /// The logged order a venue-side client order ID belongs to, if it is one of this
+    /// execution's orders
+    pub fn order_for_client_id(&self, client_id: &str) -> Option<&IntentRecord> {
+        self.records
+            .iter()
+            .filter(|r| r.phase == IntentPhase::Pending)
+            .find(|r| r.client_order_id().is_some_and(|id| id.eq_ignore_ascii_case(client_id)))
+    }
+
+    /// Compact description of each leg's last phase, for logs
+    pub fn summary(&self) -> String {
+        let mut legs: Vec<OrderLeg> = Vec::new();
+        for leg in self.records.iter().filter_map(|r| r.leg) {
+            if !legs.contains(&leg) {
+                legs.push(leg);
+            }
+        }
+        legs.iter()
+            .filter_map(|&leg| self.last_phase(leg).map(|phase| format!("{:?}={:?}", leg, phase)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+pub struct IntentLog {
+    path: String,
+}
+
+impl IntentLog {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Append a record and flush it to disk before returning
+    pub fn record(&self, record: &IntentRecord) -> Result<(), BotError> {
+        if let Some(parent) = Path::new(&self.path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Append a record that is informational only (outcome of an order already sent)
+    pub fn record_or_warn(&self, record: &IntentRecord) {
+        if let Err(e) = self.record(record) {
+            warn!("Failed to write intent log {}: {}", self.path, e);
+        }
+    }
+
+    /// Mark every record of a position as settled
+    pub fn resolve(&self, position_id: &str, symbol: &str) {
+        self.record_or_warn(&IntentRecord::resolved(position_id, symbol));
+    }
+
+    /// All records in order. A torn last line (crash mid-write) is skipped.
+    pub fn read_all(&self) -> Result<Vec<IntentRecord>, BotError> {
+        if !Path::new(&self.path).exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)?;
+        let mut records = Vec::new();
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(record) => records.push(record),
+                Err(e) => warn!("Skipping unreadable intent log line: {}", e),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Executions without a `Resolved` record, oldest first
+    pub fn unresolved(&self) -> Result<Vec<UnresolvedExecution>, BotError> {
+        Ok(group_unresolved(self.read_all()?))
+    }
+
+    /// Rewrite the log keeping only unresolved executions
+    pub fn compact(&self) -> Result<(), BotError> {
+        let records: Vec<IntentRecord> = self
+            .unresolved()?
+            .into_iter()
+            .flat_map(|execution| execution.records)
+            .collect();
+        let mut content = String::new();
+        for record in &records {
+            content.push_str(&serde_json::to_string(record)?);
+            content.push('\n');
+        }
+        let temp_path = format!("{}.tmp", self.path);
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+fn group_unresolved(records: Vec<IntentRecord>) -> Vec<UnresolvedExecution> {
+    let mut executions: Vec<UnresolvedExecution> = Vec::new();
+    for record in records {
+        if record.phase == IntentPhase::Resolved {
+            executions.retain(|e| e.position_id != record.position_id);
+            continue;
+        }
+        match executions.iter_mut().find(|e| e.position_id == record.position_id) {
+            Some(execution) => execution.records.push(record),
+            None => executions.push(UnresolvedExecution {
+                position_id: record.position_id.clone(),
+                symbol: record.symbol.clone(),
+                records: vec![record],
+            }),
+        }
+    }
+    executions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_record(id: &Uuid, leg: OrderLeg, phase: IntentPhase) -> IntentRecord {
+        IntentRecord::order(id, "BTC", leg, "Buy", 0.01, 1, phase)
+    }
+
+    #[test]
+    fn test_unresolved_detects_crash_between_legs() {
+        let crashed = Uuid::new_v4();
+        let settled = Uuid::new_v4();
+        let records = vec![
+            open_record(&settled, OrderLeg::ExtendedOpen, IntentPhase::Pending),
+            open_record(&settled, OrderLeg::ExtendedOpen, IntentPhase::Filled),
+            IntentRecord::resolved(&settled.to_string(), "BTC"),
+            open_record(&crashed, OrderLeg::ExtendedOpen, IntentPhase::Pending),
+            open_record(&crashed, OrderLeg::ExtendedOpen, IntentPhase::Filled),
+            open_record(&crashed, OrderLeg::PacificaOpen, IntentPhase::Pending),
+        ];
+
+        let unresolved = group_unresolved(records);
+        assert_eq!(unresolved.len(), 1);
+        let execution = &unresolved[0];
+        assert_eq!(execution.position_id, crashed.to_string());
+        assert!(execution.died_between_legs());
+        assert_eq!(execution.summary(), "ExtendedOpen=Filled, PacificaOpen=Pending");
+
+        let mut hedged = execution.clone();
+        hedged.records.push(open_record(&crashed, OrderLeg::PacificaOpen, IntentPhase::Filled));
+        assert!(!hedged.died_between_legs());
+
+        // Spread-capture opens lead with Pacifica
+        let pacifica_first = UnresolvedExecution {
+            position_id: crashed.to_string(),
+            symbol: "BTC".to_string(),
+            records: vec![
+                open_record(&crashed, OrderLeg::PacificaOpen, IntentPhase::Filled),
+                open_record(&crashed, OrderLeg::ExtendedOpen, IntentPhase::Failed),
+            ],
+        };
+        assert!(pacifica_first.died_between_legs());
+        let mut rolled_back = pacifica_first.clone();
+        rolled_back.records.push(open_record(&crashed, OrderLeg::PacificaRollback, IntentPhase::Filled));
+        assert!(!rolled_back.died_between_legs());
+    }
+
+    #[test]
+    fn test_order_for_client_id_matches_logged_attempts() {
+        let id = Uuid::new_v4();
+        let execution = UnresolvedExecution {
+            position_id: id.to_string(),
+            symbol: "BTC".to_string(),
+            records: vec![
+                IntentRecord::order(&id, "BTC", OrderLeg::ExtendedOpen, "Buy", 0.01, 2, IntentPhase::Pending),
+                IntentRecord::order(&id, "BTC", OrderLeg::PacificaOpen, "Sell", 0.01, 1, IntentPhase::Pending),
+            ],
+        };
+
+        let extended = client_order_id(&id, OrderLeg::ExtendedOpen, 2).to_string();
+        let matched = execution.order_for_client_id(&extended).unwrap();
+        assert_eq!((matched.leg, matched.attempt), (Some(OrderLeg::ExtendedOpen), 2));
+        assert!(execution.order_for_client_id(&extended.to_uppercase()).is_some());
+
+        // An attempt that was never logged, or another position's order, is not adopted
+        assert!(execution.order_for_client_id(&client_order_id(&id, OrderLeg::ExtendedOpen, 1).to_string()).is_none());
+        assert!(execution.order_for_client_id(&Uuid::new_v4().to_string()).is_none());
+
+        let outcome = matched.outcome(IntentPhase::Filled, 0.004);
+        assert_eq!((outcome.phase, outcome.size_base, outcome.attempt), (IntentPhase::Filled, 0.004, 2));
+        assert_eq!(outcome.client_order_id(), Some(extended));
+    }
+
+    #[test]
+    fn test_log_round_trip_and_compact() {
+        let path = std::env::temp_dir().join(format!("intent_log_test_{}.jsonl", Uuid::new_v4()));
+        let log = IntentLog::new(path.to_str().unwrap());
+        let done = Uuid::new_v4();
+        let open = Uuid::new_v4();
+
+        log.record(&open_record(&done, OrderLeg::ExtendedClose, IntentPhase::Pending)).unwrap();
+        log.resolve(&done.to_string(), "BTC");
+        log.record(&open_record(&open, OrderLeg::ExtendedOpen, IntentPhase::Pending)).unwrap();
+        // Simulate a crash mid-write
+        OpenOptions::new().append(true).open(log.path()).unwrap().write_all(b"{\"position_id\":").unwrap();
+
+        assert_eq!(log.read_all().unwrap().len(), 3);
+        log.compact().unwrap();
+        let records = log.read_all().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].position_id, open.to_string());
+        let _ = fs::remove_file(log.path());
+    }
+}
@@ -1,4 +1,5 @@
 pub mod error;
+pub mod numeric;
 pub mod rest;
 pub mod signature;
 pub mod snip12;
@@ -6,36 +7,136 @@ pub mod types;
 pub mod websocket;
 pub mod pacifica;
 pub mod opportunity;
+pub mod retry;
 pub mod trading;
+pub mod funding;
+pub mod sizing;
+pub mod capital;
 pub mod bot;
+pub mod heartbeat;
+pub mod intent_log;
+pub mod scan_daemon;
+pub mod strategy;
+pub mod risk;
+pub mod metrics;
+pub mod watchdog;
+pub mod observer;
+pub mod position_manager;
+pub mod daily_summary;
+pub mod equity;
+pub mod state_store;
+pub mod decision_log;
+pub mod control;
+pub mod spread_history;
+pub mod preflight;
+pub mod report;
+pub mod audit;
+pub mod scoring;
+pub mod funding_stream;
+pub mod contingency;
+pub mod state_lock;
+pub mod order_sweeper;
+pub mod protective_orders;
+pub mod symbols;
+pub mod stress;
+pub mod api_capture;
+pub mod account_stream;
+pub mod margin_watch;
+pub mod fees;
+pub mod shutdown;
+pub mod data_failover;
+pub mod entry_timing;
+pub mod build_info;
+pub mod cycle_budget;
+pub mod notify;
 
 // Re-export commonly used types
-pub use error::{ConnectorError, Result};
+pub use error::{BotError, ConnectorError, Result};
+pub use numeric::Decimal;
 pub use rest::RestClient;
-pub use types::{Balance, BidAsk, FundingRateInfo, MarketInfo, OrderBook, OrderSide, OrderResponse, Position, PositionSide};
-pub use websocket::{MultiMarketSubscriber, WebSocketClient};
+pub use types::{AccountTrade, Balance, BidAsk, Candle, FundingPayment, FundingRateInfo, LimitOrder, MarketInfo, MarketStats, OrderBook, OrderSide, OrderResponse, Position, PositionSide, TpSlTrigger, TpSlType};
+pub use websocket::{ConnectionEvent, MultiMarketSubscriber, TopOfBookCache, WebSocketClient};
 
 // Re-export Pacifica types
 pub use pacifica::{
-    PacificaTrading, PacificaCredentials, PacificaAccountInfo, PacificaFundingRate, PacificaMarketInfo,
-    PacificaPosition, OrderbookClient, OrderbookConfig, FillDetectionClient,
-    FillDetectionConfig, PacificaWsTrading, TradeHistoryItem,
+    PriceStreamClient, PriceUpdate,
+    PacificaTrading, PacificaCredentials, PacificaAccountInfo, PacificaCandle, PacificaFundingRate, PacificaMarketInfo,
+    PacificaPosition, StopLeg, OrderbookClient, OrderbookConfig, DepthCache, FillDetectionClient,
+    FillDetectionConfig, FillWatcher, ConfirmedFill, PacificaWsTrading, TradeHistoryItem, FundingHistoryItem,
 };
 
 // Re-export Opportunity types
 pub use opportunity::{
     Config as OpportunityConfig, Opportunity, OpportunityFinder, VolumeData,
     ScanResult, FilterStats, FilterConfig, TradingConfig, OpportunityCandidate, FilterResult,
+    Environment, MarginMode, ExecutionConfig, LegOrder, SizingConfig, CapitalConfig, ExportConfig, ExportFormat,
+    RotationConfig, ScanDaemonConfig, ExtendedVaultConfig, Direction, BookDepth, TradeSimulation,
 };
 
 // Re-export Trading types
 pub use trading::{
-    DeltaNeutralPosition, TradingError, calculate_position_size,
-    open_delta_neutral_position, close_delta_neutral_position, retry_with_backoff,
+    DeltaNeutralPosition, calculate_position_size,
+    open_delta_neutral_position, close_delta_neutral_position,
+    estimated_liquidation_distance_pct, validate_leverage, validate_order_size, OrderSizeLimits, CloseVerificationError, ResidualLeg,
+    client_order_id, reduction_id, derives_from_position, close_partial, partial_close_size, minimum_order_notional_usd, OrderLeg, TopOfBookQuotes, emergency_flatten_all, FlattenLeg, FlattenReport,
+    FlattenStatus, basis_pct, plan_spread_capture, adaptive_slippage_tolerance_pct, SpreadCapture, ExecutionKind, ExecutionReport, LegExecution,
+    LegSequence, LeggingStats,
 };
 
+// Re-export Capital types
+pub use capital::{max_pair_notional_usd, CapitalSnapshot, LogAlertHook, TransferSuggestion, Venue, VenueMargin};
+
+// Re-export API metrics
+pub use metrics::{ApiMetrics, Degradation, EndpointSummary, ErrorBudgetConfig, VenueHealth};
+
+// Re-export funding normalization
+pub use funding::{apr_from_rate, FundingCapConfig, FundingForecast, FundingIntervalConfig, FundingRateAverage};
+
+// Re-export streamed funding rates
+pub use funding_stream::{FundingRateCache, FundingStreamConfig};
+
+// Re-export failed-rollback contingency
+pub use contingency::{BackupHedge, ContingencyConfig, ExposedLeg, ExposureAlert, HedgeOutcome};
+
+// Re-export orphan-order sweeper
+pub use order_sweeper::{OrderSweepConfig, OrderSweeper, RestingOrder};
+
+// Re-export protective TP/SL orders
+pub use protective_orders::{ProtectiveBand, ProtectiveOrdersConfig};
+
+// Re-export retry policy
+pub use retry::RetryPolicy;
+
 // Re-export Bot types
-pub use bot::{BotState, FundingBot};
+pub use bot::{BotBuilder, BotState, FundingBot, PositionStatus, RuntimeStatus, StatusSnapshot};
+pub use observer::ReadOnlyBot;
+pub use position_manager::PositionManager;
+pub use audit::{AuditConfig, AuditReport, Divergence};
+pub use daily_summary::{DailySummary, DailySummaryConfig, LedgerTotals};
+pub use equity::{Drawdown, EquityCurve, EquityHistory};
+pub use decision_log::{CandidateSnapshot, CycleDecision, DecisionAction, DecisionLog};
+pub use control::{ControlCommand, ControlConfig, ControlServer};
+pub use spread_history::{SpreadHistory, SpreadHistoryConfig};
+pub use preflight::{CheckStatus, PreflightCheck, PreflightCredentials, PreflightReport};
+pub use report::{render_json, render_plain, render_pretty, ReportCell, ReportTable, ReportView, ScanSummaryView, Tone};
+pub use heartbeat::Heartbeat;
+pub use state_store::StateStore;
+pub use symbols::{ContractMultipliers, SymbolMap, VenueMarkets};
+pub use strategy::{CrossExchangeFunding, PositionTarget, Strategy};
+pub use scoring::{Scorer, ScoringConfig, ScoringMethod};
+pub use risk::{RiskConfig, RiskManager};
+pub use stress::{risk_report, LegStress, RiskReport, StressConfig, StressLeg, StressScenario};
+pub use api_capture::{ApiCapture, ApiCaptureConfig, CapturedCall};
+pub use account_stream::{AccountStreamConfig, PacificaAccountCache};
+pub use fees::{FeeConfig, FeeSchedule, FeeTier};
+pub use shutdown::{Drain, InFlight, ShutdownConfig, ShutdownController};
+pub use data_failover::{BookSources, DataAvailability, DataFailoverConfig, QuoteSource, VenueData};
+pub use build_info::BuildInfo;
+pub use cycle_budget::{CycleBudgetConfig, CycleTimer, Phase};
+pub use entry_timing::{CandleConditions, EntryDelay, EntryTiming, EntryTimingConfig};
+pub use margin_watch::{BalanceSample, MarginAssessment, MarginCallConfig, MarginLevel, MarginWatch};
+pub use watchdog::{Incident, IncidentKind, WatchdogConfig};
+pub use notify::{Hook, HookFuture, WebhookSink};
 
 /// Initialize logging for the library
 pub fn init_logging() {
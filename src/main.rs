@@ -1,26 +1,236 @@
-/// Funding Rate Arbitrage Bot - Main Entry Point
-///
-/// This bot automatically finds and executes delta neutral funding rate arbitrage
-/// opportunities between Extended DEX and Pacifica.
-///
-/// Setup:
-/// 1. Create .env file with credentials:
-///    - API_KEY (Extended API key)
-///    - SOL_WALLET, API_PUBLIC, API_PRIVATE (for Pacifica)
-///    - STARK_PRIVATE, STARK_PUBLIC (for Extended trading)
-///    - VAULT_NUMBER (Extended vault/position ID)
-///
-/// 2. Adjust config.json for desired filtering parameters
-///
-/// 3. Run: cargo run
-///
+//! Funding Rate Arbitrage Bot - Main Entry Point
+//!
+//! This bot automatically finds and executes delta neutral funding rate arbitrage
+//! opportunities between Extended DEX and Pacifica.
+//!
+//! Setup:
+//! 1. Create .env file with credentials:
+//!    - API_KEY (Extended API key)
+//!    - SOL_WALLET, API_PUBLIC, API_PRIVATE (for Pacifica)
+//!    - STARK_PRIVATE, STARK_PUBLIC (for Extended trading)
+//!    - VAULT_NUMBER (Extended vault/position ID)
+//!
+//! 2. Adjust config.json for desired filtering parameters
+//!
+//! 3. Run: cargo run
+//!
+//! Signals only (no trading, no Starknet keys needed): `extended_connector scan-daemon`
+//!
+//! Read-only observer (scans, reconciliation and status, no signing keys at all):
+//! `extended_connector observe`. Reads `OBSERVER_EXTENDED_API_KEY` and
+//! `OBSERVER_SOL_WALLET`, falling back to the trading bot's API key and SOL_WALLET.
+//!
+//! Status for monitoring scripts: `extended_connector --status-json` prints a
+//! `StatusSnapshot` as JSON on stdout (logs go to stderr) and exits.
+//!
+//! Extended sub-accounts: `extended_connector --list-accounts` prints the accounts and
+//! vaults the API key can see, for use in `extended_vaults`.
+//!
+//! Equity curve: `extended_connector equity-curve [--csv]` prints the per-cycle equity
+//! history as a chart with drawdown and funding statistics, or as CSV.
+//!
+//! Decision log: `extended_connector decisions [N]` prints the last N cycles (default 20):
+//! top candidates, which filter rejected which symbols, the action and why nothing else
+//! happened, followed by rejection counts per symbol over those cycles.
+//!
+//! Preflight before going live: `extended_connector preflight` checks credentials, signs
+//! (without sending) an Extended order and a Pacifica payload, and checks balances, clock
+//! skew and markets, printing a pass/fail checklist; exits non-zero if any check fails.
+//!
+//! Health check for supervisors: `extended_connector --check-health [max_age_secs]`
+//! exits non-zero when the heartbeat file is missing or older than max_age_secs.
+//!
 use extended_connector::{
-    FundingBot, OpportunityConfig, PacificaCredentials,
+    decision_log::{rejection_counts, resolve_decision_log_path, DecisionLog},
+    equity::{resolve_equity_history_path, EquityHistory},
+    heartbeat::{resolve_heartbeat_path, DEFAULT_MAX_HEARTBEAT_AGE_SECS},
+    preflight::run_preflight,
+    scan_daemon::ScanDaemon,
+    FundingBot, Heartbeat, OpportunityConfig, PacificaCredentials, PreflightCredentials,
 };
 use colored::*;
 
+/// Report heartbeat freshness; returns the process exit code
+fn check_health(max_age_secs: u64) -> i32 {
+    let path = resolve_heartbeat_path();
+    let heartbeat = match Heartbeat::load_from_file(&path) {
+        Ok(heartbeat) => heartbeat,
+        Err(e) => {
+            eprintln!("unhealthy: cannot read heartbeat {}: {}", path, e);
+            return 1;
+        }
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let age = |ts: Option<u64>| ts.map_or("never".to_string(), |t| format!("{}s ago", now.saturating_sub(t)));
+    let status = if heartbeat.is_stale(max_age_secs, now) { "unhealthy" } else { "healthy" };
+    println!(
+        "{}: pid {}, last loop {}s ago (max {}s), last scan {}, last reconcile {}",
+        status,
+        heartbeat.pid,
+        heartbeat.age_secs(now),
+        max_age_secs,
+        age(heartbeat.last_scan_at),
+        age(heartbeat.last_reconcile_at)
+    );
+    if status == "healthy" { 0 } else { 1 }
+}
+
+/// Print the recorded equity history. Reads the history file only; no credentials needed.
+fn print_equity_curve(csv: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let curve = EquityHistory::new(resolve_equity_history_path()).curve()?;
+    if csv {
+        print!("{}", curve.to_csv());
+        return Ok(());
+    }
+    print!("{}", curve.to_ascii(72, 12));
+    let usd = |v: Option<f64>| v.map_or_else(|| "n/a".to_string(), |v| format!("${:+.2}", v));
+    println!("Snapshots: {} ({} with both venues)", curve.points.len(), curve.equity_series().len());
+    println!("Equity change: {}", usd(curve.equity_change_usd()));
+    println!("Funding net of fees (ledger): {}", usd(curve.net_funding_usd()));
+    match curve.max_drawdown() {
+        Some(drawdown) => println!("Max drawdown: ${:.2} ({:.2}%)", drawdown.amount_usd(), drawdown.pct()),
+        None => println!("Max drawdown: none"),
+    }
+    Ok(())
+}
+
+/// Print the last `limit` cycle decisions. Reads the decision log only; no credentials needed.
+fn print_decisions(limit: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let decisions = DecisionLog::new(resolve_decision_log_path()).recent(limit)?;
+    if decisions.is_empty() {
+        println!("No decisions recorded yet");
+        return Ok(());
+    }
+    for decision in &decisions {
+        print!("{}", decision.render());
+    }
+    println!();
+    println!("Rejections over the last {} cycles:", decisions.len());
+    for (symbol, filters) in rejection_counts(&decisions) {
+        let counts: Vec<String> = filters.iter().map(|(filter, n)| format!("{:?} x{}", filter, n)).collect();
+        println!("  {:<10} {}", symbol, counts.join(", "));
+    }
+    Ok(())
+}
+
+/// Run the preflight checklist; returns the process exit code
+async fn preflight() -> i32 {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .without_time()
+        .with_max_level(tracing::Level::WARN)
+        .init();
+    let report = run_preflight("config.json", &PreflightCredentials::from_env()).await;
+    print!("{}", report.render());
+    if report.passed() { 0 } else { 1 }
+}
+
+/// Print the bot status as JSON. No orders are sent, so Starknet keys are optional.
+async fn print_status_json() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .without_time()
+        .init();
+    dotenv::dotenv().ok();
+
+    let extended_api_key = std::env::var("EXTENDED_API_KEY")
+        .or_else(|_| std::env::var("API_KEY"))
+        .ok();
+    let bot = FundingBot::new(
+        extended_api_key,
+        PacificaCredentials::load()?,
+        OpportunityConfig::load("config.json")?,
+        std::env::var("STARK_PRIVATE").unwrap_or_default(),
+        std::env::var("STARK_PUBLIC").unwrap_or_default(),
+        std::env::var("VAULT_NUMBER").unwrap_or_default(),
+    )?;
+    println!("{}", serde_json::to_string_pretty(&bot.status_snapshot().await?)?);
+    Ok(())
+}
+
+/// Watch the trading bot's state and both venues without any signing key
+async fn observe() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_target(false)
+        .without_time()
+        .init();
+    dotenv::dotenv().ok();
+
+    let extended_api_key = std::env::var("OBSERVER_EXTENDED_API_KEY")
+        .or_else(|_| std::env::var("EXTENDED_API_KEY"))
+        .or_else(|_| std::env::var("API_KEY"))
+        .ok();
+    let pacifica_account = std::env::var("OBSERVER_SOL_WALLET")
+        .or_else(|_| std::env::var("SOL_WALLET"))
+        .map_err(|_| "OBSERVER_SOL_WALLET (or SOL_WALLET) must be set")?;
+    let mut observer = FundingBot::builder(OpportunityConfig::load("config.json")?)
+        .extended_api_key(extended_api_key)
+        .pacifica_account(pacifica_account)
+        .build_read_only()?;
+    observer.run(std::time::Duration::from_secs(60)).await?;
+    Ok(())
+}
+
+/// Print the Extended accounts (vaults) tied to the API key
+async fn list_accounts() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv::dotenv().ok();
+    let api_key = std::env::var("EXTENDED_API_KEY")
+        .or_else(|_| std::env::var("API_KEY"))
+        .map_err(|_| "EXTENDED_API_KEY (or API_KEY) must be set")?;
+    let config = OpportunityConfig::load("config.json")?;
+    let client = config.environment.extended_client(Some(api_key))?;
+    for account in client.get_accounts().await? {
+        println!(
+            "account {} (index {}): vault {}, status {}, stark key {}{}",
+            account.account_id,
+            account.account_index.map_or("?".to_string(), |i| i.to_string()),
+            account.l2_vault,
+            account.status,
+            account.l2_key,
+            account.description.map(|d| format!(", {}", d)).unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("--check-health") {
+        let max_age_secs = match args.get(2) {
+            Some(value) => value.parse()?,
+            None => DEFAULT_MAX_HEARTBEAT_AGE_SECS,
+        };
+        std::process::exit(check_health(max_age_secs));
+    }
+    if args.get(1).map(String::as_str) == Some("preflight") {
+        std::process::exit(preflight().await);
+    }
+    if args.get(1).map(String::as_str) == Some("--status-json") {
+        return print_status_json().await;
+    }
+    if args.get(1).map(String::as_str) == Some("--list-accounts") {
+        return list_accounts().await;
+    }
+    if args.get(1).map(String::as_str) == Some("equity-curve") {
+        return print_equity_curve(args.get(2).map(String::as_str) == Some("--csv"));
+    }
+    if args.get(1).map(String::as_str) == Some("decisions") {
+        let limit = match args.get(2) {
+            Some(value) => value.parse()?,
+            None => 20,
+        };
+        return print_decisions(limit);
+    }
+    if args.get(1).map(String::as_str) == Some("observe") {
+        return observe().await;
+    }
+
     // Initialize logging
     tracing_subscriber::fmt()
         .with_target(false)
@@ -46,9 +256,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         eprintln!("⚠️  Warning: No Extended API key found. Some features may be limited.");
     }
 
-    let pacifica_creds = PacificaCredentials::from_env()?;
+    let pacifica_creds = PacificaCredentials::load()?;
     println!("✅ Loaded Pacifica credentials");
 
+    if args.get(1).map(String::as_str) == Some("scan-daemon") {
+        let config = OpportunityConfig::load("config.json")?;
+        println!("✅ Loaded config from config.json");
+        println!("📡 Starting scan-only daemon (no orders will be placed)");
+        println!();
+        let mut daemon = ScanDaemon::new(extended_api_key.clone(), pacifica_creds, config)?;
+        daemon.run(extended_api_key).await?;
+        return Ok(());
+    }
+
     // Load Starknet credentials for Extended trading
     let stark_private_key = std::env::var("STARK_PRIVATE")
         .expect("STARK_PRIVATE must be set in .env");
@@ -79,6 +299,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         vault_id,
     )?;
 
+    // Vault overrides must be usable before any order is routed to them
+    bot.verify_extended_vaults().await?;
+
     // Reconcile saved state with live positions before entering the main loop
     if let Err(e) = bot.reconcile_state().await {
         eprintln!("⚠️  Warning: failed to reconcile state at startup: {}", e);
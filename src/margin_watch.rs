@@ -0,0 +1,288 @@
+//! Extended margin-call watch
+//!
+//! With `risk.margin_call.enabled`, while a position is held the bot polls the Extended
+//! balance every `poll_interval_seconds` between cycles and hands it to the risk
+//! manager. The margin ratio (maintenance margin over equity; Extended liquidates at
+//! 100%) picks the response: from `warn_margin_ratio_pct` the bot logs and alerts, from
+//! `reduce_margin_ratio_pct` it closes `reduce_fraction` of both legs, from
+//! `close_margin_ratio_pct` it closes the position and pauses.
+//!
+//! A fast deterioration within `window_seconds` escalates the response one step, so a
+//! crash is acted on before the ratio reaches the next threshold: free collateral down
+//! `available_drop_pct` from its peak in the window, or the ratio up
+//! `ratio_rise_pct` points from its low. After a reduce, further reduces wait one
+//! window for the balance to show its effect.
+use crate::types::Balance;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarginCallConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+    /// Span over which falls and rises are measured
+    #[serde(default = "default_window_seconds")]
+    pub window_seconds: u64,
+    #[serde(default = "default_warn_margin_ratio_pct")]
+    pub warn_margin_ratio_pct: f64,
+    #[serde(default = "default_reduce_margin_ratio_pct")]
+    pub reduce_margin_ratio_pct: f64,
+    #[serde(default = "default_close_margin_ratio_pct")]
+    pub close_margin_ratio_pct: f64,
+    /// Fall of free collateral (% of its peak in the window) that escalates (0 = off)
+    #[serde(default = "default_available_drop_pct")]
+    pub available_drop_pct: f64,
+    /// Rise of the margin ratio (points over its low in the window) that escalates (0 = off)
+    #[serde(default = "default_ratio_rise_pct")]
+    pub ratio_rise_pct: f64,
+    /// Share of both legs closed by a reduce
+    #[serde(default = "default_reduce_fraction")]
+    pub reduce_fraction: f64,
+}
+
+fn default_poll_interval_seconds() -> u64 {
+    15
+}
+
+fn default_window_seconds() -> u64 {
+    300
+}
+
+fn default_warn_margin_ratio_pct() -> f64 {
+    50.0
+}
+
+fn default_reduce_margin_ratio_pct() -> f64 {
+    70.0
+}
+
+fn default_close_margin_ratio_pct() -> f64 {
+    85.0
+}
+
+fn default_available_drop_pct() -> f64 {
+    50.0
+}
+
+fn default_ratio_rise_pct() -> f64 {
+    20.0
+}
+
+fn default_reduce_fraction() -> f64 {
+    0.5
+}
+
+impl Default for MarginCallConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_seconds: default_poll_interval_seconds(),
+            window_seconds: default_window_seconds(),
+            warn_margin_ratio_pct: default_warn_margin_ratio_pct(),
+            reduce_margin_ratio_pct: default_reduce_margin_ratio_pct(),
+            close_margin_ratio_pct: default_close_margin_ratio_pct(),
+            available_drop_pct: default_available_drop_pct(),
+            ratio_rise_pct: default_ratio_rise_pct(),
+            reduce_fraction: default_reduce_fraction(),
+        }
+    }
+}
+
+impl MarginCallConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.poll_interval_seconds == 0 || self.window_seconds == 0 {
+            return Err("risk.margin_call.poll_interval_seconds and window_seconds must be positive".into());
+        }
+        let thresholds = [self.warn_margin_ratio_pct, self.reduce_margin_ratio_pct, self.close_margin_ratio_pct];
+        if !(thresholds[0] > 0.0 && thresholds[0] <= thresholds[1] && thresholds[1] <= thresholds[2] && thresholds[2] <= 100.0) {
+            return Err("risk.margin_call thresholds must satisfy 0 < warn <= reduce <= close <= 100".into());
+        }
+        if self.available_drop_pct < 0.0 || self.available_drop_pct > 100.0 || self.ratio_rise_pct < 0.0 {
+            return Err("risk.margin_call.available_drop_pct must be 0-100 and ratio_rise_pct non-negative".into());
+        }
+        if !(self.reduce_fraction > 0.0 && self.reduce_fraction < 1.0) {
+            return Err("risk.margin_call.reduce_fraction must be between 0 and 1 (exclusive)".into());
+        }
+        Ok(())
+    }
+}
+
+/// Response to a balance reading, mildest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarginLevel {
+    Normal,
+    Warn,
+    Reduce,
+    Close,
+}
+
+impl MarginLevel {
+    fn escalated(self) -> Self {
+        match self {
+            Self::Normal => Self::Warn,
+            Self::Warn => Self::Reduce,
+            Self::Reduce | Self::Close => Self::Close,
+        }
+    }
+}
+
+/// One Extended balance reading
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BalanceSample {
+    /// Unix timestamp (seconds)
+    pub at: u64,
+    pub available_usd: f64,
+    pub equity_usd: f64,
+    pub margin_ratio_pct: f64,
+}
+
+impl BalanceSample {
+    /// Extended reports the margin ratio as a fraction
+    pub fn from_balance(balance: &Balance, at: u64) -> Self {
+        Self {
+            at,
+            available_usd: balance.available_for_trade_f64(),
+            equity_usd: balance.equity_f64(),
+            margin_ratio_pct: balance.margin_ratio.parse::<f64>().unwrap_or(0.0) * 100.0,
+        }
+    }
+}
+
+/// What a reading calls for, and why
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarginAssessment {
+    pub level: MarginLevel,
+    pub sample: BalanceSample,
+    /// Fall of free collateral from its peak in the window, in %
+    pub available_drop_pct: f64,
+    /// Rise of the margin ratio over its low in the window, in points
+    pub ratio_rise_pct: f64,
+    /// The response was escalated for a fast deterioration
+    pub escalated: bool,
+}
+
+impl fmt::Display for MarginAssessment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "margin ratio {:.1}% (+{:.1} pts in window), free collateral ${:.2} (-{:.1}% in window), equity ${:.2}{}",
+            self.sample.margin_ratio_pct,
+            self.ratio_rise_pct,
+            self.sample.available_usd,
+            self.available_drop_pct,
+            self.sample.equity_usd,
+            if self.escalated { ", escalated for a fast move" } else { "" }
+        )
+    }
+}
+
+/// Readings within the window and the last reduce it called for
+#[derive(Debug, Clone)]
+pub struct MarginWatch {
+    config: MarginCallConfig,
+    samples: VecDeque<BalanceSample>,
+    last_reduce_at: Option<u64>,
+}
+
+impl MarginWatch {
+    pub fn new(config: MarginCallConfig) -> Self {
+        Self { config, samples: VecDeque::new(), last_reduce_at: None }
+    }
+
+    pub fn config(&self) -> &MarginCallConfig {
+        &self.config
+    }
+
+    /// Forget past readings, once no position is held
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.last_reduce_at = None;
+    }
+
+    fn threshold_level(&self, margin_ratio_pct: f64) -> MarginLevel {
+        if margin_ratio_pct >= self.config.close_margin_ratio_pct {
+            MarginLevel::Close
+        } else if margin_ratio_pct >= self.config.reduce_margin_ratio_pct {
+            MarginLevel::Reduce
+        } else if margin_ratio_pct >= self.config.warn_margin_ratio_pct {
+            MarginLevel::Warn
+        } else {
+            MarginLevel::Normal
+        }
+    }
+
+    /// Assess `sample` against the thresholds and the window's earlier readings
+    pub fn observe(&mut self, sample: BalanceSample) -> MarginAssessment {
+        let window_start = sample.at.saturating_sub(self.config.window_seconds);
+        self.samples.retain(|s| s.at >= window_start);
+
+        let peak_available = self.samples.iter().map(|s| s.available_usd).fold(sample.available_usd, f64::max);
+        let available_drop_pct = if peak_available > 0.0 {
+            ((peak_available - sample.available_usd) / peak_available * 100.0).max(0.0)
+        } else {
+            0.0
+        };
+        let low_ratio = self.samples.iter().map(|s| s.margin_ratio_pct).fold(sample.margin_ratio_pct, f64::min);
+        let ratio_rise_pct = sample.margin_ratio_pct - low_ratio;
+        self.samples.push_back(sample);
+
+        let fast = (self.config.available_drop_pct > 0.0 && available_drop_pct >= self.config.available_drop_pct)
+            || (self.config.ratio_rise_pct > 0.0 && ratio_rise_pct >= self.config.ratio_rise_pct);
+        let mut level = self.threshold_level(sample.margin_ratio_pct);
+        if fast {
+            level = level.escalated();
+        }
+        if level == MarginLevel::Reduce {
+            if self.last_reduce_at.is_some_and(|at| at >= window_start) {
+                level = MarginLevel::Warn;
+            } else {
+                self.last_reduce_at = Some(sample.at);
+            }
+        }
+        MarginAssessment { level, sample, available_drop_pct, ratio_rise_pct, escalated: fast }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(at: u64, available_usd: f64, margin_ratio_pct: f64) -> BalanceSample {
+        BalanceSample { at, available_usd, equity_usd: 1_000.0, margin_ratio_pct }
+    }
+
+    #[test]
+    fn test_margin_levels_and_escalation() {
+        let mut watch = MarginWatch::new(MarginCallConfig { enabled: true, ..MarginCallConfig::default() });
+        assert!(watch.config().validate().is_ok());
+
+        assert_eq!(watch.observe(sample(0, 800.0, 10.0)).level, MarginLevel::Normal);
+        assert_eq!(watch.observe(sample(15, 750.0, 12.0)).level, MarginLevel::Normal);
+        // Free collateral halved within the window: escalated to a warning below any threshold
+        let crash = watch.observe(sample(30, 390.0, 25.0));
+        assert_eq!(crash.level, MarginLevel::Warn);
+        assert!(crash.escalated && crash.available_drop_pct > 50.0);
+        // Past the reduce threshold and still falling fast: close
+        assert_eq!(watch.observe(sample(45, 100.0, 72.0)).level, MarginLevel::Close);
+
+        // Once the window has passed, the same ratio without a fast move only reduces,
+        // and a second reduce within the window only warns
+        assert_eq!(watch.observe(sample(1_000, 100.0, 72.0)).level, MarginLevel::Reduce);
+        assert_eq!(watch.observe(sample(1_015, 100.0, 72.0)).level, MarginLevel::Warn);
+        assert_eq!(watch.observe(sample(1_400, 100.0, 72.0)).level, MarginLevel::Reduce);
+        assert_eq!(watch.observe(sample(1_415, 100.0, 90.0)).level, MarginLevel::Close);
+
+        watch.reset();
+        assert_eq!(watch.observe(sample(2_000, 500.0, 55.0)).level, MarginLevel::Warn);
+
+        let inverted = MarginCallConfig { enabled: true, reduce_margin_ratio_pct: 90.0, ..MarginCallConfig::default() };
+        assert!(inverted.validate().is_err());
+    }
+}
@@ -0,0 +1,432 @@
+//! Latency and error tracking for venue API calls
+//!
+//! Every REST request and WebSocket connect/request is timed through `observe` or
+//! `observe_http` under an endpoint name such as "extended GET /user/balance". Each
+//! endpoint keeps a latency histogram and its recent outcomes; `ApiMetrics::assess`
+//! turns the recent error rate into a `Degradation` level the bot uses to delay opens
+//! or skip scans while a venue is struggling. A 401/403 answer is also kept as the
+//! venue's latest `AuthFailure`: the bot takes it after each cycle and pauses, since no
+//! retry succeeds until the credentials are replaced. A call that times out, or that an
+//! outer timeout cancels before it returns, counts as an error and as a timeout.
+use crate::capital::Venue;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Upper bounds (ms) of the latency histogram buckets; slower calls land in a final overflow bucket
+pub const LATENCY_BUCKETS_MS: [u64; 9] = [25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+/// Outcomes older than this are dropped from the error-rate window
+const MAX_WINDOW: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBudgetConfig {
+    /// Window (minutes) over which the error rate is measured
+    #[serde(default = "default_window_minutes")]
+    pub window_minutes: u64,
+    /// Fewer calls than this in the window never degrade (too little data)
+    #[serde(default = "default_min_calls")]
+    pub min_calls: u64,
+    /// Error rate (%) at which new positions are not opened (0 = never)
+    #[serde(default = "default_delay_opens_error_rate_pct")]
+    pub delay_opens_error_rate_pct: f64,
+    /// Error rate (%) at which opportunity scans are skipped (0 = never)
+    #[serde(default = "default_skip_scans_error_rate_pct")]
+    pub skip_scans_error_rate_pct: f64,
+}
+
+fn default_window_minutes() -> u64 {
+    15
+}
+
+fn default_min_calls() -> u64 {
+    20
+}
+
+fn default_delay_opens_error_rate_pct() -> f64 {
+    20.0
+}
+
+fn default_skip_scans_error_rate_pct() -> f64 {
+    50.0
+}
+
+impl Default for ErrorBudgetConfig {
+    fn default() -> Self {
+        Self {
+            window_minutes: default_window_minutes(),
+            min_calls: default_min_calls(),
+            delay_opens_error_rate_pct: default_delay_opens_error_rate_pct(),
+            skip_scans_error_rate_pct: default_skip_scans_error_rate_pct(),
+        }
+    }
+}
+
+/// How far the bot backs off because of API errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Degradation {
+    Normal,
+    /// Keep scanning and monitoring, but do not open new positions
+    DelayOpens,
+    /// Skip opportunity scans (and therefore opens) entirely
+    SkipScans,
+}
+
+/// Error budget verdict for one venue
+#[derive(Debug, Clone, Serialize)]
+pub struct VenueHealth {
+    pub venue: Venue,
+    pub calls: u64,
+    pub errors: u64,
+    pub error_rate_pct: f64,
+    pub degradation: Degradation,
+}
+
+/// How a call ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CallOutcome {
+    Ok,
+    Error,
+    /// Timed out, or cancelled by the caller's timeout before it returned
+    Timeout,
+}
+
+impl CallOutcome {
+    fn from_ok(ok: bool) -> Self {
+        if ok { Self::Ok } else { Self::Error }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct EndpointStats {
+    calls: u64,
+    errors: u64,
+    /// Errors that were timeouts, including calls cancelled before they returned
+    timeouts: u64,
+    /// Counts per `LATENCY_BUCKETS_MS` bound plus the overflow bucket
+    buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    total_latency_ms: u64,
+    max_latency_ms: u64,
+    /// (time, succeeded) of recent calls, oldest first
+    recent: VecDeque<(Instant, bool)>,
+}
+
+impl EndpointStats {
+    fn record(&mut self, latency: Duration, outcome: CallOutcome, now: Instant) {
+        let ms = latency.as_millis() as u64;
+        let ok = outcome == CallOutcome::Ok;
+        self.calls += 1;
+        if !ok {
+            self.errors += 1;
+        }
+        if outcome == CallOutcome::Timeout {
+            self.timeouts += 1;
+        }
+        let bucket = LATENCY_BUCKETS_MS.iter().position(|&bound| ms <= bound).unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+        self.total_latency_ms += ms;
+        self.max_latency_ms = self.max_latency_ms.max(ms);
+
+        self.recent.push_back((now, ok));
+        while self.recent.front().is_some_and(|(at, _)| now.duration_since(*at) > MAX_WINDOW) {
+            self.recent.pop_front();
+        }
+    }
+
+    /// (calls, errors) within `window` before `now`
+    fn recent_counts(&self, window: Duration, now: Instant) -> (u64, u64) {
+        self.recent
+            .iter()
+            .filter(|(at, _)| now.duration_since(*at) <= window)
+            .fold((0, 0), |(calls, errors), (_, ok)| (calls + 1, errors + u64::from(!ok)))
+    }
+
+    /// Upper bound (ms) of the bucket holding the `quantile` call; `None` past the last bound
+    fn quantile_ms(&self, quantile: f64) -> Option<u64> {
+        let rank = ((self.calls as f64) * quantile).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return LATENCY_BUCKETS_MS.get(i).copied();
+            }
+        }
+        None
+    }
+}
+
+/// Per-endpoint totals for status output
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointSummary {
+    pub endpoint: String,
+    pub calls: u64,
+    pub errors: u64,
+    /// Errors that were timeouts or calls cancelled by a timeout
+    pub timeouts: u64,
+    pub mean_latency_ms: u64,
+    /// Bucket upper bounds; `None` means slower than the largest bucket
+    pub p50_latency_ms: Option<u64>,
+    pub p95_latency_ms: Option<u64>,
+    pub max_latency_ms: u64,
+    /// Counts per `LATENCY_BUCKETS_MS` bound plus the overflow bucket
+    pub latency_histogram: Vec<u64>,
+}
+
+/// A request the venue refused for its credentials
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthFailure {
+    pub venue: Venue,
+    pub endpoint: String,
+    pub status: u16,
+}
+
+impl std::fmt::Display for AuthFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} answered HTTP {} to {}", self.venue, self.status, self.endpoint)
+    }
+}
+
+#[derive(Default)]
+pub struct ApiMetrics {
+    endpoints: Mutex<BTreeMap<String, EndpointStats>>,
+    auth_failures: Mutex<Vec<AuthFailure>>,
+}
+
+impl ApiMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, endpoint: &str, latency: Duration, ok: bool) {
+        self.record_outcome(endpoint, latency, CallOutcome::from_ok(ok), Instant::now());
+    }
+
+    /// Record a call that timed out or was cancelled before it returned
+    pub fn record_timeout(&self, endpoint: &str, latency: Duration) {
+        self.record_outcome(endpoint, latency, CallOutcome::Timeout, Instant::now());
+    }
+
+    #[cfg(test)]
+    fn record_at(&self, endpoint: &str, latency: Duration, ok: bool, now: Instant) {
+        self.record_outcome(endpoint, latency, CallOutcome::from_ok(ok), now);
+    }
+
+    fn record_outcome(&self, endpoint: &str, latency: Duration, outcome: CallOutcome, now: Instant) {
+        let mut endpoints = self.endpoints.lock().unwrap_or_else(|e| e.into_inner());
+        endpoints.entry(endpoint.to_string()).or_default().record(latency, outcome, now);
+    }
+
+    /// Remember a 401/403 on `endpoint`; endpoints of neither venue are ignored
+    pub fn record_auth_failure(&self, endpoint: &str, status: u16) {
+        let venue = match endpoint.split_whitespace().next() {
+            Some("extended") => Venue::Extended,
+            Some("pacifica") => Venue::Pacifica,
+            _ => return,
+        };
+        let mut failures = self.auth_failures.lock().unwrap_or_else(|e| e.into_inner());
+        failures.retain(|f| f.venue != venue);
+        failures.push(AuthFailure { venue, endpoint: endpoint.to_string(), status });
+    }
+
+    /// Latest auth failure per venue since the previous call
+    pub fn take_auth_failures(&self) -> Vec<AuthFailure> {
+        std::mem::take(&mut *self.auth_failures.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    pub fn summaries(&self) -> Vec<EndpointSummary> {
+        let endpoints = self.endpoints.lock().unwrap_or_else(|e| e.into_inner());
+        endpoints
+            .iter()
+            .map(|(endpoint, stats)| EndpointSummary {
+                endpoint: endpoint.clone(),
+                calls: stats.calls,
+                errors: stats.errors,
+                timeouts: stats.timeouts,
+                mean_latency_ms: stats.total_latency_ms.checked_div(stats.calls).unwrap_or(0),
+                p50_latency_ms: stats.quantile_ms(0.5),
+                p95_latency_ms: stats.quantile_ms(0.95),
+                max_latency_ms: stats.max_latency_ms,
+                latency_histogram: stats.buckets.to_vec(),
+            })
+            .collect()
+    }
+
+    /// Error budget verdict per venue over the configured window
+    pub fn assess(&self, config: &ErrorBudgetConfig) -> Vec<VenueHealth> {
+        self.assess_at(config, Instant::now())
+    }
+
+    fn assess_at(&self, config: &ErrorBudgetConfig, now: Instant) -> Vec<VenueHealth> {
+        let window = Duration::from_secs(config.window_minutes * 60);
+        let endpoints = self.endpoints.lock().unwrap_or_else(|e| e.into_inner());
+        [Venue::Extended, Venue::Pacifica]
+            .into_iter()
+            .map(|venue| {
+                let prefix = venue.to_string().to_lowercase();
+                let (calls, errors) = endpoints
+                    .iter()
+                    .filter(|(endpoint, _)| endpoint.starts_with(&prefix))
+                    .map(|(_, stats)| stats.recent_counts(window, now))
+                    .fold((0, 0), |(c, e), (calls, errors)| (c + calls, e + errors));
+                let error_rate_pct = if calls > 0 { errors as f64 / calls as f64 * 100.0 } else { 0.0 };
+                let exceeds = |limit: f64| limit > 0.0 && calls >= config.min_calls && error_rate_pct >= limit;
+                let degradation = if exceeds(config.skip_scans_error_rate_pct) {
+                    Degradation::SkipScans
+                } else if exceeds(config.delay_opens_error_rate_pct) {
+                    Degradation::DelayOpens
+                } else {
+                    Degradation::Normal
+                };
+                VenueHealth { venue, calls, errors, error_rate_pct, degradation }
+            })
+            .collect()
+    }
+
+    /// Most severe degradation across venues
+    pub fn degradation(&self, config: &ErrorBudgetConfig) -> (Degradation, Vec<VenueHealth>) {
+        let health = self.assess(config);
+        let worst = health.iter().map(|h| h.degradation).max().unwrap_or(Degradation::Normal);
+        (worst, health)
+    }
+}
+
+/// Process-wide registry used by the API clients
+pub fn global() -> &'static ApiMetrics {
+    static METRICS: OnceLock<ApiMetrics> = OnceLock::new();
+    METRICS.get_or_init(ApiMetrics::new)
+}
+
+/// Call in progress; dropped before `finish`, the caller gave up on it (typically an
+/// outer `tokio::time::timeout`) and it is recorded as a timeout
+struct PendingCall<'a> {
+    endpoint: &'a str,
+    started: Instant,
+    finished: bool,
+}
+
+impl<'a> PendingCall<'a> {
+    fn start(endpoint: &'a str) -> Self {
+        Self { endpoint, started: Instant::now(), finished: false }
+    }
+
+    fn finish(mut self, outcome: CallOutcome) {
+        self.finished = true;
+        global().record_outcome(self.endpoint, self.started.elapsed(), outcome, Instant::now());
+    }
+}
+
+impl Drop for PendingCall<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            global().record_timeout(self.endpoint, self.started.elapsed());
+        }
+    }
+}
+
+/// Time a call and record whether it succeeded
+pub async fn observe<T, E>(endpoint: &str, call: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+    let pending = PendingCall::start(endpoint);
+    let result = call.await;
+    pending.finish(CallOutcome::from_ok(result.is_ok()));
+    result
+}
+
+/// Time an HTTP request. Rate limiting (429) and server errors (5xx) count as errors;
+/// other 4xx answers are the venue working as intended and count as successes, with
+/// 401/403 also recorded as an `AuthFailure`.
+pub async fn observe_http(
+    endpoint: &str,
+    request: impl Future<Output = reqwest::Result<reqwest::Response>>,
+) -> reqwest::Result<reqwest::Response> {
+    let pending = PendingCall::start(endpoint);
+    let result = request.await;
+    let outcome = match &result {
+        Ok(response) => {
+            let status = response.status();
+            if matches!(status.as_u16(), 401 | 403) {
+                global().record_auth_failure(endpoint, status.as_u16());
+            }
+            CallOutcome::from_ok(!(status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS))
+        }
+        Err(e) if e.is_timeout() => CallOutcome::Timeout,
+        Err(_) => CallOutcome::Error,
+    };
+    pending.finish(outcome);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_and_error_budget() {
+        let metrics = ApiMetrics::new();
+        let config = ErrorBudgetConfig {
+            window_minutes: 5,
+            min_calls: 10,
+            delay_opens_error_rate_pct: 20.0,
+            skip_scans_error_rate_pct: 50.0,
+        };
+        let start = Instant::now();
+        for i in 0..10 {
+            metrics.record_at("extended GET /user/balance", Duration::from_millis(40 + i * 10), true, start);
+        }
+        metrics.record_at("pacifica GET /api/v1/info", Duration::from_millis(20_000), false, start);
+
+        let summaries = metrics.summaries();
+        let balance = summaries.iter().find(|s| s.endpoint.starts_with("extended")).unwrap();
+        assert_eq!(balance.calls, 10);
+        assert_eq!(balance.p50_latency_ms, Some(100));
+        assert_eq!(balance.max_latency_ms, 130);
+        let info = summaries.iter().find(|s| s.endpoint.starts_with("pacifica")).unwrap();
+        assert_eq!(info.p95_latency_ms, None);
+        assert_eq!(*info.latency_histogram.last().unwrap(), 1);
+
+        // One Pacifica error is below min_calls: no degradation yet
+        assert_eq!(metrics.assess_at(&config, start)[1].degradation, Degradation::Normal);
+
+        // 3 errors in 10 Extended calls (30%) delays opens
+        for _ in 0..3 {
+            metrics.record_at("extended POST /user/order", Duration::from_millis(100), false, start);
+        }
+        let health = metrics.assess_at(&config, start);
+        assert_eq!(health[0].calls, 13);
+        assert_eq!(health[0].degradation, Degradation::DelayOpens);
+
+        // Outside the window the errors no longer count
+        let later = start + Duration::from_secs(6 * 60);
+        assert_eq!(metrics.assess_at(&config, later)[0].calls, 0);
+        assert_eq!(metrics.assess_at(&config, later)[0].degradation, Degradation::Normal);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_call_counts_as_timeout() {
+        let endpoint = "extended GET /test/cancelled";
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(10),
+            observe(endpoint, std::future::pending::<Result<(), ()>>()),
+        )
+        .await;
+        assert!(cancelled.is_err());
+        observe(endpoint, async { Ok::<_, ()>(()) }).await.unwrap();
+
+        let summary = global().summaries().into_iter().find(|s| s.endpoint == endpoint).unwrap();
+        assert_eq!((summary.calls, summary.errors, summary.timeouts), (2, 1, 1));
+        assert!(summary.max_latency_ms >= 10);
+    }
+
+    #[test]
+    fn test_auth_failures_latest_per_venue() {
+        let metrics = ApiMetrics::new();
+        metrics.record_auth_failure("extended GET /user/balance", 401);
+        metrics.record_auth_failure("extended POST /user/order", 403);
+        metrics.record_auth_failure("webhook POST", 401);
+        let failures = metrics.take_auth_failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!((failures[0].venue, failures[0].status), (Venue::Extended, 403));
+        assert!(metrics.take_auth_failures().is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+//! Alert receivers
+//!
+//! Incidents, exposure alerts, audits, daily summaries, opportunity alerts and
+//! rebalance suggestions are all handed to `Hook`s of their record type. `WebhookSink`
+//! posts any of them as JSON, so each kind only picks its message and field name.
+use crate::error::BotError;
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+pub type HookFuture<'a> = Pin<Box<dyn Future<Output = Result<(), BotError>> + Send + 'a>>;
+
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// HTTP client shared by every webhook sink
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+/// Receives records of type `T` (e.g. to page an operator or post to a chat channel)
+pub trait Hook<T: ?Sized>: Send + Sync {
+    fn notify<'a>(&'a self, record: &'a T) -> HookFuture<'a>;
+}
+
+/// POST each record as JSON. `text` and `content` carry the message for Slack and
+/// Discord webhooks; the record itself goes under `field` for custom receivers.
+pub struct WebhookSink<T> {
+    url: String,
+    field: &'static str,
+    message: fn(&T) -> String,
+}
+
+impl<T> WebhookSink<T> {
+    pub fn new(url: impl Into<String>, field: &'static str, message: fn(&T) -> String) -> Self {
+        Self { url: url.into(), field, message }
+    }
+}
+
+impl<T: Serialize + Sync> Hook<T> for WebhookSink<T> {
+    fn notify<'a>(&'a self, record: &'a T) -> HookFuture<'a> {
+        Box::pin(async move {
+            let message = (self.message)(record);
+            let mut body = serde_json::json!({ "text": message, "content": message });
+            body[self.field] = serde_json::to_value(record)
+                .map_err(|e| BotError::classify("Webhook", format!("cannot serialize {}: {}", self.field, e)))?;
+            let response = CLIENT
+                .post(&self.url)
+                .json(&body)
+                .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+                .send()
+                .await
+                .map_err(|e| BotError::classify("Webhook", e.to_string()))?;
+            if !response.status().is_success() {
+                return Err(BotError::classify("Webhook", format!("HTTP {}", response.status())));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[derive(Serialize)]
+    struct Alert {
+        level: u8,
+    }
+
+    #[tokio::test]
+    async fn test_webhook_sink_posts_message_and_record() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !request.ends_with(b"}") {
+                let n = stream.read(&mut chunk).await.unwrap();
+                request.extend_from_slice(&chunk[..n]);
+            }
+            stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+            let request = String::from_utf8(request).unwrap();
+            let body = &request[request.find("\r\n\r\n").unwrap() + 4..];
+            serde_json::from_str::<serde_json::Value>(body).unwrap()
+        });
+
+        let sink = WebhookSink::new(url, "alert", |alert: &Alert| format!("level {}", alert.level));
+        sink.notify(&Alert { level: 2 }).await.unwrap();
+        assert_eq!(
+            server.await.unwrap(),
+            serde_json::json!({ "text": "level 2", "content": "level 2", "alert": { "level": 2 } })
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! Exact decimal helpers for prices, sizes and money
+//!
+//! Exchange APIs return numbers as strings and validate order sizes against exact
+//! lot increments, so rounding is done on `Decimal` rather than `f64` (where e.g.
+//! `(0.3 / 0.1).floor()` is 2).
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::RoundingStrategy;
+use std::str::FromStr;
+
+pub use rust_decimal::Decimal;
+
+/// Parse an API numeric string (plain or scientific notation)
+pub fn parse_decimal(s: &str) -> Option<Decimal> {
+    let s = s.trim();
+    Decimal::from_str(s)
+        .or_else(|_| Decimal::from_scientific(s))
+        .ok()
+}
+
+/// Parse an API numeric string, treating missing/invalid values as zero
+pub fn parse_decimal_or_zero(s: &str) -> Decimal {
+    parse_decimal(s).unwrap_or(Decimal::ZERO)
+}
+
+/// Convert an f64 using its shortest round-trip representation, so 0.1 becomes exactly 0.1
+pub fn decimal_from_f64(value: f64) -> Decimal {
+    if !value.is_finite() {
+        return Decimal::ZERO;
+    }
+    Decimal::from_str(&value.to_string())
+        .ok()
+        .or_else(|| Decimal::from_f64(value))
+        .unwrap_or(Decimal::ZERO)
+}
+
+/// Convert back to f64 for display and legacy call sites
+pub fn decimal_to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(0.0)
+}
+
+/// Round down to a whole number of `step` increments (a non-positive step returns the value)
+pub fn round_down_to_step(value: Decimal, step: Decimal) -> Decimal {
+    if step <= Decimal::ZERO {
+        return value;
+    }
+    ((value / step).floor() * step).normalize()
+}
+
+/// Round to the nearest multiple of `step`, halves away from zero
+pub fn round_to_step(value: Decimal, step: Decimal) -> Decimal {
+    if step <= Decimal::ZERO {
+        return value;
+    }
+    let steps = (value / step).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero);
+    (steps * step).normalize()
+}
+
+/// Format with exactly `dp` decimal places (halves away from zero), e.g. for signed order fields
+pub fn format_fixed(value: Decimal, dp: u32) -> String {
+    let mut rounded = value.round_dp_with_strategy(dp, RoundingStrategy::MidpointAwayFromZero);
+    rounded.rescale(dp);
+    rounded.to_string()
+}
+
+/// Number of decimal places implied by an increment such as "0.001" (3) or "1" (0)
+pub fn step_decimals(step: Decimal) -> u32 {
+    step.normalize().scale()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> Decimal {
+        parse_decimal(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(d("0.001"), Decimal::new(1, 3));
+        assert_eq!(d(" 42 "), Decimal::new(42, 0));
+        assert_eq!(d("1e-4"), Decimal::new(1, 4));
+        assert!(parse_decimal("abc").is_none());
+        assert_eq!(parse_decimal_or_zero(""), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_round_down_to_step_is_exact() {
+        // f64: (0.3 / 0.1).floor() * 0.1 == 0.2
+        assert_eq!(round_down_to_step(decimal_from_f64(0.3), d("0.1")), d("0.3"));
+        assert_eq!(round_down_to_step(d("1.2399"), d("0.01")), d("1.23"));
+        assert_eq!(round_down_to_step(d("7"), d("5")), d("5"));
+        assert_eq!(round_down_to_step(d("7"), Decimal::ZERO), d("7"));
+    }
+
+    #[test]
+    fn test_round_to_step() {
+        assert_eq!(round_to_step(d("1.235"), d("0.01")), d("1.24"));
+        assert_eq!(round_to_step(d("1.234"), d("0.01")), d("1.23"));
+        assert_eq!(round_to_step(d("12.5"), d("5")), d("15"));
+    }
+
+    #[test]
+    fn test_format_fixed_and_step_decimals() {
+        assert_eq!(format_fixed(d("0.3"), 3), "0.300");
+        assert_eq!(format_fixed(d("101234.5"), 0), "101235");
+        assert_eq!(step_decimals(d("0.0010")), 3);
+        assert_eq!(step_decimals(d("1")), 0);
+    }
+}
@@ -0,0 +1,105 @@
+//! Read-only observer: scans, reconciliation and status without signing keys
+//!
+//! `BotBuilder::build_read_only` yields a `ReadOnlyBot`, which needs only read
+//! credentials: an Extended API key and the Pacifica account address. Signing keys given
+//! to the builder are dropped, and the type has no open, close or flatten methods, so an
+//! observer cannot reach an order path. Each cycle re-reads the state file written by the
+//! trading bot and reconciles it against both venues in memory only; the observer never
+//! writes the state, heartbeat or intent log files.
+use crate::bot::{BotState, FundingBot, StatusSnapshot};
+use crate::error::BotError;
+use crate::opportunity::ScanResult;
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub struct ReadOnlyBot {
+    bot: FundingBot,
+    extended_api_key: Option<String>,
+}
+
+impl ReadOnlyBot {
+    pub(crate) fn new(bot: FundingBot, extended_api_key: Option<String>) -> Self {
+        Self { bot, extended_api_key }
+    }
+
+    /// State as last reloaded and reconciled
+    pub fn state(&self) -> &BotState {
+        self.bot.state()
+    }
+
+    pub async fn scan(&self) -> Result<ScanResult, BotError> {
+        self.bot.scan(self.extended_api_key.clone()).await
+    }
+
+    /// Reload the trading bot's state file and reconcile it with live positions
+    pub async fn reconcile(&mut self) -> Result<(), BotError> {
+        if let Err(e) = self.bot.reload_state().await {
+            warn!("Failed to reload state file: {}. Using the last state read.", e);
+        }
+        self.bot.reconcile_or_recover().await
+    }
+
+    pub async fn status_snapshot(&self) -> Result<StatusSnapshot, BotError> {
+        self.bot.status_snapshot().await
+    }
+
+    pub async fn display_status(&self) -> Result<(), BotError> {
+        self.bot.display_status().await
+    }
+
+    /// Reconcile, show status and scan every `interval` until Ctrl+C
+    pub async fn run(&mut self, interval: Duration) -> Result<(), BotError> {
+        info!("👀 Observer mode: read-only, no orders can be placed. Refreshing every {}s", interval.as_secs());
+        loop {
+            if let Err(e) = self.reconcile().await {
+                warn!("Reconciliation failed: {}", e);
+            }
+            if let Err(e) = self.display_status().await {
+                warn!("Status failed: {}", e);
+            }
+            match self.scan().await {
+                Ok(scan) => match scan.opportunities.first() {
+                    Some(best) => info!("Best opportunity: {} at {:.2}% net APR ({})", best.symbol, best.best_net_apr, best.best_direction),
+                    None => info!("No opportunity passes the filters"),
+                },
+                Err(e) => warn!("Scan failed: {}", e),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {},
+                _ = tokio::signal::ctrl_c() => {
+                    info!("🛑 Shutdown signal received. Observer stopped.");
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opportunity::Config;
+    use crate::pacifica::PacificaCredentials;
+
+    #[test]
+    fn test_builder_requires_signing_keys_only_to_trade() {
+        let signing = PacificaCredentials {
+            account: "account".into(),
+            agent_wallet: "agent".into(),
+            private_key: "key".into(),
+        };
+
+        let missing_stark = FundingBot::builder(Config::default_config()).pacifica_credentials(signing.clone()).build();
+        assert!(matches!(missing_stark, Err(BotError::Config(_))));
+        let account_only = FundingBot::builder(Config::default_config())
+            .pacifica_account("account")
+            .stark_keys("0x1", "0x2", "1")
+            .build();
+        assert!(matches!(account_only, Err(BotError::Config(_))));
+
+        assert!(matches!(FundingBot::builder(Config::default_config()).build_read_only(), Err(BotError::Config(_))));
+        // The agent key is dropped rather than rejected
+        assert!(FundingBot::builder(Config::default_config()).pacifica_credentials(signing).build_read_only().is_ok());
+    }
+}
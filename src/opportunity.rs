@@ -1,49 +1,587 @@
-/// Opportunity finding and filtering for cross-exchange arbitrage
-use crate::{PacificaTrading, PacificaCredentials, RestClient};
+//! Opportunity finding and filtering for cross-exchange arbitrage
+use crate::websocket::{MultiMarketSubscriber, TopOfBookCache, WebSocketClient};
+use crate::types::{AccountInfo, OrderBook};
+use crate::{
+    BidAsk, BotError, ConnectorError, DepthCache, MarketInfo, OrderbookClient, OrderbookConfig, PacificaMarketInfo, PacificaTrading,
+    PacificaCredentials, RestClient,
+};
+use crate::capital::Venue;
+use crate::api_capture::{self, ApiCaptureConfig};
+use crate::metrics::ErrorBudgetConfig;
+use crate::funding::{apr_from_rate, net_apr_pct, FundingCapConfig, FundingForecast, FundingIntervalConfig, HOURS_PER_YEAR};
+use crate::retry::RetryPolicy;
+use crate::trading::{estimate_fill_slippage_pct, parse_levels, LegSequence};
+use crate::risk::RiskConfig;
+use crate::watchdog::WatchdogConfig;
+use crate::daily_summary::DailySummaryConfig;
+use crate::audit::AuditConfig;
+use crate::control::ControlConfig;
+use crate::spread_history::{resolve_spread_history_path, SpreadHistory, SpreadHistoryConfig};
+use crate::contingency::ContingencyConfig;
+use crate::funding_stream::FundingStreamConfig;
+use crate::account_stream::AccountStreamConfig;
+use crate::fees::{self, FeeConfig, FeeSchedule};
+use crate::data_failover::{BookSources, DataAvailability, DataFailoverConfig, QuoteSource};
+use crate::cycle_budget::CycleBudgetConfig;
+use crate::entry_timing::EntryTimingConfig;
+use crate::shutdown::ShutdownConfig;
+use crate::order_sweeper::OrderSweepConfig;
+use crate::protective_orders::ProtectiveOrdersConfig;
+use crate::symbols::{ContractMultipliers, SymbolMap};
+use crate::stress::StressConfig;
+use crate::scoring::{dedup_by_symbol, rank, scorer_from_config, Scorer, ScoringConfig};
+use crate::pacifica::trading::OrderbookSnapshot;
+use crate::pacifica::OrderTimeoutConfig;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::Duration;
+use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
-use tracing::info;
-use prettytable::{Table, Row, Cell, format};
-use colored::*;
+use tokio::time::{sleep, timeout};
+use tracing::{debug, warn};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
+    /// Exchange environment used by every client (mainnet unless set)
+    #[serde(default)]
+    pub environment: Environment,
     pub filters: FilterConfig,
     pub trading: TradingConfig,
     pub display: DisplayConfig,
     pub performance: PerformanceConfig,
+    #[serde(default)]
+    pub execution: ExecutionConfig,
+    #[serde(default)]
+    pub sizing: SizingConfig,
+    #[serde(default)]
+    pub capital: CapitalConfig,
+    #[serde(default)]
+    pub export: ExportConfig,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    #[serde(default)]
+    pub rotation: RotationConfig,
+    #[serde(default)]
+    pub scan_daemon: ScanDaemonConfig,
+    #[serde(default)]
+    pub risk: RiskConfig,
+    #[serde(default)]
+    pub error_budget: ErrorBudgetConfig,
+    #[serde(default)]
+    pub funding_intervals: FundingIntervalConfig,
+    #[serde(default)]
+    pub funding_caps: FundingCapConfig,
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+    #[serde(default)]
+    pub extended_vaults: ExtendedVaultConfig,
+    #[serde(default)]
+    pub daily_summary: DailySummaryConfig,
+    #[serde(default)]
+    pub pacifica_order_timeout: OrderTimeoutConfig,
+    #[serde(default)]
+    pub control: ControlConfig,
+    #[serde(default)]
+    pub spread_history: SpreadHistoryConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub scoring: ScoringConfig,
+    #[serde(default)]
+    pub funding_stream: FundingStreamConfig,
+    #[serde(default)]
+    pub contingency: ContingencyConfig,
+    #[serde(default)]
+    pub order_sweep: OrderSweepConfig,
+    #[serde(default)]
+    pub protective_orders: ProtectiveOrdersConfig,
+    #[serde(default)]
+    pub symbols: SymbolMap,
+    #[serde(default)]
+    pub stress: StressConfig,
+    #[serde(default)]
+    pub api_capture: ApiCaptureConfig,
+    #[serde(default)]
+    pub account_stream: AccountStreamConfig,
+    #[serde(default)]
+    pub fees: FeeConfig,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    #[serde(default)]
+    pub data_failover: DataFailoverConfig,
+    #[serde(default)]
+    pub entry_timing: EntryTimingConfig,
+    #[serde(default)]
+    pub cycle_budget: CycleBudgetConfig,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Exchange environment for both venues
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl Environment {
+    pub fn is_testnet(&self) -> bool {
+        *self == Environment::Testnet
+    }
+
+    /// Extended REST client for this environment (Sepolia on testnet)
+    pub fn extended_client(&self, api_key: Option<String>) -> Result<RestClient, ConnectorError> {
+        match self {
+            Environment::Mainnet => RestClient::new_mainnet(api_key),
+            Environment::Testnet => RestClient::new_testnet(api_key),
+        }
+    }
+
+    /// Extended WebSocket client for this environment
+    pub fn extended_ws_client(&self, api_key: Option<String>) -> WebSocketClient {
+        match self {
+            Environment::Mainnet => WebSocketClient::new_mainnet(api_key),
+            Environment::Testnet => WebSocketClient::new_testnet(api_key),
+        }
+    }
+
+    /// Pacifica orderbook WebSocket client for this environment
+    pub fn pacifica_orderbook_client(&self, config: OrderbookConfig) -> Result<OrderbookClient, BotError> {
+        let client = match self {
+            Environment::Mainnet => OrderbookClient::new(config)?,
+            Environment::Testnet => OrderbookClient::new_testnet(config)?,
+        };
+        Ok(client)
+    }
+
+    /// Pacifica REST trading client for this environment
+    pub fn pacifica_client(&self, credentials: PacificaCredentials) -> PacificaTrading {
+        match self {
+            Environment::Mainnet => PacificaTrading::new(credentials),
+            Environment::Testnet => PacificaTrading::new_testnet(credentials),
+        }
+    }
+}
+
+impl std::fmt::Display for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Environment::Mainnet => write!(f, "mainnet"),
+            Environment::Testnet => write!(f, "testnet"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FilterConfig {
     pub min_combined_volume_usd: f64,
     pub max_intra_exchange_spread_pct: f64,
     pub max_cross_exchange_spread_pct: f64,
     pub min_net_apr_pct: f64,
+    /// Base symbols never traded or scanned (case-insensitive, e.g. ["PUMP", "XPL"])
+    #[serde(default)]
+    pub symbol_blacklist: Vec<String>,
+    /// Skip markets whose open interest on either venue is below this multiple of
+    /// max_position_size_usd (0 = off; venues not reporting open interest are not checked)
+    #[serde(default)]
+    pub min_open_interest_multiple: f64,
+    /// Skip markets whose mark price deviates from the index price by more than this %
+    /// on either venue, a sign of a squeeze (0 = off; venues not reporting both prices
+    /// are not checked)
+    #[serde(default)]
+    pub max_mark_index_gap_pct: f64,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TradingConfig {
     pub max_position_size_usd: f64,
     pub hold_time_hours: u64,
+    /// Leverage applied on both exchanges (validated against each market's max_leverage)
+    #[serde(default = "default_leverage")]
+    pub leverage: u32,
+    /// Margin mode used on Pacifica
+    #[serde(default)]
+    pub pacifica_margin_mode: MarginMode,
+    /// Minimum estimated distance to liquidation (% of entry price) the leverage must leave
+    #[serde(default = "default_min_liquidation_distance_pct")]
+    pub min_liquidation_distance_pct: f64,
+    /// Minutes to wait after a close before opening again; also blocks re-entering the
+    /// last closed symbol at a lower APR (0 disables churn protection)
+    #[serde(default)]
+    pub min_minutes_between_rotations: u64,
+}
+
+/// Margin mode for venues that support both cross and isolated margin
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MarginMode {
+    #[default]
+    Cross,
+    Isolated,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+fn default_leverage() -> u32 {
+    1
+}
+
+fn default_min_liquidation_distance_pct() -> f64 {
+    20.0
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DisplayConfig {
     pub max_opportunities_shown: usize,
     pub show_filtered_out_count: bool,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PerformanceConfig {
     pub fetch_timeout_seconds: u64,
     pub rate_limit_delay_ms: u64,
+    /// Maximum number of per-symbol fetch tasks in flight during a scan
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// Keep Extended best bid/ask for all markets streaming over one WebSocket and
+    /// use it in scans instead of a REST orderbook call per symbol
+    #[serde(default = "default_stream_extended_orderbooks")]
+    pub stream_extended_orderbooks: bool,
+    /// Same for Pacifica, over one WebSocket subscribed to every scanned symbol
+    #[serde(default = "default_stream_pacifica_orderbooks")]
+    pub stream_pacifica_orderbooks: bool,
+    /// Streamed quotes older than this fall back to REST
+    #[serde(default = "default_orderbook_max_age_seconds")]
+    pub orderbook_max_age_seconds: u64,
+    /// A started orderbook stream with no update for this long is considered dead:
+    /// opens and rotations wait until it recovers (0 = off)
+    #[serde(default = "default_max_stream_silence_seconds")]
+    pub max_stream_silence_seconds: u64,
+}
+
+fn default_max_concurrent_requests() -> usize {
+    8
+}
+
+fn default_stream_extended_orderbooks() -> bool {
+    true
+}
+
+fn default_stream_pacifica_orderbooks() -> bool {
+    true
+}
+
+fn default_orderbook_max_age_seconds() -> u64 {
+    10
+}
+
+fn default_max_stream_silence_seconds() -> u64 {
+    60
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExecutionConfig {
+    /// Maximum expected slippage vs mid (%) per leg; also caps the Pacifica market order
+    /// tolerance, and is the tolerance of rollbacks
+    #[serde(default = "default_max_slippage_pct")]
+    pub max_slippage_pct: f64,
+    /// Added to a Pacifica open's expected book-walk slippage (%) to set its tolerance
+    #[serde(default = "default_slippage_margin_pct")]
+    pub slippage_margin_pct: f64,
+    /// Share (%) of a favorable cross-venue price difference the leading leg must keep
+    /// when the long venue is cheaper than the short venue (0 = always open at market)
+    #[serde(default)]
+    pub spread_capture_pct: f64,
+    /// Which open leg is sent first; a spread-capture plan overrides it
+    #[serde(default)]
+    pub leg_order: LegOrder,
+    /// Re-fetch both funding rates right before sending orders and abort the open if the
+    /// net funding of the chosen direction is no longer positive
+    #[serde(default = "default_recheck_funding")]
+    pub recheck_funding: bool,
+}
+
+fn default_recheck_funding() -> bool {
+    true
+}
+
+/// Submission order of the two open legs (`execution.leg_order`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LegOrder {
+    #[default]
+    ExtendedFirst,
+    PacificaFirst,
+    /// The venue that costs more to fill the size first: it is the leg most likely to
+    /// miss, and a miss there leaves nothing to roll back
+    LessLiquidFirst,
+    /// Both legs at once, rolling back the one whose counterpart failed
+    Concurrent,
+}
+
+fn default_max_slippage_pct() -> f64 {
+    0.5
+}
+
+fn default_slippage_margin_pct() -> f64 {
+    0.1
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            max_slippage_pct: default_max_slippage_pct(),
+            slippage_margin_pct: default_slippage_margin_pct(),
+            spread_capture_pct: 0.0,
+            leg_order: LegOrder::default(),
+            recheck_funding: default_recheck_funding(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SizingConfig {
+    /// Scale max_position_size_usd by opportunity quality and volatility
+    #[serde(default)]
+    pub dynamic: bool,
+    /// Net APR (%) at which the full max_position_size_usd is deployed
+    #[serde(default = "default_reference_apr_pct")]
+    pub reference_apr_pct: f64,
+    /// Lower bound for each scaling factor
+    #[serde(default = "default_min_scale")]
+    pub min_scale: f64,
+    /// Hourly ATR (% of price) above which size is reduced proportionally
+    #[serde(default = "default_target_atr_pct")]
+    pub target_atr_pct: f64,
+    /// Number of hourly candles used for ATR
+    #[serde(default = "default_atr_periods")]
+    pub atr_periods: usize,
+    /// Grow max_position_size_usd with account equity at each rotation (compounds funding gains)
+    #[serde(default)]
+    pub compound: bool,
+    /// Share of profits (% of equity gained since compounding started) kept idle as a buffer
+    #[serde(default)]
+    pub profit_skim_pct: f64,
+}
+
+fn default_reference_apr_pct() -> f64 {
+    50.0
+}
+
+fn default_min_scale() -> f64 {
+    0.25
+}
+
+fn default_target_atr_pct() -> f64 {
+    1.0
+}
+
+fn default_atr_periods() -> usize {
+    24
+}
+
+impl Default for SizingConfig {
+    fn default() -> Self {
+        Self {
+            dynamic: false,
+            reference_apr_pct: default_reference_apr_pct(),
+            min_scale: default_min_scale(),
+            target_atr_pct: default_target_atr_pct(),
+            atr_periods: default_atr_periods(),
+            compound: false,
+            profit_skim_pct: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CapitalConfig {
+    /// Free-collateral imbalance (% of total) that triggers a rebalance suggestion
+    #[serde(default = "default_rebalance_threshold_pct")]
+    pub rebalance_threshold_pct: f64,
+    /// Smallest transfer worth suggesting
+    #[serde(default = "default_min_transfer_usd")]
+    pub min_transfer_usd: f64,
+    /// Largest share of either account's equity committed as margin after a new open,
+    /// across every position the account holds (0 = no cap)
+    #[serde(default)]
+    pub max_margin_utilization_pct: f64,
+}
+
+fn default_rebalance_threshold_pct() -> f64 {
+    30.0
+}
+
+fn default_min_transfer_usd() -> f64 {
+    50.0
+}
+
+impl Default for CapitalConfig {
+    fn default() -> Self {
+        Self {
+            rebalance_threshold_pct: default_rebalance_threshold_pct(),
+            min_transfer_usd: default_min_transfer_usd(),
+            max_margin_utilization_pct: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RotationConfig {
+    /// Rotate before hold_time_hours when the best opportunity beats the held one by at
+    /// least this many APR points after switching costs (0 = disabled)
+    #[serde(default)]
+    pub early_rotation_threshold_apr_pct: f64,
+    /// Taker fee (%) assumed per order when estimating costs, unless `fees.enabled`
+    #[serde(default = "default_taker_fee_pct")]
+    pub taker_fee_pct: f64,
+}
+
+fn default_taker_fee_pct() -> f64 {
+    0.05
+}
+
+impl Default for RotationConfig {
+    fn default() -> Self {
+        Self {
+            early_rotation_threshold_apr_pct: 0.0,
+            taker_fee_pct: default_taker_fee_pct(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportConfig {
+    /// Write every scan to a timestamped file
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_export_directory")]
+    pub directory: String,
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    Both,
+}
+
+fn default_export_directory() -> String {
+    "scans".to_string()
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: default_export_directory(),
+            format: ExportFormat::default(),
+        }
+    }
+}
+
+/// Settings for `extended_connector scan-daemon` (scan and alert, never trade)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScanDaemonConfig {
+    #[serde(default = "default_scan_interval_minutes")]
+    pub interval_minutes: u64,
+    /// Alert when an opportunity's net APR reaches this value (%)
+    #[serde(default = "default_alert_min_net_apr_pct")]
+    pub alert_min_net_apr_pct: f64,
+    /// Optional URL receiving a JSON POST per alert (Slack/Discord-style webhooks)
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+fn default_scan_interval_minutes() -> u64 {
+    5
+}
+
+fn default_alert_min_net_apr_pct() -> f64 {
+    50.0
+}
+
+impl Default for ScanDaemonConfig {
+    fn default() -> Self {
+        Self {
+            interval_minutes: default_scan_interval_minutes(),
+            alert_min_net_apr_pct: default_alert_min_net_apr_pct(),
+            webhook_url: None,
+        }
+    }
+}
+
+/// Extended vaults (sub-accounts) to trade in instead of `VAULT_NUMBER`, so symbols or
+/// strategies can be kept in isolated margin buckets. Positions, balances, reconciliation
+/// and recovery all go through the one API key of `VAULT_NUMBER`, while every sub-account
+/// has its own API key and Stark key, so routing to another vault is refused until vaults
+/// carry their own credentials.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ExtendedVaultConfig {
+    /// Vault per strategy name (e.g. "cross_exchange_funding")
+    #[serde(default)]
+    pub strategy_vaults: HashMap<String, String>,
+    /// Vault per base symbol (e.g. {"BTC": "123456"}); overrides the strategy's vault
+    #[serde(default)]
+    pub symbol_vaults: HashMap<String, String>,
+}
+
+impl ExtendedVaultConfig {
+    /// Vault for opening `symbol` under `strategy`, falling back to `default_vault`
+    pub fn vault_for<'a>(&'a self, strategy: &str, symbol: &str, default_vault: &'a str) -> &'a str {
+        self.symbol_vaults
+            .get(symbol)
+            .or_else(|| self.strategy_vaults.get(strategy))
+            .map(String::as_str)
+            .unwrap_or(default_vault)
+    }
+
+    /// Every vault named in the config, deduplicated
+    pub fn configured_vaults(&self) -> Vec<&str> {
+        let mut vaults: Vec<&str> =
+            self.strategy_vaults.values().chain(self.symbol_vaults.values()).map(String::as_str).collect();
+        vaults.sort_unstable();
+        vaults.dedup();
+        vaults
+    }
+
+    /// Check configured vaults against the accounts the API key can see. Only
+    /// `default_vault` is supported: the bot has no credentials for other vaults.
+    pub fn verify(&self, default_vault: &str, accounts: &[AccountInfo], stark_public_key: &str) -> Result<(), String> {
+        let normalize = |key: &str| key.trim_start_matches("0x").trim_start_matches('0').to_lowercase();
+        for vault in self.configured_vaults() {
+            if vault != default_vault {
+                return Err(format!(
+                    "extended_vaults: routing to vault {} is not supported: positions, balances and reconciliation \
+                     use the API key of VAULT_NUMBER {}, and another vault needs its own API key and Stark key",
+                    vault, default_vault
+                ));
+            }
+            let Some(account) = accounts.iter().find(|a| a.l2_vault == vault) else {
+                let known: Vec<&str> = accounts.iter().map(|a| a.l2_vault.as_str()).collect();
+                return Err(format!(
+                    "extended_vaults: vault {} is not an account of this API key (accounts: {})",
+                    vault,
+                    known.join(", ")
+                ));
+            };
+            if normalize(&account.l2_key) != normalize(stark_public_key) {
+                return Err(format!(
+                    "extended_vaults: vault {} belongs to Stark key {}, not the configured STARK_PUBLIC",
+                    vault, account.l2_key
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Config {
-    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn load(path: &str) -> Result<Self, BotError> {
         let config_str = fs::read_to_string(path)
             .map_err(|e| format!("Failed to read {}: {}", path, e))?;
         let config: Config = serde_json::from_str(&config_str)
@@ -56,7 +594,7 @@ impl Config {
     }
 
     /// Validate configuration parameters for sanity
-    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn validate(&self) -> Result<(), BotError> {
         // Validate filters
         if self.filters.min_combined_volume_usd < 0.0 {
             return Err("min_combined_volume_usd must be non-negative".into());
@@ -99,6 +637,25 @@ impl Config {
         if self.trading.hold_time_hours > 720 {
             return Err("hold_time_hours is very high (>30 days). Please verify this is intentional.".into());
         }
+        if self.trading.leverage == 0 {
+            return Err("leverage must be at least 1".into());
+        }
+        if self.trading.leverage > 50 {
+            return Err("leverage is very high (>50x). Please verify this is intentional.".into());
+        }
+        if self.trading.min_liquidation_distance_pct < 0.0 || self.trading.min_liquidation_distance_pct > 100.0 {
+            return Err("min_liquidation_distance_pct must be between 0 and 100".into());
+        }
+        if self.trading.min_minutes_between_rotations > 7 * 24 * 60 {
+            return Err("min_minutes_between_rotations is very high (>7 days). Please verify this is intentional.".into());
+        }
+        let liquidation_distance = crate::trading::estimated_liquidation_distance_pct(self.trading.leverage);
+        if liquidation_distance < self.trading.min_liquidation_distance_pct {
+            return Err(format!(
+                "leverage {}x leaves ~{:.1}% liquidation distance, below min_liquidation_distance_pct {:.1}%",
+                self.trading.leverage, liquidation_distance, self.trading.min_liquidation_distance_pct
+            ).into());
+        }
 
         // Validate performance config
         if self.performance.fetch_timeout_seconds == 0 {
@@ -107,21 +664,233 @@ impl Config {
         if self.performance.fetch_timeout_seconds > 600 {
             return Err("fetch_timeout_seconds is very high (>10 minutes)".into());
         }
+        if self.performance.max_concurrent_requests == 0 {
+            return Err("max_concurrent_requests must be positive".into());
+        }
+
+        // Validate execution config
+        if self.execution.max_slippage_pct <= 0.0 {
+            return Err("max_slippage_pct must be positive".into());
+        }
+        if self.execution.max_slippage_pct > 10.0 {
+            return Err("max_slippage_pct is very high (>10%)".into());
+        }
+        if !(0.0..=100.0).contains(&self.execution.spread_capture_pct) {
+            return Err("execution.spread_capture_pct must be between 0 and 100".into());
+        }
+        if self.execution.slippage_margin_pct < 0.0 {
+            return Err("execution.slippage_margin_pct cannot be negative".into());
+        }
+
+        // Validate sizing config
+        if !(0.0..=1.0).contains(&self.sizing.min_scale) {
+            return Err("sizing.min_scale must be between 0 and 1".into());
+        }
+        if self.sizing.reference_apr_pct <= 0.0 {
+            return Err("sizing.reference_apr_pct must be positive".into());
+        }
+        if self.sizing.target_atr_pct <= 0.0 {
+            return Err("sizing.target_atr_pct must be positive".into());
+        }
+        if self.sizing.atr_periods < 2 {
+            return Err("sizing.atr_periods must be at least 2".into());
+        }
+        if !(0.0..=100.0).contains(&self.sizing.profit_skim_pct) {
+            return Err("sizing.profit_skim_pct must be between 0 and 100".into());
+        }
+
+        // Validate capital config
+        if !(0.0..=100.0).contains(&self.capital.rebalance_threshold_pct) {
+            return Err("capital.rebalance_threshold_pct must be between 0 and 100".into());
+        }
+        if self.capital.min_transfer_usd < 0.0 {
+            return Err("capital.min_transfer_usd must be non-negative".into());
+        }
+
+        // Validate scan daemon config
+        if self.scan_daemon.interval_minutes == 0 || self.scan_daemon.interval_minutes > 24 * 60 {
+            return Err("scan_daemon.interval_minutes must be between 1 and 1440".into());
+        }
+
+        // Validate risk limits
+        if self.risk.max_notional_per_symbol_usd < 0.0 {
+            return Err("risk.max_notional_per_symbol_usd must be non-negative".into());
+        }
+        if let Some((symbol, _)) = self.risk.symbol_max_notional_usd.iter().find(|(_, cap)| **cap < 0.0) {
+            return Err(format!("risk.symbol_max_notional_usd for {} must be non-negative", symbol).into());
+        }
+        if !(0.0..=100.0).contains(&self.risk.max_equity_share_pct) {
+            return Err("risk.max_equity_share_pct must be between 0 and 100".into());
+        }
+
+        // Validate error budget
+        if self.error_budget.window_minutes == 0 {
+            return Err("error_budget.window_minutes must be greater than 0".into());
+        }
+        for (name, pct) in [
+            ("delay_opens_error_rate_pct", self.error_budget.delay_opens_error_rate_pct),
+            ("skip_scans_error_rate_pct", self.error_budget.skip_scans_error_rate_pct),
+        ] {
+            if !(0.0..=100.0).contains(&pct) {
+                return Err(format!("error_budget.{} must be between 0 and 100", name).into());
+            }
+        }
+
+        if let Some((key, hours)) = self.funding_intervals.invalid_entry() {
+            return Err(format!("funding_intervals.{} must be a positive number of hours, got {}", key, hours).into());
+        }
+        if let Some((key, cap)) = self.funding_caps.invalid_entry() {
+            return Err(format!("funding_caps.{} must be a non-negative rate, got {}", key, cap).into());
+        }
+        if !(self.filters.min_open_interest_multiple.is_finite() && self.filters.min_open_interest_multiple >= 0.0) {
+            return Err("filters.min_open_interest_multiple must be non-negative".into());
+        }
+        if !(self.filters.max_mark_index_gap_pct.is_finite() && self.filters.max_mark_index_gap_pct >= 0.0) {
+            return Err("filters.max_mark_index_gap_pct must be non-negative".into());
+        }
+
+        if let Some(vault) = self.extended_vaults.configured_vaults().into_iter().find(|v| v.parse::<u64>().is_err()) {
+            return Err(format!("extended_vaults: vault ID must be numeric, got {:?}", vault).into());
+        }
+
+        // Validate watchdog
+        if self.watchdog.cycle_deadline_minutes > 24 * 60 {
+            return Err("watchdog.cycle_deadline_minutes must be at most 1440 (0 = no deadline)".into());
+        }
+
+        if self.daily_summary.hour_utc > 23 {
+            return Err("daily_summary.hour_utc must be between 0 and 23".into());
+        }
+
+        if self.audit.enabled && self.audit.interval_minutes == 0 {
+            return Err("audit.interval_minutes must be positive".into());
+        }
+
+        if self.pacifica_order_timeout.enabled
+            && (self.pacifica_order_timeout.max_age_seconds == 0 || self.pacifica_order_timeout.check_interval_seconds == 0)
+        {
+            return Err("pacifica_order_timeout.max_age_seconds and check_interval_seconds must be positive".into());
+        }
+
+        if self.spread_history.enabled {
+            if self.spread_history.window_samples == 0 {
+                return Err("spread_history.window_samples must be positive".into());
+            }
+            if !(0.0..=100.0).contains(&self.spread_history.percentile) {
+                return Err("spread_history.percentile must be between 0 and 100".into());
+            }
+        }
+
+        if self.scoring.stability_window_samples == 0 {
+            return Err("scoring.stability_window_samples must be positive".into());
+        }
+        if self.scoring.volume_half_weight_usd <= 0.0 {
+            return Err("scoring.volume_half_weight_usd must be positive".into());
+        }
+        if self.scoring.switch_margin_apr_pct < 0.0 {
+            return Err("scoring.switch_margin_apr_pct cannot be negative".into());
+        }
+
+        if self.order_sweep.enabled && self.order_sweep.check_interval_seconds == 0 {
+            return Err("order_sweep.check_interval_seconds must be positive".into());
+        }
+
+        if self.funding_stream.enabled {
+            if self.funding_stream.max_age_seconds == 0 {
+                return Err("funding_stream.max_age_seconds must be positive".into());
+            }
+            if self.funding_stream.check_interval_seconds == 0 {
+                return Err("funding_stream.check_interval_seconds must be positive".into());
+            }
+        }
+
+        if self.control.enabled && self.control.bind_address.parse::<std::net::SocketAddr>().is_err() {
+            return Err(format!("control.bind_address must be an IP:port address, got {}", self.control.bind_address).into());
+        }
+
+        // Validate rotation config
+        if self.rotation.early_rotation_threshold_apr_pct < 0.0 {
+            return Err("rotation.early_rotation_threshold_apr_pct must be non-negative".into());
+        }
+        if !(0.0..=1.0).contains(&self.rotation.taker_fee_pct) {
+            return Err("rotation.taker_fee_pct must be between 0 and 1".into());
+        }
+
+        // Validate retry policy
+        if self.retry.max_attempts == 0 {
+            return Err("retry.max_attempts must be at least 1".into());
+        }
+        if self.retry.max_attempts > 20 {
+            return Err("retry.max_attempts is very high (>20)".into());
+        }
+        if self.retry.max_delay_ms < self.retry.base_delay_ms {
+            return Err("retry.max_delay_ms must be at least retry.base_delay_ms".into());
+        }
+        if !(0.0..=100.0).contains(&self.retry.jitter_pct) {
+            return Err("retry.jitter_pct must be between 0 and 100".into());
+        }
+
+        self.symbols.validate()?;
+        self.stress.validate()?;
+        self.api_capture.validate()?;
+        self.risk.margin_call.validate()?;
+        if !(0.0..=100.0).contains(&self.capital.max_margin_utilization_pct) {
+            return Err("capital.max_margin_utilization_pct must be between 0 and 100".into());
+        }
+        if self.account_stream.enabled && self.account_stream.max_age_seconds == 0 {
+            return Err("account_stream.max_age_seconds must be positive".into());
+        }
+        self.fees.validate()?;
+        self.data_failover.validate()?;
+        self.entry_timing.validate()?;
+        if let (Some(budget), Some(deadline)) = (self.cycle_budget.budget(), self.watchdog.cycle_deadline()) {
+            if budget >= deadline {
+                return Err("cycle_budget.budget_seconds must be below watchdog.cycle_deadline_minutes".into());
+            }
+        }
 
         Ok(())
     }
 
+    /// Fee rates of both venues: the `fees` tiers (Pacifica's as last read from the
+    /// account) when enabled, else `rotation.taker_fee_pct` on every order
+    pub fn fee_schedule(&self) -> FeeSchedule {
+        if self.fees.enabled {
+            self.fees.schedule(fees::pacifica_tier())
+        } else {
+            FeeSchedule::flat(self.rotation.taker_fee_pct)
+        }
+    }
+
+    /// Fees (% of one leg's notional) of opening both legs
+    pub fn entry_fee_pct(&self) -> f64 {
+        self.fee_schedule().entry_fee_pct(self.execution.spread_capture_pct > 0.0)
+    }
+
+    /// Fees (% of one leg's notional) of opening and closing both legs
+    pub fn round_trip_fee_pct(&self) -> f64 {
+        self.fee_schedule().round_trip_fee_pct(self.execution.spread_capture_pct > 0.0)
+    }
+
     pub fn default_config() -> Self {
         Config {
+            environment: Environment::default(),
             filters: FilterConfig {
                 min_combined_volume_usd: 10_000_000.0,
                 max_intra_exchange_spread_pct: 0.15,
                 max_cross_exchange_spread_pct: 0.25,
                 min_net_apr_pct: 5.0,
+                symbol_blacklist: Vec::new(),
+                min_open_interest_multiple: 0.0,
+                max_mark_index_gap_pct: 0.0,
             },
             trading: TradingConfig {
                 max_position_size_usd: 1000.0,
                 hold_time_hours: 48,
+                leverage: default_leverage(),
+                pacifica_margin_mode: MarginMode::default(),
+                min_liquidation_distance_pct: default_min_liquidation_distance_pct(),
+                min_minutes_between_rotations: 0,
             },
             display: DisplayConfig {
                 max_opportunities_shown: 10,
@@ -130,17 +899,196 @@ impl Config {
             performance: PerformanceConfig {
                 fetch_timeout_seconds: 30,
                 rate_limit_delay_ms: 100,
+                max_concurrent_requests: default_max_concurrent_requests(),
+                stream_extended_orderbooks: default_stream_extended_orderbooks(),
+                stream_pacifica_orderbooks: default_stream_pacifica_orderbooks(),
+                orderbook_max_age_seconds: default_orderbook_max_age_seconds(),
+                max_stream_silence_seconds: default_max_stream_silence_seconds(),
             },
+            execution: ExecutionConfig::default(),
+            sizing: SizingConfig::default(),
+            capital: CapitalConfig::default(),
+            export: ExportConfig::default(),
+            retry: RetryPolicy::default(),
+            rotation: RotationConfig::default(),
+            scan_daemon: ScanDaemonConfig::default(),
+            risk: RiskConfig::default(),
+            error_budget: ErrorBudgetConfig::default(),
+            funding_intervals: FundingIntervalConfig::default(),
+            funding_caps: FundingCapConfig::default(),
+            watchdog: WatchdogConfig::default(),
+            extended_vaults: ExtendedVaultConfig::default(),
+            daily_summary: DailySummaryConfig::default(),
+            pacifica_order_timeout: OrderTimeoutConfig::default(),
+            control: ControlConfig::default(),
+            spread_history: SpreadHistoryConfig::default(),
+            audit: AuditConfig::default(),
+            scoring: ScoringConfig::default(),
+            funding_stream: FundingStreamConfig::default(),
+            contingency: ContingencyConfig::default(),
+            order_sweep: OrderSweepConfig::default(),
+            protective_orders: ProtectiveOrdersConfig::default(),
+            symbols: SymbolMap::default(),
+            stress: StressConfig::default(),
+            api_capture: ApiCaptureConfig::default(),
+            account_stream: AccountStreamConfig::default(),
+            fees: FeeConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            data_failover: DataFailoverConfig::default(),
+            entry_timing: EntryTimingConfig::default(),
+            cycle_budget: CycleBudgetConfig::default(),
         }
     }
 }
 
+/// APR points gained by rotating from `held` (at `held_net_apr`) into `best` now,
+/// after switching costs: `switching_fee_pct` (closing both legs and opening both legs)
+/// plus half the spread on each leg of both symbols, amortized over a full `hold_time_hours`.
+pub fn early_rotation_gain_apr(
+    held: &Opportunity,
+    held_net_apr: f64,
+    best: &Opportunity,
+    hold_time_hours: u64,
+    switching_fee_pct: f64,
+) -> f64 {
+    if hold_time_hours == 0 {
+        return f64::NEG_INFINITY;
+    }
+    let switching_cost_pct = switching_fee_pct + held.half_spread_cost_pct() + best.half_spread_cost_pct();
+    let cost_apr = apr_from_rate(switching_cost_pct, hold_time_hours as f64);
+    best.best_net_apr - held_net_apr - cost_apr
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct VolumeData {
     pub symbol: String,
     pub extended_volume: f64,
     pub pacifica_volume: f64,
     pub total_volume: f64,
+    /// Open interest (USD) where the venue reports it
+    pub extended_open_interest_usd: Option<f64>,
+    pub pacifica_open_interest_usd: Option<f64>,
+    /// Mark vs index price gap (%, see `mark_index_gap_pct`) where the venue reports both
+    pub extended_mark_index_gap_pct: Option<f64>,
+    pub pacifica_mark_index_gap_pct: Option<f64>,
+}
+
+/// Which venue holds the long leg of a delta neutral position. Serialized as the
+/// labels exports and state files have always carried; snake_case is accepted too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    #[serde(rename = "Long Extended / Short Pacifica", alias = "long_extended_short_pacifica")]
+    LongExtendedShortPacifica,
+    #[serde(rename = "Long Pacifica / Short Extended", alias = "long_pacifica_short_extended")]
+    LongPacificaShortExtended,
+}
+
+impl Direction {
+    pub fn from_long_on_extended(long_on_extended: bool) -> Self {
+        if long_on_extended {
+            Direction::LongExtendedShortPacifica
+        } else {
+            Direction::LongPacificaShortExtended
+        }
+    }
+
+    pub fn long_on_extended(self) -> bool {
+        self == Direction::LongExtendedShortPacifica
+    }
+
+    pub fn inverse(self) -> Self {
+        Self::from_long_on_extended(!self.long_on_extended())
+    }
+
+    /// Compact label for narrow tables
+    pub fn short_label(self) -> &'static str {
+        match self {
+            Direction::LongExtendedShortPacifica => "L.Ext/S.Pac",
+            Direction::LongPacificaShortExtended => "L.Pac/S.Ext",
+        }
+    }
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::LongExtendedShortPacifica => write!(f, "Long Extended / Short Pacifica"),
+            Direction::LongPacificaShortExtended => write!(f, "Long Pacifica / Short Extended"),
+        }
+    }
+}
+
+/// Visible order book of one venue as (price, quantity) levels, best first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookDepth {
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+impl BookDepth {
+    pub fn from_levels<'a>(
+        bids: impl Iterator<Item = (&'a str, &'a str)>,
+        asks: impl Iterator<Item = (&'a str, &'a str)>,
+    ) -> Self {
+        Self { bids: parse_levels(bids), asks: parse_levels(asks) }
+    }
+
+    /// Top level only, from a streamed quote
+    pub fn from_quote(quote: &BidAsk) -> Self {
+        Self::from_levels(
+            quote.best_bid.as_deref().zip(quote.bid_quantity.as_deref()).into_iter(),
+            quote.best_ask.as_deref().zip(quote.ask_quantity.as_deref()).into_iter(),
+        )
+    }
+
+    fn mid(&self) -> Option<f64> {
+        let (bid, ask) = (self.bids.first()?.0, self.asks.first()?.0);
+        Some((bid + ask) / 2.0).filter(|mid| *mid > 0.0)
+    }
+
+    /// Whether the top level on both sides can fill `notional_usd` on its own
+    pub fn top_covers(&self, notional_usd: f64) -> bool {
+        [self.bids.first(), self.asks.first()]
+            .into_iter()
+            .all(|level| level.is_some_and(|(price, qty)| price * qty >= notional_usd))
+    }
+
+    /// Cost (% of notional) of a market order for `notional_usd` against the mid: half
+    /// the spread plus the slippage of walking the book past the top level. `None` when
+    /// the visible book is too thin for the size.
+    pub fn fill_cost_pct(&self, is_buy: bool, notional_usd: f64) -> Option<f64> {
+        let mid = self.mid()?;
+        let levels = if is_buy { &self.asks } else { &self.bids };
+        estimate_fill_slippage_pct(levels, notional_usd / mid, mid)
+    }
+}
+
+/// Projected result of holding an opportunity (see `Opportunity::simulate`)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TradeSimulation {
+    /// Size of each leg
+    pub notional_usd: f64,
+    pub hold_hours: f64,
+    /// Taker fees on both legs, open and close
+    pub fees_usd: f64,
+    /// Half spreads and book slippage on both legs, open and close
+    pub slippage_usd: f64,
+    /// Net funding over the hold at the current net APR
+    pub funding_usd: f64,
+    /// Hours of funding that pay back fees and slippage; `None` when funding does not pay
+    pub break_even_hours: Option<f64>,
+}
+
+impl TradeSimulation {
+    /// Funding less fees and slippage over the hold
+    pub fn net_usd(&self) -> f64 {
+        self.funding_usd - self.fees_usd - self.slippage_usd
+    }
+
+    /// Whether funding pays back the costs within the hold
+    pub fn breaks_even(&self) -> bool {
+        self.break_even_hours.is_some_and(|hours| hours <= self.hold_hours)
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -154,193 +1102,211 @@ pub struct Opportunity {
     pub total_volume_24h: f64,
     pub extended_volume_24h: f64,
     pub pacifica_volume_24h: f64,
-    pub best_direction: String,
+    pub best_direction: Direction,
     pub best_net_apr: f64,
+    pub extended_open_interest_usd: Option<f64>,
+    pub pacifica_open_interest_usd: Option<f64>,
+    /// Mark vs index price gap (%) on each venue, where reported
+    pub extended_mark_index_gap_pct: Option<f64>,
+    pub pacifica_mark_index_gap_pct: Option<f64>,
+    /// An advertised funding rate exceeded its venue's cap and was clamped for the APRs
+    pub funding_capped: bool,
+    /// Book on each venue when scanned, for entry cost estimates (the top level only
+    /// when a streamed quote alone covered the scan size)
+    #[serde(skip)]
+    pub extended_depth: Option<BookDepth>,
+    #[serde(skip)]
+    pub pacifica_depth: Option<BookDepth>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct FilterStats {
     pub total_common_symbols: usize,
     pub filtered_by_volume: usize,
     pub filtered_by_spread: usize,
     pub filtered_by_apr: usize,
+    pub filtered_by_open_interest: usize,
+    #[serde(default)]
+    pub filtered_by_mark_index_gap: usize,
     pub passed_filters: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ScanResult {
     pub opportunities: Vec<Opportunity>,
     pub all_candidates: Vec<OpportunityCandidate>,
     pub stats: FilterStats,
+    /// Unix timestamp (seconds) when the scan completed
+    pub scanned_at: u64,
+    /// Where each venue's books came from
+    pub data_availability: DataAvailability,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct OpportunityCandidate {
     pub opportunity: Opportunity,
     pub filter_result: FilterResult,
+    /// Cross spread the filter judged when it came from the spread history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filtered_cross_spread_pct: Option<f64>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum FilterResult {
     Passed,
     FailedVolume,
     FailedIntraSpread,
     FailedCrossSpread,
     FailedApr,
+    FailedOpenInterest,
+    FailedMarkIndexGap,
 }
 
 impl ScanResult {
-    /// Display comprehensive scan summary table
-    pub fn display_summary(&self, config: &FilterConfig) {
-        let mut table = Table::new();
-        table.set_format(*format::consts::FORMAT_BOX_CHARS);
-
-        // Title
-        table.set_titles(Row::new(vec![
-            Cell::new("OPPORTUNITY SCAN SUMMARY")
-                .style_spec("cb")
-                .with_hspan(2)
-        ]));
-
-        // Filter Stats
-        table.add_row(Row::new(vec![Cell::new("Markets Scanned"), Cell::new(&self.stats.total_common_symbols.to_string())]));
-        table.add_row(Row::new(vec![Cell::new("Passed All Filters"), Cell::new(&self.stats.passed_filters.to_string()).style_spec("Fg")])); // Green
-        table.add_row(Row::new(vec![Cell::new("Filtered (Volume)"), Cell::new(&self.stats.filtered_by_volume.to_string())]));
-        table.add_row(Row::new(vec![Cell::new("Filtered (Spread)"), Cell::new(&self.stats.filtered_by_spread.to_string())]));
-        table.add_row(Row::new(vec![Cell::new("Filtered (APR)"), Cell::new(&self.stats.filtered_by_apr.to_string())]));
-
-        // Filter Criteria
-        table.add_row(Row::new(vec![
-            Cell::new("FILTER CRITERIA")
-                .style_spec("c")
-                .with_hspan(2)
-        ]));
-        table.add_row(Row::new(vec![Cell::new("Min Volume"), Cell::new(&format_volume(config.min_combined_volume_usd))]));
-        table.add_row(Row::new(vec![Cell::new("Max Intra Spread"), Cell::new(&format!("{}%", config.max_intra_exchange_spread_pct))]));
-        table.add_row(Row::new(vec![Cell::new("Max Cross Spread"), Cell::new(&format!("{}%", config.max_cross_exchange_spread_pct))]));
-        table.add_row(Row::new(vec![Cell::new("Min Net APR"), Cell::new(&format!("{}%", config.min_net_apr_pct))]));
-
-        table.printstd();
-        println!();
-
-        if !self.opportunities.is_empty() {
-            let mut opp_table = Table::new();
-            opp_table.set_format(*format::consts::FORMAT_BOX_CHARS);
-            
-            opp_table.set_titles(Row::new(vec![
-                Cell::new("OPPORTUNITIES (PASSED FILTERS)").style_spec("cb").with_hspan(7)
-            ]));
-            
-            opp_table.add_row(Row::new(vec![
-                Cell::new("Sym").style_spec("b"),
-                Cell::new("Volume").style_spec("b"),
-                Cell::new("Net APR").style_spec("b"),
-                Cell::new("Strategy").style_spec("b"),
-                Cell::new("Ext FR").style_spec("b"),
-                Cell::new("Pac FR").style_spec("b"),
-                Cell::new("Spreads").style_spec("b"),
-            ]));
-
-            for opp in &self.opportunities {
-                let sym = truncate(&opp.symbol, 4);
-                let vol = format_volume(opp.total_volume_24h);
-                let apr_formatted = format!("{:.1}%", opp.best_net_apr);
-                
-                // Color APR
-                let apr_style = if opp.best_net_apr >= 20.0 { "Fg" } else { "" }; // Green if > 20%
-
-                let strategy = if opp.best_direction.contains("Long Extended") {
-                    "L.Ext/S.Pac"
-                } else {
-                    "L.Pac/S.Ext"
-                };
+    /// Serialize the full scan (opportunities, candidates, stats) as pretty JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
 
-                let ext_fr = format!("{:.1}%", opp.extended_funding_rate_apr);
-                let pac_fr = format!("{:.1}%", opp.pacifica_funding_rate_apr);
-                let spreads = format!("{:.2}/{:.2}/{:.2}",
-                    opp.extended_spread_pct,
-                    opp.pacifica_spread_pct,
-                    opp.cross_spread_pct
-                );
-
-                opp_table.add_row(Row::new(vec![
-                    Cell::new(&sym),
-                    Cell::new(&vol),
-                    Cell::new(&apr_formatted).style_spec(apr_style),
-                    Cell::new(strategy),
-                    Cell::new(&ext_fr),
-                    Cell::new(&pac_fr),
-                    Cell::new(&spreads),
-                ]));
-            }
-            opp_table.printstd();
-            println!();
-        }
-
-        if self.stats.filtered_by_volume + self.stats.filtered_by_spread + self.stats.filtered_by_apr > 0 {
-            let mut filtered_table = Table::new();
-            filtered_table.set_format(*format::consts::FORMAT_BOX_CHARS);
-
-            filtered_table.set_titles(Row::new(vec![
-                Cell::new("FILTERED OUT (TOP 10 BY VOLUME)").style_spec("cb").with_hspan(5)
-            ]));
-
-            filtered_table.add_row(Row::new(vec![
-                Cell::new("Sym").style_spec("b"),
-                Cell::new("Volume").style_spec("b"),
-                Cell::new("Net APR").style_spec("b"),
-                Cell::new("Reason").style_spec("b"),
-                Cell::new("Detail").style_spec("b"),
-            ]));
-
-            let mut filtered: Vec<_> = self.all_candidates.iter()
-                .filter(|c| c.filter_result != FilterResult::Passed)
-                .collect();
-            filtered.sort_by(|a, b|
-                b.opportunity.total_volume_24h
-                    .partial_cmp(&a.opportunity.total_volume_24h)
-                    .unwrap()
-            );
-
-            for candidate in filtered.iter().take(10) {
-                let opp = &candidate.opportunity;
-                let sym = truncate(&opp.symbol, 4);
-                let vol = format_volume(opp.total_volume_24h);
-                let apr = format!("{:.1}%", opp.best_net_apr);
-
-                let (reason, detail) = match candidate.filter_result {
-                    FilterResult::FailedVolume => {
-                        ("Volume too low", format_volume(opp.total_volume_24h))
-                    },
-                    FilterResult::FailedIntraSpread => {
-                        ("Spread too wide", format!("E:{:.2}% P:{:.2}%",
-                            opp.extended_spread_pct, opp.pacifica_spread_pct))
-                    },
-                    FilterResult::FailedCrossSpread => {
-                        ("Cross spread", format!("{:.2}%", opp.cross_spread_pct))
-                    },
-                    FilterResult::FailedApr => {
-                        ("APR too low", format!("E:{:.1}% P:{:.1}%",
-                            opp.extended_funding_rate_apr, opp.pacifica_funding_rate_apr))
-                    },
-                    FilterResult::Passed => continue,
-                };
+    /// One CSV row per evaluated candidate, including why it was filtered
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "scanned_at,symbol,filter_result,best_direction,best_net_apr,extended_funding_rate_apr,\
+pacifica_funding_rate_apr,extended_spread_pct,pacifica_spread_pct,cross_spread_pct,\
+total_volume_24h,extended_volume_24h,pacifica_volume_24h,extended_mark_index_gap_pct,pacifica_mark_index_gap_pct\n",
+        );
+        for candidate in &self.all_candidates {
+            let opp = &candidate.opportunity;
+            out.push_str(&format!(
+                "{},{},{:?},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                self.scanned_at,
+                csv_field(&opp.symbol),
+                candidate.filter_result,
+                csv_field(&opp.best_direction.to_string()),
+                opp.best_net_apr,
+                opp.extended_funding_rate_apr,
+                opp.pacifica_funding_rate_apr,
+                opp.extended_spread_pct,
+                opp.pacifica_spread_pct,
+                opp.cross_spread_pct,
+                opp.total_volume_24h,
+                opp.extended_volume_24h,
+                opp.pacifica_volume_24h,
+                opp.extended_mark_index_gap_pct.map(|g| g.to_string()).unwrap_or_default(),
+                opp.pacifica_mark_index_gap_pct.map(|g| g.to_string()).unwrap_or_default(),
+            ));
+        }
+        out
+    }
 
-                filtered_table.add_row(Row::new(vec![
-                    Cell::new(&sym),
-                    Cell::new(&vol),
-                    Cell::new(&apr),
-                    Cell::new(reason).style_spec("Fr"), // Red
-                    Cell::new(&detail),
-                ]));
-            }
-            filtered_table.printstd();
-            println!();
+    /// Write the scan to timestamped file(s) under `config.directory`
+    pub fn export(&self, config: &ExportConfig) -> std::io::Result<Vec<std::path::PathBuf>> {
+        fs::create_dir_all(&config.directory)?;
+
+        let stamp = chrono::DateTime::from_timestamp(self.scanned_at as i64, 0)
+            .map(|dt| dt.format("%Y%m%d_%H%M%S").to_string())
+            .unwrap_or_else(|| self.scanned_at.to_string());
+        let base = std::path::Path::new(&config.directory).join(format!("scan_{}", stamp));
+
+        let mut written = Vec::new();
+        if matches!(config.format, ExportFormat::Json | ExportFormat::Both) {
+            let path = base.with_extension("json");
+            let json = self.to_json().map_err(std::io::Error::other)?;
+            fs::write(&path, json)?;
+            written.push(path);
+        }
+        if matches!(config.format, ExportFormat::Csv | ExportFormat::Both) {
+            let path = base.with_extension("csv");
+            fs::write(&path, self.to_csv())?;
+            written.push(path);
         }
+        Ok(written)
+    }
+
+    /// Display comprehensive scan summary table
+    pub fn display_summary(&self, config: &Config) {
+        crate::report::print_pretty(&crate::report::ScanSummaryView::new(self, config));
     }
 }
 
 impl Opportunity {
+    /// How the open legs are submitted under `order` for `notional_usd` per leg.
+    /// `LessLiquidFirst` compares the cost of walking each scanned book (a book too thin
+    /// for the size is the less liquid one), and the 24h volumes when neither book tells.
+    pub fn leg_sequence(&self, order: LegOrder, notional_usd: f64) -> LegSequence {
+        match order {
+            LegOrder::ExtendedFirst => LegSequence::ExtendedFirst,
+            LegOrder::PacificaFirst => LegSequence::PacificaFirst,
+            LegOrder::Concurrent => LegSequence::Concurrent,
+            LegOrder::LessLiquidFirst => {
+                let long_on_extended = self.best_direction == Direction::LongExtendedShortPacifica;
+                let cost = |depth: &Option<BookDepth>, is_buy: bool| {
+                    depth.as_ref().map(|d| d.fill_cost_pct(is_buy, notional_usd).unwrap_or(f64::INFINITY))
+                };
+                let extended = cost(&self.extended_depth, long_on_extended);
+                let pacifica = cost(&self.pacifica_depth, !long_on_extended);
+                let pacifica_thinner = match (extended, pacifica) {
+                    (Some(e), Some(p)) if e != p => p > e,
+                    _ => self.pacifica_volume_24h < self.extended_volume_24h,
+                };
+                if pacifica_thinner { LegSequence::PacificaFirst } else { LegSequence::ExtendedFirst }
+            }
+        }
+    }
+
+    /// Net APR (%) of this symbol in a given direction, whichever is best right now
+    pub fn net_apr_for(&self, direction: Direction) -> f64 {
+        match direction {
+            Direction::LongExtendedShortPacifica => self.pacifica_funding_rate_apr - self.extended_funding_rate_apr,
+            Direction::LongPacificaShortExtended => self.extended_funding_rate_apr - self.pacifica_funding_rate_apr,
+        }
+    }
+
+    /// Cost (% of notional) of crossing half the spread on both legs once
+    pub fn half_spread_cost_pct(&self) -> f64 {
+        (self.extended_spread_pct + self.pacifica_spread_pct) / 2.0
+    }
+
+    /// Estimated cost (bps of `notional_usd`) of opening both legs of the best direction
+    /// at market: half the spread on each venue, slippage through the scanned book depth,
+    /// and `entry_fee_pct` over both legs (`Config::entry_fee_pct`). `None` when a book is
+    /// unknown or too thin for the size.
+    pub fn estimated_entry_cost_bps(&self, notional_usd: f64, entry_fee_pct: f64) -> Option<f64> {
+        let long_on_extended = self.best_direction.long_on_extended();
+        let extended_pct = self.extended_depth.as_ref()?.fill_cost_pct(long_on_extended, notional_usd)?;
+        let pacifica_pct = self.pacifica_depth.as_ref()?.fill_cost_pct(!long_on_extended, notional_usd)?;
+        Some((extended_pct + pacifica_pct + entry_fee_pct) * 100.0)
+    }
+
+    /// What-if projection of holding this opportunity at `notional_usd` per leg for
+    /// `trading.hold_time_hours`: fees (`Config::round_trip_fee_pct`) and slippage on
+    /// both legs for the open and the close, and funding at the current net APR. The
+    /// close is assumed to cost what the open does; slippage is walked through the scanned
+    /// book, or half the spread where a book is unknown or too thin.
+    pub fn simulate(&self, notional_usd: f64, config: &Config) -> TradeSimulation {
+        let long_on_extended = self.best_direction.long_on_extended();
+        let fill_cost_pct = |depth: Option<&BookDepth>, is_buy: bool, spread_pct: f64| {
+            depth.and_then(|d| d.fill_cost_pct(is_buy, notional_usd)).unwrap_or(spread_pct / 2.0)
+        };
+        let entry_slippage_pct = fill_cost_pct(self.extended_depth.as_ref(), long_on_extended, self.extended_spread_pct)
+            + fill_cost_pct(self.pacifica_depth.as_ref(), !long_on_extended, self.pacifica_spread_pct);
+
+        let hold_hours = config.trading.hold_time_hours as f64;
+        let fees_usd = config.round_trip_fee_pct() / 100.0 * notional_usd;
+        let slippage_usd = 2.0 * entry_slippage_pct / 100.0 * notional_usd;
+        let hourly_funding_usd = self.best_net_apr / 100.0 * notional_usd / HOURS_PER_YEAR;
+        let break_even_hours = (hourly_funding_usd > 0.0).then(|| (fees_usd + slippage_usd) / hourly_funding_usd);
+
+        TradeSimulation {
+            notional_usd,
+            hold_hours,
+            fees_usd,
+            slippage_usd,
+            funding_usd: hourly_funding_usd * hold_hours,
+            break_even_hours,
+        }
+    }
+
     pub fn passes_filters(&self, config: &FilterConfig) -> bool {
         self.extended_spread_pct <= config.max_intra_exchange_spread_pct
             && self.pacifica_spread_pct <= config.max_intra_exchange_spread_pct
@@ -350,6 +1316,12 @@ impl Opportunity {
     }
 
     pub fn check_filters(&self, config: &FilterConfig) -> FilterResult {
+        self.check_filters_with_cross_spread(config, self.cross_spread_pct)
+    }
+
+    /// `check_filters` judging the cross spread by `cross_spread_pct` (e.g. a percentile
+    /// of the spread history) instead of this snapshot's
+    pub fn check_filters_with_cross_spread(&self, config: &FilterConfig, cross_spread_pct: f64) -> FilterResult {
         if self.total_volume_24h < config.min_combined_volume_usd {
             return FilterResult::FailedVolume;
         }
@@ -357,7 +1329,7 @@ impl Opportunity {
             || self.pacifica_spread_pct > config.max_intra_exchange_spread_pct {
             return FilterResult::FailedIntraSpread;
         }
-        if self.cross_spread_pct > config.max_cross_exchange_spread_pct {
+        if cross_spread_pct > config.max_cross_exchange_spread_pct {
             return FilterResult::FailedCrossSpread;
         }
         if self.best_net_apr < config.min_net_apr_pct {
@@ -366,6 +1338,40 @@ impl Opportunity {
         FilterResult::Passed
     }
 
+    /// Whether every reported open interest is at least `min_usd` (venues that do not
+    /// report open interest pass)
+    pub fn has_open_interest(&self, min_usd: f64) -> bool {
+        [self.extended_open_interest_usd, self.pacifica_open_interest_usd]
+            .into_iter()
+            .flatten()
+            .all(|oi| oi >= min_usd)
+    }
+
+    /// Largest mark vs index gap (absolute %) over the venues that report one
+    pub fn max_mark_index_gap_pct(&self) -> Option<f64> {
+        [self.extended_mark_index_gap_pct, self.pacifica_mark_index_gap_pct]
+            .into_iter()
+            .flatten()
+            .map(f64::abs)
+            .reduce(f64::max)
+    }
+
+    /// Copy with spreads recomputed from current top of book on both venues, or `None`
+    /// when either book is missing a side
+    pub fn with_live_spreads(&self, extended: &BidAsk, pacifica: &BidAsk, multipliers: ContractMultipliers) -> Option<Opportunity> {
+        let (ext_mid, ext_spread) = mid_and_spread_pct(extended)?;
+        let (pac_mid, pac_spread) = mid_and_spread_pct(pacifica)?;
+        if ext_mid <= 0.0 || pac_mid <= 0.0 {
+            return None;
+        }
+        Some(Opportunity {
+            extended_spread_pct: ext_spread,
+            pacifica_spread_pct: pac_spread,
+            cross_spread_pct: cross_spread_pct(ext_mid, pac_mid, multipliers),
+            ..self.clone()
+        })
+    }
+
     pub fn quality_rating(&self) -> &str {
         let net_apr = self.best_net_apr;
         if net_apr >= 100.0 {
@@ -393,6 +1399,14 @@ pub fn format_volume(v: f64) -> String {
     }
 }
 
+fn csv_field(s: &str) -> String {
+    if s.contains(',') || s.contains('"') || s.contains('\n') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
 pub fn truncate(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
@@ -405,6 +1419,19 @@ pub struct OpportunityFinder {
     extended_client: RestClient,
     pacifica_creds: PacificaCredentials,
     config: Config,
+    /// Streamed Extended top of book, when enabled and created inside a Tokio runtime
+    extended_books: Option<TopOfBookCache>,
+    /// Streamed Pacifica top of book and full-depth books, started on the first scan
+    /// for its symbols
+    pacifica_books: OnceLock<(TopOfBookCache, DepthCache)>,
+    /// Cross spread samples per symbol (`spread_history`)
+    spread_history: Mutex<SpreadHistory>,
+    /// Ranks scanned candidates (`scoring`)
+    scorer: Box<dyn Scorer>,
+    /// Book sources of the last scan (`data_failover`)
+    data_availability: Mutex<DataAvailability>,
+    /// Client of `data_failover.extended_secondary_url`, without the API key: books are public
+    extended_secondary: Option<Arc<RestClient>>,
 }
 
 impl OpportunityFinder {
@@ -412,60 +1439,170 @@ impl OpportunityFinder {
         extended_api_key: Option<String>,
         pacifica_creds: PacificaCredentials,
         config: Config,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let extended_client = RestClient::new_mainnet(extended_api_key)?;
+    ) -> Result<Self, BotError> {
+        let extended_client = config.environment.extended_client(extended_api_key.clone())?;
+        let extended_secondary = config
+            .data_failover
+            .extended_secondary_url
+            .as_deref()
+            .filter(|_| config.data_failover.enabled)
+            .map(|url| RestClient::new(url, None).map(Arc::new))
+            .transpose()?;
+
+        let extended_books = if config.performance.stream_extended_orderbooks
+            && tokio::runtime::Handle::try_current().is_ok()
+        {
+            let subscriber = MultiMarketSubscriber::new(config.environment.extended_ws_client(extended_api_key));
+            Some(subscriber.stream_all_into_cache())
+        } else {
+            None
+        };
+
+        let spread_history = if config.spread_history.enabled && config.spread_history.persist {
+            let path = resolve_spread_history_path();
+            SpreadHistory::load_from_file(&path).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable spread history {}: {}", path, e);
+                SpreadHistory::default()
+            })
+        } else {
+            SpreadHistory::default()
+        };
 
         Ok(Self {
             extended_client,
             pacifica_creds,
+            extended_books,
+            pacifica_books: OnceLock::new(),
+            spread_history: Mutex::new(spread_history),
+            scorer: scorer_from_config(&config.scoring),
+            data_availability: Mutex::new(DataAvailability::default()),
+            extended_secondary,
             config,
         })
     }
 
-    /// Find common symbols between Extended and Pacifica
-    pub async fn find_common_symbols(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    /// Rank scans with `scorer` instead of the one `scoring.method` selects
+    pub fn set_scorer(&mut self, scorer: Box<dyn Scorer>) {
+        self.scorer = scorer;
+    }
+
+    /// Position size the open-interest filter is measured against
+    pub(crate) fn set_max_position_size_usd(&mut self, max_position_size_usd: f64) {
+        self.config.trading.max_position_size_usd = max_position_size_usd;
+    }
+
+    /// Find common symbols between Extended and Pacifica, excluding blacklisted and restricted markets
+    pub async fn find_common_symbols(&self) -> Result<Vec<String>, BotError> {
         let extended_markets = self.extended_client.get_all_markets().await?;
+        let symbols = &self.config.symbols;
         let extended_symbols: HashSet<String> = extended_markets
             .iter()
-            .filter_map(|m| m.name.strip_suffix("-USD").map(|s| s.to_string()))
+            .filter_map(|m| symbols.symbol(Venue::Extended, &m.name))
             .collect();
 
-        let mut pacifica_client = PacificaTrading::new(self.pacifica_creds.clone());
+        let mut pacifica_client = self.config.environment.pacifica_client(self.pacifica_creds.clone());
         let pacifica_markets = pacifica_client.get_market_info().await?;
-        let pacifica_symbols: HashSet<String> = pacifica_markets.keys().cloned().collect();
-
-        let common: Vec<String> = extended_symbols
-            .intersection(&pacifica_symbols)
-            .cloned()
+        let pacifica_symbols: HashSet<String> = pacifica_markets
+            .keys()
+            .filter_map(|market| symbols.symbol(Venue::Pacifica, market))
             .collect();
 
+        let mut common = Vec::new();
+        for symbol in extended_symbols.intersection(&pacifica_symbols) {
+            match restriction_reason(symbol, &extended_markets, pacifica_markets, &self.config.filters.symbol_blacklist, symbols) {
+                Some(reason) => debug!("Excluding {} from scan: {}", symbol, reason),
+                None => common.push(symbol.clone()),
+            }
+        }
+
         Ok(common)
     }
 
-    /// Fetch 24h volumes for all symbols in parallel
-    pub async fn fetch_volumes(&self, symbols: &[String], extended_api_key: Option<String>) -> Result<Vec<VolumeData>, Box<dyn std::error::Error>> {
+    /// Why `symbol` must not be held right now (blacklisted, delisted, reduce-only...), if anything
+    pub async fn symbol_restriction(&self, symbol: &str) -> Result<Option<String>, BotError> {
+        let extended_markets = self.extended_client.get_all_markets().await?;
+        let mut pacifica_client = self.config.environment.pacifica_client(self.pacifica_creds.clone());
+        let pacifica_markets = pacifica_client.get_market_info().await?;
+
+        Ok(restriction_reason(symbol, &extended_markets, pacifica_markets, &self.config.filters.symbol_blacklist, &self.config.symbols))
+    }
+
+    /// Evaluate one symbol outside a scan (no volume pre-filter), for opens chosen by an
+    /// external signal. Fails when the symbol is restricted or not listed on both venues.
+    pub async fn evaluate_symbol(&self, symbol: &str, extended_api_key: Option<String>) -> Result<Opportunity, BotError> {
+        if let Some(reason) = self.symbol_restriction(symbol).await? {
+            return Err(BotError::Config(format!("{} cannot be opened: {}", symbol, reason)));
+        }
+        let symbols = vec![symbol.to_string()];
+        let volumes = self.fetch_volumes(&symbols, extended_api_key.clone()).await?;
+        self.find_opportunities(&symbols, &volumes, extended_api_key)
+            .await?
+            .into_iter()
+            .next()
+            .map(|candidate| candidate.opportunity)
+            .ok_or_else(|| BotError::execution(format!("No market data for {}", symbol), true))
+    }
+
+    /// Fetch 24h volumes and open interest for all symbols in parallel
+    /// (bounded by `max_concurrent_requests`, each symbol limited to `fetch_timeout_seconds`)
+    pub async fn fetch_volumes(&self, symbols: &[String], extended_api_key: Option<String>) -> Result<Vec<VolumeData>, BotError> {
         let mut volume_tasks = JoinSet::new();
+        let semaphore = Arc::new(Semaphore::new(self.config.performance.max_concurrent_requests));
+        let task_timeout = Duration::from_secs(self.config.performance.fetch_timeout_seconds);
+        let delay = Duration::from_millis(self.config.performance.rate_limit_delay_ms);
+        let extended_client = Arc::new(RestClient::new(self.extended_client.base_url(), extended_api_key)?);
+        let pacifica_client = Arc::new(self.config.environment.pacifica_client(self.pacifica_creds.clone()));
+        let pacifica_prices = match fetch_pacifica_prices(&pacifica_client, task_timeout).await {
+            Ok(prices) => prices,
+            Err(e) => {
+                warn!("Failed to fetch Pacifica prices (not filtering on open interest or mark/index gap this scan): {}", e);
+                HashMap::new()
+            }
+        };
 
         for symbol in symbols {
             let symbol = symbol.clone();
-            let api_key = extended_api_key.clone();
+            let semaphore = semaphore.clone();
+            let extended_client = extended_client.clone();
+            let pacifica_client = pacifica_client.clone();
+            let (extended_market, pacifica_market) = (self.config.symbols.extended_market(&symbol), self.config.symbols.pacifica_market(&symbol));
+            let pacifica_price = pacifica_prices.get(&pacifica_market).copied().unwrap_or_default();
             volume_tasks.spawn(async move {
-                let extended_vol = fetch_extended_volume_with_key(&format!("{}-USD", symbol), api_key)
-                    .await
-                    .unwrap_or(0.0);
-                let pacifica_vol = fetch_pacifica_volume(&symbol).await.unwrap_or(0.0);
-                VolumeData {
-                    symbol,
-                    extended_volume: extended_vol,
-                    pacifica_volume: pacifica_vol,
-                    total_volume: extended_vol + pacifica_vol,
-                }
+                let _permit = semaphore.acquire_owned().await.ok()?;
+                let fetch = async {
+                    let extended_stats = extended_client.get_market_stats(&extended_market).await.ok();
+                    let pacifica_vol = fetch_pacifica_volume(&pacifica_client, &pacifica_market).await.unwrap_or(0.0);
+                    (extended_stats, pacifica_vol)
+                };
+                let result = match timeout(task_timeout, fetch).await {
+                    Ok((extended_stats, pacifica_vol)) => {
+                        let extended_vol = extended_stats.as_ref().map_or(0.0, |s| s.daily_volume_f64());
+                        Some(VolumeData {
+                            symbol,
+                            extended_volume: extended_vol,
+                            pacifica_volume: pacifica_vol,
+                            total_volume: extended_vol + pacifica_vol,
+                            extended_open_interest_usd: extended_stats.as_ref().and_then(|s| s.open_interest_f64()),
+                            pacifica_open_interest_usd: pacifica_price.open_interest_usd,
+                            extended_mark_index_gap_pct: extended_stats
+                                .as_ref()
+                                .and_then(|s| mark_index_gap_pct(s.mark_price_f64()?, s.index_price_f64()?)),
+                            pacifica_mark_index_gap_pct: pacifica_price.mark_index_gap_pct,
+                        })
+                    }
+                    Err(_) => {
+                        warn!("Volume fetch for {} timed out after {:?}", symbol, task_timeout);
+                        None
+                    }
+                };
+                sleep(delay).await;
+                result
             });
         }
 
         let mut results = Vec::new();
         while let Some(result) = volume_tasks.join_next().await {
-            if let Ok(vol_data) = result {
+            if let Ok(Some(vol_data)) = result {
                 results.push(vol_data);
             }
         }
@@ -476,59 +1613,233 @@ impl OpportunityFinder {
         Ok(results)
     }
 
+    fn max_quote_age(&self) -> Duration {
+        Duration::from_secs(self.config.performance.orderbook_max_age_seconds)
+    }
+
+    /// Streamed Extended best bid/ask for `symbol` (e.g. "BTC"), if fresh
+    pub fn extended_quote(&self, symbol: &str) -> Option<BidAsk> {
+        self.extended_books
+            .as_ref()
+            .and_then(|books| books.fresh(&self.config.symbols.extended_market(symbol), self.max_quote_age()))
+    }
+
+    /// Streamed Pacifica best bid/ask for `symbol`, if fresh
+    pub fn pacifica_quote(&self, symbol: &str) -> Option<BidAsk> {
+        self.pacifica_books
+            .get()
+            .and_then(|(books, _)| books.fresh(&self.config.symbols.pacifica_market(symbol), self.max_quote_age()))
+    }
+
+    /// Streamed Pacifica book for `symbol` with every level, if fresh
+    pub fn pacifica_depth(&self, symbol: &str) -> Option<OrderbookSnapshot> {
+        self.pacifica_books
+            .get()
+            .and_then(|(_, depth)| depth.fresh(&self.config.symbols.pacifica_market(symbol), self.max_quote_age()))
+    }
+
+    /// Started orderbook streams that delivered nothing for `max_stream_silence_seconds`,
+    /// with how long they have been silent
+    pub fn stale_streams(&self) -> Vec<(Venue, Duration)> {
+        let max_silence = self.config.performance.max_stream_silence_seconds;
+        if max_silence == 0 {
+            return Vec::new();
+        }
+        [(Venue::Extended, self.extended_books.as_ref()), (Venue::Pacifica, self.pacifica_books.get().map(|(books, _)| books))]
+            .into_iter()
+            .filter_map(|(venue, books)| Some((venue, books?.silent_for())))
+            .filter(|(_, silent)| *silent > Duration::from_secs(max_silence))
+            .collect()
+    }
+
+    /// What a scan may fall back to for `symbol` when a venue's REST book fails: the
+    /// streamed quote within `data_failover.max_cached_quote_age_seconds` where no fresh
+    /// one was found, and the Extended secondary endpoint
+    fn book_fallbacks(&self, symbol: &str, extended_stale: bool, pacifica_stale: bool) -> BookFallbacks {
+        let failover = &self.config.data_failover;
+        if !failover.enabled {
+            return BookFallbacks::default();
+        }
+        let max_age = Duration::from_secs(failover.max_cached_quote_age_seconds);
+        let extended_market = self.config.symbols.extended_market(symbol);
+        let pacifica_market = self.config.symbols.pacifica_market(symbol);
+        BookFallbacks {
+            extended_cached: self.extended_books.as_ref().filter(|_| extended_stale).and_then(|books| books.fresh(&extended_market, max_age)),
+            pacifica_cached: self
+                .pacifica_books
+                .get()
+                .filter(|_| pacifica_stale)
+                .and_then(|(books, _)| books.fresh(&pacifica_market, max_age)),
+            extended_secondary: self.extended_secondary.clone(),
+        }
+    }
+
+    /// Book sources of both venues over the last scan
+    pub fn data_availability(&self) -> DataAvailability {
+        *self.data_availability.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Start streaming Pacifica books for `symbols` over one connection, once.
+    /// Symbols that first appear in later scans keep using REST.
+    fn start_pacifica_stream(&self, symbols: &[String]) {
+        if !self.config.performance.stream_pacifica_orderbooks
+            || symbols.is_empty()
+            || self.pacifica_books.get().is_some()
+            || tokio::runtime::Handle::try_current().is_err()
+        {
+            return;
+        }
+        let config = OrderbookConfig::for_symbols(symbols.iter().map(|s| self.config.symbols.pacifica_market(s)).collect());
+        match self.config.environment.pacifica_orderbook_client(config) {
+            Ok(client) => {
+                let _ = self.pacifica_books.set(client.stream_into_caches());
+            }
+            Err(e) => warn!("Pacifica orderbook stream not started (scans use REST): {}", e),
+        }
+    }
+
     /// Find opportunities for high-volume symbols
     pub async fn find_opportunities(
         &self,
         symbols: &[String],
         volumes: &[VolumeData],
         extended_api_key: Option<String>,
-    ) -> Result<Vec<OpportunityCandidate>, Box<dyn std::error::Error>> {
+    ) -> Result<Vec<OpportunityCandidate>, BotError> {
         let mut opp_tasks = Vec::new();
+        let semaphore = Arc::new(Semaphore::new(self.config.performance.max_concurrent_requests));
+        let task_timeout = Duration::from_secs(self.config.performance.fetch_timeout_seconds);
+        let delay = Duration::from_millis(self.config.performance.rate_limit_delay_ms);
+        let (mut extended_streamed, mut pacifica_streamed) = (0, 0);
+        let min_open_interest_usd = self.config.filters.min_open_interest_multiple * self.config.trading.max_position_size_usd;
+        let max_gap_pct = self.config.filters.max_mark_index_gap_pct;
+        let depth_notional_usd = self.config.trading.max_position_size_usd;
+        self.start_pacifica_stream(symbols);
 
         for symbol in symbols {
             let symbol = symbol.clone();
             let api_key = extended_api_key.clone();
+            let extended_quote = self.extended_quote(&symbol);
+            let pacifica_quote = self.pacifica_quote(&symbol);
+            let pacifica_streamed_book = self.pacifica_depth(&symbol);
+            let fallbacks = self.book_fallbacks(&symbol, extended_quote.is_none(), pacifica_quote.is_none());
+            extended_streamed += extended_quote.is_some() as usize;
+            pacifica_streamed += pacifica_quote.is_some() as usize;
             let vol_data = volumes
                 .iter()
                 .find(|v| v.symbol == symbol)
                 .unwrap()
                 .clone();
             let pacifica_creds = self.pacifica_creds.clone();
-            let config = self.config.filters.clone();
+            let environment = self.config.environment;
+            let funding_intervals = self.config.funding_intervals.clone();
+            let funding_caps = self.config.funding_caps.clone();
+            let markets = (self.config.symbols.extended_market(&symbol), self.config.symbols.pacifica_market(&symbol));
+            let multipliers = self.config.symbols.multipliers(&symbol);
+
+            let semaphore = semaphore.clone();
 
             let task = tokio::spawn(async move {
-                if let Ok(Some(opp)) = fetch_opportunity_data(symbol, api_key, pacifica_creds, vol_data).await {
-                    let filter_result = opp.check_filters(&config);
-                    Some(OpportunityCandidate {
-                        opportunity: opp,
-                        filter_result,
-                    })
-                } else {
-                    None
-                }
+                let mut sources = BookSources::default();
+                let Ok(_permit) = semaphore.acquire_owned().await else {
+                    return (None, sources);
+                };
+                let fetched = timeout(
+                    task_timeout,
+                    fetch_opportunity_data(
+                        symbol.clone(),
+                        markets,
+                        multipliers,
+                        environment,
+                        api_key,
+                        pacifica_creds,
+                        vol_data,
+                        extended_quote,
+                        pacifica_quote,
+                        pacifica_streamed_book,
+                        fallbacks,
+                        &mut sources,
+                        &funding_intervals,
+                        &funding_caps,
+                        depth_notional_usd,
+                    ),
+                ).await;
+                sleep(delay).await;
+
+                let opp = match fetched {
+                    Ok(Ok(opp)) => opp,
+                    Ok(Err(_)) => None,
+                    Err(_) => {
+                        warn!("Opportunity fetch for {} timed out after {:?}", symbol, task_timeout);
+                        None
+                    }
+                };
+                (opp, sources)
             });
             opp_tasks.push(task);
         }
 
-        let mut candidates = Vec::new();
+        debug!(
+            "Top of book from streams: Extended {}/{}, Pacifica {}/{} symbols",
+            extended_streamed,
+            symbols.len(),
+            pacifica_streamed,
+            symbols.len()
+        );
+
+        let mut opportunities = Vec::new();
+        let mut availability = DataAvailability::default();
         for task in opp_tasks {
-            if let Ok(Some(candidate)) = task.await {
-                candidates.push(candidate);
+            if let Ok((opp, sources)) = task.await {
+                availability.record(sources);
+                opportunities.extend(opp);
             }
         }
+        if availability.is_degraded() {
+            warn!("Scan book data degraded: {}", availability);
+        }
+        *self.data_availability.lock().unwrap_or_else(PoisonError::into_inner) = availability;
 
-        // Sort by net APR descending
-        candidates.sort_by(|a, b|
-            b.opportunity.best_net_apr
-                .partial_cmp(&a.opportunity.best_net_apr)
-                .unwrap()
-        );
+        let history_config = &self.config.spread_history;
+        let mut history = self.spread_history.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut candidates = Vec::new();
+        for opp in opportunities {
+            let filtered_cross_spread_pct = if history_config.enabled {
+                history.record_and_filter_value(&opp.symbol, opp.cross_spread_pct, history_config)
+            } else {
+                None
+            };
+            let cross_spread_pct = filtered_cross_spread_pct.unwrap_or(opp.cross_spread_pct);
+            let filter_result = match opp.check_filters_with_cross_spread(&self.config.filters, cross_spread_pct) {
+                FilterResult::Passed if !opp.has_open_interest(min_open_interest_usd) => FilterResult::FailedOpenInterest,
+                FilterResult::Passed
+                    if max_gap_pct > 0.0 && opp.max_mark_index_gap_pct().is_some_and(|gap| gap > max_gap_pct) =>
+                {
+                    FilterResult::FailedMarkIndexGap
+                }
+                result => result,
+            };
+            candidates.push(OpportunityCandidate {
+                opportunity: opp,
+                filter_result,
+                filtered_cross_spread_pct,
+            });
+        }
+        if history_config.enabled && history_config.persist {
+            let path = resolve_spread_history_path();
+            if let Err(e) = history.save_to_file(&path) {
+                warn!("Failed to save spread history to {}: {}", path, e);
+            }
+        }
+        drop(history);
+
+        rank(self.scorer.as_ref(), &mut candidates, &self.config);
+        dedup_by_symbol(&mut candidates);
 
         Ok(candidates)
     }
 
     /// Complete workflow: find common symbols, fetch volumes, filter, and find opportunities
-    pub async fn scan(&self, extended_api_key: Option<String>) -> Result<ScanResult, Box<dyn std::error::Error>> {
+    pub async fn scan(&self, extended_api_key: Option<String>) -> Result<ScanResult, BotError> {
         // Find common symbols
         let common_symbols = self.find_common_symbols().await?;
         let total_common = common_symbols.len();
@@ -564,6 +1875,8 @@ impl OpportunityFinder {
         // Count filter failures
         let mut filtered_by_spread = 0;
         let mut filtered_by_apr = 0;
+        let mut filtered_by_open_interest = 0;
+        let mut filtered_by_mark_index_gap = 0;
 
         for candidate in &all_candidates {
             match candidate.filter_result {
@@ -573,6 +1886,12 @@ impl OpportunityFinder {
                 FilterResult::FailedApr => {
                     filtered_by_apr += 1;
                 }
+                FilterResult::FailedOpenInterest => {
+                    filtered_by_open_interest += 1;
+                }
+                FilterResult::FailedMarkIndexGap => {
+                    filtered_by_mark_index_gap += 1;
+                }
                 _ => {}
             }
         }
@@ -582,186 +1901,323 @@ impl OpportunityFinder {
             filtered_by_volume,
             filtered_by_spread,
             filtered_by_apr,
+            filtered_by_open_interest,
+            filtered_by_mark_index_gap,
             passed_filters: opportunities.len(),
         };
 
-        Ok(ScanResult {
+        let result = ScanResult {
             opportunities,
             all_candidates,
             stats,
-        })
+            scanned_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs(),
+            data_availability: self.data_availability(),
+        };
+
+        if self.config.export.enabled {
+            match result.export(&self.config.export) {
+                Ok(paths) => {
+                    for path in paths {
+                        tracing::info!("Exported scan to {}", path.display());
+                    }
+                }
+                Err(e) => warn!("Failed to export scan to {}: {}", self.config.export.directory, e),
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Reason a base symbol is untradeable: blacklisted in config, not in an ACTIVE state on
+/// Extended (e.g. REDUCE_ONLY, DELISTED, DISABLED), or missing from Pacifica market info.
+pub fn restriction_reason(
+    symbol: &str,
+    extended_markets: &[MarketInfo],
+    pacifica_markets: &HashMap<String, PacificaMarketInfo>,
+    blacklist: &[String],
+    symbols: &SymbolMap,
+) -> Option<String> {
+    if blacklist.iter().any(|s| s.eq_ignore_ascii_case(symbol)) {
+        return Some("blacklisted in config".to_string());
+    }
+
+    let extended_market = symbols.extended_market(symbol);
+    match extended_markets.iter().find(|m| m.name == extended_market) {
+        None => return Some(format!("{} not listed on Extended", extended_market)),
+        Some(m) if !m.active || !m.status.eq_ignore_ascii_case("ACTIVE") => {
+            return Some(format!("Extended market {} is {}", extended_market, m.status));
+        }
+        Some(_) => {}
+    }
+
+    let pacifica_market = symbols.pacifica_market(symbol);
+    if !pacifica_markets.contains_key(&pacifica_market) {
+        return Some(format!("{} not listed on Pacifica", pacifica_market));
     }
+
+    None
 }
 
 // Helper functions (same as before)
-async fn fetch_extended_volume_with_key(
-    market: &str,
-    api_key: Option<String>,
-) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-    let url = format!(
-        "https://api.starknet.extended.exchange/api/v1/info/markets/{}/stats",
-        market
-    );
-
-    let client = reqwest::Client::new();
-    let mut request = client
-        .get(&url)
-        .header("User-Agent", "extended-connector/0.1.0");
-
-    if let Some(key) = api_key {
-        request = request.header("X-Api-Key", key);
-    }
-
-    let response = request.send().await?;
-
-    if response.status().is_success() {
-        #[derive(serde::Deserialize)]
-        struct StatsResponse {
-            data: Option<MarketStats>,
-        }
-        #[derive(serde::Deserialize)]
-        struct MarketStats {
-            #[serde(rename = "dailyVolume")]
-            daily_volume: String,
-        }
-
-        let stats: StatsResponse = response.json().await?;
-        Ok(stats
-            .data
-            .and_then(|d| d.daily_volume.parse::<f64>().ok())
-            .unwrap_or(0.0))
-    } else {
-        Ok(0.0)
+/// Gap between mark and index price, as % of the index (positive = mark above index)
+pub fn mark_index_gap_pct(mark: f64, index: f64) -> Option<f64> {
+    (mark > 0.0 && index > 0.0).then(|| (mark - index) / index * 100.0)
+}
+
+/// Open interest and mark/index gap of one Pacifica market
+#[derive(Debug, Clone, Copy, Default)]
+struct PacificaPriceData {
+    open_interest_usd: Option<f64>,
+    mark_index_gap_pct: Option<f64>,
+}
+
+/// Per-symbol Pacifica prices: open interest (USD, base open interest x mark price) and
+/// the mark vs oracle (index) price gap; fails after `timeout` like the per-symbol fetches
+async fn fetch_pacifica_prices(client: &PacificaTrading, timeout: Duration) -> Result<HashMap<String, PacificaPriceData>, BotError> {
+    let url = format!("{}/api/v1/info/prices", client.rest_url());
+    let request = client.http_client().get(&url).timeout(timeout).send();
+    let response = api_capture::observe_http("pacifica GET /api/v1/info/prices", request)
+        .await
+        .map_err(anyhow::Error::from)?;
+    if !response.status().is_success() {
+        return Err(BotError::classify("Pacifica", format!("prices request failed: HTTP {}", response.status())));
+    }
+
+    #[derive(serde::Deserialize)]
+    struct PricesResponse {
+        data: Option<Vec<Price>>,
     }
+
+    #[derive(serde::Deserialize)]
+    struct Price {
+        symbol: String,
+        open_interest: String,
+        mark: String,
+        #[serde(default)]
+        oracle: Option<String>,
+    }
+
+    let prices: PricesResponse = response.json().await.map_err(anyhow::Error::from)?;
+    Ok(prices
+        .data
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| {
+            let mark = p.mark.parse::<f64>().ok();
+            let open_interest = p.open_interest.parse::<f64>().ok();
+            let oracle = p.oracle.as_deref().and_then(|o| o.parse::<f64>().ok());
+            let data = PacificaPriceData {
+                open_interest_usd: open_interest.zip(mark).map(|(oi, mark)| oi * mark),
+                mark_index_gap_pct: mark.zip(oracle).and_then(|(mark, index)| mark_index_gap_pct(mark, index)),
+            };
+            (p.symbol, data)
+        })
+        .collect())
 }
 
 async fn fetch_pacifica_volume(
+    client: &PacificaTrading,
     symbol: &str,
-) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<f64, BotError> {
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)?
         .as_millis() as u64;
     let start = now - (24 * 60 * 60 * 1000);
 
-    let url = format!(
-        "https://api.pacifica.fi/api/v1/kline?symbol={}&interval=1d&start_time={}&end_time={}",
-        symbol, start, now
-    );
-
-    let response = reqwest::get(&url).await?;
-
-    if response.status().is_success() {
-        #[derive(serde::Deserialize)]
-        struct KlineResponse {
-            data: Option<Vec<Candle>>,
-        }
+    let candles = client.get_klines(symbol, "1d", start, now).await?;
+    Ok(candles.first().map_or(0.0, |c| c.volume_usd()))
+}
 
-        #[derive(serde::Deserialize)]
-        struct Candle {
-            #[serde(rename = "v")]
-            volume: String,
-            #[serde(rename = "c")]
-            close: String,
-        }
+/// Mid price and bid-ask spread (% of mid) of a quote with both sides
+pub(crate) fn mid_and_spread_pct(quote: &BidAsk) -> Option<(f64, f64)> {
+    let bid = quote.best_bid.as_ref()?.parse::<f64>().unwrap_or(0.0);
+    let ask = quote.best_ask.as_ref()?.parse::<f64>().unwrap_or(0.0);
+    let mid = (bid + ask) / 2.0;
+    let spread = if mid > 0.0 {
+        ((ask - bid) / mid) * 100.0
+    } else {
+        999.0
+    };
+    Some((mid, spread))
+}
 
-        let kline: KlineResponse = response.json().await?;
+/// Gap between the venues' mids (per contract), compared per canonical unit, in
+/// percent of the Extended mid
+fn cross_spread_pct(ext_mid: f64, pac_mid: f64, multipliers: ContractMultipliers) -> f64 {
+    let ext_mid = multipliers.base_price(Venue::Extended, ext_mid);
+    let pac_mid = multipliers.base_price(Venue::Pacifica, pac_mid);
+    ((pac_mid - ext_mid).abs() / ext_mid) * 100.0
+}
 
-        if let Some(candles) = kline.data {
-            if let Some(candle) = candles.first() {
-                let vol_base = candle.volume.parse::<f64>().unwrap_or(0.0);
-                let price = candle.close.parse::<f64>().unwrap_or(0.0);
-                return Ok(vol_base * price);
-            }
-        }
-    }
+/// Streamed quotes a scan may fall back to, and the Extended secondary endpoint
+#[derive(Clone, Default)]
+struct BookFallbacks {
+    extended_cached: Option<BidAsk>,
+    pacifica_cached: Option<BidAsk>,
+    extended_secondary: Option<Arc<RestClient>>,
+}
 
-    Ok(0.0)
+/// Extended book from the `data_failover` secondary endpoint, if one is set and answers
+async fn secondary_extended_orderbook(client: Option<&RestClient>, market: &str) -> Option<OrderBook> {
+    client?.get_orderbook(market).await.ok()
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn fetch_opportunity_data(
     symbol: String,
+    (extended_market, pacifica_market): (String, String),
+    multipliers: ContractMultipliers,
+    environment: Environment,
     extended_api_key: Option<String>,
     pacifica_creds: PacificaCredentials,
     vol_data: VolumeData,
-) -> Result<Option<Opportunity>, Box<dyn std::error::Error + Send + Sync>> {
-    let extended_market = format!("{}-USD", symbol);
-
-    let extended_client = RestClient::new_mainnet(extended_api_key)?;
-    let mut pacifica_client = PacificaTrading::new(pacifica_creds);
-
-    // Fetch orderbooks
-    let (_ext_bid, _ext_ask, ext_mid, ext_spread) =
-        match extended_client.get_orderbook(&extended_market).await {
+    extended_quote: Option<BidAsk>,
+    pacifica_quote: Option<BidAsk>,
+    pacifica_streamed_book: Option<OrderbookSnapshot>,
+    fallbacks: BookFallbacks,
+    sources: &mut BookSources,
+    funding_intervals: &FundingIntervalConfig,
+    funding_caps: &FundingCapConfig,
+    depth_notional_usd: f64,
+) -> Result<Option<Opportunity>, BotError> {
+
+    let extended_client = environment.extended_client(extended_api_key)?;
+    let mut pacifica_client = environment.pacifica_client(pacifica_creds);
+
+    // Top of book on both venues: streamed quote when fresh, REST otherwise. The REST
+    // book also gives the depth, fetched for streamed quotes whose top level cannot
+    // fill `depth_notional_usd` on its own (Pacifica's streamed book is used first).
+    let extended_book = |ob: &OrderBook| {
+        BookDepth::from_levels(
+            ob.bid.iter().map(|l| (l.price.as_str(), l.quantity.as_str())),
+            ob.ask.iter().map(|l| (l.price.as_str(), l.quantity.as_str())),
+        )
+    };
+    let pacifica_book = |ob: &OrderbookSnapshot| {
+        BookDepth::from_levels(
+            ob.bids.iter().map(|l| (l.price.as_str(), l.size.as_str())),
+            ob.asks.iter().map(|l| (l.price.as_str(), l.size.as_str())),
+        )
+    };
+    // When REST fails too, `data_failover` falls back to an older streamed quote, then
+    // (Extended only) to the secondary endpoint
+    let cached = |quote: Option<BidAsk>| quote.map(|q| (BookDepth::from_quote(&q), q));
+    let (extended_quote, extended_depth) = match extended_quote {
+        Some(quote) => {
+            sources.extended = QuoteSource::Stream;
+            let top = BookDepth::from_quote(&quote);
+            let depth = if top.top_covers(depth_notional_usd) {
+                Some(top)
+            } else {
+                extended_client.get_orderbook(&extended_market).await.ok().map(|ob| extended_book(&ob))
+            };
+            (quote, depth)
+        }
+        None => match extended_client.get_orderbook(&extended_market).await {
             Ok(ob) => {
-                if let (Some(b), Some(a)) = (ob.bid.first(), ob.ask.first()) {
-                    let bid = b.price.parse::<f64>().unwrap_or(0.0);
-                    let ask = a.price.parse::<f64>().unwrap_or(0.0);
-                    let mid = (bid + ask) / 2.0;
-                    let spread = if mid > 0.0 {
-                        ((ask - bid) / mid) * 100.0
-                    } else {
-                        999.0
-                    };
-                    (bid, ask, mid, spread)
+                sources.extended = QuoteSource::Rest;
+                (BidAsk::from(&ob), Some(extended_book(&ob)))
+            }
+            Err(e) => {
+                if let Some((depth, quote)) = cached(fallbacks.extended_cached) {
+                    debug!("{}: Extended REST book failed ({}), using a streamed quote", symbol, e);
+                    sources.extended = QuoteSource::CachedStream;
+                    (quote, Some(depth))
+                } else if let Some(ob) =
+                    secondary_extended_orderbook(fallbacks.extended_secondary.as_deref(), &extended_market).await
+                {
+                    debug!("{}: Extended REST book failed ({}), using the secondary endpoint", symbol, e);
+                    sources.extended = QuoteSource::Secondary;
+                    (BidAsk::from(&ob), Some(extended_book(&ob)))
                 } else {
                     return Ok(None);
                 }
             }
-            Err(_) => return Ok(None),
-        };
-
-    let (_pac_bid, _pac_ask, pac_mid, pac_spread) =
-        match pacifica_client.get_orderbook_rest(&symbol, 1).await {
+        },
+    };
+    let (pacifica_quote, pacifica_depth) = match pacifica_quote {
+        Some(quote) => {
+            sources.pacifica = QuoteSource::Stream;
+            let top = BookDepth::from_quote(&quote);
+            let depth = if top.top_covers(depth_notional_usd) {
+                Some(top)
+            } else if let Some(ob) = &pacifica_streamed_book {
+                Some(pacifica_book(ob))
+            } else {
+                pacifica_client.get_orderbook_rest(&pacifica_market, 1).await.ok().map(|ob| pacifica_book(&ob))
+            };
+            (quote, depth)
+        }
+        None => match pacifica_client.get_orderbook_rest(&pacifica_market, 1).await {
             Ok(ob) => {
-                if let (Some(b), Some(a)) = (ob.bids.first(), ob.asks.first()) {
-                    let bid = b.price.parse::<f64>().unwrap_or(0.0);
-                    let ask = a.price.parse::<f64>().unwrap_or(0.0);
-                    let mid = (bid + ask) / 2.0;
-                    let spread = if mid > 0.0 {
-                        ((ask - bid) / mid) * 100.0
-                    } else {
-                        999.0
-                    };
-                    (bid, ask, mid, spread)
-                } else {
-                    return Ok(None);
-                }
+                sources.pacifica = QuoteSource::Rest;
+                (ob.top_of_book(&pacifica_market), Some(pacifica_book(&ob)))
             }
-            Err(_) => return Ok(None),
-        };
+            Err(e) => match cached(fallbacks.pacifica_cached) {
+                Some((depth, quote)) => {
+                    debug!("{}: Pacifica REST book failed ({}), using a streamed quote", symbol, e);
+                    sources.pacifica = QuoteSource::CachedStream;
+                    (quote, Some(depth))
+                }
+                None => return Ok(None),
+            },
+        },
+    };
+    let Some((ext_mid, ext_spread)) = mid_and_spread_pct(&extended_quote) else {
+        return Ok(None);
+    };
+    let Some((pac_mid, pac_spread)) = mid_and_spread_pct(&pacifica_quote) else {
+        return Ok(None);
+    };
 
     if ext_mid == 0.0 || pac_mid == 0.0 {
         return Ok(None);
     }
 
-    let cross_spread = ((pac_mid - ext_mid).abs() / ext_mid) * 100.0;
+    let cross_spread = cross_spread_pct(ext_mid, pac_mid, multipliers);
 
-    // Fetch funding rates
-    // Extended funding rates are HOURLY (applied once per hour)
-    // Use raw decimal rate, multiply by periods per year, then convert to percentage
-    let ext_funding_apr = match extended_client.get_funding_rate(&extended_market).await {
-        Ok(Some(fr)) => fr.rate * 24.0 * 365.0 * 100.0,  // rate is decimal, convert to %
-        _ => 0.0,
+    // Fetch funding rates, normalized to the expected rate at the next settlement
+    let ext_interval = funding_intervals.hours(Venue::Extended, &symbol);
+    let ext_forecast = match extended_client.get_funding_rate(&extended_market).await {
+        Ok(Some(fr)) => FundingForecast::from_extended(&fr).with_interval_hours(ext_interval),
+        _ => FundingForecast::new(Venue::Extended, 0.0, ext_interval),
     };
 
-    let pac_funding_apr = match pacifica_client.get_funding_rate(&symbol).await {
-        Ok(fr) => fr.next_rate_percentage * 24.0 * 365.0,  // Use next/projected rate (not historical)
-        Err(_) => 0.0,
+    let pac_interval = funding_intervals.hours(Venue::Pacifica, &symbol);
+    let pac_forecast = match pacifica_client.get_funding_rate(&pacifica_market).await {
+        Ok(fr) => FundingForecast::from_pacifica(&fr).with_interval_hours(pac_interval),
+        Err(_) => FundingForecast::new(Venue::Pacifica, 0.0, pac_interval),
     };
 
-    let net_apr_long_ext = -ext_funding_apr + pac_funding_apr;
-    let net_apr_long_pac = -pac_funding_apr + ext_funding_apr;
+    // Rates beyond a venue's cap will not be settled in full; rank on what will be
+    let ext_capped = ext_forecast.capped(funding_caps.max_rate(Venue::Extended, &symbol));
+    let pac_capped = pac_forecast.capped(funding_caps.max_rate(Venue::Pacifica, &symbol));
+    let funding_capped = ext_capped != ext_forecast || pac_capped != pac_forecast;
+    if funding_capped {
+        debug!(
+            "{}: funding clamped to caps (Extended {:.4}% -> {:.4}%, Pacifica {:.4}% -> {:.4}%)",
+            symbol,
+            ext_forecast.next_settlement_rate * 100.0,
+            ext_capped.next_settlement_rate * 100.0,
+            pac_forecast.next_settlement_rate * 100.0,
+            pac_capped.next_settlement_rate * 100.0
+        );
+    }
+    let (ext_forecast, pac_forecast) = (ext_capped, pac_capped);
+
+    let ext_funding_apr = ext_forecast.apr_pct();
+    let pac_funding_apr = pac_forecast.apr_pct();
+
+    let net_apr_long_ext = net_apr_pct(&ext_forecast, &pac_forecast);
+    let net_apr_long_pac = net_apr_pct(&pac_forecast, &ext_forecast);
 
     let (best_direction, best_net_apr) = if net_apr_long_ext > net_apr_long_pac {
-        (
-            "Long Extended / Short Pacifica".to_string(),
-            net_apr_long_ext,
-        )
+        (Direction::LongExtendedShortPacifica, net_apr_long_ext)
     } else {
-        (
-            "Long Pacifica / Short Extended".to_string(),
-            net_apr_long_pac,
-        )
+        (Direction::LongPacificaShortExtended, net_apr_long_pac)
     };
 
     Ok(Some(Opportunity {
@@ -776,5 +2232,317 @@ async fn fetch_opportunity_data(
         pacifica_volume_24h: vol_data.pacifica_volume,
         best_direction,
         best_net_apr,
+        extended_open_interest_usd: vol_data.extended_open_interest_usd,
+        pacifica_open_interest_usd: vol_data.pacifica_open_interest_usd,
+        extended_mark_index_gap_pct: vol_data.extended_mark_index_gap_pct,
+        pacifica_mark_index_gap_pct: vol_data.pacifica_mark_index_gap_pct,
+        funding_capped,
+        extended_depth,
+        pacifica_depth,
     }))
 }
+
+/// Opportunity with typical spreads and volumes, for tests elsewhere in the crate
+#[cfg(test)]
+pub(crate) fn sample_opportunity(symbol: &str, net_apr: f64, direction: Direction) -> Opportunity {
+    Opportunity {
+        symbol: symbol.to_string(),
+        extended_spread_pct: 0.01,
+        pacifica_spread_pct: 0.02,
+        cross_spread_pct: 0.03,
+        extended_funding_rate_apr: 0.0,
+        pacifica_funding_rate_apr: net_apr,
+        total_volume_24h: 3_000_000.0,
+        extended_volume_24h: 2_000_000.0,
+        pacifica_volume_24h: 1_000_000.0,
+        best_direction: direction,
+        best_net_apr: net_apr,
+        extended_open_interest_usd: None,
+        pacifica_open_interest_usd: None,
+        extended_mark_index_gap_pct: None,
+        pacifica_mark_index_gap_pct: None,
+        funding_capped: false,
+        extended_depth: None,
+        pacifica_depth: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_scan() -> ScanResult {
+        let opportunity = Opportunity {
+            extended_funding_rate_apr: 10.0,
+            pacifica_funding_rate_apr: -5.0,
+            extended_open_interest_usd: Some(5_000_000.0),
+            pacifica_open_interest_usd: Some(400_000.0),
+            ..sample_opportunity("BTC", 15.0, Direction::LongPacificaShortExtended)
+        };
+        ScanResult {
+            opportunities: vec![opportunity.clone()],
+            all_candidates: vec![OpportunityCandidate {
+                opportunity,
+                filter_result: FilterResult::Passed,
+                filtered_cross_spread_pct: None,
+            }],
+            stats: FilterStats {
+                total_common_symbols: 1,
+                filtered_by_volume: 0,
+                filtered_by_spread: 0,
+                filtered_by_apr: 0,
+                filtered_by_open_interest: 0,
+                filtered_by_mark_index_gap: 0,
+                passed_filters: 1,
+            },
+            scanned_at: 1_700_000_000,
+            data_availability: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_scan_to_csv() {
+        let csv = sample_scan().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("scanned_at,symbol,filter_result"));
+        assert!(lines[1].starts_with("1700000000,BTC,Passed,Long Pacifica / Short Extended,15,"));
+        assert_eq!(lines[0].split(',').count(), lines[1].split(',').count());
+    }
+
+    #[test]
+    fn test_scan_to_json() {
+        let json: serde_json::Value = serde_json::from_str(&sample_scan().to_json().unwrap()).unwrap();
+        assert_eq!(json["scanned_at"], 1_700_000_000);
+        assert_eq!(json["opportunities"][0]["symbol"], "BTC");
+        assert_eq!(json["all_candidates"][0]["filter_result"], "Passed");
+    }
+
+    #[test]
+    fn test_early_rotation_gain_apr() {
+        let held = sample_scan().opportunities[0].clone();
+        assert_eq!(held.net_apr_for(Direction::LongPacificaShortExtended), 15.0);
+        assert_eq!(held.net_apr_for(Direction::LongExtendedShortPacifica), -15.0);
+
+        let mut best = held.clone();
+        best.symbol = "ETH".to_string();
+        best.best_net_apr = 60.0;
+        // Costs: 4 x 0.05% + 0.015% + 0.015% = 0.23% over 48h -> 0.23 * 8760 / 48 = 41.975 APR points
+        let gain = early_rotation_gain_apr(&held, 15.0, &best, 48, FeeSchedule::flat(0.05).round_trip_fee_pct(false));
+        assert!((gain - (60.0 - 15.0 - 41.975)).abs() < 1e-9);
+        // Shorter holds amortize the same cost over less time
+        assert!(early_rotation_gain_apr(&held, 15.0, &best, 24, 0.2) < 0.0);
+    }
+
+    #[test]
+    fn test_estimated_entry_cost_bps() {
+        // Long Pacifica / short Extended: sells into Extended bids, buys Pacifica asks
+        let mut opp = sample_scan().opportunities[0].clone();
+        assert_eq!(opp.estimated_entry_cost_bps(500.0, 0.1), None);
+
+        opp.extended_depth = Some(BookDepth { bids: vec![(99.9, 10.0)], asks: vec![(100.1, 10.0)] });
+        opp.pacifica_depth = Some(BookDepth { bids: vec![(99.95, 1.0)], asks: vec![(100.05, 2.0), (100.25, 10.0)] });
+        assert!(opp.extended_depth.as_ref().unwrap().top_covers(500.0));
+        assert!(!opp.pacifica_depth.as_ref().unwrap().top_covers(500.0));
+
+        // Extended 0.10% (half spread), Pacifica 0.17% (5 units at a 100.17 VWAP), fees 0.10% over both legs
+        let cost = opp.estimated_entry_cost_bps(500.0, 0.1).unwrap();
+        assert!((cost - 37.0).abs() < 1e-6, "{}", cost);
+        // More than the visible Pacifica asks
+        assert_eq!(opp.estimated_entry_cost_bps(5_000.0, 0.1), None);
+    }
+
+    #[test]
+    fn test_leg_sequence() {
+        let mut opp = sample_scan().opportunities[0].clone();
+        assert_eq!(opp.leg_sequence(LegOrder::ExtendedFirst, 500.0), LegSequence::ExtendedFirst);
+        assert_eq!(opp.leg_sequence(LegOrder::Concurrent, 500.0), LegSequence::Concurrent);
+
+        // Without books the venue with less 24h volume leads
+        opp.extended_volume_24h = 1_000_000.0;
+        opp.pacifica_volume_24h = 5_000_000.0;
+        assert_eq!(opp.leg_sequence(LegOrder::LessLiquidFirst, 500.0), LegSequence::ExtendedFirst);
+
+        // Pacifica asks cost more to walk for the long Pacifica leg
+        opp.extended_depth = Some(BookDepth { bids: vec![(99.9, 10.0)], asks: vec![(100.1, 10.0)] });
+        opp.pacifica_depth = Some(BookDepth { bids: vec![(99.95, 1.0)], asks: vec![(100.05, 2.0), (100.25, 10.0)] });
+        assert_eq!(opp.leg_sequence(LegOrder::LessLiquidFirst, 500.0), LegSequence::PacificaFirst);
+        // A book too thin for the size is the less liquid one
+        opp.extended_depth = Some(BookDepth { bids: vec![(99.9, 1.0)], asks: vec![(100.1, 1.0)] });
+        assert_eq!(opp.leg_sequence(LegOrder::LessLiquidFirst, 500.0), LegSequence::ExtendedFirst);
+    }
+
+    #[test]
+    fn test_simulate_break_even() {
+        let mut config = Config::default_config();
+        config.rotation.taker_fee_pct = 0.05;
+        config.trading.hold_time_hours = 48;
+
+        // No books: half spreads 0.005% + 0.01%, twice, plus 4 x 0.05% fees = 0.23% of $10k
+        let opp = sample_scan().opportunities[0].clone();
+        let sim = opp.simulate(10_000.0, &config);
+        assert!((sim.fees_usd - 20.0).abs() < 1e-9);
+        assert!((sim.slippage_usd - 3.0).abs() < 1e-9);
+        // 15% APR on $10k earns $1500 / 8760 per hour
+        let hours = sim.break_even_hours.unwrap();
+        assert!((hours - 23.0 * 8760.0 / 1500.0).abs() < 1e-6, "{}", hours);
+        assert!(!sim.breaks_even());
+        assert!(sim.net_usd() < 0.0);
+
+        config.trading.hold_time_hours = 168;
+        assert!(opp.simulate(10_000.0, &config).breaks_even());
+
+        let mut paying = opp.clone();
+        paying.best_net_apr = -1.0;
+        assert_eq!(paying.simulate(10_000.0, &config).break_even_hours, None);
+    }
+
+    #[test]
+    fn test_with_live_spreads() {
+        let quote = |bid: &str, ask: &str| BidAsk {
+            market: "BTC".to_string(),
+            best_bid: Some(bid.to_string()),
+            best_ask: Some(ask.to_string()),
+            bid_quantity: None,
+            ask_quantity: None,
+            timestamp: 0,
+        };
+        let filters = FilterConfig {
+            min_combined_volume_usd: 1_000_000.0,
+            max_intra_exchange_spread_pct: 0.1,
+            max_cross_exchange_spread_pct: 0.2,
+            min_net_apr_pct: 10.0,
+            symbol_blacklist: Vec::new(),
+            min_open_interest_multiple: 0.0,
+            max_mark_index_gap_pct: 0.0,
+        };
+        let scanned = sample_scan().opportunities.remove(0);
+        // Thinnest venue's open interest decides
+        assert!(scanned.has_open_interest(400_000.0));
+        assert!(!scanned.has_open_interest(500_000.0));
+
+        // Widest mark/index gap decides, whatever its sign
+        assert!((mark_index_gap_pct(100.5, 100.0).unwrap() - 0.5).abs() < 1e-9);
+        assert_eq!(mark_index_gap_pct(100.0, 0.0), None);
+        assert_eq!(scanned.max_mark_index_gap_pct(), None);
+        let squeezed = Opportunity {
+            extended_mark_index_gap_pct: Some(0.1),
+            pacifica_mark_index_gap_pct: Some(-1.5),
+            ..scanned.clone()
+        };
+        assert_eq!(squeezed.max_mark_index_gap_pct(), Some(1.5));
+
+        let unit = ContractMultipliers::default();
+        let live = scanned.with_live_spreads(&quote("99.99", "100.01"), &quote("100.09", "100.11"), unit).unwrap();
+        assert!((live.cross_spread_pct - 0.1).abs() < 1e-9);
+        assert!(matches!(live.check_filters(&filters), FilterResult::Passed));
+
+        // Cross spread widened past the filter since the scan
+        let live = scanned.with_live_spreads(&quote("99.99", "100.01"), &quote("100.49", "100.51"), unit).unwrap();
+        assert!(matches!(live.check_filters(&filters), FilterResult::FailedCrossSpread));
+
+        // A Pacifica contract of 1000 units is compared per unit
+        let per_thousand = ContractMultipliers { extended: 1.0, pacifica: 1000.0 };
+        let live = scanned.with_live_spreads(&quote("99.99", "100.01"), &quote("100090", "100110"), per_thousand).unwrap();
+        assert!((live.cross_spread_pct - 0.1).abs() < 1e-9);
+
+        let mut one_sided = quote("100", "100");
+        one_sided.best_ask = None;
+        assert!(scanned.with_live_spreads(&quote("100", "100"), &one_sided, unit).is_none());
+    }
+
+    #[test]
+    fn test_csv_field_escaping() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_environment_selects_endpoints() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "environment": "testnet",
+                "filters": {"min_combined_volume_usd": 0, "max_intra_exchange_spread_pct": 1,
+                            "max_cross_exchange_spread_pct": 1, "min_net_apr_pct": 0},
+                "trading": {"max_position_size_usd": 100, "hold_time_hours": 1},
+                "display": {"max_opportunities_shown": 1, "show_filtered_out_count": false},
+                "performance": {"fetch_timeout_seconds": 1, "rate_limit_delay_ms": 0}
+            }"#,
+        ).unwrap();
+        assert!(config.environment.is_testnet());
+
+        let ext = config.environment.extended_client(None).unwrap();
+        assert!(ext.base_url().contains("sepolia"));
+        let creds = PacificaCredentials {
+            account: String::new(),
+            agent_wallet: String::new(),
+            private_key: String::new(),
+        };
+        assert!(config.environment.pacifica_client(creds.clone()).rest_url().contains("test-api"));
+        assert_eq!(Config::default_config().environment, Environment::Mainnet);
+        assert!(!Environment::Mainnet.pacifica_client(creds).rest_url().contains("test"));
+    }
+
+    #[test]
+    fn test_restriction_reason() {
+        let market = |name: &str, active: bool, status: &str| MarketInfo {
+            name: name.to_string(),
+            active,
+            status: status.to_string(),
+            asset_name: name.trim_end_matches("-USD").to_string(),
+        };
+        let extended = vec![
+            market("BTC-USD", true, "ACTIVE"),
+            market("ETH-USD", true, "REDUCE_ONLY"),
+            market("SOL-USD", true, "ACTIVE"),
+        ];
+        let pac_info: PacificaMarketInfo = serde_json::from_value(serde_json::json!({
+            "symbol": "BTC", "tick_size": "1", "lot_size": "0.001",
+            "funding_rate": "0", "next_funding_rate": "0"
+        })).unwrap();
+        let mut pacifica = HashMap::new();
+        pacifica.insert("BTC".to_string(), pac_info.clone());
+        pacifica.insert("ETH".to_string(), pac_info);
+
+        assert_eq!(restriction_reason("BTC", &extended, &pacifica, &[], &SymbolMap::default()), None);
+        assert!(restriction_reason("btc", &extended, &pacifica, &["BTC".to_string()], &SymbolMap::default())
+            .is_some_and(|r| r.contains("blacklisted")));
+        assert!(restriction_reason("BTC", &extended, &pacifica, &["btc".to_string()], &SymbolMap::default()).is_some());
+        assert!(restriction_reason("ETH", &extended, &pacifica, &[], &SymbolMap::default())
+            .is_some_and(|r| r.contains("REDUCE_ONLY")));
+        assert!(restriction_reason("SOL", &extended, &pacifica, &[], &SymbolMap::default())
+            .is_some_and(|r| r.contains("Pacifica")));
+        assert!(restriction_reason("DOGE", &extended, &pacifica, &[], &SymbolMap::default())
+            .is_some_and(|r| r.contains("Extended")));
+    }
+
+    #[test]
+    fn test_extended_vault_routing_and_verification() {
+        let mut vaults = ExtendedVaultConfig::default();
+        assert_eq!(vaults.vault_for("cross_exchange_funding", "BTC", "100"), "100");
+        vaults.strategy_vaults.insert("cross_exchange_funding".to_string(), "200".to_string());
+        vaults.symbol_vaults.insert("BTC".to_string(), "300".to_string());
+        assert_eq!(vaults.vault_for("cross_exchange_funding", "BTC", "100"), "300");
+        assert_eq!(vaults.vault_for("cross_exchange_funding", "ETH", "100"), "200");
+        assert_eq!(vaults.vault_for("other", "ETH", "100"), "100");
+        assert_eq!(vaults.configured_vaults(), vec!["200", "300"]);
+
+        let account = |vault: &str, key: &str| -> AccountInfo {
+            serde_json::from_value(serde_json::json!({
+                "l2Key": key, "l2Vault": vault, "accountId": 1, "status": "ACTIVE", "accountIndex": 1,
+            }))
+            .unwrap()
+        };
+        let accounts = [account("200", "0x0abc"), account("300", "0xABC")];
+        assert!(vaults.verify("200", &accounts, "0xabc").unwrap_err().contains("vault 300 is not supported"));
+        vaults.symbol_vaults.insert("BTC".to_string(), "200".to_string());
+        assert!(vaults.verify("200", &accounts, "0xabc").is_ok());
+        assert!(vaults.verify("200", &accounts, "0xdef").unwrap_err().contains("Stark key"));
+        assert!(vaults.verify("200", &accounts[1..], "0xabc").unwrap_err().contains("vault 200 is not an account"));
+
+        let mut config = Config::default_config();
+        config.extended_vaults.symbol_vaults.insert("BTC".to_string(), "main".to_string());
+        assert!(config.validate().is_err());
+    }
+}
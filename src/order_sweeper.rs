@@ -0,0 +1,139 @@
+//! Orphan-order sweeper
+//!
+//! Manual testing or an interrupted run can leave resting orders on either venue that
+//! no position of the bot accounts for; one filling later silently opens exposure. With
+//! `order_sweep.enabled`, every `check_interval_seconds` while the bot waits between
+//! cycles it lists resting orders on both venues and cancels each one whose client order
+//! ID derives from neither the held position nor an execution still open in the intent
+//! log, once it has rested longer than `grace_seconds` (by the venue's creation time, or
+//! since the sweeper first saw it when the venue reports none). Extended orders without
+//! an external ID cannot be cancelled and are only reported.
+use crate::capital::Venue;
+use crate::trading::derives_from_position;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderSweepConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Orphans resting for less than this are left alone
+    #[serde(default = "default_grace_seconds")]
+    pub grace_seconds: u64,
+    #[serde(default = "default_check_interval_seconds")]
+    pub check_interval_seconds: u64,
+}
+
+fn default_grace_seconds() -> u64 {
+    120
+}
+
+fn default_check_interval_seconds() -> u64 {
+    300
+}
+
+impl Default for OrderSweepConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            grace_seconds: default_grace_seconds(),
+            check_interval_seconds: default_check_interval_seconds(),
+        }
+    }
+}
+
+/// An order resting on either venue
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestingOrder {
+    pub venue: Venue,
+    pub symbol: String,
+    /// Venue order ID
+    pub order_id: String,
+    /// Client (Pacifica) or external (Extended) order ID, if any
+    pub client_order_id: Option<String>,
+    /// Creation time (Unix ms) when the venue reports it
+    pub created_at_ms: Option<u64>,
+}
+
+/// Remembers when each order was first seen, for venues that do not report creation time
+#[derive(Debug, Default)]
+pub struct OrderSweeper {
+    first_seen_ms: HashMap<(Venue, String), u64>,
+}
+
+impl OrderSweeper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Orders in `orders` that belong to none of `position_ids` and rested longer than
+    /// `grace_seconds` at `now_ms`
+    pub fn orphans<'a>(
+        &mut self,
+        orders: &'a [RestingOrder],
+        position_ids: &[String],
+        config: &OrderSweepConfig,
+        now_ms: u64,
+    ) -> Vec<&'a RestingOrder> {
+        // Forget orders that are gone
+        self.first_seen_ms
+            .retain(|(venue, id), _| orders.iter().any(|o| o.venue == *venue && &o.order_id == id));
+
+        let grace_ms = config.grace_seconds.saturating_mul(1000);
+        orders
+            .iter()
+            .filter(|order| {
+                !order.client_order_id.as_deref().is_some_and(|client_id| {
+                    position_ids.iter().any(|position_id| derives_from_position(client_id, position_id))
+                })
+            })
+            .filter(|order| {
+                let first_seen = *self
+                    .first_seen_ms
+                    .entry((order.venue, order.order_id.clone()))
+                    .or_insert(now_ms);
+                let since = order.created_at_ms.unwrap_or(first_seen);
+                now_ms.saturating_sub(since) > grace_ms
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trading::{client_order_id, OrderLeg};
+    use uuid::Uuid;
+
+    fn order(venue: Venue, order_id: &str, client_order_id: Option<String>, created_at_ms: Option<u64>) -> RestingOrder {
+        RestingOrder {
+            venue,
+            symbol: "BTC".to_string(),
+            order_id: order_id.to_string(),
+            client_order_id,
+            created_at_ms,
+        }
+    }
+
+    #[test]
+    fn test_orphans_skip_owned_and_recent_orders() {
+        let config = OrderSweepConfig { enabled: true, ..OrderSweepConfig::default() };
+        let held = Uuid::new_v4();
+        let own_close = client_order_id(&held, OrderLeg::ExtendedClose, 2).to_string();
+        let now_ms = 1_000_000;
+        let orders = vec![
+            order(Venue::Extended, "1", Some(own_close), Some(0)),
+            order(Venue::Pacifica, "2", None, Some(now_ms - 121_000)),
+            order(Venue::Pacifica, "3", Some("manual".to_string()), Some(now_ms - 60_000)),
+            order(Venue::Extended, "4", Some(Uuid::new_v4().to_string()), None),
+        ];
+        let mut sweeper = OrderSweeper::new();
+        let ids = |orphans: Vec<&RestingOrder>| orphans.iter().map(|o| o.order_id.clone()).collect::<Vec<_>>();
+
+        assert_eq!(ids(sweeper.orphans(&orders, &[held.to_string()], &config, now_ms)), ["2"]);
+        // Without a creation time the grace runs from the first sighting
+        assert_eq!(ids(sweeper.orphans(&orders, &[held.to_string()], &config, now_ms + 121_000)), ["2", "3", "4"]);
+        // The held position's orders are orphans once it is no longer held
+        assert_eq!(sweeper.orphans(&orders, &[], &config, now_ms + 121_000).len(), 4);
+    }
+}
@@ -0,0 +1,67 @@
+use super::subscription::{self, Subscription};
+use super::types::PacificaAccountInfo;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// Client for the `account_info` channel, which pushes an account's equity, free
+/// collateral and margin whenever they change
+#[derive(Debug, Clone)]
+pub struct AccountStreamClient {
+    ws_url: String,
+    account: String,
+}
+
+impl AccountStreamClient {
+    pub fn new(account: impl Into<String>, is_testnet: bool) -> Self {
+        Self { ws_url: subscription::ws_url(is_testnet).to_string(), account: account.into() }
+    }
+
+    /// Stream account updates in the background, reconnecting with backoff (1s doubling
+    /// up to 30s) whenever the connection drops. The task ends once the receiver is dropped.
+    pub fn subscribe_reconnecting(self) -> mpsc::UnboundedReceiver<PacificaAccountInfo> {
+        Subscription {
+            ws_url: self.ws_url,
+            channel: "account_info",
+            subscribe: json!({
+                "method": "subscribe",
+                "params": { "source": "account_info", "account": self.account }
+            }),
+            parse: |text| parse_account_info(text).into_iter().collect(),
+        }
+        .spawn()
+    }
+}
+
+/// Account info in an `account_info` channel message; other messages yield none
+fn parse_account_info(text: &str) -> Option<PacificaAccountInfo> {
+    let msg: serde_json::Value = serde_json::from_str(text).ok()?;
+    if msg.get("channel").and_then(|c| c.as_str()) != Some("account_info") {
+        return None;
+    }
+    match serde_json::from_value(msg.get("data")?.clone()) {
+        Ok(info) => Some(info),
+        Err(e) => {
+            debug!("[PACIFICA] Ignoring unparsable account_info message ({}): {}", e, text);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_account_info() {
+        let text = r#"{"channel":"account_info","data":{"ae":"1250.5","as":"800.25","aw":"790","b":"1200","mu":"450.25",
+            "cm":"45.1","f":0,"oc":1,"pc":1,"sc":0,"t":1730000000000}}"#;
+        let info = parse_account_info(text).unwrap();
+        assert_eq!(info.account_equity_f64(), 1250.5);
+        assert_eq!(info.available_to_spend_f64(), 800.25);
+        assert_eq!(info.timestamp, 1_730_000_000_000);
+
+        assert!(parse_account_info(r#"{"channel":"pong"}"#).is_none());
+        assert!(parse_account_info(r#"{"channel":"account_info","data":{"ae":"1"}}"#).is_none());
+    }
+}
@@ -0,0 +1,152 @@
+//! Agent wallet keys and credential files for key rotation
+//!
+//! Pacifica orders are signed by an agent wallet bound to the main account. Rotating it
+//! means generating a fresh keypair, binding it (`PacificaTrading::rotate_agent_key`),
+//! and writing it to the credentials file. Bots started with `PACIFICA_CREDENTIALS_FILE`
+//! load their keys from that file and re-read it every cycle
+//! (`PacificaCredentials::from_env_file`), so no restart is needed. `.env` is never
+//! re-read: values already set in the environment win over it at startup, and a reload
+//! would silently replace them.
+use anyhow::{Context, Result};
+use ed25519_dalek::SigningKey;
+use std::collections::HashMap;
+
+/// Credentials file the rotation tool rewrites: `PACIFICA_CREDENTIALS_FILE`, else `.env`
+pub fn resolve_credentials_path() -> String {
+    reloadable_credentials_path().unwrap_or_else(|| ".env".to_string())
+}
+
+/// Credentials file running bots load and re-read for rotated keys, only when set
+/// explicitly with `PACIFICA_CREDENTIALS_FILE`
+pub fn reloadable_credentials_path() -> Option<String> {
+    std::env::var("PACIFICA_CREDENTIALS_FILE").ok().filter(|path| !path.is_empty())
+}
+
+/// Solana-style Ed25519 keypair, base58 encoded as Pacifica expects
+#[derive(Clone)]
+pub struct AgentKeypair {
+    /// 32-byte public key (API_PUBLIC)
+    pub public_key: String,
+    /// 64-byte seed + public key (API_PRIVATE)
+    pub private_key: String,
+}
+
+impl std::fmt::Debug for AgentKeypair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentKeypair").field("public_key", &self.public_key).finish_non_exhaustive()
+    }
+}
+
+impl AgentKeypair {
+    /// Fresh keypair from the OS random number generator
+    pub fn generate() -> Result<Self> {
+        let mut seed = [0u8; 32];
+        getrandom::fill(&mut seed).map_err(|e| anyhow::anyhow!("OS random number generator failed: {}", e))?;
+        Ok(Self::from_seed(&seed))
+    }
+
+    fn from_seed(seed: &[u8; 32]) -> Self {
+        let signing_key = SigningKey::from_bytes(seed);
+        let public = signing_key.verifying_key().to_bytes();
+        let mut keypair = [0u8; 64];
+        keypair[..32].copy_from_slice(seed);
+        keypair[32..].copy_from_slice(&public);
+        Self {
+            public_key: bs58::encode(public).into_string(),
+            private_key: bs58::encode(keypair).into_string(),
+        }
+    }
+
+    /// Keypair of a base58 64-byte private key; the public key is derived from the seed
+    pub fn from_private_key(private_key: &str) -> Result<Self> {
+        Ok(Self::from_seed(&signing_seed(private_key)?))
+    }
+}
+
+/// 32-byte Ed25519 seed of a base58 Solana keypair (seed followed by public key)
+pub(crate) fn signing_seed(private_key: &str) -> Result<[u8; 32]> {
+    let bytes = bs58::decode(private_key).into_vec().context("Failed to decode private key")?;
+    if bytes.len() != 64 {
+        anyhow::bail!("Invalid private key length: expected 64 bytes, got {}", bytes.len());
+    }
+    bytes[..32].try_into().context("Failed to extract seed from private key")
+}
+
+/// Values of a dotenv-style file, without touching the process environment
+pub fn read_env_file(path: &str) -> Result<HashMap<String, String>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to open {}", path))?;
+    Ok(parse_env(&content))
+}
+
+/// `KEY=value` lines (optionally `export`-prefixed, values optionally quoted); comments
+/// and blank lines are skipped
+fn parse_env(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.trim_start_matches("export ").split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim();
+            let unquoted = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            (key.trim().to_string(), unquoted.to_string())
+        })
+        .collect()
+}
+
+/// `content` with each `KEY=value` line of `updates` replaced, or appended when missing.
+/// Comments and other lines are kept as they are.
+pub fn rewrite_env(content: &str, updates: &[(&str, &str)]) -> String {
+    let mut done = vec![false; updates.len()];
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let key = line.trim_start().trim_start_matches("export ").split('=').next().unwrap_or("").trim();
+            match updates.iter().position(|(k, _)| *k == key && line.contains('=')) {
+                Some(i) => {
+                    done[i] = true;
+                    format!("{}={}", updates[i].0, updates[i].1)
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect();
+    for ((key, value), done) in updates.iter().zip(done) {
+        if !done {
+            lines.push(format!("{}={}", key, value));
+        }
+    }
+    let mut rewritten = lines.join("\n");
+    rewritten.push('\n');
+    rewritten
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keypair_round_trip_and_env_rewrite() {
+        let keypair = AgentKeypair::from_seed(&[7u8; 32]);
+        let parsed = AgentKeypair::from_private_key(&keypair.private_key).unwrap();
+        assert_eq!(parsed.public_key, keypair.public_key);
+        assert!(AgentKeypair::from_private_key("abc").is_err());
+        assert!(!format!("{:?}", keypair).contains(&keypair.private_key));
+
+        let generated = AgentKeypair::generate().unwrap();
+        assert_ne!(generated.public_key, AgentKeypair::generate().unwrap().public_key);
+
+        let content = "# Pacifica\nSOL_WALLET=abc\nAPI_PUBLIC=old\nexport API_PRIVATE=oldkey\n";
+        let rewritten = rewrite_env(content, &[("API_PUBLIC", "new"), ("API_PRIVATE", "newkey"), ("EXTRA", "1")]);
+        assert_eq!(rewritten, "# Pacifica\nSOL_WALLET=abc\nAPI_PUBLIC=new\nAPI_PRIVATE=newkey\nEXTRA=1\n");
+
+        let values = parse_env("# c\nexport API_PUBLIC=\"pub\"\n\nAPI_PRIVATE = 'priv'\n");
+        assert_eq!(values["API_PUBLIC"], "pub");
+        assert_eq!(values["API_PRIVATE"], "priv");
+        assert_eq!(values.len(), 2);
+    }
+}
@@ -1,16 +1,26 @@
 use super::types::*;
+use super::trading::{OrderbookLevel, OrderbookSnapshot};
+use crate::types::BidAsk;
+use crate::metrics;
+use crate::websocket::TopOfBookCache;
 use anyhow::{anyhow, Result};
 use futures_util::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 use tokio::time::{sleep, Duration, interval};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing::{debug, error, info, warn};
 
 const MAINNET_WS_URL: &str = "wss://ws.pacifica.fi/ws";
+const TESTNET_WS_URL: &str = "wss://test-ws.pacifica.fi/ws";
 
 /// Configuration for the orderbook client
 #[derive(Debug, Clone)]
 pub struct OrderbookConfig {
     pub symbol: String,
+    /// Further symbols subscribed on the same connection
+    pub additional_symbols: Vec<String>,
     pub agg_level: u32,
     pub reconnect_attempts: u32,
     pub ping_interval_secs: u64,
@@ -20,6 +30,7 @@ impl Default for OrderbookConfig {
     fn default() -> Self {
         Self {
             symbol: "BTC".to_string(),
+            additional_symbols: Vec::new(),
             agg_level: 1,
             reconnect_attempts: 5,
             ping_interval_secs: 30,
@@ -27,10 +38,109 @@ impl Default for OrderbookConfig {
     }
 }
 
+impl OrderbookConfig {
+    /// Config subscribing every symbol in `symbols` over one connection
+    pub fn for_symbols(symbols: Vec<String>) -> Self {
+        let mut symbols = symbols.into_iter();
+        Self {
+            symbol: symbols.next().unwrap_or_default(),
+            additional_symbols: symbols.collect(),
+            ..Self::default()
+        }
+    }
+
+    /// All subscribed symbols, without duplicates
+    pub fn symbols(&self) -> Vec<&str> {
+        let mut symbols: Vec<&str> = Vec::new();
+        for symbol in std::iter::once(&self.symbol).chain(&self.additional_symbols) {
+            if !symbol.is_empty() && !symbols.contains(&symbol.as_str()) {
+                symbols.push(symbol);
+            }
+        }
+        symbols
+    }
+
+    fn symbols_label(&self) -> String {
+        let symbols = self.symbols();
+        match symbols.len() {
+            0..=3 => symbols.join(", "),
+            n => format!("{} symbols", n),
+        }
+    }
+}
+
+/// Latest full book per symbol from the `book` channel, with the local receive time
+///
+/// The channel sends a complete snapshot of every level (at the subscribed
+/// `agg_level`) with each update rather than diffs, so maintaining the book means
+/// replacing it with each snapshot that is newer than the one held.
+#[derive(Clone, Default)]
+pub struct DepthCache {
+    books: Arc<RwLock<HashMap<String, HeldBook>>>,
+}
+
+struct HeldBook {
+    book: OrderbookSnapshot,
+    /// Exchange timestamp of the snapshot (ms)
+    timestamp: u64,
+    received: Instant,
+}
+
+impl DepthCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the symbol's book with `data`, unless a later snapshot is already held
+    pub fn apply(&self, data: &OrderbookData) {
+        let mut books = self.books.write().unwrap_or_else(|e| e.into_inner());
+        if books.get(&data.symbol).is_some_and(|held| held.timestamp > data.timestamp) {
+            return;
+        }
+        let held = HeldBook { book: OrderbookSnapshot::from(data), timestamp: data.timestamp, received: Instant::now() };
+        books.insert(data.symbol.clone(), held);
+    }
+
+    /// Book for `symbol` if it was received within `max_age`
+    pub fn fresh(&self, symbol: &str, max_age: Duration) -> Option<OrderbookSnapshot> {
+        let books = self.books.read().unwrap_or_else(|e| e.into_inner());
+        books
+            .get(symbol)
+            .filter(|held| held.received.elapsed() <= max_age)
+            .map(|held| held.book.clone())
+    }
+}
+
+impl From<&OrderbookData> for OrderbookSnapshot {
+    fn from(data: &OrderbookData) -> Self {
+        let side = |index: usize| -> Vec<OrderbookLevel> {
+            data.levels
+                .get(index)
+                .map(|levels| {
+                    levels
+                        .iter()
+                        .map(|l| OrderbookLevel { price: l.price.clone(), size: l.amount.clone() })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        OrderbookSnapshot { bids: side(0), asks: side(1) }
+    }
+}
+
 /// Orderbook WebSocket client with health check and auto-reconnect
+///
+/// Every update also refreshes a per-symbol top-of-book cache (keyed by Pacifica
+/// symbol, e.g. "BTC") with the local receive time, for readers that poll prices
+/// instead of handling callbacks, and a `DepthCache` with every level of the book
+/// for depth-aware slippage estimates.
 pub struct OrderbookClient {
     config: OrderbookConfig,
     ws_url: String,
+    cache: TopOfBookCache,
+    depth: DepthCache,
+    /// Set by `stream_into_cache`: close the connection once no reader holds the cache
+    stop_when_orphaned: bool,
 }
 
 impl OrderbookClient {
@@ -43,10 +153,70 @@ impl OrderbookClient {
 
         info!(
             "[PACIFICA] Initializing orderbook client for {} on mainnet",
-            config.symbol
+            config.symbols_label()
         );
 
-        Ok(Self { config, ws_url })
+        Ok(Self::with_url(config, ws_url))
+    }
+
+    /// Create a new orderbook client against the Pacifica testnet
+    pub fn new_testnet(config: OrderbookConfig) -> Result<Self> {
+        info!(
+            "[PACIFICA] Initializing orderbook client for {} on testnet",
+            config.symbols_label()
+        );
+
+        Ok(Self::with_url(config, TESTNET_WS_URL.to_string()))
+    }
+
+    fn with_url(config: OrderbookConfig, ws_url: String) -> Self {
+        Self {
+            config,
+            ws_url,
+            cache: TopOfBookCache::new(),
+            depth: DepthCache::new(),
+            stop_when_orphaned: false,
+        }
+    }
+
+    /// Top-of-book cache kept up to date while the client runs
+    pub fn cache(&self) -> TopOfBookCache {
+        self.cache.clone()
+    }
+
+    /// Full-depth books kept up to date while the client runs
+    pub fn depth_cache(&self) -> DepthCache {
+        self.depth.clone()
+    }
+
+    /// Run the client in the background, only feeding the cache. The connection is
+    /// re-established whenever it drops or runs out of reconnect attempts, and closed
+    /// once every other handle to the returned cache is dropped.
+    pub fn stream_into_cache(self) -> TopOfBookCache {
+        self.stream_into_caches().0
+    }
+
+    /// Same as `stream_into_cache`, also returning the full-depth books. The connection
+    /// is closed once the top-of-book cache is no longer read.
+    pub fn stream_into_caches(mut self) -> (TopOfBookCache, DepthCache) {
+        self.stop_when_orphaned = true;
+        let caches = (self.cache.clone(), self.depth.clone());
+        tokio::spawn(async move {
+            loop {
+                let pause = match self.start(|_, _, _, _| {}).await {
+                    Ok(()) => Duration::from_secs(1),
+                    Err(e) => {
+                        warn!("[PACIFICA] Orderbook stream stopped: {}", e);
+                        Duration::from_secs(30)
+                    }
+                };
+                if self.cache.is_orphaned() {
+                    return;
+                }
+                sleep(pause).await;
+            }
+        });
+        caches
     }
 
     /// Start the client with a callback for top of book updates
@@ -102,21 +272,20 @@ impl OrderbookClient {
         info!("[PACIFICA] Connecting to {}", self.ws_url);
 
         // Connect to WebSocket
-        let (ws_stream, _) = connect_async(&self.ws_url).await?;
+        let (ws_stream, _) = metrics::observe("pacifica WS connect (book)", connect_async(&self.ws_url)).await?;
         info!("[PACIFICA] WebSocket connected successfully");
 
         let (mut write, mut read) = ws_stream.split();
 
-        // Subscribe to orderbook
-        let subscribe_msg = SubscribeMessage::new(
-            self.config.symbol.clone(),
-            self.config.agg_level,
-        );
-        let subscribe_json = serde_json::to_string(&subscribe_msg)?;
+        // Subscribe to the orderbook of every symbol on this connection
+        for symbol in self.config.symbols() {
+            let subscribe_msg = SubscribeMessage::new(symbol.to_string(), self.config.agg_level);
+            let subscribe_json = serde_json::to_string(&subscribe_msg)?;
 
-        debug!("[PACIFICA] Sending subscription: {}", subscribe_json);
-        write.send(Message::Text(subscribe_json)).await?;
-        info!("[PACIFICA] Subscribed to orderbook for {}", self.config.symbol);
+            debug!("[PACIFICA] Sending subscription: {}", subscribe_json);
+            write.send(Message::Text(subscribe_json)).await?;
+        }
+        info!("[PACIFICA] Subscribed to orderbook for {}", self.config.symbols_label());
 
         // Setup ping interval
         let mut ping_interval = interval(Duration::from_secs(self.config.ping_interval_secs));
@@ -156,6 +325,10 @@ impl OrderbookClient {
 
                 // Send periodic pings
                 _ = ping_interval.tick() => {
+                    if self.stop_when_orphaned && self.cache.is_orphaned() {
+                        info!("[PACIFICA] Orderbook cache no longer read, closing stream");
+                        break;
+                    }
                     let ping_msg = PingMessage::new();
                     let ping_json = serde_json::to_string(&ping_msg)?;
                     debug!("[PACIFICA] Sending ping: {}", ping_json);
@@ -188,9 +361,11 @@ impl OrderbookClient {
                 // Parse as orderbook response
                 let orderbook_response: OrderbookResponse = serde_json::from_str(text)?;
                 let orderbook_data = orderbook_response.data;
+                self.depth.apply(&orderbook_data);
 
                 // Extract top of book
                 let tob = orderbook_data.get_top_of_book();
+                self.cache.update(BidAsk::from(&tob));
 
                 // Call the callback with top of book data
                 if let (Some(bid), Some(ask)) = (tob.best_bid, tob.best_ask) {
@@ -216,6 +391,51 @@ impl OrderbookClient {
 
 impl Drop for OrderbookClient {
     fn drop(&mut self) {
-        info!("[PACIFICA] OrderbookClient dropped for symbol: {}", self.config.symbol);
+        info!("[PACIFICA] OrderbookClient dropped for {}", self.config.symbols_label());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book_message(symbol: &str, bid: &str, ask: &str) -> String {
+        format!(
+            r#"{{"channel":"book","data":{{"l":[[{{"a":"1.5","n":2,"p":"{}"}}],[{{"a":"0.7","n":1,"p":"{}"}}]],"s":"{}","t":1700000000000}}}}"#,
+            bid, ask, symbol
+        )
+    }
+
+    #[test]
+    fn test_multi_symbol_updates_cache() {
+        let config = OrderbookConfig::for_symbols(vec!["BTC".into(), "ETH".into(), "BTC".into()]);
+        assert_eq!(config.symbols(), vec!["BTC", "ETH"]);
+
+        let client = OrderbookClient::new(config).unwrap();
+        let mut updates = Vec::new();
+        let mut callback = |bid: String, _ask: String, symbol: String, _ts: u64| updates.push((symbol, bid));
+        client.handle_message(&book_message("BTC", "100", "101"), &mut callback).unwrap();
+        client.handle_message(&book_message("ETH", "10", "10.1"), &mut callback).unwrap();
+        client.handle_message(r#"{"channel":"pong"}"#, &mut callback).unwrap();
+        assert_eq!(updates.len(), 2);
+
+        let cache = client.cache();
+        let btc = cache.fresh("BTC", Duration::from_secs(5)).unwrap();
+        assert_eq!(btc.best_bid.as_deref(), Some("100"));
+        assert_eq!(btc.ask_quantity.as_deref(), Some("0.7"));
+        assert_eq!(cache.fresh("ETH", Duration::from_secs(5)).unwrap().best_ask.as_deref(), Some("10.1"));
+        assert_eq!(cache.len(), 2);
+
+        let depth = client.depth_cache();
+        client.handle_message(
+            r#"{"channel":"book","data":{"l":[[{"a":"1","n":1,"p":"99"},{"a":"2","n":3,"p":"98"}],[{"a":"3","n":1,"p":"102"}]],"s":"BTC","t":1700000001000}}"#,
+            &mut |_, _, _, _| {},
+        ).unwrap();
+        // An older snapshot arriving late does not replace the newer book
+        client.handle_message(&book_message("BTC", "100", "101"), &mut |_, _, _, _| {}).unwrap();
+        let btc = depth.fresh("BTC", Duration::from_secs(5)).unwrap();
+        assert_eq!(btc.bids.len(), 2);
+        assert_eq!(btc.bids[1].price, "98");
+        assert_eq!(btc.asks[0].size, "3");
     }
 }
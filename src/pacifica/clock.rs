@@ -0,0 +1,91 @@
+use anyhow::{anyhow, Context, Result};
+use crate::api_capture;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Instant;
+
+/// Offset (server - local) in ms applied to every signed Pacifica timestamp.
+/// Signatures expire after 5s, so a skewed host clock otherwise fails auth silently.
+static OFFSET_MS: AtomicI64 = AtomicI64::new(0);
+
+/// Skew above which a warning is logged
+pub const CLOCK_SKEW_WARN_MS: i64 = 1_000;
+/// Skew above which the host clock is considered broken and the bot refuses to start
+pub const CLOCK_SKEW_MAX_MS: i64 = 60_000;
+/// HTTP `Date` has 1s resolution, so smaller estimates are indistinguishable from noise
+const CLOCK_DEADBAND_MS: i64 = 1_000;
+const CLOCK_SAMPLES: usize = 3;
+
+/// Current offset (server - local) in ms
+pub fn offset_ms() -> i64 {
+    OFFSET_MS.load(Ordering::Relaxed)
+}
+
+pub fn set_offset_ms(offset: i64) {
+    OFFSET_MS.store(offset, Ordering::Relaxed);
+}
+
+/// Local time corrected by the estimated offset, for signed request timestamps
+pub fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis() + offset_ms()
+}
+
+/// NTP-style estimate: server time minus the midpoint of the local send/receive times
+pub fn estimate_offset_ms(sent_ms: i64, received_ms: i64, server_ms: i64) -> i64 {
+    server_ms - (sent_ms + received_ms) / 2
+}
+
+/// Parse an RFC 2822 HTTP `Date` header into ms, centred in its 1s resolution window
+pub fn parse_date_header(value: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|dt| dt.timestamp_millis() + 500)
+}
+
+/// Sample the server `Date` header a few times and keep the lowest-latency estimate
+pub async fn measure_offset_ms(client: &reqwest::Client, url: &str) -> Result<i64> {
+    let mut best: Option<(u128, i64)> = None;
+
+    for _ in 0..CLOCK_SAMPLES {
+        let sent_ms = chrono::Utc::now().timestamp_millis();
+        let started = Instant::now();
+        let response = api_capture::observe_http("pacifica HEAD /api/v1/info", client.head(url).send())
+            .await
+            .context("Failed to reach Pacifica for clock check")?;
+        let rtt = started.elapsed().as_millis();
+        let received_ms = chrono::Utc::now().timestamp_millis();
+
+        let server_ms = response
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_date_header)
+            .ok_or_else(|| anyhow!("Pacifica response has no usable Date header"))?;
+
+        let offset = estimate_offset_ms(sent_ms, received_ms, server_ms);
+        if best.is_none_or(|(best_rtt, _)| rtt < best_rtt) {
+            best = Some((rtt, offset));
+        }
+    }
+
+    let (_, offset) = best.ok_or_else(|| anyhow!("No clock samples taken"))?;
+    Ok(if offset.abs() < CLOCK_DEADBAND_MS { 0 } else { offset })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_offset_ms() {
+        // Request sent at 1000, answered at 1200, server stamped 6100 -> server is 5000ms ahead
+        assert_eq!(estimate_offset_ms(1_000, 1_200, 6_100), 5_000);
+        assert_eq!(estimate_offset_ms(1_000, 1_200, 100), -1_000);
+    }
+
+    #[test]
+    fn test_parse_date_header() {
+        let ms = parse_date_header("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+        assert_eq!(ms, 1_445_412_480_000 + 500);
+        assert!(parse_date_header("not a date").is_none());
+    }
+}
@@ -1,13 +1,22 @@
 use anyhow::{Context, Result};
+use crate::metrics;
 use futures_util::{SinkExt, StreamExt};
-use tokio::time::{interval, Duration};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::net::TcpStream;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration, Instant};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
 use tracing::{debug, error, info, warn};
 
 use super::types::{
-    AccountOrderUpdatesResponse, AccountOrderUpdatesSubscribe, FillEvent, PingMessage,
+    AccountOrderUpdatesResponse, AccountOrderUpdatesSubscribe, ConfirmedFill, FillEvent,
+    PingMessage,
 };
 
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
 /// Configuration for fill detection client
 #[derive(Debug, Clone)]
 pub struct FillDetectionConfig {
@@ -91,25 +100,39 @@ impl FillDetectionClient {
     where
         F: FnMut(FillEvent) + Send + 'static,
     {
+        let ws_stream = self.connect().await?;
+        self.run(ws_stream, callback).await
+    }
+
+    /// Connect to WebSocket and subscribe to account order updates
+    async fn connect(&self) -> Result<WsStream> {
         info!("Connecting to Pacifica WebSocket: {}", self.ws_url);
 
-        let (ws_stream, _) = connect_async(&self.ws_url)
+        let (mut ws_stream, _) = metrics::observe("pacifica WS connect (fills)", connect_async(&self.ws_url))
             .await
             .context("Failed to connect to WebSocket")?;
 
         info!("WebSocket connected successfully");
 
-        let (mut write, mut read) = ws_stream.split();
-
         // Subscribe to account order updates
         let subscribe_msg = AccountOrderUpdatesSubscribe::new(self.config.account.clone());
         let subscribe_json = serde_json::to_string(&subscribe_msg)?;
-        write.send(Message::Text(subscribe_json)).await?;
+        ws_stream.send(Message::Text(subscribe_json)).await?;
         info!(
             "Subscribed to account_order_updates for account: {}",
             self.config.account
         );
 
+        Ok(ws_stream)
+    }
+
+    /// Read order updates from a subscribed stream until it closes
+    async fn run<F>(&self, ws_stream: WsStream, callback: &mut F) -> Result<()>
+    where
+        F: FnMut(FillEvent) + Send + 'static,
+    {
+        let (mut write, mut read) = ws_stream.split();
+
         // Set up ping interval
         let mut ping_interval = interval(Duration::from_secs(self.config.ping_interval_secs));
 
@@ -199,3 +222,124 @@ impl FillDetectionClient {
         Ok(())
     }
 }
+
+/// Final order outcomes keyed by client order ID
+#[derive(Debug, Default)]
+struct FillBook {
+    outcomes: Mutex<HashMap<String, ConfirmedFill>>,
+    updated: Notify,
+}
+
+impl FillBook {
+    fn record(&self, event: &FillEvent) {
+        if let (Some(client_order_id), Some(outcome)) = (event.client_order_id(), event.outcome()) {
+            self.outcomes.lock().unwrap_or_else(PoisonError::into_inner).insert(client_order_id.to_string(), outcome);
+            self.updated.notify_waiters();
+        }
+    }
+
+    fn get(&self, client_order_id: &str) -> Option<ConfirmedFill> {
+        self.outcomes.lock().unwrap_or_else(PoisonError::into_inner).get(client_order_id).copied()
+    }
+}
+
+/// Live `account_order_updates` subscription that confirms individual orders
+///
+/// Start it before sending orders: updates are only observed once the
+/// subscription is in place. The background reader stops when dropped.
+pub struct FillWatcher {
+    book: Arc<FillBook>,
+    reader: JoinHandle<()>,
+}
+
+impl FillWatcher {
+    /// Connect, subscribe and start recording order outcomes
+    pub async fn start(config: FillDetectionConfig, is_testnet: bool) -> Result<Self> {
+        let client = FillDetectionClient::new(config, is_testnet)?;
+        let ws_stream = client.connect().await?;
+        let book = Arc::new(FillBook::default());
+
+        let reader_book = Arc::clone(&book);
+        let reader = tokio::spawn(async move {
+            let mut record = move |event: FillEvent| reader_book.record(&event);
+            if let Err(e) = client.run(ws_stream, &mut record).await {
+                warn!("Fill watcher stopped: {}", e);
+            }
+        });
+
+        Ok(Self { book, reader })
+    }
+
+    /// Wait until the order with `client_order_id` is filled or cancelled
+    ///
+    /// Returns `None` if no final update arrives within `timeout`.
+    pub async fn wait_for(&self, client_order_id: &str, timeout: Duration) -> Option<ConfirmedFill> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            // Register for the next update before checking, so none is missed in between
+            let updated = self.book.updated.notified();
+            tokio::pin!(updated);
+            updated.as_mut().enable();
+
+            if let Some(outcome) = self.book.get(client_order_id) {
+                return Some(outcome);
+            }
+            if tokio::time::timeout_at(deadline, updated).await.is_err() {
+                return None;
+            }
+        }
+    }
+}
+
+impl Drop for FillWatcher {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cancelled(client_order_id: &str, filled: &str) -> FillEvent {
+        FillEvent::Cancelled {
+            order_id: 1,
+            client_order_id: Some(client_order_id.to_string()),
+            symbol: "BTC".to_string(),
+            side: "bid".to_string(),
+            filled_amount: filled.to_string(),
+            original_amount: "1.0".to_string(),
+            reason: "expired".to_string(),
+            timestamp: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn watcher_returns_recorded_outcome_and_times_out_otherwise() {
+        let book = Arc::new(FillBook::default());
+        let watcher = FillWatcher { book: Arc::clone(&book), reader: tokio::spawn(async {}) };
+
+        book.record(&FillEvent::PartialFill {
+            order_id: 1,
+            client_order_id: Some("a".to_string()),
+            symbol: "BTC".to_string(),
+            side: "bid".to_string(),
+            filled_amount: "0.4".to_string(),
+            original_amount: "1.0".to_string(),
+            avg_price: "100".to_string(),
+            timestamp: 0,
+        });
+        assert_eq!(watcher.wait_for("a", Duration::from_millis(10)).await, None);
+
+        let waiter = tokio::spawn(async move { watcher.wait_for("a", Duration::from_secs(5)).await });
+        tokio::task::yield_now().await;
+        book.record(&cancelled("a", "0.4"));
+        let outcome = waiter.await.unwrap().unwrap();
+        assert_eq!(outcome.filled_amount, 0.4);
+        assert_eq!(outcome.avg_price, None);
+
+        // An amount that does not parse leaves the order unconfirmed rather than unfilled
+        book.record(&cancelled("b", "n/a"));
+        assert_eq!(book.get("b"), None);
+    }
+}
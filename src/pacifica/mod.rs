@@ -1,11 +1,21 @@
 pub mod types;
+pub mod clock;
+pub mod agent;
 mod client;
 pub mod trading;
 pub mod fill_detection;
 pub mod ws_trading;
+pub mod order_timeout;
+pub mod prices;
+pub mod account_stream;
+mod subscription;
 
-pub use client::{OrderbookClient, OrderbookConfig};
-pub use trading::{PacificaTrading, PacificaCredentials, OrderSide, TradeHistoryItem};
-pub use fill_detection::{FillDetectionClient, FillDetectionConfig};
+pub use client::{DepthCache, OrderbookClient, OrderbookConfig};
+pub use trading::{PacificaTrading, PacificaCredentials, OrderSide, TimeInForce, OpenOrder, OrderHistoryItem, StopLeg, TradeHistoryItem, FundingHistoryItem};
+pub use fill_detection::{FillDetectionClient, FillDetectionConfig, FillWatcher};
 pub use ws_trading::PacificaWsTrading;
-pub use types::{FillEvent, OrderStatus, OrderEvent, PacificaAccountInfo, PacificaFundingRate, PacificaMarketInfo, PacificaPosition};
+pub use order_timeout::OrderTimeoutConfig;
+pub use prices::PriceStreamClient;
+pub use account_stream::AccountStreamClient;
+pub use agent::AgentKeypair;
+pub use types::{ConfirmedFill, PriceUpdate, FillEvent, OrderStatus, OrderEvent, PacificaAccountInfo, PacificaCandle, PacificaFundingRate, PacificaMarketInfo, PacificaPosition};
@@ -0,0 +1,166 @@
+//! Auto-cancel of stale resting Pacifica orders
+//!
+//! A limit or stop order left by manual intervention can fill against a bot position
+//! later (e.g. reopen a leg the bot just closed). With `pacifica_order_timeout.enabled`,
+//! a background task lists resting orders every `check_interval_seconds` and cancels
+//! each one older than `max_age_seconds`. The bot's own orders are spared: those whose
+//! client order ID derives from the held position (its protective TP/SL rest for as
+//! long as it is held) or from an execution still unresolved in the intent log.
+use super::trading::{OpenOrder, PacificaTrading};
+use crate::error::BotError;
+use crate::trading::derives_from_position;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderTimeoutConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Orders resting longer than this are cancelled
+    #[serde(default = "default_max_age_seconds")]
+    pub max_age_seconds: u64,
+    #[serde(default = "default_check_interval_seconds")]
+    pub check_interval_seconds: u64,
+    /// Leave reduce-only orders (e.g. manual take-profit/stop-loss) in place
+    #[serde(default)]
+    pub keep_reduce_only: bool,
+}
+
+fn default_max_age_seconds() -> u64 {
+    300
+}
+
+fn default_check_interval_seconds() -> u64 {
+    60
+}
+
+impl Default for OrderTimeoutConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_age_seconds: default_max_age_seconds(),
+            check_interval_seconds: default_check_interval_seconds(),
+            keep_reduce_only: false,
+        }
+    }
+}
+
+impl OrderTimeoutConfig {
+    /// Orders in `orders` that rested longer than `max_age_seconds` at `now_ms` and
+    /// belong to none of `position_ids`
+    pub fn stale_orders<'a>(&self, orders: &'a [OpenOrder], position_ids: &[String], now_ms: u64) -> Vec<&'a OpenOrder> {
+        let max_age_ms = self.max_age_seconds.saturating_mul(1000);
+        orders
+            .iter()
+            .filter(|o| now_ms.saturating_sub(o.created_at) > max_age_ms)
+            .filter(|o| {
+                !o.client_order_id.as_deref().is_some_and(|client_id| {
+                    position_ids.iter().any(|position_id| derives_from_position(client_id, position_id))
+                })
+            })
+            .filter(|o| !(self.keep_reduce_only && o.reduce_only))
+            .collect()
+    }
+}
+
+/// Cancel every stale resting order not derived from `position_ids` once; returns how
+/// many were cancelled
+pub async fn cancel_stale_orders(
+    client: &PacificaTrading,
+    config: &OrderTimeoutConfig,
+    position_ids: &[String],
+) -> anyhow::Result<usize> {
+    let orders = client.get_open_orders(None).await?;
+    let now_ms = super::clock::now_ms().max(0) as u64;
+    let mut cancelled = 0;
+    for order in config.stale_orders(&orders, position_ids, now_ms) {
+        let age_secs = now_ms.saturating_sub(order.created_at) / 1000;
+        warn!("Cancelling Pacifica {} {} order {} ({} {} @ {}) after {}s resting",
+            order.symbol, order.order_type, order.order_id, order.side, order.remaining_amount(), order.price, age_secs);
+        let result = match order.client_order_id.as_deref() {
+            Some(client_id) => client.cancel_order(&order.symbol, client_id).await,
+            None => client.cancel_order_by_id(&order.symbol, order.order_id).await,
+        };
+        match result {
+            Ok(()) => cancelled += 1,
+            Err(e) => warn!("Failed to cancel stale Pacifica order {}: {}", order.order_id, e),
+        }
+    }
+    Ok(cancelled)
+}
+
+/// Run `cancel_stale_orders` every `check_interval_seconds` until the task is aborted.
+/// `owned_positions` gives the position IDs whose orders are kept at each check; a check
+/// is skipped when it fails, since an in-flight order could then look stale.
+pub fn spawn_stale_order_canceller<F>(client: PacificaTrading, config: OrderTimeoutConfig, owned_positions: F) -> JoinHandle<()>
+where
+    F: Fn() -> Result<Vec<String>, BotError> + Send + 'static,
+{
+    info!("⏲️  Cancelling Pacifica orders resting longer than {}s{}", config.max_age_seconds,
+        if config.keep_reduce_only { " (reduce-only orders kept)" } else { "" });
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(config.check_interval_seconds.max(1)));
+        loop {
+            interval.tick().await;
+            let position_ids = match owned_positions() {
+                Ok(ids) => ids,
+                Err(e) => {
+                    warn!("Stale Pacifica order check skipped: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = cancel_stale_orders(&client, &config, &position_ids).await {
+                warn!("Stale Pacifica order check failed: {}", e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trading::{client_order_id, OrderLeg};
+    use uuid::Uuid;
+
+    fn order(order_id: u64, created_at: u64, reduce_only: bool) -> OpenOrder {
+        OpenOrder {
+            order_id,
+            client_order_id: None,
+            symbol: "BTC".to_string(),
+            side: "bid".to_string(),
+            price: "50000".to_string(),
+            initial_amount: "0.01".to_string(),
+            filled_amount: "0".to_string(),
+            cancelled_amount: "0".to_string(),
+            order_type: "limit".to_string(),
+            reduce_only,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_stale_orders_respect_age_and_reduce_only() {
+        let now_ms = 1_000_000;
+        let orders = vec![
+            order(1, now_ms - 301_000, false),
+            order(2, now_ms - 299_000, false),
+            order(3, now_ms - 600_000, true),
+        ];
+        let config = OrderTimeoutConfig { enabled: true, ..OrderTimeoutConfig::default() };
+        let ids = |stale: Vec<&OpenOrder>| stale.iter().map(|o| o.order_id).collect::<Vec<_>>();
+        assert_eq!(ids(config.stale_orders(&orders, &[], now_ms)), vec![1, 3]);
+
+        let keep = OrderTimeoutConfig { keep_reduce_only: true, ..config.clone() };
+        assert_eq!(ids(keep.stale_orders(&orders, &[], now_ms)), vec![1]);
+
+        // The held position's TP/SL rests as long as the position is held
+        let held = Uuid::new_v4();
+        let mut protective = order(4, now_ms - 600_000, true);
+        protective.client_order_id = Some(client_order_id(&held, OrderLeg::PacificaStopLoss, 1).to_string());
+        let orders = vec![protective];
+        assert!(config.stale_orders(&orders, &[held.to_string()], now_ms).is_empty());
+        assert_eq!(ids(config.stale_orders(&orders, &[], now_ms)), vec![4]);
+    }
+}
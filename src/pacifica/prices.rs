@@ -0,0 +1,41 @@
+use super::subscription::{self, Subscription};
+use super::types::{PriceUpdate, PricesResponse, PricesSubscribe};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// Client for the `prices` channel, which pushes mark price and funding rates for
+/// every market on one subscription
+#[derive(Debug, Clone)]
+pub struct PriceStreamClient {
+    ws_url: String,
+}
+
+impl PriceStreamClient {
+    pub fn new(is_testnet: bool) -> Self {
+        Self { ws_url: subscription::ws_url(is_testnet).to_string() }
+    }
+
+    /// Stream price updates in the background, reconnecting with backoff (1s doubling
+    /// up to 30s) whenever the connection drops. The task ends once the receiver is dropped.
+    pub fn subscribe_reconnecting(self) -> mpsc::UnboundedReceiver<PriceUpdate> {
+        Subscription {
+            ws_url: self.ws_url,
+            channel: "prices",
+            subscribe: PricesSubscribe::new(),
+            parse: parse_prices,
+        }
+        .spawn()
+    }
+}
+
+/// Price updates in a `prices` channel message; other channels yield none
+fn parse_prices(text: &str) -> Vec<PriceUpdate> {
+    match serde_json::from_str::<PricesResponse>(text) {
+        Ok(response) if response.channel == "prices" => response.data,
+        Ok(_) => Vec::new(),
+        Err(_) => {
+            debug!("[PACIFICA] Ignoring non-prices message: {}", text);
+            Vec::new()
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Reconnecting subscription to one Pacifica WebSocket channel
+//!
+//! Shared by the push streams (`prices`, `account_info`): connect, send the channel's
+//! subscribe message, ping to keep the connection alive and forward what `parse`
+//! finds in each message, reconnecting with `websocket::Backoff` whenever it drops.
+use super::types::PingMessage;
+use crate::metrics;
+use crate::websocket::Backoff;
+use anyhow::{anyhow, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::{interval, sleep, Duration};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tracing::{info, warn};
+
+const MAINNET_WS_URL: &str = "wss://ws.pacifica.fi/ws";
+const TESTNET_WS_URL: &str = "wss://test-ws.pacifica.fi/ws";
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+pub(super) fn ws_url(is_testnet: bool) -> &'static str {
+    if is_testnet { TESTNET_WS_URL } else { MAINNET_WS_URL }
+}
+
+/// One channel: where to connect, what to send, and how to read its messages
+pub(super) struct Subscription<T, S> {
+    pub ws_url: String,
+    /// Channel name, for logs and metrics
+    pub channel: &'static str,
+    pub subscribe: S,
+    /// Items carried by one text message; other channels' messages yield none
+    pub parse: fn(&str) -> Vec<T>,
+}
+
+impl<T: Send + 'static, S: Serialize + Send + Sync + 'static> Subscription<T, S> {
+    /// Stream the channel in the background, reconnecting whenever the connection drops.
+    /// The task ends once the receiver is dropped.
+    pub fn spawn(self) -> mpsc::UnboundedReceiver<T> {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let endpoint = format!("pacifica WS connect ({})", self.channel);
+            let mut backoff = Backoff::default();
+            while !tx.is_closed() {
+                let reason = match metrics::observe(&endpoint, connect_async(&self.ws_url)).await {
+                    Ok((ws_stream, _)) => {
+                        backoff.reset();
+                        match self.run(ws_stream, &tx).await {
+                            Ok(()) => break,
+                            Err(e) => e.to_string(),
+                        }
+                    }
+                    Err(e) => e.to_string(),
+                };
+                if tx.is_closed() {
+                    break;
+                }
+                let delay = backoff.next_delay();
+                warn!("[PACIFICA] {} stream down ({}), reconnecting in {:?}", self.channel, reason, delay);
+                sleep(delay).await;
+            }
+        });
+
+        rx
+    }
+
+    /// Subscribe and forward until the connection fails, or the receiver is dropped (`Ok`)
+    async fn run(&self, ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>, tx: &mpsc::UnboundedSender<T>) -> Result<()> {
+        let (mut write, mut read) = ws_stream.split();
+        write.send(Message::Text(serde_json::to_string(&self.subscribe)?)).await?;
+        info!("[PACIFICA] Subscribed to {}", self.channel);
+
+        let mut ping_interval = interval(PING_INTERVAL);
+        ping_interval.tick().await; // Skip first immediate tick
+
+        loop {
+            tokio::select! {
+                msg = read.next() => {
+                    match msg {
+                        Some(Ok(Message::Text(text))) => {
+                            for item in (self.parse)(&text) {
+                                if tx.send(item).is_err() {
+                                    return Ok(());
+                                }
+                            }
+                        }
+                        Some(Ok(Message::Ping(data))) => {
+                            write.send(Message::Pong(data)).await?;
+                        }
+                        Some(Ok(Message::Close(_))) | None => {
+                            return Err(anyhow!("stream closed"));
+                        }
+                        Some(Err(e)) => {
+                            return Err(anyhow!("WebSocket error: {}", e));
+                        }
+                        _ => {}
+                    }
+                }
+
+                _ = ping_interval.tick() => {
+                    if tx.is_closed() {
+                        return Ok(());
+                    }
+                    write.send(Message::Text(serde_json::to_string(&PingMessage::new())?)).await?;
+                }
+            }
+        }
+    }
+}
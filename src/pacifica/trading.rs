@@ -1,17 +1,15 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use ed25519_dalek::{Signer, SigningKey};
 use reqwest::StatusCode;
 use serde::Deserialize;
 use serde_json::json;
 use std::collections::HashMap;
-use std::time::Duration;
-use tokio::time::sleep;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 const MAINNET_REST_URL: &str = "https://api.pacifica.fi";
-const MARKET_INFO_MAX_RETRIES: u32 = 5;
-const MARKET_INFO_BASE_BACKOFF_MS: u64 = 500;
+const TESTNET_REST_URL: &str = "https://test-api.pacifica.fi";
+const FUNDING_HISTORY_PAGE_LIMIT: u32 = 100;
 
 /// Credentials for Pacifica trading
 #[derive(Debug, Clone)]
@@ -39,9 +37,74 @@ impl PacificaCredentials {
             private_key,
         })
     }
+
+    /// From `PACIFICA_CREDENTIALS_FILE` when set, the file re-read for rotated keys,
+    /// else from the environment
+    pub fn load() -> Result<Self> {
+        match agent::reloadable_credentials_path() {
+            Some(path) => Self::from_env_file(&path),
+            None => Self::from_env(),
+        }
+    }
+
+    /// Account address only, for public reads (positions, funding history). Anything
+    /// that needs a signature fails with these credentials.
+    pub fn read_only(account: impl Into<String>) -> Self {
+        Self {
+            account: account.into(),
+            agent_wallet: String::new(),
+            private_key: String::new(),
+        }
+    }
+
+    /// Whether an agent key is present to sign requests with
+    pub fn can_sign(&self) -> bool {
+        !self.private_key.is_empty()
+    }
+
+    /// Load credentials from a dotenv-style file, falling back to the environment for
+    /// keys the file lacks. Used to pick up a rotated agent key while running.
+    pub fn from_env_file(path: &str) -> Result<Self> {
+        let values = read_env_file(path)?;
+        let var = |key: &str| {
+            values
+                .get(key)
+                .cloned()
+                .or_else(|| std::env::var(key).ok())
+                .with_context(|| format!("{} not found in {} or environment", key, path))
+        };
+        let credentials = Self {
+            account: var("SOL_WALLET")?,
+            agent_wallet: var("API_PUBLIC")?,
+            private_key: var("API_PRIVATE")?,
+        };
+        credentials.check_agent_key()?;
+        Ok(credentials)
+    }
+
+    /// Fail when API_PRIVATE is not the key of API_PUBLIC (e.g. a half-edited file)
+    pub fn check_agent_key(&self) -> Result<()> {
+        let derived = AgentKeypair::from_private_key(&self.private_key)?.public_key;
+        if derived != self.agent_wallet {
+            anyhow::bail!("API_PRIVATE belongs to agent wallet {}, not API_PUBLIC {}", derived, self.agent_wallet);
+        }
+        Ok(())
+    }
+
+    /// Same account and agent key
+    pub fn same_as(&self, other: &PacificaCredentials) -> bool {
+        self.account == other.account && self.agent_wallet == other.agent_wallet && self.private_key == other.private_key
+    }
 }
 
-use super::types::{PacificaFundingRate, PacificaMarketInfo, PacificaPosition};
+use crate::numeric::{decimal_from_f64, parse_decimal, parse_decimal_or_zero, round_to_step, Decimal};
+use crate::types::BidAsk;
+use crate::api_capture;
+use crate::error::BotError;
+use crate::retry::RetryPolicy;
+use super::agent::{self, read_env_file, signing_seed, AgentKeypair};
+use super::clock;
+use super::types::{ConfirmedFill, PacificaCandle, PacificaFundingRate, PacificaMarketInfo, PacificaPosition};
 
 /// Order side
 #[derive(Debug, Clone, Copy)]
@@ -77,6 +140,126 @@ pub struct OrderData {
     pub symbol: Option<String>,
 }
 
+/// Time in force for limit orders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeInForce {
+    /// Good till cancelled
+    Gtc,
+    /// Immediate or cancel
+    Ioc,
+    /// Add liquidity only (post-only): rejected if it would take
+    Alo,
+}
+
+impl TimeInForce {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeInForce::Gtc => "GTC",
+            TimeInForce::Ioc => "IOC",
+            TimeInForce::Alo => "ALO",
+        }
+    }
+}
+
+/// One leg of a position take-profit/stop-loss (see `PacificaTrading::set_position_tpsl`)
+#[derive(Debug, Clone)]
+pub struct StopLeg {
+    pub stop_price: f64,
+    /// Limit price once triggered; market execution when `None`
+    pub limit_price: Option<f64>,
+    pub client_order_id: String,
+}
+
+/// Resting order from the orders endpoint
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenOrder {
+    pub order_id: u64,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+    pub symbol: String,
+    pub side: String,             // "bid" or "ask"
+    pub price: String,
+    pub initial_amount: String,
+    #[serde(default)]
+    pub filled_amount: String,
+    #[serde(default)]
+    pub cancelled_amount: String,
+    #[serde(default)]
+    pub order_type: String,       // "limit", "market", "stop_limit", ...
+    #[serde(default)]
+    pub reduce_only: bool,
+    #[serde(default)]
+    pub created_at: u64,          // Timestamp in milliseconds
+}
+
+impl OpenOrder {
+    pub fn price_f64(&self) -> f64 {
+        self.price.parse().unwrap_or(0.0)
+    }
+
+    /// Size still resting on the book
+    pub fn remaining_amount(&self) -> f64 {
+        let parse = |s: &str| s.parse::<f64>().unwrap_or(0.0);
+        (parse(&self.initial_amount) - parse(&self.filled_amount) - parse(&self.cancelled_amount)).max(0.0)
+    }
+
+    pub fn is_buy(&self) -> bool {
+        self.side == "bid"
+    }
+}
+
+/// Open orders response from API
+#[derive(Debug, Deserialize)]
+pub struct OpenOrdersResponse {
+    pub success: bool,
+    pub data: Option<Vec<OpenOrder>>,
+    pub error: Option<String>,
+}
+
+/// Order from the account order history, including finished ones
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderHistoryItem {
+    pub order_id: u64,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+    pub symbol: String,
+    #[serde(default)]
+    pub filled_amount: String,
+    #[serde(default)]
+    pub average_filled_price: Option<String>,
+    #[serde(default)]
+    pub order_status: String,     // "open", "partially_filled", "filled", "cancelled", "rejected"
+    #[serde(default)]
+    pub created_at: u64,          // Timestamp in milliseconds
+}
+
+impl OrderHistoryItem {
+    pub fn filled_amount_f64(&self) -> f64 {
+        self.filled_amount.parse().unwrap_or(0.0)
+    }
+
+    /// Whether the order can no longer fill
+    pub fn is_final(&self) -> bool {
+        matches!(self.order_status.as_str(), "filled" | "cancelled" | "rejected")
+    }
+
+    /// Outcome of a final order, in the shape order updates report it
+    pub fn confirmed_fill(&self) -> Option<ConfirmedFill> {
+        self.is_final().then(|| ConfirmedFill {
+            filled_amount: self.filled_amount_f64(),
+            avg_price: self.average_filled_price.as_deref().and_then(|p| p.parse().ok()).filter(|p: &f64| *p > 0.0),
+        })
+    }
+}
+
+/// Order history response from API
+#[derive(Debug, Deserialize)]
+pub struct OrderHistoryResponse {
+    pub success: bool,
+    pub data: Option<Vec<OrderHistoryItem>>,
+    pub error: Option<String>,
+}
+
 /// Orderbook level from REST API
 #[derive(Debug, Clone, Deserialize)]
 pub struct RestBookLevel {
@@ -118,6 +301,20 @@ pub struct OrderbookSnapshot {
     pub asks: Vec<OrderbookLevel>,
 }
 
+impl OrderbookSnapshot {
+    /// Best bid and ask of the snapshot
+    pub fn top_of_book(&self, symbol: &str) -> BidAsk {
+        BidAsk {
+            market: symbol.to_string(),
+            best_bid: self.bids.first().map(|l| l.price.clone()),
+            best_ask: self.asks.first().map(|l| l.price.clone()),
+            bid_quantity: self.bids.first().map(|l| l.size.clone()),
+            ask_quantity: self.asks.first().map(|l| l.size.clone()),
+            timestamp: chrono::Utc::now().timestamp_millis() as u64,
+        }
+    }
+}
+
 /// Trade history item from positions/history endpoint
 #[derive(Debug, Clone, Deserialize)]
 pub struct TradeHistoryItem {
@@ -147,104 +344,207 @@ pub struct TradeHistoryResponse {
     pub code: Option<String>,
 }
 
+/// Funding payment item from funding/history endpoint
+#[derive(Debug, Clone, Deserialize)]
+pub struct FundingHistoryItem {
+    pub history_id: u64,
+    pub symbol: String,
+    pub side: String,             // "bid" (long) or "ask" (short)
+    pub amount: String,           // Position size in token denomination
+    pub payout: String,           // USD paid out (positive = received, negative = paid)
+    pub rate: String,             // Funding rate applied
+    pub created_at: u64,          // Timestamp in milliseconds
+}
+
+impl FundingHistoryItem {
+    pub fn payout_f64(&self) -> f64 {
+        self.payout.parse().unwrap_or(0.0)
+    }
+
+    /// Payout as exact decimal, for summing many small payments
+    pub fn payout_decimal(&self) -> Decimal {
+        parse_decimal_or_zero(&self.payout)
+    }
+}
+
+/// Funding history response from API
+#[derive(Debug, Deserialize)]
+pub struct FundingHistoryResponse {
+    pub success: bool,
+    pub data: Option<Vec<FundingHistoryItem>>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+/// Kline response from API
+#[derive(Debug, Deserialize)]
+struct KlineResponse {
+    data: Option<Vec<KlineCandle>>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Candle as sent by the API, with decimal strings
+#[derive(Debug, Deserialize)]
+struct KlineCandle {
+    #[serde(rename = "t")]
+    open_time: u64,
+    #[serde(rename = "T", default)]
+    close_time: u64,
+    #[serde(rename = "o")]
+    open: String,
+    #[serde(rename = "h")]
+    high: String,
+    #[serde(rename = "l")]
+    low: String,
+    #[serde(rename = "c")]
+    close: String,
+    #[serde(rename = "v")]
+    volume: String,
+    #[serde(rename = "n", default)]
+    trades: u64,
+}
+
+impl KlineCandle {
+    fn parse(&self) -> Option<PacificaCandle> {
+        Some(PacificaCandle {
+            open_time: self.open_time,
+            close_time: self.close_time,
+            open: self.open.parse().ok()?,
+            high: self.high.parse().ok()?,
+            low: self.low.parse().ok()?,
+            close: self.close.parse().ok()?,
+            volume: self.volume.parse().ok()?,
+            trades: self.trades,
+        })
+    }
+}
+
 /// Pacifica trading client
 pub struct PacificaTrading {
     credentials: PacificaCredentials,
     rest_url: String,
     client: reqwest::Client,
     market_info_cache: Option<HashMap<String, PacificaMarketInfo>>,
+    /// Retries of the market info request
+    retry: RetryPolicy,
 }
 
 impl PacificaTrading {
-    /// Create a new trading client (mainnet only)
+    /// Create a new trading client for mainnet
     pub fn new(credentials: PacificaCredentials) -> Self {
+        Self::with_rest_url(credentials, MAINNET_REST_URL)
+    }
+
+    /// Create a new trading client for testnet
+    pub fn new_testnet(credentials: PacificaCredentials) -> Self {
+        Self::with_rest_url(credentials, TESTNET_REST_URL)
+    }
+
+    fn with_rest_url(credentials: PacificaCredentials, rest_url: &str) -> Self {
         Self {
             credentials,
-            rest_url: MAINNET_REST_URL.to_string(),
+            rest_url: rest_url.to_string(),
             client: reqwest::Client::new(),
             market_info_cache: None,
+            retry: RetryPolicy::default(),
         }
     }
 
-    /// Fetch market info for all symbols
-    pub async fn get_market_info(&mut self) -> Result<&HashMap<String, PacificaMarketInfo>> {
-        #[derive(Deserialize)]
-        struct ApiResponse {
-            data: Vec<PacificaMarketInfo>,
-        }
-
-        if self.market_info_cache.is_none() {
-            let url = format!("{}/api/v1/info", self.rest_url);
-            let mut last_error = None;
+    /// Retry the market info request per `retry` instead of the default policy
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
 
-            for attempt in 1..=MARKET_INFO_MAX_RETRIES {
-                if attempt > 1 {
-                    let backoff_ms = MARKET_INFO_BASE_BACKOFF_MS * 2u64.pow(attempt - 2);
-                    sleep(Duration::from_millis(backoff_ms)).await;
-                }
+    /// REST base URL (without the /api/v1 prefix)
+    pub fn rest_url(&self) -> &str {
+        &self.rest_url
+    }
 
-                let response = match self.client.get(&url).send().await {
-                    Ok(resp) => resp,
-                    Err(e) => {
-                        let err = anyhow!("[PACIFICA] Market info request attempt {}/{} failed: {}", attempt, MARKET_INFO_MAX_RETRIES, e);
-                        warn!("{}", err);
-                        last_error = Some(err);
-                        continue;
-                    }
-                };
+    /// HTTP client for requests this client has no method for, sharing its connections
+    pub fn http_client(&self) -> &reqwest::Client {
+        &self.client
+    }
 
-                let status = response.status();
-                if status.is_success() {
-                    let api_response: ApiResponse = response.json().await?;
+    /// Whether this client trades on testnet
+    pub fn is_testnet(&self) -> bool {
+        self.rest_url == TESTNET_REST_URL
+    }
 
-                    let mut cache = HashMap::new();
-                    for info in api_response.data {
-                        cache.insert(info.symbol.clone(), info);
-                    }
+    /// Estimate the host clock offset against Pacifica and apply it to all signed timestamps.
+    /// Returns the applied offset in ms (server - local).
+    pub async fn sync_clock(&self) -> Result<i64> {
+        let url = format!("{}/api/v1/info", self.rest_url);
+        let offset = clock::measure_offset_ms(&self.client, &url).await?;
+        let previous = clock::offset_ms();
+        clock::set_offset_ms(offset);
+
+        if offset != previous {
+            if offset.abs() >= clock::CLOCK_SKEW_WARN_MS {
+                warn!("[PACIFICA] Host clock is {}ms {} server time; correcting signed timestamps",
+                    offset.abs(), if offset > 0 { "behind" } else { "ahead of" });
+            } else {
+                info!("[PACIFICA] Host clock in sync with server (offset reset from {}ms)", previous);
+            }
+        }
+        Ok(offset)
+    }
 
-                    info!("[PACIFICA] Cached market info for {} symbols", cache.len());
-                    self.market_info_cache = Some(cache);
-                    break;
-                }
+    /// Fetch market info for all symbols, cached after the first call (lot and tick
+    /// sizes; funding rates in it go stale, see `get_current_funding_rate`)
+    pub async fn get_market_info(&mut self) -> Result<&HashMap<String, PacificaMarketInfo>> {
+        if self.market_info_cache.is_none() {
+            let cache = self.fetch_market_info().await?;
+            info!("[PACIFICA] Cached market info for {} symbols", cache.len());
+            self.market_info_cache = Some(cache);
+        }
+        Ok(self.market_info_cache.as_ref().unwrap())
+    }
 
-                if status == StatusCode::TOO_MANY_REQUESTS {
-                    let retry_after_ms = response
-                        .headers()
-                        .get("retry-after")
-                        .and_then(|v| v.to_str().ok())
-                        .and_then(|s| s.parse::<u64>().ok())
-                        .map(|secs| secs * 1000);
-
-                    let delay_ms = retry_after_ms.unwrap_or_else(|| MARKET_INFO_BASE_BACKOFF_MS * 2u64.pow(attempt - 1));
-                    warn!(
-                        "[PACIFICA] Market info rate limited (429) attempt {}/{}. Waiting {}ms before retry.",
-                        attempt,
-                        MARKET_INFO_MAX_RETRIES,
-                        delay_ms
-                    );
-                    last_error = Some(anyhow!("[PACIFICA] Market info rate limited (429)"));
-                    sleep(Duration::from_millis(delay_ms)).await;
-                    continue;
-                }
+    /// Fetch market info for all symbols, bypassing the cache
+    async fn fetch_market_info(&self) -> Result<HashMap<String, PacificaMarketInfo>> {
+        let markets = self
+            .retry
+            .run("[PACIFICA] Market info request", || async { self.request_market_info().await.map_err(BotError::from) })
+            .await?;
+        Ok(markets)
+    }
 
-                let error_text = response.text().await.unwrap_or_default();
-                let err = anyhow!(
-                    "[PACIFICA] Market info request failed (attempt {}/{}): {} - {}",
-                    attempt,
-                    MARKET_INFO_MAX_RETRIES,
-                    status,
-                    error_text
-                );
-                warn!("{}", err);
-                last_error = Some(err);
-            }
+    async fn request_market_info(&self) -> Result<HashMap<String, PacificaMarketInfo>> {
+        #[derive(Deserialize)]
+        struct ApiResponse {
+            data: Vec<PacificaMarketInfo>,
+        }
 
-            if self.market_info_cache.is_none() {
-                return Err(last_error.unwrap_or_else(|| anyhow!("[PACIFICA] Market info request failed after retries")));
-            }
+        let url = format!("{}/api/v1/info", self.rest_url);
+        let response = api_capture::observe_http("pacifica GET /api/v1/info", self.client.get(&url).send()).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("[PACIFICA] Market info request failed: {} - {}", status, error_text);
         }
 
-        Ok(self.market_info_cache.as_ref().unwrap())
+        let api_response: ApiResponse = response.json().await?;
+        Ok(api_response.data.into_iter().map(|info| (info.symbol.clone(), info)).collect())
+    }
+
+    /// Funding rate for `symbol` from freshly fetched market info, for long-lived clients
+    /// whose cached market info holds the rates of its first fetch
+    pub async fn get_current_funding_rate(&self, symbol: &str) -> Result<PacificaFundingRate> {
+        let market_info = self.fetch_market_info().await?;
+        let info = market_info
+            .get(symbol)
+            .context(format!("Symbol {} not found in market info", symbol))?;
+
+        Ok(PacificaFundingRate::from_market_info(
+            info.symbol.clone(),
+            info.funding_rate.clone(),
+            info.next_funding_rate.clone(),
+        ))
     }
 
     /// Get funding rate for a specific symbol
@@ -294,6 +594,45 @@ impl PacificaTrading {
         Ok(funding_rates)
     }
 
+    /// Fetch candles of a market, oldest first
+    ///
+    /// # Arguments
+    /// * `symbol` - Trading symbol (e.g., "SOL", "BTC")
+    /// * `interval` - Candle interval ("1m", "5m", "15m", "1h", "4h", "1d", ...)
+    /// * `start_ms` / `end_ms` - Time range in milliseconds
+    ///
+    /// # Returns
+    /// Candles in the range; candles with unparseable values are skipped
+    pub async fn get_klines(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<Vec<PacificaCandle>> {
+        let url = format!(
+            "{}/api/v1/kline?symbol={}&interval={}&start_time={}&end_time={}",
+            self.rest_url, symbol, interval, start_ms, end_ms
+        );
+
+        debug!("[PACIFICA] Fetching klines: {}", url);
+
+        let response = api_capture::observe_http("pacifica GET /api/v1/kline", self.client.get(&url).send()).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+            anyhow::bail!("Failed to fetch klines: {} - {}", status, error_text);
+        }
+
+        let kline: KlineResponse = response.json().await?;
+        let candles = kline
+            .data
+            .with_context(|| format!("No kline data in response: {}", kline.error.unwrap_or_default()))?;
+
+        Ok(candles.iter().filter_map(KlineCandle::parse).collect())
+    }
+
     /// Fetch orderbook snapshot via REST API
     ///
     /// # Arguments
@@ -314,7 +653,7 @@ impl PacificaTrading {
 
         debug!("[PACIFICA] Fetching orderbook via REST: {}", url);
 
-        let response = self.client.get(&url).send().await?;
+        let response = api_capture::observe_http("pacifica GET /api/v1/book", self.client.get(&url).send()).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -390,78 +729,98 @@ impl PacificaTrading {
         }
     }
 
-    /// Round price to tick size
-    fn round_to_tick_size(&self, price: f64, tick_size: String) -> Result<f64> {
-        let tick: f64 = tick_size.parse()?;
-        let rounded = (price / tick).round() * tick;
-
-        let decimal_places = if tick_size.contains('.') {
-            tick_size.split('.').nth(1).unwrap().len()
-        } else {
-            0
-        };
+    /// Best bid and ask with their sizes from REST API
+    pub async fn get_bid_ask_rest(&self, symbol: &str, agg_level: u32) -> Result<BidAsk> {
+        let snapshot = self.get_orderbook_rest(symbol, agg_level).await?;
+        Ok(snapshot.top_of_book(symbol))
+    }
 
-        let factor = 10_f64.powi(decimal_places as i32);
-        Ok((rounded * factor).round() / factor)
+    /// Round price to tick size
+    fn round_to_tick_size(&self, price: f64, tick_size: String) -> Result<Decimal> {
+        let tick = parse_decimal(&tick_size)
+            .with_context(|| format!("Invalid tick size: {}", tick_size))?;
+        Ok(round_to_step(decimal_from_f64(price), tick))
     }
 
     /// Round size to lot size
-    fn round_to_lot_size(&self, size: f64, lot_size: String) -> Result<f64> {
-        let lot: f64 = lot_size.parse()?;
-        let rounded = (size / lot).round() * lot;
-
-        let decimal_places = if lot_size.contains('.') {
-            lot_size.split('.').nth(1).unwrap().len()
-        } else {
-            0
-        };
-
-        let factor = 10_f64.powi(decimal_places as i32);
-        Ok((rounded * factor).round() / factor)
+    fn round_to_lot_size(&self, size: f64, lot_size: String) -> Result<Decimal> {
+        let lot = parse_decimal(&lot_size)
+            .with_context(|| format!("Invalid lot size: {}", lot_size))?;
+        Ok(round_to_step(decimal_from_f64(size), lot))
     }
 
     /// Sign a message using Ed25519
     fn sign_message(&self, header: serde_json::Value, payload: serde_json::Value) -> Result<String> {
-        // Construct message: {... header, data: payload}
-        let mut message = serde_json::json!({});
-        if let serde_json::Value::Object(ref mut map) = message {
-            if let serde_json::Value::Object(header_map) = header {
-                for (k, v) in header_map {
-                    map.insert(k, v);
-                }
-            }
-            map.insert("data".to_string(), payload);
-        }
+        sign_with_key(&self.credentials.private_key, header, payload)
+    }
 
-        // Canonicalize JSON (sort keys alphabetically)
-        let canonical = canonicalize_json(&message);
+    /// Credentials requests are currently signed with
+    pub fn credentials(&self) -> &PacificaCredentials {
+        &self.credentials
+    }
 
-        // Decode private key from base58
-        let private_key_bytes = bs58::decode(&self.credentials.private_key)
-            .into_vec()
-            .context("Failed to decode private key")?;
-
-        // Solana/Pacifica private keys are 64 bytes (32 bytes seed + 32 bytes public key)
-        // Ed25519 SigningKey needs only the first 32 bytes (the seed)
-        if private_key_bytes.len() != 64 {
-            anyhow::bail!(
-                "Invalid private key length: expected 64 bytes, got {}",
-                private_key_bytes.len()
-            );
+    /// Sign subsequent requests with other credentials (e.g. a rotated agent key)
+    pub fn set_credentials(&mut self, credentials: PacificaCredentials) {
+        self.credentials = credentials;
+    }
+
+    /// Bind `agent_wallet` to the account. Pacifica only accepts binds signed by the main
+    /// account key (the SOL_WALLET keypair), passed as `main_private_key`.
+    pub async fn bind_agent_wallet(&self, main_private_key: &str, agent_wallet: &str) -> Result<()> {
+        let main_public = AgentKeypair::from_private_key(main_private_key)?.public_key;
+        if main_public != self.credentials.account {
+            anyhow::bail!("Main key belongs to {}, not SOL_WALLET {}", main_public, self.credentials.account);
         }
 
-        let seed_bytes: [u8; 32] = private_key_bytes[0..32]
-            .try_into()
-            .context("Failed to extract seed from private key")?;
+        let timestamp = clock::now_ms();
+        let expiry_window = 5000;
+        let header = json!({
+            "type": "bind_agent_wallet",
+            "timestamp": timestamp,
+            "expiry_window": expiry_window
+        });
+        let payload = json!({ "agent_wallet": agent_wallet });
+        let signature = sign_with_key(main_private_key, header, payload)?;
 
-        // Create signing key from seed
-        let signing_key = SigningKey::from_bytes(&seed_bytes);
+        let request_body = json!({
+            "account": self.credentials.account,
+            "signature": signature,
+            "timestamp": timestamp,
+            "expiry_window": expiry_window,
+            "agent_wallet": agent_wallet
+        });
+        let url = format!("{}/api/v1/agent/bind", self.rest_url);
+        let request = self.client.post(&url).json(&request_body).send();
+        let response = api_capture::observe_http_json("pacifica POST /api/v1/agent/bind", &request_body, request).await?;
 
-        // Sign the message
-        let signature = signing_key.sign(canonical.as_bytes());
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            anyhow::bail!("Agent wallet bind failed: {} - {}", status, body);
+        }
+        let parsed: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
+        if parsed.get("success").and_then(|s| s.as_bool()) == Some(false) {
+            anyhow::bail!("Agent wallet bind failed: {}", body);
+        }
+        info!("[PACIFICA] Agent wallet {} bound to {}", agent_wallet, self.credentials.account);
+        Ok(())
+    }
 
-        // Encode signature as base58
-        Ok(bs58::encode(signature.to_bytes()).into_string())
+    /// Rotate the agent key without downtime: generate a keypair, bind it (requests keep
+    /// being signed with the current key meanwhile), then switch this client to it.
+    /// Returns the new credentials to persist; the old agent stays bound until revoked.
+    pub async fn rotate_agent_key(&mut self, main_private_key: &str) -> Result<PacificaCredentials> {
+        let keypair = AgentKeypair::generate()?;
+        self.bind_agent_wallet(main_private_key, &keypair.public_key).await?;
+
+        let rotated = PacificaCredentials {
+            account: self.credentials.account.clone(),
+            agent_wallet: keypair.public_key,
+            private_key: keypair.private_key,
+        };
+        info!("[PACIFICA] Switching from agent wallet {} to {}", self.credentials.agent_wallet, rotated.agent_wallet);
+        self.credentials = rotated.clone();
+        Ok(rotated)
     }
 
     /// Place a limit order
@@ -474,6 +833,7 @@ impl PacificaTrading {
     /// * `mid_price_offset_pct` - Offset from mid price in percentage (default 1.0%)
     /// * `current_bid` - Current best bid price (required if price is None)
     /// * `current_ask` - Current best ask price (required if price is None)
+    #[allow(clippy::too_many_arguments)]
     pub async fn place_limit_order(
         &mut self,
         symbol: &str,
@@ -484,15 +844,6 @@ impl PacificaTrading {
         current_bid: Option<f64>,
         current_ask: Option<f64>,
     ) -> Result<OrderData> {
-        // Get market info and clone the strings we need
-        let market_info = self.get_market_info().await?;
-        let symbol_info = market_info
-            .get(symbol)
-            .context(format!("Market info not found for {}", symbol))?;
-
-        let tick_size = symbol_info.tick_size.clone();
-        let lot_size = symbol_info.lot_size.clone();
-
         // Calculate price
         let order_price = if let Some(p) = price {
             p
@@ -508,12 +859,56 @@ impl PacificaTrading {
             }
         };
 
+        let client_order_id = Uuid::new_v4().to_string();
+        self.place_limit_order_with_options(
+            symbol,
+            side,
+            size,
+            order_price,
+            TimeInForce::Alo,
+            false,
+            &client_order_id,
+        )
+        .await
+    }
+
+    /// Place a limit order with explicit time-in-force, reduce-only flag and client order ID
+    ///
+    /// # Arguments
+    /// * `symbol` - Trading symbol (e.g., "SOL", "BTC")
+    /// * `side` - Order side (Buy or Sell)
+    /// * `size` - Order size, rounded to the market lot size
+    /// * `price` - Limit price, rounded to the market tick size
+    /// * `tif` - Time in force (GTC, IOC or ALO/post-only)
+    /// * `reduce_only` - If true, order can only reduce an existing position
+    /// * `client_order_id` - Client order ID (UUID string), usable for cancel/modify
+    #[allow(clippy::too_many_arguments)]
+    pub async fn place_limit_order_with_options(
+        &mut self,
+        symbol: &str,
+        side: OrderSide,
+        size: f64,
+        price: f64,
+        tif: TimeInForce,
+        reduce_only: bool,
+        client_order_id: &str,
+    ) -> Result<OrderData> {
+        // Get market info and clone the strings we need
+        let market_info = self.get_market_info().await?;
+        let symbol_info = market_info
+            .get(symbol)
+            .context(format!("Market info not found for {}", symbol))?;
+
+        let tick_size = symbol_info.tick_size.clone();
+        let lot_size = symbol_info.lot_size.clone();
+
         // Round to tick and lot size
-        let rounded_price = self.round_to_tick_size(order_price, tick_size.clone())?;
+        let rounded_price = self.round_to_tick_size(price, tick_size.clone())?;
         let rounded_size = self.round_to_lot_size(size, lot_size.clone())?;
 
         info!(
-            "[PACIFICA] Placing {} order: {} {} @ ${} (tick: {}, lot: {})",
+            "[PACIFICA] Placing {} {} order: {} {} @ ${} (tick: {}, lot: {})",
+            tif.as_str(),
             match side { OrderSide::Buy => "BUY", OrderSide::Sell => "SELL" },
             rounded_size,
             symbol,
@@ -522,11 +917,8 @@ impl PacificaTrading {
             lot_size
         );
 
-        // Generate client order ID
-        let client_order_id = Uuid::new_v4().to_string();
-
         // Build signature
-        let timestamp = chrono::Utc::now().timestamp_millis();
+        let timestamp = clock::now_ms();
         let expiry_window = 5000; // 5 seconds
 
         let header = json!({
@@ -540,8 +932,8 @@ impl PacificaTrading {
             "price": rounded_price.to_string(),
             "amount": rounded_size.to_string(),
             "side": side.as_str(),
-            "tif": "ALO",  // Add Liquidity Only (post-only)
-            "reduce_only": false,
+            "tif": tif.as_str(),
+            "reduce_only": reduce_only,
             "client_order_id": client_order_id
         });
 
@@ -557,23 +949,23 @@ impl PacificaTrading {
             "price": rounded_price.to_string(),
             "amount": rounded_size.to_string(),
             "side": side.as_str(),
-            "tif": "ALO",
-            "reduce_only": false,
+            "tif": tif.as_str(),
+            "reduce_only": reduce_only,
             "client_order_id": client_order_id,
             "agent_wallet": self.credentials.agent_wallet
         });
 
         // Avoid logging full request body (contains signature and keys)
-        debug!("[PACIFICA] Order request prepared (symbol: {}, side: {}, tif: ALO)",
-            symbol, side.as_str());
+        debug!("[PACIFICA] Order request prepared (symbol: {}, side: {}, tif: {})",
+            symbol, side.as_str(), tif.as_str());
 
         // Send request
         let url = format!("{}/api/v1/orders/create", self.rest_url);
-        let response = self.client
+        let request = self.client
             .post(&url)
             .json(&request_body)
-            .send()
-            .await?;
+            .send();
+        let response = api_capture::observe_http_json("pacifica POST /api/v1/orders/create", &request_body, request).await?;
 
         let status = response.status();
         if !status.is_success() {
@@ -604,18 +996,183 @@ impl PacificaTrading {
             .or(order_data.i)
             .context("No order ID in response")?;
 
-        info!(
-            "[PACIFICA] Order placed successfully: ID={}, ClientID={}",
-            order_id,
-            client_order_id
-        );
+        info!(
+            "[PACIFICA] Order placed successfully: ID={}, ClientID={}",
+            order_id,
+            client_order_id
+        );
+
+        Ok(OrderData {
+            order_id: Some(order_id),
+            i: Some(order_id),
+            client_order_id: Some(client_order_id.to_string()),
+            symbol: Some(symbol.to_string()),
+        })
+    }
+
+    /// Modify the price and size of a resting order
+    ///
+    /// # Arguments
+    /// * `symbol` - Trading symbol of the order
+    /// * `client_order_id` - Client order ID the order was placed with
+    /// * `price` - New limit price, rounded to the market tick size
+    /// * `size` - New order size, rounded to the market lot size
+    pub async fn modify_order(
+        &mut self,
+        symbol: &str,
+        client_order_id: &str,
+        price: f64,
+        size: f64,
+    ) -> Result<OrderData> {
+        let market_info = self.get_market_info().await?;
+        let symbol_info = market_info
+            .get(symbol)
+            .context(format!("Market info not found for {}", symbol))?;
+
+        let tick_size = symbol_info.tick_size.clone();
+        let lot_size = symbol_info.lot_size.clone();
+        let rounded_price = self.round_to_tick_size(price, tick_size)?;
+        let rounded_size = self.round_to_lot_size(size, lot_size)?;
+
+        info!(
+            "[PACIFICA] Modifying order {} on {}: {} @ ${}",
+            client_order_id, symbol, rounded_size, rounded_price
+        );
+
+        // Build signature
+        let timestamp = clock::now_ms();
+        let expiry_window = 5000;
+
+        let header = json!({
+            "type": "edit_order",
+            "timestamp": timestamp,
+            "expiry_window": expiry_window
+        });
+
+        let payload = json!({
+            "symbol": symbol,
+            "price": rounded_price.to_string(),
+            "amount": rounded_size.to_string(),
+            "client_order_id": client_order_id
+        });
+
+        let signature = self.sign_message(header, payload.clone())?;
+
+        // Build request
+        let request_body = json!({
+            "account": self.credentials.account,
+            "signature": signature,
+            "timestamp": timestamp,
+            "expiry_window": expiry_window,
+            "symbol": symbol,
+            "price": rounded_price.to_string(),
+            "amount": rounded_size.to_string(),
+            "client_order_id": client_order_id,
+            "agent_wallet": self.credentials.agent_wallet
+        });
+
+        // Send request
+        let url = format!("{}/api/v1/orders/edit", self.rest_url);
+        let request = self.client
+            .post(&url)
+            .json(&request_body)
+            .send();
+        let response = api_capture::observe_http_json("pacifica POST /api/v1/orders/edit", &request_body, request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await?;
+            anyhow::bail!("Order modification failed: {} - {}", status, error_text);
+        }
+
+        let order_response: OrderResponse = response.json().await?;
+        let order_data = order_response.data.unwrap_or(OrderData {
+            order_id: None,
+            i: None,
+            client_order_id: None,
+            symbol: None,
+        });
+        let order_id = order_data.order_id.or(order_data.i);
+
+        info!("[PACIFICA] Order modified successfully: {} (ID: {:?})", client_order_id, order_id);
+
+        Ok(OrderData {
+            order_id,
+            i: order_id,
+            client_order_id: Some(client_order_id.to_string()),
+            symbol: Some(symbol.to_string()),
+        })
+    }
+
+    /// Get resting orders for the account
+    ///
+    /// # Arguments
+    /// * `symbol` - Optional symbol filter (e.g., "BTC")
+    pub async fn get_open_orders(&self, symbol: Option<&str>) -> Result<Vec<OpenOrder>> {
+        let url = format!(
+            "{}/api/v1/orders?account={}",
+            self.rest_url, self.credentials.account
+        );
+
+        debug!("[PACIFICA] Fetching open orders from {}", url);
+
+        let request = self.client
+            .get(&url)
+            .send();
+        let response = api_capture::observe_http("pacifica GET /api/v1/orders", request).await
+            .context("Failed to fetch open orders")?;
+
+        let response_text = response.text().await?;
+        let orders_response: OpenOrdersResponse = serde_json::from_str(&response_text)
+            .with_context(|| format!("Failed to parse open orders response: {}", response_text))?;
+
+        if !orders_response.success {
+            let error_msg = orders_response.error.unwrap_or_else(|| "Unknown error".to_string());
+            anyhow::bail!("Get open orders failed: {}", error_msg);
+        }
+
+        let orders: Vec<OpenOrder> = orders_response
+            .data
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|o| symbol.map(|s| o.symbol == s).unwrap_or(true))
+            .collect();
+
+        debug!("[PACIFICA] Retrieved {} open order(s)", orders.len());
+        Ok(orders)
+    }
+
+    /// Get the account's most recent orders, finished ones included
+    ///
+    /// # Arguments
+    /// * `limit` - Optional maximum number of orders to return
+    pub async fn get_order_history(&self, limit: Option<u32>) -> Result<Vec<OrderHistoryItem>> {
+        let mut url = format!(
+            "{}/api/v1/orders/history?account={}",
+            self.rest_url, self.credentials.account
+        );
+        if let Some(lim) = limit {
+            url.push_str(&format!("&limit={}", lim));
+        }
+
+        debug!("[PACIFICA] Fetching order history from {}", url);
+
+        let request = self.client
+            .get(&url)
+            .send();
+        let response = api_capture::observe_http("pacifica GET /api/v1/orders/history", request).await
+            .context("Failed to fetch order history")?;
+
+        let response_text = response.text().await?;
+        let history_response: OrderHistoryResponse = serde_json::from_str(&response_text)
+            .with_context(|| format!("Failed to parse order history response: {}", response_text))?;
+
+        if !history_response.success {
+            let error_msg = history_response.error.unwrap_or_else(|| "Unknown error".to_string());
+            anyhow::bail!("Get order history failed: {}", error_msg);
+        }
 
-        Ok(OrderData {
-            order_id: Some(order_id),
-            i: Some(order_id),
-            client_order_id: Some(client_order_id),
-            symbol: Some(symbol.to_string()),
-        })
+        Ok(history_response.data.unwrap_or_default())
     }
 
     /// Place a market order
@@ -633,6 +1190,24 @@ impl PacificaTrading {
         size: f64,
         slippage_percent: f64,
         reduce_only: bool,
+    ) -> Result<OrderData> {
+        let client_order_id = Uuid::new_v4().to_string();
+        self.place_market_order_with_id(symbol, side, size, slippage_percent, reduce_only, &client_order_id)
+            .await
+    }
+
+    /// Place a market order with a caller-chosen client order ID (UUID string)
+    ///
+    /// Reusing the same ID for a retry lets fills be matched afterwards in
+    /// trade history (see [`filled_amount_for_client_id`]).
+    pub async fn place_market_order_with_id(
+        &mut self,
+        symbol: &str,
+        side: OrderSide,
+        size: f64,
+        slippage_percent: f64,
+        reduce_only: bool,
+        client_order_id: &str,
     ) -> Result<OrderData> {
         // Get market info for lot size validation
         let market_info = self.get_market_info().await?;
@@ -653,11 +1228,8 @@ impl PacificaTrading {
             slippage_percent
         );
 
-        // Generate client order ID
-        let client_order_id = Uuid::new_v4().to_string();
-
         // Build signature
-        let timestamp = chrono::Utc::now().timestamp_millis();
+        let timestamp = clock::now_ms();
         let expiry_window = 5000; // 5 seconds
 
         let header = json!({
@@ -696,11 +1268,11 @@ impl PacificaTrading {
 
         // Send request
         let url = format!("{}/api/v1/orders/create_market", self.rest_url);
-        let response = self.client
+        let request = self.client
             .post(&url)
             .json(&request_body)
-            .send()
-            .await?;
+            .send();
+        let response = api_capture::observe_http_json("pacifica POST /api/v1/orders/create_market", &request_body, request).await?;
 
         let status = response.status();
         if !status.is_success() {
@@ -740,7 +1312,7 @@ impl PacificaTrading {
         Ok(OrderData {
             order_id: Some(order_id),
             i: Some(order_id),
-            client_order_id: Some(client_order_id),
+            client_order_id: Some(client_order_id.to_string()),
             symbol: Some(symbol.to_string()),
         })
     }
@@ -757,6 +1329,18 @@ impl PacificaTrading {
         &mut self,
         position: &PacificaPosition,
         slippage_percent: f64,
+    ) -> Result<OrderData> {
+        let client_order_id = Uuid::new_v4().to_string();
+        self.close_position_with_id(position, slippage_percent, &client_order_id)
+            .await
+    }
+
+    /// Close an existing position with a caller-chosen client order ID
+    pub async fn close_position_with_id(
+        &mut self,
+        position: &PacificaPosition,
+        slippage_percent: f64,
+        client_order_id: &str,
     ) -> Result<OrderData> {
         // Determine opposite side to close the position
         let close_side = if position.is_long() {
@@ -774,30 +1358,120 @@ impl PacificaTrading {
         );
 
         // Place reduce-only market order to close
-        self.place_market_order(
+        self.place_market_order_with_id(
             &position.symbol,
             close_side,
             position_size,
             slippage_percent,
             true, // reduce_only = true
+            client_order_id,
         )
         .await
     }
 
-    /// Cancel an order by client order ID
-    pub async fn cancel_order(
+    /// Rest a take-profit and/or stop-loss on the whole open position in `symbol`
+    ///
+    /// `side` is the side of the closing orders (ask for a long). Each leg triggers at
+    /// its stop price, rounded to the tick size, and executes at market unless it has a
+    /// limit price; it closes the entire position and is cancelled with
+    /// `cancel_stop_order` by its client order ID.
+    pub async fn set_position_tpsl(
+        &mut self,
+        symbol: &str,
+        side: OrderSide,
+        take_profit: Option<&StopLeg>,
+        stop_loss: Option<&StopLeg>,
+    ) -> Result<()> {
+        if take_profit.is_none() && stop_loss.is_none() {
+            anyhow::bail!("Position TP/SL needs a take-profit or a stop-loss");
+        }
+        let tick_size = self
+            .get_market_info()
+            .await?
+            .get(symbol)
+            .context(format!("Market info not found for {}", symbol))?
+            .tick_size
+            .clone();
+        let leg_json = |leg: &StopLeg| -> Result<serde_json::Value> {
+            let mut value = json!({
+                "stop_price": self.round_to_tick_size(leg.stop_price, tick_size.clone())?.to_string(),
+                "client_order_id": leg.client_order_id,
+            });
+            if let Some(limit_price) = leg.limit_price {
+                value["limit_price"] = json!(self.round_to_tick_size(limit_price, tick_size.clone())?.to_string());
+            }
+            Ok(value)
+        };
+
+        info!(
+            "[PACIFICA] Setting position TP/SL on {} ({}): TP {} / SL {}",
+            symbol,
+            side.as_str(),
+            take_profit.map(|l| l.stop_price.to_string()).unwrap_or_else(|| "none".to_string()),
+            stop_loss.map(|l| l.stop_price.to_string()).unwrap_or_else(|| "none".to_string())
+        );
+
+        // Build signature
+        let timestamp = clock::now_ms();
+        let expiry_window = 5000;
+
+        let header = json!({
+            "type": "set_position_tpsl",
+            "timestamp": timestamp,
+            "expiry_window": expiry_window
+        });
+
+        let mut payload = json!({
+            "symbol": symbol,
+            "side": side.as_str()
+        });
+        if let Some(leg) = take_profit {
+            payload["take_profit"] = leg_json(leg)?;
+        }
+        if let Some(leg) = stop_loss {
+            payload["stop_loss"] = leg_json(leg)?;
+        }
+
+        let signature = self.sign_message(header, payload.clone())?;
+
+        // Build request
+        let mut request_body = payload;
+        request_body["account"] = json!(self.credentials.account);
+        request_body["signature"] = json!(signature);
+        request_body["timestamp"] = json!(timestamp);
+        request_body["expiry_window"] = json!(expiry_window);
+        request_body["agent_wallet"] = json!(self.credentials.agent_wallet);
+
+        // Send request
+        let url = format!("{}/api/v1/positions/tpsl", self.rest_url);
+        let request = self.client
+            .post(&url)
+            .json(&request_body)
+            .send();
+        let response = api_capture::observe_http_json("pacifica POST /api/v1/positions/tpsl", &request_body, request).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            anyhow::bail!("Position TP/SL placement failed: {}", error_text);
+        }
+
+        Ok(())
+    }
+
+    /// Cancel a stop order (such as a position TP/SL leg) by client order ID
+    pub async fn cancel_stop_order(
         &self,
         symbol: &str,
         client_order_id: &str,
     ) -> Result<()> {
-        info!("[PACIFICA] Cancelling order: {} (ClientID: {})", symbol, client_order_id);
+        info!("[PACIFICA] Cancelling stop order: {} (ClientID: {})", symbol, client_order_id);
 
         // Build signature
-        let timestamp = chrono::Utc::now().timestamp_millis();
+        let timestamp = clock::now_ms();
         let expiry_window = 5000;
 
         let header = json!({
-            "type": "cancel_order",
+            "type": "cancel_stop_order",
             "timestamp": timestamp,
             "expiry_window": expiry_window
         });
@@ -807,7 +1481,7 @@ impl PacificaTrading {
             "client_order_id": client_order_id
         });
 
-        let signature = self.sign_message(header, payload.clone())?;
+        let signature = self.sign_message(header, payload)?;
 
         // Build request
         let request_body = json!({
@@ -821,19 +1495,90 @@ impl PacificaTrading {
         });
 
         // Send request
-        let url = format!("{}/api/v1/orders/cancel", self.rest_url);
-        let response = self.client
+        let url = format!("{}/api/v1/orders/stop/cancel", self.rest_url);
+        let request = self.client
             .post(&url)
             .json(&request_body)
-            .send()
-            .await?;
+            .send();
+        let response = api_capture::observe_http_json("pacifica POST /api/v1/orders/stop/cancel", &request_body, request).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
-            anyhow::bail!("Order cancellation failed: {}", error_text);
+            anyhow::bail!("Stop order cancellation failed: {}", error_text);
         }
 
+        Ok(())
+    }
+
+    /// Cancel an order by client order ID
+    pub async fn cancel_order(
+        &self,
+        symbol: &str,
+        client_order_id: &str,
+    ) -> Result<()> {
+        info!("[PACIFICA] Cancelling order: {} (ClientID: {})", symbol, client_order_id);
+        self.cancel_order_by(symbol, "client_order_id", json!(client_order_id)).await?;
         info!("[PACIFICA] Order cancelled successfully: {}", client_order_id);
+        Ok(())
+    }
+
+    /// Cancel an order by exchange order ID, for orders placed without a client order ID
+    pub async fn cancel_order_by_id(
+        &self,
+        symbol: &str,
+        order_id: u64,
+    ) -> Result<()> {
+        info!("[PACIFICA] Cancelling order: {} (OrderID: {})", symbol, order_id);
+        self.cancel_order_by(symbol, "order_id", json!(order_id)).await?;
+        info!("[PACIFICA] Order cancelled successfully: {}", order_id);
+        Ok(())
+    }
+
+    /// Signed cancel request identifying the order by `id_field` (`order_id` or `client_order_id`)
+    async fn cancel_order_by(
+        &self,
+        symbol: &str,
+        id_field: &str,
+        id: serde_json::Value,
+    ) -> Result<()> {
+        // Build signature
+        let timestamp = clock::now_ms();
+        let expiry_window = 5000;
+
+        let header = json!({
+            "type": "cancel_order",
+            "timestamp": timestamp,
+            "expiry_window": expiry_window
+        });
+
+        let mut payload = json!({ "symbol": symbol });
+        payload[id_field] = id.clone();
+
+        let signature = self.sign_message(header, payload.clone())?;
+
+        // Build request
+        let mut request_body = json!({
+            "account": self.credentials.account,
+            "signature": signature,
+            "timestamp": timestamp,
+            "expiry_window": expiry_window,
+            "symbol": symbol,
+            "agent_wallet": self.credentials.agent_wallet
+        });
+        request_body[id_field] = id;
+
+        // Send request
+        let url = format!("{}/api/v1/orders/cancel", self.rest_url);
+        let request = self.client
+            .post(&url)
+            .json(&request_body)
+            .send();
+        let response = api_capture::observe_http_json("pacifica POST /api/v1/orders/cancel", &request_body, request).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            anyhow::bail!("Order cancellation failed: {}", error_text);
+        }
 
         Ok(())
     }
@@ -862,7 +1607,7 @@ impl PacificaTrading {
         );
 
         // Build signature
-        let timestamp = chrono::Utc::now().timestamp_millis();
+        let timestamp = clock::now_ms();
         let expiry_window = 5000;
 
         let header = json!({
@@ -901,11 +1646,11 @@ impl PacificaTrading {
 
         // Send request
         let url = format!("{}/api/v1/orders/cancel_all", self.rest_url);
-        let response = self.client
+        let request = self.client
             .post(&url)
             .json(&request_body)
-            .send()
-            .await?;
+            .send();
+        let response = api_capture::observe_http_json("pacifica POST /api/v1/orders/cancel_all", &request_body, request).await?;
 
         // Get response text for debugging
         let response_text = response.text().await?;
@@ -985,10 +1730,10 @@ impl PacificaTrading {
 
         info!("[PACIFICA] Fetching trade history from {}", url);
 
-        let response = self.client
+        let request = self.client
             .get(&url)
-            .send()
-            .await
+            .send();
+        let response = api_capture::observe_http("pacifica GET /api/v1/positions/history", request).await
             .context("Failed to fetch trade history")?;
 
         let response_text = response.text().await?;
@@ -1013,6 +1758,72 @@ impl PacificaTrading {
         Ok(history_response.data.unwrap_or_default())
     }
 
+    /// Get funding payments settled on the account
+    ///
+    /// Follows `next_cursor` until the history is exhausted or entries fall
+    /// before `start_time` (the endpoint returns newest first).
+    ///
+    /// # Arguments
+    /// * `symbol` - Optional symbol filter (e.g., "BTC")
+    /// * `start_time` - Only return payments at or after this timestamp (milliseconds)
+    pub async fn get_funding_payments(
+        &self,
+        symbol: Option<&str>,
+        start_time: Option<u64>,
+    ) -> Result<Vec<FundingHistoryItem>> {
+        let mut payments = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut url = format!("{}/api/v1/funding/history?account={}&limit={}",
+                self.rest_url,
+                self.credentials.account,
+                FUNDING_HISTORY_PAGE_LIMIT
+            );
+
+            if let Some(c) = &cursor {
+                url.push_str(&format!("&cursor={}", c));
+            }
+
+            debug!("[PACIFICA] Fetching funding history from {}", url);
+
+            let request = self.client
+                .get(&url)
+                .send();
+            let response = api_capture::observe_http("pacifica GET /api/v1/funding/history", request).await
+                .context("Failed to fetch funding history")?;
+
+            let response_text = response.text().await?;
+            let history_response: FundingHistoryResponse = serde_json::from_str(&response_text)
+                .with_context(|| format!("Failed to parse funding history response: {}", response_text))?;
+
+            if !history_response.success {
+                let error_msg = history_response.error.unwrap_or_else(|| "Unknown error".to_string());
+                anyhow::bail!("Get funding history failed: {}", error_msg);
+            }
+
+            let page = history_response.data.unwrap_or_default();
+            let reached_start = start_time
+                .map(|start| page.iter().any(|p| p.created_at < start))
+                .unwrap_or(false);
+
+            payments.extend(page.into_iter().filter(|p| {
+                symbol.map(|s| p.symbol == s).unwrap_or(true)
+                    && start_time.map(|start| p.created_at >= start).unwrap_or(true)
+            }));
+
+            match history_response.next_cursor {
+                Some(next) if history_response.has_more && !reached_start && cursor.as_ref() != Some(&next) => {
+                    cursor = Some(next);
+                }
+                _ => break,
+            }
+        }
+
+        info!("[PACIFICA] Retrieved {} funding payment(s)", payments.len());
+        Ok(payments)
+    }
+
     /// Get current open positions
     ///
     /// # Returns
@@ -1025,7 +1836,7 @@ impl PacificaTrading {
 
         debug!("[PACIFICA] Fetching positions from: {}", url);
 
-        let response = self.client.get(&url).send().await?;
+        let response = api_capture::observe_http("pacifica GET /api/v1/positions", self.client.get(&url).send()).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -1072,7 +1883,7 @@ impl PacificaTrading {
         info!("[PACIFICA] Updating leverage for {} to {}x", symbol, leverage);
 
         // Build signature
-        let timestamp = chrono::Utc::now().timestamp_millis();
+        let timestamp = clock::now_ms();
         let expiry_window = 5000; // 5 seconds
 
         let header = json!({
@@ -1104,11 +1915,11 @@ impl PacificaTrading {
 
         // Send request
         let url = format!("{}/api/v1/account/leverage", self.rest_url);
-        let response = self.client
+        let request = self.client
             .post(&url)
             .json(&request_body)
-            .send()
-            .await?;
+            .send();
+        let response = api_capture::observe_http_json("pacifica POST /api/v1/account/leverage", &request_body, request).await?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -1133,6 +1944,115 @@ impl PacificaTrading {
 
         Ok(())
     }
+
+    /// Switch margin mode for a symbol
+    ///
+    /// # Arguments
+    /// * `symbol` - Trading symbol (e.g., "BTC")
+    /// * `isolated` - true for isolated margin, false for cross margin
+    pub async fn update_margin_mode(&self, symbol: &str, isolated: bool) -> Result<()> {
+        let mode = if isolated { "isolated" } else { "cross" };
+        info!("[PACIFICA] Updating margin mode for {} to {}", symbol, mode);
+
+        let timestamp = clock::now_ms();
+        let expiry_window = 5000; // 5 seconds
+
+        let header = json!({
+            "type": "update_margin_mode",
+            "timestamp": timestamp,
+            "expiry_window": expiry_window
+        });
+
+        let payload = json!({
+            "symbol": symbol,
+            "is_isolated": isolated
+        });
+
+        let signature = self.sign_message(header, payload.clone())?;
+
+        let request_body = json!({
+            "account": self.credentials.account,
+            "symbol": symbol,
+            "is_isolated": isolated,
+            "timestamp": timestamp,
+            "expiry_window": expiry_window,
+            "agent_wallet": self.credentials.agent_wallet,
+            "signature": signature
+        });
+
+        debug!("[PACIFICA] Sending update_margin_mode request (symbol: {}, mode: {})", symbol, mode);
+
+        let url = format!("{}/api/v1/account/margin", self.rest_url);
+        let request = self.client
+            .post(&url)
+            .json(&request_body)
+            .send();
+        let response = api_capture::observe_http_json("pacifica POST /api/v1/account/margin", &request_body, request).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            anyhow::bail!("Failed to update margin mode: {}", error_text);
+        }
+
+        #[derive(Deserialize, Debug)]
+        struct MarginModeResponse {
+            success: bool,
+            error: Option<String>,
+        }
+
+        let margin_response: MarginModeResponse = response.json().await?;
+
+        if !margin_response.success {
+            let error_msg = margin_response.error.unwrap_or_else(|| "Unknown error".to_string());
+            anyhow::bail!("Margin mode update failed: {}", error_msg);
+        }
+
+        info!("[PACIFICA] Successfully updated margin mode for {} to {}", symbol, mode);
+
+        Ok(())
+    }
+}
+
+/// Sign `{...header, data: payload}` (keys sorted) with a base58 Solana keypair
+fn sign_with_key(private_key: &str, header: serde_json::Value, payload: serde_json::Value) -> Result<String> {
+    let canonical = signing_message(header, payload);
+
+    // Solana/Pacifica private keys are 64 bytes (32 bytes seed + 32 bytes public key)
+    // Ed25519 SigningKey needs only the first 32 bytes (the seed)
+    let signing_key = SigningKey::from_bytes(&signing_seed(private_key)?);
+
+    // Sign the message
+    let signature = signing_key.sign(canonical.as_bytes());
+
+    // Encode signature as base58
+    Ok(bs58::encode(signature.to_bytes()).into_string())
+}
+
+/// Canonical message signed for a request: `{...header, data: payload}`, keys sorted
+fn signing_message(header: serde_json::Value, payload: serde_json::Value) -> String {
+    // Construct message: {... header, data: payload}
+    let mut message = serde_json::json!({});
+    if let serde_json::Value::Object(ref mut map) = message {
+        if let serde_json::Value::Object(header_map) = header {
+            for (k, v) in header_map {
+                map.insert(k, v);
+            }
+        }
+        map.insert("data".to_string(), payload);
+    }
+
+    // Canonicalize JSON (sort keys alphabetically)
+    canonicalize_json(&message)
+}
+
+/// Sum of trade amounts executed for `client_order_id`.
+/// Used to find out whether an order whose response was lost actually executed.
+pub fn filled_amount_for_client_id(trades: &[TradeHistoryItem], client_order_id: &str) -> f64 {
+    trades
+        .iter()
+        .filter(|t| t.client_order_id == client_order_id)
+        .map(|t| t.amount.parse::<f64>().unwrap_or(0.0).abs())
+        .sum()
 }
 
 /// Canonicalize JSON by sorting keys alphabetically
@@ -1168,3 +2088,192 @@ pub fn canonicalize_json(value: &serde_json::Value) -> String {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use ed25519_dalek::{Verifier, VerifyingKey};
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Requests the mock venue received: path and JSON body (`Null` for a GET)
+    type Received = Arc<Mutex<Vec<(String, serde_json::Value)>>>;
+
+    /// Check a base58 signature from `sign_with_key` against a base58 public key
+    fn verify_with_key(public_key: &str, header: serde_json::Value, payload: serde_json::Value, signature: &str) -> Result<()> {
+        let public: [u8; 32] = bs58::decode(public_key)
+            .into_vec()
+            .context("Failed to decode public key")?
+            .try_into()
+            .map_err(|_| anyhow!("Public key must be 32 bytes"))?;
+        let signature = bs58::decode(signature).into_vec().context("Failed to decode signature")?;
+        let signature = ed25519_dalek::Signature::from_slice(&signature).context("Malformed signature")?;
+        VerifyingKey::from_bytes(&public)
+            .context("Invalid public key")?
+            .verify(signing_message(header, payload).as_bytes(), &signature)
+            .context("Signature does not verify against the public key")
+    }
+
+    /// Local HTTP server answering each path (query stripped) with its canned JSON body
+    async fn mock_venue(routes: Vec<(&'static str, serde_json::Value)>) -> (String, Received) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let received: Received = Arc::default();
+        let log = received.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let (head_len, content_length) = loop {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        let head = String::from_utf8_lossy(&buf[..end]).to_lowercase();
+                        let length = head
+                            .lines()
+                            .find_map(|l| l.strip_prefix("content-length:"))
+                            .map(|v| v.trim().parse::<usize>().unwrap())
+                            .unwrap_or(0);
+                        break (end + 4, length);
+                    }
+                };
+                while buf.len() < head_len + content_length {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let request_line = String::from_utf8_lossy(&buf[..head_len]).lines().next().unwrap_or_default().to_string();
+                let path = request_line.split(' ').nth(1).unwrap_or_default().split('?').next().unwrap_or_default().to_string();
+                let body = serde_json::from_slice(&buf[head_len..head_len + content_length]).unwrap_or(serde_json::Value::Null);
+                let answer = routes.iter().find(|(p, _)| *p == path).map(|(_, a)| a.to_string()).unwrap_or_default();
+                log.lock().unwrap().push((path, body));
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    answer.len(),
+                    answer
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    fn market_info() -> serde_json::Value {
+        json!({"data": [{
+            "symbol": "BTC", "tick_size": "0.1", "lot_size": "0.001",
+            "funding_rate": "0.0001", "next_funding_rate": "0.0001"
+        }]})
+    }
+
+    fn trading(url: &str) -> (PacificaTrading, AgentKeypair) {
+        let agent = AgentKeypair::generate().unwrap();
+        let credentials = PacificaCredentials {
+            account: "main-wallet".to_string(),
+            agent_wallet: agent.public_key.clone(),
+            private_key: agent.private_key.clone(),
+        };
+        (PacificaTrading::with_rest_url(credentials, url), agent)
+    }
+
+    /// The signed fields of a request body, checked against the agent key
+    fn assert_signed(body: &serde_json::Value, agent: &AgentKeypair, kind: &str, fields: &[&str]) {
+        let header = json!({"type": kind, "timestamp": body["timestamp"], "expiry_window": body["expiry_window"]});
+        let payload: serde_json::Map<String, serde_json::Value> =
+            fields.iter().map(|f| (f.to_string(), body[*f].clone())).collect();
+        let signature = body["signature"].as_str().unwrap();
+        verify_with_key(&agent.public_key, header, serde_json::Value::Object(payload), signature).unwrap();
+        assert_eq!(body["account"], "main-wallet");
+        assert_eq!(body["agent_wallet"], agent.public_key.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_limit_order_rounds_and_signs_options() {
+        let (url, received) = mock_venue(vec![
+            ("/api/v1/info", market_info()),
+            ("/api/v1/orders/create", json!({"success": true, "data": {"order_id": 42}})),
+            ("/api/v1/orders/edit", json!({"success": true, "data": {"i": 43}})),
+        ])
+        .await;
+        let (mut client, agent) = trading(&url);
+
+        let order = client
+            .place_limit_order_with_options("BTC", OrderSide::Sell, 0.0014, 100.04, TimeInForce::Ioc, true, "cid-1")
+            .await
+            .unwrap();
+        assert_eq!(order.order_id, Some(42));
+        assert_eq!(order.client_order_id.as_deref(), Some("cid-1"));
+
+        let modified = client.modify_order("BTC", "cid-1", 99.94, 0.0026).await.unwrap();
+        assert_eq!(modified.order_id, Some(43));
+
+        let received = received.lock().unwrap();
+        // Market info is fetched once and cached for the modify
+        let paths: Vec<&str> = received.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, ["/api/v1/info", "/api/v1/orders/create", "/api/v1/orders/edit"]);
+
+        let create = &received[1].1;
+        assert_eq!(create["price"], "100");
+        assert_eq!(create["amount"], "0.001");
+        assert_eq!(create["side"], "ask");
+        assert_eq!(create["tif"], "IOC");
+        assert_eq!(create["reduce_only"], true);
+        assert_eq!(create["client_order_id"], "cid-1");
+        assert_signed(create, &agent, "create_order",
+            &["symbol", "price", "amount", "side", "tif", "reduce_only", "client_order_id"]);
+
+        let edit = &received[2].1;
+        assert_eq!(edit["price"], "99.9");
+        assert_eq!(edit["amount"], "0.003");
+        assert_signed(edit, &agent, "edit_order", &["symbol", "price", "amount", "client_order_id"]);
+    }
+
+    #[tokio::test]
+    async fn test_open_orders_and_cancel() {
+        let order = |symbol: &str, filled: &str| json!({
+            "order_id": 7, "client_order_id": "cid-7", "symbol": symbol, "side": "bid",
+            "price": "100.5", "initial_amount": "0.010", "filled_amount": filled,
+            "cancelled_amount": "0.002", "order_type": "limit", "created_at": 1
+        });
+        let (url, received) = mock_venue(vec![
+            ("/api/v1/orders", json!({"success": true, "data": [order("BTC", "0.003"), order("ETH", "0")]})),
+            ("/api/v1/orders/cancel", json!({"success": true})),
+        ])
+        .await;
+        let (client, agent) = trading(&url);
+
+        let orders = client.get_open_orders(Some("BTC")).await.unwrap();
+        assert_eq!(orders.len(), 1);
+        assert!(orders[0].is_buy());
+        assert_eq!(orders[0].price_f64(), 100.5);
+        assert!((orders[0].remaining_amount() - 0.005).abs() < 1e-12);
+        assert_eq!(client.get_open_orders(None).await.unwrap().len(), 2);
+
+        client.cancel_order("BTC", "cid-7").await.unwrap();
+        let received = received.lock().unwrap();
+        let cancel = &received.last().unwrap().1;
+        assert_eq!(cancel["client_order_id"], "cid-7");
+        assert!(cancel.get("order_id").is_none());
+        assert_signed(cancel, &agent, "cancel_order", &["symbol", "client_order_id"]);
+    }
+
+    #[tokio::test]
+    async fn test_order_history_final_states() {
+        let order = |id: u64, status: &str, filled: &str| json!({
+            "order_id": id, "client_order_id": format!("cid-{}", id), "symbol": "BTC",
+            "filled_amount": filled, "average_filled_price": "100.5", "order_status": status, "created_at": 1
+        });
+        let (url, _) = mock_venue(vec![(
+            "/api/v1/orders/history",
+            json!({"success": true, "data": [order(1, "partially_filled", "0.002"), order(2, "cancelled", "0.004"), order(3, "rejected", "0")]}),
+        )])
+        .await;
+        let (client, _) = trading(&url);
+
+        let orders = client.get_order_history(Some(10)).await.unwrap();
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[0].confirmed_fill(), None);
+        assert_eq!(orders[1].confirmed_fill(), Some(ConfirmedFill { filled_amount: 0.004, avg_price: Some(100.5) }));
+        assert_eq!(orders[2].confirmed_fill().map(|f| f.filled_amount), Some(0.0));
+    }
+}
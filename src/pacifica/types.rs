@@ -1,3 +1,5 @@
+use crate::numeric::{parse_decimal_or_zero, Decimal};
+use crate::types::BidAsk;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -29,6 +31,64 @@ pub struct PingMessage {
     pub method: String,
 }
 
+/// Subscription to the `prices` channel (mark, oracle and funding for every market)
+#[derive(Debug, Serialize)]
+pub struct PricesSubscribe {
+    pub method: String,
+    pub params: PricesParams,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PricesParams {
+    pub source: String,
+}
+
+impl PricesSubscribe {
+    pub fn new() -> Self {
+        Self {
+            method: "subscribe".to_string(),
+            params: PricesParams {
+                source: "prices".to_string(),
+            },
+        }
+    }
+}
+
+impl Default for PricesSubscribe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `prices` channel message
+#[derive(Debug, Deserialize)]
+pub struct PricesResponse {
+    pub channel: String,
+    pub data: Vec<PriceUpdate>,
+}
+
+/// One market's prices and funding from the `prices` channel
+#[derive(Debug, Clone, Deserialize)]
+pub struct PriceUpdate {
+    pub symbol: String,
+    /// Last settled funding rate (decimal string)
+    pub funding: String,
+    /// Predicted next funding rate (decimal string)
+    pub next_funding: String,
+    #[serde(default)]
+    pub mark: Option<String>,
+    #[serde(default)]
+    pub oracle: Option<String>,
+    pub timestamp: u64,
+}
+
+impl PriceUpdate {
+    /// Predicted next funding rate as decimal, the same figure REST reports as `next_funding_rate`
+    pub fn next_funding_rate(&self) -> Option<f64> {
+        self.next_funding.parse().ok()
+    }
+}
+
 /// Generic websocket response
 #[derive(Debug, Deserialize)]
 pub struct WebSocketResponse {
@@ -78,7 +138,7 @@ pub struct TopOfBook {
 impl OrderbookData {
     /// Extract the top of book (best bid and ask)
     pub fn get_top_of_book(&self) -> TopOfBook {
-        let best_bid = self.levels.get(0)
+        let best_bid = self.levels.first()
             .and_then(|bids| bids.first())
             .cloned();
 
@@ -95,6 +155,19 @@ impl OrderbookData {
     }
 }
 
+impl From<&TopOfBook> for BidAsk {
+    fn from(tob: &TopOfBook) -> Self {
+        BidAsk {
+            market: tob.symbol.clone(),
+            best_bid: tob.best_bid.as_ref().map(|l| l.price.clone()),
+            best_ask: tob.best_ask.as_ref().map(|l| l.price.clone()),
+            bid_quantity: tob.best_bid.as_ref().map(|l| l.amount.clone()),
+            ask_quantity: tob.best_ask.as_ref().map(|l| l.amount.clone()),
+            timestamp: tob.timestamp,
+        }
+    }
+}
+
 impl SubscribeMessage {
     pub fn new(symbol: String, agg_level: u32) -> Self {
         Self {
@@ -319,6 +392,42 @@ impl OrderUpdate {
     }
 }
 
+/// Final outcome of a Pacifica order as reported by `account_order_updates`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfirmedFill {
+    /// Amount filled before the order finished (0 when cancelled unfilled)
+    pub filled_amount: f64,
+    /// Average fill price (not reported for cancellations)
+    pub avg_price: Option<f64>,
+}
+
+impl FillEvent {
+    /// Client order ID of the order this event belongs to
+    pub fn client_order_id(&self) -> Option<&str> {
+        match self {
+            FillEvent::PartialFill { client_order_id, .. }
+            | FillEvent::FullFill { client_order_id, .. }
+            | FillEvent::Cancelled { client_order_id, .. } => client_order_id.as_deref(),
+        }
+    }
+
+    /// Final outcome of the order, or `None` while it can still fill or when the filled
+    /// amount does not parse, leaving the fill to be confirmed from history
+    pub fn outcome(&self) -> Option<ConfirmedFill> {
+        match self {
+            FillEvent::PartialFill { .. } => None,
+            FillEvent::FullFill { filled_amount, avg_price, .. } => Some(ConfirmedFill {
+                filled_amount: filled_amount.parse().ok()?,
+                avg_price: avg_price.parse().ok().filter(|p: &f64| *p > 0.0),
+            }),
+            FillEvent::Cancelled { filled_amount, .. } => Some(ConfirmedFill {
+                filled_amount: filled_amount.parse().ok()?,
+                avg_price: None,
+            }),
+        }
+    }
+}
+
 // ═══════════════════════════════════════════════════
 // WebSocket Trading Operations
 // ═══════════════════════════════════════════════════
@@ -453,6 +562,34 @@ impl fmt::Display for PacificaFundingRate {
     }
 }
 
+// ═══════════════════════════════════════════════════
+// Klines
+// ═══════════════════════════════════════════════════
+
+/// OHLCV candle of a Pacifica market (from /api/v1/kline)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PacificaCandle {
+    /// Candle open time in milliseconds
+    pub open_time: u64,
+    /// Candle close time in milliseconds
+    pub close_time: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Traded volume in base asset
+    pub volume: f64,
+    /// Number of trades in the candle
+    pub trades: u64,
+}
+
+impl PacificaCandle {
+    /// Traded volume in USD, valued at the close
+    pub fn volume_usd(&self) -> f64 {
+        self.volume * self.close
+    }
+}
+
 /// Market information with funding rates from /api/v1/info
 #[derive(Debug, Clone, Deserialize)]
 pub struct PacificaMarketInfo {
@@ -514,6 +651,16 @@ impl PacificaPosition {
     pub fn entry(&self) -> f64 {
         self.entry_price.parse().unwrap_or(0.0)
     }
+
+    /// Get position size as exact decimal
+    pub fn size_decimal(&self) -> Decimal {
+        parse_decimal_or_zero(&self.amount)
+    }
+
+    /// Get entry price as exact decimal
+    pub fn entry_decimal(&self) -> Decimal {
+        parse_decimal_or_zero(&self.entry_price)
+    }
 }
 
 impl fmt::Display for PacificaPosition {
@@ -566,11 +713,17 @@ impl PacificaAccountInfo {
         self.account_equity.parse().unwrap_or(0.0)
     }
 
+    /// Get account equity as exact decimal
+    pub fn account_equity_decimal(&self) -> Decimal {
+        parse_decimal_or_zero(&self.account_equity)
+    }
+
     /// Get available to spend (available capital for trading) as f64
     pub fn available_to_spend_f64(&self) -> f64 {
         self.available_to_spend.parse().unwrap_or(0.0)
     }
 
+
     /// Get available to withdraw as f64
     pub fn available_to_withdraw_f64(&self) -> f64 {
         self.available_to_withdraw.parse().unwrap_or(0.0)
@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use crate::metrics;
 use ed25519_dalek::{Signer, SigningKey};
 use futures_util::{SinkExt, StreamExt};
 use serde_json::json;
@@ -68,7 +69,7 @@ impl PacificaWsTrading {
         );
 
         // Connect to WebSocket
-        let (ws_stream, _) = connect_async(&self.ws_url)
+        let (ws_stream, _) = metrics::observe("pacifica WS connect (cancel_all_orders)", connect_async(&self.ws_url))
             .await
             .context("Failed to connect to Pacifica WebSocket")?;
 
@@ -80,7 +81,7 @@ impl PacificaWsTrading {
         let request_id = Uuid::new_v4().to_string();
 
         // Build signature
-        let timestamp = chrono::Utc::now().timestamp_millis();
+        let timestamp = super::clock::now_ms();
         let expiry_window = 5000;
 
         let header = json!({
@@ -236,7 +237,7 @@ impl PacificaWsTrading {
         info!("[PACIFICA_WS] Fetching account info via WebSocket");
 
         // Connect to WebSocket
-        let (ws_stream, _) = connect_async(&self.ws_url)
+        let (ws_stream, _) = metrics::observe("pacifica WS connect (account_info)", connect_async(&self.ws_url))
             .await
             .context("Failed to connect to Pacifica WebSocket")?;
 
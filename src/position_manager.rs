@@ -0,0 +1,106 @@
+//! Position management for externally generated signals
+//!
+//! `BotBuilder::build_position_manager` yields a `PositionManager`: the trading bot
+//! without its scanning loop. The caller decides what to hold (e.g. a model in another
+//! process sending commands over a pipe or socket) and calls `open`, `close` and
+//! `status`; the manager keeps everything else from `FundingBot`: startup recovery,
+//! state persistence, reconciliation, risk limits, sizing against free collateral and
+//! the two-leg execution with rollback. Like the bot, it holds one position at a time.
+use crate::bot::{BotState, FundingBot, StatusSnapshot};
+use crate::error::BotError;
+use crate::opportunity::Direction;
+use crate::strategy::PositionTarget;
+use tracing::{error, info};
+
+pub struct PositionManager {
+    bot: FundingBot,
+    extended_api_key: Option<String>,
+    started: bool,
+}
+
+impl PositionManager {
+    pub(crate) fn new(bot: FundingBot, extended_api_key: Option<String>) -> Self {
+        Self { bot, extended_api_key, started: false }
+    }
+
+    /// Recover what a previous run left behind (resting orders, half-done executions,
+    /// untracked positions) and reconcile state. Runs once; `open` and `close` call it.
+    pub async fn start(&mut self) -> Result<(), BotError> {
+        if self.started {
+            return Ok(());
+        }
+        self.bot.recover_at_startup().await?;
+        self.bot.reconcile_or_recover().await?;
+        self.started = true;
+        Ok(())
+    }
+
+    pub fn state(&self) -> &BotState {
+        self.bot.state()
+    }
+
+    /// Open a delta-neutral position on `symbol` with the long leg on the venue given by
+    /// `direction`. Sized at `notional_usd`, reduced to fit free collateral and risk
+    /// limits; fails if a position is already held or opening is blocked.
+    pub async fn open(&mut self, symbol: &str, direction: Direction, notional_usd: f64) -> Result<(), BotError> {
+        if !(notional_usd.is_finite() && notional_usd > 0.0) {
+            return Err(BotError::Config(format!("notional must be positive, got {}", notional_usd)));
+        }
+        self.start().await?;
+        self.bot.reconcile_state().await?;
+        if let Some(held) = &self.bot.state().current_position {
+            return Err(BotError::execution(
+                format!("Cannot open {}: a {} position is already held; close it first", symbol, held.symbol),
+                false,
+            ));
+        }
+        if let Some(reason) = self.bot.open_blocker().await? {
+            return Err(BotError::execution(format!("Cannot open {}: {}", symbol, reason), true));
+        }
+
+        let opportunity = self.bot.evaluate_symbol(symbol, self.extended_api_key.clone()).await?;
+        info!("📡 External signal: open {} ({}) for ${:.2}, net APR now {:.2}%",
+            symbol, direction, notional_usd, opportunity.net_apr_for(direction));
+        self.bot.open_target(&PositionTarget { opportunity, direction }, Some(notional_usd)).await
+    }
+
+    /// Close the held position on both venues; does nothing when flat
+    pub async fn close(&mut self) -> Result<(), BotError> {
+        self.start().await?;
+        self.bot.close_current_position().await
+    }
+
+    /// Held position, ledger and API health, with live prices where available
+    pub async fn status(&self) -> Result<StatusSnapshot, BotError> {
+        self.bot.status_snapshot().await
+    }
+
+    /// Reconcile state with both venues and refresh realized funding. A position left
+    /// with one leg is closed, as in the bot loop. Call it periodically between signals.
+    pub async fn reconcile(&mut self) -> Result<(), BotError> {
+        self.start().await?;
+        self.bot.reconcile_or_recover().await?;
+        if self.bot.is_imbalanced() {
+            error!("⚠️  Position imbalance detected! Closing the remaining leg.");
+            self.bot.close_current_position().await?;
+        }
+        self.bot.refresh_realized_funding().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opportunity::Config;
+
+    #[test]
+    fn test_position_manager_requires_signing_keys() {
+        let read_only = FundingBot::builder(Config::default_config())
+            .pacifica_account("account")
+            .stark_keys("0x1", "0x2", "1")
+            .build_position_manager();
+        assert!(matches!(read_only, Err(BotError::Config(_))));
+        let no_stark = FundingBot::builder(Config::default_config()).pacifica_account("account").build_position_manager();
+        assert!(matches!(no_stark, Err(BotError::Config(_))));
+    }
+}
@@ -0,0 +1,343 @@
+//! Preflight checks before trading
+//!
+//! `extended_connector preflight` exercises what the first live order depends on without
+//! placing one: the Extended API key and account, that STARK_PUBLIC is the key of
+//! STARK_PRIVATE and owns VAULT_NUMBER, signing a real (unsent) Extended order, the
+//! Pacifica agent key and a signed (unsent) Pacifica payload, free collateral on both
+//! venues, host clock skew against Pacifica, and that the symbols named in config.json
+//! (and the held position, if any) are tradable on both venues. Each check prints as a
+//! line of a pass/warn/fail checklist; the command exits non-zero if any check fails.
+use crate::bot::{resolve_state_store, BotState};
+use crate::capital::Venue;
+use crate::opportunity::{restriction_reason, Config};
+use crate::pacifica::{clock, PacificaCredentials, PacificaWsTrading};
+use crate::snip12::{felt_to_hex, hex_to_felt};
+use crate::types::AccountInfo;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// Market the test Extended order is signed for
+pub const PREFLIGHT_SIGNING_MARKET: &str = "BTC-USD";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    fn add(&mut self, name: &'static str, status: CheckStatus, detail: impl Into<String>) {
+        self.checks.push(PreflightCheck { name, status, detail: detail.into() });
+    }
+
+    /// Pass with the detail on `Ok`, fail with the error on `Err`
+    fn record(&mut self, name: &'static str, result: Result<String, String>) {
+        match result {
+            Ok(detail) => self.add(name, CheckStatus::Pass, detail),
+            Err(detail) => self.add(name, CheckStatus::Fail, detail),
+        }
+    }
+
+    /// No check failed (warnings allowed)
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Fail)
+    }
+
+    /// One line per check, then a summary line
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for check in &self.checks {
+            let mark = match check.status {
+                CheckStatus::Pass => "✅ PASS",
+                CheckStatus::Warn => "⚠️  WARN",
+                CheckStatus::Fail => "❌ FAIL",
+            };
+            out.push_str(&format!("{}  {}: {}\n", mark, check.name, check.detail));
+        }
+        let failed = self.checks.iter().filter(|c| c.status == CheckStatus::Fail).count();
+        let warned = self.checks.iter().filter(|c| c.status == CheckStatus::Warn).count();
+        out.push_str(&format!(
+            "{} checks: {} passed, {} warnings, {} failed — {}\n",
+            self.checks.len(),
+            self.checks.len() - failed - warned,
+            warned,
+            failed,
+            if failed == 0 { "ready to trade" } else { "fix the failures before trading" }
+        ));
+        out
+    }
+}
+
+/// Credentials as found in the environment; missing ones fail their checks
+#[derive(Debug, Clone, Default)]
+pub struct PreflightCredentials {
+    pub extended_api_key: Option<String>,
+    pub pacifica: Option<PacificaCredentials>,
+    pub stark_private_key: Option<String>,
+    pub stark_public_key: Option<String>,
+    pub vault_id: Option<String>,
+}
+
+impl PreflightCredentials {
+    /// Same variables as the bot: EXTENDED_API_KEY (or API_KEY), SOL_WALLET, API_PUBLIC,
+    /// API_PRIVATE, STARK_PRIVATE, STARK_PUBLIC and VAULT_NUMBER
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        Self {
+            extended_api_key: var("EXTENDED_API_KEY").or_else(|| var("API_KEY")),
+            pacifica: PacificaCredentials::from_env().ok(),
+            stark_private_key: var("STARK_PRIVATE"),
+            stark_public_key: var("STARK_PUBLIC"),
+            vault_id: var("VAULT_NUMBER"),
+        }
+    }
+}
+
+/// Base symbols named in the config (per-symbol vaults, funding intervals and caps)
+pub fn configured_symbols(config: &Config) -> BTreeSet<String> {
+    config
+        .extended_vaults
+        .symbol_vaults
+        .keys()
+        .chain(config.funding_intervals.extended_symbol_hours.keys())
+        .chain(config.funding_intervals.pacifica_symbol_hours.keys())
+        .chain(config.funding_caps.extended_symbol_max_rate_pct.keys())
+        .chain(config.funding_caps.pacifica_symbol_max_rate_pct.keys())
+        .map(|s| s.to_uppercase())
+        .collect()
+}
+
+/// Whether two Stark keys are the same field element, whatever their hex formatting
+fn same_stark_key(a: &str, b: &str) -> bool {
+    let normalize = |key: &str| key.trim_start_matches("0x").trim_start_matches('0').to_lowercase();
+    normalize(a) == normalize(b)
+}
+
+/// Run every check against the config at `config_path` and the live venues
+pub async fn run_preflight(config_path: &str, credentials: &PreflightCredentials) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    let config = match Config::load(config_path) {
+        Ok(config) => {
+            report.add("Config", CheckStatus::Pass, format!("{} is valid ({:?})", config_path, config.environment));
+            config
+        }
+        Err(e) => {
+            report.add("Config", CheckStatus::Fail, e.to_string());
+            return report;
+        }
+    };
+
+    check_extended(&config, credentials, &mut report).await;
+    check_pacifica(&config, credentials, &mut report).await;
+    check_markets(&config, credentials, &mut report).await;
+    report
+}
+
+async fn check_extended(config: &Config, credentials: &PreflightCredentials, report: &mut PreflightReport) {
+    let stark_keys = match (&credentials.stark_private_key, &credentials.stark_public_key, &credentials.vault_id) {
+        (Some(private), Some(public), Some(vault)) => Some((private.as_str(), public.as_str(), vault.as_str())),
+        _ => None,
+    };
+
+    report.record("Stark key pair", match stark_keys {
+        None => Err("STARK_PRIVATE, STARK_PUBLIC and VAULT_NUMBER must all be set".to_string()),
+        Some((private, public, _)) => hex_to_felt(private)
+            .map_err(|e| format!("STARK_PRIVATE is not a hex field element: {}", e))
+            .and_then(|private| {
+                let derived = felt_to_hex(&starknet_crypto::get_public_key(&private));
+                if same_stark_key(&derived, public) {
+                    Ok("STARK_PUBLIC is the public key of STARK_PRIVATE".to_string())
+                } else {
+                    Err(format!("STARK_PRIVATE belongs to {}, not STARK_PUBLIC {}", derived, public))
+                }
+            }),
+    });
+
+    let Some(api_key) = credentials.extended_api_key.clone() else {
+        report.add("Extended API key", CheckStatus::Fail, "EXTENDED_API_KEY (or API_KEY) is not set");
+        return;
+    };
+    let client = match config.environment.extended_client(Some(api_key)) {
+        Ok(client) => client,
+        Err(e) => {
+            report.add("Extended API key", CheckStatus::Fail, e.to_string());
+            return;
+        }
+    };
+    let accounts = match client.get_accounts().await {
+        Ok(accounts) => {
+            report.add("Extended API key", CheckStatus::Pass, format!("{} account(s) visible", accounts.len()));
+            accounts
+        }
+        Err(e) => {
+            report.add("Extended API key", CheckStatus::Fail, format!("account lookup failed: {}", e));
+            return;
+        }
+    };
+
+    if let Some((private, public, vault)) = stark_keys {
+        report.record("Extended vault", check_vault(&accounts, public, vault).and_then(|detail| {
+            config.extended_vaults.verify(vault, &accounts, public).map(|()| detail)
+        }));
+        report.record("Extended order signing", match client.sign_test_order(PREFLIGHT_SIGNING_MARKET, private, public, vault).await {
+            Ok(_) => Ok(format!("signed a minimum-size {} buy (not sent)", PREFLIGHT_SIGNING_MARKET)),
+            Err(e) => Err(format!("signing failed: {}", e)),
+        });
+    }
+
+    match client.get_balance().await {
+        Ok(balance) => {
+            let free = balance.available_for_trade.parse::<f64>().unwrap_or(0.0);
+            collateral_check(report, "Extended collateral", balance.equity_f64(), free, config);
+        }
+        Err(e) => report.add("Extended collateral", CheckStatus::Fail, format!("balance lookup failed: {}", e)),
+    }
+}
+
+/// VAULT_NUMBER must be an account of the API key, signed for by STARK_PUBLIC
+fn check_vault(accounts: &[AccountInfo], stark_public_key: &str, vault_id: &str) -> Result<String, String> {
+    let account = accounts.iter().find(|a| a.l2_vault == vault_id).ok_or_else(|| {
+        let known: Vec<&str> = accounts.iter().map(|a| a.l2_vault.as_str()).collect();
+        format!("VAULT_NUMBER {} is not an account of this API key (accounts: {})", vault_id, known.join(", "))
+    })?;
+    if !same_stark_key(&account.l2_key, stark_public_key) {
+        return Err(format!("vault {} belongs to Stark key {}, not STARK_PUBLIC", vault_id, account.l2_key));
+    }
+    Ok(format!("vault {} (account {}, {}) matches STARK_PUBLIC", vault_id, account.account_id, account.status))
+}
+
+/// Fail without equity; warn when free collateral cannot margin a full-size leg
+fn collateral_check(report: &mut PreflightReport, name: &'static str, equity: f64, free: f64, config: &Config) {
+    let leg_margin = config.trading.max_position_size_usd / f64::from(config.trading.leverage.max(1));
+    let detail = format!("equity ${:.2}, free ${:.2}", equity, free);
+    if equity <= 0.0 {
+        report.add(name, CheckStatus::Fail, format!("{}: no collateral deposited", detail));
+    } else if free < leg_margin {
+        report.add(name, CheckStatus::Warn, format!(
+            "{}: below the ${:.2} margin of a ${:.0} leg at {}x, positions will be sized down",
+            detail, leg_margin, config.trading.max_position_size_usd, config.trading.leverage
+        ));
+    } else {
+        report.add(name, CheckStatus::Pass, detail);
+    }
+}
+
+async fn check_pacifica(config: &Config, credentials: &PreflightCredentials, report: &mut PreflightReport) {
+    let Some(pacifica_creds) = credentials.pacifica.clone() else {
+        report.add("Pacifica agent key", CheckStatus::Fail, "SOL_WALLET, API_PUBLIC and API_PRIVATE must all be set");
+        return;
+    };
+    report.record("Pacifica agent key", pacifica_creds
+        .check_agent_key()
+        .map(|()| "API_PRIVATE is the key of API_PUBLIC".to_string())
+        .map_err(|e| e.to_string()));
+
+    let client = config.environment.pacifica_client(pacifica_creds.clone());
+    match client.sync_clock().await {
+        Ok(offset) if offset.abs() > clock::CLOCK_SKEW_MAX_MS => report.add("Clock skew", CheckStatus::Fail,
+            format!("host clock is {}ms off Pacifica; fix NTP, the bot refuses to start", offset)),
+        Ok(offset) if offset.abs() >= clock::CLOCK_SKEW_WARN_MS => report.add("Clock skew", CheckStatus::Warn,
+            format!("host clock is {}ms off Pacifica; signed timestamps are corrected", offset)),
+        Ok(offset) => report.add("Clock skew", CheckStatus::Pass, format!("{}ms against Pacifica", offset)),
+        Err(e) => report.add("Clock skew", CheckStatus::Fail, format!("cannot reach Pacifica: {}", e)),
+    }
+
+    let pacifica_ws = PacificaWsTrading::new(pacifica_creds, config.environment.is_testnet());
+    match pacifica_ws.get_account_info().await {
+        Ok(info) => collateral_check(report, "Pacifica collateral", info.account_equity_f64(), info.available_to_spend_f64(), config),
+        Err(e) => report.add("Pacifica collateral", CheckStatus::Fail, format!("account lookup failed: {}", e)),
+    }
+}
+
+async fn check_markets(config: &Config, credentials: &PreflightCredentials, report: &mut PreflightReport) {
+    let extended_markets = match config.environment.extended_client(credentials.extended_api_key.clone()) {
+        Ok(client) => client.get_all_markets().await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    let pacifica_creds = credentials.pacifica.clone().unwrap_or_else(|| PacificaCredentials::read_only(""));
+    let mut pacifica_client = config.environment.pacifica_client(pacifica_creds);
+    let pacifica_markets = pacifica_client.get_market_info().await.cloned().map_err(|e| e.to_string());
+    let (extended_markets, pacifica_markets) = match (extended_markets, pacifica_markets) {
+        (Ok(extended), Ok(pacifica)) => (extended, pacifica),
+        (Err(e), _) => return report.add("Markets", CheckStatus::Fail, format!("Extended markets unavailable: {}", e)),
+        (_, Err(e)) => return report.add("Markets", CheckStatus::Fail, format!("Pacifica markets unavailable: {}", e)),
+    };
+
+    let blacklist = &config.filters.symbol_blacklist;
+    let symbol_map = &config.symbols;
+    let tradable = extended_markets
+        .iter()
+        .filter_map(|m| symbol_map.symbol(Venue::Extended, &m.name))
+        .filter(|symbol| restriction_reason(symbol, &extended_markets, &pacifica_markets, blacklist, symbol_map).is_none())
+        .count();
+    if tradable == 0 {
+        report.add("Markets", CheckStatus::Fail, "no symbol is tradable on both venues");
+    } else {
+        report.add("Markets", CheckStatus::Pass, format!("{} symbols tradable on both venues", tradable));
+    }
+
+    let mut symbols = configured_symbols(config);
+    let held = match resolve_state_store() {
+        Ok(store) => store.blocking(BotState::read).await.ok().and_then(|s| s.current_position),
+        Err(_) => None,
+    };
+    if let Some(held) = held {
+        symbols.insert(held.symbol);
+    }
+    let restricted: Vec<String> = symbols
+        .iter()
+        .filter_map(|symbol| {
+            restriction_reason(symbol, &extended_markets, &pacifica_markets, blacklist, symbol_map).map(|r| format!("{} ({})", symbol, r))
+        })
+        .collect();
+    if symbols.is_empty() {
+        return;
+    }
+    if restricted.is_empty() {
+        report.add("Configured symbols", CheckStatus::Pass, symbols.into_iter().collect::<Vec<_>>().join(", "));
+    } else {
+        report.add("Configured symbols", CheckStatus::Fail, format!("not tradable: {}", restricted.join(", ")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_and_configured_symbols() {
+        let mut report = PreflightReport::default();
+        report.record("Stark key pair", Ok("matches".to_string()));
+        report.add("Clock skew", CheckStatus::Warn, "1500ms");
+        assert!(report.passed());
+        report.record("Extended vault", Err("VAULT_NUMBER 7 is not an account".to_string()));
+        assert!(!report.passed());
+        let rendered = report.render();
+        assert!(rendered.contains("❌ FAIL  Extended vault: VAULT_NUMBER 7 is not an account"));
+        assert!(rendered.contains("3 checks: 1 passed, 1 warnings, 1 failed"));
+
+        assert!(same_stark_key("0x00abc", "0xABC"));
+        assert!(!same_stark_key("0xabc", "0xabd"));
+
+        let mut config = Config::default_config();
+        config.extended_vaults.symbol_vaults.insert("btc".to_string(), "1".to_string());
+        config.funding_intervals.pacifica_symbol_hours.insert("ETH".to_string(), 8.0);
+        let symbols: Vec<String> = configured_symbols(&config).into_iter().collect();
+        assert_eq!(symbols, vec!["BTC".to_string(), "ETH".to_string()]);
+    }
+}
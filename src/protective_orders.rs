@@ -0,0 +1,321 @@
+//! Protective take-profit/stop-loss orders on both legs
+//!
+//! A delta-neutral position is only safe while the bot watches both legs: if the process
+//! dies and the price runs, the losing leg drifts towards liquidation while the winning
+//! leg's profit sits on the other venue. With `protective_orders.enabled`, right after a
+//! position opens the bot rests reduce-only take-profit and stop-loss orders on each leg
+//! at `band_pct` above and below one reference price, executed at market with up to
+//! `slippage_pct` past the trigger: a position TP/SL (`TPSL` order) on Extended and a
+//! position TP/SL on Pacifica. The reference is the mean of both legs' entries per
+//! canonical unit, so one leg's stop and the other's take-profit sit at the same price.
+//! They still do not trigger together: each venue triggers on its own mark price, which
+//! differs from the other's by the basis, so a move can close one leg and leave the other
+//! open. The imbalance check closes that lone leg on the bot's next cycle; while the bot
+//! is down it stays open until its own trigger is reached.
+//!
+//! Client order IDs derive from the position (`OrderLeg::ExtendedProtect` and the Pacifica
+//! legs) and its `protective_generation`, which keeps them out of the order sweeper while
+//! the position is held. A partial close replaces them, under the next generation, with
+//! orders sized to what is left; every exit (close, emergency flatten, clearing a position
+//! found gone) cancels them, while the startup recovery of resting orders leaves them in
+//! place. Failing to place them is logged and does not undo the open.
+use crate::error::BotError;
+use crate::pacifica::{OrderSide as PacificaSide, PacificaTrading, StopLeg};
+use crate::rest::RestClient;
+use crate::capital::Venue;
+use crate::trading::{client_order_id, DeltaNeutralPosition, OrderLeg};
+use crate::types::{OrderSide, TpSlTrigger};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Lifetime of the Extended TP/SL order (Extended accepts up to 90 days)
+const EXTENDED_EXPIRY_MILLIS: u64 = 60 * 24 * 3600 * 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtectiveOrdersConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Distance of both triggers from the leg's entry price, in %
+    #[serde(default = "default_band_pct")]
+    pub band_pct: f64,
+    /// Worst execution price past the trigger, in %
+    #[serde(default = "default_slippage_pct")]
+    pub slippage_pct: f64,
+}
+
+fn default_band_pct() -> f64 {
+    10.0
+}
+
+fn default_slippage_pct() -> f64 {
+    2.0
+}
+
+impl Default for ProtectiveOrdersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            band_pct: default_band_pct(),
+            slippage_pct: default_slippage_pct(),
+        }
+    }
+}
+
+/// Trigger and worst execution prices of one leg's take-profit and stop-loss
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProtectiveBand {
+    pub take_profit_trigger: f64,
+    pub take_profit_price: f64,
+    pub stop_loss_trigger: f64,
+    pub stop_loss_price: f64,
+}
+
+impl ProtectiveBand {
+    /// Band around `entry_price` for a long (TP above, SL below) or short leg
+    pub fn around(entry_price: f64, is_long: bool, config: &ProtectiveOrdersConfig) -> Self {
+        let band = config.band_pct / 100.0;
+        let slippage = config.slippage_pct / 100.0;
+        // Triggers in the leg's favour / against it; the closing order trades the other way
+        let (up, down) = (entry_price * (1.0 + band), entry_price * (1.0 - band));
+        if is_long {
+            Self {
+                take_profit_trigger: up,
+                take_profit_price: up * (1.0 - slippage),
+                stop_loss_trigger: down,
+                stop_loss_price: down * (1.0 - slippage),
+            }
+        } else {
+            Self {
+                take_profit_trigger: down,
+                take_profit_price: down * (1.0 + slippage),
+                stop_loss_trigger: up,
+                stop_loss_price: up * (1.0 + slippage),
+            }
+        }
+    }
+}
+
+/// Generation the position's protective orders are placed under; positions saved before
+/// generations were recorded used the first
+fn generation(position: &DeltaNeutralPosition) -> u32 {
+    position.protective_generation.unwrap_or(1)
+}
+
+/// Price per canonical unit both legs' bands are centred on: the mean of the legs'
+/// entries, either one alone, or `fallback_price`
+fn reference_price(position: &DeltaNeutralPosition, fallback_price: Option<f64>) -> Option<f64> {
+    let extended = position
+        .extended_entry_price
+        .or(position.extended_position.as_ref().map(|leg| leg.entry_f64()))
+        .filter(|&p| p > 0.0)
+        .map(|p| position.multipliers.base_price(Venue::Extended, p));
+    let pacifica = position
+        .pacifica_entry_price
+        .or(position.pacifica_position.as_ref().map(|leg| leg.entry()))
+        .filter(|&p| p > 0.0)
+        .map(|p| position.multipliers.base_price(Venue::Pacifica, p));
+    match (extended, pacifica) {
+        (Some(ext), Some(pac)) => Some((ext + pac) / 2.0),
+        (one, other) => one.or(other).or(fallback_price),
+    }
+}
+
+/// Rest TP/SL orders on both legs of `position` under its current generation, around
+/// the reference price of its entries (`fallback_price`, per canonical unit, where
+/// neither venue reported one). Both legs are attempted; the error lists every leg
+/// that failed.
+#[allow(clippy::too_many_arguments)]
+pub async fn place_protective_orders(
+    position: &DeltaNeutralPosition,
+    fallback_price: Option<f64>,
+    extended_client: &RestClient,
+    pacifica_client: &mut PacificaTrading,
+    stark_private_key: &str,
+    stark_public_key: &str,
+    vault_id: &str,
+    config: &ProtectiveOrdersConfig,
+) -> Result<(), BotError> {
+    let Ok(seed) = Uuid::parse_str(&position.position_id) else {
+        return Err(BotError::execution(
+            format!("{} has no position ID to derive protective order IDs from", position.symbol),
+            false,
+        ));
+    };
+    let Some(reference) = reference_price(position, fallback_price) else {
+        return Err(BotError::execution(
+            format!("{} has no entry price to place protective orders around", position.symbol),
+            false,
+        ));
+    };
+    let generation = generation(position);
+    let mut failures = Vec::new();
+
+    if let Some(leg) = &position.extended_position {
+        let entry = reference * position.multipliers.of(Venue::Extended);
+        let band = ProtectiveBand::around(entry, leg.is_long(), config);
+        let close_side = if leg.is_long() { OrderSide::Sell } else { OrderSide::Buy };
+        let placed = extended_client
+            .place_position_tpsl(
+                &leg.market,
+                close_side,
+                leg.size_f64(),
+                Some(&TpSlTrigger::market(band.take_profit_trigger, band.take_profit_price)),
+                Some(&TpSlTrigger::market(band.stop_loss_trigger, band.stop_loss_price)),
+                EXTENDED_EXPIRY_MILLIS,
+                stark_private_key,
+                stark_public_key,
+                vault_id,
+                &client_order_id(&seed, OrderLeg::ExtendedProtect, generation).to_string(),
+            )
+            .await;
+        match placed {
+            Ok(_) => info!("🛟 Extended TP/SL on {}: TP {:.6}, SL {:.6}",
+                leg.market, band.take_profit_trigger, band.stop_loss_trigger),
+            Err(e) => failures.push(format!("Extended: {}", e)),
+        }
+    }
+
+    if let Some(leg) = &position.pacifica_position {
+        let entry = reference * position.multipliers.of(Venue::Pacifica);
+        let band = ProtectiveBand::around(entry, leg.is_long(), config);
+        let close_side = if leg.is_long() { PacificaSide::Sell } else { PacificaSide::Buy };
+        let stop_leg = |stop_price: f64, order_leg: OrderLeg| StopLeg {
+            stop_price,
+            limit_price: None,
+            client_order_id: client_order_id(&seed, order_leg, generation).to_string(),
+        };
+        let placed = pacifica_client
+            .set_position_tpsl(
+                &leg.symbol,
+                close_side,
+                Some(&stop_leg(band.take_profit_trigger, OrderLeg::PacificaTakeProfit)),
+                Some(&stop_leg(band.stop_loss_trigger, OrderLeg::PacificaStopLoss)),
+            )
+            .await;
+        match placed {
+            Ok(()) => info!("🛟 Pacifica TP/SL on {}: TP {:.6}, SL {:.6}",
+                leg.symbol, band.take_profit_trigger, band.stop_loss_trigger),
+            Err(e) => failures.push(format!("Pacifica: {}", e)),
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(BotError::execution(
+            format!("Protective orders for {} failed: {}", position.symbol, failures.join("; ")),
+            true,
+        ))
+    }
+}
+
+/// Client order IDs of the current generation of `position`'s protective orders, which
+/// must be left resting while it is held
+pub fn protective_order_ids(position: &DeltaNeutralPosition) -> Vec<String> {
+    let Ok(seed) = Uuid::parse_str(&position.position_id) else {
+        return Vec::new();
+    };
+    let generation = generation(position);
+    [OrderLeg::ExtendedProtect, OrderLeg::PacificaTakeProfit, OrderLeg::PacificaStopLoss]
+        .map(|leg| client_order_id(&seed, leg, generation).to_string())
+        .to_vec()
+}
+
+/// Cancel the current generation of protective orders of `position`, once it is closed
+/// or before they are replaced. Best effort: an order already triggered or expired is
+/// gone anyway, and the order sweeper catches any left behind.
+pub async fn cancel_protective_orders(
+    position: &DeltaNeutralPosition,
+    extended_client: &RestClient,
+    pacifica_client: &PacificaTrading,
+) {
+    let Ok(seed) = Uuid::parse_str(&position.position_id) else {
+        return;
+    };
+    let generation = generation(position);
+    let external_id = client_order_id(&seed, OrderLeg::ExtendedProtect, generation).to_string();
+    if let Err(e) = extended_client.cancel_order_by_external_id(&external_id).await {
+        warn!("Failed to cancel Extended TP/SL of {}: {}", position.symbol, e);
+    }
+    for leg in [OrderLeg::PacificaTakeProfit, OrderLeg::PacificaStopLoss] {
+        let id = client_order_id(&seed, leg, generation).to_string();
+        if let Err(e) = pacifica_client.cancel_stop_order(&position.pacifica_market_name(), &id).await {
+            warn!("Failed to cancel Pacifica {:?} of {}: {}", leg, position.symbol, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_band_sides() {
+        let config = ProtectiveOrdersConfig { enabled: true, band_pct: 10.0, slippage_pct: 2.0 };
+
+        let long = ProtectiveBand::around(100.0, true, &config);
+        assert!((long.take_profit_trigger - 110.0).abs() < 1e-9);
+        assert!((long.stop_loss_trigger - 90.0).abs() < 1e-9);
+        // A long is closed by selling: worst prices sit below the triggers
+        assert!((long.stop_loss_price - 88.2).abs() < 1e-9);
+        assert!(long.take_profit_price < long.take_profit_trigger);
+
+        let short = ProtectiveBand::around(100.0, false, &config);
+        assert!((short.take_profit_trigger - 90.0).abs() < 1e-9);
+        assert!((short.stop_loss_trigger - 110.0).abs() < 1e-9);
+        assert!((short.stop_loss_price - 112.2).abs() < 1e-9);
+        assert!(short.take_profit_price > short.take_profit_trigger);
+
+        // Around one reference price, one leg's stop is the other's take-profit
+        assert_eq!(long.stop_loss_trigger, short.take_profit_trigger);
+    }
+
+    #[test]
+    fn test_reference_price_is_shared_by_both_legs() {
+        let mut position: DeltaNeutralPosition = serde_json::from_value(serde_json::json!({
+            "symbol": "PEPE",
+            "extended_position": null,
+            "pacifica_position": null,
+            "opened_at": 0,
+            "target_notional_usd": 100.0,
+            "extended_entry_price": 0.0000101,
+            "pacifica_entry_price": 0.0099,
+        }))
+        .unwrap();
+        position.multipliers.pacifica = 1000.0;
+
+        // Compared per canonical unit: 1 PEPE on Extended, 1000 on Pacifica
+        let reference = reference_price(&position, Some(1.0)).unwrap();
+        assert!((reference - 0.00001).abs() < 1e-12);
+
+        position.extended_entry_price = None;
+        assert!((reference_price(&position, Some(1.0)).unwrap() - 0.0000099).abs() < 1e-12);
+        position.pacifica_entry_price = None;
+        assert_eq!(reference_price(&position, Some(1.0)), Some(1.0));
+        assert_eq!(reference_price(&position, None), None);
+    }
+
+    #[test]
+    fn test_protective_order_ids_follow_the_generation() {
+        let mut position: DeltaNeutralPosition = serde_json::from_value(serde_json::json!({
+            "symbol": "BTC",
+            "extended_position": null,
+            "pacifica_position": null,
+            "opened_at": 0,
+            "target_notional_usd": 100.0,
+        }))
+        .unwrap();
+        assert!(protective_order_ids(&position).is_empty());
+
+        let seed = Uuid::new_v4();
+        position.position_id = seed.to_string();
+        position.protective_generation = Some(2);
+        let ids = protective_order_ids(&position);
+        assert!(ids.contains(&client_order_id(&seed, OrderLeg::ExtendedProtect, 2).to_string()));
+        assert!(ids.contains(&client_order_id(&seed, OrderLeg::PacificaStopLoss, 2).to_string()));
+        // Replaced generations and the position's other orders are not protected
+        assert!(!ids.contains(&client_order_id(&seed, OrderLeg::ExtendedProtect, 1).to_string()));
+        assert!(!ids.contains(&client_order_id(&seed, OrderLeg::ExtendedClose, 2).to_string()));
+    }
+}
@@ -0,0 +1,648 @@
+//! Report view models and renderers
+//!
+//! Scan summaries and bot status are first turned into typed, serializable view models
+//! (`ScanSummaryView`, `StatusSnapshot`), which describe themselves as titled tables of
+//! cells tagged with a `Tone` rather than colours. Renderers then turn any `ReportView`
+//! into plain aligned text, box-drawn prettytable output (coloured on a terminal with
+//! `print_pretty`) or JSON, so applications embedding the library can show the same
+//! data without the bot's terminal art.
+use crate::bot::StatusSnapshot;
+use crate::capital::Venue;
+use crate::metrics::{self, Degradation};
+use crate::stress::RiskReport;
+use crate::opportunity::{format_volume, truncate, Config, Direction, FilterResult, OpportunityCandidate, ScanResult};
+use prettytable::{format, Cell, Row, Table};
+use serde::Serialize;
+
+/// How a cell should stand out; renderers without styling ignore it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tone {
+    Plain,
+    Bold,
+    Good,
+    Bad,
+    Warn,
+}
+
+impl Tone {
+    /// Green when `value` is non-negative, red otherwise
+    pub fn of_sign(value: f64) -> Self {
+        if value >= 0.0 { Tone::Good } else { Tone::Bad }
+    }
+
+    fn style_spec(self) -> &'static str {
+        match self {
+            Tone::Plain => "",
+            Tone::Bold => "b",
+            Tone::Good => "Fg",
+            Tone::Bad => "Fr",
+            Tone::Warn => "Fy",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportCell {
+    pub text: String,
+    pub tone: Tone,
+}
+
+impl ReportCell {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into(), tone: Tone::Plain }
+    }
+
+    pub fn toned(text: impl Into<String>, tone: Tone) -> Self {
+        Self { text: text.into(), tone }
+    }
+}
+
+/// A titled table; `header` is `None` for label/value tables
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportTable {
+    pub title: String,
+    pub header: Option<Vec<String>>,
+    pub rows: Vec<Vec<ReportCell>>,
+}
+
+impl ReportTable {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self { title: title.into(), header: None, rows: Vec::new() }
+    }
+
+    pub fn with_header(title: impl Into<String>, header: &[&str]) -> Self {
+        Self { title: title.into(), header: Some(header.iter().map(|h| h.to_string()).collect()), rows: Vec::new() }
+    }
+
+    /// Add a label/value row
+    pub fn field(&mut self, label: &str, value: impl Into<String>) {
+        self.field_toned(label, value, Tone::Plain);
+    }
+
+    pub fn field_toned(&mut self, label: &str, value: impl Into<String>, tone: Tone) {
+        self.rows.push(vec![ReportCell::new(label), ReportCell::toned(value, tone)]);
+    }
+
+    fn columns(&self) -> usize {
+        self.rows.iter().map(Vec::len).chain(self.header.as_ref().map(Vec::len)).max().unwrap_or(1)
+    }
+}
+
+/// A view model that can be rendered as tables and serialized as JSON
+pub trait ReportView: Serialize {
+    fn tables(&self) -> Vec<ReportTable>;
+}
+
+/// Tables as aligned plain text, without colour or box characters
+pub fn render_plain(view: &impl ReportView) -> String {
+    let mut out = String::new();
+    for table in view.tables() {
+        out.push_str(&table.title);
+        out.push('\n');
+        out.push_str(&"-".repeat(table.title.chars().count()));
+        out.push('\n');
+        let header: Option<Vec<ReportCell>> = table.header.as_ref().map(|h| h.iter().map(ReportCell::new).collect());
+        let lines: Vec<&Vec<ReportCell>> = header.iter().chain(table.rows.iter()).collect();
+        let mut widths = vec![0; table.columns()];
+        for line in &lines {
+            for (i, cell) in line.iter().enumerate() {
+                widths[i] = widths[i].max(cell.text.chars().count());
+            }
+        }
+        for line in lines {
+            let cells: Vec<String> = line
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| format!("{:<width$}", cell.text, width = widths[i]))
+                .collect();
+            out.push_str(cells.join("  ").trim_end());
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn pretty_tables(view: &impl ReportView) -> Vec<Table> {
+    view.tables()
+        .into_iter()
+        .map(|report_table| {
+            let mut table = Table::new();
+            table.set_format(*format::consts::FORMAT_BOX_CHARS);
+            table.set_titles(Row::new(vec![
+                Cell::new(&report_table.title).style_spec("cb").with_hspan(report_table.columns()),
+            ]));
+            if let Some(header) = &report_table.header {
+                table.add_row(Row::new(header.iter().map(|h| Cell::new(h).style_spec("b")).collect()));
+            }
+            for row in &report_table.rows {
+                table.add_row(Row::new(row.iter().map(|c| Cell::new(&c.text).style_spec(c.tone.style_spec())).collect()));
+            }
+            table
+        })
+        .collect()
+}
+
+/// Tables drawn with box characters (no colour codes)
+pub fn render_pretty(view: &impl ReportView) -> String {
+    pretty_tables(view).iter().map(|table| format!("{}\n", table)).collect()
+}
+
+/// Print the box-drawn tables to stdout, coloured by tone when stdout is a terminal
+pub fn print_pretty(view: &impl ReportView) {
+    for table in pretty_tables(view) {
+        table.printstd();
+        println!();
+    }
+}
+
+/// The view model as pretty JSON
+pub fn render_json(view: &impl ReportView) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(view)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FilterCriteriaView {
+    pub min_combined_volume_usd: f64,
+    pub max_intra_exchange_spread_pct: f64,
+    pub max_cross_exchange_spread_pct: f64,
+    pub min_net_apr_pct: f64,
+    /// 0 = not filtered
+    pub max_mark_index_gap_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpportunityRow {
+    pub symbol: String,
+    pub total_volume_24h: f64,
+    pub net_apr_pct: f64,
+    pub direction: Direction,
+    pub extended_funding_rate_apr: f64,
+    pub pacifica_funding_rate_apr: f64,
+    pub extended_spread_pct: f64,
+    pub pacifica_spread_pct: f64,
+    pub cross_spread_pct: f64,
+    pub extended_mark_index_gap_pct: Option<f64>,
+    pub pacifica_mark_index_gap_pct: Option<f64>,
+    /// Estimated cost of opening both legs at `entry_cost_notional_usd`, in bps
+    pub entry_cost_bps: Option<f64>,
+    /// Hours of funding that pay back opening and closing at `entry_cost_notional_usd`;
+    /// `None` when funding does not pay
+    pub break_even_hours: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FilteredRow {
+    pub symbol: String,
+    pub total_volume_24h: f64,
+    pub net_apr_pct: f64,
+    pub reason: String,
+    pub detail: String,
+}
+
+/// Outcome of a scan: filter counts, criteria, passing opportunities and the
+/// highest-volume candidates that were filtered out (at most `FILTERED_ROWS`)
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanSummaryView {
+    pub markets_scanned: usize,
+    pub passed_filters: usize,
+    pub filtered_by_volume: usize,
+    pub filtered_by_spread: usize,
+    pub filtered_by_apr: usize,
+    pub filtered_by_open_interest: usize,
+    pub filtered_by_mark_index_gap: usize,
+    pub criteria: FilterCriteriaView,
+    /// Position size (USD) the entry costs are estimated for
+    pub entry_cost_notional_usd: f64,
+    /// Configured hold; opportunities breaking even later are flagged
+    pub hold_time_hours: u64,
+    pub opportunities: Vec<OpportunityRow>,
+    pub filtered: Vec<FilteredRow>,
+}
+
+impl ScanSummaryView {
+    pub const FILTERED_ROWS: usize = 10;
+
+    pub fn new(scan: &ScanResult, config: &Config) -> Self {
+        let entry_cost_notional_usd = config.trading.max_position_size_usd;
+        let entry_fee_pct = config.entry_fee_pct();
+        let opportunities = scan
+            .opportunities
+            .iter()
+            .map(|opp| OpportunityRow {
+                symbol: opp.symbol.clone(),
+                total_volume_24h: opp.total_volume_24h,
+                net_apr_pct: opp.best_net_apr,
+                direction: opp.best_direction,
+                extended_funding_rate_apr: opp.extended_funding_rate_apr,
+                pacifica_funding_rate_apr: opp.pacifica_funding_rate_apr,
+                extended_spread_pct: opp.extended_spread_pct,
+                pacifica_spread_pct: opp.pacifica_spread_pct,
+                cross_spread_pct: opp.cross_spread_pct,
+                extended_mark_index_gap_pct: opp.extended_mark_index_gap_pct,
+                pacifica_mark_index_gap_pct: opp.pacifica_mark_index_gap_pct,
+                entry_cost_bps: opp.estimated_entry_cost_bps(entry_cost_notional_usd, entry_fee_pct),
+                break_even_hours: opp.simulate(entry_cost_notional_usd, config).break_even_hours,
+            })
+            .collect();
+
+        let mut filtered: Vec<&OpportunityCandidate> =
+            scan.all_candidates.iter().filter(|c| c.filter_result != FilterResult::Passed).collect();
+        filtered.sort_by(|a, b| b.opportunity.total_volume_24h.total_cmp(&a.opportunity.total_volume_24h));
+        let filtered = filtered
+            .into_iter()
+            .filter_map(|candidate| {
+                let (reason, detail) = filter_reason(candidate)?;
+                Some(FilteredRow {
+                    symbol: candidate.opportunity.symbol.clone(),
+                    total_volume_24h: candidate.opportunity.total_volume_24h,
+                    net_apr_pct: candidate.opportunity.best_net_apr,
+                    reason: reason.to_string(),
+                    detail,
+                })
+            })
+            .take(Self::FILTERED_ROWS)
+            .collect();
+
+        Self {
+            markets_scanned: scan.stats.total_common_symbols,
+            passed_filters: scan.stats.passed_filters,
+            filtered_by_volume: scan.stats.filtered_by_volume,
+            filtered_by_spread: scan.stats.filtered_by_spread,
+            filtered_by_apr: scan.stats.filtered_by_apr,
+            filtered_by_open_interest: scan.stats.filtered_by_open_interest,
+            filtered_by_mark_index_gap: scan.stats.filtered_by_mark_index_gap,
+            criteria: FilterCriteriaView {
+                min_combined_volume_usd: config.filters.min_combined_volume_usd,
+                max_intra_exchange_spread_pct: config.filters.max_intra_exchange_spread_pct,
+                max_cross_exchange_spread_pct: config.filters.max_cross_exchange_spread_pct,
+                min_net_apr_pct: config.filters.min_net_apr_pct,
+                max_mark_index_gap_pct: config.filters.max_mark_index_gap_pct,
+            },
+            entry_cost_notional_usd,
+            hold_time_hours: config.trading.hold_time_hours,
+            opportunities,
+            filtered,
+        }
+    }
+}
+
+/// Mark vs index gap on both venues, e.g. `E:+0.12% P:N/A`
+fn format_mark_index_gaps(extended: Option<f64>, pacifica: Option<f64>) -> String {
+    let fmt_gap = |gap: Option<f64>| gap.map(|g| format!("{:+.2}%", g)).unwrap_or_else(|| "N/A".to_string());
+    format!("E:{} P:{}", fmt_gap(extended), fmt_gap(pacifica))
+}
+
+/// Short reason and detail for a filtered candidate; `None` if it passed
+fn filter_reason(candidate: &OpportunityCandidate) -> Option<(&'static str, String)> {
+    let opp = &candidate.opportunity;
+    Some(match candidate.filter_result {
+        FilterResult::FailedVolume => ("Volume too low", format_volume(opp.total_volume_24h)),
+        FilterResult::FailedIntraSpread => {
+            ("Spread too wide", format!("E:{:.2}% P:{:.2}%", opp.extended_spread_pct, opp.pacifica_spread_pct))
+        }
+        FilterResult::FailedCrossSpread => match candidate.filtered_cross_spread_pct {
+            Some(typical) => ("Cross spread", format!("{:.2}% (typ. {:.2}%)", opp.cross_spread_pct, typical)),
+            None => ("Cross spread", format!("{:.2}%", opp.cross_spread_pct)),
+        },
+        FilterResult::FailedApr => {
+            ("APR too low", format!("E:{:.1}% P:{:.1}%", opp.extended_funding_rate_apr, opp.pacifica_funding_rate_apr))
+        }
+        FilterResult::FailedOpenInterest => {
+            let fmt_oi = |oi: Option<f64>| oi.map(format_volume).unwrap_or_else(|| "N/A".to_string());
+            ("Open interest low", format!("E:{} P:{}", fmt_oi(opp.extended_open_interest_usd), fmt_oi(opp.pacifica_open_interest_usd)))
+        }
+        FilterResult::FailedMarkIndexGap => (
+            "Mark/index gap",
+            format_mark_index_gaps(opp.extended_mark_index_gap_pct, opp.pacifica_mark_index_gap_pct),
+        ),
+        FilterResult::Passed => return None,
+    })
+}
+
+impl ReportView for ScanSummaryView {
+    fn tables(&self) -> Vec<ReportTable> {
+        let mut summary = ReportTable::new("OPPORTUNITY SCAN SUMMARY");
+        summary.field("Markets Scanned", self.markets_scanned.to_string());
+        summary.field_toned("Passed All Filters", self.passed_filters.to_string(), Tone::Good);
+        summary.field("Filtered (Volume)", self.filtered_by_volume.to_string());
+        summary.field("Filtered (Spread)", self.filtered_by_spread.to_string());
+        summary.field("Filtered (APR)", self.filtered_by_apr.to_string());
+        if self.filtered_by_open_interest > 0 {
+            summary.field("Filtered (Open Interest)", self.filtered_by_open_interest.to_string());
+        }
+        if self.filtered_by_mark_index_gap > 0 {
+            summary.field("Filtered (Mark/Index)", self.filtered_by_mark_index_gap.to_string());
+        }
+        summary.rows.push(vec![ReportCell::toned("FILTER CRITERIA", Tone::Bold), ReportCell::new("")]);
+        summary.field("Min Volume", format_volume(self.criteria.min_combined_volume_usd));
+        summary.field("Max Intra Spread", format!("{}%", self.criteria.max_intra_exchange_spread_pct));
+        summary.field("Max Cross Spread", format!("{}%", self.criteria.max_cross_exchange_spread_pct));
+        summary.field("Min Net APR", format!("{}%", self.criteria.min_net_apr_pct));
+        if self.criteria.max_mark_index_gap_pct > 0.0 {
+            summary.field("Max Mark/Index Gap", format!("{}%", self.criteria.max_mark_index_gap_pct));
+        }
+        summary.field("Entry Cost Size", format_volume(self.entry_cost_notional_usd));
+        summary.field("Hold Time", format!("{}h", self.hold_time_hours));
+        let mut tables = vec![summary];
+
+        if !self.opportunities.is_empty() {
+            let mut table = ReportTable::with_header(
+                "OPPORTUNITIES (PASSED FILTERS)",
+                &["Sym", "Volume", "Net APR", "Strategy", "Ext FR", "Pac FR", "Spreads", "Mark/Index", "Entry Cost", "Break-even"],
+            );
+            for opp in &self.opportunities {
+                table.rows.push(vec![
+                    ReportCell::new(truncate(&opp.symbol, 4)),
+                    ReportCell::new(format_volume(opp.total_volume_24h)),
+                    ReportCell::toned(
+                        format!("{:.1}%", opp.net_apr_pct),
+                        if opp.net_apr_pct >= 20.0 { Tone::Good } else { Tone::Plain },
+                    ),
+                    ReportCell::new(opp.direction.short_label()),
+                    ReportCell::new(format!("{:.1}%", opp.extended_funding_rate_apr)),
+                    ReportCell::new(format!("{:.1}%", opp.pacifica_funding_rate_apr)),
+                    ReportCell::new(format!(
+                        "{:.2}/{:.2}/{:.2}",
+                        opp.extended_spread_pct, opp.pacifica_spread_pct, opp.cross_spread_pct
+                    )),
+                    ReportCell::new(format_mark_index_gaps(
+                        opp.extended_mark_index_gap_pct,
+                        opp.pacifica_mark_index_gap_pct,
+                    )),
+                    ReportCell::new(
+                        opp.entry_cost_bps.map(|bps| format!("{:.1}bp", bps)).unwrap_or_else(|| "N/A".to_string()),
+                    ),
+                    match opp.break_even_hours {
+                        Some(hours) => ReportCell::toned(
+                            format!("{:.1}h", hours),
+                            if hours <= self.hold_time_hours as f64 { Tone::Plain } else { Tone::Bad },
+                        ),
+                        None => ReportCell::toned("never", Tone::Bad),
+                    },
+                ]);
+            }
+            tables.push(table);
+        }
+
+        if !self.filtered.is_empty() {
+            let mut table = ReportTable::with_header(
+                "FILTERED OUT (TOP 10 BY VOLUME)",
+                &["Sym", "Volume", "Net APR", "Reason", "Detail"],
+            );
+            for row in &self.filtered {
+                table.rows.push(vec![
+                    ReportCell::new(truncate(&row.symbol, 4)),
+                    ReportCell::new(format_volume(row.total_volume_24h)),
+                    ReportCell::new(format!("{:.1}%", row.net_apr_pct)),
+                    ReportCell::toned(&row.reason, Tone::Bad),
+                    ReportCell::new(&row.detail),
+                ]);
+            }
+            tables.push(table);
+        }
+        tables
+    }
+}
+
+impl ReportView for StatusSnapshot {
+    fn tables(&self) -> Vec<ReportTable> {
+        let mut table = ReportTable::new("FUNDING RATE BOT STATUS");
+
+        if let Some(pos) = &self.position {
+            let format_time = |ts: u64| {
+                chrono::DateTime::from_timestamp(ts as i64, 0)
+                    .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+                    .unwrap_or_else(|| "Unknown".to_string())
+            };
+            let hours_remaining = pos.hours_until_rotation;
+
+            table.field_toned("Symbol", &pos.symbol, Tone::Bold);
+            table.field("Notional", format!("${:.2}", pos.target_notional_usd));
+            table.field("Opened", format_time(pos.opened_at));
+            table.field("Rotation", format_time(pos.rotation_at));
+            table.field_toned(
+                "Time Remaining",
+                format!("{:.1} hours", hours_remaining),
+                if hours_remaining < 1.0 { Tone::Bad } else { Tone::Good },
+            );
+
+            // Realized funding (actual payments, not the theoretical scan APR)
+            let funding_tone = Tone::of_sign(pos.realized_funding_usd);
+            table.field_toned(
+                "Realized Funding",
+                format!(
+                    "${:.4} (Ext ${:.4} / Pac ${:.4})",
+                    pos.realized_funding_usd, pos.realized_funding_extended_usd, pos.realized_funding_pacifica_usd
+                ),
+                funding_tone,
+            );
+            let yield_formatted = pos
+                .realized_funding_apr
+                .map(|apr| format!("{:.2}% APR", apr))
+                .unwrap_or_else(|| "N/A".to_string());
+            table.field_toned("Realized Yield", yield_formatted, funding_tone);
+            // Funding decay: the differential at open against what was actually seen since
+            let fmt_apr = |apr: Option<f64>| apr.map(|a| format!("{:.2}%", a)).unwrap_or_else(|| "N/A".to_string());
+            table.field(
+                "Funding APR (Entry → Avg)",
+                format!("{} → {}", fmt_apr(pos.entry_net_apr), fmt_apr(pos.average_net_apr)),
+            );
+
+            let leg_status = |active: bool| if active { ("ACTIVE", Tone::Good) } else { ("NONE", Tone::Bad) };
+            let (text, tone) = leg_status(pos.extended_leg_active);
+            table.field_toned("Extended Position", text, tone);
+            let (text, tone) = leg_status(pos.pacifica_leg_active);
+            table.field_toned("Pacifica Position", text, tone);
+
+            if let Some(pnl) = pos.extended_unrealized_pnl_usd {
+                table.field_toned("Extended PnL", format!("${:.2}", pnl), Tone::of_sign(pnl));
+            }
+            if let Some(entry) = pos.extended_entry_price {
+                table.field("Extended Entry", format!("${:.4}", entry));
+            }
+            if let (Some(entry), Some(size)) = (pos.pacifica_entry_price, pos.pacifica_size) {
+                table.field("Pacifica Entry", format!("${:.4}", entry));
+                table.field("Pacifica Size", format!("{:.6}", size));
+            }
+
+            // Basis drift since entry, kept apart from funding
+            let fmt_basis = |b: Option<f64>| b.map(|b| format!("{:+.4}%", b)).unwrap_or_else(|| "N/A".to_string());
+            table.field(
+                "Basis (Entry → Now)",
+                format!("{} → {}", fmt_basis(pos.entry_basis_pct), fmt_basis(pos.current_basis_pct)),
+            );
+            if let Some(captured) = pos.captured_basis_usd {
+                table.field_toned("Captured Basis", format!("${:.4}", captured), Tone::Good);
+            }
+            if let Some(projected) = pos.projected_funding_usd {
+                table.field_toned(
+                    "Projected Funding",
+                    format!(
+                        "${:.4} (Ext ${:.4} / Pac ${:.4}) over {:.1}h at current rates",
+                        projected,
+                        pos.projected_funding_extended_usd.unwrap_or(0.0),
+                        pos.projected_funding_pacifica_usd.unwrap_or(0.0),
+                        hours_remaining
+                    ),
+                    Tone::of_sign(projected),
+                );
+            }
+            if let Some(basis_pnl) = pos.basis_pnl_usd {
+                table.field_toned("Basis PnL", format!("${:.4}", basis_pnl), Tone::of_sign(basis_pnl));
+                let total = basis_pnl + pos.realized_funding_usd;
+                table.field_toned("Net PnL (Basis + Funding)", format!("${:.4}", total), Tone::of_sign(total));
+            }
+        } else {
+            table.field_toned("Status", "NO ACTIVE POSITION", Tone::Warn);
+        }
+
+        table.field("Total Rotations", self.total_rotations.to_string());
+        table.field("Closed Funding Total", format!("${:.4}", self.total_realized_funding_usd));
+        if let (Some(entry), Some(average)) = (self.last_closed_net_apr, self.last_closed_average_net_apr) {
+            table.field_toned(
+                "Last Close Funding APR",
+                format!("{:.2}% → {:.2}% ({:+.2}%)", entry, average, average - entry),
+                Tone::of_sign(average - entry),
+            );
+        }
+        if self.total_captured_basis_usd != 0.0 {
+            table.field("Closed Captured Basis", format!("${:.4}", self.total_captured_basis_usd));
+        }
+        if self.total_fees_usd != 0.0 || self.total_slippage_usd != 0.0 {
+            table.field(
+                "Execution Costs",
+                format!("${:.4} fees, ${:.4} slippage", self.total_fees_usd, self.total_slippage_usd),
+            );
+        }
+
+        if let Some(runtime) = &self.runtime {
+            let api_errors = runtime
+                .api_health
+                .iter()
+                .map(|h| format!("{} {}/{} ({:.1}%)", h.venue, h.errors, h.calls, h.error_rate_pct))
+                .collect::<Vec<_>>()
+                .join(", ");
+            table.field_toned(
+                "API Errors",
+                format!("{} - {:?}", api_errors, runtime.api_degradation),
+                if runtime.api_degradation == Degradation::Normal { Tone::Good } else { Tone::Bad },
+            );
+            if let Some(slowest) = runtime.api_endpoints.iter().max_by_key(|e| e.p95_latency_ms.unwrap_or(u64::MAX)) {
+                let p95 = slowest
+                    .p95_latency_ms
+                    .map(|ms| format!("{} ms", ms))
+                    .unwrap_or_else(|| format!("> {} ms", metrics::LATENCY_BUCKETS_MS[metrics::LATENCY_BUCKETS_MS.len() - 1]));
+                table.field("Slowest Endpoint", format!("{} (p95 {}, {} calls)", slowest.endpoint, p95, slowest.calls));
+            }
+        }
+
+        let mut tables = vec![table];
+        if let Some(risk) = &self.risk_report {
+            tables.push(stress_table(risk));
+        }
+        tables
+    }
+}
+
+/// Pair and per-leg outcome of each stressed price move, current prices first
+fn stress_table(risk: &RiskReport) -> ReportTable {
+    let title = match risk.basis_pct {
+        Some(basis) => format!("STRESS TEST {} (BASIS {:+.4}% KEPT)", risk.symbol, basis),
+        None => format!("STRESS TEST {}", risk.symbol),
+    };
+    let mut table = ReportTable::with_header(
+        &title,
+        &["Move", "Pair PnL", "Ext PnL", "Ext Margin", "Ext Liq", "Pac PnL", "Pac Margin", "Pac Liq"],
+    );
+    for scenario in std::iter::once(&risk.current).chain(&risk.scenarios) {
+        let mut row = vec![
+            ReportCell::new(if scenario.price_move_pct == 0.0 { "now".to_string() } else { format!("{:+.0}%", scenario.price_move_pct) }),
+            ReportCell::toned(format!("${:.2}", scenario.pnl_usd), Tone::of_sign(scenario.pnl_usd)),
+        ];
+        for venue in [Venue::Extended, Venue::Pacifica] {
+            match scenario.leg(venue) {
+                Some(leg) => row.extend([
+                    ReportCell::toned(format!("${:.2}", leg.pnl_usd), Tone::of_sign(leg.pnl_usd)),
+                    match leg.margin_usage_pct {
+                        Some(pct) => ReportCell::toned(format!("{:.0}%", pct), if pct >= 100.0 { Tone::Bad } else { Tone::Plain }),
+                        None => ReportCell::toned("no equity", Tone::Bad),
+                    },
+                    ReportCell::toned(
+                        format!("{:.1}%", leg.liquidation_distance_pct),
+                        if leg.liquidation_distance_pct < 5.0 { Tone::Bad } else { Tone::Plain },
+                    ),
+                ]),
+                None => row.extend((0..3).map(|_| ReportCell::new("N/A"))),
+            }
+        }
+        table.rows.push(row);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opportunity::{sample_opportunity, FilterStats, Opportunity};
+
+    fn opportunity(symbol: &str, volume: f64, net_apr: f64) -> Opportunity {
+        Opportunity {
+            extended_spread_pct: 0.02,
+            pacifica_spread_pct: 0.03,
+            cross_spread_pct: 0.05,
+            extended_funding_rate_apr: 5.0,
+            pacifica_funding_rate_apr: 5.0 + net_apr,
+            total_volume_24h: volume,
+            extended_volume_24h: volume / 2.0,
+            pacifica_volume_24h: volume / 2.0,
+            ..sample_opportunity(symbol, net_apr, Direction::LongExtendedShortPacifica)
+        }
+    }
+
+    #[test]
+    fn test_scan_summary_renderers() {
+        let passed = opportunity("BTC", 5_000_000.0, 25.0);
+        let candidate = |opp: Opportunity, filter_result| OpportunityCandidate { opportunity: opp, filter_result, filtered_cross_spread_pct: None };
+        let scan = ScanResult {
+            opportunities: vec![passed.clone()],
+            all_candidates: vec![
+                candidate(passed, FilterResult::Passed),
+                candidate(opportunity("DOGE", 200_000.0, 3.0), FilterResult::FailedVolume),
+                candidate(opportunity("ETH", 9_000_000.0, 2.0), FilterResult::FailedApr),
+            ],
+            stats: FilterStats {
+                total_common_symbols: 3,
+                filtered_by_volume: 1,
+                filtered_by_spread: 0,
+                filtered_by_apr: 1,
+                filtered_by_open_interest: 0,
+                filtered_by_mark_index_gap: 0,
+                passed_filters: 1,
+            },
+            scanned_at: 0,
+            data_availability: Default::default(),
+        };
+        let view = ScanSummaryView::new(&scan, &crate::opportunity::Config::default_config());
+
+        // Filtered rows are ordered by volume, highest first
+        let filtered: Vec<&str> = view.filtered.iter().map(|r| r.symbol.as_str()).collect();
+        assert_eq!(filtered, vec!["ETH", "DOGE"]);
+        assert_eq!(view.filtered[0].reason, "APR too low");
+
+        let tables = view.tables();
+        assert_eq!(tables.len(), 3);
+        assert_eq!(tables[1].rows[0][2], ReportCell::toned("25.0%", Tone::Good));
+
+        let plain = render_plain(&view);
+        assert!(plain.contains("OPPORTUNITIES (PASSED FILTERS)"));
+        assert!(plain.contains("Markets Scanned     3\n"));
+        assert!(!plain.contains('\u{1b}') && !plain.contains('│'));
+        assert!(render_pretty(&view).contains('│'));
+
+        let json: serde_json::Value = serde_json::from_str(&render_json(&view).unwrap()).unwrap();
+        assert_eq!(json["opportunities"][0]["net_apr_pct"], 25.0);
+        assert_eq!(json["filtered"][1]["detail"], "$200.0K");
+    }
+}
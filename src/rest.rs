@@ -1,13 +1,34 @@
 use crate::error::{ConnectorError, Result};
+use crate::api_capture;
 use crate::types::{
-    AccountInfo, ApiResponse, Balance, BidAsk, FeeInfo, FundingRateData, FundingRateInfo, MarketConfig,
-    MarketInfo, OrderBook, OrderRequest, OrderResponse, OrderSide, OrderType, PaginatedResponse,
-    Position, Settlement, TimeInForce,
+    AccountInfo, AccountTrade, ApiResponse, Balance, BidAsk, Candle, FeeInfo, FundingPayment, FundingRateInfo, LimitOrder,
+    MarketConfig, MarketInfo, MarketStats,
+    OrderBook, OrderInfo, OrderRequest, OrderResponse, OrderSide, OrderType, Position, Settlement, TimeInForce,
+    TpSlLegRequest, TpSlTrigger, TpSlType,
+};
+use crate::numeric::{
+    decimal_from_f64, format_fixed, parse_decimal, round_down_to_step, round_to_step,
+    step_decimals, Decimal,
 };
 use reqwest::Client;
+use rust_decimal::RoundingStrategy;
 use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
+const FUNDING_HISTORY_PAGE_LIMIT: u32 = 100;
+const FILL_POLL_INTERVAL_MS: u64 = 250;
+/// Lookups by external ID after an order submission timed out, before reporting it failed
+const SUBMIT_LOOKUP_ATTEMPTS: u32 = 3;
+const SUBMIT_LOOKUP_INTERVAL_MS: u64 = 500;
+/// Price limit (%) past the best level for market orders whose caller sets none
+pub const DEFAULT_MARKET_SLIPPAGE_PCT: f64 = 0.75;
+
+/// The order a submission that got no clear answer created, if it reached the exchange:
+/// any order with its external ID that Extended did not reject
+fn submitted_order(orders: &[OrderInfo]) -> Option<&OrderInfo> {
+    orders.iter().find(|o| o.status != "REJECTED")
+}
+
 /// REST API client for Extended exchange
 pub struct RestClient {
     client: Client,
@@ -43,6 +64,11 @@ impl RestClient {
         })
     }
 
+    /// API base URL (including the /api/v1 prefix)
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
     /// Get orderbook for a specific market
     pub async fn get_orderbook(&self, market: &str) -> Result<OrderBook> {
         let url = format!("{}/info/markets/{}/orderbook", self.base_url, market);
@@ -55,7 +81,7 @@ impl RestClient {
             request = request.header("X-Api-Key", api_key);
         }
 
-        let response = request.send().await?;
+        let response = api_capture::observe_http("extended GET /info/markets/{market}/orderbook", request.send()).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -145,7 +171,7 @@ impl RestClient {
             request = request.header("X-Api-Key", api_key);
         }
 
-        let response = request.send().await?;
+        let response = api_capture::observe_http("extended GET /info/markets", request.send()).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -178,9 +204,8 @@ impl RestClient {
         }
     }
 
-    /// Get latest funding rate for a specific market from market stats endpoint
-    /// The fundingRate field represents the current hourly funding rate
-    pub async fn get_funding_rate(&self, market: &str) -> Result<Option<FundingRateInfo>> {
+    /// Get 24h statistics of a market: volume, open interest, mark and index price
+    pub async fn get_market_stats(&self, market: &str) -> Result<MarketStats> {
         let url = format!("{}/info/markets/{}/stats", self.base_url, market);
         debug!("Fetching market stats for {} from {}", market, url);
 
@@ -190,56 +215,100 @@ impl RestClient {
             request = request.header("X-Api-Key", api_key);
         }
 
-        let response = request.send().await?;
+        let response = api_capture::observe_http("extended GET /info/markets/{market}/stats", request.send()).await?;
 
-        if !response.status().is_success() {
-            let status = response.status();
+        let status = response.status();
+        if !status.is_success() {
             let error_text = response
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            warn!("Could not fetch market stats for {}: {} - {}", market, status, error_text);
-            return Ok(None);
+            return Err(ConnectorError::ApiError(format!(
+                "HTTP {}: {}",
+                status, error_text
+            )));
         }
 
-        #[derive(serde::Deserialize)]
-        struct MarketStatsResponse {
-            status: String,
-            data: Option<MarketStatsData>,
+        let api_response: ApiResponse<MarketStats> = response.json().await?;
+
+        api_response.data.ok_or_else(|| {
+            let error_msg = api_response
+                .error
+                .map(|e| format!("{}: {}", e.code, e.message))
+                .unwrap_or_else(|| format!("No market stats for {}", market));
+            ConnectorError::ApiError(error_msg)
+        })
+    }
+
+    /// Get the latest trade-price candles of a market, oldest first
+    ///
+    /// `interval` is an ISO 8601 duration ("PT1M", "PT5M", "PT1H", ...)
+    pub async fn get_candles(&self, market: &str, interval: &str, limit: u32) -> Result<Vec<Candle>> {
+        let url = format!(
+            "{}/info/candles/{}/trades?interval={}&limit={}",
+            self.base_url, market, interval, limit
+        );
+        debug!("Fetching candles for {} from {}", market, url);
+
+        let mut request = self.client.get(&url);
+
+        if let Some(api_key) = &self.api_key {
+            request = request.header("X-Api-Key", api_key);
         }
 
-        #[derive(serde::Deserialize)]
-        #[allow(dead_code)]
-        struct MarketStatsData {
-            #[serde(rename = "fundingRate")]
-            funding_rate: String,
+        let response = api_capture::observe_http("extended GET /info/candles/{market}/trades", request.send()).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(ConnectorError::ApiError(format!(
+                "HTTP {}: {}",
+                status, error_text
+            )));
         }
 
-        let api_response: MarketStatsResponse = response.json().await?;
+        let api_response: ApiResponse<Vec<Candle>> = response.json().await?;
 
-        match api_response.data {
-            Some(data) => {
-                let rate: f64 = data.funding_rate.parse().unwrap_or(0.0);
-                let rate_percentage = rate * 100.0;
-                let is_positive = rate >= 0.0;
-                let now = chrono::Utc::now().timestamp_millis() as u64;
-
-                let info = FundingRateInfo {
-                    market: market.to_string(),
-                    rate,
-                    rate_percentage,
-                    timestamp: now,
-                    is_positive,
-                };
+        let mut candles = api_response.data.ok_or_else(|| {
+            let error_msg = api_response
+                .error
+                .map(|e| format!("{}: {}", e.code, e.message))
+                .unwrap_or_else(|| format!("No candles for {}", market));
+            ConnectorError::ApiError(error_msg)
+        })?;
+        candles.sort_by_key(|c| c.timestamp);
+        Ok(candles)
+    }
 
-                debug!("Fetched funding rate for {} from stats: {}", market, info.rate_percentage);
-                Ok(Some(info))
-            }
-            None => {
-                debug!("No market stats data available for {}", market);
-                Ok(None)
+    /// Get latest funding rate for a specific market from market stats endpoint
+    /// The fundingRate field represents the current hourly funding rate
+    pub async fn get_funding_rate(&self, market: &str) -> Result<Option<FundingRateInfo>> {
+        let stats = match self.get_market_stats(market).await {
+            Ok(stats) => stats,
+            Err(ConnectorError::ApiError(e)) => {
+                warn!("Could not fetch market stats for {}: {}", market, e);
+                return Ok(None);
             }
-        }
+            Err(e) => return Err(e),
+        };
+
+        let Some(rate) = stats.funding_rate_f64() else {
+            debug!("No funding rate in market stats for {}", market);
+            return Ok(None);
+        };
+        let info = FundingRateInfo {
+            market: market.to_string(),
+            rate,
+            rate_percentage: rate * 100.0,
+            timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            is_positive: rate >= 0.0,
+        };
+
+        debug!("Fetched funding rate for {} from stats: {}", market, info.rate_percentage);
+        Ok(Some(info))
     }
 
     /// Get funding rates for all active markets
@@ -297,12 +366,12 @@ impl RestClient {
             ConnectorError::ApiError("API key required for account info".to_string())
         })?;
 
-        let response = self
+        let request = self
             .client
             .get(&url)
             .header("X-Api-Key", api_key)
-            .send()
-            .await?;
+            .send();
+        let response = api_capture::observe_http("extended GET /user/account/info", request).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -338,6 +407,53 @@ impl RestClient {
         }
     }
 
+    /// List the accounts (sub-accounts and their vaults) the API key can see (requires API key)
+    pub async fn get_accounts(&self) -> Result<Vec<AccountInfo>> {
+        let url = format!("{}/user/accounts", self.base_url);
+        debug!("Fetching accounts from {}", url);
+
+        let api_key = self.api_key.as_ref().ok_or_else(|| {
+            ConnectorError::ApiError("API key required for accounts".to_string())
+        })?;
+
+        let request = self
+            .client
+            .get(&url)
+            .header("X-Api-Key", api_key)
+            .send();
+        let response = api_capture::observe_http("extended GET /user/accounts", request).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            error!("API error: {} - {}", status, error_text);
+            return Err(ConnectorError::ApiError(format!(
+                "HTTP {}: {}",
+                status, error_text
+            )));
+        }
+
+        let api_response: ApiResponse<Vec<AccountInfo>> = response.json().await?;
+
+        match api_response.data {
+            Some(accounts) => {
+                debug!("Fetched {} accounts", accounts.len());
+                Ok(accounts)
+            }
+            None => {
+                let error_msg = api_response
+                    .error
+                    .map(|e| format!("{}: {}", e.code, e.message))
+                    .unwrap_or_else(|| "Unknown error".to_string());
+                error!("API error response: {}", error_msg);
+                Err(ConnectorError::ApiError(error_msg))
+            }
+        }
+    }
+
     /// Get user positions, optionally filtered by market (requires API key)
     pub async fn get_positions(&self, market: Option<&str>) -> Result<Vec<Position>> {
         let url = if let Some(m) = market {
@@ -351,12 +467,12 @@ impl RestClient {
             ConnectorError::ApiError("API key required for positions".to_string())
         })?;
 
-        let response = self
+        let request = self
             .client
             .get(&url)
             .header("X-Api-Key", api_key)
-            .send()
-            .await?;
+            .send();
+        let response = api_capture::observe_http("extended GET /user/positions", request).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -389,6 +505,91 @@ impl RestClient {
         }
     }
 
+    /// Get funding payments settled on the account (requires API key)
+    ///
+    /// Follows the cursor pagination until all pages are consumed.
+    ///
+    /// # Arguments
+    /// * `market` - Optional market filter (e.g., "BTC-USD")
+    /// * `from_time_ms` - Only return payments paid at or after this timestamp (milliseconds)
+    pub async fn get_funding_payments(
+        &self,
+        market: Option<&str>,
+        from_time_ms: Option<u64>,
+    ) -> Result<Vec<FundingPayment>> {
+        let api_key = self.api_key.as_ref().ok_or_else(|| {
+            ConnectorError::ApiError("API key required for funding history".to_string())
+        })?;
+
+        let mut payments = Vec::new();
+        let mut cursor: Option<i64> = None;
+
+        loop {
+            let mut url = format!(
+                "{}/user/funding/history?limit={}",
+                self.base_url, FUNDING_HISTORY_PAGE_LIMIT
+            );
+            if let Some(m) = market {
+                url.push_str(&format!("&market={}", m));
+            }
+            if let Some(from) = from_time_ms {
+                url.push_str(&format!("&fromTime={}", from));
+            }
+            if let Some(c) = cursor {
+                url.push_str(&format!("&cursor={}", c));
+            }
+            debug!("Fetching funding payments from {}", url);
+
+            let request = self
+                .client
+                .get(&url)
+                .header("X-Api-Key", api_key)
+                .send();
+            let response = api_capture::observe_http("extended GET /user/funding/history", request).await?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                error!("API error: {} - {}", status, error_text);
+                return Err(ConnectorError::ApiError(format!(
+                    "HTTP {}: {}",
+                    status, error_text
+                )));
+            }
+
+            let api_response: ApiResponse<Vec<FundingPayment>> = response.json().await?;
+
+            let page = match api_response.data {
+                Some(page) => page,
+                None => {
+                    let error_msg = api_response
+                        .error
+                        .map(|e| format!("{}: {}", e.code, e.message))
+                        .unwrap_or_else(|| "Unknown error".to_string());
+                    error!("API error response: {}", error_msg);
+                    return Err(ConnectorError::ApiError(error_msg));
+                }
+            };
+
+            let page_len = page.len();
+            payments.extend(page);
+
+            let next_cursor = api_response.pagination.and_then(|p| p.cursor);
+            match next_cursor {
+                Some(c) if page_len >= FUNDING_HISTORY_PAGE_LIMIT as usize && Some(c) != cursor => {
+                    cursor = Some(c);
+                }
+                _ => break,
+            }
+        }
+
+        info!("Fetched {} funding payments", payments.len());
+        Ok(payments)
+    }
+
     /// Get account balance and margin information (requires API key)
     pub async fn get_balance(&self) -> Result<Balance> {
         let url = format!("{}/user/balance", self.base_url);
@@ -398,12 +599,12 @@ impl RestClient {
             ConnectorError::ApiError("API key required for balance".to_string())
         })?;
 
-        let response = self
+        let request = self
             .client
             .get(&url)
             .header("X-Api-Key", api_key)
-            .send()
-            .await?;
+            .send();
+        let response = api_capture::observe_http("extended GET /user/balance", request).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -462,14 +663,14 @@ impl RestClient {
 
         debug!("Sending PATCH request: {}", request_body);
 
-        let response = self
+        let request = self
             .client
             .patch(&url)
             .header("X-Api-Key", api_key)
             .header("Content-Type", "application/json")
             .json(&request_body)
-            .send()
-            .await?;
+            .send();
+        let response = api_capture::observe_http_json("extended PATCH /user/leverage", &request_body, request).await?;
 
         // Get response text for debugging
         let response_text = response.text().await?;
@@ -518,12 +719,12 @@ impl RestClient {
             ConnectorError::ApiError("API key required for fee info".to_string())
         })?;
 
-        let response = self
+        let request = self
             .client
             .get(&url)
             .header("X-Api-Key", api_key)
-            .send()
-            .await?;
+            .send();
+        let response = api_capture::observe_http("extended GET /user/fees", request).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -568,7 +769,7 @@ impl RestClient {
         let url = format!("{}/info/markets", self.base_url);
         debug!("Fetching market config for {} from {}", market, url);
 
-        let response = self.client.get(&url).send().await?;
+        let response = api_capture::observe_http("extended GET /info/markets", self.client.get(&url).send()).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -626,6 +827,7 @@ impl RestClient {
     /// - stark_public_key: Stark public key
     /// - vault_id: Collateral position ID
     /// - reduce_only: If true, order can only reduce existing position (for closing)
+    #[allow(clippy::too_many_arguments)]
     pub async fn place_market_order(
         &self,
         market: &str,
@@ -636,6 +838,43 @@ impl RestClient {
         vault_id: &str,
         reduce_only: bool,
         max_base_size: Option<f64>,
+    ) -> Result<OrderResponse> {
+        let external_id = format!("rust-{}", chrono::Utc::now().timestamp_millis());
+        self.place_market_order_with_id(
+            market,
+            side,
+            notional_usd,
+            stark_private_key,
+            stark_public_key,
+            vault_id,
+            reduce_only,
+            max_base_size,
+            DEFAULT_MARKET_SLIPPAGE_PCT,
+            &external_id,
+        )
+        .await
+    }
+
+    /// Place a market order with a caller-chosen external ID
+    ///
+    /// Deriving `external_id` from the position and attempt number lets every attempt be
+    /// looked up afterwards with [`RestClient::get_orders_by_external_id`]. When the
+    /// submission times out or gets a 5xx answer, the order is looked up by its ID
+    /// before an error is returned: if it reached Extended, it is returned as placed.
+    /// The order is priced `slippage_pct` past the best ask (buy) or bid (sell).
+    #[allow(clippy::too_many_arguments)]
+    pub async fn place_market_order_with_id(
+        &self,
+        market: &str,
+        side: OrderSide,
+        notional_usd: f64,
+        stark_private_key: &str,
+        stark_public_key: &str,
+        vault_id: &str,
+        reduce_only: bool,
+        max_base_size: Option<f64>,
+        slippage_pct: f64,
+        external_id: &str,
     ) -> Result<OrderResponse> {
         info!(
             "Placing market {} order on {} for ${:.2}",
@@ -644,7 +883,6 @@ impl RestClient {
 
         // 1. Get market configuration for asset IDs and resolutions
         let market_config = self.get_market_config(market).await?;
-        let l2_config = &market_config.l2_config;
 
         // 2. Get current orderbook to calculate price and quantity
         let orderbook = self.get_orderbook(market).await?;
@@ -662,46 +900,49 @@ impl RestClient {
             ConnectorError::Other("Failed to parse best ask price".to_string())
         })?;
 
-        // 3. Calculate market order price
-        // Buy: ask * 1.0075 (0.75% above best ask)
-        // Sell: bid * 0.9925 (0.75% below best bid)
+        // 3. Calculate market order price: slippage_pct above the best ask (buy) or
+        //    below the best bid (sell)
         let raw_price = match side {
-            OrderSide::Buy => best_ask * 1.0075,
-            OrderSide::Sell => best_bid * 0.9925,
+            OrderSide::Buy => best_ask * (1.0 + slippage_pct / 100.0),
+            OrderSide::Sell => best_bid * (1.0 - slippage_pct / 100.0),
         };
 
         // Get price precision from market config
         let price_precision = market_config.trading_config.get_price_precision();
-        let price_multiplier = 10f64.powi(price_precision as i32);
 
         // Round price to correct precision BEFORE using it for calculations
         // This ensures our signed amounts match what the server recalculates
-        let price = (raw_price * price_multiplier).round() / price_multiplier;
+        let price = decimal_from_f64(raw_price).round_dp_with_strategy(
+            price_precision as u32,
+            RoundingStrategy::MidpointAwayFromZero,
+        );
+        if price <= Decimal::ZERO {
+            return Err(ConnectorError::Other("Computed order price is not positive".to_string()));
+        }
 
         // 4. Calculate quantity
         //    For reduce-only closes, derive quantity directly from the current
         //    base position size to avoid dust from price/notional rounding.
         //    For normal opens, if a desired base size is provided, use it directly
         //    so Extended matches the targeted base size precisely.
-        let mut raw_quantity = if reduce_only {
-            if let Some(max_base) = max_base_size { max_base } else { notional_usd / price }
-        } else {
-            if let Some(desired_base) = max_base_size { desired_base } else { notional_usd / price }
+        let raw_quantity = match max_base_size {
+            Some(base) => decimal_from_f64(base),
+            None => decimal_from_f64(notional_usd) / price,
         };
 
         // Get trading config constraints
-        let min_size: f64 = market_config.trading_config.min_order_size.parse()
-            .map_err(|e| ConnectorError::Other(format!("Failed to parse minOrderSize: {}", e)))?;
-        let size_increment: f64 = market_config.trading_config.min_order_size_change.parse()
-            .map_err(|e| ConnectorError::Other(format!("Failed to parse minOrderSizeChange: {}", e)))?;
+        let min_size = parse_decimal(&market_config.trading_config.min_order_size)
+            .ok_or_else(|| ConnectorError::Other("Failed to parse minOrderSize".to_string()))?;
+        let size_increment = parse_decimal(&market_config.trading_config.min_order_size_change)
+            .ok_or_else(|| ConnectorError::Other("Failed to parse minOrderSizeChange".to_string()))?;
 
         // Round quantity according to context:
         // - Normal orders: nearest increment
         // - Reduce-only: round DOWN to avoid exceeding current position size
         let mut quantity = if reduce_only {
-            (raw_quantity / size_increment).floor() * size_increment
+            round_down_to_step(raw_quantity, size_increment)
         } else {
-            (raw_quantity / size_increment).round() * size_increment
+            round_to_step(raw_quantity, size_increment)
         };
 
         // For normal orders, enforce exchange minimum. For reduce-only, avoid bumping up which can exceed size.
@@ -709,36 +950,251 @@ impl RestClient {
             quantity = quantity.max(min_size);
         }
 
-        // Calculate formatting precisions
-        let qty_precision = if size_increment >= 1.0 {
-            0
-        } else {
-            (-size_increment.log10()).ceil() as usize
-        };
-        let price_precision = market_config.trading_config.get_price_precision();
-
-        // Format and parse back to get EXACT values that will be sent to the server
-        // This ensures our signature calculation matches the server's
-        let quantity_formatted = format!("{:.prec$}", quantity, prec = qty_precision);
-        let price_formatted = format!("{:.prec$}", price, prec = price_precision);
-        let quantity_exact: f64 = quantity_formatted.parse().unwrap();
-        let price_exact: f64 = price_formatted.parse().unwrap();
-
         info!(
             "Order details - Price: ${:.2}, Raw Qty: {:.6}, Rounded Qty: {}, Min: {}, Best Bid: ${:.2}, Best Ask: ${:.2}",
             price, raw_quantity, quantity, min_size, best_bid, best_ask
         );
 
-        // 5. Get taker fee
+        // Market orders are implemented as limit IOC orders with aggressive pricing, valid for 1 hour
+        let expiry_epoch_millis = (chrono::Utc::now().timestamp_millis() + (3600 * 1000)) as u64;
+        self.submit_order(
+            &market_config,
+            OrderType::Limit,
+            side,
+            quantity,
+            price,
+            TimeInForce::IOC,
+            expiry_epoch_millis,
+            reduce_only,
+            false,
+            None,
+            None,
+            stark_private_key,
+            stark_public_key,
+            vault_id,
+            external_id,
+        )
+        .await
+    }
+
+    /// Sign a minimum-size buy on `market` at the best ask exactly as an order would be
+    /// signed, without sending it. Surfaces a broken signer or malformed Stark keys before
+    /// a live order does.
+    pub async fn sign_test_order(
+        &self,
+        market: &str,
+        stark_private_key: &str,
+        stark_public_key: &str,
+        vault_id: &str,
+    ) -> Result<Settlement> {
+        let market_config = self.get_market_config(market).await?;
+        let orderbook = self.get_orderbook(market).await?;
+        let best_ask: f64 = orderbook
+            .ask
+            .first()
+            .ok_or_else(|| ConnectorError::ApiError("Orderbook has no asks".to_string()))?
+            .price
+            .parse()
+            .map_err(|_| ConnectorError::Other("Failed to parse best ask price".to_string()))?;
+
+        let price_precision = market_config.trading_config.get_price_precision() as u32;
+        let min_size = parse_decimal(&market_config.trading_config.min_order_size)
+            .ok_or_else(|| ConnectorError::Other("Failed to parse minOrderSize".to_string()))?;
+        let size_increment = parse_decimal(&market_config.trading_config.min_order_size_change)
+            .ok_or_else(|| ConnectorError::Other("Failed to parse minOrderSizeChange".to_string()))?;
+        let price = decimal_from_f64(best_ask).round_dp_with_strategy(price_precision, RoundingStrategy::MidpointAwayFromZero);
+        let quantity_exact: f64 = format_fixed(min_size, step_decimals(size_increment)).parse().unwrap_or(0.0);
+        let price_exact: f64 = format_fixed(price, price_precision).parse().unwrap_or(0.0);
+
         let fee_info = self.get_fees(market).await?;
         let taker_fee_rate: f64 = fee_info.taker_fee_str().parse().unwrap_or(0.0006);
+        let expiry_epoch_millis = (chrono::Utc::now().timestamp_millis() + (3600 * 1000)) as u64;
+        self.sign_settlement(
+            &market_config,
+            &OrderSide::Buy,
+            quantity_exact,
+            price_exact,
+            taker_fee_rate,
+            chrono::Utc::now().timestamp() as u64,
+            expiry_epoch_millis,
+            stark_private_key,
+            stark_public_key,
+            vault_id,
+        )
+    }
+
+    /// Place a limit order, optionally post-only and with attached take-profit/stop-loss
+    ///
+    /// Price is rounded to the market tick size and quantity to the size step
+    /// (down for reduce-only orders). Each TP/SL leg is signed separately as an
+    /// opposite-side order for the same quantity at the leg's price.
+    pub async fn place_limit_order(
+        &self,
+        order: &LimitOrder,
+        stark_private_key: &str,
+        stark_public_key: &str,
+        vault_id: &str,
+    ) -> Result<OrderResponse> {
+        order.validate().map_err(ConnectorError::Other)?;
+
+        info!(
+            "Placing limit {} order on {}: {} @ {} ({:?}{}{})",
+            order.side,
+            order.market,
+            order.qty,
+            order.price,
+            order.time_in_force,
+            if order.post_only { ", post-only" } else { "" },
+            if order.take_profit.is_some() || order.stop_loss.is_some() { ", TP/SL" } else { "" }
+        );
+
+        let market_config = self.get_market_config(&order.market).await?;
+
+        let price_precision = market_config.trading_config.get_price_precision();
+        let price = decimal_from_f64(order.price).round_dp_with_strategy(
+            price_precision as u32,
+            RoundingStrategy::MidpointAwayFromZero,
+        );
+        if price <= Decimal::ZERO {
+            return Err(ConnectorError::Other("Computed order price is not positive".to_string()));
+        }
+
+        let min_size = parse_decimal(&market_config.trading_config.min_order_size)
+            .ok_or_else(|| ConnectorError::Other("Failed to parse minOrderSize".to_string()))?;
+        let size_increment = parse_decimal(&market_config.trading_config.min_order_size_change)
+            .ok_or_else(|| ConnectorError::Other("Failed to parse minOrderSizeChange".to_string()))?;
+        let quantity = if order.reduce_only {
+            round_down_to_step(decimal_from_f64(order.qty), size_increment)
+        } else {
+            round_to_step(decimal_from_f64(order.qty), size_increment)
+        };
+        if quantity < min_size && !order.reduce_only {
+            return Err(ConnectorError::Other(format!(
+                "Order quantity {} is below the minimum order size {}",
+                quantity, min_size
+            )));
+        }
+        if quantity <= Decimal::ZERO {
+            return Err(ConnectorError::Other("Order quantity rounds to zero".to_string()));
+        }
+
+        let external_id = order
+            .external_id
+            .clone()
+            .unwrap_or_else(|| format!("rust-{}", chrono::Utc::now().timestamp_millis()));
+        let expiry_epoch_millis = chrono::Utc::now().timestamp_millis() as u64 + order.expiry_millis;
+
+        self.submit_order(
+            &market_config,
+            OrderType::Limit,
+            order.side.clone(),
+            quantity,
+            price,
+            order.time_in_force.clone(),
+            expiry_epoch_millis,
+            order.reduce_only,
+            order.post_only,
+            order.take_profit.as_ref(),
+            order.stop_loss.as_ref(),
+            stark_private_key,
+            stark_public_key,
+            vault_id,
+            &external_id,
+        )
+        .await
+    }
+
+    /// Rest a take-profit and/or stop-loss on the whole open position in `market`
+    ///
+    /// Sent as a reduce-only `TPSL` order on `close_side` (sell for a long) whose legs
+    /// close the entire position when triggered, whatever its size by then. `qty` is
+    /// the current position size and the order's own price is the worst price of the
+    /// stop-loss (or take-profit) leg; both are only used for signing. The order lives
+    /// for `expiry_millis`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn place_position_tpsl(
+        &self,
+        market: &str,
+        close_side: OrderSide,
+        qty: f64,
+        take_profit: Option<&TpSlTrigger>,
+        stop_loss: Option<&TpSlTrigger>,
+        expiry_millis: u64,
+        stark_private_key: &str,
+        stark_public_key: &str,
+        vault_id: &str,
+        external_id: &str,
+    ) -> Result<OrderResponse> {
+        let Some(worst) = stop_loss.or(take_profit) else {
+            return Err(ConnectorError::Other("Position TP/SL needs a take-profit or a stop-loss".to_string()));
+        };
+        info!(
+            "Placing position TP/SL on {} ({} {}): TP {} / SL {}",
+            market,
+            close_side,
+            qty,
+            take_profit.map(|t| t.trigger_price.to_string()).unwrap_or_else(|| "none".to_string()),
+            stop_loss.map(|t| t.trigger_price.to_string()).unwrap_or_else(|| "none".to_string())
+        );
+
+        let market_config = self.get_market_config(market).await?;
+        let price_precision = market_config.trading_config.get_price_precision();
+        let price = decimal_from_f64(worst.price)
+            .round_dp_with_strategy(price_precision as u32, RoundingStrategy::MidpointAwayFromZero);
+        if price <= Decimal::ZERO {
+            return Err(ConnectorError::Other("Computed order price is not positive".to_string()));
+        }
+        let size_increment = parse_decimal(&market_config.trading_config.min_order_size_change)
+            .ok_or_else(|| ConnectorError::Other("Failed to parse minOrderSizeChange".to_string()))?;
+        let quantity = round_down_to_step(decimal_from_f64(qty), size_increment);
+        if quantity <= Decimal::ZERO {
+            return Err(ConnectorError::Other("Order quantity rounds to zero".to_string()));
+        }
+
+        let expiry_epoch_millis = chrono::Utc::now().timestamp_millis() as u64 + expiry_millis;
+        self.submit_order(
+            &market_config,
+            OrderType::Tpsl,
+            close_side,
+            quantity,
+            price,
+            TimeInForce::GTT,
+            expiry_epoch_millis,
+            true,
+            false,
+            take_profit,
+            stop_loss,
+            stark_private_key,
+            stark_public_key,
+            vault_id,
+            external_id,
+        )
+        .await
+    }
+
+    /// Compute signed Stark amounts for one order leg and sign them
+    #[allow(clippy::too_many_arguments)]
+    fn sign_settlement(
+        &self,
+        market_config: &MarketConfig,
+        side: &OrderSide,
+        quantity_exact: f64,
+        price_exact: f64,
+        fee_rate: f64,
+        nonce: u64,
+        expiry_epoch_millis: u64,
+        stark_private_key: &str,
+        stark_public_key: &str,
+        vault_id: &str,
+    ) -> Result<Settlement> {
+        let l2_config = &market_config.l2_config;
 
-        // 6. Calculate signed amounts using EXACT formatted values
+        // Calculate signed amounts using EXACT formatted values
         let (base_amount, quote_amount, fee_amount) = crate::signature::calculate_signed_amounts(
-            &side,
+            side,
             quantity_exact,
             price_exact,
-            taker_fee_rate,
+            fee_rate,
             l2_config.synthetic_resolution,
             l2_config.collateral_resolution,
         );
@@ -748,27 +1204,14 @@ impl RestClient {
             base_amount, quote_amount, fee_amount
         );
 
-        // 7. Generate order ID and nonce
-        // Use a simpler timestamp-based ID
-        let now = chrono::Utc::now();
-        let order_id = format!("rust-{}", now.timestamp_millis());
-        // Nonce must be between 1 and 2^31 per Extended API requirements
-        // Use timestamp in seconds (current ~1.7B, fits well under 2^31 = 2.1B)
-        let nonce = now.timestamp() as u64;
-
-        // Do not log nonce values to avoid leaking signing metadata
-
-        // 8. Set expiry (1 hour from now for market order)
-        let expiry_epoch_millis = (chrono::Utc::now().timestamp_millis() + (3600 * 1000)) as u64;
-
-        // 9. Determine environment (mainnet vs testnet)
+        // Determine environment (mainnet vs testnet)
         let domain_chain_id = if self.base_url.contains("sepolia") {
             "SN_SEPOLIA"
         } else {
             "SN_MAIN"
         };
 
-        // 10. Sign the order using Python SDK
+        // Sign the order using Python SDK
         let signature = crate::signature::sign_order(
             &l2_config.synthetic_id,
             &l2_config.collateral_id,
@@ -783,33 +1226,137 @@ impl RestClient {
             domain_chain_id,
         )?;
 
-        // 11. Create order request
-        // Note: Market orders are implemented as limit IOC orders with aggressive pricing
+        Ok(Settlement {
+            signature,
+            stark_key: stark_public_key.to_string(),
+            collateral_position: vault_id.to_string(),
+        })
+    }
+
+    /// Sign and submit an order whose price and quantity are already rounded
+    ///
+    /// TP/SL legs of a `Tpsl` order close the whole position, those of any other
+    /// order the order's own quantity.
+    #[allow(clippy::too_many_arguments)]
+    async fn submit_order(
+        &self,
+        market_config: &MarketConfig,
+        order_type: OrderType,
+        side: OrderSide,
+        quantity: Decimal,
+        price: Decimal,
+        time_in_force: TimeInForce,
+        expiry_epoch_millis: u64,
+        reduce_only: bool,
+        post_only: bool,
+        take_profit: Option<&TpSlTrigger>,
+        stop_loss: Option<&TpSlTrigger>,
+        stark_private_key: &str,
+        stark_public_key: &str,
+        vault_id: &str,
+        external_id: &str,
+    ) -> Result<OrderResponse> {
+        let market = market_config.name.as_str();
+        let price_precision = market_config.trading_config.get_price_precision() as u32;
+        let size_increment = parse_decimal(&market_config.trading_config.min_order_size_change)
+            .ok_or_else(|| ConnectorError::Other("Failed to parse minOrderSizeChange".to_string()))?;
+        let qty_precision = step_decimals(size_increment);
+
+        // Format to get EXACT values that will be sent to the server
+        // This ensures our signature calculation matches the server's
+        let quantity_formatted = format_fixed(quantity, qty_precision);
+        let price_formatted = format_fixed(price, price_precision);
+        let quantity_exact: f64 = quantity_formatted.parse().unwrap();
+        let price_exact: f64 = price_formatted.parse().unwrap();
+
+        // Get taker fee (the signed fee is a cap, so it also covers maker fills)
+        let fee_info = self.get_fees(market).await?;
+        let taker_fee_rate: f64 = fee_info.taker_fee_str().parse().unwrap_or(0.0006);
+
+        // Nonce must be between 1 and 2^31 per Extended API requirements
+        // Use timestamp in seconds (current ~1.7B, fits well under 2^31 = 2.1B)
+        // Do not log nonce values to avoid leaking signing metadata
+        let nonce = chrono::Utc::now().timestamp() as u64;
+
+        let settlement = self.sign_settlement(
+            market_config,
+            &side,
+            quantity_exact,
+            price_exact,
+            taker_fee_rate,
+            nonce,
+            expiry_epoch_millis,
+            stark_private_key,
+            stark_public_key,
+            vault_id,
+        )?;
+
+        // TP/SL legs close what this order opens: opposite side, same quantity, own price.
+        // They are signed with the order's nonce, as in the Python SDK: the request carries
+        // no nonce of their own, so Extended checks their settlements against this one.
+        let close_side = match side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        let sign_leg = |trigger: &TpSlTrigger| -> Result<TpSlLegRequest> {
+            let round = |value: f64| {
+                format_fixed(
+                    decimal_from_f64(value).round_dp_with_strategy(price_precision, RoundingStrategy::MidpointAwayFromZero),
+                    price_precision,
+                )
+            };
+            let leg_price = round(trigger.price);
+            let leg_settlement = self.sign_settlement(
+                market_config,
+                &close_side,
+                quantity_exact,
+                leg_price.parse().unwrap_or(trigger.price),
+                taker_fee_rate,
+                nonce,
+                expiry_epoch_millis,
+                stark_private_key,
+                stark_public_key,
+                vault_id,
+            )?;
+            Ok(TpSlLegRequest {
+                trigger_price: round(trigger.trigger_price),
+                trigger_price_type: trigger.trigger_price_type,
+                price: leg_price,
+                price_type: trigger.price_type,
+                settlement: leg_settlement,
+            })
+        };
+        let take_profit = take_profit.map(&sign_leg).transpose()?;
+        let stop_loss = stop_loss.map(&sign_leg).transpose()?;
+        let tp_sl_type = (take_profit.is_some() || stop_loss.is_some()).then_some(match order_type {
+            OrderType::Tpsl => TpSlType::Position,
+            _ => TpSlType::Order,
+        });
 
         info!("Formatting - Qty precision: {}, Price precision: {}", qty_precision, price_precision);
 
+        let order_id = external_id.to_string();
         let order_request = OrderRequest {
             id: order_id.clone(),
             market: market.to_string(),
-            order_type: OrderType::Limit,  // Market orders use Limit type with IOC
+            order_type,
             side: side.clone(),
             qty: quantity_formatted,
             price: price_formatted,
-            time_in_force: TimeInForce::IOC,
+            time_in_force,
             expiry_epoch_millis,
             fee: format!("{:.6}", taker_fee_rate),  // Fee RATE, not calculated amount
             nonce: nonce.to_string(),
-            settlement: Settlement {
-                signature,
-                stark_key: stark_public_key.to_string(),
-                collateral_position: vault_id.to_string(),
-            },
+            settlement,
             self_trade_protection_level: "ACCOUNT".to_string(),
             reduce_only,
-            post_only: false,
+            post_only,
+            tp_sl_type,
+            take_profit,
+            stop_loss,
         };
 
-        // 9. Submit order
+        // Submit order
         let url = format!("{}/user/order", self.base_url);
         debug!("Submitting order to {} (id: {}, market: {}, side: {})",
             url, order_id, market, match side { OrderSide::Buy => "BUY", OrderSide::Sell => "SELL" });
@@ -818,18 +1365,43 @@ impl RestClient {
             ConnectorError::ApiError("API key required for order placement".to_string())
         })?;
 
-        let response = self
+        let request = self
             .client
             .post(&url)
             .header("X-Api-Key", api_key)
             .header("Accept", "application/json")
             .header("Content-Type", "application/json")
             .json(&order_request)
-            .send()
-            .await?;
-
-        let status = response.status();
-        let response_text = response.text().await?;
+            .send();
+        // A timeout or a gateway error does not say whether the order reached the matching
+        // engine. Look it up by its external ID before failing, so the caller's retry
+        // (under a new ID) cannot execute the same trade twice.
+        let (status, response_text) = match api_capture::observe_http_json("extended POST /user/order", &order_request, request).await {
+            Ok(response) if !response.status().is_server_error() => {
+                let status = response.status();
+                (status, response.text().await?)
+            }
+            Err(e) if e.is_connect() => return Err(e.into()),
+            outcome => {
+                let error = match outcome {
+                    Ok(response) => {
+                        let status = response.status();
+                        ConnectorError::ApiError(format!("HTTP {}: {}", status, response.text().await.unwrap_or_default()))
+                    }
+                    Err(e) => e.into(),
+                };
+                return match self.find_submitted_order(&order_id).await {
+                    Some(order) => {
+                        warn!("Order {} reached Extended (id {}, {}) despite: {}", order_id, order.id, order.status, error);
+                        Ok(OrderResponse { id: order.id, external_id: order.external_id })
+                    }
+                    None => {
+                        error!("Order placement failed: {}", error);
+                        Err(error)
+                    }
+                };
+            }
+        };
 
         debug!("Order response status: {}", status);
         // Do not print full response body at info level; debug-only is acceptable and typically non-sensitive
@@ -886,6 +1458,20 @@ impl RestClient {
         stark_private_key: &str,
         stark_public_key: &str,
         vault_id: &str,
+    ) -> Result<OrderResponse> {
+        let external_id = format!("rust-{}", chrono::Utc::now().timestamp_millis());
+        self.close_position_with_id(position, stark_private_key, stark_public_key, vault_id, &external_id)
+            .await
+    }
+
+    /// Close an existing position with a caller-chosen external ID (see `place_market_order_with_id`)
+    pub async fn close_position_with_id(
+        &self,
+        position: &Position,
+        stark_private_key: &str,
+        stark_public_key: &str,
+        vault_id: &str,
+        external_id: &str,
     ) -> Result<OrderResponse> {
         // Determine opposite side to close the position
         let close_side = match position.side {
@@ -902,7 +1488,7 @@ impl RestClient {
         );
 
         // Place reduce-only market order to close
-        self.place_market_order(
+        self.place_market_order_with_id(
             &position.market,
             close_side,
             position_value_usd,
@@ -911,15 +1497,312 @@ impl RestClient {
             vault_id,
             true, // reduce_only = true
             Some(position.size_f64()),
+            DEFAULT_MARKET_SLIPPAGE_PCT,
+            external_id,
         )
         .await
     }
+
+    /// Get orders placed with the given external ID (requires API key)
+    ///
+    /// Returns an empty list if no order with that ID reached the exchange.
+    pub async fn get_orders_by_external_id(&self, external_id: &str) -> Result<Vec<OrderInfo>> {
+        let url = format!("{}/user/orders/external/{}", self.base_url, external_id);
+        debug!("Fetching orders by external id from {}", url);
+
+        let api_key = self.api_key.as_ref().ok_or_else(|| {
+            ConnectorError::ApiError("API key required for orders".to_string())
+        })?;
+
+        let request = self
+            .client
+            .get(&url)
+            .header("X-Api-Key", api_key)
+            .send();
+        let response = api_capture::observe_http("extended GET /user/orders/external/{id}", request).await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        if !status.is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            error!("API error: {} - {}", status, error_text);
+            return Err(ConnectorError::ApiError(format!(
+                "HTTP {}: {}",
+                status, error_text
+            )));
+        }
+
+        let api_response: ApiResponse<Vec<OrderInfo>> = response.json().await?;
+
+        match api_response.data {
+            Some(orders) => Ok(orders),
+            None => {
+                let error_msg = api_response
+                    .error
+                    .map(|e| format!("{}: {}", e.code, e.message))
+                    .unwrap_or_else(|| "Unknown error".to_string());
+                error!("API error response: {}", error_msg);
+                Err(ConnectorError::ApiError(error_msg))
+            }
+        }
+    }
+
+    /// The order submitted under `external_id`, looked up a few times to give Extended
+    /// time to record it; `None` if none shows up or the lookups fail
+    async fn find_submitted_order(&self, external_id: &str) -> Option<OrderInfo> {
+        for lookup in 1..=SUBMIT_LOOKUP_ATTEMPTS {
+            tokio::time::sleep(Duration::from_millis(SUBMIT_LOOKUP_INTERVAL_MS)).await;
+            match self.get_orders_by_external_id(external_id).await {
+                Ok(orders) => {
+                    if let Some(order) = submitted_order(&orders) {
+                        return Some(order.clone());
+                    }
+                }
+                Err(e) => warn!("Looking up order {} ({}/{}) failed: {}", external_id, lookup, SUBMIT_LOOKUP_ATTEMPTS, e),
+            }
+        }
+        None
+    }
+
+    /// Poll an order by external ID until it is filled, cancelled, rejected or expired
+    ///
+    /// Returns the final order record with its filled size, average price and fee.
+    /// Fails if the order is still working (or unknown) when `timeout` elapses.
+    pub async fn wait_for_fill(&self, external_id: &str, timeout: Duration) -> Result<OrderInfo> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            match self.get_orders_by_external_id(external_id).await {
+                Ok(orders) => {
+                    if let Some(order) = orders.into_iter().find(OrderInfo::is_final) {
+                        debug!("Order {} is {} with {} filled", external_id, order.status, order.filled_qty_f64());
+                        return Ok(order);
+                    }
+                }
+                // Polling with rejected credentials cannot succeed
+                Err(e) if e.is_auth() => return Err(e),
+                Err(e) => warn!("Polling order {} failed: {}", external_id, e),
+            }
+
+            if tokio::time::Instant::now() + Duration::from_millis(FILL_POLL_INTERVAL_MS) > deadline {
+                return Err(ConnectorError::Other(format!(
+                    "Order {} not filled or cancelled within {}ms",
+                    external_id,
+                    timeout.as_millis()
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(FILL_POLL_INTERVAL_MS)).await;
+        }
+    }
+
+    /// Get resting orders of the account, optionally for one market (requires API key)
+    pub async fn get_open_orders(&self, market: Option<&str>) -> Result<Vec<OrderInfo>> {
+        let url = match market {
+            Some(market) => format!("{}/user/orders?market={}", self.base_url, market),
+            None => format!("{}/user/orders", self.base_url),
+        };
+        debug!("Fetching open orders from {}", url);
+
+        let api_key = self.api_key.as_ref().ok_or_else(|| {
+            ConnectorError::ApiError("API key required for orders".to_string())
+        })?;
+
+        let request = self
+            .client
+            .get(&url)
+            .header("X-Api-Key", api_key)
+            .send();
+        let response = api_capture::observe_http("extended GET /user/orders", request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            error!("API error: {} - {}", status, error_text);
+            return Err(ConnectorError::ApiError(format!(
+                "HTTP {}: {}",
+                status, error_text
+            )));
+        }
+
+        let api_response: ApiResponse<Vec<OrderInfo>> = response.json().await?;
+
+        match api_response.data {
+            Some(orders) => Ok(orders),
+            None => {
+                let error_msg = api_response
+                    .error
+                    .map(|e| format!("{}: {}", e.code, e.message))
+                    .unwrap_or_else(|| "Unknown error".to_string());
+                error!("API error response: {}", error_msg);
+                Err(ConnectorError::ApiError(error_msg))
+            }
+        }
+    }
+
+    /// Get the account's most recent fills, newest first, optionally for one market
+    /// (requires API key)
+    pub async fn get_trades(&self, market: Option<&str>, limit: u32) -> Result<Vec<AccountTrade>> {
+        let mut url = format!("{}/user/trades?limit={}", self.base_url, limit);
+        if let Some(m) = market {
+            url.push_str(&format!("&market={}", m));
+        }
+        debug!("Fetching trades from {}", url);
+
+        let api_key = self.api_key.as_ref().ok_or_else(|| {
+            ConnectorError::ApiError("API key required for trades".to_string())
+        })?;
+
+        let request = self
+            .client
+            .get(&url)
+            .header("X-Api-Key", api_key)
+            .send();
+        let response = api_capture::observe_http("extended GET /user/trades", request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            error!("API error: {} - {}", status, error_text);
+            return Err(ConnectorError::ApiError(format!(
+                "HTTP {}: {}",
+                status, error_text
+            )));
+        }
+
+        let api_response: ApiResponse<Vec<AccountTrade>> = response.json().await?;
+
+        match api_response.data {
+            Some(trades) => Ok(trades),
+            None => {
+                let error_msg = api_response
+                    .error
+                    .map(|e| format!("{}: {}", e.code, e.message))
+                    .unwrap_or_else(|| "Unknown error".to_string());
+                error!("API error response: {}", error_msg);
+                Err(ConnectorError::ApiError(error_msg))
+            }
+        }
+    }
+
+    /// Cancel a resting order by the external ID it was placed with (requires API key)
+    pub async fn cancel_order_by_external_id(&self, external_id: &str) -> Result<()> {
+        let url = format!("{}/user/order?externalId={}", self.base_url, external_id);
+        info!("Cancelling Extended order {}", external_id);
+
+        let api_key = self.api_key.as_ref().ok_or_else(|| {
+            ConnectorError::ApiError("API key required to cancel orders".to_string())
+        })?;
+
+        let request = self
+            .client
+            .delete(&url)
+            .header("X-Api-Key", api_key)
+            .send();
+        let response = api_capture::observe_http("extended DELETE /user/order", request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            error!("API error: {} - {}", status, error_text);
+            return Err(ConnectorError::ApiError(format!(
+                "HTTP {}: {}",
+                status, error_text
+            )));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_limit_order_validation() {
+        let order = LimitOrder::new("BTC-USD", OrderSide::Buy, 0.01, 50_000.0)
+            .post_only()
+            .take_profit(TpSlTrigger::market(55_000.0, 54_000.0))
+            .stop_loss(TpSlTrigger::market(48_000.0, 47_000.0));
+        assert!(order.validate().is_ok());
+
+        // Stop above entry on a buy is wrong
+        let bad_sl = LimitOrder::new("BTC-USD", OrderSide::Buy, 0.01, 50_000.0)
+            .stop_loss(TpSlTrigger::market(52_000.0, 51_000.0));
+        assert!(bad_sl.validate().is_err());
+
+        // Sell: take-profit must be below entry
+        let sell = LimitOrder::new("BTC-USD", OrderSide::Sell, 0.01, 50_000.0)
+            .take_profit(TpSlTrigger::market(45_000.0, 46_000.0));
+        assert!(sell.validate().is_ok());
+
+        let post_only_ioc = LimitOrder::new("BTC-USD", OrderSide::Sell, 0.01, 50_000.0)
+            .post_only()
+            .time_in_force(TimeInForce::IOC);
+        assert!(post_only_ioc.validate().is_err());
+    }
+
+    #[test]
+    fn test_submitted_order_ignores_rejections() {
+        let orders: Vec<OrderInfo> = serde_json::from_str(
+            r#"[{"id": 1, "externalId": "a", "status": "REJECTED"}, {"id": 2, "externalId": "a", "status": "NEW"}]"#,
+        ).unwrap();
+        assert_eq!(submitted_order(&orders).map(|o| o.id), Some(2));
+        assert!(submitted_order(&orders[..1]).is_none());
+        assert!(submitted_order(&[]).is_none());
+    }
+
+    /// Serves `bodies` in turn to successive requests, repeating the last one
+    async fn mock_orders(bodies: Vec<&'static str>) -> RestClient {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut served = 0;
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let body = bodies[served.min(bodies.len() - 1)];
+                served += 1;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        RestClient::new(&url, Some("key".to_string())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_fill() {
+        let working = r#"{"status": "OK", "data": [{"id": 1, "externalId": "a", "status": "NEW"}]}"#;
+        let filled = r#"{"status": "OK", "data": [{"id": 1, "externalId": "a", "status": "FILLED", "filledQty": "0.5"}]}"#;
+
+        let client = mock_orders(vec![working, working, filled]).await;
+        let order = client.wait_for_fill("a", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(order.status, "FILLED");
+        assert_eq!(order.filled_qty_f64(), 0.5);
+
+        // Still working at the deadline: the fill is unknown, not zero
+        let client = mock_orders(vec![working]).await;
+        let err = client.wait_for_fill("a", Duration::from_millis(600)).await.unwrap_err();
+        assert!(err.to_string().contains("not filled or cancelled"));
+    }
+
     #[tokio::test]
     async fn test_get_orderbook() {
         let client = RestClient::new_mainnet(None).unwrap();
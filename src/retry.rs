@@ -0,0 +1,195 @@
+//! Shared retry policy for order placement, closes and position queries
+//!
+//! Delays grow exponentially from `base_delay_ms` up to `max_delay_ms`; rate-limited
+//! errors instead back off linearly by `rate_limit_delay_ms` per attempt. Every delay
+//! is randomized by ±`jitter_pct` so both legs (and several bots) don't retry in lockstep.
+use crate::error::BotError;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Attempts per operation, including the first one
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay after the first failure; doubled on each further attempt
+    #[serde(default = "default_base_delay_ms")]
+    pub base_delay_ms: u64,
+    /// Upper bound for the exponential delay
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u64,
+    /// Random spread applied to every delay, in % of the delay
+    #[serde(default = "default_jitter_pct")]
+    pub jitter_pct: f64,
+    /// Linear step used instead of the exponential delay when rate limited
+    #[serde(default = "default_rate_limit_delay_ms")]
+    pub rate_limit_delay_ms: u64,
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_base_delay_ms() -> u64 {
+    1_000
+}
+
+fn default_max_delay_ms() -> u64 {
+    64_000
+}
+
+fn default_jitter_pct() -> f64 {
+    20.0
+}
+
+fn default_rate_limit_delay_ms() -> u64 {
+    5_000
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            base_delay_ms: default_base_delay_ms(),
+            max_delay_ms: default_max_delay_ms(),
+            jitter_pct: default_jitter_pct(),
+            rate_limit_delay_ms: default_rate_limit_delay_ms(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after failed attempt `attempt` (1-based), without jitter
+    pub fn base_delay(&self, attempt: u32, rate_limited: bool) -> u64 {
+        let attempt = attempt.max(1);
+        if rate_limited {
+            self.rate_limit_delay_ms.saturating_mul(attempt as u64)
+        } else {
+            let factor = 2u64.saturating_pow(attempt - 1);
+            self.base_delay_ms.saturating_mul(factor).min(self.max_delay_ms)
+        }
+    }
+
+    /// Delay before retrying after failed attempt `attempt`, with jitter applied
+    pub fn delay(&self, attempt: u32, rate_limited: bool) -> Duration {
+        let base = self.base_delay(attempt, rate_limited) as f64;
+        let spread = base * self.jitter_pct.clamp(0.0, 100.0) / 100.0;
+        Duration::from_millis((base + spread * jitter_unit()).max(0.0) as u64)
+    }
+
+    /// Whether another attempt should follow failed attempt `attempt`
+    pub fn should_retry(&self, attempt: u32, err: &BotError) -> bool {
+        attempt < self.max_attempts && err.is_retryable()
+    }
+
+    /// Run `operation` until it succeeds, a non-retryable error occurs, or attempts run out
+    pub async fn run<F, Fut, T>(&self, operation_name: &str, mut operation: F) -> Result<T, BotError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, BotError>>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            match operation().await {
+                Ok(result) => {
+                    if attempt > 1 {
+                        info!("{} succeeded on attempt {}/{}", operation_name, attempt, self.max_attempts);
+                    }
+                    return Ok(result);
+                }
+                Err(e) => {
+                    if !self.should_retry(attempt, &e) {
+                        error!("{} failed after {} attempt(s): {}", operation_name, attempt, e);
+                        return Err(e);
+                    }
+
+                    let rate_limited = e.is_rate_limited();
+                    let delay = self.delay(attempt, rate_limited);
+                    warn!(
+                        "{} failed (attempt {}/{}{}): {}. Retrying in {}ms...",
+                        operation_name,
+                        attempt,
+                        self.max_attempts,
+                        if rate_limited { " - rate limited" } else { "" },
+                        e,
+                        delay.as_millis()
+                    );
+                    sleep(delay).await;
+                }
+            }
+        }
+    }
+}
+
+/// Uniform value in [-1, 1]
+fn jitter_unit() -> f64 {
+    let bits = (uuid::Uuid::new_v4().as_u128() >> 64) as u64;
+    (bits as f64 / u64::MAX as f64) * 2.0 - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_jitter() -> RetryPolicy {
+        RetryPolicy {
+            jitter_pct: 0.0,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_exponential_delay_is_capped() {
+        let policy = no_jitter();
+        assert_eq!(policy.base_delay(1, false), 1_000);
+        assert_eq!(policy.base_delay(2, false), 2_000);
+        assert_eq!(policy.base_delay(4, false), 8_000);
+        assert_eq!(policy.base_delay(30, false), 64_000);
+        assert_eq!(policy.delay(3, false), Duration::from_millis(4_000));
+    }
+
+    #[test]
+    fn test_rate_limit_delay_is_linear() {
+        let policy = no_jitter();
+        assert_eq!(policy.base_delay(1, true), 5_000);
+        assert_eq!(policy.base_delay(3, true), 15_000);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let policy = RetryPolicy::default();
+        for _ in 0..100 {
+            let ms = policy.delay(2, false).as_millis() as u64;
+            assert!((1_600..=2_400).contains(&ms), "delay {} out of range", ms);
+        }
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = no_jitter();
+        let transient = BotError::classify("Extended", "HTTP 429".into());
+        assert!(policy.should_retry(1, &transient));
+        assert!(!policy.should_retry(5, &transient));
+        assert!(!policy.should_retry(1, &BotError::Config("bad".into())));
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_on_non_retryable_error() {
+        let policy = no_jitter();
+        let mut calls = 0;
+        let result: Result<(), BotError> = policy
+            .run("test", || {
+                calls += 1;
+                async { Err(BotError::Config("bad".into())) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
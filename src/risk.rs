@@ -0,0 +1,151 @@
+//! Central pre-trade risk limits
+//!
+//! `RiskManager` is consulted by the bot before any order of a new position is sent:
+//! it caps the notional per symbol and as a share of total equity, and halts opening
+//! after too many consecutive failed opens (which usually means something is wrong
+//! with a venue or the account, and retrying every cycle only adds exposure risk).
+//! While a position is held it also judges the Extended balance readings of the margin
+//! watch (see `margin_watch`).
+use crate::error::BotError;
+use crate::margin_watch::{BalanceSample, MarginAssessment, MarginCallConfig, MarginWatch};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RiskConfig {
+    /// Notional cap (USD) for a position in any symbol (0 = no cap)
+    #[serde(default)]
+    pub max_notional_per_symbol_usd: f64,
+    /// Per-symbol notional caps (USD) overriding `max_notional_per_symbol_usd`
+    #[serde(default)]
+    pub symbol_max_notional_usd: HashMap<String, f64>,
+    /// Cap on position notional as % of combined equity on both venues (0 = no cap)
+    #[serde(default)]
+    pub max_equity_share_pct: f64,
+    /// Stop opening positions after this many failed opens in a row until restart (0 = never)
+    #[serde(default)]
+    pub max_consecutive_failed_opens: u32,
+    /// Extended margin-call watch while a position is held
+    #[serde(default)]
+    pub margin_call: MarginCallConfig,
+}
+
+pub struct RiskManager {
+    config: RiskConfig,
+    consecutive_failed_opens: u32,
+    margin_watch: MarginWatch,
+}
+
+impl RiskManager {
+    pub fn new(config: RiskConfig) -> Self {
+        Self {
+            margin_watch: MarginWatch::new(config.margin_call.clone()),
+            config,
+            consecutive_failed_opens: 0,
+        }
+    }
+
+    /// Response called for by an Extended balance reading of the held position
+    pub fn observe_margin(&mut self, sample: BalanceSample) -> MarginAssessment {
+        self.margin_watch.observe(sample)
+    }
+
+    /// Forget the margin watch's readings once no position is held
+    pub fn reset_margin_watch(&mut self) {
+        self.margin_watch.reset();
+    }
+
+    pub fn consecutive_failed_opens(&self) -> u32 {
+        self.consecutive_failed_opens
+    }
+
+    /// Largest notional (USD) allowed for a new position in `symbol`
+    pub fn max_notional_usd(&self, symbol: &str, equity_usd: f64) -> f64 {
+        let symbol_cap = self
+            .config
+            .symbol_max_notional_usd
+            .get(symbol)
+            .copied()
+            .unwrap_or(self.config.max_notional_per_symbol_usd);
+        let mut cap = f64::INFINITY;
+        if symbol_cap > 0.0 {
+            cap = cap.min(symbol_cap);
+        }
+        if self.config.max_equity_share_pct > 0.0 {
+            cap = cap.min(equity_usd.max(0.0) * self.config.max_equity_share_pct / 100.0);
+        }
+        cap
+    }
+
+    /// Fails once `max_consecutive_failed_opens` opens in a row have failed
+    pub fn check_can_open(&self) -> Result<(), BotError> {
+        let limit = self.config.max_consecutive_failed_opens;
+        if limit > 0 && self.consecutive_failed_opens >= limit {
+            return Err(BotError::Risk(format!(
+                "{} consecutive failed opens (limit {}); new positions are halted until the bot is restarted",
+                self.consecutive_failed_opens, limit
+            )));
+        }
+        Ok(())
+    }
+
+    /// Final check of a sized position, right before its orders are sent
+    pub fn check_open(&self, symbol: &str, notional_usd: f64, equity_usd: f64) -> Result<(), BotError> {
+        self.check_can_open()?;
+        let cap = self.max_notional_usd(symbol, equity_usd);
+        // Allow for rounding to lot size
+        if notional_usd > cap * 1.001 {
+            return Err(BotError::Risk(format!(
+                "{} notional ${:.2} exceeds the ${:.2} limit (equity ${:.2})",
+                symbol, notional_usd, cap, equity_usd
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn record_open_result(&mut self, succeeded: bool) {
+        if succeeded {
+            self.consecutive_failed_opens = 0;
+        } else {
+            self.consecutive_failed_opens += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notional_caps() {
+        let unlimited = RiskManager::new(RiskConfig::default());
+        assert_eq!(unlimited.max_notional_usd("BTC", 1_000.0), f64::INFINITY);
+
+        let risk = RiskManager::new(RiskConfig {
+            max_notional_per_symbol_usd: 5_000.0,
+            symbol_max_notional_usd: HashMap::from([("PUMP".to_string(), 500.0)]),
+            max_equity_share_pct: 50.0,
+            ..RiskConfig::default()
+        });
+        assert_eq!(risk.max_notional_usd("BTC", 20_000.0), 5_000.0);
+        assert_eq!(risk.max_notional_usd("BTC", 4_000.0), 2_000.0);
+        assert_eq!(risk.max_notional_usd("PUMP", 20_000.0), 500.0);
+        assert!(risk.check_open("BTC", 2_000.0, 4_000.0).is_ok());
+        assert!(matches!(risk.check_open("BTC", 2_500.0, 4_000.0), Err(BotError::Risk(_))));
+    }
+
+    #[test]
+    fn test_consecutive_failed_opens_halt() {
+        let mut risk = RiskManager::new(RiskConfig {
+            max_consecutive_failed_opens: 2,
+            ..RiskConfig::default()
+        });
+        risk.record_open_result(false);
+        assert!(risk.check_can_open().is_ok());
+        risk.record_open_result(true);
+        risk.record_open_result(false);
+        risk.record_open_result(false);
+        assert!(risk.check_can_open().is_err());
+        assert!(!risk.check_can_open().unwrap_err().is_retryable());
+    }
+}
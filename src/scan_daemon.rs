@@ -0,0 +1,194 @@
+//! Scan-only daemon: signals without trading
+//!
+//! Runs `OpportunityFinder::scan` every `scan_daemon.interval_minutes`, writes each
+//! scan to the export directory and alerts through `Hook<Opportunity>`s when an
+//! opportunity newly reaches `scan_daemon.alert_min_net_apr_pct`. An opportunity that
+//! stays above the threshold is alerted once, and again only after it drops below.
+use crate::error::BotError;
+use crate::notify::{Hook, HookFuture, WebhookSink};
+use crate::opportunity::{Config, Opportunity, OpportunityFinder, ScanResult};
+use crate::pacifica::PacificaCredentials;
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::{info, warn};
+
+fn alert_message(opportunity: &Opportunity) -> String {
+    format!(
+        "{}: {:.2}% net APR ({}), cross spread {:.3}%, 24h volume ${:.0}",
+        opportunity.symbol,
+        opportunity.best_net_apr,
+        opportunity.best_direction,
+        opportunity.cross_spread_pct,
+        opportunity.total_volume_24h
+    )
+}
+
+/// Default hook: log the opportunity
+pub struct LogOpportunityAlert;
+
+impl Hook<Opportunity> for LogOpportunityAlert {
+    fn notify<'a>(&'a self, opportunity: &'a Opportunity) -> HookFuture<'a> {
+        Box::pin(async move {
+            warn!("🔔 Opportunity alert: {}", alert_message(opportunity));
+            Ok(())
+        })
+    }
+}
+
+fn alert_key(opportunity: &Opportunity) -> String {
+    format!("{}:{}", opportunity.symbol, opportunity.best_direction)
+}
+
+/// Opportunities at or above `min_net_apr_pct` that were not above it in the previous
+/// scan, plus the set of keys above the threshold now (to pass to the next call)
+pub fn new_alerts<'a>(
+    scan: &'a ScanResult,
+    previously_alerted: &HashSet<String>,
+    min_net_apr_pct: f64,
+) -> (Vec<&'a Opportunity>, HashSet<String>) {
+    let above: Vec<&Opportunity> = scan
+        .opportunities
+        .iter()
+        .filter(|o| o.best_net_apr >= min_net_apr_pct)
+        .collect();
+    let fresh = above
+        .iter()
+        .copied()
+        .filter(|o| !previously_alerted.contains(&alert_key(o)))
+        .collect();
+    (fresh, above.iter().map(|o| alert_key(o)).collect())
+}
+
+pub struct ScanDaemon {
+    finder: OpportunityFinder,
+    config: Config,
+    hooks: Vec<Box<dyn Hook<Opportunity>>>,
+    alerted: HashSet<String>,
+}
+
+impl ScanDaemon {
+    /// Scans are always exported, whatever `export.enabled` says
+    pub fn new(
+        extended_api_key: Option<String>,
+        pacifica_creds: PacificaCredentials,
+        mut config: Config,
+    ) -> Result<Self, BotError> {
+        config.export.enabled = true;
+        let finder = OpportunityFinder::new(extended_api_key, pacifica_creds, config.clone())?;
+
+        let mut hooks: Vec<Box<dyn Hook<Opportunity>>> = vec![Box::new(LogOpportunityAlert)];
+        if let Some(url) = config.scan_daemon.webhook_url.as_ref().filter(|u| !u.is_empty()) {
+            hooks.push(Box::new(WebhookSink::new(url.clone(), "opportunity", alert_message)));
+        }
+
+        Ok(Self {
+            finder,
+            config,
+            hooks,
+            alerted: HashSet::new(),
+        })
+    }
+
+    /// Add an alert receiver (e.g. email, Telegram) next to the configured ones
+    pub fn add_hook(&mut self, hook: Box<dyn Hook<Opportunity>>) {
+        self.hooks.push(hook);
+    }
+
+    /// Run one scan and send alerts for opportunities that newly crossed the threshold
+    pub async fn scan_once(&mut self, extended_api_key: Option<String>) -> Result<ScanResult, BotError> {
+        let scan = self.finder.scan(extended_api_key).await?;
+        let (fresh, above) = new_alerts(&scan, &self.alerted, self.config.scan_daemon.alert_min_net_apr_pct);
+
+        for opportunity in fresh {
+            for hook in &self.hooks {
+                if let Err(e) = hook.notify(opportunity).await {
+                    warn!("Opportunity alert hook failed: {}", e);
+                }
+            }
+        }
+        self.alerted = above;
+        Ok(scan)
+    }
+
+    /// Scan until Ctrl+C. Failed scans are logged and retried next interval.
+    pub async fn run(&mut self, extended_api_key: Option<String>) -> Result<(), BotError> {
+        let interval = Duration::from_secs(self.config.scan_daemon.interval_minutes * 60);
+        info!(
+            "📡 Scan daemon: scanning every {} minutes, alerting at >= {:.1}% net APR, saving scans to {}/",
+            self.config.scan_daemon.interval_minutes,
+            self.config.scan_daemon.alert_min_net_apr_pct,
+            self.config.export.directory
+        );
+
+        loop {
+            match self.scan_once(extended_api_key.clone()).await {
+                Ok(scan) => scan.display_summary(&self.config),
+                Err(e) => warn!("Scan failed: {}", e),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {},
+                _ = tokio::signal::ctrl_c() => {
+                    info!("🛑 Shutdown signal received. Scan daemon stopped.");
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opportunity::{sample_opportunity, Direction, FilterStats};
+
+    fn opportunity(symbol: &str, net_apr: f64) -> Opportunity {
+        Opportunity {
+            pacifica_spread_pct: 0.01,
+            cross_spread_pct: 0.02,
+            total_volume_24h: 10_000_000.0,
+            extended_volume_24h: 5_000_000.0,
+            pacifica_volume_24h: 5_000_000.0,
+            ..sample_opportunity(symbol, net_apr, Direction::LongExtendedShortPacifica)
+        }
+    }
+
+    fn scan(opportunities: Vec<Opportunity>) -> ScanResult {
+        ScanResult {
+            opportunities,
+            all_candidates: Vec::new(),
+            stats: FilterStats {
+                total_common_symbols: 0,
+                filtered_by_volume: 0,
+                filtered_by_spread: 0,
+                filtered_by_apr: 0,
+                filtered_by_open_interest: 0,
+                filtered_by_mark_index_gap: 0,
+                passed_filters: 0,
+            },
+            scanned_at: 0,
+            data_availability: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_alerts_only_on_crossing() {
+        let first = scan(vec![opportunity("BTC", 80.0), opportunity("ETH", 20.0)]);
+        let (fresh, alerted) = new_alerts(&first, &HashSet::new(), 50.0);
+        assert_eq!(fresh.iter().map(|o| o.symbol.as_str()).collect::<Vec<_>>(), vec!["BTC"]);
+
+        // Still above: no repeat alert; ETH newly crosses
+        let second = scan(vec![opportunity("BTC", 75.0), opportunity("ETH", 60.0)]);
+        let (fresh, alerted) = new_alerts(&second, &alerted, 50.0);
+        assert_eq!(fresh.iter().map(|o| o.symbol.as_str()).collect::<Vec<_>>(), vec!["ETH"]);
+
+        // BTC drops out and comes back: alerted again
+        let third = scan(vec![opportunity("ETH", 60.0)]);
+        let (fresh, alerted) = new_alerts(&third, &alerted, 50.0);
+        assert!(fresh.is_empty());
+        let fourth = scan(vec![opportunity("BTC", 90.0), opportunity("ETH", 60.0)]);
+        let (fresh, _) = new_alerts(&fourth, &alerted, 50.0);
+        assert_eq!(fresh.len(), 1);
+        assert_eq!(fresh[0].symbol, "BTC");
+    }
+}
@@ -0,0 +1,264 @@
+//! Opportunity ranking
+//!
+//! Scans rank candidates with a `Scorer` instead of a fixed sort on `best_net_apr`.
+//! `scoring.method` picks one of the built-in scorers:
+//! - `apr` (default): the net APR itself
+//! - `apr_stability`: net APR scaled by how steady the symbol's net APR was over the
+//!   last `stability_window_samples` scans (1 - standard deviation / mean, floored at 0;
+//!   the history is kept in memory only)
+//! - `apr_minus_cost`: net APR less the estimated round-trip cost (twice the entry
+//!   slippage from the scanned books, or half spreads when a book is too thin, plus the
+//!   open and close fees), amortized over `trading.hold_time_hours`
+//! - `volume_weighted`: net APR scaled by volume / (volume + `volume_half_weight_usd`),
+//!   so thin markets need a higher APR to rank first
+//!
+//! Ranking only orders the scan; filters and the strategy's selection rules still apply.
+//! A symbol is ranked once: a later candidate for a symbol already ranked is dropped.
+//! `switch_margin_apr_pct` adds hysteresis to selection: the default strategy keeps the
+//! previous cycle's pick (or the held symbol) while it still passes the filters, unless
+//! the top-ranked opportunity beats its net APR by that many points.
+//! Other scorers can be plugged in with `OpportunityFinder::set_scorer`.
+use crate::funding::apr_from_rate;
+use crate::opportunity::{Config, Opportunity, OpportunityCandidate};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Mutex, PoisonError};
+use tracing::debug;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringMethod {
+    #[default]
+    Apr,
+    AprStability,
+    AprMinusCost,
+    VolumeWeighted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoringConfig {
+    #[serde(default)]
+    pub method: ScoringMethod,
+    /// Scans of net APR history per symbol that `apr_stability` judges
+    #[serde(default = "default_stability_window_samples")]
+    pub stability_window_samples: usize,
+    /// 24h combined volume (USD) at which `volume_weighted` keeps half the APR
+    #[serde(default = "default_volume_half_weight_usd")]
+    pub volume_half_weight_usd: f64,
+    /// Net APR points a new opportunity must add over the previous pick to replace it
+    #[serde(default)]
+    pub switch_margin_apr_pct: f64,
+}
+
+fn default_stability_window_samples() -> usize {
+    12
+}
+
+fn default_volume_half_weight_usd() -> f64 {
+    50_000_000.0
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            method: ScoringMethod::default(),
+            stability_window_samples: default_stability_window_samples(),
+            volume_half_weight_usd: default_volume_half_weight_usd(),
+            switch_margin_apr_pct: 0.0,
+        }
+    }
+}
+
+pub trait Scorer: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Called once per scan with every fetched opportunity, before any is scored
+    fn observe(&self, _opportunities: &[Opportunity], _config: &Config) {}
+
+    /// Ranking score; higher ranks first
+    fn score(&self, opportunity: &Opportunity, config: &Config) -> f64;
+}
+
+/// Built-in scorer selected by `scoring.method`
+pub fn scorer_from_config(config: &ScoringConfig) -> Box<dyn Scorer> {
+    match config.method {
+        ScoringMethod::Apr => Box::new(AprScorer),
+        ScoringMethod::AprStability => Box::new(AprStabilityScorer::default()),
+        ScoringMethod::AprMinusCost => Box::new(AprMinusCostScorer),
+        ScoringMethod::VolumeWeighted => Box::new(VolumeWeightedScorer),
+    }
+}
+
+/// Sort `candidates` by descending score; ties keep their order
+pub fn rank(scorer: &dyn Scorer, candidates: &mut Vec<OpportunityCandidate>, config: &Config) {
+    let opportunities: Vec<Opportunity> = candidates.iter().map(|c| c.opportunity.clone()).collect();
+    scorer.observe(&opportunities, config);
+
+    let mut scored: Vec<(f64, OpportunityCandidate)> = candidates
+        .drain(..)
+        .map(|c| (scorer.score(&c.opportunity, config), c))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    candidates.extend(scored.into_iter().map(|(_, c)| c));
+}
+
+/// Keep only the first (best-ranked) candidate of each symbol
+pub fn dedup_by_symbol(candidates: &mut Vec<OpportunityCandidate>) {
+    let mut seen = HashSet::new();
+    candidates.retain(|c| {
+        let first = seen.insert(c.opportunity.symbol.clone());
+        if !first {
+            debug!("Dropping duplicate scan entry for {}", c.opportunity.symbol);
+        }
+        first
+    });
+}
+
+/// Net APR, as ranked before scorers existed
+pub struct AprScorer;
+
+impl Scorer for AprScorer {
+    fn name(&self) -> &str {
+        "apr"
+    }
+
+    fn score(&self, opportunity: &Opportunity, _config: &Config) -> f64 {
+        opportunity.best_net_apr
+    }
+}
+
+/// Net APR scaled by its steadiness over recent scans
+#[derive(Default)]
+pub struct AprStabilityScorer {
+    history: Mutex<HashMap<String, VecDeque<f64>>>,
+}
+
+impl AprStabilityScorer {
+    /// 1 - standard deviation / mean of the symbol's recorded APRs, in [0, 1];
+    /// 1 until two samples are recorded
+    pub fn stability(&self, symbol: &str) -> f64 {
+        let history = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(samples) = history.get(symbol).filter(|s| s.len() >= 2) else {
+            return 1.0;
+        };
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        if mean <= 0.0 {
+            return 0.0;
+        }
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+        (1.0 - variance.sqrt() / mean).clamp(0.0, 1.0)
+    }
+}
+
+impl Scorer for AprStabilityScorer {
+    fn name(&self) -> &str {
+        "apr_stability"
+    }
+
+    fn observe(&self, opportunities: &[Opportunity], config: &Config) {
+        let window = config.scoring.stability_window_samples.max(1);
+        let mut history = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+        for opp in opportunities {
+            let samples = history.entry(opp.symbol.clone()).or_default();
+            samples.push_back(opp.best_net_apr);
+            while samples.len() > window {
+                samples.pop_front();
+            }
+        }
+    }
+
+    fn score(&self, opportunity: &Opportunity, _config: &Config) -> f64 {
+        opportunity.best_net_apr * self.stability(&opportunity.symbol)
+    }
+}
+
+/// Net APR less the estimated cost of opening and closing, amortized over the hold
+pub struct AprMinusCostScorer;
+
+impl Scorer for AprMinusCostScorer {
+    fn name(&self) -> &str {
+        "apr_minus_cost"
+    }
+
+    fn score(&self, opportunity: &Opportunity, config: &Config) -> f64 {
+        let fees = config.fee_schedule();
+        let entry_fee_pct = config.entry_fee_pct();
+        let entry_cost_pct = opportunity
+            .estimated_entry_cost_bps(config.trading.max_position_size_usd, entry_fee_pct)
+            .map(|bps| bps / 100.0)
+            .unwrap_or_else(|| opportunity.half_spread_cost_pct() + entry_fee_pct);
+        // The close pays the same slippage and taker fees on both legs
+        let exit_cost_pct = entry_cost_pct - entry_fee_pct + fees.exit_fee_pct();
+        let cost_apr = apr_from_rate(entry_cost_pct + exit_cost_pct, config.trading.hold_time_hours.max(1) as f64);
+        opportunity.best_net_apr - cost_apr
+    }
+}
+
+/// Net APR scaled by combined 24h volume, saturating towards 1
+pub struct VolumeWeightedScorer;
+
+impl Scorer for VolumeWeightedScorer {
+    fn name(&self) -> &str {
+        "volume_weighted"
+    }
+
+    fn score(&self, opportunity: &Opportunity, config: &Config) -> f64 {
+        let volume = opportunity.total_volume_24h.max(0.0);
+        opportunity.best_net_apr * volume / (volume + config.scoring.volume_half_weight_usd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opportunity::{sample_opportunity, Direction, FilterResult};
+
+    fn candidate(symbol: &str, net_apr: f64, volume: f64) -> OpportunityCandidate {
+        OpportunityCandidate {
+            opportunity: Opportunity {
+                total_volume_24h: volume,
+                extended_volume_24h: volume / 2.0,
+                pacifica_volume_24h: volume / 2.0,
+                ..sample_opportunity(symbol, net_apr, Direction::LongExtendedShortPacifica)
+            },
+            filter_result: FilterResult::Passed,
+            filtered_cross_spread_pct: None,
+        }
+    }
+
+    fn symbols(candidates: &[OpportunityCandidate]) -> Vec<&str> {
+        candidates.iter().map(|c| c.opportunity.symbol.as_str()).collect()
+    }
+
+    #[test]
+    fn test_scorers_rank_candidates() {
+        let config = Config::default_config();
+        let scan = || vec![candidate("THIN", 40.0, 5_000_000.0), candidate("DEEP", 30.0, 500_000_000.0)];
+
+        let mut ranked = scan();
+        rank(&AprScorer, &mut ranked, &config);
+        assert_eq!(symbols(&ranked), ["THIN", "DEEP"]);
+
+        let mut ranked = scan();
+        rank(&VolumeWeightedScorer, &mut ranked, &config);
+        assert_eq!(symbols(&ranked), ["DEEP", "THIN"]);
+
+        // THIN swings between 10% and 70%, DEEP holds 30%: steadiness wins once seen
+        let stability = AprStabilityScorer::default();
+        for apr in [10.0, 70.0, 10.0] {
+            let mut ranked = vec![candidate("THIN", apr, 5_000_000.0), candidate("DEEP", 30.0, 500_000_000.0)];
+            rank(&stability, &mut ranked, &config);
+        }
+        let mut ranked = scan();
+        rank(&stability, &mut ranked, &config);
+        assert_eq!(symbols(&ranked), ["DEEP", "THIN"]);
+        assert_eq!(stability.stability("DEEP"), 1.0);
+
+        let mut ranked = vec![candidate("DEEP", 35.0, 5_000_000.0), candidate("THIN", 40.0, 5_000_000.0), candidate("DEEP", 30.0, 5_000_000.0)];
+        rank(&AprScorer, &mut ranked, &config);
+        dedup_by_symbol(&mut ranked);
+        assert_eq!(symbols(&ranked), ["THIN", "DEEP"]);
+        assert_eq!(ranked[1].opportunity.best_net_apr, 35.0);
+    }
+}
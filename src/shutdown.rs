@@ -0,0 +1,215 @@
+//! Draining shutdown
+//!
+//! `FundingBot::run` listens for Ctrl+C (and SIGTERM on Unix) for its whole life through
+//! a `ShutdownController`. Opens, closes and reductions hold an `InFlight` guard from
+//! their first check until the resulting state is saved. A signal stops the loop at
+//! once between cycles, and mid-cycle as soon as no operation is in flight: a scan is
+//! abandoned, but an open whose first leg is placed is carried through to its hedge (or
+//! rolled back) before the bot exits. If an operation is still running after
+//! `shutdown.drain_timeout_seconds`, the bot exits anyway and the intent log replay on
+//! the next start finds what it left half-done. The final state is saved on the way out.
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownConfig {
+    /// Longest wait for an in-flight open, close or reduction after a shutdown signal
+    #[serde(default = "default_drain_timeout_seconds")]
+    pub drain_timeout_seconds: u64,
+}
+
+fn default_drain_timeout_seconds() -> u64 {
+    300
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_timeout_seconds: default_drain_timeout_seconds(),
+        }
+    }
+}
+
+impl ShutdownConfig {
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_seconds)
+    }
+}
+
+/// How the wait for in-flight operations ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Drain {
+    /// Nothing was left in flight
+    Drained,
+    /// The drain timeout passed with these operations still running
+    TimedOut(Vec<String>),
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    requested: AtomicBool,
+    in_flight: Mutex<Vec<(u64, String)>>,
+    next_id: AtomicU64,
+    changed: Notify,
+}
+
+/// Shutdown request flag and the operations that must finish before exit
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownController {
+    shared: Arc<Shared>,
+}
+
+impl ShutdownController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request shutdown on Ctrl+C and SIGTERM, in the background. Signals arriving while
+    /// draining only log what is still running.
+    pub fn listen_for_signals(&self) {
+        let controller = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if wait_for_signal().await.is_err() {
+                    warn!("Cannot listen for shutdown signals; stop the bot between cycles");
+                    return;
+                }
+                if controller.is_requested() {
+                    let running = controller.in_flight();
+                    if running.is_empty() {
+                        info!("🛑 Shutdown already in progress");
+                    } else {
+                        warn!("🛑 Shutdown already in progress, waiting for: {}", running.join(", "));
+                    }
+                } else {
+                    info!("{}", "");
+                    info!("{}", "🛑 Shutdown signal received. Stopping bot gracefully...");
+                    controller.request();
+                }
+            }
+        });
+    }
+
+    pub fn request(&self) {
+        self.shared.requested.store(true, Ordering::SeqCst);
+        self.shared.changed.notify_waiters();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.shared.requested.load(Ordering::SeqCst)
+    }
+
+    /// Mark `operation` in flight until the returned guard is dropped
+    pub fn begin(&self, operation: impl Into<String>) -> InFlight {
+        let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+        self.shared.in_flight.lock().unwrap_or_else(PoisonError::into_inner).push((id, operation.into()));
+        InFlight { shared: Arc::clone(&self.shared), id }
+    }
+
+    /// Operations currently in flight, oldest first
+    pub fn in_flight(&self) -> Vec<String> {
+        let in_flight = self.shared.in_flight.lock().unwrap_or_else(PoisonError::into_inner);
+        in_flight.iter().map(|(_, operation)| operation.clone()).collect()
+    }
+
+    /// Resolves once shutdown is requested
+    pub async fn requested(&self) {
+        loop {
+            let changed = self.shared.changed.notified();
+            if self.is_requested() {
+                return;
+            }
+            changed.await;
+        }
+    }
+
+    /// Resolves once nothing is in flight
+    pub async fn idle(&self) {
+        loop {
+            let changed = self.shared.changed.notified();
+            if self.in_flight().is_empty() {
+                return;
+            }
+            changed.await;
+        }
+    }
+
+    /// Resolves once shutdown is requested and then nothing is in flight, or `timeout`
+    /// after the request with the operations still running
+    pub async fn drained(&self, timeout: Duration) -> Drain {
+        self.requested().await;
+        let running = self.in_flight();
+        if !running.is_empty() {
+            info!("⏳ Waiting up to {}s for {} to finish before exiting", timeout.as_secs(), running.join(", "));
+        }
+        match tokio::time::timeout(timeout, self.idle()).await {
+            Ok(()) => Drain::Drained,
+            Err(_) => Drain::TimedOut(self.in_flight()),
+        }
+    }
+}
+
+/// An operation that must finish before the bot exits; ends when dropped
+#[derive(Debug)]
+pub struct InFlight {
+    shared: Arc<Shared>,
+    id: u64,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.shared.in_flight.lock().unwrap_or_else(PoisonError::into_inner).retain(|(id, _)| *id != self.id);
+        self.shared.changed.notify_waiters();
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> std::io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> std::io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_in_flight_operations() {
+        let controller = ShutdownController::new();
+        let open = controller.begin("open BTC");
+        let close = controller.begin("close ETH");
+        assert_eq!(controller.in_flight(), vec!["open BTC", "close ETH"]);
+        drop(close);
+
+        let waiter = tokio::spawn({
+            let controller = controller.clone();
+            async move { controller.drained(Duration::from_secs(5)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+        controller.request();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        // Requested, but the open is still running
+        assert!(!waiter.is_finished());
+        drop(open);
+        assert_eq!(waiter.await.unwrap(), Drain::Drained);
+
+        let stuck = controller.begin("reduce BTC");
+        assert_eq!(controller.drained(Duration::from_millis(20)).await, Drain::TimedOut(vec!["reduce BTC".to_string()]));
+        drop(stuck);
+        assert_eq!(controller.drained(Duration::from_millis(20)).await, Drain::Drained);
+    }
+}
@@ -20,6 +20,7 @@ use std::process::{Command, Stdio};
 /// - public_key: Stark public key (hex string with 0x prefix)
 /// - private_key: Stark private key (hex string with 0x prefix)
 /// - domain_chain_id: "SN_MAIN" or "SN_SEPOLIA"
+#[allow(clippy::too_many_arguments)]
 pub fn sign_order(
     base_asset_id: &str,
     quote_asset_id: &str,
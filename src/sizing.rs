@@ -0,0 +1,255 @@
+//! Dynamic position sizing driven by opportunity quality and recent volatility
+use crate::opportunity::SizingConfig;
+use crate::pacifica::{PacificaCandle, PacificaTrading};
+use crate::trading::calculate_position_size;
+
+/// OHLC candle used for volatility estimation
+#[derive(Debug, Clone, Copy)]
+pub struct OhlcCandle {
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+impl From<&PacificaCandle> for OhlcCandle {
+    fn from(candle: &PacificaCandle) -> Self {
+        Self {
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+        }
+    }
+}
+
+/// Average True Range over the candles, as a percentage of the last close.
+/// Needs at least two candles (the first only seeds the previous close).
+pub fn atr_pct(candles: &[OhlcCandle]) -> Option<f64> {
+    if candles.len() < 2 {
+        return None;
+    }
+
+    let mut total = 0.0;
+    for window in candles.windows(2) {
+        let prev_close = window[0].close;
+        let c = window[1];
+        let true_range = (c.high - c.low)
+            .max((c.high - prev_close).abs())
+            .max((c.low - prev_close).abs());
+        total += true_range;
+    }
+
+    let atr = total / (candles.len() - 1) as f64;
+    let last_close = candles.last()?.close;
+    if last_close <= 0.0 {
+        return None;
+    }
+    Some(atr / last_close * 100.0)
+}
+
+/// Scale in [min_scale, 1] growing linearly with net APR up to `reference_apr_pct`
+pub fn quality_scale(net_apr_pct: f64, config: &SizingConfig) -> f64 {
+    if config.reference_apr_pct <= 0.0 {
+        return 1.0;
+    }
+    (net_apr_pct / config.reference_apr_pct).clamp(config.min_scale, 1.0)
+}
+
+/// Scale in [min_scale, 1] shrinking when volatility exceeds `target_atr_pct`
+pub fn volatility_scale(atr_pct: Option<f64>, config: &SizingConfig) -> f64 {
+    match atr_pct {
+        Some(atr) if atr > config.target_atr_pct && atr > 0.0 => {
+            (config.target_atr_pct / atr).clamp(config.min_scale, 1.0)
+        }
+        _ => 1.0,
+    }
+}
+
+/// Position size in base units after applying quality and volatility scaling to
+/// `max_position_size_usd`. Capital and lot constraints are those of
+/// `calculate_position_size`; with dynamic sizing disabled the result is identical.
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_dynamic_position_size(
+    extended_free_collateral: f64,
+    pacifica_free_collateral: f64,
+    extended_lot_size: f64,
+    pacifica_lot_size: f64,
+    current_price: f64,
+    max_position_size_usd: f64,
+    net_apr_pct: f64,
+    atr_pct: Option<f64>,
+    config: &SizingConfig,
+) -> f64 {
+    let scale = if config.dynamic {
+        quality_scale(net_apr_pct, config) * volatility_scale(atr_pct, config)
+    } else {
+        1.0
+    };
+
+    calculate_position_size(
+        extended_free_collateral,
+        pacifica_free_collateral,
+        extended_lot_size,
+        pacifica_lot_size,
+        current_price,
+        max_position_size_usd * scale,
+    )
+}
+
+/// Largest notional (USD) `free_collateral` can margin at `leverage`.
+///
+/// A position needs `notional x fraction` of initial margin, where the fraction is
+/// 1 / leverage or, when higher, the market's risk factor for the position's value:
+/// `tiers` are (upper bound USD, fraction) in ascending order, the fraction of the
+/// first tier whose bound covers the whole position applying. Positions beyond the
+/// last tier are not allowed, so the capacity stops at its bound. Without tiers the
+/// capacity is `free_collateral x leverage`.
+pub fn margin_capacity_usd(free_collateral: f64, leverage: u32, tiers: &[(f64, f64)]) -> f64 {
+    let leverage_fraction = 1.0 / leverage.max(1) as f64;
+    let free_collateral = free_collateral.max(0.0);
+    if tiers.is_empty() {
+        return free_collateral / leverage_fraction;
+    }
+
+    let mut capacity: f64 = 0.0;
+    let mut lower_bound = 0.0;
+    for &(upper_bound, risk_factor) in tiers {
+        let fraction = risk_factor.max(leverage_fraction);
+        let notional = (free_collateral / fraction).min(upper_bound);
+        if notional > lower_bound {
+            capacity = capacity.max(notional);
+        }
+        lower_bound = upper_bound;
+    }
+    capacity
+}
+
+/// Max position size (USD) scaled by equity growth since compounding started.
+///
+/// Gains above `base_equity_usd` are reinvested except for `profit_skim_pct` of them,
+/// which stays idle; losses shrink the size in full. Returns `max_position_size_usd`
+/// unchanged when no base equity is known.
+pub fn compounded_max_position_size_usd(
+    max_position_size_usd: f64,
+    base_equity_usd: f64,
+    current_equity_usd: f64,
+    profit_skim_pct: f64,
+) -> f64 {
+    if base_equity_usd <= 0.0 || current_equity_usd <= 0.0 {
+        return max_position_size_usd;
+    }
+    let profit = (current_equity_usd - base_equity_usd).max(0.0);
+    let deployable = current_equity_usd.min(base_equity_usd)
+        + profit * (1.0 - profit_skim_pct.clamp(0.0, 100.0) / 100.0);
+    max_position_size_usd * deployable / base_equity_usd
+}
+
+/// Fetch recent hourly Pacifica candles and return ATR as % of price
+pub async fn fetch_atr_pct(
+    client: &PacificaTrading,
+    symbol: &str,
+    periods: usize,
+) -> anyhow::Result<Option<f64>> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis() as u64;
+    let start = now - ((periods as u64 + 1) * 60 * 60 * 1000);
+
+    let candles: Vec<OhlcCandle> = client
+        .get_klines(symbol, "1h", start, now)
+        .await?
+        .iter()
+        .map(OhlcCandle::from)
+        .collect();
+
+    Ok(atr_pct(&candles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(dynamic: bool) -> SizingConfig {
+        SizingConfig {
+            dynamic,
+            reference_apr_pct: 50.0,
+            min_scale: 0.25,
+            target_atr_pct: 2.0,
+            atr_periods: 14,
+            compound: false,
+            profit_skim_pct: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_atr_pct() {
+        let candles = [
+            OhlcCandle { high: 101.0, low: 99.0, close: 100.0 },
+            OhlcCandle { high: 102.0, low: 100.0, close: 101.0 },
+            // Gap down: true range uses previous close (101 - 96 = 5)
+            OhlcCandle { high: 98.0, low: 96.0, close: 100.0 },
+        ];
+        // ATR = (2 + 5) / 2 = 3.5 on last close 100
+        assert!((atr_pct(&candles).unwrap() - 3.5).abs() < 1e-9);
+        assert!(atr_pct(&candles[..1]).is_none());
+    }
+
+    #[test]
+    fn test_scales() {
+        let cfg = config(true);
+        assert_eq!(quality_scale(100.0, &cfg), 1.0);
+        assert_eq!(quality_scale(25.0, &cfg), 0.5);
+        assert_eq!(quality_scale(1.0, &cfg), 0.25);
+
+        assert_eq!(volatility_scale(None, &cfg), 1.0);
+        assert_eq!(volatility_scale(Some(1.0), &cfg), 1.0);
+        assert_eq!(volatility_scale(Some(4.0), &cfg), 0.5);
+        assert_eq!(volatility_scale(Some(100.0), &cfg), 0.25);
+    }
+
+    #[test]
+    fn test_dynamic_size_matches_static_when_disabled() {
+        let static_size = calculate_position_size(10000.0, 10000.0, 0.001, 0.01, 50000.0, 1000.0);
+        let size = calculate_dynamic_position_size(
+            10000.0, 10000.0, 0.001, 0.01, 50000.0, 1000.0, 5.0, Some(10.0), &config(false),
+        );
+        assert_eq!(size, static_size);
+    }
+
+    #[test]
+    fn test_margin_capacity() {
+        // No tiers: plain leverage
+        assert!((margin_capacity_usd(1000.0, 5, &[]) - 5000.0).abs() < 1e-9);
+
+        let tiers = [(10_000.0, 0.1), (50_000.0, 0.25), (100_000.0, 0.5)];
+        // At 5x (20%) the first tier allows 10000, its bound; the second tier's
+        // 25% only margins 8000, which is back inside the first tier
+        assert!((margin_capacity_usd(2000.0, 5, &tiers) - 10_000.0).abs() < 1e-9);
+        // Enough collateral for the second tier at its 25% fraction
+        assert!((margin_capacity_usd(5000.0, 20, &tiers) - 20_000.0).abs() < 1e-9);
+        // High-IM market: 20x is capped by the 10% risk factor, not 5%
+        assert!((margin_capacity_usd(500.0, 20, &tiers) - 5000.0).abs() < 1e-9);
+        // Never beyond the last tier
+        assert!((margin_capacity_usd(1e9, 1, &tiers) - 100_000.0).abs() < 1e-9);
+        assert_eq!(margin_capacity_usd(-10.0, 3, &tiers), 0.0);
+    }
+
+    #[test]
+    fn test_compounded_max_position_size() {
+        // +20% equity fully reinvested
+        assert!((compounded_max_position_size_usd(1000.0, 2000.0, 2400.0, 0.0) - 1200.0).abs() < 1e-9);
+        // Half of the $400 profit skimmed
+        assert!((compounded_max_position_size_usd(1000.0, 2000.0, 2400.0, 50.0) - 1100.0).abs() < 1e-9);
+        // Losses are not skimmed
+        assert!((compounded_max_position_size_usd(1000.0, 2000.0, 1500.0, 50.0) - 750.0).abs() < 1e-9);
+        assert_eq!(compounded_max_position_size_usd(1000.0, 0.0, 1500.0, 0.0), 1000.0);
+    }
+
+    #[test]
+    fn test_dynamic_size_scales_down() {
+        // APR 25% -> 0.5, ATR 4% -> 0.5 => $250 cap => 0.005 BTC at 50k, lot 0.001
+        let size = calculate_dynamic_position_size(
+            10000.0, 10000.0, 0.001, 0.001, 50000.0, 1000.0, 25.0, Some(4.0), &config(true),
+        );
+        assert!((size - 0.005).abs() < 1e-12);
+    }
+}
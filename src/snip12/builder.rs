@@ -0,0 +1,166 @@
+//! Order builder with precomputed SNIP-12 constants
+//!
+//! Sliced execution signs many child orders for the same market and account. The
+//! domain hash, type hash, message prefix, asset IDs and keys are the same for every
+//! child, so `OrderBuilder` computes them once and each signature only hashes the
+//! order struct and signs it.
+
+use starknet_crypto::{rfc6979_generate_k, sign as stark_sign, Felt};
+
+use super::domain::StarknetDomain;
+use super::hash::{
+    calculate_settlement_expiration, get_message_prefix, get_order_type_hash, hash_domain, hash_message,
+    hash_order_with_type, OrderParams,
+};
+use super::signing::Signature;
+use super::{felt_to_hex, hex_to_felt};
+
+/// Fields that differ between orders signed by one builder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderAmounts {
+    /// Signed amount of synthetic (negative for SELL, positive for BUY)
+    pub base_amount: i128,
+    /// Signed amount of collateral (negative for BUY, positive for SELL)
+    pub quote_amount: i128,
+    /// Fee amount (always positive)
+    pub fee_amount: u128,
+    pub nonce: u64,
+    /// Order expiration in milliseconds
+    pub expiry_epoch_millis: u64,
+}
+
+/// Signs orders for one market, vault and key pair
+#[derive(Debug, Clone)]
+pub struct OrderBuilder {
+    prefix: Felt,
+    domain_hash: Felt,
+    order_type_hash: Felt,
+    account: Felt,
+    private_key: Felt,
+    position_id: u64,
+    base_asset_id: Felt,
+    quote_asset_id: Felt,
+}
+
+impl OrderBuilder {
+    /// Precompute the constants shared by every order
+    ///
+    /// # Arguments
+    /// * `base_asset_id` - Synthetic asset ID (hex string, e.g., "0x534f4c...")
+    /// * `quote_asset_id` - Collateral asset ID (hex string, e.g., "0x1"), also the fee asset
+    /// * `position_id` - Vault/collateral position ID
+    /// * `public_key` - Stark public key (hex string with 0x prefix)
+    /// * `private_key` - Stark private key (hex string with 0x prefix)
+    /// * `domain_chain_id` - "SN_MAIN" or "SN_SEPOLIA"
+    pub fn new(
+        base_asset_id: &str,
+        quote_asset_id: &str,
+        position_id: u64,
+        public_key: &str,
+        private_key: &str,
+        domain_chain_id: &str,
+    ) -> Result<Self, String> {
+        let private_key = hex_to_felt(private_key)
+            .map_err(|e| format!("Failed to parse private key '{}': {}", private_key, e))?;
+
+        Ok(Self {
+            prefix: get_message_prefix(),
+            domain_hash: hash_domain(&StarknetDomain::from_chain_id(domain_chain_id)),
+            order_type_hash: get_order_type_hash(),
+            account: hex_to_felt(public_key)?,
+            private_key,
+            position_id,
+            base_asset_id: hex_to_felt(base_asset_id)?,
+            quote_asset_id: hex_to_felt(quote_asset_id)?,
+        })
+    }
+
+    /// SNIP-12 message hash of `order`
+    pub fn message_hash(&self, order: &OrderAmounts) -> Felt {
+        let struct_hash = hash_order_with_type(
+            self.order_type_hash,
+            &OrderParams {
+                position_id: self.position_id,
+                base_asset_id: self.base_asset_id,
+                base_amount: order.base_amount,
+                quote_asset_id: self.quote_asset_id,
+                quote_amount: order.quote_amount,
+                fee_asset_id: self.quote_asset_id, // fee_asset_id is same as quote_asset_id
+                fee_amount: order.fee_amount,
+                expiration: calculate_settlement_expiration(order.expiry_epoch_millis) as u64,
+                salt: order.nonce,
+            },
+        );
+        hash_message(self.prefix, self.domain_hash, self.account, struct_hash)
+    }
+
+    /// Sign one order
+    ///
+    /// The ECDSA nonce is derived deterministically (RFC 6979), like Extended's SDK does.
+    pub fn sign(&self, order: &OrderAmounts) -> Result<Signature, String> {
+        let message_hash = self.message_hash(order);
+        let k = rfc6979_generate_k(&message_hash, &self.private_key, None);
+        let signature = stark_sign(&self.private_key, &message_hash, &k)
+            .map_err(|e| format!("Failed to sign: {:?}", e))?;
+
+        Ok(Signature {
+            r: felt_to_hex(&signature.r),
+            s: felt_to_hex(&signature.s),
+            message_hash: Some(felt_to_hex(&message_hash)),
+        })
+    }
+
+    /// Sign `orders` in order, failing on the first order that cannot be signed
+    pub fn sign_batch(&self, orders: &[OrderAmounts]) -> Result<Vec<Signature>, String> {
+        orders.iter().map(|order| self.sign(order)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snip12::get_order_message_hash;
+
+    #[test]
+    fn test_batch_matches_unbatched_signing() {
+        let public_key = "0x338f4cb92453dfb7c7764549d85ab624e6614db51b4c25c0fd63da09f07d127";
+        let private_key = "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abc";
+        let builder = OrderBuilder::new("0x534f4c2d33", "0x1", 226109, public_key, private_key, "SN_MAIN").unwrap();
+
+        let children: Vec<OrderAmounts> = (0..3)
+            .map(|i| OrderAmounts {
+                base_amount: -100 - i as i128,
+                quote_amount: 16229000,
+                fee_amount: 9738,
+                nonce: 1234567890 + i,
+                expiry_epoch_millis: 1700000000000,
+            })
+            .collect();
+        let batch = builder.sign_batch(&children).unwrap();
+        assert_eq!(batch.len(), 3);
+
+        for (child, signature) in children.iter().zip(&batch) {
+            let expected = get_order_message_hash(
+                226109,
+                "0x534f4c2d33",
+                child.base_amount,
+                "0x1",
+                child.quote_amount,
+                child.fee_amount,
+                "0x1",
+                child.expiry_epoch_millis,
+                child.nonce,
+                public_key,
+                &StarknetDomain::mainnet(),
+            )
+            .unwrap();
+            assert_eq!(signature.message_hash.as_deref(), Some(felt_to_hex(&expected).as_str()));
+
+            let single = builder.sign(child).unwrap();
+            assert_eq!((&single.r, &single.s), (&signature.r, &signature.s));
+        }
+        assert_ne!(batch[0].r, batch[1].r);
+
+        assert!(OrderBuilder::new("0x1", "0x1", 1, public_key, "not hex", "SN_MAIN").is_err());
+    }
+}
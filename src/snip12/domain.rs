@@ -1,4 +1,4 @@
-/// Starknet domain separator for SNIP-12
+//! Starknet domain separator for SNIP-12
 
 /// Domain separator to prevent replay attacks across different chains/applications
 #[derive(Debug, Clone)]
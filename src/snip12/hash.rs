@@ -1,4 +1,4 @@
-/// SNIP-12 hashing functions for order signing
+//! SNIP-12 hashing functions for order signing
 
 use starknet_crypto::{poseidon_hash_many, Felt};
 use sha3::{Digest, Keccak256};
@@ -20,14 +20,14 @@ use super::hex_to_felt;
 /// # Returns
 /// Settlement expiration time in seconds since epoch
 pub fn calculate_settlement_expiration(expiry_epoch_millis: u64) -> i64 {
-    let expiry_seconds = (expiry_epoch_millis / 1000) as i64;
+    let expiry_seconds = expiry_epoch_millis.div_ceil(1000) as i64;
     let buffer_seconds: i64 = 14 * 24 * 60 * 60; // 14 days
     expiry_seconds + buffer_seconds
 }
 
 /// Compute starknet_keccak hash
 ///
-/// This is standard Keccak-256 truncated to fit Starknet's field element size (251 bits).
+/// This is standard Keccak-256 masked to its low 250 bits, as defined by Starknet.
 /// Used for computing type hashes in SNIP-12.
 ///
 /// # Arguments
@@ -40,32 +40,37 @@ pub fn starknet_keccak(input: &[u8]) -> Felt {
     hasher.update(input);
     let result = hasher.finalize();
 
-    // Convert to Felt (automatically truncates to 251 bits)
-    // from_bytes_be expects a 32-byte array
+    // Keep the low 250 bits (from_bytes_be expects a 32-byte array)
     let mut bytes = [0u8; 32];
     bytes.copy_from_slice(&result);
+    bytes[0] &= 0x03;
     Felt::from_bytes_be(&bytes)
 }
 
 /// Compute the type hash for an Order struct
 ///
-/// The order type definition for Extended DEX perpetual orders.
-/// Format: "Order(position_id:felt,base_asset_id:felt,base_amount:felt,
-///          quote_asset_id:felt,quote_amount:felt,fee_amount:felt,
-///          fee_asset_id:felt,expiration:felt,salt:felt)"
+/// SNIP-12 revision 1 encoding of Extended's perpetuals `Order` struct followed by
+/// the types it references, exactly as the contract emits it:
+/// `"Order"("position_id":"felt","base_asset_id":"AssetId","base_amount":"i64",
+/// "quote_asset_id":"AssetId","quote_amount":"i64","fee_asset_id":"AssetId",
+/// "fee_amount":"u64","expiration":"Timestamp","salt":"felt")"PositionId"("value":"u32")
+/// "AssetId"("value":"felt")"Timestamp"("seconds":"u64")`
 pub fn get_order_type_hash() -> Felt {
     let order_type_string = concat!(
-        "Order(",
-        "position_id:felt,",
-        "base_asset_id:felt,",
-        "base_amount:felt,",
-        "quote_asset_id:felt,",
-        "quote_amount:felt,",
-        "fee_amount:felt,",
-        "fee_asset_id:felt,",
-        "expiration:felt,",
-        "salt:felt",
-        ")"
+        "\"Order\"(",
+        "\"position_id\":\"felt\",",
+        "\"base_asset_id\":\"AssetId\",",
+        "\"base_amount\":\"i64\",",
+        "\"quote_asset_id\":\"AssetId\",",
+        "\"quote_amount\":\"i64\",",
+        "\"fee_asset_id\":\"AssetId\",",
+        "\"fee_amount\":\"u64\",",
+        "\"expiration\":\"Timestamp\",",
+        "\"salt\":\"felt\"",
+        ")",
+        "\"PositionId\"(\"value\":\"u32\")",
+        "\"AssetId\"(\"value\":\"felt\")",
+        "\"Timestamp\"(\"seconds\":\"u64\")"
     );
 
     starknet_keccak(order_type_string.as_bytes())
@@ -74,11 +79,11 @@ pub fn get_order_type_hash() -> Felt {
 /// Compute the type hash for StarknetDomain
 pub fn get_domain_type_hash() -> Felt {
     let domain_type_string = concat!(
-        "StarknetDomain(",
-        "name:shortstring,",
-        "version:shortstring,",
-        "chainId:shortstring,",
-        "revision:shortstring",
+        "\"StarknetDomain\"(",
+        "\"name\":\"shortstring\",",
+        "\"version\":\"shortstring\",",
+        "\"chainId\":\"shortstring\",",
+        "\"revision\":\"shortstring\"",
         ")"
     );
 
@@ -114,7 +119,45 @@ pub fn hash_domain(domain: &StarknetDomain) -> Felt {
     poseidon_hash_many(&[type_hash, name, version, chain_id, revision])
 }
 
+/// Fields of Extended's `Order` struct, declared in hashing order
+///
+/// Asset IDs are the market's `l2Config` IDs, amounts are already scaled by the asset
+/// resolutions, and `expiration` is the settlement expiration in seconds (see
+/// `calculate_settlement_expiration`). The nonce is hashed as `salt`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderParams {
+    /// Vault/collateral position ID
+    pub position_id: u64,
+    /// Synthetic asset ID
+    pub base_asset_id: Felt,
+    /// Signed amount of synthetic (negative for SELL, positive for BUY)
+    pub base_amount: i128,
+    /// Collateral asset ID
+    pub quote_asset_id: Felt,
+    /// Signed amount of collateral (negative for BUY, positive for SELL)
+    pub quote_amount: i128,
+    /// Fee asset ID (the collateral asset on Extended)
+    pub fee_asset_id: Felt,
+    /// Fee amount (always positive)
+    pub fee_amount: u128,
+    /// Settlement expiration in seconds since epoch
+    pub expiration: u64,
+    /// Order nonce
+    pub salt: u64,
+}
+
 /// Hash the Order struct
+///
+/// `poseidon_hash([type_hash, position_id, base_asset_id, base_amount, quote_asset_id,
+/// quote_amount, fee_asset_id, fee_amount, expiration, salt])`, with negative amounts
+/// encoded as `PRIME - |amount|`. Verified against Extended's Python SDK
+/// (`fast_stark_crypto.get_order_msg_hash`), see the vectors in `snip12::tests`.
+pub fn hash_order(order: OrderParams) -> Felt {
+    hash_order_with_type(get_order_type_hash(), &order)
+}
+
+/// Hash the Order struct from hex asset IDs
+#[allow(clippy::too_many_arguments)]
 pub fn hash_order_struct(
     position_id: u64,
     base_asset_id: &str,
@@ -126,51 +169,62 @@ pub fn hash_order_struct(
     expiration_seconds: i64,
     salt: u64,
 ) -> Result<Felt, String> {
-    let type_hash = get_order_type_hash();
-
-    // Convert all parameters to Felts
-    let position_id_felt = Felt::from(position_id);
-    let base_asset_id_felt = hex_to_felt(base_asset_id)?;
-    let quote_asset_id_felt = hex_to_felt(quote_asset_id)?;
-    let fee_asset_id_felt = hex_to_felt(fee_asset_id)?;
-    let salt_felt = Felt::from(salt);
-
-    // Handle signed amounts - convert to Felt representation
-    let base_amount_felt = if base_amount >= 0 {
-        Felt::from(base_amount as u128)
+    Ok(hash_order(OrderParams {
+        position_id,
+        base_asset_id: hex_to_felt(base_asset_id)?,
+        base_amount,
+        quote_asset_id: hex_to_felt(quote_asset_id)?,
+        quote_amount,
+        fee_asset_id: hex_to_felt(fee_asset_id)?,
+        fee_amount,
+        expiration: expiration_seconds as u64,
+        salt,
+    }))
+}
+
+/// Encode a signed amount as a Felt
+pub(super) fn signed_amount_felt(amount: i128) -> Felt {
+    if amount >= 0 {
+        Felt::from(amount as u128)
     } else {
         // For negative numbers, use field arithmetic: -x = PRIME - x
         // Negate using Felt's sub operation: 0 - x
-        Felt::ZERO - Felt::from(base_amount.unsigned_abs())
-    };
+        Felt::ZERO - Felt::from(amount.unsigned_abs())
+    }
+}
 
-    let quote_amount_felt = if quote_amount >= 0 {
-        Felt::from(quote_amount as u128)
-    } else {
-        // For negative numbers: 0 - abs(x)
-        Felt::ZERO - Felt::from(quote_amount.unsigned_abs())
-    };
+/// Hash the Order struct with an already computed type hash
+pub(super) fn hash_order_with_type(type_hash: Felt, order: &OrderParams) -> Felt {
+    // Field ordering matches Extended's Order struct (fee_asset_id before fee_amount)
+    poseidon_hash_many(&[
+        type_hash,
+        Felt::from(order.position_id),
+        order.base_asset_id,
+        signed_amount_felt(order.base_amount),
+        order.quote_asset_id,
+        signed_amount_felt(order.quote_amount),
+        order.fee_asset_id,
+        Felt::from(order.fee_amount),
+        Felt::from(order.expiration),
+        Felt::from(order.salt),
+    ])
+}
 
-    let fee_amount_felt = Felt::from(fee_amount);
-    let expiration_felt = Felt::from(expiration_seconds as u64);
+/// Prefix every SNIP-12 message starts with: the short string "StarkNet Message"
+pub fn get_message_prefix() -> Felt {
+    encode_short_string("StarkNet Message")
+}
 
-    // Hash all fields together using Poseidon
-    // NOTE: Field ordering must match Extended's smart contract Order struct
-    // Current ordering is based on standard SNIP-12 conventions but may need adjustment
-    let struct_hash = poseidon_hash_many(&[
-        type_hash,
-        position_id_felt,
-        base_asset_id_felt,
-        base_amount_felt,
-        quote_asset_id_felt,
-        quote_amount_felt,
-        fee_amount_felt,
-        fee_asset_id_felt,
-        expiration_felt,
-        salt_felt,
-    ]);
-
-    Ok(struct_hash)
+/// Final message hash: poseidon_hash([prefix, domain_hash, account, struct_hash])
+pub(super) fn hash_message(prefix: Felt, domain_hash: Felt, account: Felt, struct_hash: Felt) -> Felt {
+    poseidon_hash_many(&[prefix, domain_hash, account, struct_hash])
+}
+
+/// Compute the SNIP-12 message hash that is signed for `order`
+///
+/// `account` is the Stark public key of the signer.
+pub fn hash_order_message(order: OrderParams, account: Felt, domain: &StarknetDomain) -> Felt {
+    hash_message(get_message_prefix(), hash_domain(domain), account, hash_order(order))
 }
 
 /// Compute the final SNIP-12 message hash for an order
@@ -178,11 +232,7 @@ pub fn hash_order_struct(
 /// This combines the domain separator, account address, and order struct hash
 /// according to the SNIP-12 specification.
 ///
-/// # Note
-/// This implementation follows standard SNIP-12 revision 1 conventions.
-/// The actual Order struct field ordering used by Extended DEX may differ
-/// from this implementation. For production use, consider using the Python SDK
-/// until the exact struct definition is confirmed.
+#[allow(clippy::too_many_arguments)]
 pub fn get_order_message_hash(
     position_id: u64,
     base_asset_id: &str,
@@ -218,13 +268,7 @@ pub fn get_order_message_hash(
     // Convert public key
     let account = hex_to_felt(user_public_key)?;
 
-    // Compute message prefix hash
-    let prefix = starknet_keccak(b"StarkNet Message");
-
-    // Final message hash: poseidon_hash([prefix, domain_hash, account, struct_hash])
-    let message_hash = poseidon_hash_many(&[prefix, domain_hash, account, struct_hash]);
-
-    Ok(message_hash)
+    Ok(hash_message(get_message_prefix(), domain_hash, account, struct_hash))
 }
 
 #[cfg(test)]
@@ -239,6 +283,9 @@ mod tests {
         // Should add 14 days in seconds
         let expected = (now_millis / 1000) as i64 + (14 * 24 * 60 * 60);
         assert_eq!(expiration, expected);
+
+        // A fractional second rounds up, as in the Python SDK
+        assert_eq!(calculate_settlement_expiration(now_millis + 1), expected + 1);
     }
 
     #[test]
@@ -299,6 +346,6 @@ mod tests {
         let mut hasher = Keccak256::new();
         hasher.update(domain_type_string.as_bytes());
         let result = hasher.finalize();
-        println!("Raw Keccak-256 bytes: 0x{}", hex::encode(&result));
+        println!("Raw Keccak-256 bytes: 0x{}", hex::encode(result));
     }
 }
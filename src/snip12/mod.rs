@@ -1,43 +1,47 @@
-/// SNIP-12 (Starknet Typed Data) implementation for Extended DEX order signing
-///
-/// This module implements the SNIP-12 revision 1 standard for signing orders
-/// on Extended DEX using pure Rust, without relying on the Python SDK.
-///
-/// # Current Status
-///
-/// **⚠️ Work in Progress**: This implementation correctly handles all SNIP-12 components
-/// (domain encoding, type hashing, Poseidon hashing) but produces different message
-/// hashes than Extended's Python SDK. This is due to an unknown Order struct field
-/// ordering difference in Extended's smart contract.
-///
-/// **Recommendation**: For production use, continue using Python SDK integration
-/// (via subprocess) until the exact Order struct definition is obtained from Extended.
-///
-/// # What's Implemented & Verified
-///
-/// - ✅ Domain separator encoding (Perpetuals, v0, SN_MAIN/SN_SEPOLIA, revision 1)
-/// - ✅ Type hash computation (Keccak-256 with modular reduction)
-/// - ✅ Short string encoding for domain fields
-/// - ✅ Negative number handling for signed amounts
-/// - ✅ Settlement expiration calculation (14-day buffer)
-/// - ✅ Poseidon hashing for struct and message hashes
-/// - ✅ ECDSA signing on STARK curve
-///
-/// # What Needs Confirmation
-///
-/// - ❓ Exact Order struct field ordering (currently uses standard conventions)
-/// - ❓ Whether user_public_key is part of the Order struct or added separately
-///
-/// # Future Work
-///
-/// Once Extended's exact Order struct definition is obtained:
-/// 1. Update `hash::get_order_type_hash()` with correct field order
-/// 2. Update `hash::hash_order_struct()` to match
-/// 3. Test against Python SDK to verify signatures match
-/// 4. Switch production code to use this pure Rust implementation
+//! SNIP-12 (Starknet Typed Data) implementation for Extended DEX order signing
+//!
+//! This module implements the SNIP-12 revision 1 standard for signing orders
+//! on Extended DEX using pure Rust, without relying on the Python SDK.
+//!
+//! # Order Hash Layout
+//!
+//! `hash_order` hashes Extended's `Order` struct with Poseidon, fields in this order:
+//!
+//! | # | Field            | Encoding                                   |
+//! |---|------------------|--------------------------------------------|
+//! | 0 | type hash        | `get_order_type_hash()`                    |
+//! | 1 | `position_id`    | vault ID                                   |
+//! | 2 | `base_asset_id`  | synthetic asset ID                         |
+//! | 3 | `base_amount`    | signed, negative as `PRIME - abs(amount)`  |
+//! | 4 | `quote_asset_id` | collateral asset ID                        |
+//! | 5 | `quote_amount`   | signed, negative as `PRIME - abs(amount)`  |
+//! | 6 | `fee_asset_id`   | collateral asset ID                        |
+//! | 7 | `fee_amount`     | unsigned                                   |
+//! | 8 | `expiration`     | settlement expiration in seconds (+14 days)|
+//! | 9 | `salt`           | order nonce                                |
+//!
+//! The signed message is `poseidon_hash(["StarkNet Message", domain_hash, public_key,
+//! order_hash])` (`hash_order_message`), signed with an RFC 6979 nonce. The test
+//! vectors in `tests` were produced by Extended's Python SDK (`fast_stark_crypto`);
+//! the message hash and both signature components match.
+//!
+//! # What's Implemented & Verified
+//!
+//! - ✅ Domain separator encoding (Perpetuals, v0, SN_MAIN/SN_SEPOLIA, revision 1)
+//! - ✅ Type hash computation (SNIP-12 revision 1 type encoding, 250-bit Keccak)
+//! - ✅ Short string encoding for domain fields and the message prefix
+//! - ✅ Negative number handling for signed amounts
+//! - ✅ Settlement expiration calculation (14-day buffer)
+//! - ✅ Poseidon hashing for struct and message hashes
+//! - ✅ ECDSA signing on STARK curve with RFC 6979 nonces
+//! - ✅ Batch signing with precomputed domain and asset constants (`OrderBuilder`)
+//!
+//! Production order placement still goes through the Python SDK subprocess
+//! (`crate::signature::sign_order`).
 
 use starknet_crypto::Felt;
 
+mod builder;
 mod domain;
 mod hash;
 mod signing;
@@ -45,8 +49,12 @@ mod signing;
 #[cfg(test)]
 mod tests;
 
+pub use builder::{OrderAmounts, OrderBuilder};
 pub use domain::StarknetDomain;
-pub use hash::{calculate_settlement_expiration, get_order_message_hash};
+pub use hash::{
+    calculate_settlement_expiration, get_message_prefix, get_order_message_hash, get_order_type_hash,
+    hash_order, hash_order_message, OrderParams,
+};
 pub use signing::{sign_order, Signature};
 
 /// Convert hex string to Felt
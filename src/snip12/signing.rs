@@ -1,10 +1,6 @@
-/// Order signing using ECDSA on the STARK curve
+//! Order signing using ECDSA on the STARK curve
 
-use starknet_crypto::{sign as stark_sign, Felt};
-
-use super::domain::StarknetDomain;
-use super::hash::get_order_message_hash;
-use super::{felt_to_hex, hex_to_felt};
+use super::builder::{OrderAmounts, OrderBuilder};
 
 /// ECDSA signature on the STARK curve
 #[derive(Debug, Clone)]
@@ -18,6 +14,7 @@ pub struct Signature {
 /// Sign an order using pure Rust implementation (no Python subprocess)
 ///
 /// This function implements the complete SNIP-12 signing flow for Extended DEX orders.
+/// To sign many orders for the same market, reuse an `OrderBuilder` instead.
 ///
 /// # Arguments
 /// * `base_asset_id` - Synthetic asset ID (hex string, e.g., "0x534f4c...")
@@ -34,6 +31,7 @@ pub struct Signature {
 ///
 /// # Returns
 /// Result containing the signature (r, s components as hex strings)
+#[allow(clippy::too_many_arguments)]
 pub fn sign_order(
     base_asset_id: &str,
     quote_asset_id: &str,
@@ -47,42 +45,21 @@ pub fn sign_order(
     private_key: &str,
     domain_chain_id: &str,
 ) -> Result<Signature, String> {
-    // Create domain separator
-    let domain = StarknetDomain::from_chain_id(domain_chain_id);
-
-    // Compute message hash
-    let message_hash = get_order_message_hash(
-        position_id,
+    let builder = OrderBuilder::new(
         base_asset_id,
-        base_amount,
         quote_asset_id,
+        position_id,
+        public_key,
+        private_key,
+        domain_chain_id,
+    )?;
+
+    builder.sign(&OrderAmounts {
+        base_amount,
         quote_amount,
         fee_amount,
-        quote_asset_id, // fee_asset_id is same as quote_asset_id
-        expiry_epoch_millis,
         nonce,
-        public_key,
-        &domain,
-    ).map_err(|e| format!("get_order_message_hash failed: {}", e))?;
-
-    // Convert private key from hex
-    let private_key_felt = hex_to_felt(private_key)
-        .map_err(|e| format!("Failed to parse private key '{}': {}", private_key, e))?;
-
-    // Sign the message hash using starknet-crypto
-    let signature =
-        stark_sign(&private_key_felt, &message_hash, &Felt::from(nonce))
-            .map_err(|e| format!("Failed to sign: {:?}", e))?;
-
-    // Convert signature components to hex strings
-    let r = felt_to_hex(&signature.r);
-    let s = felt_to_hex(&signature.s);
-    let message_hash_hex = felt_to_hex(&message_hash);
-
-    Ok(Signature {
-        r,
-        s,
-        message_hash: Some(message_hash_hex),
+        expiry_epoch_millis,
     })
 }
 
@@ -1,7 +1,7 @@
-/// Test vectors and comparison with Python SDK
-///
-/// This module provides utilities to test the Rust SNIP-12 implementation
-/// against the Python SDK to ensure 100% compatibility.
+//! Test vectors and comparison with Python SDK
+//!
+//! This module provides utilities to test the Rust SNIP-12 implementation
+//! against the Python SDK to ensure 100% compatibility.
 
 #[cfg(test)]
 use super::*;
@@ -52,28 +52,103 @@ impl OrderTestVector {
         }
     }
 
-    /// Create a test vector for a SELL order
+    /// Create a test vector for a SELL order: the Python SDK's SELL 0.001 BTC @ 43445.11653
     pub fn sell_order() -> Self {
+        Self::python_sdk_order(
+            -1000,
+            43445116,
+            21723,
+            1705626536861,
+            "0x604ef07147d4251385eaaa630e6a71db8f0a8c7cb33021c98698047db80edfa",
+            "0x6c707d9a06604d3f8ffd34378bf4fce7c0aaf50cba4cf37c3525c323106cda5",
+        )
+        .with_message_hash("0x6909589f9151be3f75b334a043904d92d6cb53c8a927c85c2e2c769ee75a0d9")
+    }
+
+    /// The SDK's same SELL built without an explicit expiration, at its default one
+    pub fn python_sdk_sell_default_expiration() -> Self {
+        Self::python_sdk_order(
+            -1000,
+            43445116,
+            21723,
+            1704420537000,
+            "0x3d17d8b9652e5f60d40d079653cfa92b1065ea8cf159609a3c390070dcd44f7",
+            "0x76a6deccbc84ac324f695cfbde80e0ed62443e95f5dcd8722d12650ccc122e5",
+        )
+        .with_message_hash("0x12bc162fe9a46e9056b595a8ca56aaec4cfe1c009786fdca3430f52c9be89d7")
+    }
+
+    /// The SDK's BUY 0.001 BTC @ 43445.11653, rounded up against the buyer
+    pub fn python_sdk_buy() -> Self {
+        Self::python_sdk_order(
+            1000,
+            -43445117,
+            21723,
+            1705626536861,
+            "0xa55625c7d5f1b85bed22556fc805224b8363074979cf918091d9ddb1403e13",
+            "0x504caf634d859e643569743642ccf244434322859b2421d76f853af43ae7a46",
+        )
+        .with_message_hash("0x58454e78c25644cbcab59444736d573f169fb0996dafe1900a05e2ac50567f0")
+    }
+
+    /// Order of the Extended Python SDK's order tests (`tests/perpetual/test_order_object.py`):
+    /// its test account (vault 10002) on the testnet BTC-USD market, nonce 1473459052
+    fn python_sdk_order(
+        base_amount: i128,
+        quote_amount: i128,
+        fee_amount: u128,
+        expiry_epoch_millis: u64,
+        expected_r: &str,
+        expected_s: &str,
+    ) -> Self {
         Self {
-            base_asset_id: "0x534f4c2d33".to_string(),
-            quote_asset_id: "0x1".to_string(),
-            base_amount: -100,      // negative for SELL
-            quote_amount: 16229000, // positive for SELL
-            fee_amount: 9738,
-            position_id: 226109,
-            nonce: 1234567891,  // Different nonce from buy order
-            expiry_epoch_millis: 1700000000000,
-            public_key: "0x338f4cb92453dfb7c7764549d85ab624e6614db51b4c25c0fd63da09f07d127"
-                .to_string(),
-            private_key: "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abc"
-                .to_string(),  // Valid 251-bit key
-            domain_chain_id: "SN_MAIN".to_string(),
-            expected_r: None,  // Will be generated during test
-            expected_s: None,
+            base_asset_id: "0x4254432d3600000000000000000000".to_string(), // BTC-6
+            quote_asset_id: "0x31857064564ed0ff978e687456963cba09c2c6985d8f9300a1de4962fafa054".to_string(),
+            base_amount,
+            quote_amount,
+            fee_amount,
+            position_id: 10002,
+            nonce: 1473459052,
+            expiry_epoch_millis,
+            public_key: "0x61c5e7e8339b7d56f197f54ea91b776776690e3232313de0f2ecbd0ef76f466".to_string(),
+            private_key: "0x7a7ff6fd3cab02ccdcd4a572563f5976f8976899b03a39773795a3c486d4986".to_string(),
+            domain_chain_id: "SN_SEPOLIA".to_string(),
+            expected_r: Some(expected_r.to_string()),
+            expected_s: Some(expected_s.to_string()),
             expected_message_hash: None,
         }
     }
 
+    /// Order hash the SDK reports as the order ID, in hex
+    fn with_message_hash(mut self, message_hash: &str) -> Self {
+        self.expected_message_hash = Some(message_hash.to_string());
+        self
+    }
+
+    /// Take-profit leg of the SDK's BUY 0.001 BTC order: a SELL at 50000, fee 0.0005
+    pub fn python_sdk_take_profit_leg() -> Self {
+        Self::python_sdk_order(
+            -1000,
+            50000000,
+            25000,
+            1705626536861,
+            "0x19a043716e5b47bdfa8743e1cad471da3a86dc5a4044a87fb51bea4d61d788c",
+            "0x70db738d6d4896b757e062fec0f3eb8fdcf7d5de23ace3d3c44c1fc9c9c66d4",
+        )
+    }
+
+    /// Stop-loss leg of the same order: a SELL at 39000
+    pub fn python_sdk_stop_loss_leg() -> Self {
+        Self::python_sdk_order(
+            -1000,
+            39000000,
+            19500,
+            1705626536861,
+            "0xa1d28df388fb5038c2475527667b726ccec821d8362a803702b3a0428ba647",
+            "0x511a2c6a9dc215d965ca08fe2c1533923b2470b1625e1144c70c63b26671086",
+        )
+    }
+
     /// Sign this test vector using Python SDK and return the result
     pub fn sign_with_python(&self) -> Result<serde_json::Value, String> {
         let input_json = serde_json::json!({
@@ -155,13 +230,9 @@ impl OrderTestVector {
 
     /// Compare Rust and Python implementations
     ///
-    /// Note: This currently shows differences due to unknown Order struct field ordering.
-    /// This is informational only - the comparison will not match until Extended's
-    /// exact struct definition is obtained.
+    /// Informational: prints both results side by side. Needs the Python SDK installed.
     pub fn compare_implementations(&self) {
         println!("\n=== COMPARING RUST VS PYTHON ===\n");
-        println!("NOTE: Signatures currently differ due to unknown Order struct field ordering");
-        println!("See DOC/SNIP12_STATUS.md for details\n");
 
         let python_result = match self.sign_with_python() {
             Ok(r) => r,
@@ -191,7 +262,7 @@ impl OrderTestVector {
         if hashes_match {
             println!("✓ Message hashes MATCH");
         } else {
-            println!("✗ Message hashes DIFFER (expected - struct ordering issue)");
+            println!("✗ Message hashes DIFFER");
         }
 
         println!("\n--- Signatures ---");
@@ -204,10 +275,8 @@ impl OrderTestVector {
 
         if rust_sig.r == python_r && rust_sig.s == python_s {
             println!("\n✓ SIGNATURES MATCH! 🎉");
-            println!("The Rust implementation is now ready for production use!");
         } else {
-            println!("\n✗ Signatures differ (expected until struct definition is fixed)");
-            println!("For production, use Python subprocess integration");
+            println!("\n✗ Signatures differ");
         }
     }
 }
@@ -232,3 +301,145 @@ mod comparison_tests {
         test_vector.compare_implementations();
     }
 }
+
+#[cfg(test)]
+mod vector_tests {
+    use super::*;
+
+    /// `OrderParams` for a test vector, fee paid in the collateral asset
+    fn order_params(vector: &OrderTestVector) -> OrderParams {
+        OrderParams {
+            position_id: vector.position_id,
+            base_asset_id: hex_to_felt(&vector.base_asset_id).unwrap(),
+            base_amount: vector.base_amount,
+            quote_asset_id: hex_to_felt(&vector.quote_asset_id).unwrap(),
+            quote_amount: vector.quote_amount,
+            fee_asset_id: hex_to_felt(&vector.quote_asset_id).unwrap(),
+            fee_amount: vector.fee_amount,
+            expiration: calculate_settlement_expiration(vector.expiry_epoch_millis) as u64,
+            salt: vector.nonce,
+        }
+    }
+
+    #[test]
+    fn test_type_hashes_match_extended_contract() {
+        // Selectors hardcoded in Extended's SDKs and the perpetuals contract
+        assert_eq!(
+            felt_to_hex(&get_order_type_hash()),
+            "0x36da8d51815527cabfaa9c982f564c80fa7429616739306036f1f9b608dd112"
+        );
+        assert_eq!(
+            felt_to_hex(&hash::get_domain_type_hash()),
+            "0x1ff2f602e42168014d405a94f75e8a93d640751d71d16311266e140d8b0a210"
+        );
+    }
+
+    #[test]
+    fn test_python_buy_vector() {
+        let vector = OrderTestVector::buy_order();
+        let account = hex_to_felt(&vector.public_key).unwrap();
+        let message_hash = hash_order_message(order_params(&vector), account, &StarknetDomain::mainnet());
+        assert_eq!(Some(felt_to_hex(&message_hash)), vector.expected_message_hash);
+
+        let signature = vector.sign_with_rust().unwrap();
+        assert_eq!(signature.message_hash, vector.expected_message_hash);
+        assert_eq!(Some(signature.r), vector.expected_r);
+        assert_eq!(Some(signature.s), vector.expected_s);
+    }
+
+    #[test]
+    fn test_python_sdk_order_vectors() {
+        // Both sides, the 0.0005 fee and both expirations of the SDK's order tests, with
+        // the amounts the SDK signed. Reduce-only is a flag of the order request, outside
+        // the signed settlement, so the SDK's reduce-only SELL signs as the plain one.
+        let vectors = [
+            OrderTestVector::sell_order(),
+            OrderTestVector::python_sdk_sell_default_expiration(),
+            OrderTestVector::python_sdk_buy(),
+        ];
+        for vector in vectors {
+            let account = hex_to_felt(&vector.public_key).unwrap();
+            let message_hash = hash_order_message(order_params(&vector), account, &StarknetDomain::testnet());
+            assert_eq!(Some(felt_to_hex(&message_hash)), vector.expected_message_hash);
+
+            let signature = vector.sign_with_rust().unwrap();
+            assert_eq!(signature.message_hash, vector.expected_message_hash);
+            assert_eq!(Some(signature.r), vector.expected_r);
+            assert_eq!(Some(signature.s), vector.expected_s);
+        }
+    }
+
+    #[test]
+    fn test_python_sdk_tpsl_leg_vectors() {
+        // Each leg is the opposite side of the order at the leg's own price, with the
+        // order's quantity and fee rate: the amounts `RestClient` signs for it
+        let legs = [
+            (OrderTestVector::python_sdk_take_profit_leg(), 50000.0),
+            (OrderTestVector::python_sdk_stop_loss_leg(), 39000.0),
+        ];
+        for (vector, price) in legs {
+            let amounts = crate::signature::calculate_signed_amounts(
+                &crate::types::OrderSide::Sell, 0.001, price, 0.0005, 1_000_000, 1_000_000,
+            );
+            assert_eq!(amounts, (vector.base_amount, vector.quote_amount, vector.fee_amount));
+
+            // The legs are signed with the order's nonce: the request carries only that one
+            let signature = vector.sign_with_rust().unwrap();
+            assert_eq!(Some(signature.r), vector.expected_r);
+            assert_eq!(Some(signature.s), vector.expected_s);
+        }
+    }
+
+    #[test]
+    fn test_hash_order_matches_hex_entry_points() {
+        for vector in [OrderTestVector::buy_order(), OrderTestVector::sell_order()] {
+            let params = order_params(&vector);
+            let from_hex = hash::hash_order_struct(
+                vector.position_id,
+                &vector.base_asset_id,
+                vector.base_amount,
+                &vector.quote_asset_id,
+                vector.quote_amount,
+                vector.fee_amount,
+                &vector.quote_asset_id,
+                params.expiration as i64,
+                vector.nonce,
+            )
+            .unwrap();
+            assert_eq!(hash_order(params), from_hex);
+
+            let message_hash = get_order_message_hash(
+                vector.position_id,
+                &vector.base_asset_id,
+                vector.base_amount,
+                &vector.quote_asset_id,
+                vector.quote_amount,
+                vector.fee_amount,
+                &vector.quote_asset_id,
+                vector.expiry_epoch_millis,
+                vector.nonce,
+                &vector.public_key,
+                &StarknetDomain::mainnet(),
+            )
+            .unwrap();
+            let account = hex_to_felt(&vector.public_key).unwrap();
+            assert_eq!(hash_order_message(params, account, &StarknetDomain::mainnet()), message_hash);
+        }
+    }
+
+    #[test]
+    fn test_hash_order_field_sensitivity() {
+        let base = order_params(&OrderTestVector::sell_order());
+        let hash = hash_order(base);
+
+        // Swapping fee_asset_id and fee_amount must change the hash
+        let swapped = OrderParams {
+            fee_asset_id: Felt::from(base.fee_amount),
+            fee_amount: 1,
+            ..base
+        };
+        assert_ne!(hash_order(swapped), hash);
+        assert_ne!(hash_order(OrderParams { salt: base.salt + 1, ..base }), hash);
+        assert_ne!(hash_order(OrderParams { base_amount: -base.base_amount, ..base }), hash);
+    }
+}
@@ -0,0 +1,156 @@
+//! Cross-exchange spread history per symbol
+//!
+//! A single snapshot of the mid-price gap between Extended and Pacifica is noisy: one
+//! stale quote can exclude an otherwise good market for a whole cycle. With
+//! `spread_history.enabled`, every scan records each symbol's cross spread and the
+//! `max_cross_exchange_spread_pct` filter is applied to a percentile (the median by
+//! default) of the last `window_samples` samples instead. Until a symbol has
+//! `min_samples` samples the latest one is used, as without history. With `persist`,
+//! the history is kept in `spread_history.json` (override with `SPREAD_HISTORY_PATH`)
+//! so restarts do not reset it.
+use crate::error::BotError;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+pub const DEFAULT_SPREAD_HISTORY_FILE: &str = "spread_history.json";
+
+/// Spread history path, overridable with `SPREAD_HISTORY_PATH`
+pub fn resolve_spread_history_path() -> String {
+    std::env::var("SPREAD_HISTORY_PATH").unwrap_or_else(|_| DEFAULT_SPREAD_HISTORY_FILE.to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpreadHistoryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Samples kept per symbol (one per scan)
+    #[serde(default = "default_window_samples")]
+    pub window_samples: usize,
+    /// Percentile of the window compared against `max_cross_exchange_spread_pct`
+    #[serde(default = "default_percentile")]
+    pub percentile: f64,
+    /// Samples needed before the percentile replaces the latest sample
+    #[serde(default = "default_min_samples")]
+    pub min_samples: usize,
+    /// Keep the history in a file across restarts
+    #[serde(default)]
+    pub persist: bool,
+}
+
+fn default_window_samples() -> usize {
+    30
+}
+
+fn default_percentile() -> f64 {
+    50.0
+}
+
+fn default_min_samples() -> usize {
+    5
+}
+
+impl Default for SpreadHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_samples: default_window_samples(),
+            percentile: default_percentile(),
+            min_samples: default_min_samples(),
+            persist: false,
+        }
+    }
+}
+
+/// Latest cross spreads (%) per symbol, oldest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpreadHistory {
+    samples: HashMap<String, VecDeque<f64>>,
+}
+
+impl SpreadHistory {
+    /// Read a saved history; a missing file is an empty history
+    pub fn load_from_file(path: &str) -> Result<Self, BotError> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Write atomically (temp file + rename)
+    pub fn save_to_file(&self, path: &str) -> Result<(), BotError> {
+        let tmp_path = format!("{}.tmp", path);
+        fs::write(&tmp_path, serde_json::to_string(self)?)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Add a sample for `symbol`, dropping the oldest beyond `window` samples
+    pub fn record(&mut self, symbol: &str, cross_spread_pct: f64, window: usize) {
+        if !cross_spread_pct.is_finite() {
+            return;
+        }
+        let samples = self.samples.entry(symbol.to_string()).or_default();
+        samples.push_back(cross_spread_pct);
+        while samples.len() > window.max(1) {
+            samples.pop_front();
+        }
+    }
+
+    pub fn sample_count(&self, symbol: &str) -> usize {
+        self.samples.get(symbol).map_or(0, VecDeque::len)
+    }
+
+    /// Nearest-rank `percentile` (0-100) of the recorded samples for `symbol`
+    pub fn percentile(&self, symbol: &str, percentile: f64) -> Option<f64> {
+        let mut sorted: Vec<f64> = self.samples.get(symbol)?.iter().copied().collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.saturating_sub(1).min(sorted.len() - 1)])
+    }
+
+    /// Record `cross_spread_pct` and return the spread the filter should judge: the
+    /// configured percentile once `min_samples` are recorded, `None` before
+    pub fn record_and_filter_value(&mut self, symbol: &str, cross_spread_pct: f64, config: &SpreadHistoryConfig) -> Option<f64> {
+        self.record(symbol, cross_spread_pct, config.window_samples);
+        if self.sample_count(symbol) < config.min_samples.max(1) {
+            return None;
+        }
+        self.percentile(symbol, config.percentile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_over_rolling_window() {
+        let config = SpreadHistoryConfig { enabled: true, window_samples: 5, min_samples: 3, ..SpreadHistoryConfig::default() };
+        let mut history = SpreadHistory::default();
+        assert_eq!(history.record_and_filter_value("BTC", 0.05, &config), None);
+        assert_eq!(history.record_and_filter_value("BTC", 0.9, &config), None);
+        // One noisy sample no longer decides: the median of [0.05, 0.9, 0.07] is 0.07
+        assert_eq!(history.record_and_filter_value("BTC", 0.07, &config), Some(0.07));
+
+        for spread in [0.06, 0.08, 0.04, 0.05] {
+            history.record("BTC", spread, config.window_samples);
+        }
+        // Window keeps the last 5: [0.07, 0.06, 0.08, 0.04, 0.05]
+        assert_eq!(history.sample_count("BTC"), 5);
+        assert_eq!(history.percentile("BTC", 100.0), Some(0.08));
+        assert_eq!(history.percentile("BTC", 0.0), Some(0.04));
+        assert_eq!(history.percentile("ETH", 50.0), None);
+
+        let path = std::env::temp_dir().join(format!("spread_history_test_{}.json", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        history.save_to_file(path).unwrap();
+        assert_eq!(SpreadHistory::load_from_file(path).unwrap().percentile("BTC", 50.0), Some(0.06));
+        let _ = fs::remove_file(path);
+    }
+}
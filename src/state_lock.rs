@@ -0,0 +1,182 @@
+//! Single-instance lock on the state file
+//!
+//! Two bots trading one account from the same state file open double positions and
+//! overwrite each other's state. Before touching any position the bot takes an OS
+//! file lock on `<state file>.lock` and writes its PID there for operators; a second
+//! instance finding the file locked refuses to start. The OS releases the lock when
+//! the holding process exits, crashed or not, so a lock is never left stale. The file
+//! itself stays in place: removing it would let a waiting instance and a new one lock
+//! two different files.
+//!
+//! A Redis store is shared across hosts, where a local file would not keep another
+//! instance out. There the lock is the key `<key>.lock`, set with `SET NX PX` to a
+//! token naming the holder and expiring after `REDIS_LOCK_TTL_SECS` unless the holder
+//! keeps refreshing it, so a crashed bot's lock frees itself.
+use crate::error::BotError;
+use crate::state_store::StateStore;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+
+/// Lifetime of a Redis lock that is no longer refreshed
+#[cfg(feature = "redis")]
+const REDIS_LOCK_TTL_SECS: u64 = 30;
+/// How often the holder extends its Redis lock
+#[cfg(feature = "redis")]
+const REDIS_LOCK_REFRESH_SECS: u64 = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct LockOwner {
+    pid: u32,
+    /// Unix timestamp (seconds) of acquisition
+    acquired_at: u64,
+    /// Distinguishes holders on different hosts; only set for Redis locks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+}
+
+/// Held lock on a state store, released when dropped or when the process exits
+#[derive(Debug)]
+pub struct StateLock {
+    _held: Held,
+}
+
+#[derive(Debug)]
+enum Held {
+    /// The OS lock lives as long as this handle
+    File { _file: File },
+    #[cfg(feature = "redis")]
+    Redis { _lock: RedisLock },
+}
+
+impl StateLock {
+    /// Lock file guarding `state_path`
+    pub fn lock_path(state_path: &str) -> String {
+        format!("{}.lock", state_path)
+    }
+
+    /// Take the instance lock on `store`. Fails with `BotError::InstanceLocked` while
+    /// another instance holds it.
+    pub fn acquire(store: &StateStore, now: u64) -> Result<Self, BotError> {
+        let held = match store {
+            StateStore::File(path) => Held::File { _file: lock_file(path, now)? },
+            #[cfg(feature = "sqlite")]
+            StateStore::Sqlite(path) => Held::File { _file: lock_file(path, now)? },
+            #[cfg(feature = "redis")]
+            StateStore::Redis { url, key } => Held::Redis { _lock: RedisLock::acquire(store, url, key, now)? },
+        };
+        Ok(Self { _held: held })
+    }
+}
+
+fn lock_file(state_path: &str, now: u64) -> Result<File, BotError> {
+    let path = StateLock::lock_path(state_path);
+    let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            // PID 0 when the holder has not written itself in yet
+            let pid = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<LockOwner>(&content).ok())
+                .map_or(0, |holder| holder.pid);
+            return Err(BotError::InstanceLocked { pid, path });
+        }
+        Err(TryLockError::Error(e)) => return Err(e.into()),
+    }
+
+    let owner = LockOwner { pid: std::process::id(), acquired_at: now, instance: None };
+    file.set_len(0)?;
+    file.write_all(serde_json::to_string(&owner)?.as_bytes())?;
+    Ok(file)
+}
+
+#[cfg(feature = "redis")]
+#[derive(Debug)]
+struct RedisLock {
+    url: String,
+    lock_key: String,
+    /// Value only this holder writes, so it never extends or deletes someone else's lock
+    token: String,
+    refresher: Option<tokio::task::JoinHandle<()>>,
+}
+
+#[cfg(feature = "redis")]
+impl RedisLock {
+    fn acquire(store: &StateStore, url: &str, key: &str, now: u64) -> Result<Self, BotError> {
+        use crate::state_store::redis_store;
+
+        let lock_key = StateLock::lock_path(key);
+        let owner = LockOwner { pid: std::process::id(), acquired_at: now, instance: Some(uuid::Uuid::new_v4().to_string()) };
+        let token = serde_json::to_string(&owner)?;
+        if !redis_store::try_lock(url, &lock_key, &token, REDIS_LOCK_TTL_SECS * 1000)? {
+            let pid = redis_store::get(url, &lock_key)?
+                .and_then(|content| serde_json::from_str::<LockOwner>(&content).ok())
+                .map_or(0, |holder| holder.pid);
+            return Err(BotError::InstanceLocked { pid, path: format!("{}.lock", store) });
+        }
+
+        // Refreshed outside the trading loop, so a long cycle cannot let the lock expire
+        let refresher = tokio::runtime::Handle::try_current().ok().map(|runtime| {
+            let (url, lock_key, token) = (url.to_string(), lock_key.clone(), token.clone());
+            runtime.spawn(async move {
+                let mut ticker = tokio::time::interval(std::time::Duration::from_secs(REDIS_LOCK_REFRESH_SECS));
+                ticker.tick().await;
+                loop {
+                    ticker.tick().await;
+                    let (url, key, token) = (url.clone(), lock_key.clone(), token.clone());
+                    let refreshed = tokio::task::spawn_blocking(move || {
+                        redis_store::refresh_lock(&url, &key, &token, REDIS_LOCK_TTL_SECS * 1000)
+                    })
+                    .await;
+                    match refreshed {
+                        Ok(Ok(true)) => {}
+                        Ok(Ok(false)) => tracing::error!("Lost the instance lock {}: another bot may now trade from this store", lock_key),
+                        Ok(Err(e)) => tracing::warn!("Failed to refresh the instance lock {}: {}", lock_key, e),
+                        Err(e) => tracing::warn!("Instance lock refresh task failed: {}", e),
+                    }
+                }
+            })
+        });
+        Ok(Self { url: url.to_string(), lock_key, token, refresher })
+    }
+}
+
+#[cfg(feature = "redis")]
+impl Drop for RedisLock {
+    fn drop(&mut self) {
+        if let Some(refresher) = self.refresher.take() {
+            refresher.abort();
+        }
+        if let Err(e) = crate::state_store::redis_store::release_lock(&self.url, &self.lock_key, &self.token) {
+            tracing::warn!("Failed to release the instance lock {}: {}; it expires in {}s", self.lock_key, e, REDIS_LOCK_TTL_SECS);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_excludes_other_holders_until_released() {
+        let state_path = std::env::temp_dir().join(format!("state_lock_test_{}.json", std::process::id()));
+        let state_path = state_path.to_str().unwrap();
+        let lock_path = StateLock::lock_path(state_path);
+        let store = StateStore::File(state_path.to_string());
+
+        // A file left by a process that is gone holds no lock
+        fs::write(&lock_path, r#"{"pid": 1, "acquired_at": 0}"#).unwrap();
+        let lock = StateLock::acquire(&store, 1_000).unwrap();
+        assert!(matches!(
+            StateLock::acquire(&store, 2_000),
+            Err(BotError::InstanceLocked { pid, .. }) if pid == std::process::id()
+        ));
+
+        drop(lock);
+        assert!(fs::metadata(&lock_path).is_ok());
+        let taken = StateLock::acquire(&store, 3_000).unwrap();
+        drop(taken);
+        let _ = fs::remove_file(&lock_path);
+    }
+}
@@ -0,0 +1,338 @@
+//! Where the bot state is persisted
+//!
+//! `STATE_FILE_PATH` takes a plain path (as before) or a URI whose scheme picks the
+//! backend, so deployments on ephemeral containers can keep their state outside the
+//! container:
+//!
+//! | URI                              | Backend                                          | Feature  |
+//! |----------------------------------|--------------------------------------------------|----------|
+//! | `bot_state.json`, `file://path`  | JSON file, previous version kept as `path.bak`   | -        |
+//! | `sqlite://bot.db`                | single-row table `bot_state` in a SQLite file    | `sqlite` |
+//! | `redis://host:6379/0#bot_state`  | key `bot_state` (the fragment), backup `.bak`    | `redis`  |
+//!
+//! Every backend stores the same JSON document and keeps the previous one as a backup,
+//! read when the current one does not parse. A scheme whose feature was not compiled in
+//! is a configuration error rather than a silent fallback to a local file.
+//!
+//! The backends block on disk or network I/O; code on the async runtime goes through
+//! `StateStore::blocking` so a slow SQLite file or Redis server cannot stall the loop.
+use crate::error::BotError;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+/// Redis key used when the URI has no fragment
+#[cfg(feature = "redis")]
+const DEFAULT_REDIS_KEY: &str = "bot_state";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateStore {
+    File(String),
+    #[cfg(feature = "sqlite")]
+    Sqlite(String),
+    #[cfg(feature = "redis")]
+    Redis { url: String, key: String },
+}
+
+impl StateStore {
+    /// Backend for `uri`; a bare path is a file
+    pub fn parse(uri: &str) -> Result<Self, BotError> {
+        let Some((scheme, rest)) = uri.split_once("://") else {
+            return Ok(StateStore::File(uri.to_string()));
+        };
+        match scheme {
+            "file" => Ok(StateStore::File(rest.to_string())),
+            #[cfg(feature = "sqlite")]
+            "sqlite" => Ok(StateStore::Sqlite(rest.to_string())),
+            #[cfg(feature = "redis")]
+            "redis" | "rediss" => {
+                let (url, key) = match uri.split_once('#') {
+                    Some((url, key)) if !key.is_empty() => (url, key),
+                    Some((url, _)) => (url, DEFAULT_REDIS_KEY),
+                    None => (uri, DEFAULT_REDIS_KEY),
+                };
+                Ok(StateStore::Redis { url: url.to_string(), key: key.to_string() })
+            }
+            #[cfg(not(feature = "sqlite"))]
+            "sqlite" => Err(missing_feature(scheme, "sqlite")),
+            #[cfg(not(feature = "redis"))]
+            "redis" | "rediss" => Err(missing_feature(scheme, "redis")),
+            other => Err(BotError::Config(format!("unknown state store scheme {}://", other))),
+        }
+    }
+
+    /// The current document, `None` when nothing was stored yet
+    pub fn read(&self) -> Result<Option<String>, BotError> {
+        match self {
+            StateStore::File(path) => read_file(path),
+            #[cfg(feature = "sqlite")]
+            StateStore::Sqlite(path) => sqlite::read(path, "content"),
+            #[cfg(feature = "redis")]
+            StateStore::Redis { url, key } => redis_store::get(url, key),
+        }
+    }
+
+    /// The document the last write replaced
+    pub fn read_backup(&self) -> Result<Option<String>, BotError> {
+        match self {
+            StateStore::File(path) => read_file(&format!("{}.bak", path)),
+            #[cfg(feature = "sqlite")]
+            StateStore::Sqlite(path) => sqlite::read(path, "backup"),
+            #[cfg(feature = "redis")]
+            StateStore::Redis { url, key } => redis_store::get(url, &format!("{}.bak", key)),
+        }
+    }
+
+    /// Replace the current document, keeping the previous one as the backup
+    pub fn write(&self, content: &str) -> Result<(), BotError> {
+        match self {
+            StateStore::File(path) => write_file(path, content),
+            #[cfg(feature = "sqlite")]
+            StateStore::Sqlite(path) => sqlite::write(path, content),
+            #[cfg(feature = "redis")]
+            StateStore::Redis { url, key } => redis_store::set(url, key, content),
+        }
+    }
+
+    /// Run `op` against this store on tokio's blocking pool
+    pub async fn blocking<T, F>(&self, op: F) -> Result<T, BotError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&StateStore) -> Result<T, BotError> + Send + 'static,
+    {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || op(&store))
+            .await
+            .map_err(|e| BotError::Other(format!("State store task failed: {}", e)))?
+    }
+}
+
+impl fmt::Display for StateStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateStore::File(path) => write!(f, "{}", path),
+            #[cfg(feature = "sqlite")]
+            StateStore::Sqlite(path) => write!(f, "sqlite://{}", path),
+            #[cfg(feature = "redis")]
+            StateStore::Redis { url, key } => {
+                // Never log the password
+                match url::Url::parse(url) {
+                    Ok(mut shown) => {
+                        if shown.password().is_some() {
+                            let _ = shown.set_password(Some("***"));
+                        }
+                        write!(f, "{}#{}", shown, key)
+                    }
+                    // Unparseable: keep the scheme and host, drop any credentials
+                    Err(_) => match (url.split_once("://"), url.rsplit_once('@')) {
+                        (Some((scheme, _)), Some((_, host))) => write!(f, "{}://***@{}#{}", scheme, host, key),
+                        _ => write!(f, "{}#{}", url, key),
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(all(feature = "sqlite", feature = "redis")))]
+fn missing_feature(scheme: &str, feature: &str) -> BotError {
+    BotError::Config(format!("state store {}:// needs a build with the `{}` feature", scheme, feature))
+}
+
+fn read_file(path: &str) -> Result<Option<String>, BotError> {
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+    Ok(Some(fs::read_to_string(path)?))
+}
+
+fn write_file(path: &str, content: &str) -> Result<(), BotError> {
+    if let Some(parent) = Path::new(path).parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    if Path::new(path).exists() {
+        let backup_path = format!("{}.bak", path);
+        if let Err(e) = fs::copy(path, &backup_path) {
+            warn!("Failed to write backup state file {}: {}", backup_path, e);
+        }
+    }
+    let temp_path = format!("{}.tmp", path);
+    fs::write(&temp_path, content)?;
+    if let Err(e) = fs::rename(&temp_path, path) {
+        warn!("Atomic state file replace failed: {}. Falling back to direct write.", e);
+        fs::write(path, content)?;
+        let _ = fs::remove_file(&temp_path);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use crate::error::BotError;
+    use rusqlite::{Connection, OptionalExtension};
+
+    fn open(path: &str) -> Result<Connection, BotError> {
+        let connection = Connection::open(path).map_err(store_error)?;
+        connection
+            .execute(
+                "CREATE TABLE IF NOT EXISTS bot_state (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    content TEXT NOT NULL,
+                    backup TEXT,
+                    updated_at INTEGER NOT NULL
+                )",
+                [],
+            )
+            .map_err(store_error)?;
+        Ok(connection)
+    }
+
+    fn store_error(e: rusqlite::Error) -> BotError {
+        BotError::Other(format!("SQLite state store: {}", e))
+    }
+
+    /// `column` is `content` or `backup`
+    pub(super) fn read(path: &str, column: &str) -> Result<Option<String>, BotError> {
+        open(path)?
+            .query_row(&format!("SELECT {} FROM bot_state WHERE id = 1", column), [], |row| {
+                row.get::<_, Option<String>>(0)
+            })
+            .optional()
+            .map(Option::flatten)
+            .map_err(store_error)
+    }
+
+    pub(super) fn write(path: &str, content: &str) -> Result<(), BotError> {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64;
+        open(path)?
+            .execute(
+                "INSERT INTO bot_state (id, content, backup, updated_at) VALUES (1, ?1, NULL, ?2)
+                 ON CONFLICT(id) DO UPDATE SET backup = content, content = excluded.content,
+                 updated_at = excluded.updated_at",
+                rusqlite::params![content, now],
+            )
+            .map_err(store_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "redis")]
+pub(crate) mod redis_store {
+    use crate::error::BotError;
+    use redis::Commands;
+
+    fn connect(url: &str) -> Result<redis::Connection, BotError> {
+        redis::Client::open(url)
+            .and_then(|client| client.get_connection())
+            .map_err(store_error)
+    }
+
+    fn store_error(e: redis::RedisError) -> BotError {
+        BotError::Other(format!("Redis state store: {}", e))
+    }
+
+    pub(crate) fn get(url: &str, key: &str) -> Result<Option<String>, BotError> {
+        connect(url)?.get(key).map_err(store_error)
+    }
+
+    pub(super) fn set(url: &str, key: &str, content: &str) -> Result<(), BotError> {
+        let mut connection = connect(url)?;
+        let previous: Option<String> = connection.get(key).map_err(store_error)?;
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        if let Some(previous) = previous {
+            pipe.set(format!("{}.bak", key), previous).ignore();
+        }
+        pipe.set(key, content).ignore();
+        pipe.query::<()>(&mut connection).map_err(store_error)
+    }
+
+    /// `SET NX PX`: true when `lock_key` was free and now holds `token` for `ttl_ms`
+    pub(crate) fn try_lock(url: &str, lock_key: &str, token: &str, ttl_ms: u64) -> Result<bool, BotError> {
+        let set: Option<String> = redis::cmd("SET")
+            .arg(lock_key)
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl_ms)
+            .query(&mut connect(url)?)
+            .map_err(store_error)?;
+        Ok(set.is_some())
+    }
+
+    /// Extend `lock_key` by `ttl_ms` if it still holds `token`; false once it was lost
+    pub(crate) fn refresh_lock(url: &str, lock_key: &str, token: &str, ttl_ms: u64) -> Result<bool, BotError> {
+        let refreshed: i64 = redis::Script::new(
+            "if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('PEXPIRE', KEYS[1], ARGV[2]) else return 0 end",
+        )
+        .key(lock_key)
+        .arg(token)
+        .arg(ttl_ms)
+        .invoke(&mut connect(url)?)
+        .map_err(store_error)?;
+        Ok(refreshed == 1)
+    }
+
+    /// Delete `lock_key` if it still holds `token`
+    pub(crate) fn release_lock(url: &str, lock_key: &str, token: &str) -> Result<(), BotError> {
+        redis::Script::new("if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('DEL', KEYS[1]) else return 0 end")
+            .key(lock_key)
+            .arg(token)
+            .invoke::<i64>(&mut connect(url)?)
+            .map_err(store_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_schemes() {
+        assert_eq!(StateStore::parse("bot_state.json").unwrap(), StateStore::File("bot_state.json".into()));
+        assert_eq!(StateStore::parse("file:///var/lib/bot/state.json").unwrap(), StateStore::File("/var/lib/bot/state.json".into()));
+        assert!(matches!(StateStore::parse("s3://bucket/state.json"), Err(BotError::Config(_))));
+        #[cfg(not(feature = "sqlite"))]
+        assert!(matches!(StateStore::parse("sqlite://bot.db"), Err(BotError::Config(m)) if m.contains("`sqlite` feature")));
+        #[cfg(not(feature = "redis"))]
+        assert!(matches!(StateStore::parse("rediss://host:6380"), Err(BotError::Config(m)) if m.contains("`redis` feature")));
+        #[cfg(feature = "redis")]
+        {
+            let store = StateStore::parse("redis://:secret@host:6379/0#bot_a").unwrap();
+            assert_eq!(store, StateStore::Redis { url: "redis://:secret@host:6379/0".into(), key: "bot_a".into() });
+            assert!(!store.to_string().contains("secret"));
+            let unparseable = StateStore::Redis { url: "redis://:secret@host:port".into(), key: "bot_a".into() };
+            assert_eq!(unparseable.to_string(), "redis://***@host:port#bot_a");
+        }
+    }
+
+    #[test]
+    fn test_file_store_keeps_backup() {
+        let path = std::env::temp_dir().join(format!("state_store_test_{}.json", uuid::Uuid::new_v4()));
+        let store = StateStore::File(path.to_str().unwrap().to_string());
+        assert_eq!(store.read().unwrap(), None);
+        store.write("{\"a\":1}").unwrap();
+        store.write("{\"a\":2}").unwrap();
+        assert_eq!(store.read().unwrap().as_deref(), Some("{\"a\":2}"));
+        assert_eq!(store.read_backup().unwrap().as_deref(), Some("{\"a\":1}"));
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(format!("{}.bak", path.display()));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_store_keeps_backup() {
+        let path = std::env::temp_dir().join(format!("state_store_test_{}.db", uuid::Uuid::new_v4()));
+        let store = StateStore::parse(&format!("sqlite://{}", path.display())).unwrap();
+        assert_eq!(store.read().unwrap(), None);
+        store.write("{\"a\":1}").unwrap();
+        store.write("{\"a\":2}").unwrap();
+        assert_eq!(store.read().unwrap().as_deref(), Some("{\"a\":2}"));
+        assert_eq!(store.read_backup().unwrap().as_deref(), Some("{\"a\":1}"));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,162 @@
+//! Strategy selection for the bot
+//!
+//! A `Strategy` only decides what to hold next from a scan. Everything around that
+//! decision (the monitoring schedule, state persistence and recovery, rotation,
+//! sizing and the two-leg execution with rollback) stays in `FundingBot`, so an
+//! alternative strategy is a small type plugged in with `FundingBot::set_strategy`.
+use crate::bot::BotState;
+use crate::opportunity::{Config, Direction, Opportunity, ScanResult};
+use tracing::info;
+
+/// Position a strategy wants the bot to open
+#[derive(Debug, Clone)]
+pub struct PositionTarget {
+    /// Scanned market data for the symbol; `best_net_apr` is the expected net APR
+    pub opportunity: Opportunity,
+    pub direction: Direction,
+}
+
+impl PositionTarget {
+    /// Target following the scan's best direction for `opportunity`
+    pub fn best_direction(opportunity: &Opportunity) -> Self {
+        Self {
+            direction: opportunity.best_direction,
+            opportunity: opportunity.clone(),
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.opportunity.symbol
+    }
+}
+
+pub trait Strategy: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Position to open from `scan`, or `None` to stay flat this cycle
+    fn select(&self, scan: &ScanResult, state: &BotState, config: &Config) -> Option<PositionTarget>;
+}
+
+/// Default strategy: cross-exchange funding arbitrage on the top-ranked filtered
+/// opportunity, skipping churn re-entries when rotation cooldown is enabled. The previous
+/// pick (`BotState::previous_selection`) is kept while it passes the filters and the top
+/// one does not beat its net APR by `scoring.switch_margin_apr_pct`.
+pub struct CrossExchangeFunding;
+
+impl Strategy for CrossExchangeFunding {
+    fn name(&self) -> &str {
+        "cross_exchange_funding"
+    }
+
+    fn select(&self, scan: &ScanResult, state: &BotState, config: &Config) -> Option<PositionTarget> {
+        let churn_protection = config.trading.min_minutes_between_rotations > 0;
+        let is_churn = |o: &Opportunity| churn_protection && state.is_churn_reentry(&o.symbol, o.best_net_apr);
+        let best = scan.opportunities.iter().find(|o| {
+            let churn = is_churn(o);
+            if churn {
+                info!("Skipping {}: re-entry at {:.2}% APR is below its last entry APR",
+                    o.symbol, o.best_net_apr);
+            }
+            !churn
+        })?;
+
+        let margin = config.scoring.switch_margin_apr_pct;
+        let previous = state
+            .previous_selection()
+            .filter(|symbol| margin > 0.0 && *symbol != best.symbol)
+            .and_then(|symbol| scan.opportunities.iter().find(|o| o.symbol == symbol && !is_churn(o)));
+        match previous {
+            Some(previous) if best.best_net_apr < previous.best_net_apr + margin => {
+                info!("Keeping {} ({:.2}% APR): {} at {:.2}% does not beat it by {:.2} points",
+                    previous.symbol, previous.best_net_apr, best.symbol, best.best_net_apr, margin);
+                Some(PositionTarget::best_direction(previous))
+            }
+            _ => Some(PositionTarget::best_direction(best)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opportunity::{sample_opportunity, FilterStats};
+
+    #[test]
+    fn test_cross_exchange_funding_skips_churn_reentry() {
+        let scan = ScanResult {
+            opportunities: vec![
+                sample_opportunity("BTC", 40.0, Direction::LongPacificaShortExtended),
+                sample_opportunity("ETH", 30.0, Direction::LongExtendedShortPacifica),
+            ],
+            all_candidates: Vec::new(),
+            stats: FilterStats {
+                total_common_symbols: 2,
+                filtered_by_volume: 0,
+                filtered_by_spread: 0,
+                filtered_by_apr: 0,
+                filtered_by_open_interest: 0,
+                filtered_by_mark_index_gap: 0,
+                passed_filters: 2,
+            },
+            scanned_at: 0,
+            data_availability: Default::default(),
+        };
+        let mut config = Config::default_config();
+        let state = BotState {
+            last_closed_symbol: Some("BTC".to_string()),
+            last_closed_net_apr: Some(50.0),
+            ..BotState::default()
+        };
+
+        let target = CrossExchangeFunding.select(&scan, &state, &config).unwrap();
+        assert_eq!(target.symbol(), "BTC");
+        assert_eq!(target.direction, Direction::LongPacificaShortExtended);
+
+        // With churn protection on, BTC re-entry at a lower APR is skipped
+        config.trading.min_minutes_between_rotations = 60;
+        let target = CrossExchangeFunding.select(&scan, &state, &config).unwrap();
+        assert_eq!(target.symbol(), "ETH");
+        assert_eq!(target.direction, Direction::LongExtendedShortPacifica);
+    }
+
+    #[test]
+    fn test_cross_exchange_funding_hysteresis() {
+        let mut scan = ScanResult {
+            opportunities: vec![
+                sample_opportunity("BTC", 41.0, Direction::LongPacificaShortExtended),
+                sample_opportunity("ETH", 40.0, Direction::LongExtendedShortPacifica),
+            ],
+            all_candidates: Vec::new(),
+            stats: FilterStats {
+                total_common_symbols: 2,
+                filtered_by_volume: 0,
+                filtered_by_spread: 0,
+                filtered_by_apr: 0,
+                filtered_by_open_interest: 0,
+                filtered_by_mark_index_gap: 0,
+                passed_filters: 2,
+            },
+            scanned_at: 0,
+            data_availability: Default::default(),
+        };
+        let mut config = Config::default_config();
+        let state = BotState { last_selected_symbol: Some("ETH".to_string()), ..BotState::default() };
+
+        // No margin: the top-ranked opportunity wins
+        assert_eq!(CrossExchangeFunding.select(&scan, &state, &config).unwrap().symbol(), "BTC");
+
+        // BTC leads by 1 point, short of the 2-point margin: ETH is kept
+        config.scoring.switch_margin_apr_pct = 2.0;
+        let target = CrossExchangeFunding.select(&scan, &state, &config).unwrap();
+        assert_eq!(target.symbol(), "ETH");
+        assert_eq!(target.direction, Direction::LongExtendedShortPacifica);
+
+        scan.opportunities[0].best_net_apr = 42.5;
+        assert_eq!(CrossExchangeFunding.select(&scan, &state, &config).unwrap().symbol(), "BTC");
+
+        // A previous pick that no longer passes the filters is not kept
+        scan.opportunities[0].best_net_apr = 41.0;
+        scan.opportunities.truncate(1);
+        assert_eq!(CrossExchangeFunding.select(&scan, &state, &config).unwrap().symbol(), "BTC");
+    }
+}
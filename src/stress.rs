@@ -0,0 +1,245 @@
+//! Stress test of the held pair
+//!
+//! `risk_report` moves the price on both venues by each of `stress.price_moves_pct`, up
+//! and down, and reports what each move does to the position: the PnL of each leg and of
+//! the pair, each venue's margin usage afterwards and how much further the price could go
+//! against each leg before it is liquidated. Both venues move by the same percentage, so
+//! the current basis is kept and the pair PnL is what the size mismatch and basis leave
+//! unhedged.
+//!
+//! Each venue account is taken as cross margin holding only its leg: the leg is
+//! liquidated once the account's equity falls to `maintenance_margin_pct` of the leg's
+//! notional. Real liquidation prices also depend on the venue's margin tiers and other
+//! positions, so the distances are estimates.
+use crate::capital::Venue;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StressConfig {
+    /// Price moves to test, in % of the current price; each is applied up and down
+    #[serde(default = "default_price_moves_pct")]
+    pub price_moves_pct: Vec<f64>,
+    /// Maintenance margin assumed on both venues, in % of notional
+    #[serde(default = "default_maintenance_margin_pct")]
+    pub maintenance_margin_pct: f64,
+}
+
+fn default_price_moves_pct() -> Vec<f64> {
+    vec![5.0, 10.0, 20.0]
+}
+
+fn default_maintenance_margin_pct() -> f64 {
+    1.0
+}
+
+impl Default for StressConfig {
+    fn default() -> Self {
+        Self {
+            price_moves_pct: default_price_moves_pct(),
+            maintenance_margin_pct: default_maintenance_margin_pct(),
+        }
+    }
+}
+
+impl StressConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(pct) = self.price_moves_pct.iter().find(|pct| !(pct.is_finite() && **pct > 0.0 && **pct < 100.0)) {
+            return Err(format!("stress.price_moves_pct must be between 0 and 100 (exclusive), got {}", pct));
+        }
+        if !(0.0..100.0).contains(&self.maintenance_margin_pct) {
+            return Err("stress.maintenance_margin_pct must be between 0 and 100".into());
+        }
+        Ok(())
+    }
+}
+
+/// One held leg, as its venue reports it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StressLeg {
+    pub venue: Venue,
+    pub is_long: bool,
+    /// Contracts held
+    pub size: f64,
+    /// Current mid, per contract
+    pub price: f64,
+    /// Equity of the venue account, in USD
+    pub equity_usd: f64,
+    pub leverage: u32,
+}
+
+impl StressLeg {
+    fn direction(&self) -> f64 {
+        if self.is_long { 1.0 } else { -1.0 }
+    }
+
+    /// PnL of a `move_pct` price move, in USD
+    pub fn pnl_usd(&self, move_pct: f64) -> f64 {
+        self.direction() * self.size.abs() * self.price * move_pct / 100.0
+    }
+
+    /// The leg and its account after a `move_pct` price move
+    pub fn moved(&self, move_pct: f64) -> Self {
+        Self {
+            price: self.price * (1.0 + move_pct / 100.0),
+            equity_usd: self.equity_usd + self.pnl_usd(move_pct),
+            ..*self
+        }
+    }
+
+    /// Price at which the account's equity falls to the maintenance margin of the leg
+    pub fn liquidation_price(&self, maintenance_margin_pct: f64) -> f64 {
+        let (size, maintenance) = (self.size.abs(), maintenance_margin_pct / 100.0);
+        if size <= 0.0 {
+            return if self.is_long { 0.0 } else { f64::INFINITY };
+        }
+        if self.is_long {
+            ((size * self.price - self.equity_usd) / (size * (1.0 - maintenance))).max(0.0)
+        } else {
+            (self.equity_usd + size * self.price) / (size * (1.0 + maintenance))
+        }
+    }
+
+    /// Move against the leg (% of its price) left before liquidation; 0 once past it.
+    /// A long that survives the price going to zero is 100% away.
+    pub fn liquidation_distance_pct(&self, maintenance_margin_pct: f64) -> f64 {
+        if self.price <= 0.0 {
+            return 0.0;
+        }
+        let liquidation = self.liquidation_price(maintenance_margin_pct);
+        let distance = if self.is_long { self.price - liquidation } else { liquidation - self.price };
+        (distance / self.price * 100.0).max(0.0)
+    }
+
+    /// Initial margin of the leg at its leverage, in % of the account's equity; `None`
+    /// once the equity is gone
+    pub fn margin_usage_pct(&self) -> Option<f64> {
+        let initial_margin = self.size.abs() * self.price / f64::from(self.leverage.max(1));
+        (self.equity_usd > 0.0).then(|| initial_margin / self.equity_usd * 100.0)
+    }
+}
+
+/// One leg after a price move
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LegStress {
+    pub venue: Venue,
+    pub pnl_usd: f64,
+    pub equity_usd: f64,
+    pub margin_usage_pct: Option<f64>,
+    pub liquidation_price: f64,
+    pub liquidation_distance_pct: f64,
+}
+
+/// The pair after one price move
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StressScenario {
+    /// Price move on both venues, in %
+    pub price_move_pct: f64,
+    /// PnL of both legs together, in USD
+    pub pnl_usd: f64,
+    pub legs: Vec<LegStress>,
+}
+
+impl StressScenario {
+    fn new(legs: &[StressLeg], move_pct: f64, maintenance_margin_pct: f64) -> Self {
+        let legs: Vec<LegStress> = legs
+            .iter()
+            .map(|leg| {
+                let moved = leg.moved(move_pct);
+                LegStress {
+                    venue: leg.venue,
+                    pnl_usd: leg.pnl_usd(move_pct),
+                    equity_usd: moved.equity_usd,
+                    margin_usage_pct: moved.margin_usage_pct(),
+                    liquidation_price: moved.liquidation_price(maintenance_margin_pct),
+                    liquidation_distance_pct: moved.liquidation_distance_pct(maintenance_margin_pct),
+                }
+            })
+            .collect();
+        Self { price_move_pct: move_pct, pnl_usd: legs.iter().map(|l| l.pnl_usd).sum(), legs }
+    }
+
+    pub fn leg(&self, venue: Venue) -> Option<&LegStress> {
+        self.legs.iter().find(|l| l.venue == venue)
+    }
+}
+
+/// Stress test of the held pair, see the module documentation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RiskReport {
+    pub symbol: String,
+    /// Pacifica premium over Extended (%) the moves keep, if both legs are priced
+    pub basis_pct: Option<f64>,
+    /// The legs at current prices
+    pub current: StressScenario,
+    /// Each configured move, largest fall first
+    pub scenarios: Vec<StressScenario>,
+    /// Worst pair PnL over the scenarios, in USD
+    pub worst_pnl_usd: f64,
+    /// Smallest liquidation distance of any leg over the scenarios, in %
+    pub min_liquidation_distance_pct: Option<f64>,
+}
+
+/// Stress `legs` by every configured price move
+pub fn risk_report(symbol: &str, basis_pct: Option<f64>, legs: &[StressLeg], config: &StressConfig) -> RiskReport {
+    let maintenance = config.maintenance_margin_pct;
+    let mut moves: Vec<f64> = config.price_moves_pct.iter().flat_map(|pct| [-pct, *pct]).collect();
+    moves.sort_by(|a, b| a.total_cmp(b));
+    moves.dedup();
+    let scenarios: Vec<StressScenario> = moves.into_iter().map(|pct| StressScenario::new(legs, pct, maintenance)).collect();
+    RiskReport {
+        symbol: symbol.to_string(),
+        basis_pct,
+        current: StressScenario::new(legs, 0.0, maintenance),
+        worst_pnl_usd: scenarios.iter().map(|s| s.pnl_usd).fold(0.0, f64::min),
+        min_liquidation_distance_pct: scenarios
+            .iter()
+            .flat_map(|s| s.legs.iter().map(|l| l.liquidation_distance_pct))
+            .reduce(f64::min),
+        scenarios,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_risk_report() {
+        // $10k long on Extended and short on Pacifica at 5x, $2.5k equity on each venue
+        let long = StressLeg { venue: Venue::Extended, is_long: true, size: 0.1, price: 100_000.0, equity_usd: 2_500.0, leverage: 5 };
+        let short = StressLeg { venue: Venue::Pacifica, is_long: false, size: 0.1, price: 100_100.0, equity_usd: 2_500.0, leverage: 5 };
+        let config = StressConfig { price_moves_pct: vec![10.0, 20.0], maintenance_margin_pct: 1.0 };
+        let report = risk_report("BTC", Some(0.1), &[long, short], &config);
+
+        // Now: 2500 + 0.1 * (P - 100000) = 0.01 * 0.1 * P, so the long liquidates near 75758
+        assert!((report.current.leg(Venue::Extended).unwrap().liquidation_price - 75_757.576).abs() < 0.01);
+        assert!((report.current.leg(Venue::Extended).unwrap().margin_usage_pct.unwrap() - 80.0).abs() < 1e-9);
+
+        let moves: Vec<f64> = report.scenarios.iter().map(|s| s.price_move_pct).collect();
+        assert_eq!(moves, vec![-20.0, -10.0, 10.0, 20.0]);
+        // The hedge offsets all but the basis: -$2000 on the long, +$2002 on the short
+        let down = &report.scenarios[0];
+        assert!((down.pnl_usd - 2.0).abs() < 1e-6);
+        let long_down = down.leg(Venue::Extended).unwrap();
+        assert!((long_down.equity_usd - 500.0).abs() < 1e-6);
+        // $8000 notional on $500 equity: 320% of the initial margin, ~6% from liquidation
+        assert!((long_down.margin_usage_pct.unwrap() - 320.0).abs() < 1e-6);
+        assert!(long_down.liquidation_distance_pct > 5.0 && long_down.liquidation_distance_pct < 6.0);
+        // A rise of 20% costs the short $2002 of its $2500, leaving it ~3% from liquidation
+        let up_short = report.scenarios[3].leg(Venue::Pacifica).unwrap();
+        assert!(up_short.liquidation_distance_pct > 3.0 && up_short.liquidation_distance_pct < 3.5);
+        assert_eq!(report.min_liquidation_distance_pct, Some(up_short.liquidation_distance_pct));
+        assert!((report.worst_pnl_usd + 2.0).abs() < 1e-6);
+
+        // Past liquidation: no equity left and no distance
+        let wiped = long.moved(-30.0);
+        assert_eq!(wiped.margin_usage_pct(), None);
+        assert_eq!(wiped.liquidation_distance_pct(1.0), 0.0);
+        // A fully collateralized long cannot be liquidated by the price
+        let unlevered = StressLeg { equity_usd: 20_000.0, ..long };
+        assert_eq!(unlevered.liquidation_distance_pct(1.0), 100.0);
+
+        assert!(StressConfig::default().validate().is_ok());
+        assert!(StressConfig { price_moves_pct: vec![0.0], ..StressConfig::default() }.validate().is_err());
+    }
+}
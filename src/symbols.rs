@@ -0,0 +1,219 @@
+//! Canonical symbols and each venue's market names for them
+//!
+//! Positions, state, logs and per-symbol config are keyed by one canonical symbol
+//! (`BTC`). By default Extended lists it as `BTC-USD` and Pacifica as `BTC`;
+//! `symbols.aliases` overrides either name, for assets a venue quotes per 1000 units or
+//! under another ticker:
+//!
+//! ```json
+//! "symbols": { "aliases": { "1000PEPE": { "pacifica": "kPEPE" } } }
+//! ```
+//!
+//! A venue that lists a contract for a multiple of the asset (1000 PEPE per `kPEPE`) while
+//! the other lists one unit gets a contract multiplier, the number of canonical units
+//! one venue contract stands for, so both legs of a position hold the same quantity:
+//!
+//! ```json
+//! "symbols": { "aliases": { "PEPE": { "pacifica": "kPEPE", "pacifica_multiplier": 1000 } } }
+//! ```
+//!
+//! An alias shadows the default market name: with the alias above, a Pacifica market
+//! named `1000PEPE` maps to no symbol rather than to a second `1000PEPE`. Two symbols
+//! mapping to the same market on a venue are rejected when the config is validated.
+use crate::capital::Venue;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Suffix of Extended market names (`BTC-USD`)
+pub const EXTENDED_MARKET_SUFFIX: &str = "-USD";
+
+/// Venue market names and contract multipliers of one symbol; `None` keeps the default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VenueMarkets {
+    #[serde(default)]
+    pub extended: Option<String>,
+    #[serde(default)]
+    pub pacifica: Option<String>,
+    /// Canonical units per Extended contract (default 1)
+    #[serde(default)]
+    pub extended_multiplier: Option<f64>,
+    /// Canonical units per Pacifica contract (default 1)
+    #[serde(default)]
+    pub pacifica_multiplier: Option<f64>,
+}
+
+/// Canonical units one contract stands for on each venue. Venue quantities are
+/// contracts and venue prices are per contract; base quantities and prices are per
+/// canonical unit, the same on both venues.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ContractMultipliers {
+    pub extended: f64,
+    pub pacifica: f64,
+}
+
+impl Default for ContractMultipliers {
+    fn default() -> Self {
+        Self { extended: 1.0, pacifica: 1.0 }
+    }
+}
+
+impl ContractMultipliers {
+    pub fn of(&self, venue: Venue) -> f64 {
+        match venue {
+            Venue::Extended => self.extended,
+            Venue::Pacifica => self.pacifica,
+        }
+    }
+
+    /// Both venues list one canonical unit per contract
+    pub fn is_unit(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Contracts to trade on `venue` for `base` canonical units
+    pub fn venue_size(&self, venue: Venue, base: f64) -> f64 {
+        base / self.of(venue)
+    }
+
+    /// Canonical units held by `contracts` on `venue`
+    pub fn base_size(&self, venue: Venue, contracts: f64) -> f64 {
+        contracts * self.of(venue)
+    }
+
+    /// Price per canonical unit of a `venue` price per contract
+    pub fn base_price(&self, venue: Venue, price: f64) -> f64 {
+        price / self.of(venue)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SymbolMap {
+    /// Canonical symbol -> market names that differ from the defaults
+    #[serde(default)]
+    pub aliases: BTreeMap<String, VenueMarkets>,
+}
+
+impl SymbolMap {
+    fn alias(&self, venue: Venue, symbol: &str) -> Option<&str> {
+        let markets = self.aliases.get(symbol)?;
+        match venue {
+            Venue::Extended => markets.extended.as_deref(),
+            Venue::Pacifica => markets.pacifica.as_deref(),
+        }
+    }
+
+    /// Market name of `symbol` on `venue`
+    pub fn market(&self, venue: Venue, symbol: &str) -> String {
+        match (self.alias(venue, symbol), venue) {
+            (Some(market), _) => market.to_string(),
+            (None, Venue::Extended) => format!("{}{}", symbol, EXTENDED_MARKET_SUFFIX),
+            (None, Venue::Pacifica) => symbol.to_string(),
+        }
+    }
+
+    pub fn extended_market(&self, symbol: &str) -> String {
+        self.market(Venue::Extended, symbol)
+    }
+
+    pub fn pacifica_market(&self, symbol: &str) -> String {
+        self.market(Venue::Pacifica, symbol)
+    }
+
+    /// Contract multipliers of `symbol` on both venues
+    pub fn multipliers(&self, symbol: &str) -> ContractMultipliers {
+        let markets = self.aliases.get(symbol);
+        ContractMultipliers {
+            extended: markets.and_then(|m| m.extended_multiplier).unwrap_or(1.0),
+            pacifica: markets.and_then(|m| m.pacifica_multiplier).unwrap_or(1.0),
+        }
+    }
+
+    /// Canonical symbol of a `venue` market; `None` for a market that maps to no symbol
+    /// (an Extended market not quoted in USD, or the default name of an aliased symbol)
+    pub fn symbol(&self, venue: Venue, market: &str) -> Option<String> {
+        if let Some(symbol) = self.aliases.keys().find(|symbol| self.alias(venue, symbol) == Some(market)) {
+            return Some(symbol.clone());
+        }
+        let symbol = match venue {
+            Venue::Extended => market.strip_suffix(EXTENDED_MARKET_SUFFIX)?,
+            Venue::Pacifica => market,
+        };
+        self.alias(venue, symbol).is_none().then(|| symbol.to_string())
+    }
+
+    /// Canonical symbol of a market, or the market name itself when it maps to none,
+    /// for reporting positions and orders the bot did not open
+    pub fn symbol_or_market(&self, venue: Venue, market: &str) -> String {
+        self.symbol(venue, market).unwrap_or_else(|| market.to_string())
+    }
+
+    /// Every alias names a market with a positive multiplier, and no two symbols share a
+    /// market on a venue
+    pub fn validate(&self) -> Result<(), String> {
+        for (symbol, markets) in &self.aliases {
+            for (venue, multiplier) in [(Venue::Extended, markets.extended_multiplier), (Venue::Pacifica, markets.pacifica_multiplier)] {
+                if let Some(m) = multiplier.filter(|m| !(m.is_finite() && *m > 0.0)) {
+                    return Err(format!("symbols.aliases.{}: {} multiplier must be positive, got {}", symbol, venue, m));
+                }
+            }
+        }
+        for venue in [Venue::Extended, Venue::Pacifica] {
+            let mut seen: BTreeMap<String, &str> = BTreeMap::new();
+            for symbol in self.aliases.keys() {
+                let market = self.market(venue, symbol);
+                if market.trim().is_empty() {
+                    return Err(format!("symbols.aliases.{}: empty {} market name", symbol, venue));
+                }
+                if let Some(other) = seen.insert(market.clone(), symbol) {
+                    return Err(format!("symbols.aliases: {} and {} both map to {} market {}", other, symbol, venue, market));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_map_aliases_and_collisions() {
+        let map: SymbolMap = serde_json::from_str(r#"{"aliases": {"1000PEPE": {"pacifica": "kPEPE"}}}"#).unwrap();
+        assert!(map.validate().is_ok());
+        assert_eq!(map.extended_market("BTC"), "BTC-USD");
+        assert_eq!(map.pacifica_market("BTC"), "BTC");
+        assert_eq!(map.extended_market("1000PEPE"), "1000PEPE-USD");
+        assert_eq!(map.pacifica_market("1000PEPE"), "kPEPE");
+
+        assert_eq!(map.symbol(Venue::Extended, "BTC-USD").as_deref(), Some("BTC"));
+        assert_eq!(map.symbol(Venue::Extended, "BTC-PERP"), None);
+        assert_eq!(map.symbol(Venue::Pacifica, "kPEPE").as_deref(), Some("1000PEPE"));
+        assert_eq!(map.symbol(Venue::Extended, "1000PEPE-USD").as_deref(), Some("1000PEPE"));
+        // The alias shadows Pacifica's default name for the symbol
+        assert_eq!(map.symbol(Venue::Pacifica, "1000PEPE"), None);
+        assert_eq!(map.symbol_or_market(Venue::Pacifica, "1000PEPE"), "1000PEPE");
+
+        let colliding: SymbolMap = serde_json::from_str(
+            r#"{"aliases": {"1000PEPE": {"pacifica": "kPEPE"}, "kPEPE": {}}}"#,
+        ).unwrap();
+        assert!(colliding.validate().unwrap_err().contains("Pacifica market kPEPE"));
+    }
+
+    #[test]
+    fn test_contract_multipliers() {
+        let map: SymbolMap = serde_json::from_str(r#"{"aliases": {"PEPE": {"pacifica": "kPEPE", "pacifica_multiplier": 1000}}}"#).unwrap();
+        assert!(map.validate().is_ok());
+        assert!(map.multipliers("BTC").is_unit());
+        let pepe = map.multipliers("PEPE");
+        assert_eq!((pepe.extended, pepe.pacifica), (1.0, 1000.0));
+        // 2M PEPE is 2M contracts on Extended and 2000 kPEPE on Pacifica
+        assert_eq!(pepe.venue_size(Venue::Extended, 2_000_000.0), 2_000_000.0);
+        assert_eq!(pepe.venue_size(Venue::Pacifica, 2_000_000.0), 2000.0);
+        assert_eq!(pepe.base_size(Venue::Pacifica, 2000.0), 2_000_000.0);
+        assert!((pepe.base_price(Venue::Pacifica, 0.012) - 0.000012).abs() < 1e-15);
+
+        let zero: SymbolMap = serde_json::from_str(r#"{"aliases": {"PEPE": {"extended_multiplier": 0}}}"#).unwrap();
+        assert!(zero.validate().unwrap_err().contains("Extended multiplier must be positive"));
+    }
+}
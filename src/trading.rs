@@ -1,64 +1,650 @@
-/// Delta neutral position execution and management
+//! Delta neutral position execution and management
 use crate::{
-    types::{OrderSide, Position},
+    types::{BidAsk, LimitOrder, OrderSide, Position, TimeInForce, TradingConfig},
     RestClient, PacificaTrading,
-    pacifica::{types::PacificaPosition, trading::OrderSide as PacificaOrderSide},
+    pacifica::{
+        clock,
+        fill_detection::{FillDetectionConfig, FillWatcher},
+        types::{ConfirmedFill, PacificaMarketInfo, PacificaPosition},
+        trading::{
+            filled_amount_for_client_id, OrderSide as PacificaOrderSide, TimeInForce as PacificaTimeInForce,
+            TradeHistoryItem,
+        },
+    },
 };
+use crate::capital::Venue;
+use crate::contingency::{Contingency, ExposedLeg};
+use crate::error::BotError;
+use crate::fees::FeeSchedule;
+use crate::funding::{apr_from_rate, FundingRateAverage};
+use crate::intent_log::{IntentLog, IntentPhase, IntentRecord};
+use crate::pacifica::trading::OrderbookSnapshot;
+use crate::opportunity::{mid_and_spread_pct, Direction};
+use crate::symbols::{ContractMultipliers, SymbolMap};
+use crate::retry::RetryPolicy;
+use crate::numeric::{decimal_from_f64, decimal_to_f64, parse_decimal, parse_decimal_or_zero, round_down_to_step, Decimal};
+use std::collections::HashMap;
 use std::time::Duration;
 use tokio::time::sleep;
 use tracing::{info, warn, error};
+use uuid::Uuid;
 
-const ORDER_MAX_ATTEMPTS: u32 = 5;
-const ORDER_BASE_BACKOFF_MS: u64 = 1_000;
-const RATE_LIMIT_BACKOFF_MS: u64 = 5_000;
-const BACKOFF_MAX_EXPONENT: u32 = 6;
-const POSITION_FETCH_MAX_ATTEMPTS: u32 = 6;
+const CLOSE_VERIFY_MAX_ROUNDS: u32 = 3;
+const CLOSE_VERIFY_DELAY_MS: u64 = 2_000;
+/// Trade history window searched for fills of earlier attempts, before the operation started
+const ORDER_LOOKUP_LOOKBACK_MS: i64 = 60_000;
+const ORDER_LOOKUP_TRADE_LIMIT: u32 = 100;
+/// Longest wait for an accepted Extended order to be filled or cancelled
+const EXTENDED_FILL_WAIT_TIMEOUT_MS: u64 = 5_000;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct DeltaNeutralPosition {
+    /// Seed for deterministic client order IDs; empty for positions saved by older versions
+    #[serde(default)]
+    pub position_id: String,
     pub symbol: String,
     pub extended_position: Option<Position>,
     pub pacifica_position: Option<PacificaPosition>,
     pub opened_at: u64,
     pub target_notional_usd: f64,
+    /// Funding received (+) or paid (-) on the Extended leg since open, in USD
+    #[serde(default)]
+    pub realized_funding_extended_usd: f64,
+    /// Funding received (+) or paid (-) on the Pacifica leg since open, in USD
+    #[serde(default)]
+    pub realized_funding_pacifica_usd: f64,
+    /// Net APR (%) of the opportunity when the position was opened, if known
+    #[serde(default)]
+    pub entry_net_apr: Option<f64>,
+    /// Average fill price of the Extended leg as reported by the venue after open
+    #[serde(default)]
+    pub extended_entry_price: Option<f64>,
+    /// Average fill price of the Pacifica leg as reported by the venue after open
+    #[serde(default)]
+    pub pacifica_entry_price: Option<f64>,
+    /// Basis at entry, see `basis_pct`
+    #[serde(default)]
+    pub entry_basis_pct: Option<f64>,
+    /// Spread capture the position was opened with, if its limit order filled
+    #[serde(default)]
+    pub spread_capture: Option<SpreadCapture>,
+    /// Basis locked in by that spread capture, in USD (see `entry_basis_usd`)
+    #[serde(default)]
+    pub captured_basis_usd: Option<f64>,
+    /// How the open orders executed
+    #[serde(default)]
+    pub open_execution: Option<ExecutionReport>,
+    /// Extended vault the position was opened in; `None` means the default `VAULT_NUMBER`
+    #[serde(default)]
+    pub extended_vault_id: Option<String>,
+    /// Direction the position was opened in; `None` for positions saved by older
+    /// versions, see `held_direction`
+    #[serde(default)]
+    pub direction: Option<Direction>,
+    /// Partial closes started so far, the one in progress included; seeds their client
+    /// order IDs (`reduction_id`)
+    #[serde(default)]
+    pub reductions: u32,
+    /// Generation of the resting protective orders, seeding their client order IDs;
+    /// bumped each time they are replaced, see `protective_orders`
+    #[serde(default)]
+    pub protective_generation: Option<u32>,
+    /// Unix time the hold timer last restarted because a rotation would have reopened
+    /// the same position, see `hold_started_at`
+    #[serde(default)]
+    pub hold_restarted_at: Option<u64>,
+    /// Venue market names the position was opened on; `None` for positions saved by
+    /// older versions, see `extended_market_name`
+    #[serde(default)]
+    pub extended_market: Option<String>,
+    #[serde(default)]
+    pub pacifica_market: Option<String>,
+    /// Canonical units per contract of each venue market; leg sizes and entry prices are
+    /// per contract, as the venues report them
+    #[serde(default)]
+    pub multipliers: ContractMultipliers,
+    /// Funding APRs of both legs while held, time-weighted
+    #[serde(default)]
+    pub funding_average: FundingRateAverage,
 }
 
-#[derive(Debug)]
-pub struct TradingError {
-    pub message: String,
-    pub recoverable: bool,
+impl DeltaNeutralPosition {
+    /// Unix time the current hold period started: the open, or the last rotation the
+    /// position was kept through
+    pub fn hold_started_at(&self) -> u64 {
+        self.hold_restarted_at.unwrap_or(self.opened_at)
+    }
+
+    /// Time-weighted net funding APR (%) in the held direction since open
+    pub fn average_net_apr(&self) -> Option<f64> {
+        self.funding_average.average_net_apr(self.held_direction()?.long_on_extended())
+    }
+
+    /// Recorded direction, or the one implied by whichever leg is still held
+    pub fn held_direction(&self) -> Option<Direction> {
+        self.direction.or_else(|| {
+            let long_on_extended = match (&self.extended_position, &self.pacifica_position) {
+                (Some(ext), _) => ext.is_long(),
+                (None, Some(pac)) => !pac.is_long(),
+                (None, None) => return None,
+            };
+            Some(Direction::from_long_on_extended(long_on_extended))
+        })
+    }
+
+    /// Extended market of the position: as opened, else the leg's, else the default name
+    pub fn extended_market_name(&self) -> String {
+        self.extended_market
+            .clone()
+            .or_else(|| self.extended_position.as_ref().map(|p| p.market.clone()))
+            .unwrap_or_else(|| SymbolMap::default().extended_market(&self.symbol))
+    }
+
+    /// Pacifica market of the position, as `extended_market_name`
+    pub fn pacifica_market_name(&self) -> String {
+        self.pacifica_market
+            .clone()
+            .or_else(|| self.pacifica_position.as_ref().map(|p| p.symbol.clone()))
+            .unwrap_or_else(|| SymbolMap::default().pacifica_market(&self.symbol))
+    }
+
+    /// Size held on both legs in canonical base units (the smaller leg's), or `None`
+    /// unless both legs are known
+    pub fn hedged_base_size(&self) -> Option<f64> {
+        let ext = self.multipliers.base_size(Venue::Extended, self.extended_position.as_ref()?.size_f64().abs());
+        let pac = self.multipliers.base_size(Venue::Pacifica, self.pacifica_position.as_ref()?.size().abs());
+        Some(ext.min(pac))
+    }
+
+    /// Net funding realized across both legs, in USD
+    pub fn realized_funding_usd(&self) -> f64 {
+        self.realized_funding_extended_usd + self.realized_funding_pacifica_usd
+    }
+
+    /// Annualized yield of realized funding on the position notional, in percent
+    pub fn realized_funding_apr(&self, now: u64) -> Option<f64> {
+        let elapsed_secs = now.saturating_sub(self.opened_at);
+        if elapsed_secs == 0 || self.target_notional_usd <= 0.0 {
+            return None;
+        }
+        let elapsed_hours = elapsed_secs as f64 / 3600.0;
+        Some(apr_from_rate(self.realized_funding_usd() / self.target_notional_usd, elapsed_hours) * 100.0)
+    }
+
+    /// Take entry prices and the entry basis from the venue-reported legs
+    pub fn record_entry_prices(&mut self) {
+        self.extended_entry_price = self
+            .extended_position
+            .as_ref()
+            .and_then(|p| p.entry_price.as_deref())
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|price| *price > 0.0);
+        self.pacifica_entry_price = self
+            .pacifica_position
+            .as_ref()
+            .map(|p| p.entry())
+            .filter(|price| *price > 0.0);
+        self.entry_basis_pct = match (self.extended_entry_price, self.pacifica_entry_price) {
+            (Some(ext), Some(pac)) => Some(self.basis_pct(ext, pac)),
+            _ => None,
+        };
+    }
+
+    /// `basis_pct` of venue prices per contract, compared per canonical unit
+    pub fn basis_pct(&self, extended_price: f64, pacifica_price: f64) -> f64 {
+        basis_pct(
+            self.multipliers.base_price(Venue::Extended, extended_price),
+            self.multipliers.base_price(Venue::Pacifica, pacifica_price),
+        )
+    }
+
+    /// What the short leg sold above the long leg's buy price, times the smaller leg
+    /// size, in USD. `None` unless both legs and both entry prices are known.
+    pub fn entry_basis_usd(&self) -> Option<f64> {
+        let ext = self.extended_position.as_ref()?;
+        let ext_entry = self.multipliers.base_price(Venue::Extended, self.extended_entry_price?);
+        let pac_entry = self.multipliers.base_price(Venue::Pacifica, self.pacifica_entry_price?);
+        let size = self.hedged_base_size()?;
+        let (long_entry, short_entry) = if ext.is_long() { (ext_entry, pac_entry) } else { (pac_entry, ext_entry) };
+        Some((short_entry - long_entry) * size)
+    }
+
+    /// Price PnL of both legs marked at the given prices, in USD. Funding is excluded, so
+    /// on a matched hedge this is the effect of basis drift since entry.
+    /// `None` unless both legs and both entry prices are known.
+    pub fn basis_pnl_usd(&self, extended_price: f64, pacifica_price: f64) -> Option<f64> {
+        let ext = self.extended_position.as_ref()?;
+        let pac = self.pacifica_position.as_ref()?;
+        let ext_entry = self.extended_entry_price?;
+        let pac_entry = self.pacifica_entry_price?;
+        let direction = |long: bool| if long { 1.0 } else { -1.0 };
+        Some(
+            direction(ext.is_long()) * ext.size_f64().abs() * (extended_price - ext_entry)
+                + direction(pac.is_long()) * pac.size().abs() * (pacifica_price - pac_entry),
+        )
+    }
+}
+
+/// Pacifica price premium over Extended, in percent of the Extended price
+pub fn basis_pct(extended_price: f64, pacifica_price: f64) -> f64 {
+    if extended_price <= 0.0 {
+        return 0.0;
+    }
+    (pacifica_price - extended_price) / extended_price * 100.0
+}
+
+type Result<T> = std::result::Result<T, BotError>;
+
+/// Order slot within a position's lifecycle, encoded into its client order IDs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum OrderLeg {
+    ExtendedOpen = 1,
+    PacificaOpen = 2,
+    ExtendedRollback = 3,
+    ExtendedClose = 4,
+    PacificaClose = 5,
+    PacificaRollback = 6,
+    /// Partial closes, under a `reduction_id` seed
+    ExtendedReduce = 7,
+    PacificaReduce = 8,
+    /// Resting take-profit/stop-loss, see `protective_orders`
+    ExtendedProtect = 9,
+    PacificaTakeProfit = 10,
+    PacificaStopLoss = 11,
+}
+
+/// Deterministic client order ID for `attempt` (1-based) of `leg` of a position.
+///
+/// The same inputs always give the same ID, so after a timeout the bot can ask the
+/// venue whether an earlier attempt executed before sending another one.
+pub fn client_order_id(position_id: &Uuid, leg: OrderLeg, attempt: u32) -> Uuid {
+    let mut bytes = *position_id.as_bytes();
+    bytes[13] = leg as u8;
+    bytes[14..16].copy_from_slice(&(attempt.min(u16::MAX as u32) as u16).to_be_bytes());
+    uuid::Builder::from_custom_bytes(bytes).into_uuid()
+}
+
+/// Seed for the client order IDs of the `reduction`-th (1-based) partial close of a
+/// position. Only the `*Reduce` legs are used under it, so its IDs never collide with
+/// the position's own orders.
+pub fn reduction_id(position_id: &Uuid, reduction: u32) -> Uuid {
+    let mut bytes = *position_id.as_bytes();
+    bytes[10..12].copy_from_slice(&(reduction.min(u16::MAX as u32) as u16).to_be_bytes());
+    uuid::Builder::from_custom_bytes(bytes).into_uuid()
+}
+
+/// Whether `client_order_id` was derived from `position_id` by `client_order_id` or
+/// `reduction_id`. Only the bytes both leave alone are compared (deriving overwrites
+/// bytes 10..16 and the UUID version and variant bits).
+pub fn derives_from_position(client_order_id: &str, position_id: &str) -> bool {
+    let (Ok(client), Ok(position)) = (Uuid::parse_str(client_order_id), Uuid::parse_str(position_id)) else {
+        return false;
+    };
+    let (client, position) = (client.as_bytes(), position.as_bytes());
+    client[..6] == position[..6]
+        && client[6] & 0x0f == position[6] & 0x0f
+        && client[7] == position[7]
+        && client[8] & 0x3f == position[8] & 0x3f
+        && client[9] == position[9]
+}
+
+/// Whether an execution opened or closed a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionKind {
+    Open,
+    Close,
+    /// Partial close, see `close_partial`
+    Reduce,
 }
 
-impl std::fmt::Display for TradingError {
+/// Order the two legs of an open were actually submitted in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LegSequence {
+    ExtendedFirst,
+    PacificaFirst,
+    Concurrent,
+}
+
+impl std::fmt::Display for LegSequence {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "TradingError: {}", self.message)
+        match self {
+            LegSequence::ExtendedFirst => write!(f, "Extended first"),
+            LegSequence::PacificaFirst => write!(f, "Pacifica first"),
+            LegSequence::Concurrent => write!(f, "concurrent"),
+        }
     }
 }
 
-impl std::error::Error for TradingError {}
+/// How one order of an open or close executed. Fill fields are looked up from the venue
+/// after the order is accepted and stay `None` when that lookup fails.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LegExecution {
+    pub venue: Venue,
+    pub leg: OrderLeg,
+    pub is_buy: bool,
+    /// Client order ID of the attempt that executed
+    pub client_order_id: String,
+    /// Venue-assigned order ID
+    pub order_id: Option<String>,
+    /// Attempt that executed (0 = spread-capture limit order)
+    pub attempt: u32,
+    pub requested_size: f64,
+    pub filled_size: Option<f64>,
+    pub avg_fill_price: Option<f64>,
+    pub fee_usd: Option<f64>,
+    /// `fee_usd` was priced from the fee schedule because the venue did not report it
+    #[serde(default)]
+    pub fee_estimated: bool,
+    /// Mid price on the venue when the order was decided on
+    pub decision_price: Option<f64>,
+    /// Unix time (ms) the first attempt was sent
+    pub sent_at_ms: i64,
+    /// Time from the first attempt until the venue confirmed the order
+    pub latency_ms: u64,
+    /// Slippage (%) the venue was allowed to fill a market order at, where the bot
+    /// sets one (Pacifica opens, see `adaptive_slippage_tolerance_pct`)
+    #[serde(default)]
+    pub slippage_tolerance_pct: Option<f64>,
+}
+
+impl LegExecution {
+    /// Price given up against the decision price per unit; negative when the fill was better
+    fn adverse_move(&self) -> Option<f64> {
+        let fill = self.avg_fill_price?;
+        let decision = self.decision_price.filter(|p| *p > 0.0)?;
+        Some(if self.is_buy { fill - decision } else { decision - fill })
+    }
+
+    /// Slippage of the average fill against the decision price, in percent
+    pub fn slippage_pct(&self) -> Option<f64> {
+        Some(self.adverse_move()? / self.decision_price? * 100.0)
+    }
+
+    /// Slippage cost in USD over the filled size
+    pub fn slippage_usd(&self) -> Option<f64> {
+        Some(self.adverse_move()? * self.filled_size.unwrap_or(self.requested_size))
+    }
 
-impl TradingError {
-    pub fn new(message: String, recoverable: bool) -> Self {
-        Self { message, recoverable }
+    /// Fee of this order at `fees`' rates: maker for a spread-capture order when the
+    /// schedule credits those as maker, taker otherwise. `None` without a price.
+    pub fn scheduled_fee_usd(&self, fees: &FeeSchedule) -> Option<f64> {
+        let price = self.avg_fill_price.or(self.decision_price)?;
+        let maker = self.attempt == CAPTURE_ATTEMPT && fees.capture_lead_as_maker;
+        let notional = self.filled_size.unwrap_or(self.requested_size) * price;
+        Some(fees.fee_pct(self.venue, maker) / 100.0 * notional)
     }
 }
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+/// Per-leg record of an open or close
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExecutionReport {
+    pub position_id: String,
+    pub symbol: String,
+    pub kind: ExecutionKind,
+    /// Unix time (ms) the execution started
+    pub started_at_ms: i64,
+    /// Unix time (ms) the last order was confirmed
+    pub completed_at_ms: i64,
+    pub legs: Vec<LegExecution>,
+    /// Submission order of an open's legs; `None` for closes and older reports
+    #[serde(default)]
+    pub sequence: Option<LegSequence>,
+}
+
+impl ExecutionReport {
+    pub fn leg(&self, venue: Venue) -> Option<&LegExecution> {
+        self.legs.iter().find(|leg| leg.venue == venue)
+    }
+
+    /// Fees over all legs, in USD; unknown fees count as zero
+    pub fn total_fees_usd(&self) -> f64 {
+        self.legs.iter().filter_map(|leg| leg.fee_usd).sum()
+    }
+
+    /// Price the fee of every leg the venue did not report a fee for from `fees`
+    pub fn estimate_missing_fees(&mut self, fees: &FeeSchedule) {
+        for leg in self.legs.iter_mut().filter(|leg| leg.fee_usd.is_none()) {
+            leg.fee_usd = leg.scheduled_fee_usd(fees);
+            leg.fee_estimated = leg.fee_usd.is_some();
+        }
+    }
+
+    /// Slippage cost over all legs, in USD; unknown slippage counts as zero
+    pub fn total_slippage_usd(&self) -> f64 {
+        self.legs.iter().filter_map(|leg| leg.slippage_usd()).sum()
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        (self.completed_at_ms - self.started_at_ms).max(0) as u64
+    }
 
-pub(crate) fn looks_like_rate_limit(error_msg: &str) -> bool {
-    let msg = error_msg.to_lowercase();
-    msg.contains("429") || msg.contains("too many requests") || msg.contains("rate limit")
+    /// Time (ms) one leg was confirmed while the other was not: how long the position
+    /// was exposed to price moves on one side. `None` unless both legs are reported.
+    pub fn legging_ms(&self) -> Option<u64> {
+        let confirmed = |venue| self.leg(venue).map(|leg| leg.sent_at_ms + leg.latency_ms as i64);
+        Some(confirmed(Venue::Extended)?.abs_diff(confirmed(Venue::Pacifica)?))
+    }
 }
 
-pub(crate) fn backoff_delay_ms(attempt: u32, rate_limited: bool) -> u64 {
-    if rate_limited {
-        RATE_LIMIT_BACKOFF_MS * attempt as u64
-    } else {
-        let capped_exponent = attempt.saturating_sub(1).min(BACKOFF_MAX_EXPONENT);
-        ORDER_BASE_BACKOFF_MS * 2u64.pow(capped_exponent)
+impl std::fmt::Display for ExecutionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {} in {}ms", self.kind, self.symbol, self.duration_ms())?;
+        if let Some(sequence) = self.sequence {
+            write!(f, " ({}", sequence)?;
+            if let Some(legging) = self.legging_ms() {
+                write!(f, ", legged {}ms", legging)?;
+            }
+            write!(f, ")")?;
+        }
+        for leg in &self.legs {
+            write!(
+                f,
+                "; {} {} {}",
+                leg.venue,
+                if leg.is_buy { "buy" } else { "sell" },
+                leg.filled_size.unwrap_or(leg.requested_size)
+            )?;
+            if let Some(price) = leg.avg_fill_price {
+                write!(f, " @ {}", price)?;
+            }
+            if let Some(slippage) = leg.slippage_pct() {
+                write!(f, " (slippage {:.4}%", slippage)?;
+                if let Some(tolerance) = leg.slippage_tolerance_pct {
+                    write!(f, " of {:.4}% allowed", tolerance)?;
+                }
+                write!(f, ")")?;
+            }
+            if let Some(fee) = leg.fee_usd {
+                write!(f, " fee ${:.4}{}", fee, if leg.fee_estimated { " (estimated)" } else { "" })?;
+            }
+            write!(f, " after {}ms", leg.latency_ms)?;
+        }
+        write!(f, "; total fees ${:.4}, slippage ${:.4}", self.total_fees_usd(), self.total_slippage_usd())
+    }
+}
+
+/// Realized legging risk of the opens sent in one `LegSequence`
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LeggingStats {
+    pub opens: u64,
+    /// Sum and worst of `ExecutionReport::legging_ms`
+    pub total_legging_ms: u64,
+    pub max_legging_ms: u64,
+    /// Slippage over both legs of these opens, in USD
+    pub slippage_usd: f64,
+}
+
+impl LeggingStats {
+    /// Count `report`; an open missing a leg's report has no legging to measure and is
+    /// skipped rather than averaged in as zero
+    pub fn record(&mut self, report: &ExecutionReport) {
+        let Some(legging) = report.legging_ms() else {
+            return;
+        };
+        self.opens += 1;
+        self.total_legging_ms += legging;
+        self.max_legging_ms = self.max_legging_ms.max(legging);
+        self.slippage_usd += report.total_slippage_usd();
+    }
+
+    pub fn mean_legging_ms(&self) -> Option<f64> {
+        (self.opens > 0).then(|| self.total_legging_ms as f64 / self.opens as f64)
     }
 }
 
+/// Attempt of a leg that the venue confirmed
+struct PlacedOrder {
+    client_order_id: String,
+    order_id: Option<String>,
+    attempt: u32,
+    sent_at_ms: i64,
+    latency_ms: u64,
+}
+
+impl PlacedOrder {
+    fn new(client_order_id: String, order_id: Option<String>, attempt: u32, sent_at_ms: i64) -> Self {
+        Self {
+            client_order_id,
+            order_id,
+            attempt,
+            sent_at_ms,
+            latency_ms: (clock::now_ms() - sent_at_ms).max(0) as u64,
+        }
+    }
+
+    fn execution(self, venue: Venue, leg: OrderLeg, is_buy: bool, requested_size: f64, decision_price: Option<f64>) -> LegExecution {
+        LegExecution {
+            venue,
+            leg,
+            is_buy,
+            client_order_id: self.client_order_id,
+            order_id: self.order_id,
+            attempt: self.attempt,
+            requested_size,
+            filled_size: None,
+            avg_fill_price: None,
+            fee_usd: None,
+            fee_estimated: false,
+            decision_price,
+            sent_at_ms: self.sent_at_ms,
+            latency_ms: self.latency_ms,
+            slippage_tolerance_pct: None,
+        }
+    }
+}
+
+/// Mid of a streamed quote, used as the decision price
+fn quote_mid(quote: Option<&BidAsk>) -> Option<f64> {
+    quote.and_then(mid_and_spread_pct).map(|(mid, _)| mid).filter(|mid| *mid > 0.0)
+}
+
+/// Fill an Extended leg's execution in from the order record
+async fn lookup_extended_fill(extended_client: &RestClient, execution: &mut LegExecution) {
+    let timeout = Duration::from_millis(EXTENDED_FILL_WAIT_TIMEOUT_MS);
+    let order = match extended_client.wait_for_fill(&execution.client_order_id, timeout).await {
+        Ok(order) => order,
+        Err(e) => {
+            warn!("Could not look up Extended fill of {}: {}", execution.client_order_id, e);
+            return;
+        }
+    };
+    execution.order_id.get_or_insert_with(|| order.id.to_string());
+    execution.filled_size = Some(order.filled_qty_f64()).filter(|size| *size > 0.0);
+    execution.avg_fill_price = order.average_price_f64();
+    execution.fee_usd = order.payed_fee_f64();
+}
+
+/// Fill a Pacifica leg's execution in from its trades
+fn apply_pacifica_trades(execution: &mut LegExecution, trades: &[TradeHistoryItem]) {
+    let fills: Vec<&TradeHistoryItem> = trades.iter().filter(|t| t.client_order_id == execution.client_order_id).collect();
+    let parse = |s: &str| s.parse::<f64>().unwrap_or(0.0);
+    let filled: f64 = fills.iter().map(|t| parse(&t.amount).abs()).sum();
+    if filled <= 0.0 {
+        return;
+    }
+    let notional: f64 = fills.iter().map(|t| parse(&t.amount).abs() * parse(&t.entry_price)).sum();
+    execution.order_id.get_or_insert_with(|| fills[0].order_id.to_string());
+    execution.filled_size = Some(filled);
+    execution.avg_fill_price = Some(notional / filled).filter(|price| *price > 0.0);
+    execution.fee_usd = Some(fills.iter().map(|t| parse(&t.fee)).sum());
+}
+
+async fn lookup_pacifica_fill(
+    pacifica_client: &PacificaTrading,
+    pacifica_market_symbol: &str,
+    execution: &mut LegExecution,
+) {
+    let since_ms = (execution.sent_at_ms - ORDER_LOOKUP_LOOKBACK_MS).max(0) as u64;
+    match pacifica_client
+        .get_trade_history(Some(pacifica_market_symbol), Some(ORDER_LOOKUP_TRADE_LIMIT), Some(since_ms), None)
+        .await
+    {
+        Ok(trades) => apply_pacifica_trades(execution, &trades),
+        Err(e) => warn!("Could not look up Pacifica fill of {}: {}", execution.client_order_id, e),
+    }
+}
+
+/// External ID of an Extended attempt in `1..=attempts` that executed, if any.
+/// Lookup errors are logged and count as "not filled".
+async fn find_filled_extended_attempt(
+    extended_client: &RestClient,
+    position_id: &Uuid,
+    leg: OrderLeg,
+    attempts: u32,
+) -> Option<String> {
+    for attempt in 1..=attempts {
+        let id = client_order_id(position_id, leg, attempt).to_string();
+        match extended_client.get_orders_by_external_id(&id).await {
+            Ok(orders) if orders.iter().any(|o| o.has_fill()) => return Some(id),
+            Ok(_) => {}
+            Err(e) => warn!("Could not check Extended order {}: {}", id, e),
+        }
+    }
+    None
+}
+
+/// Client order ID of a Pacifica attempt in `1..=attempts` that executed, if any.
+/// Lookup errors are logged and count as "not filled".
+async fn find_filled_pacifica_attempt(
+    pacifica_client: &PacificaTrading,
+    pacifica_market_symbol: &str,
+    position_id: &Uuid,
+    leg: OrderLeg,
+    attempts: u32,
+    since_ms: i64,
+) -> Option<String> {
+    let since_ms = (since_ms - ORDER_LOOKUP_LOOKBACK_MS).max(0) as u64;
+    let trades = match pacifica_client
+        .get_trade_history(Some(pacifica_market_symbol), Some(ORDER_LOOKUP_TRADE_LIMIT), Some(since_ms), None)
+        .await
+    {
+        Ok(trades) => trades,
+        Err(e) => {
+            warn!("Could not check Pacifica fills for {:?}: {}", leg, e);
+            return None;
+        }
+    };
+
+    (1..=attempts)
+        .map(|attempt| client_order_id(position_id, leg, attempt).to_string())
+        .find(|id| filled_amount_for_client_id(&trades, id) > 0.0)
+}
+
+/// Write the intent for an order about to be sent. The order is not sent if this fails,
+/// since a crash afterwards would leave an execution the bot cannot account for.
+fn log_intent(intents: &IntentLog, record: IntentRecord) -> Result<()> {
+    intents.record(&record).map_err(|e| {
+        BotError::execution(format!("Refusing to send order: cannot write intent log: {}", e), false)
+    })
+}
+
+/// Position ID to derive close order IDs from; legacy positions get a fresh one per close
+fn position_uuid(position: &DeltaNeutralPosition) -> Uuid {
+    Uuid::parse_str(&position.position_id).unwrap_or_else(|_| Uuid::new_v4())
+}
+
 /// Calculate position size based on available capital and lot size constraints
 pub fn calculate_position_size(
     extended_free_collateral: f64,
@@ -68,126 +654,1031 @@ pub fn calculate_position_size(
     current_price: f64,
     max_position_size_usd: f64,
 ) -> f64 {
-    // Take 95% of minimum available capital
-    let min_capital = extended_free_collateral.min(pacifica_free_collateral);
-    let available_notional = min_capital * 0.95;
+    // Take 95% of minimum available capital, in Decimal so notional and lot rounding are exact
+    let min_capital = decimal_from_f64(extended_free_collateral.min(pacifica_free_collateral));
+    let available_notional = min_capital * Decimal::new(95, 2);
 
     // Cap by max_position_size_usd
-    let target_notional = available_notional.min(max_position_size_usd);
+    let target_notional = available_notional.min(decimal_from_f64(max_position_size_usd));
 
     // Convert to base currency size
-    let base_size = target_notional / current_price;
+    let price = decimal_from_f64(current_price);
+    if price <= Decimal::ZERO {
+        return 0.0;
+    }
+    let base_size = target_notional / price;
 
     // Round to coarser lot_size
-    let coarser_lot_size = extended_lot_size.max(pacifica_lot_size);
+    let coarser_lot_size = decimal_from_f64(extended_lot_size.max(pacifica_lot_size));
 
     // Round down to nearest lot_size
-    let rounded_size = (base_size / coarser_lot_size).floor() * coarser_lot_size;
+    decimal_to_f64(round_down_to_step(base_size, coarser_lot_size))
+}
+
+/// Rough distance to liquidation (% of entry price) for a leg opened at `leverage`.
+/// Ignores maintenance margin, so the real distance is slightly smaller.
+pub fn estimated_liquidation_distance_pct(leverage: u32) -> f64 {
+    100.0 / leverage.max(1) as f64
+}
+
+/// Validate the configured leverage against each exchange's market limit.
+/// Returns an error describing the offending venue instead of silently clamping.
+pub fn validate_leverage(
+    requested: u32,
+    extended_max_leverage: Option<f64>,
+    pacifica_max_leverage: Option<i32>,
+) -> std::result::Result<u32, String> {
+    if requested == 0 {
+        return Err("Leverage must be at least 1x".to_string());
+    }
+    if let Some(max) = extended_max_leverage {
+        if max > 0.0 && requested as f64 > max {
+            return Err(format!("Leverage {}x exceeds Extended max leverage {}x", requested, max));
+        }
+    }
+    if let Some(max) = pacifica_max_leverage {
+        if max > 0 && requested as i64 > max as i64 {
+            return Err(format!("Leverage {}x exceeds Pacifica max leverage {}x", requested, max));
+        }
+    }
+    Ok(requested)
+}
 
-    rounded_size
+/// One venue's order size constraints for a market. Sizes are in venue contracts until
+/// `with_multiplier` converts them to canonical base units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderSizeLimits {
+    pub venue: Venue,
+    /// Canonical units per contract the sizes are expressed in (1 = contracts)
+    pub multiplier: f64,
+    /// Size increment (base units)
+    pub lot_size: f64,
+    /// Price increment
+    pub tick_size: f64,
+    /// Smallest order (base units, 0 = none)
+    pub min_size: f64,
+    /// Smallest order value (USD, 0 = none)
+    pub min_notional_usd: f64,
+    /// Largest order value (USD)
+    pub max_notional_usd: Option<f64>,
 }
 
-/// Retry function with exponential backoff
-pub async fn retry_with_backoff<F, Fut, T>(
-    max_attempts: u32,
-    operation_name: &str,
-    mut operation: F,
-) -> Result<T>
-where
-    F: FnMut() -> Fut,
-    Fut: std::future::Future<Output = Result<T>>,
-{
-    let mut attempt = 0;
+impl OrderSizeLimits {
+    /// Extended limits are in base units: `minOrderSize`, step `minOrderSizeChange`;
+    /// the largest order is the `maxMarketOrderValue` in USD, since opens trade at market
+    pub fn extended(config: &TradingConfig) -> std::result::Result<Self, String> {
+        let parse = |field: &str, value: &str| {
+            parse_decimal(value).map(decimal_to_f64).ok_or_else(|| format!("Extended market has invalid {}: {:?}", field, value))
+        };
+        Ok(Self {
+            venue: Venue::Extended,
+            multiplier: 1.0,
+            lot_size: parse("minOrderSizeChange", &config.min_order_size_change)?,
+            tick_size: parse("minPriceChange", &config.min_price_change)?,
+            min_size: parse("minOrderSize", &config.min_order_size)?,
+            min_notional_usd: 0.0,
+            max_notional_usd: config
+                .max_market_order_value
+                .as_deref()
+                .map(|v| parse("maxMarketOrderValue", v))
+                .transpose()?
+                .filter(|v| *v > 0.0),
+        })
+    }
 
-    loop {
-        attempt += 1;
+    /// Pacifica `min_order_size` / `max_order_size` are order values in USD
+    pub fn pacifica(info: &PacificaMarketInfo) -> std::result::Result<Self, String> {
+        let parse = |field: &str, value: &str| {
+            parse_decimal(value).map(decimal_to_f64).ok_or_else(|| format!("Pacifica market has invalid {}: {:?}", field, value))
+        };
+        Ok(Self {
+            venue: Venue::Pacifica,
+            multiplier: 1.0,
+            lot_size: parse("lot_size", &info.lot_size)?,
+            tick_size: parse("tick_size", &info.tick_size)?,
+            min_size: 0.0,
+            min_notional_usd: info.min_order_size.as_deref().map(|v| parse("min_order_size", v)).transpose()?.unwrap_or(0.0),
+            max_notional_usd: info.max_order_size.as_deref().map(|v| parse("max_order_size", v)).transpose()?.filter(|v| *v > 0.0),
+        })
+    }
 
-        match operation().await {
-            Ok(result) => {
-                if attempt > 1 {
-                    info!("{} succeeded on attempt {}/{}", operation_name, attempt, max_attempts);
-                }
-                return Ok(result);
-            }
-            Err(e) => {
-                if attempt >= max_attempts {
-                    error!("{} failed after {} attempts: {}", operation_name, max_attempts, e);
-                    return Err(e);
-                }
+    /// The same limits in canonical base units, for a venue whose contract stands for
+    /// `multiplier` units, so they compare with the other venue's
+    pub fn with_multiplier(self, multiplier: f64) -> Self {
+        let scale = multiplier / self.multiplier;
+        Self {
+            multiplier,
+            lot_size: self.lot_size * scale,
+            tick_size: self.tick_size / scale,
+            min_size: self.min_size * scale,
+            ..self
+        }
+    }
+
+    /// Largest order in base units at `price`
+    fn max_base(&self, price: f64) -> Option<f64> {
+        self.max_notional_usd.map(|usd| usd / price)
+    }
+
+    /// Smallest order in base units at `price`
+    fn min_base(&self, price: f64) -> f64 {
+        self.min_size.max(self.min_notional_usd / price)
+    }
+}
+
+/// Pre-trade size check for a delta-neutral open, where both legs trade the same size.
+/// Sizes above a venue maximum are clamped down to it; the result is rounded down to
+/// the coarser lot and must be a whole number of every venue's lot. Sizes below a venue
+/// minimum, and markets without a usable lot or tick size, are rejected.
+pub fn validate_order_size(size: f64, price: f64, limits: &[OrderSizeLimits]) -> std::result::Result<f64, String> {
+    if !price.is_finite() || price <= 0.0 {
+        return Err(format!("Cannot validate order size without a positive price (got {})", price));
+    }
+    for limit in limits {
+        if limit.lot_size <= 0.0 || limit.tick_size <= 0.0 {
+            return Err(format!("{} market has no usable lot size ({}) or tick size ({})", limit.venue, limit.lot_size, limit.tick_size));
+        }
+    }
+
+    let mut size = size;
+    for limit in limits {
+        if let Some(max) = limit.max_base(price).filter(|max| size > *max) {
+            warn!("Order size {:.6} exceeds {} maximum {:.6} (${:.2}); clamping", size, limit.venue, max, max * price);
+            size = max;
+        }
+    }
+
+    let coarser_lot = limits.iter().map(|l| decimal_from_f64(l.lot_size)).max().unwrap_or(Decimal::ZERO);
+    let rounded = round_down_to_step(decimal_from_f64(size), coarser_lot);
+    for limit in limits {
+        if !(rounded % decimal_from_f64(limit.lot_size)).is_zero() {
+            return Err(format!(
+                "Order size {} is not a multiple of the {} lot size {} (lots differ too much to trade both legs the same size)",
+                rounded, limit.venue, limit.lot_size
+            ));
+        }
+    }
+    let size = decimal_to_f64(rounded);
+    if size <= 0.0 {
+        return Err(format!("Order size rounds to zero at lot size {}", coarser_lot));
+    }
+
+    for limit in limits {
+        let min = limit.min_base(price);
+        if size + limit.lot_size * 1e-6 < min {
+            return Err(format!(
+                "Order size {} (${:.2}) is below the {} minimum of {:.6} (${:.2})",
+                size,
+                size * price,
+                limit.venue,
+                min,
+                min * price
+            ));
+        }
+    }
+    Ok(size)
+}
+
+/// Smallest notional (USD) at `price` that both legs of a delta-neutral open can trade:
+/// the largest venue minimum, rounded up to the coarser lot. Below it `validate_order_size`
+/// rejects the order, so an open that cannot reach it is skipped before anything is sent.
+pub fn minimum_order_notional_usd(price: f64, limits: &[OrderSizeLimits]) -> f64 {
+    if !price.is_finite() || price <= 0.0 {
+        return 0.0;
+    }
+    let min_base = limits.iter().map(|l| l.min_base(price)).fold(0.0, f64::max);
+    let coarser_lot = limits.iter().map(|l| decimal_from_f64(l.lot_size)).max().unwrap_or(Decimal::ZERO);
+    let min_base = decimal_from_f64(min_base);
+    let rounded = if coarser_lot > Decimal::ZERO { (min_base / coarser_lot).ceil() * coarser_lot } else { min_base };
+    decimal_to_f64(rounded) * price
+}
+
+/// Expected slippage (%) vs `mid` for a market order of `size` walking `levels`
+/// (price, quantity) from the top of the book. Returns None when the book is too
+/// thin to fill the whole size.
+pub fn estimate_fill_slippage_pct(levels: &[(f64, f64)], size: f64, mid: f64) -> Option<f64> {
+    if size <= 0.0 || mid <= 0.0 {
+        return None;
+    }
+
+    let mut remaining = size;
+    let mut cost = 0.0;
+    for &(price, qty) in levels {
+        let take = remaining.min(qty);
+        cost += take * price;
+        remaining -= take;
+        if remaining <= 1e-12 {
+            let vwap = cost / size;
+            return Some(((vwap - mid) / mid).abs() * 100.0);
+        }
+    }
+    None
+}
+
+/// Slippage (%) to allow a market order expected to slip `expected_pct` when walking
+/// the book: the expectation plus `margin_pct` for the book moving before the order
+/// lands, capped at `max_pct`
+pub fn adaptive_slippage_tolerance_pct(expected_pct: f64, margin_pct: f64, max_pct: f64) -> f64 {
+    (expected_pct + margin_pct).min(max_pct)
+}
+
+/// Book side as (price, quantity) levels, best first
+type Levels = Vec<(f64, f64)>;
+
+pub(crate) fn parse_levels<'a>(levels: impl Iterator<Item = (&'a str, &'a str)>) -> Levels {
+    levels
+        .filter_map(|(p, q)| Some((p.parse::<f64>().ok()?, q.parse::<f64>().ok()?)))
+        .collect()
+}
 
-                let rate_limited = looks_like_rate_limit(&e.to_string());
-                let delay_ms = backoff_delay_ms(attempt, rate_limited);
-                warn!(
-                    "{} failed (attempt {}/{}{}): {}. Retrying in {}ms...",
-                    operation_name,
-                    attempt,
-                    max_attempts,
-                    if rate_limited { " - rate limited" } else { "" },
-                    e,
-                    delay_ms
-                );
+/// Fresh streamed top of book on each venue, when available
+#[derive(Debug, Clone, Default)]
+pub struct TopOfBookQuotes {
+    pub extended: Option<BidAsk>,
+    pub pacifica: Option<BidAsk>,
+    /// Fresh streamed Pacifica book with every level, for sizes the top level cannot fill
+    pub pacifica_book: Option<OrderbookSnapshot>,
+}
+
+/// One-level (bids, asks) book from a streamed quote, when the top level on the side
+/// taken can fill `size` on its own (deeper levels are then irrelevant)
+fn top_level_book(quote: Option<&BidAsk>, is_buy: bool, size: f64) -> Option<(Levels, Levels)> {
+    let quote = quote?;
+    let bids = parse_levels(std::iter::once((quote.best_bid.as_deref()?, quote.bid_quantity.as_deref()?)));
+    let asks = parse_levels(std::iter::once((quote.best_ask.as_deref()?, quote.ask_quantity.as_deref()?)));
+    let top_quantity = if is_buy { asks.first()?.1 } else { bids.first()?.1 };
+    (bids.len() == 1 && top_quantity >= size).then_some((bids, asks))
+}
+
+/// Check that neither leg's expected fill (from current book depth) exceeds the slippage budget.
+/// `sizes` are the Extended and Pacifica order sizes, each in that venue's contracts.
+/// A streamed quote deep enough for the whole size, or a streamed Pacifica book, saves the
+/// REST orderbook call. Returns the expected slippage (%) of the Extended and Pacifica legs.
+#[allow(clippy::too_many_arguments)]
+async fn check_slippage_budget(
+    extended_client: &RestClient,
+    pacifica_client: &PacificaTrading,
+    extended_market_symbol: &str,
+    pacifica_market_symbol: &str,
+    long_on_extended: bool,
+    sizes: [f64; 2],
+    max_slippage_pct: f64,
+    quotes: &TopOfBookQuotes,
+) -> Result<[f64; 2]> {
+    let (ext_bids, ext_asks) = match top_level_book(quotes.extended.as_ref(), long_on_extended, sizes[0]) {
+        Some(book) => book,
+        None => {
+            let ext_book = extended_client.get_orderbook(extended_market_symbol).await?;
+            (
+                parse_levels(ext_book.bid.iter().map(|l| (l.price.as_str(), l.quantity.as_str()))),
+                parse_levels(ext_book.ask.iter().map(|l| (l.price.as_str(), l.quantity.as_str()))),
+            )
+        }
+    };
+
+    let (pac_bids, pac_asks) = match top_level_book(quotes.pacifica.as_ref(), !long_on_extended, sizes[1]) {
+        Some(book) => book,
+        None => {
+            let pac_book = match &quotes.pacifica_book {
+                Some(book) => book.clone(),
+                None => pacifica_client.get_orderbook_rest(pacifica_market_symbol, 1).await?,
+            };
+            (
+                parse_levels(pac_book.bids.iter().map(|l| (l.price.as_str(), l.size.as_str()))),
+                parse_levels(pac_book.asks.iter().map(|l| (l.price.as_str(), l.size.as_str()))),
+            )
+        }
+    };
 
-                sleep(Duration::from_millis(delay_ms)).await;
+    let legs = [
+        ("Extended", &ext_bids, &ext_asks, long_on_extended),
+        ("Pacifica", &pac_bids, &pac_asks, !long_on_extended),
+    ];
+
+    let mut expected = [0.0; 2];
+    for (i, (venue, bids, asks, is_buy)) in legs.into_iter().enumerate() {
+        let (Some(bid), Some(ask)) = (bids.first(), asks.first()) else {
+            return Err(BotError::execution(
+                format!("{} orderbook is empty, cannot estimate slippage", venue),
+                true,
+            ));
+        };
+        let mid = (bid.0 + ask.0) / 2.0;
+        let levels = if is_buy { asks } else { bids };
+
+        match estimate_fill_slippage_pct(levels, sizes[i], mid) {
+            Some(slippage) if slippage <= max_slippage_pct => {
+                info!("{} expected slippage {:.4}% (budget {:.4}%)", venue, slippage, max_slippage_pct);
+                expected[i] = slippage;
+            }
+            Some(slippage) => {
+                return Err(BotError::execution(
+                    format!(
+                        "{} expected slippage {:.4}% exceeds budget {:.4}%",
+                        venue, slippage, max_slippage_pct
+                    ),
+                    true,
+                ));
+            }
+            None => {
+                return Err(BotError::execution(
+                    format!("{} orderbook too thin to fill {:.6}", venue, sizes[i]),
+                    true,
+                ));
             }
         }
     }
+
+    Ok(expected)
+}
+
+/// Positions on both venues, the query retried under `retry`
+pub(crate) async fn fetch_live_positions(
+    extended_client: &RestClient,
+    pacifica_client: &PacificaTrading,
+    retry: &RetryPolicy,
+) -> Result<(Vec<Position>, Vec<PacificaPosition>)> {
+    retry
+        .run("Fetch positions", || async {
+            let extended = extended_client.get_positions(None).await.map_err(BotError::from)?;
+            let pacifica = pacifica_client.get_positions().await.map_err(BotError::from)?;
+            Ok((extended, pacifica))
+        })
+        .await
 }
 
 async fn fetch_opened_positions_with_backoff(
     extended_client: &RestClient,
-    pacifica_client: &mut PacificaTrading,
+    pacifica_client: &PacificaTrading,
     extended_market_symbol: &str,
     pacifica_market_symbol: &str,
+    retry: &RetryPolicy,
 ) -> Result<(Option<Position>, Option<PacificaPosition>)> {
-    let mut attempt = 0;
+    let (extended, pacifica) = fetch_live_positions(extended_client, pacifica_client, retry).await?;
+    Ok((
+        extended.into_iter().find(|p| p.market == extended_market_symbol),
+        pacifica.into_iter().find(|p| p.symbol == pacifica_market_symbol),
+    ))
+}
+
+/// Cross-venue price difference in favor of the chosen direction, and how to capture it
+///
+/// The long venue's ask is below the short venue's bid, so buying the long leg and
+/// selling the short leg at the touch locks the difference in. The leg on the thinner
+/// quote is sent first, as a limit order that only executes while the configured share
+/// of the difference is still there; the other leg follows at market.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SpreadCapture {
+    /// Touch prices per canonical unit
+    pub long_ask: f64,
+    pub short_bid: f64,
+    /// (short bid - long ask) / long ask, in percent
+    pub favorable_spread_pct: f64,
+    /// Send the Pacifica leg first instead of Extended
+    pub pacifica_first: bool,
+    /// Limit price of the leading leg, per contract of its venue
+    pub lead_limit_price: f64,
+}
+
+/// Plan a spread capture when the long venue's ask is below the short venue's bid.
+/// `capture_share_pct` is the share of that difference the leading leg must keep.
+/// Quotes are per contract; `multipliers` compares them per canonical unit.
+pub fn plan_spread_capture(
+    long_on_extended: bool,
+    extended: &BidAsk,
+    pacifica: &BidAsk,
+    multipliers: ContractMultipliers,
+    capture_share_pct: f64,
+) -> Option<SpreadCapture> {
+    if capture_share_pct <= 0.0 {
+        return None;
+    }
+    let parse = |v: &Option<String>| v.as_deref().and_then(|s| s.parse::<f64>().ok()).filter(|x| *x > 0.0);
+    let (long_venue, short_venue) = if long_on_extended { (Venue::Extended, Venue::Pacifica) } else { (Venue::Pacifica, Venue::Extended) };
+    let (long_quote, short_quote) = if long_on_extended { (extended, pacifica) } else { (pacifica, extended) };
+    let long_ask = multipliers.base_price(long_venue, parse(&long_quote.best_ask)?);
+    let short_bid = multipliers.base_price(short_venue, parse(&short_quote.best_bid)?);
+    if short_bid <= long_ask {
+        return None;
+    }
+
+    // Size at the touch each leg would hit; unknown depth counts as thin
+    let (ext_depth, pac_depth) = if long_on_extended {
+        (parse(&extended.ask_quantity), parse(&pacifica.bid_quantity))
+    } else {
+        (parse(&extended.bid_quantity), parse(&pacifica.ask_quantity))
+    };
+    let depth = |venue, depth: Option<f64>| multipliers.base_size(venue, depth.unwrap_or(0.0));
+    let pacifica_first = depth(Venue::Pacifica, pac_depth) < depth(Venue::Extended, ext_depth);
+    let lead_is_long = pacifica_first != long_on_extended;
+    let lead_venue = if pacifica_first { Venue::Pacifica } else { Venue::Extended };
+
+    let kept = (short_bid - long_ask) * capture_share_pct.min(100.0) / 100.0;
+    let lead_limit_price = if lead_is_long { short_bid - kept } else { long_ask + kept };
+    Some(SpreadCapture {
+        long_ask,
+        short_bid,
+        favorable_spread_pct: (short_bid - long_ask) / long_ask * 100.0,
+        pacifica_first,
+        lead_limit_price: lead_limit_price * multipliers.of(lead_venue),
+    })
+}
+
+/// Wait before asking a venue whether a spread-capture order executed
+const CAPTURE_FILL_CHECK_DELAY_MS: u64 = 1_000;
+/// Interval between order history lookups for a Pacifica order without a final update
+const CAPTURE_ORDER_POLL_INTERVAL_MS: u64 = 250;
+/// Attempt number of spread-capture orders; market attempts start at 1
+const CAPTURE_ATTEMPT: u32 = 0;
+/// Longest wait for the order-updates subscription before opening without it
+const FILL_WATCH_CONNECT_TIMEOUT_MS: u64 = 3_000;
+/// Longest wait for a Pacifica order's final update before falling back to trade history
+const PACIFICA_FILL_CONFIRM_TIMEOUT_MS: u64 = 3_000;
+/// Relative shortfall below which a fill counts as complete
+const PARTIAL_FILL_TOLERANCE: f64 = 1e-6;
+
+/// Subscribe to Pacifica order updates ahead of an open, so fills can be confirmed
+/// as they happen; `None` (trade-history lookups only) when the subscription fails
+async fn start_fill_watcher(pacifica_client: &PacificaTrading) -> Option<FillWatcher> {
+    let config = FillDetectionConfig {
+        account: pacifica_client.credentials().account.clone(),
+        reconnect_attempts: 1,
+        ping_interval_secs: 30,
+    };
+    let start = FillWatcher::start(config, pacifica_client.is_testnet());
+    match tokio::time::timeout(Duration::from_millis(FILL_WATCH_CONNECT_TIMEOUT_MS), start).await {
+        Ok(Ok(watcher)) => Some(watcher),
+        Ok(Err(e)) => {
+            warn!("Pacifica fill watcher unavailable, confirming fills from trade history: {}", e);
+            None
+        }
+        Err(_) => {
+            warn!("Pacifica fill watcher did not connect within {}ms, confirming fills from trade history", FILL_WATCH_CONNECT_TIMEOUT_MS);
+            None
+        }
+    }
+}
+
+fn is_partial_fill(fill: &ConfirmedFill, size: f64) -> bool {
+    fill.filled_amount < size * (1.0 - PARTIAL_FILL_TOLERANCE)
+}
 
-    loop {
-        attempt += 1;
+/// Order placement inputs shared by the legs of one open
+struct OpenLegs<'a> {
+    position_id: Uuid,
+    symbol: &'a str,
+    long_on_extended: bool,
+    current_price: f64,
+    extended_client: &'a RestClient,
+    extended_market_symbol: &'a str,
+    pacifica_market_symbol: &'a str,
+    /// Sizes passed around are canonical base units, converted to contracts per venue
+    /// when an order is sent
+    multipliers: ContractMultipliers,
+    stark_private_key: &'a str,
+    stark_public_key: &'a str,
+    vault_id: &'a str,
+    /// Tolerance for the Extended open, whose budget the pre-trade check enforced, and
+    /// for rollbacks, which must get out whatever the book looks like
+    max_slippage_pct: f64,
+    /// Tolerance for the Pacifica open, from its book walk
+    pacifica_slippage_pct: f64,
+    retry: &'a RetryPolicy,
+    intents: &'a IntentLog,
+    started_ms: i64,
+    fill_watcher: Option<&'a FillWatcher>,
+    contingency: Option<&'a Contingency>,
+}
 
-        let extended_positions = extended_client.get_positions(None).await;
-        let pacifica_positions = pacifica_client.get_positions().await;
+impl OpenLegs<'_> {
+    fn extended_side(&self) -> OrderSide {
+        if self.long_on_extended { OrderSide::Buy } else { OrderSide::Sell }
+    }
 
-        match (extended_positions, pacifica_positions) {
-            (Ok(ext), Ok(pac)) => {
-                let extended_position = ext.into_iter().find(|p| p.market == extended_market_symbol);
-                let pacifica_position = pac.into_iter().find(|p| p.symbol == pacifica_market_symbol);
-                return Ok((extended_position, pacifica_position));
+    fn pacifica_side(&self) -> PacificaOrderSide {
+        if self.long_on_extended { PacificaOrderSide::Sell } else { PacificaOrderSide::Buy }
+    }
+
+    fn extended_intent(&self, size: f64, attempt: u32, phase: IntentPhase) -> IntentRecord {
+        IntentRecord::order(&self.position_id, self.symbol, OrderLeg::ExtendedOpen, self.extended_side(), size, attempt, phase)
+    }
+
+    fn pacifica_intent(&self, size: f64, attempt: u32, phase: IntentPhase) -> IntentRecord {
+        IntentRecord::order(&self.position_id, self.symbol, OrderLeg::PacificaOpen, self.pacifica_side(), size, attempt, phase)
+    }
+
+    /// Canonical base units of a Pacifica fill, which is in contracts
+    fn pacifica_filled_base(&self, fill: &ConfirmedFill) -> f64 {
+        self.multipliers.base_size(Venue::Pacifica, fill.filled_amount)
+    }
+
+    /// Final outcome of a Pacifica order from its order updates; `None` without a fill
+    /// watcher or when no final update arrives in time
+    async fn confirm_pacifica(&self, client_id: &str) -> Option<ConfirmedFill> {
+        let watcher = self.fill_watcher?;
+        let confirmed = watcher.wait_for(client_id, Duration::from_millis(PACIFICA_FILL_CONFIRM_TIMEOUT_MS)).await;
+        match &confirmed {
+            Some(fill) => info!("Pacifica order {} confirmed: {:.6} filled", client_id, fill.filled_amount),
+            None => warn!("No final update for Pacifica order {} within {}ms", client_id, PACIFICA_FILL_CONFIRM_TIMEOUT_MS),
+        }
+        confirmed
+    }
+
+    /// Final outcome of a placed Pacifica order: its order update, or else its order and
+    /// trade history. `None` when none of them tells.
+    async fn pacifica_outcome(&self, client: &PacificaTrading, order: &PlacedOrder) -> Option<ConfirmedFill> {
+        if let Some(fill) = self.confirm_pacifica(&order.client_order_id).await {
+            return Some(fill);
+        }
+        let order_id = order.order_id.as_deref().and_then(|id| id.parse().ok());
+        match self.poll_pacifica_order(client, order_id, &order.client_order_id).await {
+            Ok(fill) => Some(fill),
+            Err(e) => {
+                warn!("{}", e);
+                None
             }
-            (ext_res, pac_res) => {
-                let mut parts = Vec::new();
-                if let Err(e) = &ext_res {
-                    parts.push(format!("Extended: {}", e));
+        }
+    }
+
+    /// Market order for the Extended open leg, retried per the retry policy
+    async fn open_extended(&self, size: f64) -> Result<PlacedOrder> {
+        let side = self.extended_side();
+        let contracts = self.multipliers.venue_size(Venue::Extended, size);
+        info!("Placing Extended order: {:?} {:.6} {} @ market", side, contracts, self.extended_market_symbol);
+        let sent_at_ms = clock::now_ms();
+
+        for attempt in 1..=self.retry.max_attempts {
+            // A timed-out attempt may still have filled; never send a second one on top of it
+            if attempt > 1 {
+                if let Some(id) = find_filled_extended_attempt(self.extended_client, &self.position_id, OrderLeg::ExtendedOpen, attempt - 1).await {
+                    info!("Earlier Extended attempt {} already filled, not resending", id);
+                    self.intents.record_or_warn(&self.extended_intent(size, attempt - 1, IntentPhase::Filled));
+                    return Ok(PlacedOrder::new(id, None, attempt - 1, sent_at_ms));
                 }
-                if let Err(e) = &pac_res {
-                    parts.push(format!("Pacifica: {}", e));
+            }
+
+            log_intent(self.intents, self.extended_intent(size, attempt, IntentPhase::Pending))?;
+            let external_id = client_order_id(&self.position_id, OrderLeg::ExtendedOpen, attempt).to_string();
+            match self.extended_client.place_market_order_with_id(
+                self.extended_market_symbol,
+                side.clone(),
+                size * self.current_price,
+                self.stark_private_key,
+                self.stark_public_key,
+                self.vault_id,
+                false, // reduce_only = false (opening position)
+                Some(contracts), // pass desired contracts to match targeted size
+                self.max_slippage_pct,
+                &external_id,
+            ).await {
+                Ok(order) => {
+                    if attempt > 1 {
+                        info!("Extended order succeeded on attempt {}/{}", attempt, self.retry.max_attempts);
+                    }
+                    self.intents.record_or_warn(&self.extended_intent(size, attempt, IntentPhase::Filled));
+                    return Ok(PlacedOrder::new(external_id, Some(order.id.to_string()), attempt, sent_at_ms));
                 }
-                let err_msg = if parts.is_empty() {
-                    "Unknown error".to_string()
-                } else {
-                    parts.join(" | ")
-                };
+                Err(e) => {
+                    let e = BotError::from(e);
+                    let rate_limited = e.is_rate_limited();
+                    if !self.retry.should_retry(attempt, &e) {
+                        if let Some(id) = find_filled_extended_attempt(self.extended_client, &self.position_id, OrderLeg::ExtendedOpen, attempt).await {
+                            warn!("Extended order reported an error but attempt {} filled: {}", id, e);
+                            self.intents.record_or_warn(&self.extended_intent(size, attempt, IntentPhase::Filled));
+                            return Ok(PlacedOrder::new(id, None, attempt, sent_at_ms));
+                        }
+                        self.intents.record_or_warn(&self.extended_intent(size, attempt, IntentPhase::Failed));
+                        error!("Extended order failed after {} attempt(s): {}", attempt, e);
+                        return Err(BotError::execution(
+                            format!("Extended order failed after {} attempt(s): {}", attempt, e),
+                            rate_limited,
+                        ));
+                    }
 
-                if attempt >= POSITION_FETCH_MAX_ATTEMPTS {
-                    return Err(Box::new(TradingError::new(
-                        format!(
-                            "Failed to fetch positions after {} attempts: {}",
-                            POSITION_FETCH_MAX_ATTEMPTS, err_msg
-                        ),
-                        looks_like_rate_limit(&err_msg),
-                    )));
+                    let delay = self.retry.delay(attempt, rate_limited);
+                    warn!(
+                        "Extended order failed (attempt {}/{}{}) : {}. Retrying in {}ms...",
+                        attempt,
+                        self.retry.max_attempts,
+                        if rate_limited { " - rate limited" } else { "" },
+                        e,
+                        delay.as_millis()
+                    );
+                    sleep(delay).await;
                 }
+            }
+        }
+        Err(BotError::Config("retry.max_attempts must be at least 1".to_string()))
+    }
+
+    /// Market order for the Pacifica open leg, retried per the retry policy
+    async fn open_pacifica(&self, pacifica_client: &mut PacificaTrading, size: f64) -> Result<PlacedOrder> {
+        let side = self.pacifica_side();
+        let contracts = self.multipliers.venue_size(Venue::Pacifica, size);
+        info!("Placing Pacifica order: {:?} {:.6} {} @ market (with {} retries)",
+            side, contracts, self.pacifica_market_symbol, self.retry.max_attempts);
+        let sent_at_ms = clock::now_ms();
+
+        for attempt in 1..=self.retry.max_attempts {
+            if attempt > 1 {
+                if let Some(id) = find_filled_pacifica_attempt(
+                    pacifica_client, self.pacifica_market_symbol, &self.position_id, OrderLeg::PacificaOpen, attempt - 1, self.started_ms,
+                ).await {
+                    info!("Earlier Pacifica attempt {} already filled, not resending", id);
+                    self.intents.record_or_warn(&self.pacifica_intent(size, attempt - 1, IntentPhase::Filled));
+                    return Ok(PlacedOrder::new(id, None, attempt - 1, sent_at_ms));
+                }
+            }
+
+            log_intent(self.intents, self.pacifica_intent(size, attempt, IntentPhase::Pending))?;
+            let client_id = client_order_id(&self.position_id, OrderLeg::PacificaOpen, attempt).to_string();
+            match pacifica_client.place_market_order_with_id(
+                self.pacifica_market_symbol,
+                side,
+                contracts,
+                self.pacifica_slippage_pct,
+                false,
+                &client_id,
+            ).await {
+                Ok(order) => {
+                    if attempt > 1 {
+                        info!("Pacifica order succeeded on attempt {}/{}", attempt, self.retry.max_attempts);
+                    }
+                    self.intents.record_or_warn(&self.pacifica_intent(size, attempt, IntentPhase::Filled));
+                    let order_id = order.order_id.or(order.i).map(|id| id.to_string());
+                    return Ok(PlacedOrder::new(client_id, order_id, attempt, sent_at_ms));
+                }
+                Err(e) => {
+                    let e = BotError::from(e);
+                    let rate_limited = e.is_rate_limited();
+                    if !self.retry.should_retry(attempt, &e) {
+                        if let Some(id) = find_filled_pacifica_attempt(
+                            pacifica_client, self.pacifica_market_symbol, &self.position_id, OrderLeg::PacificaOpen, attempt, self.started_ms,
+                        ).await {
+                            warn!("Pacifica order reported an error but attempt {} filled: {}", id, e);
+                            self.intents.record_or_warn(&self.pacifica_intent(size, attempt, IntentPhase::Filled));
+                            return Ok(PlacedOrder::new(id, None, attempt, sent_at_ms));
+                        }
+                        self.intents.record_or_warn(&self.pacifica_intent(size, attempt, IntentPhase::Failed));
+                        error!("Pacifica order failed after {} attempt(s): {}", attempt, e);
+                        return Err(e);
+                    }
+
+                    let delay = self.retry.delay(attempt, rate_limited);
+                    warn!(
+                        "Pacifica order failed (attempt {}/{}{}) : {}. Retrying in {}ms...",
+                        attempt,
+                        self.retry.max_attempts,
+                        if rate_limited { " - rate limited" } else { "" },
+                        e,
+                        delay.as_millis()
+                    );
+                    sleep(delay).await;
+                }
+            }
+        }
+        Err(BotError::Config("retry.max_attempts must be at least 1".to_string()))
+    }
+
+    /// Fill-or-kill limit order for the Extended open leg at `limit_price` per contract.
+    /// Returns the size filled (all or nothing), looking the order up when its final state
+    /// is not seen in time; fails only when whether it filled cannot be determined.
+    async fn capture_extended(&self, size: f64, limit_price: f64) -> Result<f64> {
+        let external_id = client_order_id(&self.position_id, OrderLeg::ExtendedOpen, CAPTURE_ATTEMPT).to_string();
+        let contracts = self.multipliers.venue_size(Venue::Extended, size);
+        info!("Spread capture: Extended {:?} {:.6} {} FOK @ {}", self.extended_side(), contracts, self.extended_market_symbol, limit_price);
+
+        log_intent(self.intents, self.extended_intent(size, CAPTURE_ATTEMPT, IntentPhase::Pending))?;
+        let order = LimitOrder::new(self.extended_market_symbol, self.extended_side(), contracts, limit_price)
+            .time_in_force(TimeInForce::FOK)
+            .external_id(external_id.clone());
+        let settled = match self
+            .extended_client
+            .place_limit_order(&order, self.stark_private_key, self.stark_public_key, self.vault_id)
+            .await
+        {
+            Ok(_) => {
+                let timeout = Duration::from_millis(EXTENDED_FILL_WAIT_TIMEOUT_MS);
+                self.extended_client.wait_for_fill(&external_id, timeout).await.map_err(|e| {
+                    warn!("Spread capture order on Extended not confirmed: {}", e);
+                })
+            }
+            Err(e) => {
+                // The order may still have reached the exchange
+                warn!("Spread capture order on Extended was not accepted: {}", e);
+                sleep(Duration::from_millis(CAPTURE_FILL_CHECK_DELAY_MS)).await;
+                Err(())
+            }
+        };
+        // Unconfirmed is not unfilled: whatever the lookup finds decides the hedge
+        let orders = match settled {
+            Ok(order) => vec![order],
+            Err(()) => self
+                .retry
+                .run("Check Extended spread capture fill", || async {
+                    self.extended_client.get_orders_by_external_id(&external_id).await.map_err(BotError::from)
+                })
+                .await
+                .map_err(|e| BotError::execution(format!("Cannot tell whether Extended order {} filled: {}", external_id, e), false))?,
+        };
+        let filled: f64 = orders.iter().map(|o| o.filled_qty_f64()).sum();
+        let filled = if filled <= 0.0 && orders.iter().any(|o| o.has_fill()) {
+            size
+        } else {
+            self.multipliers.base_size(Venue::Extended, filled).min(size)
+        };
+        let phase = if filled > 0.0 { IntentPhase::Filled } else { IntentPhase::Failed };
+        self.intents.record_or_warn(&self.extended_intent(size, CAPTURE_ATTEMPT, phase));
+        Ok(filled)
+    }
 
-                let rate_limited = looks_like_rate_limit(&err_msg);
-                let delay_ms = backoff_delay_ms(attempt, rate_limited);
-                warn!(
-                    "Failed to fetch positions (attempt {}/{}{}): {}. Retrying in {}ms...",
-                    attempt,
-                    POSITION_FETCH_MAX_ATTEMPTS,
-                    if rate_limited { " - rate limited" } else { "" },
-                    err_msg,
-                    delay_ms
-                );
-                sleep(Duration::from_millis(delay_ms)).await;
+    /// Immediate-or-cancel limit order for the Pacifica open leg at `limit_price` per
+    /// contract. Returns the size filled, which may be partial; fails only when the fill
+    /// cannot be determined.
+    async fn capture_pacifica(&self, pacifica_client: &mut PacificaTrading, size: f64, limit_price: f64) -> Result<f64> {
+        let client_id = client_order_id(&self.position_id, OrderLeg::PacificaOpen, CAPTURE_ATTEMPT).to_string();
+        let contracts = self.multipliers.venue_size(Venue::Pacifica, size);
+        info!("Spread capture: Pacifica {:?} {:.6} {} IOC @ {}", self.pacifica_side(), contracts, self.pacifica_market_symbol, limit_price);
+
+        log_intent(self.intents, self.pacifica_intent(size, CAPTURE_ATTEMPT, IntentPhase::Pending))?;
+        let order_id = match pacifica_client
+            .place_limit_order_with_options(
+                self.pacifica_market_symbol,
+                self.pacifica_side(),
+                contracts,
+                limit_price,
+                PacificaTimeInForce::Ioc,
+                false,
+                &client_id,
+            )
+            .await
+        {
+            Ok(order) => order.order_id.or(order.i),
+            Err(e) => {
+                // The order may still have reached the exchange
+                warn!("Spread capture order on Pacifica was not accepted: {}", e);
+                None
+            }
+        };
+
+        let fill = match self.confirm_pacifica(&client_id).await {
+            Some(fill) => fill,
+            None => self.poll_pacifica_order(pacifica_client, order_id, &client_id).await?,
+        };
+        let filled = self.pacifica_filled_base(&fill).min(size);
+        let phase = if filled > 0.0 { IntentPhase::Filled } else { IntentPhase::Failed };
+        self.intents.record_or_warn(&self.pacifica_intent(size, CAPTURE_ATTEMPT, phase));
+        Ok(filled)
+    }
+
+    /// Final outcome of a Pacifica order polled from the order history, by order ID or by
+    /// client ID when the placement response was lost. Trades for the client ID are the
+    /// fallback when the order does not show up as finished; fails when neither lookup works.
+    async fn poll_pacifica_order(&self, client: &PacificaTrading, order_id: Option<u64>, client_id: &str) -> Result<ConfirmedFill> {
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(PACIFICA_FILL_CONFIRM_TIMEOUT_MS);
+        loop {
+            match client.get_order_history(Some(ORDER_LOOKUP_TRADE_LIMIT)).await {
+                Ok(orders) => {
+                    let order = orders.iter().find(|o| {
+                        order_id == Some(o.order_id) || o.client_order_id.as_deref() == Some(client_id)
+                    });
+                    if let Some(fill) = order.and_then(|o| o.confirmed_fill()) {
+                        info!("Pacifica order {} is {}: {:.6} filled", client_id, order.map_or("", |o| o.order_status.as_str()), fill.filled_amount);
+                        return Ok(fill);
+                    }
+                }
+                Err(e) => warn!("Could not look up Pacifica order {}: {}", client_id, e),
+            }
+            if tokio::time::Instant::now() >= deadline {
+                break;
+            }
+            sleep(Duration::from_millis(CAPTURE_ORDER_POLL_INTERVAL_MS)).await;
+        }
+
+        let since_ms = (self.started_ms - ORDER_LOOKUP_LOOKBACK_MS).max(0) as u64;
+        let trades = self
+            .retry
+            .run("Check Pacifica order fills", || async {
+                client
+                    .get_trade_history(Some(self.pacifica_market_symbol), Some(ORDER_LOOKUP_TRADE_LIMIT), Some(since_ms), None)
+                    .await
+                    .map_err(BotError::from)
+            })
+            .await
+            .map_err(|e| BotError::execution(format!("Cannot tell whether Pacifica order {} filled: {}", client_id, e), false))?;
+        Ok(ConfirmedFill { filled_amount: filled_amount_for_client_id(&trades, client_id), avg_price: None })
+    }
+
+    /// Hand a leg whose rollback failed to the contingency, if any. Returns what it did,
+    /// to append to the error.
+    async fn contain_exposure(&self, venue: Venue, is_long: bool, size: f64, rollback_error: &BotError) -> String {
+        let Some(contingency) = self.contingency else {
+            return String::new();
+        };
+        let alert = contingency
+            .handle(ExposedLeg {
+                position_id: self.position_id.to_string(),
+                symbol: self.symbol.to_string(),
+                venue,
+                is_long,
+                size,
+                price: self.current_price,
+                error: rollback_error.to_string(),
+            })
+            .await;
+        format!(". Contingency: {}", alert.hedge)
+    }
+
+    /// Close the Extended open leg after the Pacifica leg failed. Always returns the
+    /// error to report: recoverable once rolled back, critical otherwise.
+    async fn rollback_extended(&self, size: f64, err_msg: &str) -> BotError {
+        error!("CRITICAL: Pacifica order failed. Initiating ROLLBACK of Extended position...");
+        match self.reduce_extended(size).await {
+            Ok(()) => {
+                self.intents.resolve(&self.position_id.to_string(), self.symbol);
+                BotError::execution(
+                    format!("Pacifica order failed. Extended position successfully rolled back (closed). Original error: {}", err_msg),
+                    true
+                )
+            }
+            Err(e) => {
+                let contained = self.contain_exposure(Venue::Extended, self.long_on_extended, size, &e).await;
+                BotError::execution(
+                    format!("Pacifica order failed AND rollback failed. CRITICAL: Check Extended position manually! Original error: {}. Rollback error: {}{}", err_msg, e, contained),
+                    false // Not recoverable automatically, needs manual intervention
+                )
+            }
+        }
+    }
+
+    /// Cut the Extended open leg down to what the Pacifica hedge filled. Whatever cannot
+    /// be cut is handed to the contingency; the hedged part stays open either way.
+    async fn trim_extended(&self, excess: f64) {
+        warn!("Trimming {:.6} {} off the Extended leg to match the Pacifica hedge", excess, self.symbol);
+        if let Err(e) = self.reduce_extended(excess).await {
+            let contained = self.contain_exposure(Venue::Extended, self.long_on_extended, excess, &e).await;
+            error!("CRITICAL: could not trim the Extended leg, {:.6} {} is unhedged: {}{}", excess, self.symbol, e, contained);
+        }
+    }
+
+    /// Reduce-only market order taking `size` off the Extended open leg, retried per the
+    /// retry policy and indefinitely while rate limited
+    async fn reduce_extended(&self, size: f64) -> Result<()> {
+        let rollback_side = if self.long_on_extended { OrderSide::Sell } else { OrderSide::Buy };
+        let rollback_intent = |attempt: u32, phase: IntentPhase| {
+            IntentRecord::order(&self.position_id, self.symbol, OrderLeg::ExtendedRollback, &rollback_side, size, attempt, phase)
+        };
+        let contracts = self.multipliers.venue_size(Venue::Extended, size);
+        info!("ROLLBACK: Placing Extended order: {:?} {:.6} {} @ market", rollback_side, contracts, self.extended_market_symbol);
+
+        // We use place_market_order directly for rollback to avoid needing a Position object
+        let mut attempt: u32 = 0;
+        loop {
+            attempt += 1;
+            if attempt > 1 {
+                if let Some(id) = find_filled_extended_attempt(self.extended_client, &self.position_id, OrderLeg::ExtendedRollback, attempt - 1).await {
+                    info!("ROLLBACK: earlier attempt {} already filled, not resending", id);
+                    self.intents.record_or_warn(&rollback_intent(attempt - 1, IntentPhase::Filled));
+                    return Ok(());
+                }
+            }
+
+            log_intent(self.intents, rollback_intent(attempt, IntentPhase::Pending))?;
+            let external_id = client_order_id(&self.position_id, OrderLeg::ExtendedRollback, attempt).to_string();
+            match self.extended_client.place_market_order_with_id(
+                self.extended_market_symbol,
+                rollback_side.clone(),
+                size * self.current_price, // Use same notional
+                self.stark_private_key,
+                self.stark_public_key,
+                self.vault_id,
+                true, // reduce_only = true
+                Some(contracts), // pass the opened contracts to ensure full close
+                self.max_slippage_pct,
+                &external_id,
+            ).await {
+                Ok(order) => {
+                    info!(
+                        "ROLLBACK SUCCESSFUL on attempt {}/{}: Extended position reduced. Order: {:?}",
+                        attempt,
+                        self.retry.max_attempts,
+                        order
+                    );
+                    self.intents.record_or_warn(&rollback_intent(attempt, IntentPhase::Filled));
+                    return Ok(());
+                }
+                Err(e) => {
+                    let e = BotError::from(e);
+                    let rate_limited = e.is_rate_limited();
+                    if !rate_limited && attempt >= self.retry.max_attempts {
+                        if find_filled_extended_attempt(self.extended_client, &self.position_id, OrderLeg::ExtendedRollback, attempt).await.is_some() {
+                            warn!("ROLLBACK order reported an error but filled: {}", e);
+                            self.intents.record_or_warn(&rollback_intent(attempt, IntentPhase::Filled));
+                            return Ok(());
+                        }
+                        self.intents.record_or_warn(&rollback_intent(attempt, IntentPhase::Failed));
+                        error!("ROLLBACK FAILED after {} attempts: {}. Extended position may be open!", self.retry.max_attempts, e);
+                        return Err(e);
+                    }
+
+                    let delay = self.retry.delay(attempt, rate_limited);
+                    warn!(
+                        "ROLLBACK Extended order failed (attempt {}{}{}) : {}. Retrying in {}ms...",
+                        attempt,
+                        if !rate_limited { format!("/{}", self.retry.max_attempts) } else { String::new() },
+                        if rate_limited { " - rate limited, will keep retrying" } else { "" },
+                        e,
+                        delay.as_millis()
+                    );
+                    sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// Close the Pacifica open leg after the Extended leg failed (Pacifica-first opens).
+    /// Always returns the error to report: recoverable once rolled back, critical otherwise.
+    async fn rollback_pacifica(&self, pacifica_client: &mut PacificaTrading, size: f64, err_msg: &str) -> BotError {
+        let rollback_side = if self.long_on_extended { PacificaOrderSide::Buy } else { PacificaOrderSide::Sell };
+        let rollback_intent = |attempt: u32, phase: IntentPhase| {
+            IntentRecord::order(&self.position_id, self.symbol, OrderLeg::PacificaRollback, rollback_side, size, attempt, phase)
+        };
+        let rolled_back = |attempt: u32| {
+            self.intents.record_or_warn(&rollback_intent(attempt, IntentPhase::Filled));
+            self.intents.resolve(&self.position_id.to_string(), self.symbol);
+            BotError::execution(
+                format!("Extended order failed. Pacifica position successfully rolled back (closed). Original error: {}", err_msg),
+                true
+            )
+        };
+
+        error!("CRITICAL: Extended order failed. Initiating ROLLBACK of Pacifica position...");
+        let contracts = self.multipliers.venue_size(Venue::Pacifica, size);
+        info!("ROLLBACK: Placing Pacifica order: {:?} {:.6} {} @ market", rollback_side, contracts, self.pacifica_market_symbol);
+
+        let mut attempt: u32 = 0;
+        loop {
+            attempt += 1;
+            if attempt > 1 {
+                if let Some(id) = find_filled_pacifica_attempt(
+                    pacifica_client, self.pacifica_market_symbol, &self.position_id, OrderLeg::PacificaRollback, attempt - 1, self.started_ms,
+                ).await {
+                    info!("ROLLBACK: earlier attempt {} already filled, not resending", id);
+                    return rolled_back(attempt - 1);
+                }
+            }
+
+            if let Err(e) = log_intent(self.intents, rollback_intent(attempt, IntentPhase::Pending)) {
+                return e;
+            }
+            let client_id = client_order_id(&self.position_id, OrderLeg::PacificaRollback, attempt).to_string();
+            match pacifica_client.place_market_order_with_id(
+                self.pacifica_market_symbol,
+                rollback_side,
+                contracts,
+                self.max_slippage_pct,
+                true, // reduce_only = true
+                &client_id,
+            ).await {
+                Ok(order) => {
+                    info!(
+                        "ROLLBACK SUCCESSFUL on attempt {}/{}: Pacifica position closed. Order: {:?}",
+                        attempt,
+                        self.retry.max_attempts,
+                        order
+                    );
+                    return rolled_back(attempt);
+                }
+                Err(e) => {
+                    let e = BotError::from(e);
+                    let rate_limited = e.is_rate_limited();
+                    if !rate_limited && attempt >= self.retry.max_attempts {
+                        if find_filled_pacifica_attempt(
+                            pacifica_client, self.pacifica_market_symbol, &self.position_id, OrderLeg::PacificaRollback, attempt, self.started_ms,
+                        ).await.is_some() {
+                            warn!("ROLLBACK order reported an error but filled: {}", e);
+                            return rolled_back(attempt);
+                        }
+                        self.intents.record_or_warn(&rollback_intent(attempt, IntentPhase::Failed));
+                        error!("ROLLBACK FAILED after {} attempts: {}. Pacifica position may be open!", self.retry.max_attempts, e);
+                        let contained = self.contain_exposure(Venue::Pacifica, !self.long_on_extended, size, &e).await;
+                        return BotError::execution(
+                            format!("Extended order failed AND rollback failed. CRITICAL: Check Pacifica position manually! Original error: {}. Rollback error: {}{}", err_msg, e, contained),
+                            false // Not recoverable automatically, needs manual intervention
+                        );
+                    }
+
+                    let delay = self.retry.delay(attempt, rate_limited);
+                    warn!(
+                        "ROLLBACK Pacifica order failed (attempt {}{}{}) : {}. Retrying in {}ms...",
+                        attempt,
+                        if !rate_limited { format!("/{}", self.retry.max_attempts) } else { String::new() },
+                        if rate_limited { " - rate limited, will keep retrying" } else { "" },
+                        e,
+                        delay.as_millis()
+                    );
+                    sleep(delay).await;
+                }
             }
         }
     }
@@ -199,7 +1690,27 @@ async fn fetch_opened_positions_with_backoff(
 /// - Long on exchange with higher funding rate (receiving funding)
 /// - Short on exchange with lower funding rate (paying less funding)
 ///
-/// Returns the opened positions if successful
+/// The legs are sent in `sequence`: one venue first and the other hedging it, or both at
+/// once with the leg that went through rolled back if the other failed. When `capture`
+/// plans a spread capture, its leading leg goes first regardless, as a limit order that
+/// falls back to a market order if the difference is gone by the time it arrives.
+///
+/// Pacifica fills are confirmed from its order updates when the subscription is up, and
+/// from its order or trade history otherwise: a Pacifica lead that fills partially is
+/// hedged with the filled size only, a Pacifica hedge that fills partially has Extended
+/// trimmed to match, and one that fills nothing aborts the open, rolling Extended back
+/// if it was already opened.
+///
+/// If the leading leg cannot be rolled back either, it is handed to `contingency` (backup
+/// hedge and critical alert) before the error is returned.
+///
+/// A Pacifica market leg may slip as far as walking its book for the size is expected to
+/// cost plus `slippage_margin_pct`, at most `max_slippage_pct`; rollbacks get the full
+/// `max_slippage_pct`.
+///
+/// Returns the opened positions and how each leg executed. Slippage in the report is
+/// measured against the mid of `quotes`, or `current_price` where a quote is missing.
+#[allow(clippy::too_many_arguments)]
 pub async fn open_delta_neutral_position(
     symbol: &str,
     long_on_extended: bool,
@@ -209,10 +1720,19 @@ pub async fn open_delta_neutral_position(
     pacifica_client: &mut PacificaTrading,
     extended_market_symbol: &str,  // e.g., "BTC-USD"
     pacifica_market_symbol: &str,   // e.g., "BTC"
+    multipliers: ContractMultipliers,
     stark_private_key: &str,
     stark_public_key: &str,
     vault_id: &str,
-) -> Result<DeltaNeutralPosition> {
+    max_slippage_pct: f64,
+    slippage_margin_pct: f64,
+    retry: &RetryPolicy,
+    intents: &IntentLog,
+    quotes: &TopOfBookQuotes,
+    sequence: LegSequence,
+    capture: Option<&SpreadCapture>,
+    contingency: Option<&Contingency>,
+) -> Result<(DeltaNeutralPosition, ExecutionReport)> {
     info!("Opening delta neutral position for {}", symbol);
     info!("Strategy: {} Extended / {} Pacifica",
         if long_on_extended { "Long" } else { "Short" },
@@ -220,291 +1740,1172 @@ pub async fn open_delta_neutral_position(
     );
 
     if position_size_base <= 0.0 {
-        return Err(Box::new(TradingError::new(
+        return Err(BotError::execution(
             "Invalid position size".to_string(),
             false,
-        )));
+        ));
     }
 
-    let notional_usd = position_size_base * current_price;
-    info!("Opening position: {:.6} {} (${:.2})", position_size_base, symbol, notional_usd);
+    let notional_usd = position_size_base * current_price;
+    info!("Opening position: {:.6} {} (${:.2})", position_size_base, symbol, notional_usd);
+
+    // Step 0: Pre-trade slippage check against current book depth on both venues
+    let [_, pacifica_expected_pct] = check_slippage_budget(
+        extended_client,
+        pacifica_client,
+        extended_market_symbol,
+        pacifica_market_symbol,
+        long_on_extended,
+        [Venue::Extended, Venue::Pacifica].map(|venue| multipliers.venue_size(venue, position_size_base)),
+        max_slippage_pct,
+        quotes,
+    ).await?;
+    let pacifica_slippage_pct = adaptive_slippage_tolerance_pct(pacifica_expected_pct, slippage_margin_pct, max_slippage_pct);
+    info!("Pacifica slippage tolerance {:.4}%", pacifica_slippage_pct);
+
+    // Subscribe before any order is sent so no Pacifica order update is missed
+    let fill_watcher = start_fill_watcher(pacifica_client).await;
+    let legs = OpenLegs {
+        position_id: Uuid::new_v4(),
+        symbol,
+        long_on_extended,
+        current_price,
+        extended_client,
+        extended_market_symbol,
+        pacifica_market_symbol,
+        multipliers,
+        stark_private_key,
+        stark_public_key,
+        vault_id,
+        max_slippage_pct,
+        pacifica_slippage_pct,
+        retry,
+        intents,
+        started_ms: clock::now_ms(),
+        fill_watcher: fill_watcher.as_ref(),
+        contingency,
+    };
+    // A spread-capture plan decides the lead: its limit order must go out first
+    let sequence = match capture {
+        Some(plan) if plan.pacifica_first => LegSequence::PacificaFirst,
+        Some(_) => LegSequence::ExtendedFirst,
+        None => sequence,
+    };
+    let pacifica_first = sequence == LegSequence::PacificaFirst;
+    let mut size = position_size_base;
+    let mut pacifica_fill = None;
+    let mut captured = false;
+
+    let (extended_order, pacifica_order, extended_requested, pacifica_requested) = if sequence == LegSequence::Concurrent {
+        // Steps 1-2 at once: both legs at market, then reconcile what each did
+        info!("Sending both legs concurrently");
+        let (extended, pacifica) = tokio::join!(legs.open_extended(size), legs.open_pacifica(pacifica_client, size));
+        match (extended, pacifica) {
+            (Ok(extended_order), Ok(pacifica_order)) => {
+                info!("Extended order placed: {}", extended_order.client_order_id);
+                info!("Pacifica order placed: {}", pacifica_order.client_order_id);
+                pacifica_fill = legs.pacifica_outcome(pacifica_client, &pacifica_order).await;
+                match pacifica_fill {
+                    Some(fill) if fill.filled_amount <= 0.0 => {
+                        intents.record_or_warn(&legs.pacifica_intent(size, pacifica_order.attempt, IntentPhase::Failed));
+                        let err_msg = format!("Pacifica order {} was cancelled without filling", pacifica_order.client_order_id);
+                        return Err(legs.rollback_extended(size, &err_msg).await);
+                    }
+                    // Bring Extended down to what Pacifica filled, as after a sequential hedge
+                    Some(fill) if is_partial_fill(&fill, multipliers.venue_size(Venue::Pacifica, size)) => {
+                        let filled = legs.pacifica_filled_base(&fill);
+                        warn!("Pacifica filled {:.6} of {:.6} {}", filled, size, symbol);
+                        legs.trim_extended(size - filled).await;
+                        size = filled;
+                    }
+                    _ => {}
+                }
+                (extended_order, pacifica_order, position_size_base, position_size_base)
+            }
+            (Ok(_), Err(e)) => return Err(legs.rollback_extended(size, &e.to_string()).await),
+            (Err(e), Ok(pacifica_order)) => {
+                // Only what Pacifica filled needs unwinding
+                let filled = legs.pacifica_outcome(pacifica_client, &pacifica_order).await.map(|fill| legs.pacifica_filled_base(&fill));
+                if filled.is_some_and(|filled| filled <= 0.0) {
+                    intents.record_or_warn(&legs.pacifica_intent(size, pacifica_order.attempt, IntentPhase::Failed));
+                    intents.resolve(&legs.position_id.to_string(), symbol);
+                    return Err(e);
+                }
+                return Err(legs.rollback_pacifica(pacifica_client, filled.unwrap_or(size), &e.to_string()).await);
+            }
+            (Err(e), Err(pacifica_err)) => {
+                warn!("Pacifica leg failed as well: {}", pacifica_err);
+                intents.resolve(&legs.position_id.to_string(), symbol);
+                return Err(e);
+            }
+        }
+    } else {
+        // Step 1: Leading leg, first as a spread-capturing limit order when planned
+        let lead_sent_ms = clock::now_ms();
+        if let Some(plan) = capture {
+            info!("Favorable cross-venue spread {:.3}% (long ask {} / short bid {}): leading with {}",
+                plan.favorable_spread_pct, plan.long_ask, plan.short_bid,
+                if plan.pacifica_first { "Pacifica" } else { "Extended" });
+            let filled = if plan.pacifica_first {
+                match legs.capture_pacifica(pacifica_client, size, plan.lead_limit_price).await {
+                    Ok(filled) => filled,
+                    // Whatever the order filled must not be left unhedged
+                    Err(e) => return Err(legs.rollback_pacifica(pacifica_client, size, &e.to_string()).await),
+                }
+            } else {
+                match legs.capture_extended(size, plan.lead_limit_price).await {
+                    Ok(filled) => filled,
+                    Err(e) => return Err(legs.rollback_extended(size, &e.to_string()).await),
+                }
+            };
+            if filled > 0.0 {
+                if filled < size {
+                    warn!("Spread capture filled {:.6} of {:.6} {}; hedging the filled size only", filled, size, symbol);
+                    size = filled;
+                }
+                captured = true;
+            } else {
+                info!("Spread capture missed (price moved); opening the leading leg at market");
+            }
+        }
+
+        let lead = if captured {
+            let lead_leg = if pacifica_first { OrderLeg::PacificaOpen } else { OrderLeg::ExtendedOpen };
+            let id = client_order_id(&legs.position_id, lead_leg, CAPTURE_ATTEMPT).to_string();
+            Ok(PlacedOrder::new(id, None, CAPTURE_ATTEMPT, lead_sent_ms))
+        } else if pacifica_first {
+            legs.open_pacifica(pacifica_client, size).await
+        } else {
+            legs.open_extended(size).await
+        };
+        let lead_order = match lead {
+            Ok(order) => order,
+            Err(e) => {
+                // Nothing was opened: the execution is settled
+                intents.resolve(&legs.position_id.to_string(), symbol);
+                return Err(e);
+            }
+        };
+        info!("{} order placed: {}", if pacifica_first { "Pacifica" } else { "Extended" }, lead_order.client_order_id);
+
+        // A Pacifica market order can end partially filled or unfilled: hedge what it filled
+        if pacifica_first && !captured {
+            pacifica_fill = legs.pacifica_outcome(pacifica_client, &lead_order).await;
+            if let Some(fill) = pacifica_fill {
+                if fill.filled_amount <= 0.0 {
+                    intents.record_or_warn(&legs.pacifica_intent(size, lead_order.attempt, IntentPhase::Failed));
+                    intents.resolve(&legs.position_id.to_string(), symbol);
+                    return Err(BotError::execution(
+                        format!("Pacifica order {} was cancelled without filling", lead_order.client_order_id),
+                        true,
+                    ));
+                }
+                if is_partial_fill(&fill, multipliers.venue_size(Venue::Pacifica, size)) {
+                    let filled = legs.pacifica_filled_base(&fill);
+                    warn!("Pacifica filled {:.6} of {:.6} {}; hedging the filled size only", filled, size, symbol);
+                    size = filled;
+                }
+            }
+        }
+
+        // Step 2: Hedge leg at market; roll the leading leg back if it cannot be placed
+        let hedge = if pacifica_first {
+            legs.open_extended(size).await
+        } else {
+            legs.open_pacifica(pacifica_client, size).await
+        };
+        let hedge_order = match hedge {
+            Ok(order) => order,
+            Err(e) => {
+                let err_msg = e.to_string();
+                return Err(if pacifica_first {
+                    legs.rollback_pacifica(pacifica_client, size, &err_msg).await
+                } else {
+                    legs.rollback_extended(size, &err_msg).await
+                });
+            }
+        };
+        info!("{} order placed: {}", if pacifica_first { "Extended" } else { "Pacifica" }, hedge_order.client_order_id);
+        let hedge_size = size;
+        if !pacifica_first {
+            pacifica_fill = legs.pacifica_outcome(pacifica_client, &hedge_order).await;
+            match pacifica_fill {
+                Some(fill) if fill.filled_amount <= 0.0 => {
+                    intents.record_or_warn(&legs.pacifica_intent(size, hedge_order.attempt, IntentPhase::Failed));
+                    let err_msg = format!("Pacifica order {} was cancelled without filling", hedge_order.client_order_id);
+                    return Err(legs.rollback_extended(size, &err_msg).await);
+                }
+                // Bring Extended down to the hedged size before calling the open a success
+                Some(fill) if is_partial_fill(&fill, multipliers.venue_size(Venue::Pacifica, size)) => {
+                    let filled = legs.pacifica_filled_base(&fill);
+                    warn!("Pacifica hedge filled {:.6} of {:.6} {}", filled, size, symbol);
+                    legs.trim_extended(size - filled).await;
+                    size = filled;
+                }
+                _ => {}
+            }
+        }
+
+        if pacifica_first {
+            (hedge_order, lead_order, size, position_size_base)
+        } else {
+            (lead_order, hedge_order, position_size_base, hedge_size)
+        }
+    };
+    let completed_at_ms = clock::now_ms();
+
+    // Step 3: Look up fills for the execution report, in contracts and prices per contract
+    let mut extended_leg = extended_order.execution(
+        Venue::Extended,
+        OrderLeg::ExtendedOpen,
+        long_on_extended,
+        multipliers.venue_size(Venue::Extended, extended_requested),
+        quote_mid(quotes.extended.as_ref()).or(Some(current_price * multipliers.extended)),
+    );
+    let mut pacifica_leg = pacifica_order.execution(
+        Venue::Pacifica,
+        OrderLeg::PacificaOpen,
+        !long_on_extended,
+        multipliers.venue_size(Venue::Pacifica, pacifica_requested),
+        quote_mid(quotes.pacifica.as_ref()).or(Some(current_price * multipliers.pacifica)),
+    );
+    if !captured || !pacifica_first {
+        pacifica_leg.slippage_tolerance_pct = Some(pacifica_slippage_pct);
+    }
+    if let Some(fill) = pacifica_fill {
+        pacifica_leg.filled_size = Some(fill.filled_amount);
+        pacifica_leg.avg_fill_price = fill.avg_price;
+    }
+    lookup_extended_fill(extended_client, &mut extended_leg).await;
+    lookup_pacifica_fill(pacifica_client, pacifica_market_symbol, &mut pacifica_leg).await;
+    let report = ExecutionReport {
+        position_id: legs.position_id.to_string(),
+        symbol: symbol.to_string(),
+        kind: ExecutionKind::Open,
+        started_at_ms: legs.started_ms,
+        completed_at_ms,
+        legs: vec![extended_leg, pacifica_leg],
+        sequence: Some(sequence),
+    };
+    info!("Execution report: {}", report);
+
+    // Step 4: Fetch opened positions
+    let (extended_position, pacifica_position) = match fetch_opened_positions_with_backoff(
+        extended_client,
+        pacifica_client,
+        extended_market_symbol,
+        pacifica_market_symbol,
+        retry,
+    ).await {
+        Ok((ext, pac)) => (ext, pac),
+        Err(e) => {
+            warn!("Failed to fetch positions after opening orders: {}. Saving state with unknown legs.", e);
+            (None, None)
+        }
+    };
+
+    let opened_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+
+    info!("✅ Delta neutral position opened successfully");
+
+    let mut position = DeltaNeutralPosition {
+        position_id: legs.position_id.to_string(),
+        symbol: symbol.to_string(),
+        extended_position,
+        pacifica_position,
+        opened_at,
+        target_notional_usd: size * current_price,
+        realized_funding_extended_usd: 0.0,
+        realized_funding_pacifica_usd: 0.0,
+        entry_net_apr: None,
+        extended_entry_price: None,
+        pacifica_entry_price: None,
+        entry_basis_pct: None,
+        spread_capture: None,
+        captured_basis_usd: None,
+        open_execution: None,
+        extended_vault_id: Some(vault_id.to_string()),
+        direction: Some(Direction::from_long_on_extended(long_on_extended)),
+        reductions: 0,
+        protective_generation: None,
+        hold_restarted_at: None,
+        funding_average: FundingRateAverage::default(),
+        extended_market: Some(extended_market_symbol.to_string()),
+        pacifica_market: Some(pacifica_market_symbol.to_string()),
+        multipliers,
+    };
+    position.record_entry_prices();
+    if captured {
+        position.spread_capture = capture.copied();
+        position.captured_basis_usd = position.entry_basis_usd();
+        if let Some(usd) = position.captured_basis_usd {
+            info!("Captured ${:.4} of cross-venue basis at entry", usd);
+        }
+    }
+    Ok((position, report))
+}
+
+/// Close a delta neutral position
+///
+/// Returns how each close order executed, with slippage measured against the mid of
+/// `quotes` (unknown where a quote is missing).
+#[allow(clippy::too_many_arguments)]
+pub async fn close_delta_neutral_position(
+    position: &DeltaNeutralPosition,
+    extended_client: &RestClient,
+    pacifica_client: &mut PacificaTrading,
+    stark_private_key: &str,
+    stark_public_key: &str,
+    vault_id: &str,
+    retry: &RetryPolicy,
+    intents: &IntentLog,
+    quotes: &TopOfBookQuotes,
+) -> Result<ExecutionReport> {
+    info!("Closing delta neutral position for {}", position.symbol);
+
+    let position_id = position_uuid(position);
+    let started_ms = clock::now_ms();
+
+    // Close both legs at once: one after the other, the second leg stays unhedged for
+    // as long as the first takes, retries included. Residuals are handled below.
+    let extended_close = async {
+        match &position.extended_position {
+            Some(ext_pos) => {
+                info!("Closing Extended position: {} {:?}", ext_pos.market, ext_pos.side);
+                Some(close_extended_leg(
+                    &position_id, OrderLeg::ExtendedClose, &position.symbol, ext_pos, extended_client,
+                    stark_private_key, stark_public_key, vault_id, retry, intents,
+                ).await)
+            }
+            None => None,
+        }
+    };
+    let pacifica_close = async {
+        match &position.pacifica_position {
+            Some(pac_pos) => {
+                info!("Closing Pacifica position: {} (size: {})", pac_pos.symbol, pac_pos.size());
+                Some(close_pacifica_leg(&position_id, OrderLeg::PacificaClose, &position.symbol, pac_pos, pacifica_client, started_ms, retry, intents).await)
+            }
+            None => None,
+        }
+    };
+    let (extended_closed, pacifica_closed) = tokio::join!(extended_close, pacifica_close);
+
+    // Settle both legs before reporting, so a refusal on one never hides the other's order
+    let mut errors = Vec::new();
+    let mut closed_venues = Vec::new();
+    let mut refused = false;
+    let mut settle = |venue: Venue, closed: Option<Result<Result<PlacedOrder>>>| match closed? {
+        Ok(Ok(placed)) => {
+            closed_venues.push(venue.to_string());
+            Some(placed)
+        }
+        Ok(Err(e)) => {
+            errors.push(format!("{}: {}", venue, e));
+            None
+        }
+        Err(e) => {
+            refused = true;
+            errors.push(format!("{}: {}", venue, e));
+            None
+        }
+    };
+    let extended_placed = settle(Venue::Extended, extended_closed);
+    let pacifica_placed = settle(Venue::Pacifica, pacifica_closed);
+    let mut legs = Vec::new();
+    if let (Some(ext_pos), Some(placed)) = (&position.extended_position, extended_placed) {
+        let decision_price = quote_mid(quotes.extended.as_ref());
+        legs.push(placed.execution(Venue::Extended, OrderLeg::ExtendedClose, !ext_pos.is_long(), ext_pos.size_f64().abs(), decision_price));
+    }
+    if let (Some(pac_pos), Some(placed)) = (&position.pacifica_position, pacifica_placed) {
+        let decision_price = quote_mid(quotes.pacifica.as_ref());
+        legs.push(placed.execution(Venue::Pacifica, OrderLeg::PacificaClose, !pac_pos.is_long(), pac_pos.size().abs(), decision_price));
+    }
+    let completed_at_ms = clock::now_ms();
+
+    if !errors.is_empty() {
+        let closed = if closed_venues.is_empty() { String::new() } else { format!(" (closed: {})", closed_venues.join(", ")) };
+        return Err(BotError::execution(
+            format!("Failed to close some positions: {}{}", errors.join(", "), closed),
+            !refused,
+        ));
+    }
+
+    verify_position_closed(
+        position,
+        extended_client,
+        pacifica_client,
+        stark_private_key,
+        stark_public_key,
+        vault_id,
+        retry,
+    ).await?;
+
+    for leg in &mut legs {
+        match leg.venue {
+            Venue::Extended => lookup_extended_fill(extended_client, leg).await,
+            Venue::Pacifica => {
+                lookup_pacifica_fill(pacifica_client, &position.pacifica_market_name(), leg).await;
+            }
+        }
+    }
+    let report = ExecutionReport {
+        position_id: position_id.to_string(),
+        symbol: position.symbol.clone(),
+        kind: ExecutionKind::Close,
+        started_at_ms: started_ms,
+        completed_at_ms,
+        legs,
+        sequence: None,
+    };
+    info!("Execution report: {}", report);
+
+    info!("✅ Delta neutral position closed successfully");
+    Ok(report)
+}
+
+/// Base size to take off each leg when reducing a position whose legs hold `leg_size`
+/// by `fraction` (between 0 and 1). Rounded and checked as in `validate_order_size`,
+/// so both legs shrink by the same whole number of lots; at least one coarser lot must
+/// remain, otherwise the position should be closed in full.
+pub fn partial_close_size(leg_size: f64, fraction: f64, price: f64, limits: &[OrderSizeLimits]) -> std::result::Result<f64, String> {
+    if !(fraction > 0.0 && fraction < 1.0) {
+        return Err(format!("Fraction to close must be strictly between 0 and 1, got {}", fraction));
+    }
+    let leg_size = leg_size.abs();
+    let size = validate_order_size(leg_size * fraction, price, limits)?;
+    let coarser_lot = limits.iter().map(|l| l.lot_size).fold(0.0, f64::max);
+    if !is_residual(leg_size - size, coarser_lot) {
+        return Err(format!(
+            "Closing {} of {} would leave less than one lot ({}); close the whole position instead",
+            size, leg_size, coarser_lot
+        ));
+    }
+    Ok(size)
+}
+
+/// `size` string of a leg after taking `reduce` base units off it, keeping its sign
+fn reduced_size(size: &str, reduce: Decimal) -> String {
+    let size = parse_decimal_or_zero(size);
+    let remaining = if size.is_sign_negative() { size + reduce } else { size - reduce };
+    remaining.normalize().to_string()
+}
+
+/// Close `fraction` of both legs of `position` with reduce-only market orders sent
+/// concurrently, e.g. to de-risk into a volatility spike or free collateral without a
+/// full rotation. The base size is the same on both legs and lot-rounded for both venues
+/// (`partial_close_size`). `position.reductions` numbers this reduction: the caller
+/// increments and saves it before calling, so a reduction cut short by a crash never
+/// hands its order IDs to the next one. Returns the position with its remaining sizes
+/// and notional, and the execution report; the caller resolves the intents logged under
+/// `reduction_id(position, reductions)` once the position is saved.
+#[allow(clippy::too_many_arguments)]
+pub async fn close_partial(
+    position: &DeltaNeutralPosition,
+    fraction: f64,
+    extended_client: &RestClient,
+    pacifica_client: &mut PacificaTrading,
+    stark_private_key: &str,
+    stark_public_key: &str,
+    vault_id: &str,
+    retry: &RetryPolicy,
+    intents: &IntentLog,
+    quotes: &TopOfBookQuotes,
+) -> Result<(DeltaNeutralPosition, ExecutionReport)> {
+    let (Some(ext_pos), Some(pac_pos)) = (&position.extended_position, &position.pacifica_position) else {
+        return Err(BotError::execution(
+            format!("Cannot partially close {}: a leg is missing, close the position instead", position.symbol),
+            false,
+        ));
+    };
+
+    let extended_market = position.extended_market_name();
+    let extended_config = extended_client.get_market_config(&extended_market).await?;
+    let pacifica_markets = pacifica_client.get_market_info().await?;
+    let pacifica_info = pacifica_markets
+        .get(&pac_pos.symbol)
+        .ok_or_else(|| BotError::Config(format!("Pacifica market {} not found", pac_pos.symbol)))?;
+    // Sized in canonical base units, then taken off each leg in its venue's contracts
+    let multipliers = position.multipliers;
+    let limits = [
+        OrderSizeLimits::extended(&extended_config.trading_config).map_err(BotError::Config)?.with_multiplier(multipliers.extended),
+        OrderSizeLimits::pacifica(pacifica_info).map_err(BotError::Config)?.with_multiplier(multipliers.pacifica),
+    ];
+    let price = quote_mid(quotes.extended.as_ref())
+        .or(position.extended_entry_price)
+        .map(|price| multipliers.base_price(Venue::Extended, price))
+        .ok_or_else(|| BotError::execution(format!("No price for {} to size the partial close", position.symbol), true))?;
+    let leg_size = position.hedged_base_size().unwrap_or_default();
+    let size = partial_close_size(leg_size, fraction, price, &limits).map_err(|e| BotError::execution(e, false))?;
+    let contracts = |venue| decimal_from_f64(size) / decimal_from_f64(multipliers.of(venue));
+    let (ext_reduce, pac_reduce) = (contracts(Venue::Extended), contracts(Venue::Pacifica));
+    let (ext_reduce_f64, pac_reduce_f64) = (decimal_to_f64(ext_reduce), decimal_to_f64(pac_reduce));
+    // Share of the Extended leg's value that is closed
+    let ext_reduce_share = ext_reduce.checked_div(ext_pos.size_decimal().abs()).unwrap_or_default();
+    info!("Closing {:.0}% of {}: {} of {} on each leg", fraction * 100.0, position.symbol, size, leg_size);
+
+    let ext_part = Position {
+        size: ext_reduce.normalize().to_string(),
+        value: (ext_pos.value_decimal().abs() * ext_reduce_share).round_dp(2).to_string(),
+        ..ext_pos.clone()
+    };
+    let pac_part = PacificaPosition { amount: pac_reduce.normalize().to_string(), ..pac_pos.clone() };
+
+    let reductions = position.reductions.max(1);
+    let reduction = reduction_id(&position_uuid(position), reductions);
+    let started_ms = clock::now_ms();
+    let (extended_closed, pacifica_closed) = tokio::join!(
+        close_extended_leg(
+            &reduction, OrderLeg::ExtendedReduce, &position.symbol, &ext_part, extended_client,
+            stark_private_key, stark_public_key, vault_id, retry, intents,
+        ),
+        close_pacifica_leg(&reduction, OrderLeg::PacificaReduce, &position.symbol, &pac_part, pacifica_client, started_ms, retry, intents),
+    );
+    let completed_at_ms = clock::now_ms();
+
+    let mut errors = Vec::new();
+    let mut legs = Vec::new();
+    match extended_closed? {
+        Ok(placed) => legs.push(placed.execution(
+            Venue::Extended, OrderLeg::ExtendedReduce, !ext_pos.is_long(), ext_reduce_f64, quote_mid(quotes.extended.as_ref()),
+        )),
+        Err(e) => errors.push(format!("Extended: {}", e)),
+    }
+    match pacifica_closed? {
+        Ok(placed) => legs.push(placed.execution(
+            Venue::Pacifica, OrderLeg::PacificaReduce, !pac_pos.is_long(), pac_reduce_f64, quote_mid(quotes.pacifica.as_ref()),
+        )),
+        Err(e) => errors.push(format!("Pacifica: {}", e)),
+    }
+    if !errors.is_empty() {
+        // One leg may have been reduced without the other; reconciliation picks up the sizes
+        return Err(BotError::execution(
+            format!("Failed to partially close {}: {}", position.symbol, errors.join(", ")),
+            true,
+        ));
+    }
+
+    for leg in &mut legs {
+        match leg.venue {
+            Venue::Extended => lookup_extended_fill(extended_client, leg).await,
+            Venue::Pacifica => lookup_pacifica_fill(pacifica_client, &pac_pos.symbol, leg).await,
+        }
+    }
+    let report = ExecutionReport {
+        position_id: reduction.to_string(),
+        symbol: position.symbol.clone(),
+        kind: ExecutionKind::Reduce,
+        started_at_ms: started_ms,
+        completed_at_ms,
+        legs,
+        sequence: None,
+    };
+    info!("Execution report: {}", report);
+
+    let remaining_share = 1.0 - size / leg_size;
+    let remaining = DeltaNeutralPosition {
+        extended_position: Some(Position {
+            size: reduced_size(&ext_pos.size, ext_reduce),
+            value: (ext_pos.value_decimal() * (Decimal::ONE - ext_reduce_share)).round_dp(2).to_string(),
+            ..ext_pos.clone()
+        }),
+        pacifica_position: Some(PacificaPosition { amount: reduced_size(&pac_pos.amount, pac_reduce), ..pac_pos.clone() }),
+        target_notional_usd: position.target_notional_usd * remaining_share,
+        reductions,
+        ..position.clone()
+    };
+    info!("✅ Reduced {} position by {}", position.symbol, size);
+    Ok((remaining, report))
+}
+
+/// Send a reduce-only Extended order for all of `ext_pos`, retried per `retry`. The
+/// outer error refuses to send (intent log not writable); the inner one is an order
+/// that failed for good.
+#[allow(clippy::too_many_arguments)]
+async fn close_extended_leg(
+    position_id: &Uuid,
+    leg: OrderLeg,
+    symbol: &str,
+    ext_pos: &Position,
+    extended_client: &RestClient,
+    stark_private_key: &str,
+    stark_public_key: &str,
+    vault_id: &str,
+    retry: &RetryPolicy,
+    intents: &IntentLog,
+) -> Result<Result<PlacedOrder>> {
+    let close_side = if ext_pos.is_long() { OrderSide::Sell } else { OrderSide::Buy };
+    let close_intent = |attempt: u32, phase: IntentPhase| {
+        IntentRecord::order(position_id, symbol, leg, &close_side, ext_pos.size_f64(), attempt, phase)
+    };
+
+    let sent_at_ms = clock::now_ms();
 
-    // Step 1: Place first order (Extended)
-    let extended_side = if long_on_extended { OrderSide::Buy } else { OrderSide::Sell };
-    info!("Placing Extended order: {:?} {:.6} {} @ market", extended_side, position_size_base, symbol);
+    // Retry logic for closing Extended position
+    for attempt in 1..=retry.max_attempts {
+        if attempt > 1 {
+            if let Some(id) = find_filled_extended_attempt(extended_client, position_id, leg, attempt - 1).await {
+                info!("Earlier Extended close attempt {} already filled, not resending", id);
+                intents.record_or_warn(&close_intent(attempt - 1, IntentPhase::Filled));
+                return Ok(Ok(PlacedOrder::new(id, None, attempt - 1, sent_at_ms)));
+            }
+        }
 
-    let mut extended_order = None;
-    for attempt in 1..=ORDER_MAX_ATTEMPTS {
-        match extended_client.place_market_order(
-            extended_market_symbol,
-            extended_side.clone(),
-            notional_usd,
+        log_intent(intents, close_intent(attempt, IntentPhase::Pending))?;
+        let external_id = client_order_id(position_id, leg, attempt).to_string();
+        match extended_client.close_position_with_id(
+            ext_pos,
             stark_private_key,
             stark_public_key,
             vault_id,
-            false, // reduce_only = false (opening position)
-            Some(position_size_base), // pass desired base to match targeted size
+            &external_id,
         ).await {
             Ok(order) => {
                 if attempt > 1 {
-                    info!("Extended order succeeded on attempt {}/{}", attempt, ORDER_MAX_ATTEMPTS);
+                    info!("Close Extended position succeeded on attempt {}/{}", attempt, retry.max_attempts);
                 }
-                extended_order = Some(order);
-                break;
+                info!("Extended position closed: {:?}", order);
+                intents.record_or_warn(&close_intent(attempt, IntentPhase::Filled));
+                return Ok(Ok(PlacedOrder::new(external_id, Some(order.id.to_string()), attempt, sent_at_ms)));
             }
             Err(e) => {
-                let rate_limited = looks_like_rate_limit(&e.to_string());
-                if attempt >= ORDER_MAX_ATTEMPTS {
-                    return Err(Box::new(TradingError::new(
-                        format!("Extended order failed after {} attempts: {}", ORDER_MAX_ATTEMPTS, e),
-                        rate_limited,
-                    )));
+                let e = BotError::from(e);
+                if !retry.should_retry(attempt, &e) {
+                    if let Some(id) = find_filled_extended_attempt(extended_client, position_id, leg, attempt).await {
+                        warn!("Close Extended position reported an error but filled: {}", e);
+                        intents.record_or_warn(&close_intent(attempt, IntentPhase::Filled));
+                        return Ok(Ok(PlacedOrder::new(id, None, attempt, sent_at_ms)));
+                    }
+                    intents.record_or_warn(&close_intent(attempt, IntentPhase::Failed));
+                    error!("Failed to close Extended position after {} attempt(s): {}", attempt, e);
+                    return Ok(Err(e));
                 }
-
-                let delay_ms = backoff_delay_ms(attempt, rate_limited);
-                warn!(
-                    "Extended order failed (attempt {}/{}{}) : {}. Retrying in {}ms...",
-                    attempt,
-                    ORDER_MAX_ATTEMPTS,
-                    if rate_limited { " - rate limited" } else { "" },
-                    e,
-                    delay_ms
-                );
-                sleep(Duration::from_millis(delay_ms)).await;
+                let delay = retry.delay(attempt, e.is_rate_limited());
+                warn!("Close Extended position failed (attempt {}/{}): {}. Retrying in {}ms...", attempt, retry.max_attempts, e, delay.as_millis());
+                sleep(delay).await;
             }
         }
     }
+    Ok(Err(BotError::execution("no close attempt allowed by the retry policy", false)))
+}
 
-    let extended_order = extended_order
-        .expect("Extended order should be set or function should have returned on failure");
-    info!("Extended order placed: {:?}", extended_order);
-
-    // Step 2: Place second order with retry (Pacifica)
-    let pacifica_side = if long_on_extended { PacificaOrderSide::Sell } else { PacificaOrderSide::Buy };
-    let slippage_percent = 0.5; // 0.5% slippage tolerance
+/// Send a reduce-only Pacifica order for all of `pac_pos`; as `close_extended_leg`
+#[allow(clippy::too_many_arguments)]
+async fn close_pacifica_leg(
+    position_id: &Uuid,
+    leg: OrderLeg,
+    symbol: &str,
+    pac_pos: &PacificaPosition,
+    pacifica_client: &mut PacificaTrading,
+    started_ms: i64,
+    retry: &RetryPolicy,
+    intents: &IntentLog,
+) -> Result<Result<PlacedOrder>> {
+    let slippage_percent = 0.5;
+    let close_side = if pac_pos.is_long() { PacificaOrderSide::Sell } else { PacificaOrderSide::Buy };
+    let close_intent = |attempt: u32, phase: IntentPhase| {
+        IntentRecord::order(position_id, symbol, leg, close_side, pac_pos.size(), attempt, phase)
+    };
 
-    info!("Placing Pacifica order: {:?} {:.6} {} @ market (with {} retries)",
-        pacifica_side, position_size_base, symbol, ORDER_MAX_ATTEMPTS);
+    let sent_at_ms = clock::now_ms();
 
-    // Retry logic for Pacifica order (inline due to mutable reference)
-    let mut pacifica_order = None;
-    let mut pacifica_error = None;
+    // Retry logic for closing Pacifica position
+    for attempt in 1..=retry.max_attempts {
+        if attempt > 1 {
+            if let Some(id) = find_filled_pacifica_attempt(
+                pacifica_client, &pac_pos.symbol, position_id, leg, attempt - 1, started_ms,
+            ).await {
+                info!("Earlier Pacifica close attempt {} already filled, not resending", id);
+                intents.record_or_warn(&close_intent(attempt - 1, IntentPhase::Filled));
+                return Ok(Ok(PlacedOrder::new(id, None, attempt - 1, sent_at_ms)));
+            }
+        }
 
-    for attempt in 1..=ORDER_MAX_ATTEMPTS {
-        match pacifica_client.place_market_order(
-            pacifica_market_symbol,
-            pacifica_side,
-            position_size_base,
-            slippage_percent,
-            false
-        ).await {
+        log_intent(intents, close_intent(attempt, IntentPhase::Pending))?;
+        let client_id = client_order_id(position_id, leg, attempt).to_string();
+        match pacifica_client.close_position_with_id(pac_pos, slippage_percent, &client_id).await {
             Ok(order) => {
                 if attempt > 1 {
-                    info!("Pacifica order succeeded on attempt {}/{}", attempt, ORDER_MAX_ATTEMPTS);
+                    info!("Close Pacifica position succeeded on attempt {}/{}", attempt, retry.max_attempts);
                 }
-                pacifica_order = Some(order);
-                break;
+                info!("Pacifica position closed: {:?}", order);
+                intents.record_or_warn(&close_intent(attempt, IntentPhase::Filled));
+                let order_id = order.order_id.or(order.i).map(|id| id.to_string());
+                return Ok(Ok(PlacedOrder::new(client_id, order_id, attempt, sent_at_ms)));
             }
             Err(e) => {
-                let rate_limited = looks_like_rate_limit(&e.to_string());
-                if attempt >= ORDER_MAX_ATTEMPTS {
-                    error!("Pacifica order failed after {} attempts: {}", ORDER_MAX_ATTEMPTS, e);
-                    pacifica_error = Some(e);
-                } else {
-                    let delay_ms = backoff_delay_ms(attempt, rate_limited);
-                    warn!(
-                        "Pacifica order failed (attempt {}/{}{}) : {}. Retrying in {}ms...",
-                        attempt,
-                        ORDER_MAX_ATTEMPTS,
-                        if rate_limited { " - rate limited" } else { "" },
-                        e,
-                        delay_ms
-                    );
-                    sleep(Duration::from_millis(delay_ms)).await;
-                }
-            }
-        }
-    }
-
-    // Handle Pacifica failure: Rollback Extended position
-    let pacifica_order = match pacifica_order {
-        Some(order) => order,
-        None => {
-            let err_msg = pacifica_error.map(|e| e.to_string()).unwrap_or_else(|| "Unknown error".to_string());
-            error!("CRITICAL: Pacifica order failed. Initiating ROLLBACK of Extended position...");
-
-            // Rollback: Close Extended position
-            let close_side = if long_on_extended { OrderSide::Sell } else { OrderSide::Buy };
-            info!("ROLLBACK: Placing Extended order: {:?} {:.6} {} @ market", close_side, position_size_base, symbol);
-
-            // We use place_market_order directly for rollback to avoid needing a Position object
-            let mut attempt: u32 = 0;
-            loop {
-                attempt += 1;
-                match extended_client.place_market_order(
-                    extended_market_symbol,
-                    close_side.clone(),
-                    notional_usd, // Use same notional
-                    stark_private_key,
-                    stark_public_key,
-                    vault_id,
-                    true, // reduce_only = true
-                    Some(position_size_base), // pass base size to ensure full close
-                ).await {
-                    Ok(order) => {
-                        info!(
-                            "ROLLBACK SUCCESSFUL on attempt {}/{}: Extended position closed. Order: {:?}",
-                            attempt,
-                            ORDER_MAX_ATTEMPTS,
-                            order
-                        );
-                        return Err(Box::new(TradingError::new(
-                            format!("Pacifica order failed. Extended position successfully rolled back (closed). Original error: {}", err_msg),
-                            true
-                        )));
-                    }
-                    Err(e) => {
-                        let rate_limited = looks_like_rate_limit(&e.to_string());
-                        if !rate_limited && attempt >= ORDER_MAX_ATTEMPTS {
-                            error!("ROLLBACK FAILED after {} attempts: {}. Extended position may be open!", ORDER_MAX_ATTEMPTS, e);
-                            return Err(Box::new(TradingError::new(
-                                format!("Pacifica order failed AND rollback failed. CRITICAL: Check Extended position manually! Original error: {}. Rollback error: {}", err_msg, e),
-                                false // Not recoverable automatically, needs manual intervention
-                            )));
-                        }
-
-                        let delay_ms = backoff_delay_ms(attempt, rate_limited);
-                        warn!(
-                            "ROLLBACK Extended order failed (attempt {}{}{}) : {}. Retrying in {}ms...",
-                            attempt,
-                            if !rate_limited { format!("/{}", ORDER_MAX_ATTEMPTS) } else { String::new() },
-                            if rate_limited { " - rate limited, will keep retrying" } else { "" },
-                            e,
-                            delay_ms
-                        );
-                        sleep(Duration::from_millis(delay_ms)).await;
+                let e = BotError::from(e);
+                if !retry.should_retry(attempt, &e) {
+                    if let Some(id) = find_filled_pacifica_attempt(
+                        pacifica_client, &pac_pos.symbol, position_id, leg, attempt, started_ms,
+                    ).await {
+                        warn!("Close Pacifica position reported an error but filled: {}", e);
+                        intents.record_or_warn(&close_intent(attempt, IntentPhase::Filled));
+                        return Ok(Ok(PlacedOrder::new(id, None, attempt, sent_at_ms)));
                     }
+                    intents.record_or_warn(&close_intent(attempt, IntentPhase::Failed));
+                    error!("Failed to close Pacifica position after {} attempt(s): {}", attempt, e);
+                    return Ok(Err(e));
                 }
+                let delay = retry.delay(attempt, e.is_rate_limited());
+                warn!("Close Pacifica position failed (attempt {}/{}): {}. Retrying in {}ms...", attempt, retry.max_attempts, e, delay.as_millis());
+                sleep(delay).await;
             }
         }
-    };
+    }
+    Ok(Err(BotError::execution("no close attempt allowed by the retry policy", false)))
+}
 
-    info!("Pacifica order placed: {:?}", pacifica_order);
+/// Size left open on one venue after a close attempt
+#[derive(Debug, Clone)]
+pub struct ResidualLeg {
+    pub exchange: String,
+    pub market: String,
+    pub size: f64,
+    pub lot_size: f64,
+}
 
-    // Step 3: Fetch opened positions
-    let (extended_position, pacifica_position) = match fetch_opened_positions_with_backoff(
-        extended_client,
-        pacifica_client,
-        extended_market_symbol,
-        pacifica_market_symbol,
-    ).await {
-        Ok((ext, pac)) => (ext, pac),
-        Err(e) => {
-            warn!("Failed to fetch positions after opening orders: {}. Saving state with unknown legs.", e);
-            (None, None)
-        }
-    };
+/// Returned when residual size remains after all reduce-only close attempts
+#[derive(Debug)]
+pub struct CloseVerificationError {
+    pub residuals: Vec<ResidualLeg>,
+}
 
-    let opened_at = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_secs();
+impl std::fmt::Display for CloseVerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let details: Vec<String> = self
+            .residuals
+            .iter()
+            .map(|r| format!("{} {} residual {} (lot {})", r.exchange, r.market, r.size, r.lot_size))
+            .collect();
+        write!(f, "Position not fully closed: {}", details.join(", "))
+    }
+}
 
-    info!("✅ Delta neutral position opened successfully");
+impl std::error::Error for CloseVerificationError {}
 
-    Ok(DeltaNeutralPosition {
-        symbol: symbol.to_string(),
-        extended_position,
-        pacifica_position,
-        opened_at,
-        target_notional_usd: notional_usd,
-    })
+/// A residual counts once it reaches one full lot; anything smaller is untradeable dust.
+pub(crate) fn is_residual(size: f64, lot_size: f64) -> bool {
+    let size = size.abs();
+    if lot_size <= 0.0 {
+        return size > 0.0;
+    }
+    size + lot_size * 1e-6 >= lot_size
 }
 
-/// Close a delta neutral position
-pub async fn close_delta_neutral_position(
+/// Re-query both exchanges after a close and retrigger reduce-only closes for any
+/// residual of at least one lot. Succeeds only once both venues are flat.
+async fn verify_position_closed(
     position: &DeltaNeutralPosition,
     extended_client: &RestClient,
     pacifica_client: &mut PacificaTrading,
     stark_private_key: &str,
     stark_public_key: &str,
     vault_id: &str,
+    retry: &RetryPolicy,
 ) -> Result<()> {
-    info!("Closing delta neutral position for {}", position.symbol);
+    let extended_market = position.extended_market_name();
+    let pacifica_market = position.pacifica_market_name();
 
-    let mut errors = Vec::new();
+    let extended_lot = match extended_client.get_market_config(&extended_market).await {
+        Ok(cfg) => cfg.trading_config.min_order_size_change.parse::<f64>().unwrap_or(0.0),
+        Err(e) => {
+            warn!("Could not fetch Extended lot size for {}: {}. Treating any size as residual.", extended_market, e);
+            0.0
+        }
+    };
+    let pacifica_lot = match pacifica_client.get_market_info().await {
+        Ok(markets) => markets
+            .get(&pacifica_market)
+            .and_then(|m| m.lot_size.parse::<f64>().ok())
+            .unwrap_or(0.0),
+        Err(e) => {
+            warn!("Could not fetch Pacifica lot size for {}: {}. Treating any size as residual.", pacifica_market, e);
+            0.0
+        }
+    };
 
-    // Close Extended position
-    if let Some(ref ext_pos) = position.extended_position {
-        info!("Closing Extended position: {} {:?}", ext_pos.market, ext_pos.side);
-
-        // Retry logic for closing Extended position (inline due to ownership)
-        for attempt in 1..=5 {
-            match extended_client.close_position(
-                ext_pos,
-                stark_private_key,
-                stark_public_key,
-                vault_id
-            ).await {
-                Ok(order) => {
-                    if attempt > 1 {
-                        info!("Close Extended position succeeded on attempt {}/5", attempt);
-                    }
-                    info!("Extended position closed: {:?}", order);
-                    break;
-                }
-                Err(e) => {
-                    if attempt >= 5 {
-                        error!("Failed to close Extended position after 5 attempts: {}", e);
-                        errors.push(format!("Extended: {}", e));
-                    } else {
-                        let delay_ms = 2u64.pow(attempt - 1) * 1000;
-                        warn!("Close Extended position failed (attempt {}/5): {}. Retrying in {}ms...", attempt, e, delay_ms);
-                        sleep(Duration::from_millis(delay_ms)).await;
-                    }
-                }
+    let mut residuals = Vec::new();
+
+    for round in 1..=CLOSE_VERIFY_MAX_ROUNDS {
+        // Give the venues a moment to reflect the fills
+        sleep(Duration::from_millis(CLOSE_VERIFY_DELAY_MS)).await;
+
+        let (live_ext, live_pac) = fetch_opened_positions_with_backoff(
+            extended_client,
+            pacifica_client,
+            &extended_market,
+            &pacifica_market,
+            retry,
+        ).await?;
+
+        let live_ext = live_ext.filter(|p| is_residual(p.size_f64(), extended_lot));
+        let live_pac = live_pac.filter(|p| is_residual(p.size(), pacifica_lot));
+
+        residuals.clear();
+        if let Some(p) = &live_ext {
+            residuals.push(ResidualLeg {
+                exchange: "Extended".to_string(),
+                market: extended_market.clone(),
+                size: p.size_f64(),
+                lot_size: extended_lot,
+            });
+        }
+        if let Some(p) = &live_pac {
+            residuals.push(ResidualLeg {
+                exchange: "Pacifica".to_string(),
+                market: pacifica_market.clone(),
+                size: p.size(),
+                lot_size: pacifica_lot,
+            });
+        }
+
+        if residuals.is_empty() {
+            info!("Verified {} is flat on both exchanges", position.symbol);
+            return Ok(());
+        }
+
+        if round == CLOSE_VERIFY_MAX_ROUNDS {
+            break;
+        }
+
+        warn!(
+            "Residual size after close (round {}/{}): {}. Retriggering reduce-only close...",
+            round,
+            CLOSE_VERIFY_MAX_ROUNDS,
+            CloseVerificationError { residuals: residuals.clone() }
+        );
+
+        if let Some(ext_pos) = &live_ext {
+            if let Err(e) = extended_client
+                .close_position(ext_pos, stark_private_key, stark_public_key, vault_id)
+                .await
+            {
+                warn!("Residual Extended close failed: {}", e);
+            }
+        }
+        if let Some(pac_pos) = &live_pac {
+            if let Err(e) = pacifica_client.close_position(pac_pos, 0.5).await {
+                warn!("Residual Pacifica close failed: {}", e);
             }
         }
     }
 
-    // Close Pacifica position
-    if let Some(ref pac_pos) = position.pacifica_position {
-        info!("Closing Pacifica position: {} (size: {})", pac_pos.symbol, pac_pos.size());
+    let err = CloseVerificationError { residuals };
+    error!("{}", err);
+    Err(err.into())
+}
 
-        let slippage_percent = 0.5;
+/// How much of one venue position an emergency flatten removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlattenStatus {
+    /// Less than one lot left
+    Closed,
+    /// Some size was closed but at least one lot remains
+    Partial,
+    /// Nothing was closed
+    Open,
+}
 
-        // Retry logic for closing Pacifica position (inline due to mutable reference)
-        for attempt in 1..=5 {
-            match pacifica_client.close_position(pac_pos, slippage_percent).await {
-                Ok(order) => {
-                    if attempt > 1 {
-                        info!("Close Pacifica position succeeded on attempt {}/5", attempt);
-                    }
-                    info!("Pacifica position closed: {:?}", order);
-                    break;
-                }
-                Err(e) => {
-                    if attempt >= 5 {
-                        error!("Failed to close Pacifica position after 5 attempts: {}", e);
-                        errors.push(format!("Pacifica: {}", e));
-                    } else {
-                        let delay_ms = 2u64.pow(attempt - 1) * 1000;
-                        warn!("Close Pacifica position failed (attempt {}/5): {}. Retrying in {}ms...", attempt, e, delay_ms);
-                        sleep(Duration::from_millis(delay_ms)).await;
+/// Outcome of flattening one position found on a venue
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FlattenLeg {
+    pub exchange: String,
+    pub market: String,
+    pub is_long: bool,
+    /// Size when the flatten started
+    pub initial_size: f64,
+    /// Size still open at the last check
+    pub remaining_size: f64,
+    /// Lot size used to tell residuals from dust (0 when unknown)
+    pub lot_size: f64,
+    /// Close orders sent, including retriggers for residual size
+    pub attempts: u32,
+    /// Last error seen while closing or verifying this leg
+    pub error: Option<String>,
+}
+
+impl FlattenLeg {
+    fn new(exchange: &str, market: &str, is_long: bool, size: f64, lot_size: f64) -> Self {
+        Self {
+            exchange: exchange.to_string(),
+            market: market.to_string(),
+            is_long,
+            initial_size: size.abs(),
+            remaining_size: size.abs(),
+            lot_size,
+            attempts: 0,
+            error: None,
+        }
+    }
+
+    pub fn status(&self) -> FlattenStatus {
+        if !is_residual(self.remaining_size, self.lot_size) {
+            FlattenStatus::Closed
+        } else if self.remaining_size.abs() < self.initial_size {
+            FlattenStatus::Partial
+        } else {
+            FlattenStatus::Open
+        }
+    }
+
+    pub fn closed_size(&self) -> f64 {
+        (self.initial_size - self.remaining_size.abs()).max(0.0)
+    }
+}
+
+/// What `emergency_flatten_all` closed and what it left open
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct FlattenReport {
+    pub legs: Vec<FlattenLeg>,
+    /// Venues whose positions could not be listed; anything open there is unaccounted for
+    pub listing_errors: Vec<String>,
+}
+
+impl FlattenReport {
+    pub fn closed(&self) -> impl Iterator<Item = &FlattenLeg> {
+        self.legs.iter().filter(|l| l.status() == FlattenStatus::Closed)
+    }
+
+    /// Legs still holding at least one lot, partially closed or untouched
+    pub fn unclosed(&self) -> impl Iterator<Item = &FlattenLeg> {
+        self.legs.iter().filter(|l| l.status() != FlattenStatus::Closed)
+    }
+
+    /// True once every listed leg is closed and both venues could be listed
+    pub fn is_flat(&self) -> bool {
+        self.listing_errors.is_empty() && self.unclosed().next().is_none()
+    }
+}
+
+impl std::fmt::Display for FlattenReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{} legs closed", self.closed().count(), self.legs.len())?;
+        for leg in self.unclosed() {
+            write!(
+                f,
+                "; {} {} {:?} {} of {} left",
+                leg.exchange,
+                leg.market,
+                leg.status(),
+                leg.remaining_size,
+                leg.initial_size
+            )?;
+            if let Some(e) = &leg.error {
+                write!(f, " ({})", e)?;
+            }
+        }
+        for e in &self.listing_errors {
+            write!(f, "; {}", e)?;
+        }
+        Ok(())
+    }
+}
+
+/// Close every position on both venues with reduce-only orders, whether or not the
+/// bot is tracking it.
+///
+/// Each leg is closed like a tracked position's (`close_extended_leg`, `close_pacifica_leg`):
+/// retried under `retry` with deterministic client order IDs, so an attempt that timed
+/// out but filled is not sent twice, and logged to `intents`. Afterwards both venues are re-queried and
+/// reduce-only closes retriggered for any residual of at least one lot. Never fails: every
+/// problem ends up in the returned report.
+pub async fn emergency_flatten_all(
+    extended_client: &RestClient,
+    pacifica_client: &mut PacificaTrading,
+    stark_private_key: &str,
+    stark_public_key: &str,
+    vault_id: &str,
+    retry: &RetryPolicy,
+    intents: &IntentLog,
+) -> FlattenReport {
+    let started_ms = clock::now_ms();
+    let mut report = FlattenReport::default();
+
+    let extended_positions = list_extended_positions(extended_client, retry).await;
+    let pacifica_positions = list_pacifica_positions(pacifica_client, retry).await;
+
+    let pacifica_lots: HashMap<String, f64> = match pacifica_client.get_market_info().await {
+        Ok(markets) => markets
+            .iter()
+            .map(|(symbol, m)| (symbol.clone(), m.lot_size.parse::<f64>().unwrap_or(0.0)))
+            .collect(),
+        Err(e) => {
+            warn!("Could not fetch Pacifica lot sizes: {}. Treating any size as residual.", e);
+            HashMap::new()
+        }
+    };
+
+    match extended_positions {
+        Ok(positions) => {
+            for pos in positions.iter().filter(|p| p.size_f64() != 0.0) {
+                let lot = match extended_client.get_market_config(&pos.market).await {
+                    Ok(cfg) => cfg.trading_config.min_order_size_change.parse::<f64>().unwrap_or(0.0),
+                    Err(e) => {
+                        warn!("Could not fetch Extended lot size for {}: {}. Treating any size as residual.", pos.market, e);
+                        0.0
                     }
-                }
+                };
+                let mut leg = FlattenLeg::new("Extended", &pos.market, pos.is_long(), pos.size_f64(), lot);
+                info!("Flattening Extended {} {:?} {}", pos.market, pos.side, pos.size);
+
+                let leg_id = Uuid::new_v4();
+                let result = close_extended_leg(
+                    &leg_id,
+                    OrderLeg::ExtendedClose,
+                    &pos.market,
+                    pos,
+                    extended_client,
+                    stark_private_key,
+                    stark_public_key,
+                    vault_id,
+                    retry,
+                    intents,
+                ).await;
+                settle_flatten_leg(&mut leg, result, &leg_id, intents);
+                report.legs.push(leg);
             }
         }
+        Err(e) => {
+            error!("Could not list Extended positions: {}", e);
+            report.listing_errors.push(format!("Extended: {}", e));
+        }
     }
 
-    if !errors.is_empty() {
-        return Err(Box::new(TradingError::new(
-            format!("Failed to close some positions: {}", errors.join(", ")),
-            true,
-        )));
+    match pacifica_positions {
+        Ok(positions) => {
+            for pos in positions.iter().filter(|p| p.size() != 0.0) {
+                let lot = pacifica_lots.get(&pos.symbol).copied().unwrap_or(0.0);
+                let mut leg = FlattenLeg::new("Pacifica", &pos.symbol, pos.is_long(), pos.size(), lot);
+                info!("Flattening Pacifica {} {} {}", pos.symbol, pos.side, pos.amount);
+
+                let leg_id = Uuid::new_v4();
+                let result = close_pacifica_leg(
+                    &leg_id, OrderLeg::PacificaClose, &pos.symbol, pos, pacifica_client, started_ms, retry, intents,
+                ).await;
+                settle_flatten_leg(&mut leg, result, &leg_id, intents);
+                report.legs.push(leg);
+            }
+        }
+        Err(e) => {
+            error!("Could not list Pacifica positions: {}", e);
+            report.listing_errors.push(format!("Pacifica: {}", e));
+        }
     }
 
-    info!("✅ Delta neutral position closed successfully");
-    Ok(())
+    if !report.legs.is_empty() {
+        verify_flattened(&mut report, extended_client, pacifica_client, stark_private_key, stark_public_key, vault_id, retry).await;
+    }
+
+    if report.is_flat() {
+        info!("✅ Emergency flatten complete: {}", report);
+    } else {
+        error!("Emergency flatten incomplete: {}", report);
+    }
+    report
+}
+
+/// Record how a flatten leg's close went, then resolve its intents: the flatten is not
+/// an execution a restarted bot resumes
+fn settle_flatten_leg(leg: &mut FlattenLeg, result: Result<Result<PlacedOrder>>, leg_id: &Uuid, intents: &IntentLog) {
+    let position_id = leg_id.to_string();
+    leg.attempts = match &result {
+        Ok(Ok(placed)) => placed.attempt,
+        // Count the orders sent from the intent log, since the close gave up
+        _ => intents
+            .unresolved()
+            .ok()
+            .and_then(|executions| executions.into_iter().find(|e| e.position_id == position_id))
+            .map_or(0, |e| e.records.iter().filter(|r| r.phase == IntentPhase::Pending).count() as u32),
+    };
+    leg.error = match result {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) | Err(e) => Some(e.to_string()),
+    };
+    intents.resolve(&position_id, &leg.market);
+}
+
+async fn list_extended_positions(extended_client: &RestClient, retry: &RetryPolicy) -> Result<Vec<Position>> {
+    retry
+        .run("List Extended positions", || async { extended_client.get_positions(None).await.map_err(BotError::from) })
+        .await
+}
+
+async fn list_pacifica_positions(pacifica_client: &PacificaTrading, retry: &RetryPolicy) -> Result<Vec<PacificaPosition>> {
+    retry
+        .run("List Pacifica positions", || async { pacifica_client.get_positions().await.map_err(BotError::from) })
+        .await
+}
+
+/// Update each leg's remaining size from fresh position lists, retriggering reduce-only
+/// closes for residuals between rounds
+async fn verify_flattened(
+    report: &mut FlattenReport,
+    extended_client: &RestClient,
+    pacifica_client: &mut PacificaTrading,
+    stark_private_key: &str,
+    stark_public_key: &str,
+    vault_id: &str,
+    retry: &RetryPolicy,
+) {
+    for round in 1..=CLOSE_VERIFY_MAX_ROUNDS {
+        // Give the venues a moment to reflect the fills
+        sleep(Duration::from_millis(CLOSE_VERIFY_DELAY_MS)).await;
+
+        let live_ext = list_extended_positions(extended_client, retry).await;
+        let live_pac = list_pacifica_positions(pacifica_client, retry).await;
+
+        for leg in &mut report.legs {
+            let remaining = if leg.exchange == "Extended" {
+                live_ext.as_ref().map(|ps| {
+                    ps.iter().find(|p| p.market == leg.market).map(|p| p.size_f64()).unwrap_or(0.0)
+                })
+            } else {
+                live_pac.as_ref().map(|ps| {
+                    ps.iter().find(|p| p.symbol == leg.market).map(|p| p.size()).unwrap_or(0.0)
+                })
+            };
+            match remaining {
+                Ok(size) => leg.remaining_size = size.abs(),
+                Err(e) => leg.error = Some(format!("Could not verify: {}", e)),
+            }
+        }
+
+        if report.unclosed().next().is_none() || round == CLOSE_VERIFY_MAX_ROUNDS {
+            break;
+        }
+
+        warn!(
+            "Residual size after flatten (round {}/{}): {}. Retriggering reduce-only closes...",
+            round, CLOSE_VERIFY_MAX_ROUNDS, report
+        );
+
+        for leg in report.legs.iter_mut().filter(|l| l.status() != FlattenStatus::Closed) {
+            let result = if leg.exchange == "Extended" {
+                let Some(pos) = live_ext.as_ref().ok().and_then(|ps| ps.iter().find(|p| p.market == leg.market)) else {
+                    continue;
+                };
+                extended_client
+                    .close_position(pos, stark_private_key, stark_public_key, vault_id)
+                    .await
+                    .map(|_| ())
+                    .map_err(BotError::from)
+            } else {
+                let Some(pos) = live_pac.as_ref().ok().and_then(|ps| ps.iter().find(|p| p.symbol == leg.market)) else {
+                    continue;
+                };
+                pacifica_client.close_position(pos, 0.5).await.map(|_| ()).map_err(BotError::from)
+            };
+            leg.attempts += 1;
+            if let Err(e) = result {
+                warn!("Residual {} {} close failed: {}", leg.exchange, leg.market, e);
+                leg.error = Some(e.to_string());
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -614,4 +3015,445 @@ mod tests {
         // Rounded = 0.02
         assert_eq!(size, 0.02);
     }
+
+    #[test]
+    fn test_client_order_id_is_deterministic() {
+        let position_id = Uuid::new_v4();
+        let first = client_order_id(&position_id, OrderLeg::ExtendedOpen, 1);
+        assert_eq!(first, client_order_id(&position_id, OrderLeg::ExtendedOpen, 1));
+        assert_ne!(first, client_order_id(&position_id, OrderLeg::ExtendedOpen, 2));
+        assert_ne!(first, client_order_id(&position_id, OrderLeg::PacificaOpen, 1));
+        assert_ne!(first, client_order_id(&Uuid::new_v4(), OrderLeg::ExtendedOpen, 1));
+        // Still a well-formed UUID, as Pacifica requires
+        assert_eq!(Uuid::parse_str(&first.to_string()).unwrap(), first);
+
+        let reduce = client_order_id(&reduction_id(&position_id, 3), OrderLeg::PacificaReduce, 1);
+        assert!(derives_from_position(&first.to_string(), &position_id.to_string()));
+        assert!(derives_from_position(&reduce.to_string(), &position_id.to_string()));
+        assert!(!derives_from_position(&first.to_string(), &Uuid::new_v4().to_string()));
+        assert!(!derives_from_position("manual-order", &position_id.to_string()));
+    }
+
+    #[test]
+    fn test_filled_amount_for_client_id() {
+        let trades: Vec<crate::pacifica::trading::TradeHistoryItem> = serde_json::from_value(serde_json::json!([
+            {"history_id": 1, "order_id": 10, "client_order_id": "a", "symbol": "BTC", "amount": "0.01",
+             "price": "1", "entry_price": "1", "fee": "0", "pnl": "0", "event_type": "fulfill_taker",
+             "side": "open_long", "created_at": 0, "cause": "normal"},
+            {"history_id": 2, "order_id": 10, "client_order_id": "a", "symbol": "BTC", "amount": "0.02",
+             "price": "1", "entry_price": "1", "fee": "0", "pnl": "0", "event_type": "fulfill_taker",
+             "side": "open_long", "created_at": 0, "cause": "normal"},
+            {"history_id": 3, "order_id": 11, "client_order_id": "b", "symbol": "BTC", "amount": "0.5",
+             "price": "1", "entry_price": "1", "fee": "0", "pnl": "0", "event_type": "fulfill_taker",
+             "side": "open_long", "created_at": 0, "cause": "normal"}
+        ])).unwrap();
+        assert!((filled_amount_for_client_id(&trades, "a") - 0.03).abs() < 1e-12);
+        assert_eq!(filled_amount_for_client_id(&trades, "c"), 0.0);
+    }
+
+    fn sample_position(opened_at: u64, notional: f64) -> DeltaNeutralPosition {
+        DeltaNeutralPosition {
+            position_id: String::new(),
+            symbol: "BTC".to_string(),
+            extended_position: None,
+            pacifica_position: None,
+            opened_at,
+            target_notional_usd: notional,
+            realized_funding_extended_usd: 0.0,
+            realized_funding_pacifica_usd: 0.0,
+            entry_net_apr: None,
+            extended_entry_price: None,
+            pacifica_entry_price: None,
+            entry_basis_pct: None,
+            spread_capture: None,
+            captured_basis_usd: None,
+            open_execution: None,
+            extended_vault_id: None,
+            direction: None,
+            reductions: 0,
+            protective_generation: None,
+            hold_restarted_at: None,
+            funding_average: FundingRateAverage::default(),
+            extended_market: None,
+            pacifica_market: None,
+            multipliers: ContractMultipliers::default(),
+        }
+    }
+
+    #[test]
+    fn test_realized_funding_apr() {
+        let mut pos = sample_position(0, 1000.0);
+        pos.realized_funding_extended_usd = 1.5;
+        pos.realized_funding_pacifica_usd = -0.5;
+
+        assert_eq!(pos.realized_funding_usd(), 1.0);
+
+        // $1 on $1000 over 1/365th of a year = 36.5% APR
+        let apr = pos.realized_funding_apr(24 * 3600).unwrap();
+        assert!((apr - 36.5).abs() < 1e-9);
+
+        assert!(pos.realized_funding_apr(0).is_none());
+        assert!(sample_position(0, 0.0).realized_funding_apr(3600).is_none());
+    }
+
+    #[test]
+    fn test_position_state_without_funding_fields_deserializes() {
+        let json = r#"{"symbol":"ETH","extended_position":null,"pacifica_position":null,"opened_at":1,"target_notional_usd":50.0}"#;
+        let pos: DeltaNeutralPosition = serde_json::from_str(json).unwrap();
+        assert_eq!(pos.realized_funding_usd(), 0.0);
+    }
+
+    #[test]
+    fn test_validate_leverage() {
+        assert_eq!(validate_leverage(3, Some(10.0), Some(5)), Ok(3));
+        assert_eq!(validate_leverage(1, None, None), Ok(1));
+        assert!(validate_leverage(0, None, None).is_err());
+        assert!(validate_leverage(20, Some(10.0), Some(50)).unwrap_err().contains("Extended"));
+        assert!(validate_leverage(10, Some(25.0), Some(5)).unwrap_err().contains("Pacifica"));
+    }
+
+    #[test]
+    fn test_validate_order_size() {
+        let extended = OrderSizeLimits {
+            venue: Venue::Extended,
+            multiplier: 1.0,
+            lot_size: 0.001,
+            tick_size: 1.0,
+            min_size: 0.01,
+            min_notional_usd: 0.0,
+            max_notional_usd: None,
+        };
+        let pacifica = OrderSizeLimits {
+            venue: Venue::Pacifica,
+            multiplier: 1.0,
+            lot_size: 0.0001,
+            tick_size: 1.0,
+            min_size: 0.0,
+            min_notional_usd: 10.0,
+            max_notional_usd: Some(5_000.0),
+        };
+        let limits = [extended, pacifica];
+
+        // Rounded down to the coarser lot
+        assert_eq!(validate_order_size(0.0257, 100_000.0, &limits), Ok(0.025));
+        // Clamped to Pacifica's $5000 maximum, then rounded
+        assert_eq!(validate_order_size(1.0, 100_000.0, &limits), Ok(0.05));
+        // Below Extended's base minimum
+        assert!(validate_order_size(0.005, 100_000.0, &limits).unwrap_err().contains("Extended minimum"));
+        // Below Pacifica's $10 minimum at a low price
+        assert!(validate_order_size(0.05, 100.0, &limits).unwrap_err().contains("Pacifica minimum"));
+        // Lots that do not divide each other
+        let odd = OrderSizeLimits { lot_size: 0.3, ..pacifica };
+        let even = OrderSizeLimits { lot_size: 0.2, ..extended };
+        assert!(validate_order_size(1.0, 10.0, &[odd, even]).unwrap_err().contains("multiple"));
+        // Missing tick size
+        let no_tick = OrderSizeLimits { tick_size: 0.0, ..extended };
+        assert!(validate_order_size(1.0, 10.0, &[no_tick]).unwrap_err().contains("tick"));
+
+        // Smallest tradable notional: Extended's 0.01 base at $100k, Pacifica's $10 at $100
+        assert!((minimum_order_notional_usd(100_000.0, &limits) - 1_000.0).abs() < 1e-6);
+        assert!((minimum_order_notional_usd(100.0, &limits) - 10.0).abs() < 1e-9);
+        // $10 at $30 is 0.3333 base, rounded up to the 0.001 lot
+        assert!((minimum_order_notional_usd(30.0, &[pacifica, OrderSizeLimits { min_size: 0.0, ..extended }]) - 10.02).abs() < 1e-9);
+        assert!(validate_order_size(0.334, 30.0, &limits).is_ok());
+
+        // A Pacifica contract of 1000 units: its one-contract lot is 1000 base units
+        let per_unit = OrderSizeLimits { lot_size: 1.0, min_size: 1.0, ..extended };
+        let per_thousand = OrderSizeLimits { lot_size: 1.0, tick_size: 0.000001, ..pacifica }.with_multiplier(1000.0);
+        assert_eq!(per_thousand.lot_size, 1000.0);
+        assert!((per_thousand.tick_size - 1e-9).abs() < 1e-18);
+        assert_eq!(validate_order_size(2_345_678.0, 0.00001, &[per_unit, per_thousand]), Ok(2_345_000.0));
+        assert_eq!(per_thousand.with_multiplier(1.0).lot_size, 1.0);
+
+        // Extended's largest market order value caps the size like Pacifica's
+        let config: TradingConfig = serde_json::from_value(serde_json::json!({
+            "minOrderSize": "0.0001", "minOrderSizeChange": "0.0001", "minPriceChange": "1",
+            "maxMarketOrderValue": "2000",
+        }))
+        .unwrap();
+        let capped = OrderSizeLimits::extended(&config).unwrap();
+        assert_eq!(capped.max_notional_usd, Some(2_000.0));
+        assert_eq!(validate_order_size(1.0, 100_000.0, &[capped]), Ok(0.02));
+    }
+
+    #[test]
+    fn test_partial_close_size() {
+        let lot = |venue, lot_size| OrderSizeLimits {
+            venue,
+            multiplier: 1.0,
+            lot_size,
+            tick_size: 1.0,
+            min_size: 0.0,
+            min_notional_usd: 10.0,
+            max_notional_usd: None,
+        };
+        let limits = [lot(Venue::Extended, 0.01), lot(Venue::Pacifica, 0.001)];
+
+        // Same size off both legs, rounded down to the coarser lot
+        assert_eq!(partial_close_size(-0.5, 0.33, 1_000.0, &limits), Ok(0.16));
+        assert!(partial_close_size(0.5, 1.0, 1_000.0, &limits).unwrap_err().contains("between 0 and 1"));
+        // Would leave less than one 0.01 lot
+        assert!(partial_close_size(0.505, 0.999, 1_000.0, &limits).unwrap_err().contains("whole position"));
+        // $5 is below the $10 minimum order
+        assert!(partial_close_size(0.5, 0.03, 500.0, &limits).unwrap_err().contains("minimum"));
+
+        assert_eq!(reduced_size("-0.50", decimal_from_f64(0.16)), "-0.34");
+        let position_id = Uuid::new_v4();
+        assert_ne!(reduction_id(&position_id, 1), reduction_id(&position_id, 2));
+        assert_ne!(
+            client_order_id(&reduction_id(&position_id, 1), OrderLeg::ExtendedReduce, 1),
+            client_order_id(&position_id, OrderLeg::ExtendedReduce, 1)
+        );
+    }
+
+    #[test]
+    fn test_estimated_liquidation_distance_pct() {
+        assert_eq!(estimated_liquidation_distance_pct(1), 100.0);
+        assert_eq!(estimated_liquidation_distance_pct(4), 25.0);
+        assert_eq!(estimated_liquidation_distance_pct(0), 100.0);
+    }
+
+    #[test]
+    fn test_is_residual() {
+        assert!(is_residual(0.01, 0.01));
+        assert!(is_residual(-0.02, 0.01));
+        assert!(!is_residual(0.005, 0.01));
+        assert!(!is_residual(0.0, 0.01));
+        // Unknown lot size: any non-zero size is residual
+        assert!(is_residual(0.0001, 0.0));
+        assert!(!is_residual(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_estimate_fill_slippage_pct() {
+        let asks = [(100.0, 1.0), (101.0, 1.0), (102.0, 5.0)];
+
+        // Fills entirely at top of book: 0.5% above a 99.5 mid
+        let s = estimate_fill_slippage_pct(&asks, 1.0, 99.5).unwrap();
+        assert!((s - 0.502512562).abs() < 1e-6);
+
+        // Walks two levels: VWAP 100.5 vs mid 100 = 0.5%
+        let s = estimate_fill_slippage_pct(&asks, 2.0, 100.0).unwrap();
+        assert!((s - 0.5).abs() < 1e-9);
+
+        // Not enough depth
+        assert!(estimate_fill_slippage_pct(&asks, 10.0, 100.0).is_none());
+        assert!(estimate_fill_slippage_pct(&[], 1.0, 100.0).is_none());
+
+        // Tolerance: expected walk plus margin, capped
+        assert!((adaptive_slippage_tolerance_pct(0.12, 0.1, 0.5) - 0.22).abs() < 1e-12);
+        assert_eq!(adaptive_slippage_tolerance_pct(0.45, 0.1, 0.5), 0.5);
+    }
+
+    #[test]
+    fn test_top_level_book_requires_enough_depth() {
+        let quote = BidAsk {
+            market: "BTC".to_string(),
+            best_bid: Some("99".to_string()),
+            best_ask: Some("101".to_string()),
+            bid_quantity: Some("0.5".to_string()),
+            ask_quantity: Some("2".to_string()),
+            timestamp: 0,
+        };
+        let (bids, asks) = top_level_book(Some(&quote), true, 1.5).unwrap();
+        assert_eq!((bids, asks), (vec![(99.0, 0.5)], vec![(101.0, 2.0)]));
+        // Selling 1.5 needs more than the 0.5 at the best bid: use the full book
+        assert!(top_level_book(Some(&quote), false, 1.5).is_none());
+        assert!(top_level_book(None, true, 1.0).is_none());
+    }
+
+    #[test]
+    fn test_calculate_position_size_exact_lot_multiple() {
+        // $15,000 at $50,000 is exactly 0.3; f64 floor(0.3 / 0.1) would give 0.2
+        let size = calculate_position_size(20000.0, 20000.0, 0.1, 0.1, 50000.0, 15000.0);
+        assert_eq!(size, 0.3);
+    }
+
+    #[test]
+    fn test_entry_basis_and_basis_pnl() {
+        let mut pos = sample_position(0, 1000.0);
+        pos.extended_position = Some(Position {
+            market: "BTC-USD".to_string(),
+            side: crate::types::PositionSide::Long,
+            size: "0.01".to_string(),
+            value: "1000".to_string(),
+            entry_price: Some("100000".to_string()),
+            unrealized_pnl: None,
+        });
+        pos.pacifica_position = Some(PacificaPosition {
+            symbol: "BTC".to_string(),
+            side: "ask".to_string(),
+            amount: "0.01".to_string(),
+            entry_price: "100100".to_string(),
+            margin: String::new(),
+            funding: "0".to_string(),
+            isolated: false,
+            created_at: 0,
+            updated_at: 0,
+        });
+        assert!(pos.basis_pnl_usd(100000.0, 100100.0).is_none());
+        // Positions saved without a direction take it from the legs
+        assert_eq!(pos.held_direction(), Some(Direction::LongExtendedShortPacifica));
+        pos.direction = serde_json::from_str("\"Long Pacifica / Short Extended\"").unwrap();
+        assert_eq!(pos.held_direction(), Some(Direction::LongPacificaShortExtended));
+        assert_eq!(serde_json::to_string(&pos.direction).unwrap(), "\"Long Pacifica / Short Extended\"");
+        let snake: Option<Direction> = serde_json::from_str("\"long_pacifica_short_extended\"").unwrap();
+        assert_eq!(snake, pos.direction);
+        pos.direction = None;
+
+        pos.record_entry_prices();
+        assert_eq!(pos.extended_entry_price, Some(100000.0));
+        assert_eq!(pos.pacifica_entry_price, Some(100100.0));
+        assert!((pos.entry_basis_pct.unwrap() - 0.1).abs() < 1e-9);
+
+        // A parallel move leaves the hedge flat; basis narrowing by $50 earns 0.01 x 50
+        assert!(pos.basis_pnl_usd(101000.0, 101100.0).unwrap().abs() < 1e-9);
+        assert!((pos.basis_pnl_usd(101000.0, 101050.0).unwrap() - 0.5).abs() < 1e-9);
+        // Short Pacifica sold $100 above the Extended buy on 0.01 BTC
+        assert!((pos.entry_basis_usd().unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_plan_spread_capture() {
+        let quote = |bid: &str, bid_qty: &str, ask: &str, ask_qty: &str| BidAsk {
+            market: String::new(),
+            best_bid: Some(bid.to_string()),
+            best_ask: Some(ask.to_string()),
+            bid_quantity: Some(bid_qty.to_string()),
+            ask_quantity: Some(ask_qty.to_string()),
+            timestamp: 0,
+        };
+        // Extended ask 100.0 below Pacifica bid 100.4: long Extended is favorable
+        let extended = quote("99.9", "5", "100.0", "5");
+        let pacifica = quote("100.4", "1", "100.5", "1");
+
+        let unit = ContractMultipliers::default();
+        assert!(plan_spread_capture(true, &extended, &pacifica, unit, 0.0).is_none());
+        assert!(plan_spread_capture(false, &extended, &pacifica, unit, 50.0).is_none());
+
+        // Pacifica's bid is thinner, so the Pacifica short leads and must sell at >= 100.2
+        let plan = plan_spread_capture(true, &extended, &pacifica, unit, 50.0).unwrap();
+        assert!(plan.pacifica_first);
+        assert!((plan.favorable_spread_pct - 0.4).abs() < 1e-9);
+        assert!((plan.lead_limit_price - 100.2).abs() < 1e-9);
+
+        // With Extended thinner, the Extended long leads and must buy at <= 100.3
+        let thin_extended = quote("99.9", "5", "100.0", "0.5");
+        let plan = plan_spread_capture(true, &thin_extended, &pacifica, unit, 25.0).unwrap();
+        assert!(!plan.pacifica_first);
+        assert!((plan.lead_limit_price - 100.3).abs() < 1e-9);
+
+        // Pacifica quoting 1000 units per contract: its 1 contract at the bid is 1000 units,
+        // deeper than Extended's 5, so Extended leads; a Pacifica lead is priced per contract
+        let per_thousand = ContractMultipliers { extended: 1.0, pacifica: 1000.0 };
+        let pacifica_k = quote("100400", "1", "100500", "1");
+        let plan = plan_spread_capture(true, &extended, &pacifica_k, per_thousand, 50.0).unwrap();
+        assert!(!plan.pacifica_first);
+        assert!((plan.favorable_spread_pct - 0.4).abs() < 1e-9);
+        let plan = plan_spread_capture(true, &extended, &quote("100400", "0.001", "100500", "1"), per_thousand, 50.0).unwrap();
+        assert!(plan.pacifica_first);
+        assert!((plan.lead_limit_price - 100_200.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_flatten_report_tracks_partial_closes() {
+        let mut closed = FlattenLeg::new("Extended", "BTC-USD", true, 0.5, 0.001);
+        closed.remaining_size = 0.0004;
+        let mut partial = FlattenLeg::new("Pacifica", "BTC", false, -0.5, 0.01);
+        partial.remaining_size = 0.2;
+        partial.error = Some("timeout".to_string());
+        let open = FlattenLeg::new("Pacifica", "ETH", true, 2.0, 0.01);
+
+        assert_eq!(closed.status(), FlattenStatus::Closed);
+        assert_eq!(partial.status(), FlattenStatus::Partial);
+        assert!((partial.closed_size() - 0.3).abs() < 1e-12);
+        assert_eq!(open.status(), FlattenStatus::Open);
+
+        let mut report = FlattenReport { legs: vec![closed.clone()], listing_errors: Vec::new() };
+        assert!(report.is_flat());
+        report.listing_errors.push("Pacifica: timeout".to_string());
+        assert!(!report.is_flat());
+
+        let report = FlattenReport { legs: vec![closed, partial, open], listing_errors: Vec::new() };
+        assert!(!report.is_flat());
+        assert_eq!(report.unclosed().count(), 2);
+        assert!(report.to_string().starts_with("1/3 legs closed; Pacifica BTC Partial 0.2 of 0.5 left (timeout)"));
+    }
+
+    #[test]
+    fn test_execution_report_fills_and_slippage() {
+        let placed = PlacedOrder {
+            client_order_id: "pac-1".to_string(),
+            order_id: None,
+            attempt: 2,
+            sent_at_ms: 1_000,
+            latency_ms: 250,
+        };
+        let mut pacifica = placed.execution(Venue::Pacifica, OrderLeg::PacificaOpen, false, 2.0, Some(100.0));
+        let trade = |client_id: &str, amount: &str, price: &str, fee: &str| -> TradeHistoryItem {
+            serde_json::from_value(serde_json::json!({
+                "history_id": 1, "order_id": 77, "client_order_id": client_id, "symbol": "BTC",
+                "amount": amount, "price": price, "entry_price": price, "fee": fee, "pnl": "0",
+                "event_type": "fulfill_taker", "side": "open_short", "created_at": 1_000, "cause": "normal",
+            }))
+            .unwrap()
+        };
+        let trades = [
+            trade("pac-1", "1.5", "99.9", "0.06"),
+            trade("other", "3", "90", "1"),
+            trade("pac-1", "0.5", "99.5", "0.02"),
+        ];
+        apply_pacifica_trades(&mut pacifica, &trades);
+        assert_eq!(pacifica.order_id.as_deref(), Some("77"));
+        assert_eq!(pacifica.filled_size, Some(2.0));
+        assert!((pacifica.avg_fill_price.unwrap() - 99.8).abs() < 1e-9);
+        assert!((pacifica.fee_usd.unwrap() - 0.08).abs() < 1e-12);
+        // Sold 0.2 below the decision mid: adverse slippage
+        assert!((pacifica.slippage_pct().unwrap() - 0.2).abs() < 1e-9);
+        assert!((pacifica.slippage_usd().unwrap() - 0.4).abs() < 1e-9);
+
+        // A buy filled below the decision price has negative (favorable) slippage
+        let mut extended = LegExecution { venue: Venue::Extended, is_buy: true, ..pacifica.clone() };
+        extended.avg_fill_price = Some(99.9);
+        extended.fee_usd = None;
+        extended.sent_at_ms = 900;
+        assert!((extended.slippage_usd().unwrap() + 0.2).abs() < 1e-9);
+
+        let report = ExecutionReport {
+            position_id: String::new(),
+            symbol: "BTC".to_string(),
+            kind: ExecutionKind::Open,
+            started_at_ms: 1_000,
+            completed_at_ms: 1_600,
+            legs: vec![extended, pacifica],
+            sequence: Some(LegSequence::Concurrent),
+        };
+        assert_eq!(report.duration_ms(), 600);
+        assert_eq!(report.legging_ms(), Some(100));
+        let mut stats = LeggingStats::default();
+        stats.record(&report);
+        stats.record(&ExecutionReport { legs: vec![report.legs[1].clone()], ..report.clone() });
+        assert_eq!((stats.opens, stats.max_legging_ms), (1, 100));
+        assert_eq!(stats.mean_legging_ms(), Some(100.0));
+        assert!(report.to_string().contains("(concurrent, legged 100ms)"));
+        assert!((report.total_fees_usd() - 0.08).abs() < 1e-12);
+        assert!((report.total_slippage_usd() - 0.2).abs() < 1e-9);
+        assert_eq!(report.leg(Venue::Pacifica).unwrap().attempt, 2);
+
+        // The unreported Extended fee is priced at the taker rate; Pacifica's reported one stays
+        let mut estimated = report.clone();
+        estimated.estimate_missing_fees(&FeeSchedule::flat(0.025));
+        let extended = estimated.leg(Venue::Extended).unwrap();
+        assert!(extended.fee_estimated && (extended.fee_usd.unwrap() - 0.04995).abs() < 1e-9);
+        assert!(!estimated.leg(Venue::Pacifica).unwrap().fee_estimated);
+        assert!(estimated.to_string().contains("(estimated)"));
+
+        // No matching trades leave the fill unknown
+        let mut unknown = PlacedOrder::new("missing".to_string(), None, 1, 0).execution(
+            Venue::Pacifica, OrderLeg::PacificaClose, true, 1.0, None,
+        );
+        apply_pacifica_trades(&mut unknown, &trades);
+        assert_eq!(unknown.filled_size, None);
+        assert_eq!(unknown.slippage_pct(), None);
+    }
 }
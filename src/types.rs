@@ -1,3 +1,4 @@
+use crate::numeric::{parse_decimal_or_zero, Decimal};
 use serde::{Deserialize, Serialize};
 
 /// Bid or Ask price level
@@ -23,6 +24,17 @@ pub struct ApiResponse<T> {
     pub status: String,
     pub data: Option<T>,
     pub error: Option<ApiError>,
+    #[serde(default)]
+    pub pagination: Option<Pagination>,
+}
+
+/// Cursor pagination block returned by list endpoints
+#[derive(Debug, Clone, Deserialize)]
+pub struct Pagination {
+    #[serde(default)]
+    pub cursor: Option<i64>,
+    #[serde(default)]
+    pub count: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -136,6 +148,15 @@ pub struct FundingRateData {
     pub f: String,      // funding rate
 }
 
+/// Funding rate update from the funding stream
+#[derive(Debug, Clone, Deserialize)]
+pub struct WsFundingMessage {
+    pub ts: u64,
+    pub data: FundingRateData,
+    #[serde(default)]
+    pub seq: u64,
+}
+
 /// Paginated response for funding rates
 #[derive(Debug, Deserialize)]
 pub struct PaginatedResponse<T> {
@@ -144,6 +165,74 @@ pub struct PaginatedResponse<T> {
     pub error: Option<ApiError>,
 }
 
+/// 24h statistics of a market (from /info/markets/{market}/stats)
+#[derive(Debug, Clone, Deserialize)]
+pub struct MarketStats {
+    /// 24h traded volume in collateral (USD)
+    #[serde(rename = "dailyVolume")]
+    pub daily_volume: String,
+    /// Open interest in collateral (USD)
+    #[serde(rename = "openInterest", default)]
+    pub open_interest: Option<String>,
+    #[serde(rename = "markPrice", default)]
+    pub mark_price: Option<String>,
+    #[serde(rename = "indexPrice", default)]
+    pub index_price: Option<String>,
+    #[serde(rename = "lastPrice", default)]
+    pub last_price: Option<String>,
+    /// Current hourly funding rate (decimal string)
+    #[serde(rename = "fundingRate", default)]
+    pub funding_rate: Option<String>,
+}
+
+impl MarketStats {
+    /// 24h volume in USD as f64
+    pub fn daily_volume_f64(&self) -> f64 {
+        self.daily_volume.parse().unwrap_or(0.0)
+    }
+
+    /// Open interest in USD, when reported
+    pub fn open_interest_f64(&self) -> Option<f64> {
+        self.open_interest.as_deref()?.parse().ok()
+    }
+
+    pub fn mark_price_f64(&self) -> Option<f64> {
+        self.mark_price.as_deref()?.parse().ok()
+    }
+
+    pub fn index_price_f64(&self) -> Option<f64> {
+        self.index_price.as_deref()?.parse().ok()
+    }
+
+    /// Funding rate as decimal, when reported
+    pub fn funding_rate_f64(&self) -> Option<f64> {
+        self.funding_rate.as_deref()?.parse().ok()
+    }
+}
+
+/// Price candle (from /info/candles/{market}/trades), with decimal strings
+#[derive(Debug, Clone, Deserialize)]
+pub struct Candle {
+    #[serde(rename = "o")]
+    pub open: String,
+    #[serde(rename = "h")]
+    pub high: String,
+    #[serde(rename = "l")]
+    pub low: String,
+    #[serde(rename = "c")]
+    pub close: String,
+    /// Candle open time in milliseconds
+    #[serde(rename = "T")]
+    pub timestamp: u64,
+}
+
+impl Candle {
+    /// (high, low, close) as f64, when all parse
+    pub fn hlc_f64(&self) -> Option<(f64, f64, f64)> {
+        Some((self.high.parse().ok()?, self.low.parse().ok()?, self.close.parse().ok()?))
+    }
+}
+
 /// Funding rate information with additional details
 #[derive(Debug, Clone)]
 pub struct FundingRateInfo {
@@ -188,9 +277,8 @@ impl FundingRateInfo {
 
     /// Calculate APR (Annual Percentage Rate)
     /// Extended funding rates are HOURLY (applied once per hour per the API docs)
-    /// APR = funding_rate * 24 * 365 = funding_rate * 8760
     pub fn calculate_apr(&self) -> f64 {
-        self.rate * 24.0 * 365.0
+        crate::funding::apr_from_rate(self.rate, crate::funding::EXTENDED_FUNDING_INTERVAL_HOURS)
     }
 
     /// Get APR as a percentage
@@ -227,12 +315,17 @@ impl std::fmt::Display for OrderSide {
     }
 }
 
-/// Order type: Market or Limit
+/// Order type: Market, Limit or a standalone TP/SL order
+///
+/// TWAP orders are out of scope: Extended lists them among open orders and history,
+/// but its create-order endpoint does not accept them.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum OrderType {
     Market,
     Limit,
+    /// Take-profit/stop-loss order on an open position (see `TpSlType::Position`)
+    Tpsl,
 }
 
 /// Time in force for orders
@@ -241,6 +334,166 @@ pub enum OrderType {
 pub enum TimeInForce {
     IOC,  // Immediate or Cancel (for market orders)
     GTT,  // Good Till Time
+    FOK,  // Fill or Kill
+}
+
+/// Price a TP/SL trigger is compared against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TriggerPriceType {
+    Last,
+    Mark,
+    Index,
+}
+
+/// How a triggered TP/SL leg executes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TpSlPriceType {
+    /// Market execution; `price` is the worst acceptable (signed) price
+    Market,
+    /// Limit order at `price`
+    Limit,
+}
+
+/// What attached take-profit/stop-loss legs close
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TpSlType {
+    /// The quantity of the order they are attached to
+    Order,
+    /// The whole position, whatever its size when they trigger
+    Position,
+}
+
+/// Take-profit or stop-loss attached to a limit order
+#[derive(Debug, Clone)]
+pub struct TpSlTrigger {
+    pub trigger_price: f64,
+    pub trigger_price_type: TriggerPriceType,
+    pub price: f64,
+    pub price_type: TpSlPriceType,
+}
+
+impl TpSlTrigger {
+    /// Market-executed trigger on the mark price
+    pub fn market(trigger_price: f64, worst_price: f64) -> Self {
+        Self {
+            trigger_price,
+            trigger_price_type: TriggerPriceType::Mark,
+            price: worst_price,
+            price_type: TpSlPriceType::Market,
+        }
+    }
+}
+
+/// Limit order parameters for `RestClient::place_limit_order`
+#[derive(Debug, Clone)]
+pub struct LimitOrder {
+    pub market: String,
+    pub side: OrderSide,
+    pub qty: f64,
+    pub price: f64,
+    pub time_in_force: TimeInForce,
+    pub post_only: bool,
+    pub reduce_only: bool,
+    /// Order lifetime in milliseconds from submission
+    pub expiry_millis: u64,
+    pub take_profit: Option<TpSlTrigger>,
+    pub stop_loss: Option<TpSlTrigger>,
+    /// Caller-chosen order ID; generated if None
+    pub external_id: Option<String>,
+}
+
+/// Default limit order lifetime (Extended accepts up to 90 days)
+pub const DEFAULT_LIMIT_ORDER_EXPIRY_MILLIS: u64 = 24 * 3600 * 1000;
+
+impl LimitOrder {
+    /// Good-till-time limit order expiring after `DEFAULT_LIMIT_ORDER_EXPIRY_MILLIS`
+    pub fn new(market: &str, side: OrderSide, qty: f64, price: f64) -> Self {
+        Self {
+            market: market.to_string(),
+            side,
+            qty,
+            price,
+            time_in_force: TimeInForce::GTT,
+            post_only: false,
+            reduce_only: false,
+            expiry_millis: DEFAULT_LIMIT_ORDER_EXPIRY_MILLIS,
+            take_profit: None,
+            stop_loss: None,
+            external_id: None,
+        }
+    }
+
+    /// Reject the order instead of taking liquidity
+    pub fn post_only(mut self) -> Self {
+        self.post_only = true;
+        self
+    }
+
+    pub fn reduce_only(mut self) -> Self {
+        self.reduce_only = true;
+        self
+    }
+
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    pub fn expires_in(mut self, millis: u64) -> Self {
+        self.expiry_millis = millis;
+        self
+    }
+
+    pub fn take_profit(mut self, trigger: TpSlTrigger) -> Self {
+        self.take_profit = Some(trigger);
+        self
+    }
+
+    pub fn stop_loss(mut self, trigger: TpSlTrigger) -> Self {
+        self.stop_loss = Some(trigger);
+        self
+    }
+
+    pub fn external_id(mut self, external_id: impl Into<String>) -> Self {
+        self.external_id = Some(external_id.into());
+        self
+    }
+
+    /// Check parameter consistency before signing
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.qty <= 0.0 || self.price <= 0.0 {
+            return Err("Limit order quantity and price must be positive".to_string());
+        }
+        if self.post_only && !matches!(self.time_in_force, TimeInForce::GTT) {
+            return Err("Post-only orders must be good-till-time".to_string());
+        }
+        if self.expiry_millis == 0 {
+            return Err("Limit order expiry must be positive".to_string());
+        }
+
+        // Profit is above entry for a buy and below for a sell; stops the other way round
+        let is_buy = matches!(self.side, OrderSide::Buy);
+        if let Some(tp) = &self.take_profit {
+            if (tp.trigger_price > self.price) != is_buy {
+                return Err(format!(
+                    "Take-profit trigger {} is on the wrong side of order price {}",
+                    tp.trigger_price, self.price
+                ));
+            }
+        }
+        if let Some(sl) = &self.stop_loss {
+            if (sl.trigger_price < self.price) != is_buy {
+                return Err(format!(
+                    "Stop-loss trigger {} is on the wrong side of order price {}",
+                    sl.trigger_price, self.price
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Signature for order settlement
@@ -283,6 +536,26 @@ pub struct OrderRequest {
     pub reduce_only: bool,
     #[serde(rename = "postOnly", skip_serializing_if = "is_false")]
     pub post_only: bool,
+    /// Set when take-profit/stop-loss legs are attached
+    #[serde(rename = "tpSlType", skip_serializing_if = "Option::is_none")]
+    pub tp_sl_type: Option<TpSlType>,
+    #[serde(rename = "takeProfit", skip_serializing_if = "Option::is_none")]
+    pub take_profit: Option<TpSlLegRequest>,
+    #[serde(rename = "stopLoss", skip_serializing_if = "Option::is_none")]
+    pub stop_loss: Option<TpSlLegRequest>,
+}
+
+/// Signed take-profit/stop-loss leg of an order request
+#[derive(Debug, Clone, Serialize)]
+pub struct TpSlLegRequest {
+    #[serde(rename = "triggerPrice")]
+    pub trigger_price: String,
+    #[serde(rename = "triggerPriceType")]
+    pub trigger_price_type: TriggerPriceType,
+    pub price: String,
+    #[serde(rename = "priceType")]
+    pub price_type: TpSlPriceType,
+    pub settlement: Settlement,
 }
 
 fn is_false(b: &bool) -> bool {
@@ -297,8 +570,57 @@ pub struct OrderResponse {
     pub external_id: String,  // User's order ID
 }
 
+/// Order record from the user orders endpoints
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderInfo {
+    pub id: i64,
+    #[serde(rename = "externalId", default)]
+    pub external_id: String,
+    #[serde(default)]
+    pub market: String,
+    #[serde(default)]
+    pub qty: Option<String>,
+    #[serde(default)]
+    pub status: String,       // NEW, PARTIALLY_FILLED, FILLED, CANCELLED, REJECTED, EXPIRED
+    #[serde(rename = "filledQty", default)]
+    pub filled_qty: Option<String>,
+    /// Volume-weighted fill price
+    #[serde(rename = "averagePrice", default)]
+    pub average_price: Option<String>,
+    /// Fee charged so far, in collateral (USD)
+    #[serde(rename = "payedFee", default)]
+    pub payed_fee: Option<String>,
+    /// Creation time (Unix ms)
+    #[serde(rename = "createdTime", default)]
+    pub created_time: Option<u64>,
+}
+
+impl OrderInfo {
+    pub fn filled_qty_f64(&self) -> f64 {
+        self.filled_qty.as_deref().and_then(|q| q.parse().ok()).unwrap_or(0.0)
+    }
+
+    pub fn average_price_f64(&self) -> Option<f64> {
+        self.average_price.as_deref().and_then(|p| p.parse().ok()).filter(|p: &f64| *p > 0.0)
+    }
+
+    pub fn payed_fee_f64(&self) -> Option<f64> {
+        self.payed_fee.as_deref().and_then(|f| f.parse().ok())
+    }
+
+    /// True if any part of the order executed
+    pub fn has_fill(&self) -> bool {
+        self.filled_qty_f64() > 0.0 || self.status == "FILLED"
+    }
+
+    /// True once the order can no longer fill (filled, cancelled, rejected or expired)
+    pub fn is_final(&self) -> bool {
+        matches!(self.status.as_str(), "FILLED" | "CANCELLED" | "REJECTED" | "EXPIRED")
+    }
+}
+
 /// Account information from API
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct AccountInfo {
     #[serde(rename = "l2Key")]
     pub l2_key: String,       // Public Stark key
@@ -307,6 +629,11 @@ pub struct AccountInfo {
     #[serde(rename = "accountId")]
     pub account_id: i64,
     pub status: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Sub-account index under the wallet (0 = main account)
+    #[serde(rename = "accountIndex", default)]
+    pub account_index: Option<u32>,
 }
 
 /// Fee information for a market
@@ -394,9 +721,38 @@ pub struct TradingConfig {
     pub min_order_size_change: String,  // Precision/increment for order sizes
     #[serde(rename = "minPriceChange")]
     pub min_price_change: String,  // Minimum price increment (e.g., "0.01" for 2 decimals, "1" for whole numbers)
+    #[serde(rename = "maxLeverage", default)]
+    pub max_leverage: Option<String>,  // Maximum leverage allowed on the market
+    #[serde(rename = "maxMarketOrderValue", default)]
+    pub max_market_order_value: Option<String>,  // Largest market order value (USD)
+    /// Initial margin fraction by position value, in ascending `upper_bound` order
+    #[serde(rename = "riskFactorConfig", default)]
+    pub risk_factor_config: Vec<RiskFactorTier>,
+}
+
+/// Initial margin fraction (`risk_factor`) for positions worth up to `upper_bound` USD
+#[derive(Debug, Clone, Deserialize)]
+pub struct RiskFactorTier {
+    #[serde(rename = "upperBound")]
+    pub upper_bound: String,
+    #[serde(rename = "riskFactor")]
+    pub risk_factor: String,
 }
 
 impl TradingConfig {
+    /// Maximum leverage allowed on the market, if reported
+    pub fn max_leverage_f64(&self) -> Option<f64> {
+        self.max_leverage.as_ref().and_then(|s| s.parse().ok())
+    }
+
+    /// Risk factor tiers as (upper bound USD, initial margin fraction); unparsable tiers are skipped
+    pub fn margin_tiers(&self) -> Vec<(f64, f64)> {
+        self.risk_factor_config
+            .iter()
+            .filter_map(|tier| Some((tier.upper_bound.parse().ok()?, tier.risk_factor.parse().ok()?)))
+            .collect()
+    }
+
     /// Calculate price precision (decimal places) from minPriceChange
     /// Examples: "1" -> 0, "0.1" -> 1, "0.01" -> 2
     pub fn get_price_precision(&self) -> usize {
@@ -479,6 +835,21 @@ impl Position {
             .and_then(|s| s.parse().ok())
             .unwrap_or(0.0)
     }
+
+    /// Get position size as exact decimal
+    pub fn size_decimal(&self) -> Decimal {
+        parse_decimal_or_zero(&self.size)
+    }
+
+    /// Get position value as exact decimal
+    pub fn value_decimal(&self) -> Decimal {
+        parse_decimal_or_zero(&self.value)
+    }
+
+    /// Get entry price as exact decimal
+    pub fn entry_decimal(&self) -> Decimal {
+        self.entry_price.as_deref().map(parse_decimal_or_zero).unwrap_or_default()
+    }
 }
 
 impl std::fmt::Display for Position {
@@ -500,6 +871,63 @@ impl std::fmt::Display for Position {
     }
 }
 
+/// Funding payment settled on a position (from /user/funding/history)
+#[derive(Debug, Clone, Deserialize)]
+pub struct FundingPayment {
+    pub id: u64,
+    #[serde(rename = "accountId", default)]
+    pub account_id: Option<u64>,
+    pub market: String,
+    #[serde(rename = "positionId", default)]
+    pub position_id: Option<u64>,
+    pub side: PositionSide,
+    pub size: String,
+    pub value: String,
+    #[serde(rename = "markPrice")]
+    pub mark_price: String,
+    #[serde(rename = "fundingFee")]
+    pub funding_fee: String,
+    #[serde(rename = "fundingRate")]
+    pub funding_rate: String,
+    #[serde(rename = "paidTime")]
+    pub paid_time: u64,
+}
+
+impl FundingPayment {
+    /// Funding fee as f64 (positive = received, negative = paid)
+    pub fn fee_f64(&self) -> f64 {
+        self.funding_fee.parse().unwrap_or(0.0)
+    }
+
+    /// Funding fee as exact decimal
+    pub fn fee_decimal(&self) -> Decimal {
+        parse_decimal_or_zero(&self.funding_fee)
+    }
+}
+
+/// Fill of one of the account's orders (from /user/trades)
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountTrade {
+    pub id: u64,
+    pub market: String,
+    #[serde(rename = "orderId", default)]
+    pub order_id: Option<u64>,
+    pub side: OrderSide,
+    pub price: String,
+    pub qty: String,
+    #[serde(default)]
+    pub fee: Option<String>,
+    #[serde(rename = "createdTime")]
+    pub created_time: u64,
+}
+
+impl AccountTrade {
+    /// Filled quantity in base asset as f64
+    pub fn qty_f64(&self) -> f64 {
+        self.qty.parse().unwrap_or(0.0)
+    }
+}
+
 /// Account balance and margin information for Extended DEX
 #[derive(Debug, Clone, Deserialize)]
 pub struct Balance {
@@ -556,6 +984,11 @@ impl Balance {
     pub fn margin_ratio_f64(&self) -> f64 {
         self.margin_ratio.parse().unwrap_or(0.0)
     }
+
+    /// Get equity as exact decimal
+    pub fn equity_decimal(&self) -> Decimal {
+        parse_decimal_or_zero(&self.equity)
+    }
 }
 
 impl std::fmt::Display for Balance {
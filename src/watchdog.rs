@@ -0,0 +1,309 @@
+//! Watchdog supervision of the bot's main loop
+//!
+//! `FundingBot::run` executes every monitoring cycle through `supervise`: a cycle that
+//! panics, returns an error or runs past `watchdog.cycle_deadline_minutes` becomes an
+//! `Incident`. The incident is logged as a single JSON line, sent to the incident hooks
+//! (a JSON POST to `watchdog.webhook_url` when set), and the loop restarts after
+//! `watchdog.restart_delay_seconds` with the intent log replayed and state reconciled
+//! against both venues. A cycle past its deadline while an open, close or reduction is in
+//! flight is not cancelled: an `overrun` incident is sent instead, and the cycle is
+//! cancelled once the execution has finished, unless it completes first.
+use crate::error::BotError;
+use crate::shutdown::ShutdownController;
+use futures_util::FutureExt;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fmt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogConfig {
+    /// Longest a single monitoring cycle may run before it is cancelled (0 = no deadline)
+    #[serde(default = "default_cycle_deadline_minutes")]
+    pub cycle_deadline_minutes: u64,
+    /// Pause before restarting the loop after an incident
+    #[serde(default = "default_restart_delay_seconds")]
+    pub restart_delay_seconds: u64,
+    /// Incidents in a row (without a clean cycle between them) after which the bot
+    /// exits with the last error instead of restarting again (0 = never give up)
+    #[serde(default = "default_max_consecutive_restarts")]
+    pub max_consecutive_restarts: u32,
+    /// POST each incident as JSON to this URL (Slack/Discord-compatible)
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+fn default_cycle_deadline_minutes() -> u64 {
+    20
+}
+
+fn default_restart_delay_seconds() -> u64 {
+    30
+}
+
+fn default_max_consecutive_restarts() -> u32 {
+    5
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            cycle_deadline_minutes: default_cycle_deadline_minutes(),
+            restart_delay_seconds: default_restart_delay_seconds(),
+            max_consecutive_restarts: default_max_consecutive_restarts(),
+            webhook_url: None,
+        }
+    }
+}
+
+impl WatchdogConfig {
+    pub fn cycle_deadline(&self) -> Option<Duration> {
+        (self.cycle_deadline_minutes > 0).then(|| Duration::from_secs(self.cycle_deadline_minutes * 60))
+    }
+
+    /// Whether `consecutive` incidents in a row exhaust the restart allowance
+    pub fn gives_up_after(&self, consecutive: u32) -> bool {
+        self.max_consecutive_restarts > 0 && consecutive > self.max_consecutive_restarts
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentKind {
+    Panic,
+    DeadlineExceeded,
+    Error,
+    /// A venue rejected the credentials; the bot pauses instead of restarting
+    AuthFailure,
+    /// The Extended margin watch reduced or closed the position
+    MarginCall,
+    /// A cycle ran past its deadline with an execution in flight; it keeps running
+    Overrun,
+}
+
+impl fmt::Display for IncidentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IncidentKind::Panic => write!(f, "panic"),
+            IncidentKind::DeadlineExceeded => write!(f, "deadline exceeded"),
+            IncidentKind::Error => write!(f, "error"),
+            IncidentKind::AuthFailure => write!(f, "authentication failure"),
+            IncidentKind::MarginCall => write!(f, "margin call"),
+            IncidentKind::Overrun => write!(f, "overrun"),
+        }
+    }
+}
+
+/// Why a supervised cycle did not complete
+#[derive(Debug, Clone, PartialEq)]
+pub struct CycleFailure {
+    pub kind: IncidentKind,
+    pub message: String,
+    pub elapsed: Duration,
+}
+
+/// One failed cycle, as logged and sent to incident hooks
+#[derive(Debug, Clone, Serialize)]
+pub struct Incident {
+    pub kind: IncidentKind,
+    pub message: String,
+    /// Unix timestamp (seconds) the incident was detected
+    pub at: u64,
+    /// How long the cycle ran before failing
+    pub cycle_secs: u64,
+    /// Symbol held when the cycle failed, if any
+    pub symbol: Option<String>,
+    /// Incidents in a row, including this one
+    pub consecutive: u32,
+    /// Whether the loop restarts (false when the bot gives up and exits)
+    pub restarting: bool,
+}
+
+impl Incident {
+    pub fn new(failure: CycleFailure, at: u64, symbol: Option<String>, consecutive: u32, restarting: bool) -> Self {
+        Self {
+            kind: failure.kind,
+            message: failure.message,
+            at,
+            cycle_secs: failure.elapsed.as_secs(),
+            symbol,
+            consecutive,
+            restarting,
+        }
+    }
+
+    /// Single-line JSON for log aggregation
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| self.to_string())
+    }
+}
+
+impl fmt::Display for Incident {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.kind == IncidentKind::AuthFailure {
+            return write!(
+                f,
+                "Authentication failure, opens and rotations paused until credentials are replaced and the bot is resumed: {}",
+                self.message
+            );
+        }
+        if self.kind == IncidentKind::MarginCall {
+            return write!(f, "Extended margin call: {}", self.message);
+        }
+        if self.kind == IncidentKind::Overrun {
+            return write!(f, "Bot loop overran its deadline: {}", self.message);
+        }
+        write!(
+            f,
+            "Bot loop {} after {}s (incident {} in a row{}): {}",
+            self.kind,
+            self.cycle_secs,
+            self.consecutive,
+            if self.restarting { ", restarting" } else { ", giving up" },
+            self.message
+        )
+    }
+}
+
+
+/// Text of a caught panic payload
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
+}
+
+/// Run one cycle, turning a panic, an error or an overrun of `deadline` into a
+/// `CycleFailure`. An overrunning cycle is dropped at the deadline if nothing is in
+/// flight on `shutdown`. Otherwise `on_overrun` is called and the cycle keeps running
+/// until it completes or, with the execution finished, is dropped in an idle phase.
+pub async fn supervise<T, F: Future<Output = ()>>(
+    deadline: Option<Duration>,
+    shutdown: &ShutdownController,
+    on_overrun: impl FnOnce(CycleFailure) -> F,
+    cycle: impl Future<Output = Result<T, BotError>>,
+) -> Result<T, CycleFailure> {
+    let started = Instant::now();
+    let guarded = AssertUnwindSafe(cycle).catch_unwind();
+    tokio::pin!(guarded);
+    let failure = |kind, message| CycleFailure { kind, message, elapsed: started.elapsed() };
+    let outcome = match deadline {
+        Some(deadline) => match tokio::time::timeout(deadline, &mut guarded).await {
+            Ok(outcome) => Some(outcome),
+            Err(_) => {
+                let running = shutdown.in_flight();
+                if running.is_empty() {
+                    None
+                } else {
+                    let message = format!(
+                        "cycle still running after {}s with {} in flight; cancelling it once that finishes",
+                        deadline.as_secs(),
+                        running.join(", ")
+                    );
+                    // The alert runs alongside the cycle, never pausing the execution
+                    let alert = on_overrun(failure(IncidentKind::Overrun, message));
+                    tokio::pin!(alert);
+                    let mut alerted = false;
+                    loop {
+                        tokio::select! {
+                            outcome = &mut guarded => break Some(outcome),
+                            () = shutdown.idle() => break None,
+                            () = &mut alert, if !alerted => alerted = true,
+                        }
+                    }
+                }
+            }
+        },
+        None => Some(guarded.await),
+    };
+    match outcome {
+        Some(Ok(Ok(value))) => Ok(value),
+        Some(Ok(Err(e))) => Err(failure(IncidentKind::Error, e.to_string())),
+        Some(Err(payload)) => Err(failure(IncidentKind::Panic, panic_message(payload.as_ref()))),
+        None => Err(failure(
+            IncidentKind::DeadlineExceeded,
+            format!("cycle still running after {}s", started.elapsed().as_secs()),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_supervise_classifies_cycle_failures() {
+        let deadline = Some(Duration::from_millis(50));
+        let shutdown = ShutdownController::new();
+        let no_alert = |_| async {};
+
+        assert_eq!(supervise(deadline, &shutdown, no_alert, async { Ok(7) }).await, Ok(7));
+
+        let error = supervise::<(), _>(deadline, &shutdown, no_alert, async { Err(BotError::Risk("limit".into())) }).await.unwrap_err();
+        assert_eq!(error.kind, IncidentKind::Error);
+        assert_eq!(error.message, "Risk limit: limit");
+
+        let panicked = supervise::<(), _>(deadline, &shutdown, no_alert, async { panic!("cycle blew up") }).await.unwrap_err();
+        assert_eq!(panicked.kind, IncidentKind::Panic);
+        assert_eq!(panicked.message, "cycle blew up");
+
+        let slow = supervise(deadline, &shutdown, no_alert, async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(slow.kind, IncidentKind::DeadlineExceeded);
+
+        let incident = Incident::new(slow, 1_000, Some("BTC".into()), 2, true);
+        let json: serde_json::Value = serde_json::from_str(&incident.to_json_line()).unwrap();
+        assert_eq!(json["kind"], "deadline_exceeded");
+        assert_eq!(json["symbol"], "BTC");
+
+        let config = WatchdogConfig::default();
+        assert!(!config.gives_up_after(5));
+        assert!(config.gives_up_after(6));
+        assert!(!WatchdogConfig { max_consecutive_restarts: 0, ..config }.gives_up_after(100));
+    }
+
+    #[tokio::test]
+    async fn test_supervise_waits_for_in_flight_execution() {
+        let deadline = Some(Duration::from_millis(50));
+        let shutdown = ShutdownController::new();
+        let alerts = std::sync::Mutex::new(Vec::new());
+        let on_overrun = |failure: CycleFailure| {
+            alerts.lock().unwrap().push(failure);
+            async {}
+        };
+
+        // An execution outlasting the deadline completes the cycle
+        let finished = supervise(deadline, &shutdown, on_overrun, async {
+            let _in_flight = shutdown.begin("open BTC");
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            Ok(7)
+        })
+        .await;
+        assert_eq!(finished, Ok(7));
+        let alert = alerts.lock().unwrap().pop().unwrap();
+        assert_eq!(alert.kind, IncidentKind::Overrun);
+        assert!(alert.message.contains("open BTC"), "{}", alert.message);
+
+        // Once the execution is done, the idle rest of the cycle is cancelled
+        let cancelled = supervise(deadline, &shutdown, |_| async {}, async {
+            {
+                let _in_flight = shutdown.begin("close BTC");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(cancelled.kind, IncidentKind::DeadlineExceeded);
+        assert!(cancelled.elapsed < Duration::from_secs(1));
+    }
+}
@@ -1,9 +1,12 @@
 use crate::error::{ConnectorError, Result};
-use crate::types::{BidAsk, WsOrderBookMessage};
+use crate::metrics;
+use crate::types::{BidAsk, FundingRateInfo, WsFundingMessage, WsOrderBookMessage};
 use futures_util::{SinkExt, StreamExt};
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tokio::net::TcpStream;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tokio_tungstenite::{
     connect_async, tungstenite::client::IntoClientRequest, tungstenite::protocol::Message,
     MaybeTlsStream, WebSocketStream,
@@ -12,7 +15,43 @@ use tracing::{debug, error, info, warn};
 
 type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
+/// Delay before reconnecting a dropped stream; doubles up to the max
+const RECONNECT_MIN_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Connection events buffered per observer; slower observers miss the oldest
+const CONNECTION_EVENT_CAPACITY: usize = 64;
+
+/// Connection state change of a reconnecting stream, named by its path
+/// (e.g. `orderbooks/BTC-USD`, or `orderbooks` for all markets)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    Connected { stream: String },
+    Disconnected { stream: String, reason: String },
+    Reconnecting { stream: String, attempt: u32, delay: Duration },
+}
+
+/// Exponential reconnect delay: `RECONNECT_MIN_DELAY` doubling per failed attempt,
+/// capped at `RECONNECT_MAX_DELAY`, back to the minimum once a connection succeeds
+#[derive(Debug, Default)]
+pub(crate) struct Backoff {
+    attempt: u32,
+}
+
+impl Backoff {
+    pub(crate) fn next_delay(&mut self) -> Duration {
+        let delay = RECONNECT_MIN_DELAY.saturating_mul(2u32.saturating_pow(self.attempt)).min(RECONNECT_MAX_DELAY);
+        self.attempt += 1;
+        delay
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
 /// WebSocket client for Extended exchange
+#[derive(Clone)]
 pub struct WebSocketClient {
     base_url: String,
     api_key: Option<String>,
@@ -68,8 +107,89 @@ impl WebSocketClient {
         self.connect_and_stream_full(url).await
     }
 
-    /// Internal method to connect and stream best bid/ask
-    async fn connect_and_stream(&self, url: String) -> Result<mpsc::UnboundedReceiver<BidAsk>> {
+    /// Stream best bid/ask for a single market, reconnecting whenever the connection
+    /// drops. See `stream_reconnecting`.
+    pub fn subscribe_orderbook_reconnecting(
+        &self,
+        market: &str,
+        events: broadcast::Sender<ConnectionEvent>,
+    ) -> mpsc::UnboundedReceiver<BidAsk> {
+        let url = format!(
+            "{}/stream.extended.exchange/v1/orderbooks/{}?depth=1",
+            self.base_url, market
+        );
+        self.stream_reconnecting(url, format!("orderbooks/{}", market), events, parse_bid_ask)
+    }
+
+    /// Stream best bid/ask for all markets, reconnecting whenever the connection drops.
+    /// See `stream_reconnecting`.
+    pub fn subscribe_all_orderbooks_reconnecting(
+        &self,
+        events: broadcast::Sender<ConnectionEvent>,
+    ) -> mpsc::UnboundedReceiver<BidAsk> {
+        let url = format!(
+            "{}/stream.extended.exchange/v1/orderbooks?depth=1",
+            self.base_url
+        );
+        self.stream_reconnecting(url, "orderbooks".to_string(), events, parse_bid_ask)
+    }
+
+    /// Stream funding rate updates for all markets, reconnecting whenever the connection
+    /// drops. See `stream_reconnecting`.
+    pub fn subscribe_all_funding_rates_reconnecting(
+        &self,
+        events: broadcast::Sender<ConnectionEvent>,
+    ) -> mpsc::UnboundedReceiver<FundingRateInfo> {
+        let url = format!("{}/stream.extended.exchange/v1/funding", self.base_url);
+        self.stream_reconnecting(url, "funding".to_string(), events, parse_funding_rate)
+    }
+
+    /// Stream messages from `url`, parsed with `parse`, in the background. A failed
+    /// connect or dropped connection is retried with exponential backoff and the stream
+    /// resubscribed, with each state change sent to `events`. The task ends once the
+    /// receiver is dropped.
+    fn stream_reconnecting<T: Send + 'static>(
+        &self,
+        url: String,
+        stream: String,
+        events: broadcast::Sender<ConnectionEvent>,
+        parse: fn(&str) -> serde_json::Result<T>,
+    ) -> mpsc::UnboundedReceiver<T> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let client = self.clone();
+
+        tokio::spawn(async move {
+            let mut backoff = Backoff::default();
+            while !tx.is_closed() {
+                let reason = match client.connect(&url).await {
+                    Ok(ws_stream) => {
+                        backoff.reset();
+                        let _ = events.send(ConnectionEvent::Connected { stream: stream.clone() });
+                        match Self::handle_stream(ws_stream, tx.clone(), parse).await {
+                            Ok(()) => "stream ended".to_string(),
+                            Err(e) => e.to_string(),
+                        }
+                    }
+                    Err(e) => e.to_string(),
+                };
+                if tx.is_closed() {
+                    break;
+                }
+                let _ = events.send(ConnectionEvent::Disconnected { stream: stream.clone(), reason: reason.clone() });
+
+                let attempt = backoff.attempt + 1;
+                let delay = backoff.next_delay();
+                warn!("Extended {} stream down ({}), reconnecting in {:?} (attempt {})", stream, reason, delay, attempt);
+                let _ = events.send(ConnectionEvent::Reconnecting { stream: stream.clone(), attempt, delay });
+                tokio::time::sleep(delay).await;
+            }
+        });
+
+        rx
+    }
+
+    /// Open a connection to `url` with the client's headers
+    async fn connect(&self, url: &str) -> Result<WsStream> {
         info!("Connecting to WebSocket: {}", url);
 
         // Build request with headers
@@ -83,14 +203,19 @@ impl WebSocketClient {
             }
         }
 
-        let (ws_stream, _) = connect_async(request).await?;
+        let (ws_stream, _) = metrics::observe("extended WS connect", connect_async(request)).await?;
 
         info!("WebSocket connected successfully");
+        Ok(ws_stream)
+    }
 
+    /// Internal method to connect and stream best bid/ask
+    async fn connect_and_stream(&self, url: String) -> Result<mpsc::UnboundedReceiver<BidAsk>> {
+        let ws_stream = self.connect(&url).await?;
         let (tx, rx) = mpsc::unbounded_channel();
 
         tokio::spawn(async move {
-            if let Err(e) = Self::handle_stream(ws_stream, tx).await {
+            if let Err(e) = Self::handle_stream(ws_stream, tx, parse_bid_ask).await {
                 error!("WebSocket stream error: {}", e);
             }
         });
@@ -103,27 +228,11 @@ impl WebSocketClient {
         &self,
         url: String,
     ) -> Result<mpsc::UnboundedReceiver<WsOrderBookMessage>> {
-        info!("Connecting to WebSocket: {}", url);
-
-        // Build request with headers
-        let mut request = url.into_client_request()?;
-        request
-            .headers_mut()
-            .insert("User-Agent", "extended-connector/0.1.0".parse().unwrap());
-        if let Some(ref api_key) = self.api_key {
-            if let Ok(hv) = api_key.parse() {
-                request.headers_mut().insert("X-Api-Key", hv);
-            }
-        }
-
-        let (ws_stream, _) = connect_async(request).await?;
-
-        info!("WebSocket connected successfully");
-
+        let ws_stream = self.connect(&url).await?;
         let (tx, rx) = mpsc::unbounded_channel();
 
         tokio::spawn(async move {
-            if let Err(e) = Self::handle_full_stream(ws_stream, tx).await {
+            if let Err(e) = Self::handle_stream(ws_stream, tx, parse_orderbook).await {
                 error!("WebSocket stream error: {}", e);
             }
         });
@@ -131,20 +240,20 @@ impl WebSocketClient {
         Ok(rx)
     }
 
-    /// Handle incoming WebSocket messages and convert to BidAsk
-    async fn handle_stream(
+    /// Handle incoming WebSocket messages, forwarding each one `parse` accepts
+    async fn handle_stream<T>(
         mut ws_stream: WsStream,
-        tx: mpsc::UnboundedSender<BidAsk>,
+        tx: mpsc::UnboundedSender<T>,
+        parse: fn(&str) -> serde_json::Result<T>,
     ) -> Result<()> {
         while let Some(msg) = ws_stream.next().await {
             match msg {
                 Ok(Message::Text(text)) => {
                     debug!("Received message: {}", text);
 
-                    match serde_json::from_str::<WsOrderBookMessage>(&text) {
-                        Ok(orderbook_msg) => {
-                            let bid_ask = BidAsk::from(&orderbook_msg);
-                            if tx.send(bid_ask).is_err() {
+                    match parse(&text) {
+                        Ok(message) => {
+                            if tx.send(message).is_err() {
                                 warn!("Receiver dropped, closing connection");
                                 break;
                             }
@@ -167,7 +276,7 @@ impl WebSocketClient {
                 }
                 Err(e) => {
                     error!("WebSocket error: {}", e);
-                    return Err(ConnectorError::WebSocket(e));
+                    return Err(ConnectorError::WebSocket(Box::new(e)));
                 }
                 _ => {}
             }
@@ -175,66 +284,141 @@ impl WebSocketClient {
 
         Ok(())
     }
+}
 
-    /// Handle incoming WebSocket messages (full orderbook)
-    async fn handle_full_stream(
-        mut ws_stream: WsStream,
-        tx: mpsc::UnboundedSender<WsOrderBookMessage>,
-    ) -> Result<()> {
-        while let Some(msg) = ws_stream.next().await {
-            match msg {
-                Ok(Message::Text(text)) => {
-                    debug!("Received message: {}", text);
+fn parse_orderbook(text: &str) -> serde_json::Result<WsOrderBookMessage> {
+    serde_json::from_str(text)
+}
 
-                    match serde_json::from_str::<WsOrderBookMessage>(&text) {
-                        Ok(orderbook_msg) => {
-                            if tx.send(orderbook_msg).is_err() {
-                                warn!("Receiver dropped, closing connection");
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to parse message: {} - Error: {}", text, e);
-                        }
-                    }
-                }
-                Ok(Message::Ping(data)) => {
-                    debug!("Received ping, sending pong");
-                    ws_stream.send(Message::Pong(data)).await?;
-                }
-                Ok(Message::Pong(_)) => {
-                    debug!("Received pong");
-                }
-                Ok(Message::Close(_)) => {
-                    info!("WebSocket closed by server");
-                    break;
-                }
-                Err(e) => {
-                    error!("WebSocket error: {}", e);
-                    return Err(ConnectorError::WebSocket(e));
-                }
-                _ => {}
+fn parse_bid_ask(text: &str) -> serde_json::Result<BidAsk> {
+    serde_json::from_str::<WsOrderBookMessage>(text).map(|msg| BidAsk::from(&msg))
+}
+
+fn parse_funding_rate(text: &str) -> serde_json::Result<FundingRateInfo> {
+    serde_json::from_str::<WsFundingMessage>(text).map(|msg| FundingRateInfo::from_data(msg.data))
+}
+
+/// Latest best bid/ask per market with the local time it was received, shared
+/// between a streaming task and its readers
+#[derive(Clone)]
+pub struct TopOfBookCache {
+    books: Arc<RwLock<HashMap<String, (BidAsk, Instant)>>>,
+    /// Last update of any market; creation time until the first one arrives
+    last_update: Arc<RwLock<Instant>>,
+}
+
+impl Default for TopOfBookCache {
+    fn default() -> Self {
+        Self {
+            books: Arc::default(),
+            last_update: Arc::new(RwLock::new(Instant::now())),
+        }
+    }
+}
+
+impl TopOfBookCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store an update. A side missing from the update keeps its previous value.
+    pub fn update(&self, mut bid_ask: BidAsk) {
+        let mut books = self.books.write().unwrap_or_else(|e| e.into_inner());
+        if let Some((previous, _)) = books.get(&bid_ask.market) {
+            if bid_ask.best_bid.is_none() {
+                bid_ask.best_bid = previous.best_bid.clone();
+                bid_ask.bid_quantity = previous.bid_quantity.clone();
+            }
+            if bid_ask.best_ask.is_none() {
+                bid_ask.best_ask = previous.best_ask.clone();
+                bid_ask.ask_quantity = previous.ask_quantity.clone();
             }
         }
+        books.insert(bid_ask.market.clone(), (bid_ask, Instant::now()));
+        *self.last_update.write().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
 
-        Ok(())
+    /// Time since the stream last delivered an update for any market (or since the cache
+    /// was created). A connected stream updates constantly, so a long silence means it is dead.
+    pub fn silent_for(&self) -> Duration {
+        self.last_update.read().unwrap_or_else(|e| e.into_inner()).elapsed()
+    }
+
+    /// Latest quote for `market` if it was received within `max_age`
+    pub fn fresh(&self, market: &str, max_age: Duration) -> Option<BidAsk> {
+        let books = self.books.read().unwrap_or_else(|e| e.into_inner());
+        books
+            .get(market)
+            .filter(|(_, received)| received.elapsed() <= max_age)
+            .map(|(bid_ask, _)| bid_ask.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.books.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// True once every reader has dropped its handle (only the streaming task holds one)
+    pub(crate) fn is_orphaned(&self) -> bool {
+        Arc::strong_count(&self.books) <= 1
     }
 }
 
 /// Helper to manage multiple market subscriptions
+///
+/// Every stream it starts reconnects on its own with exponential backoff and is
+/// resubscribed after each disconnect; `connection_events` observes the state changes.
 pub struct MultiMarketSubscriber {
     client: WebSocketClient,
-    _subscriptions: HashMap<String, mpsc::UnboundedReceiver<BidAsk>>,
+    markets: Vec<String>,
+    cache: TopOfBookCache,
+    events: broadcast::Sender<ConnectionEvent>,
 }
 
 impl MultiMarketSubscriber {
     pub fn new(client: WebSocketClient) -> Self {
         Self {
             client,
-            _subscriptions: HashMap::new(),
+            markets: Vec::new(),
+            cache: TopOfBookCache::new(),
+            events: broadcast::channel(CONNECTION_EVENT_CAPACITY).0,
         }
     }
 
+    /// Cache kept up to date by `stream_all_into_cache` and `subscribe_markets`
+    pub fn cache(&self) -> TopOfBookCache {
+        self.cache.clone()
+    }
+
+    /// Connection state changes of every stream started from now on
+    pub fn connection_events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.events.subscribe()
+    }
+
+    /// Markets subscribed with `subscribe_markets`
+    pub fn markets(&self) -> &[String] {
+        &self.markets
+    }
+
+    /// Stream best bid/ask for every market over one connection into the cache, in the
+    /// background. The task ends once all other handles to the cache are dropped.
+    pub fn stream_all_into_cache(&self) -> TopOfBookCache {
+        let mut rx = self.client.subscribe_all_orderbooks_reconnecting(self.events.clone());
+        let cache = self.cache.clone();
+        tokio::spawn(async move {
+            while let Some(bid_ask) = rx.recv().await {
+                if cache.is_orphaned() {
+                    return;
+                }
+                cache.update(bid_ask);
+            }
+        });
+        self.cache.clone()
+    }
+
     /// Subscribe to multiple markets and aggregate their updates
     pub async fn subscribe_markets(
         &mut self,
@@ -243,12 +427,14 @@ impl MultiMarketSubscriber {
         let (tx, rx) = mpsc::unbounded_channel();
 
         for market in markets {
-            let market_rx = self.client.subscribe_orderbook(&market).await?;
+            let market_rx = self.client.subscribe_orderbook_reconnecting(&market, self.events.clone());
             let tx_clone = tx.clone();
+            let cache = self.cache.clone();
 
             tokio::spawn(async move {
-                Self::forward_messages(market_rx, tx_clone).await;
+                Self::forward_messages(market_rx, tx_clone, cache).await;
             });
+            self.markets.push(market);
         }
 
         Ok(rx)
@@ -257,8 +443,10 @@ impl MultiMarketSubscriber {
     async fn forward_messages(
         mut rx: mpsc::UnboundedReceiver<BidAsk>,
         tx: mpsc::UnboundedSender<BidAsk>,
+        cache: TopOfBookCache,
     ) {
         while let Some(bid_ask) = rx.recv().await {
+            cache.update(bid_ask.clone());
             if tx.send(bid_ask).is_err() {
                 break;
             }
@@ -272,6 +460,40 @@ mod tests {
     use std::time::Duration;
     use tokio::time::timeout;
 
+    fn quote(market: &str, bid: Option<&str>, ask: Option<&str>) -> BidAsk {
+        BidAsk {
+            market: market.to_string(),
+            best_bid: bid.map(str::to_string),
+            best_ask: ask.map(str::to_string),
+            bid_quantity: None,
+            ask_quantity: None,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_to_cap_and_resets() {
+        let mut backoff = Backoff::default();
+        let delays: Vec<u64> = (0..7).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30]);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), RECONNECT_MIN_DELAY);
+    }
+
+    #[test]
+    fn test_top_of_book_cache_merges_sides() {
+        let cache = TopOfBookCache::new();
+        cache.update(quote("BTC-USD", Some("100"), Some("101")));
+        cache.update(quote("BTC-USD", None, Some("102")));
+
+        let book = cache.fresh("BTC-USD", Duration::from_secs(5)).unwrap();
+        assert_eq!(book.best_bid.as_deref(), Some("100"));
+        assert_eq!(book.best_ask.as_deref(), Some("102"));
+        assert!(cache.fresh("ETH-USD", Duration::from_secs(5)).is_none());
+        assert_eq!(cache.len(), 1);
+        assert!(cache.silent_for() < Duration::from_secs(5));
+    }
+
     #[tokio::test]
     async fn test_websocket_single_market() {
         let client = WebSocketClient::new_mainnet(None);